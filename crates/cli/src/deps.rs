@@ -0,0 +1,524 @@
+//! `roc deps`, which prints the graph of modules a module imports (directly or indirectly),
+//! as an indented tree, a Graphviz DOT file, or JSON.
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_load::{
+    ExecutionMode, FunctionKind, LoadConfig, LoadedModule, LoadingProblem, ModuleGraph, Threading,
+};
+use roc_module::ident::ModuleName;
+use roc_module::symbol::ModuleId;
+use roc_packaging::cache::RocCacheDir;
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::TargetInfo;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepsFormat {
+    Tree,
+    Dot,
+    Json,
+}
+
+impl DepsFormat {
+    pub const NAMES: [&'static str; 3] = ["tree", "dot", "json"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(Self::Tree),
+            "dot" => Some(Self::Dot),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModuleKind {
+    Root,
+    Platform,
+    Package,
+    Local,
+    Builtin,
+}
+
+impl ModuleKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ModuleKind::Root => "root",
+            ModuleKind::Platform => "platform",
+            ModuleKind::Package => "package",
+            ModuleKind::Local => "local",
+            ModuleKind::Builtin => "builtin",
+        }
+    }
+}
+
+struct Module {
+    /// e.g. `Dict` or `pf.Task`
+    name: String,
+    kind: ModuleKind,
+    shorthand: Option<String>,
+    path: Option<PathBuf>,
+    /// Sorted by name, so the output doesn't depend on hash map ordering
+    imports: Vec<ModuleId>,
+}
+
+/// The module graph reachable from the root module, with names ready for printing
+struct DepsGraph {
+    root: ModuleId,
+    modules: MutMap<ModuleId, Module>,
+}
+
+impl DepsGraph {
+    fn new(loaded: &LoadedModule, root_path: &Path) -> Self {
+        let ModuleGraph {
+            imports,
+            shorthands,
+            platform,
+        } = &loaded.module_graph;
+        let root = loaded.module_id;
+
+        let name_of = |module_id: ModuleId| {
+            let module_name = loaded.interns.module_name(module_id);
+
+            if module_id == root
+                && (module_name.as_str().is_empty() || module_name.as_str() == ModuleName::APP)
+            {
+                // Apps and platforms don't have a module name, so use their file name instead
+                root_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| ModuleName::APP.to_string())
+            } else {
+                match shorthands.get(&module_id) {
+                    // A platform's main module is named after its shorthand
+                    Some(shorthand) if module_name.as_str().is_empty() => shorthand.clone(),
+                    Some(shorthand) => format!("{shorthand}.{module_name}"),
+                    None => module_name.to_string(),
+                }
+            }
+        };
+
+        let mut modules = MutMap::default();
+        let mut stack = vec![root];
+
+        while let Some(module_id) = stack.pop() {
+            if modules.contains_key(&module_id) {
+                continue;
+            }
+
+            let mut module_imports: Vec<ModuleId> = imports
+                .get(&module_id)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|imported| *imported != module_id)
+                .collect();
+
+            // An app's platform is in its header rather than its imports, but it's still a dependency.
+            if module_id == root {
+                if let Some(platform) = platform {
+                    if !module_imports.contains(platform) {
+                        module_imports.push(*platform);
+                    }
+                }
+            }
+
+            module_imports.sort_by_key(|imported| name_of(*imported));
+            stack.extend(module_imports.iter().copied());
+
+            let kind = if module_id == root {
+                ModuleKind::Root
+            } else if Some(module_id) == *platform {
+                ModuleKind::Platform
+            } else if module_id.is_builtin() {
+                ModuleKind::Builtin
+            } else if shorthands.contains_key(&module_id) {
+                ModuleKind::Package
+            } else {
+                ModuleKind::Local
+            };
+
+            modules.insert(
+                module_id,
+                Module {
+                    name: name_of(module_id),
+                    kind,
+                    shorthand: shorthands.get(&module_id).cloned(),
+                    path: loaded.sources.get(&module_id).map(|(path, _)| path.clone()),
+                    imports: module_imports,
+                },
+            );
+        }
+
+        Self { root, modules }
+    }
+
+    /// Find a module by its full name (e.g. `pf.Task`), or by its name without the
+    /// package shorthand (e.g. `Task`) if that's unambiguous.
+    fn find(&self, name: &str) -> Result<ModuleId, Vec<String>> {
+        if let Some((module_id, _)) = self.modules.iter().find(|(_, module)| module.name == name) {
+            return Ok(*module_id);
+        }
+
+        let mut matches: Vec<_> = self
+            .modules
+            .iter()
+            .filter(|(_, module)| module.name.rsplit('.').next() == Some(name))
+            .collect();
+
+        if matches.len() == 1 {
+            Ok(*matches[0].0)
+        } else {
+            matches.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+
+            Err(matches
+                .into_iter()
+                .map(|(_, module)| module.name.clone())
+                .collect())
+        }
+    }
+
+    /// Keep only the modules which lie on some import path from the root to the given module,
+    /// which is what explains why that module is included.
+    fn retain_paths_to(&mut self, target: ModuleId) {
+        let mut importers: MutMap<ModuleId, Vec<ModuleId>> = MutMap::default();
+
+        for (module_id, module) in self.modules.iter() {
+            for imported in module.imports.iter() {
+                importers.entry(*imported).or_default().push(*module_id);
+            }
+        }
+
+        let mut keep = MutSet::default();
+        let mut queue = VecDeque::from([target]);
+
+        while let Some(module_id) = queue.pop_front() {
+            // Don't walk back past the root, e.g. to the platform which requires it
+            if keep.insert(module_id) && module_id != self.root {
+                queue.extend(importers.get(&module_id).into_iter().flatten().copied());
+            }
+        }
+
+        self.modules.retain(|module_id, _| keep.contains(module_id));
+
+        for module in self.modules.values_mut() {
+            module.imports.retain(|imported| keep.contains(imported));
+        }
+    }
+
+    /// Modules sorted by name, with the root first
+    fn sorted_modules(&self) -> Vec<(ModuleId, &Module)> {
+        let mut sorted: Vec<_> = self
+            .modules
+            .iter()
+            .map(|(module_id, module)| (*module_id, module))
+            .collect();
+
+        sorted.sort_by_key(|(module_id, module)| (*module_id != self.root, module.name.clone()));
+
+        sorted
+    }
+
+    fn to_tree(&self) -> String {
+        let mut buf = String::new();
+        let mut printed = MutSet::default();
+
+        self.write_tree(&mut buf, self.root, "", "", &mut printed);
+
+        buf
+    }
+
+    fn write_tree(
+        &self,
+        buf: &mut String,
+        module_id: ModuleId,
+        prefix: &str,
+        child_prefix: &str,
+        printed: &mut MutSet<ModuleId>,
+    ) {
+        let module = &self.modules[&module_id];
+        let kind = match module.kind {
+            ModuleKind::Root | ModuleKind::Local => String::new(),
+            kind => format!(" ({})", kind.as_str()),
+        };
+
+        // Like `cargo tree`, only print each module's imports the first time it appears
+        if !printed.insert(module_id) && !module.imports.is_empty() {
+            writeln!(buf, "{prefix}{}{kind} (*)", module.name).unwrap();
+
+            return;
+        }
+
+        writeln!(buf, "{prefix}{}{kind}", module.name).unwrap();
+
+        for (index, imported) in module.imports.iter().enumerate() {
+            let is_last = index + 1 == module.imports.len();
+            let (branch, continuation) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            self.write_tree(
+                buf,
+                *imported,
+                &format!("{child_prefix}{branch}"),
+                &format!("{child_prefix}{continuation}"),
+                printed,
+            );
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut buf = String::from("digraph deps {\n");
+
+        for (_, module) in self.sorted_modules() {
+            let shape = match module.kind {
+                ModuleKind::Root => "doublecircle",
+                ModuleKind::Platform => "box",
+                ModuleKind::Package => "ellipse",
+                ModuleKind::Local => "ellipse",
+                ModuleKind::Builtin => "note",
+            };
+
+            writeln!(buf, "    {:?} [shape={shape}];", module.name).unwrap();
+        }
+
+        for (_, module) in self.sorted_modules() {
+            for imported in module.imports.iter() {
+                writeln!(
+                    buf,
+                    "    {:?} -> {:?};",
+                    module.name, self.modules[imported].name
+                )
+                .unwrap();
+            }
+        }
+
+        buf.push_str("}\n");
+
+        buf
+    }
+
+    fn to_json(&self) -> String {
+        let mut buf = String::new();
+
+        writeln!(buf, "{{").unwrap();
+        writeln!(
+            buf,
+            "  \"root\": {},",
+            json_string(&self.modules[&self.root].name)
+        )
+        .unwrap();
+        writeln!(buf, "  \"modules\": [").unwrap();
+
+        let sorted = self.sorted_modules();
+
+        for (index, (_, module)) in sorted.iter().enumerate() {
+            let imports: Vec<String> = module
+                .imports
+                .iter()
+                .map(|imported| json_string(&self.modules[imported].name))
+                .collect();
+            let optional =
+                |value: Option<String>| value.map_or("null".to_string(), |v| json_string(&v));
+
+            writeln!(buf, "    {{").unwrap();
+            writeln!(buf, "      \"name\": {},", json_string(&module.name)).unwrap();
+            writeln!(buf, "      \"kind\": \"{}\",", module.kind.as_str()).unwrap();
+            writeln!(
+                buf,
+                "      \"package\": {},",
+                optional(module.shorthand.clone())
+            )
+            .unwrap();
+            writeln!(
+                buf,
+                "      \"path\": {},",
+                optional(module.path.as_ref().map(|path| path.display().to_string()))
+            )
+            .unwrap();
+            writeln!(buf, "      \"imports\": [{}]", imports.join(", ")).unwrap();
+            writeln!(
+                buf,
+                "    }}{}",
+                if index + 1 < sorted.len() { "," } else { "" }
+            )
+            .unwrap();
+        }
+
+        writeln!(buf, "  ]").unwrap();
+        writeln!(buf, "}}").unwrap();
+
+        buf
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut buf = String::with_capacity(string.len() + 2);
+
+    buf.push('"');
+
+    for ch in string.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            ch if ch.is_control() => write!(buf, "\\u{:04x}", ch as u32).unwrap(),
+            ch => buf.push(ch),
+        }
+    }
+
+    buf.push('"');
+
+    buf
+}
+
+/// Print the module graph of the given .roc file. If `why` names a module, only print the
+/// import paths which lead to it.
+pub fn deps(
+    roc_file_path: &Path,
+    format: DepsFormat,
+    why: Option<&str>,
+    roc_cache_dir: RocCacheDir<'_>,
+) -> io::Result<i32> {
+    let arena = Bump::new();
+    let load_config = LoadConfig {
+        // The target doesn't matter, since we only type-check
+        target_info: TargetInfo::default_x86_64(),
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::ColorTerminal,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::AllAvailable,
+        exec_mode: ExecutionMode::Check,
+    };
+
+    let loaded = match roc_load::load_and_typecheck(
+        &arena,
+        roc_file_path.to_path_buf(),
+        roc_cache_dir,
+        load_config,
+    ) {
+        Ok(loaded) => loaded,
+        Err(LoadingProblem::FormattedReport(report)) => {
+            eprintln!("{report}");
+
+            return Ok(1);
+        }
+        Err(problem) => {
+            eprintln!("{problem:?}");
+
+            return Ok(1);
+        }
+    };
+
+    let mut graph = DepsGraph::new(&loaded, roc_file_path);
+
+    if let Some(name) = why {
+        match graph.find(name) {
+            Ok(target) => graph.retain_paths_to(target),
+            Err(candidates) if candidates.is_empty() => {
+                eprintln!(
+                    "\n{} does not depend on a module named {name}.\n",
+                    roc_file_path.display()
+                );
+
+                return Ok(1);
+            }
+            Err(candidates) => {
+                eprintln!(
+                    "\nThere are multiple modules named {name}. Which one did you mean?\n\n\t{}\n",
+                    candidates.join("\n\t")
+                );
+
+                return Ok(1);
+            }
+        }
+    }
+
+    let output = match format {
+        DepsFormat::Tree => graph.to_tree(),
+        DepsFormat::Dot => graph.to_dot(),
+        DepsFormat::Json => graph.to_json(),
+    };
+
+    print!("{output}");
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn module(name: &str, kind: ModuleKind, imports: &[ModuleId]) -> Module {
+        Module {
+            name: name.to_string(),
+            kind,
+            shorthand: name
+                .split_once('.')
+                .map(|(shorthand, _)| shorthand.to_string()),
+            path: None,
+            imports: imports.to_vec(),
+        }
+    }
+
+    /// main.roc imports pf.Task and List; pf.Task imports List and Str
+    fn example_graph() -> DepsGraph {
+        let root = ModuleId::NUM;
+        let task = ModuleId::DICT;
+        let list = ModuleId::LIST;
+        let str = ModuleId::STR;
+
+        let mut modules = MutMap::default();
+
+        modules.insert(root, module("main.roc", ModuleKind::Root, &[list, task]));
+        modules.insert(task, module("pf.Task", ModuleKind::Package, &[list, str]));
+        modules.insert(list, module("List", ModuleKind::Builtin, &[]));
+        modules.insert(str, module("Str", ModuleKind::Builtin, &[]));
+
+        DepsGraph { root, modules }
+    }
+
+    #[test]
+    fn tree() {
+        assert_eq!(
+            example_graph().to_tree(),
+            "main.roc\n\
+             ├── List (builtin)\n\
+             └── pf.Task (package)\n    \
+                 ├── List (builtin)\n    \
+                 └── Str (builtin)\n"
+        );
+    }
+
+    #[test]
+    fn why() {
+        let mut graph = example_graph();
+
+        assert_eq!(graph.find("Task"), Ok(ModuleId::DICT));
+        assert_eq!(graph.find("Nope"), Err(Vec::new()));
+
+        graph.retain_paths_to(ModuleId::STR);
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph deps {\n    \
+                 \"main.roc\" [shape=doublecircle];\n    \
+                 \"Str\" [shape=note];\n    \
+                 \"pf.Task\" [shape=ellipse];\n    \
+                 \"main.roc\" -> \"pf.Task\";\n    \
+                 \"pf.Task\" -> \"Str\";\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(json_string("a \"b\"\\\n"), r#""a \"b\"\\\n""#);
+    }
+}
@@ -1,18 +1,28 @@
 use std::ffi::OsStr;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::FormatMode;
 use bumpalo::Bump;
-use roc_error_macros::{internal_error, user_error};
+use roc_error_macros::internal_error;
 use roc_fmt::def::fmt_defs;
 use roc_fmt::module::fmt_module;
 use roc_fmt::spaces::RemoveSpaces;
 use roc_fmt::{Ast, Buf};
+use roc_module::symbol::{Interns, ModuleIds};
 use roc_parse::{
     module::{self, module_defs},
-    parser::{Parser, SyntaxError},
+    parser::{FileError, Parser, SourceError, SyntaxError},
     state::State,
 };
+use roc_region::all::LineInfo;
+use roc_reporting::report::{parse_problem, RenderTarget, RocDocAllocator, DEFAULT_PALETTE};
+use similar::TextDiff;
+
+/// The file name to use in reports about code read from stdin. Formatting bugs also write their
+/// debugging output next to this (i.e. in the current directory).
+const STDIN_FILENAME: &str = "stdin.roc";
 
 fn flatten_directories(files: std::vec::Vec<PathBuf>) -> std::vec::Vec<PathBuf> {
     let mut to_flatten = files;
@@ -58,101 +68,313 @@ fn is_roc_file(path: &Path) -> bool {
     matches!(path.extension().and_then(OsStr::to_str), Some("roc"))
 }
 
+/// What formatting did to one file
+enum FileOutcome {
+    AlreadyFormatted,
+    /// In check mode, this has the diff of what formatting would change
+    Reformatted(Option<String>),
+    /// The file doesn't parse, so it was left alone; this is the report saying why
+    ParseFailed(String),
+    /// The file couldn't be read or written; this is the error message
+    IoFailed(String),
+}
+
 pub fn format(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), String> {
-    let files = flatten_directories(files);
-
-    for file in files {
-        let arena = Bump::new();
-
-        let src = std::fs::read_to_string(&file).unwrap();
-
-        let ast = arena.alloc(parse_all(&arena, &src).unwrap_or_else(|e| {
-            user_error!("Unexpected parse failure when parsing this formatting:\n\n{:?}\n\nParse error was:\n\n{:?}\n\n", src, e)
-        }));
-        let mut buf = Buf::new_in(&arena);
-        fmt_all(&mut buf, ast);
-
-        let reparsed_ast = arena.alloc(parse_all(&arena, buf.as_str()).unwrap_or_else(|e| {
-            let mut fail_file = file.clone();
-            fail_file.set_extension("roc-format-failed");
-            std::fs::write(&fail_file, buf.as_str()).unwrap();
-            internal_error!(
-                "Formatting bug; formatted code isn't valid\n\n\
-                I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
-                Parse error was: {:?}\n\n",
-                fail_file.display(),
-                e
-            );
-        }));
-
-        let ast_normalized = ast.remove_spaces(&arena);
-        let reparsed_ast_normalized = reparsed_ast.remove_spaces(&arena);
-
-        // HACK!
-        // We compare the debug format strings of the ASTs, because I'm finding in practice that _somewhere_ deep inside the ast,
-        // the PartialEq implementation is returning `false` even when the Debug-formatted impl is exactly the same.
-        // I don't have the patience to debug this right now, so let's leave it for another day...
-        // TODO: fix PartialEq impl on ast types
-        if format!("{ast_normalized:?}") != format!("{reparsed_ast_normalized:?}") {
-            let mut fail_file = file.clone();
-            fail_file.set_extension("roc-format-failed");
-            std::fs::write(&fail_file, buf.as_str()).unwrap();
-
-            let mut before_file = file.clone();
-            before_file.set_extension("roc-format-failed-ast-before");
-            std::fs::write(&before_file, format!("{ast_normalized:#?}\n")).unwrap();
-
-            let mut after_file = file.clone();
-            after_file.set_extension("roc-format-failed-ast-after");
-            std::fs::write(&after_file, format!("{reparsed_ast_normalized:#?}\n")).unwrap();
-
-            internal_error!(
-                "Formatting bug; formatting didn't reparse as the same tree\n\n\
-                I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
-                I wrote the tree before and after formatting to these files for debugging purposes:\n{}\n{}\n\n",
-                fail_file.display(),
-                before_file.display(),
-                after_file.display());
+    let mut files = flatten_directories(files);
+
+    // Print diffs and reports in a consistent order, no matter which thread got to a file first
+    files.sort();
+
+    let next_file = AtomicUsize::new(0);
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, files.len().max(1));
+
+    let mut outcomes: Vec<(usize, FileOutcome)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outcomes = Vec::new();
+
+                    loop {
+                        let index = next_file.fetch_add(1, Ordering::Relaxed);
+
+                        match files.get(index) {
+                            Some(file) => outcomes.push((index, format_file(file, &mode))),
+                            None => break outcomes,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    let mut reformatted = 0;
+    let mut already_formatted = 0;
+    let mut parse_failed = 0;
+    let mut io_failed = 0;
+
+    for (_, outcome) in outcomes {
+        match outcome {
+            FileOutcome::AlreadyFormatted => already_formatted += 1,
+            FileOutcome::Reformatted(opt_diff) => {
+                if let Some(diff) = opt_diff {
+                    print!("{diff}");
+                }
+
+                reformatted += 1;
+            }
+            FileOutcome::ParseFailed(report) => {
+                eprintln!("{report}");
+
+                parse_failed += 1;
+            }
+            FileOutcome::IoFailed(message) => {
+                eprintln!("{message}");
+
+                io_failed += 1;
+            }
         }
+    }
+
+    let mut summary = match mode {
+        FormatMode::CheckOnly => format!(
+            "{} need to be reformatted, {} already formatted",
+            count_files(reformatted),
+            count_files(already_formatted)
+        ),
+        FormatMode::Format => format!(
+            "Reformatted {}, {} already formatted",
+            count_files(reformatted),
+            count_files(already_formatted)
+        ),
+    };
+
+    let mut skipped = Vec::new();
+
+    if parse_failed > 0 {
+        skipped.push(format!(
+            "{} which could not be parsed",
+            count_files(parse_failed)
+        ));
+    }
+
+    if io_failed > 0 {
+        skipped.push(format!(
+            "{} which could not be read or written",
+            count_files(io_failed)
+        ));
+    }
+
+    if !skipped.is_empty() {
+        summary.push_str(&format!(", and skipped {}", skipped.join(" and ")));
+    }
+
+    summary.push('.');
+
+    let needs_formatting = matches!(mode, FormatMode::CheckOnly) && reformatted > 0;
+
+    if needs_formatting || !skipped.is_empty() {
+        Err(summary)
+    } else {
+        println!("{summary}");
+
+        Ok(())
+    }
+}
+
+fn count_files(count: usize) -> String {
+    match count {
+        1 => "1 file".to_string(),
+        _ => format!("{count} files"),
+    }
+}
+
+fn format_file(file: &Path, mode: &FormatMode) -> FileOutcome {
+    let arena = Bump::new();
 
-        // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
-        let mut reformatted_buf = Buf::new_in(&arena);
-        fmt_all(&mut reformatted_buf, reparsed_ast);
-        if buf.as_str() != reformatted_buf.as_str() {
-            let mut unstable_1_file = file.clone();
-            unstable_1_file.set_extension("roc-format-unstable-1");
-            std::fs::write(&unstable_1_file, buf.as_str()).unwrap();
-
-            let mut unstable_2_file = file.clone();
-            unstable_2_file.set_extension("roc-format-unstable-2");
-            std::fs::write(&unstable_2_file, reformatted_buf.as_str()).unwrap();
-
-            internal_error!(
-                "Formatting bug; formatting is not stable. Reformatting the formatted file changed it again.\n\n\
-                I wrote the result of formatting to this file for debugging purposes:\n{}\n\n\
-                I wrote the result of double-formatting here:\n{}\n\n",
-                unstable_1_file.display(),
-                unstable_2_file.display());
+    let src = match std::fs::read_to_string(file) {
+        Ok(src) => src,
+        Err(err) => {
+            return FileOutcome::IoFailed(format!("Unable to read {}: {err}", file.display()))
         }
+    };
+
+    let formatted = match format_src(&arena, file, &src) {
+        Ok(formatted) => formatted,
+        Err(report) => return FileOutcome::ParseFailed(report),
+    };
+
+    if formatted == src {
+        return FileOutcome::AlreadyFormatted;
+    }
 
-        match mode {
-            FormatMode::CheckOnly => {
-                // If we notice that this file needs to be formatted, return early
-                if buf.as_str() != src {
-                    return Err("One or more files need to be reformatted.".to_string());
+    match mode {
+        FormatMode::CheckOnly => {
+            FileOutcome::Reformatted(Some(unified_diff(file, &src, &formatted)))
+        }
+        FormatMode::Format => {
+            // If all the checks passed, actually write out the new file.
+            match std::fs::write(file, formatted) {
+                Ok(()) => FileOutcome::Reformatted(None),
+                Err(err) => {
+                    FileOutcome::IoFailed(format!("Unable to write {}: {err}", file.display()))
                 }
             }
+        }
+    }
+}
 
-            FormatMode::Format => {
-                // If all the checks above passed, actually write out the new file.
-                std::fs::write(&file, buf.as_str()).unwrap();
+/// Format the code from stdin. Unless we're only checking, write the result to stdout.
+pub fn format_stdin(mode: FormatMode) -> Result<(), String> {
+    let mut src = String::new();
+
+    std::io::stdin()
+        .read_to_string(&mut src)
+        .map_err(|err| format!("Unable to read from stdin: {err}"))?;
+
+    let arena = Bump::new();
+    let file = Path::new(STDIN_FILENAME);
+    let formatted = format_src(&arena, file, &src)?;
+
+    match mode {
+        FormatMode::CheckOnly => {
+            if formatted != src {
+                print!("{}", unified_diff(file, &src, &formatted));
+
+                return Err("The code from stdin needs to be reformatted.".to_string());
             }
         }
+
+        FormatMode::Format => print!("{formatted}"),
     }
 
     Ok(())
 }
 
+/// Format a module's source code. If it doesn't parse, returns the parse report to print.
+///
+/// The path is only used for that report, and to write debugging output next to if formatting
+/// turns out to have a bug.
+fn format_src<'a>(arena: &'a Bump, file: &Path, src: &'a str) -> Result<String, String> {
+    let ast = match parse_all(arena, src) {
+        Ok(ast) => arena.alloc(ast),
+        Err(problem) => return Err(parse_report(file, src, problem)),
+    };
+    let mut buf = Buf::new_in(arena);
+    fmt_all(&mut buf, ast);
+
+    let reparsed_ast = arena.alloc(parse_all(arena, buf.as_str()).unwrap_or_else(|e| {
+        let mut fail_file = file.to_path_buf();
+        fail_file.set_extension("roc-format-failed");
+        std::fs::write(&fail_file, buf.as_str()).unwrap();
+        internal_error!(
+            "Formatting bug; formatted code isn't valid\n\n\
+            I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
+            Parse error was: {:?}\n\n",
+            fail_file.display(),
+            e
+        );
+    }));
+
+    let ast_normalized = ast.remove_spaces(arena);
+    let reparsed_ast_normalized = reparsed_ast.remove_spaces(arena);
+
+    // HACK!
+    // We compare the debug format strings of the ASTs, because I'm finding in practice that _somewhere_ deep inside the ast,
+    // the PartialEq implementation is returning `false` even when the Debug-formatted impl is exactly the same.
+    // I don't have the patience to debug this right now, so let's leave it for another day...
+    // TODO: fix PartialEq impl on ast types
+    if format!("{ast_normalized:?}") != format!("{reparsed_ast_normalized:?}") {
+        let mut fail_file = file.to_path_buf();
+        fail_file.set_extension("roc-format-failed");
+        std::fs::write(&fail_file, buf.as_str()).unwrap();
+
+        let mut before_file = file.to_path_buf();
+        before_file.set_extension("roc-format-failed-ast-before");
+        std::fs::write(&before_file, format!("{ast_normalized:#?}\n")).unwrap();
+
+        let mut after_file = file.to_path_buf();
+        after_file.set_extension("roc-format-failed-ast-after");
+        std::fs::write(&after_file, format!("{reparsed_ast_normalized:#?}\n")).unwrap();
+
+        internal_error!(
+            "Formatting bug; formatting didn't reparse as the same tree\n\n\
+            I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
+            I wrote the tree before and after formatting to these files for debugging purposes:\n{}\n{}\n\n",
+            fail_file.display(),
+            before_file.display(),
+            after_file.display());
+    }
+
+    // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
+    let mut reformatted_buf = Buf::new_in(arena);
+    fmt_all(&mut reformatted_buf, reparsed_ast);
+    if buf.as_str() != reformatted_buf.as_str() {
+        let mut unstable_1_file = file.to_path_buf();
+        unstable_1_file.set_extension("roc-format-unstable-1");
+        std::fs::write(&unstable_1_file, buf.as_str()).unwrap();
+
+        let mut unstable_2_file = file.to_path_buf();
+        unstable_2_file.set_extension("roc-format-unstable-2");
+        std::fs::write(&unstable_2_file, reformatted_buf.as_str()).unwrap();
+
+        internal_error!(
+            "Formatting bug; formatting is not stable. Reformatting the formatted file changed it again.\n\n\
+            I wrote the result of formatting to this file for debugging purposes:\n{}\n\n\
+            I wrote the result of double-formatting here:\n{}\n\n",
+            unstable_1_file.display(),
+            unstable_2_file.display());
+    }
+
+    Ok(buf.as_str().to_string())
+}
+
+fn parse_report(file: &Path, src: &str, problem: SyntaxError) -> String {
+    let src_lines: Vec<&str> = src.lines().collect();
+    let mut module_ids = ModuleIds::default();
+    let module_id = module_ids.get_or_insert(&"find module name somehow?".into());
+    let interns = Interns::default();
+    let alloc = RocDocAllocator::new(&src_lines, module_id, &interns);
+    let lines = LineInfo::new(src);
+    let problem = FileError {
+        problem: SourceError {
+            problem,
+            bytes: src.as_bytes(),
+        },
+        filename: file.to_path_buf(),
+    };
+
+    let report = parse_problem(&alloc, &lines, file.to_path_buf(), 0, problem);
+    let mut buf = String::new();
+
+    report.render(
+        RenderTarget::ColorTerminal,
+        &mut buf,
+        &alloc,
+        &DEFAULT_PALETTE,
+    );
+
+    buf
+}
+
+/// A unified diff from the original source to the formatted one, like `diff -u` prints
+fn unified_diff(file: &Path, src: &str, formatted: &str) -> String {
+    let path = file.display().to_string();
+
+    TextDiff::from_lines(src, formatted)
+        .unified_diff()
+        .header(&path, &path)
+        .to_string()
+}
+
 fn parse_all<'a>(arena: &'a Bump, src: &'a str) -> Result<Ast<'a>, SyntaxError<'a>> {
     let (module, state) = module::parse_header(arena, State::new(src.as_bytes()))
         .map_err(|e| SyntaxError::Header(e.problem))?;
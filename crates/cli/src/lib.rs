@@ -10,16 +10,17 @@ use clap::{
 };
 use roc_build::link::{LinkType, LinkingStrategy};
 use roc_build::program::{
-    handle_error_module, handle_loading_problem, standard_load_config, BuildFileError,
-    BuildOrdering, BuiltFile, CodeGenBackend, CodeGenOptions, DEFAULT_ROC_FILENAME,
+    check_file, handle_error_module, handle_loading_problem, standard_load_config, BuildFileError,
+    BuildOrdering, BuiltFile, CheckedFile, CodeGenBackend, CodeGenOptions, DEFAULT_ROC_FILENAME,
 };
 use roc_error_macros::{internal_error, user_error};
 use roc_gen_dev::AssemblyBackendMode;
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
-use roc_load::{ExpectMetadata, Threading};
+use roc_load::{ExpectMetadata, LoadConfig, LoadingProblem, Threading};
 use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_packaging::tarball::Compression;
+use roc_reporting::report::RenderTarget;
 use roc_target::Target;
 use std::env;
 use std::ffi::{CString, OsStr, OsString};
@@ -30,12 +31,17 @@ use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Instant;
 use strum::IntoEnumIterator;
-use target_lexicon::{Architecture, Triple};
+use target_lexicon::{Architecture, BinaryFormat, Triple};
 #[cfg(not(target_os = "linux"))]
 use tempfile::TempDir;
 
+mod deps;
+pub use deps::{deps, DepsFormat};
+
 mod format;
-pub use format::format;
+pub use format::{format, format_stdin};
+
+mod watch;
 
 pub const CMD_BUILD: &str = "build";
 pub const CMD_RUN: &str = "run";
@@ -49,21 +55,41 @@ pub const CMD_FORMAT: &str = "format";
 pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
 pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
+pub const CMD_LSP: &str = "lsp";
+pub const CMD_VENDOR: &str = "vendor";
+pub const CMD_DEPS: &str = "deps";
 
 pub const FLAG_DEBUG: &str = "debug";
 pub const FLAG_BUNDLE: &str = "bundle";
+pub const FLAG_SIGN: &str = "sign";
 pub const FLAG_DEV: &str = "dev";
 pub const FLAG_OPTIMIZE: &str = "optimize";
 pub const FLAG_MAX_THREADS: &str = "max-threads";
 pub const FLAG_OPT_SIZE: &str = "opt-size";
 pub const FLAG_LIB: &str = "lib";
 pub const FLAG_NO_LINK: &str = "no-link";
+pub const FLAG_LOCKED: &str = "locked";
+pub const FLAG_STATIC: &str = "static";
+pub const FLAG_EXPORT_SYMBOLS: &str = "export-symbols";
 pub const FLAG_TARGET: &str = "target";
 pub const FLAG_TIME: &str = "time";
 pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PREBUILT: &str = "prebuilt-platform";
 pub const FLAG_CHECK: &str = "check";
+pub const FLAG_STDIN: &str = "stdin";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
+pub const FLAG_WATCH: &str = "watch";
+pub const FLAG_ERROR_FORMAT: &str = "error-format";
+pub const FLAG_COVERAGE: &str = "coverage";
+pub const FLAG_LCOV: &str = "lcov";
+pub const FLAG_DOC: &str = "doc";
+pub const FLAG_LIST: &str = "list";
+pub const FLAG_FILTER: &str = "filter";
+pub const FLAG_MODULE: &str = "module";
+pub const FLAG_OUTPUT: &str = "output";
+pub const FLAG_FORMAT: &str = "format";
+pub const FLAG_WHY: &str = "why";
+pub const FLAG_WITH_DEPENDENCIES: &str = "with-dependencies";
 pub const ROC_FILE: &str = "ROC_FILE";
 pub const ROC_DIR: &str = "ROC_DIR";
 pub const GLUE_DIR: &str = "GLUE_DIR";
@@ -112,7 +138,7 @@ pub fn build_app() -> Command {
 
     let flag_linker = Arg::new(FLAG_LINKER)
         .long(FLAG_LINKER)
-        .help("Set which linker to use\n(The surgical linker is enabled by default only when building for wasm32 or x86_64 Linux, because those are the only targets it currently supports. Otherwise the legacy linker is used by default.)")
+        .help("Set which linker to use\n(The surgical linker is enabled by default only when building for wasm32, or for x86_64 or aarch64 Linux or Windows, because those are the only targets it currently supports. Otherwise the legacy linker is used by default.)")
         .value_parser(["surgical", "legacy"])
         .required(false);
 
@@ -128,6 +154,19 @@ pub fn build_app() -> Command {
         .value_parser(value_parser!(u32))
         .required(false);
 
+    let flag_watch = Arg::new(FLAG_WATCH)
+        .long(FLAG_WATCH)
+        .help("Keep running, and start over whenever the .roc file, one of the modules it imports, or the platform's host source changes")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
+    let flag_error_format = Arg::new(FLAG_ERROR_FORMAT)
+        .long(FLAG_ERROR_FORMAT)
+        .help("How to print problems in the code\n(`json` prints each problem as a JSON object on its own line, for editors and other tools to consume.)")
+        .value_parser(["human", "json"])
+        .default_value("human")
+        .required(false);
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .value_parser(value_parser!(PathBuf))
@@ -157,6 +196,7 @@ pub fn build_app() -> Command {
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_wasm_stack_size_kb)
+            .arg(flag_error_format.clone())
             .arg(
                 Arg::new(FLAG_TARGET)
                     .long(FLAG_TARGET)
@@ -172,6 +212,22 @@ pub fn build_app() -> Command {
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_STATIC)
+                    .long(FLAG_STATIC)
+                    .help("Build a static library (.a or .lib) instead of a dynamic one\n(Requires --lib. The exposed symbols are listed in a .symbols file next to it.)")
+                    .action(ArgAction::SetTrue)
+                    .requires(FLAG_LIB)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_EXPORT_SYMBOLS)
+                    .long(FLAG_EXPORT_SYMBOLS)
+                    .help("Only export the symbols listed in this file (one per line, like the .symbols file) from the static library\n(Requires --static. Every other symbol becomes local to the library.)")
+                    .requires(FLAG_STATIC)
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
             .arg(
                 Arg::new(FLAG_BUNDLE)
                     .long(FLAG_BUNDLE)
@@ -180,6 +236,14 @@ pub fn build_app() -> Command {
                     .value_parser([".tar", ".tar.gz", ".tar.br"])
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_SIGN)
+                    .long(FLAG_SIGN)
+                    .help("Sign the bundle with this ed25519 private key (a PKCS#8 .der file)\n(Writes a detached .sig file next to the bundle.)")
+                    .requires(FLAG_BUNDLE)
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
             .arg(
                 Arg::new(FLAG_NO_LINK)
                     .long(FLAG_NO_LINK)
@@ -187,6 +251,13 @@ pub fn build_app() -> Command {
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_LOCKED)
+                    .long(FLAG_LOCKED)
+                    .help("Fail if the packages this depends on differ from the ones in roc.lock\n(Instead of updating roc.lock.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file to build")
@@ -205,6 +276,50 @@ pub fn build_app() -> Command {
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(
+                Arg::new(FLAG_COVERAGE)
+                    .long(FLAG_COVERAGE)
+                    .help("Count how often the tests run each top-level def and `when` branch, and print a summary")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_LCOV)
+                    .long(FLAG_LCOV)
+                    .help("Also write the coverage to this file, in the lcov format\n(This only applies when --coverage is also provided.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .requires(FLAG_COVERAGE)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_DOC)
+                    .long(FLAG_DOC)
+                    .help("Also run the `expect`s in the code blocks of doc comments\n(Failures are reported in the doc comment. Mark a block ```roc,unchecked to skip it.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_LIST)
+                    .long(FLAG_LIST)
+                    .help("List the `expect`s that would run, without running them")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FILTER)
+                    .long(FLAG_FILTER)
+                    .help("Only run the `expect`s whose name contains this text\n(An `expect` is named by the comment directly above it.)")
+                    .value_parser(value_parser!(String))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MODULE)
+                    .long(FLAG_MODULE)
+                    .help("Only run the `expect`s in this module\n(This can be provided more than once.)")
+                    .value_parser(value_parser!(String))
+                    .action(ArgAction::Append)
+                    .required(false)
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file for the main module")
@@ -240,6 +355,7 @@ pub fn build_app() -> Command {
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_watch.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone().last(true))
         )
@@ -254,10 +370,18 @@ pub fn build_app() -> Command {
             .arg(
                 Arg::new(FLAG_CHECK)
                     .long(FLAG_CHECK)
-                    .help("Checks that specified files are formatted\n(If formatting is needed, return a non-zero exit code.)")
+                    .help("Checks that specified files are formatted\n(If formatting is needed, print a diff of the changes and return a non-zero exit code.)")
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_STDIN)
+                    .long(FLAG_STDIN)
+                    .help("Format code read from stdin, and write the result to stdout")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with(DIRECTORY_OR_FILES)
+                    .required(false),
+            )
         )
         .subcommand(Command::new(CMD_VERSION)
             .about(concatcp!("Print the Roc compiler’s version, which is currently ", VERSION)))
@@ -265,6 +389,8 @@ pub fn build_app() -> Command {
             .about("Check the code for problems, but don’t build or run it")
             .arg(flag_time.clone())
             .arg(flag_max_threads.clone())
+            .arg(flag_watch)
+            .arg(flag_error_format)
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -276,6 +402,21 @@ pub fn build_app() -> Command {
         .subcommand(
             Command::new(CMD_DOCS)
                 .about("Generate documentation for a Roc package")
+                .arg(
+                    Arg::new(FLAG_OUTPUT)
+                        .long(FLAG_OUTPUT)
+                        .help("The directory to write the docs into")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(false)
+                        .default_value(roc_docs::DEFAULT_OUTPUT_DIR),
+                )
+                .arg(
+                    Arg::new(FLAG_WITH_DEPENDENCIES)
+                        .long(FLAG_WITH_DEPENDENCIES)
+                        .help("Also generate docs for the packages this package depends on, and link to them")
+                        .action(ArgAction::SetTrue)
+                        .required(false)
+                )
                 .arg(Arg::new(ROC_FILE)
                     .help("The package's main .roc file")
                     .value_parser(value_parser!(PathBuf))
@@ -283,12 +424,55 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME),
                 )
         )
+        .subcommand(
+            Command::new(CMD_VENDOR)
+                .about("Download the packages an app depends on into a directory, so it can be built without network access")
+                .arg(
+                    Arg::new(FLAG_OUTPUT)
+                        .long(FLAG_OUTPUT)
+                        .help("The directory to download the packages into\n(Point the ROC_PACKAGE_MIRROR environment variable at it to install packages from there.)")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(false)
+                        .default_value("vendor"),
+                )
+                .arg(Arg::new(ROC_FILE)
+                    .help("The .roc file whose header lists the packages")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+                )
+        )
+        .subcommand(
+            Command::new(CMD_DEPS)
+                .about("Print the modules a .roc file depends on, directly or indirectly")
+                .arg(
+                    Arg::new(FLAG_FORMAT)
+                        .long(FLAG_FORMAT)
+                        .help("How to print the module graph\n(`dot` can be rendered with Graphviz, and `json` is for other tools to consume.)")
+                        .value_parser(DepsFormat::NAMES)
+                        .default_value("tree")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new(FLAG_WHY)
+                        .long(FLAG_WHY)
+                        .help("Only print the import paths which lead to this module\n(e.g. `Dict`, or `pf.Task` for a module from the package with the shorthand `pf`)")
+                        .value_parser(value_parser!(String))
+                        .required(false),
+                )
+                .arg(Arg::new(ROC_FILE)
+                    .help("The .roc file whose dependencies to print")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+                )
+        )
         .subcommand(Command::new(CMD_GLUE)
             .about("Generate glue code between a platform's Roc API and its host language")
             .arg(&flag_dev)
             .arg(
                 Arg::new(GLUE_SPEC)
-                    .help("The specification for how to translate Roc types into output files.")
+                    .help("The specification for how to translate Roc types into output files.\nEither the path to a .roc glue spec, or `c` or `zig` to use a spec built into the compiler.")
                     .value_parser(value_parser!(PathBuf))
                     .required(true)
             )
@@ -306,6 +490,9 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME)
             )
         )
+        .subcommand(Command::new(CMD_LSP)
+            .about("Start a language server, which editors talk to over stdin and stdout using the Language Server Protocol")
+        )
         .subcommand(Command::new(CMD_GEN_STUB_LIB)
             .about("Generate a stubbed shared library that can be used for linking a platform binary.\nThe stubbed library has prototypes, but no function bodies.\n\nNote: This command will be removed in favor of just using `roc build` once all platforms support the surgical linker")
             .arg(
@@ -377,6 +564,14 @@ fn opt_level_from_flags(matches: &ArgMatches) -> OptLevel {
     }
 }
 
+fn render_target_from_flags(matches: &ArgMatches) -> RenderTarget {
+    // Only some subcommands accept this flag, so it may not be defined at all.
+    match matches.try_get_one::<String>(FLAG_ERROR_FORMAT) {
+        Ok(Some(format)) if format == "json" => RenderTarget::Json,
+        _ => RenderTarget::ColorTerminal,
+    }
+}
+
 #[cfg(windows)]
 pub fn test(_matches: &ArgMatches, _triple: Triple) -> io::Result<i32> {
     todo!("running tests does not work on windows right now")
@@ -385,8 +580,9 @@ pub fn test(_matches: &ArgMatches, _triple: Triple) -> io::Result<i32> {
 #[cfg(not(windows))]
 pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     use roc_build::program::report_problems_monomorphized;
-    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError};
+    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, TestOptions};
     use roc_packaging::cache;
+    use roc_repl_expect::coverage::{render_lcov, render_summary, CoverageCounters};
     use roc_target::TargetInfo;
 
     let start_time = Instant::now();
@@ -429,6 +625,10 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let target_info = TargetInfo::from(target);
     // TODO may need to determine this dynamically based on dev builds.
     let function_kind = FunctionKind::LambdaSet;
+    let exec_mode = ExecutionMode::Test(TestOptions {
+        coverage: matches.get_flag(FLAG_COVERAGE),
+        doc_tests: matches.get_flag(FLAG_DOC),
+    });
 
     // Step 1: compile the app and generate the .o file
     let load_config = LoadConfig {
@@ -438,7 +638,7 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         render: roc_reporting::report::RenderTarget::ColorTerminal,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading,
-        exec_mode: ExecutionMode::Test,
+        exec_mode,
     };
     let load_result = roc_load::load_and_monomorphize(
         arena,
@@ -450,19 +650,30 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let mut loaded = match load_result {
         Ok(loaded) => loaded,
         Err(LoadMonomorphizedError::LoadingProblem(problem)) => {
-            return handle_loading_problem(problem);
+            return handle_loading_problem(problem, RenderTarget::ColorTerminal);
         }
         Err(LoadMonomorphizedError::ErrorModule(module)) => {
-            return handle_error_module(module, start_time.elapsed(), path.as_os_str(), false);
+            return handle_error_module(
+                module,
+                start_time.elapsed(),
+                path.as_os_str(),
+                false,
+                RenderTarget::ColorTerminal,
+            );
         }
     };
-    let problems = report_problems_monomorphized(&mut loaded);
+    let problems = report_problems_monomorphized(&mut loaded, RenderTarget::ColorTerminal);
 
     let mut expectations = std::mem::take(&mut loaded.expectations);
 
+    let coverage = loaded
+        .coverage
+        .take()
+        .map(|points| (points, std::mem::take(&mut loaded.sources)));
+
     let interns = loaded.interns.clone();
 
-    let (lib, expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
+    let (lib, mut expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
         arena,
         target.clone(),
         loaded,
@@ -471,6 +682,66 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     )
     .unwrap();
 
+    let modules: Option<Vec<&String>> = matches
+        .get_many::<String>(FLAG_MODULE)
+        .map(|modules| modules.collect());
+    let filter = matches.get_one::<String>(FLAG_FILTER);
+
+    expects.retain(|expect| {
+        let in_module = || {
+            let module_name = interns.module_name(expect.symbol.module_id());
+
+            modules
+                .iter()
+                .flatten()
+                .any(|name| name.as_str() == module_name.as_str())
+        };
+        let matches_filter = || {
+            let label = expect.label.unwrap_or_default();
+
+            filter.map_or(true, |filter| label.contains(filter.as_str()))
+        };
+
+        (modules.is_none() || in_module()) && matches_filter()
+    });
+
+    if matches.get_flag(FLAG_LIST) {
+        let mut names: Vec<_> = expects
+            .iter()
+            .map(|expect| {
+                (
+                    expect.symbol.module_id(),
+                    expect.line,
+                    expect.display_name(&interns),
+                )
+            })
+            .collect();
+
+        names.sort_by(|(a_module, a_line, _), (b_module, b_line, _)| {
+            let a_module = interns.module_name(*a_module);
+            let b_module = interns.module_name(*b_module);
+
+            (a_module.as_str(), a_line).cmp(&(b_module.as_str(), b_line))
+        });
+
+        for (_, _, name) in names {
+            println!("{name}");
+        }
+
+        return Ok(0);
+    }
+
+    // Run the tests in as many processes as we would use threads to compile
+    let workers = {
+        let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+        match threading {
+            Threading::Single => 1,
+            Threading::AllAvailable => available,
+            Threading::AtMost(at_most) => Ord::min(available, at_most),
+        }
+    };
+
     // Print warnings before running tests.
     {
         debug_assert_eq!(
@@ -489,7 +760,13 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
 
     let mut writer = std::io::stdout();
 
-    let (failed, passed) = roc_repl_expect::run::run_toplevel_expects(
+    let counters = coverage.as_ref().map(|(points, _)| {
+        let mut counters = CoverageCounters::new(points.len());
+        counters.install(&lib);
+        counters
+    });
+
+    let outcomes = roc_repl_expect::run::run_toplevel_expects(
         &mut writer,
         roc_reporting::report::RenderTarget::ColorTerminal,
         arena,
@@ -498,9 +775,42 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         &lib,
         &mut expectations,
         expects,
+        workers,
     )
     .unwrap();
 
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+    let passed = outcomes.len() - failed;
+
+    if matches.get_flag(FLAG_TIME) && !outcomes.is_empty() {
+        let mut outcomes = outcomes;
+
+        // slowest first
+        outcomes.sort_by(|a, b| b.duration.cmp(&a.duration));
+
+        println!("\nTime per `expect`:\n");
+
+        for outcome in outcomes {
+            println!(
+                "{:>10.3} ms  {}",
+                outcome.duration.as_secs_f64() * 1000.0,
+                outcome.expect.display_name(interns),
+            );
+        }
+    }
+
+    if let (Some((points, sources)), Some(counters)) = (&coverage, &counters) {
+        let (points, counts) = (points.points(), counters.counts());
+
+        println!("\n{}", render_summary(sources, interns, points, counts));
+
+        if let Some(lcov_path) = matches.get_one::<PathBuf>(FLAG_LCOV) {
+            std::fs::write(lcov_path, render_lcov(sources, interns, points, counts))?;
+
+            println!("Wrote the lcov coverage report to {}", lcov_path.display());
+        }
+    }
+
     let total_time = start_time.elapsed();
 
     if failed == 0 && passed == 0 {
@@ -529,6 +839,152 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     }
 }
 
+pub fn vendor(roc_file_path: &Path, mirror_dir: &Path) -> io::Result<i32> {
+    use roc_packaging::mirror::{self, MIRROR_ENV_VAR};
+
+    let urls = mirror::package_urls(roc_file_path)?;
+
+    match mirror::vendor(urls, mirror_dir) {
+        Ok(vendored) if vendored.is_empty() => {
+            println!(
+                "{} doesn't depend on any packages from a URL, so there was nothing to download.",
+                roc_file_path.display()
+            );
+
+            Ok(0)
+        }
+        Ok(vendored) => {
+            println!(
+                "Downloaded these packages into \x1B[33m{}\x1B[39m:\n",
+                mirror_dir.display()
+            );
+
+            for url in vendored {
+                println!("\t\x1B[36m{url}\x1B[39m");
+            }
+
+            println!("\nTo install packages from there instead of the network, set the {MIRROR_ENV_VAR} environment variable to the absolute path of that directory.");
+
+            Ok(0)
+        }
+        Err((url, problem)) => {
+            let report = roc_reporting::report::to_https_problem_report_string(
+                &url,
+                problem,
+                RenderTarget::ColorTerminal,
+            );
+
+            eprintln!("{report}");
+
+            Ok(1)
+        }
+    }
+}
+
+pub fn check(matches: &ArgMatches, roc_cache_dir: RocCacheDir<'_>) -> io::Result<i32> {
+    let emit_timings = matches.get_flag(FLAG_TIME);
+    let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+    let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
+        None => Threading::AllAvailable,
+        Some(0) => user_error!("cannot build with at most 0 threads"),
+        Some(1) => Threading::Single,
+        Some(n) => Threading::AtMost(*n),
+    };
+    let render = render_target_from_flags(matches);
+
+    if matches.get_flag(FLAG_WATCH) {
+        watch::check(
+            roc_file_path,
+            emit_timings,
+            roc_cache_dir,
+            threading,
+            render,
+        )
+    } else {
+        let arena = Bump::new();
+        let (exit_code, _) = check_and_report(
+            &arena,
+            roc_file_path,
+            emit_timings,
+            roc_cache_dir,
+            threading,
+            render,
+        );
+
+        Ok(exit_code)
+    }
+}
+
+/// Checks the given file and prints a summary of the problems that were found. Returns the exit
+/// code, along with the files that were checked (if loading got far enough to know them).
+fn check_and_report(
+    arena: &Bump,
+    roc_file_path: &Path,
+    emit_timings: bool,
+    roc_cache_dir: RocCacheDir<'_>,
+    threading: Threading,
+    render: RenderTarget,
+) -> (i32, Option<Vec<PathBuf>>) {
+    match check_file(
+        arena,
+        roc_file_path.to_owned(),
+        emit_timings,
+        roc_cache_dir,
+        threading,
+        render,
+    ) {
+        Ok(CheckedFile {
+            problems,
+            total_time,
+            source_paths,
+        }) => {
+            if let RenderTarget::Json = render {
+                // Tools reading JSON expect nothing but the reports themselves.
+                return (problems.exit_code(), Some(source_paths));
+            }
+
+            println!(
+                "\x1B[{}m{}\x1B[39m {} and \x1B[{}m{}\x1B[39m {} found in {} ms.",
+                if problems.errors == 0 {
+                    32 // green
+                } else {
+                    33 // yellow
+                },
+                problems.errors,
+                if problems.errors == 1 {
+                    "error"
+                } else {
+                    "errors"
+                },
+                if problems.warnings == 0 {
+                    32 // green
+                } else {
+                    33 // yellow
+                },
+                problems.warnings,
+                if problems.warnings == 1 {
+                    "warning"
+                } else {
+                    "warnings"
+                },
+                total_time.as_millis(),
+            );
+
+            (problems.exit_code(), Some(source_paths))
+        }
+
+        Err(problem @ LoadingProblem::FormattedReport(_)) => {
+            // Printing a report can't fail.
+            let exit_code = handle_loading_problem(problem, render).unwrap_or(1);
+
+            (exit_code, None)
+        }
+        Err(other) => {
+            panic!("build_file failed with error:\n{other:?}");
+        }
+    }
+}
+
 /// Find the element of `options` with the smallest edit distance to
 /// `reference`. Returns a tuple containing the element and the distance, or
 /// `None` if the `options` `Vec` is empty.
@@ -539,6 +995,91 @@ fn nearest_match<'a>(reference: &str, options: &'a [String]) -> Option<(&'a Stri
         .min_by(|(_, a), (_, b)| a.cmp(b))
 }
 
+/// Install the packages the given module depends on and record them in the roc.lock next to it,
+/// or with `locked`, make sure they match the ones already recorded there.
+fn update_lockfile(
+    roc_file_path: &Path,
+    roc_cache_dir: RocCacheDir<'_>,
+    locked: bool,
+) -> io::Result<i32> {
+    use roc_packaging::lock::{self, LockProblem, Lockfile, LOCKFILE_NAME};
+
+    let lockfile_path = roc_file_path.with_file_name(LOCKFILE_NAME);
+
+    let problem = match Lockfile::read(&lockfile_path) {
+        Ok(None) if locked => {
+            eprintln!(
+                "\nThe --{FLAG_LOCKED} flag was given, but there is no {LOCKFILE_NAME} file to check against:\n\n\t{}\n\nRun `roc build` without --{FLAG_LOCKED} to create it.\n",
+                lockfile_path.display()
+            );
+
+            return Ok(1);
+        }
+        Ok(existing) => {
+            let locked_packages = if locked { existing.as_ref() } else { None };
+
+            match lock::resolve(roc_cache_dir, roc_file_path, locked_packages) {
+                Ok(lockfile) => {
+                    // Don't create lockfiles for modules which don't depend on any packages
+                    let needs_writing = match existing {
+                        Some(existing) => existing != lockfile,
+                        None => !lockfile.packages.is_empty(),
+                    };
+
+                    if needs_writing {
+                        std::fs::write(&lockfile_path, lockfile.to_file_contents())?;
+                    }
+
+                    return Ok(0);
+                }
+                Err(problem) => problem,
+            }
+        }
+        Err(problem) => problem,
+    };
+
+    match problem {
+        LockProblem::Install { url, problem } => {
+            let report = roc_reporting::report::to_https_problem_report_string(
+                &url,
+                problem,
+                RenderTarget::ColorTerminal,
+            );
+
+            eprintln!("{report}");
+        }
+        LockProblem::Io(err) => return Err(err),
+        LockProblem::Malformed(line) => {
+            eprintln!(
+                "\nI couldn't understand this line in {}:\n\n\t{line}\n\nDeleting the file and running `roc build` again will recreate it.\n",
+                lockfile_path.display()
+            );
+        }
+        LockProblem::NotLocked(package) => {
+            eprintln!(
+                "\n{} depends on this package, but it isn't in {}:\n\n\t{}\n\nRun `roc build` without --{FLAG_LOCKED} to update it.\n",
+                package.required_by,
+                lockfile_path.display(),
+                package.url
+            );
+        }
+        LockProblem::NoLongerNeeded(packages) => {
+            eprintln!(
+                "\n{} lists these packages, but nothing depends on them anymore:\n",
+                lockfile_path.display()
+            );
+
+            for package in packages {
+                eprintln!("\t{} (required by {})", package.url, package.required_by);
+            }
+
+            eprintln!("\nRun `roc build` without --{FLAG_LOCKED} to update it.\n");
+        }
+    }
+
+    Ok(1)
+}
+
 pub fn build(
     matches: &ArgMatches,
     subcommands: &[String],
@@ -636,8 +1177,31 @@ pub fn build(
                 created_path.to_string_lossy()
             );
 
+            if let Some(signing_key_path) = matches.get_one::<PathBuf>(FLAG_SIGN) {
+                // The filename is the content hash followed by the compression's file extension
+                let content_hash = filename.split('.').next().unwrap();
+                let (signature_path, public_key) = roc_packaging::signature::sign_bundle(
+                    signing_key_path,
+                    &created_path,
+                    content_hash,
+                )?;
+
+                println!(
+                    "Signed it with the public key \x1B[33m{public_key}\x1B[39m into:\n\n\t\x1B[33m{}\x1B[39m\n\nUpload this next to the archive, so that people who trust that key can verify it.\n",
+                    signature_path.to_string_lossy()
+                );
+            }
+
             return Ok(0);
         }
+
+        if config == BuildConfig::BuildOnly {
+            let exit_code = update_lockfile(path, roc_cache_dir, matches.get_flag(FLAG_LOCKED))?;
+
+            if exit_code != 0 {
+                return Ok(exit_code);
+            }
+        }
     }
 
     // the process will end after this function,
@@ -656,7 +1220,14 @@ pub fn build(
         if matches!(triple.architecture, Architecture::Wasm32) {
             CodeGenBackend::Wasm
         } else {
-            CodeGenBackend::Assembly(AssemblyBackendMode::Binary)
+            let backend_mode = match opt_level {
+                OptLevel::Development => AssemblyBackendMode::BinaryDev,
+                OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => {
+                    AssemblyBackendMode::Binary
+                }
+            };
+
+            CodeGenBackend::Assembly(backend_mode)
         }
     } else {
         let backend_mode = match opt_level {
@@ -680,6 +1251,10 @@ pub fn build(
     let wasm_dev_backend = matches!(code_gen_backend, CodeGenBackend::Wasm);
 
     let linking_strategy = if wasm_dev_backend {
+        if link_type == LinkType::StaticLib {
+            user_error!("`--static` is not supported by the wasm dev backend, which can only build an object file");
+        }
+
         LinkingStrategy::Additive
     } else if !roc_linker::supported(link_type, &triple)
         || matches.get_one::<String>(FLAG_LINKER).map(|s| s.as_str()) == Some("legacy")
@@ -707,6 +1282,29 @@ pub fn build(
         .flatten()
         .map(|x| x * 1024);
 
+    let static_lib_exports: Option<Vec<String>> = matches
+        .try_get_one::<PathBuf>(FLAG_EXPORT_SYMBOLS)
+        .ok()
+        .flatten()
+        .map(|symbols_path| {
+            if triple.binary_format != BinaryFormat::Elf {
+                user_error!("`--{FLAG_EXPORT_SYMBOLS}` only supports ELF targets for now");
+            }
+
+            match std::fs::read_to_string(symbols_path) {
+                Ok(contents) => contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect(),
+                Err(err) => user_error!(
+                    "could not read the symbols to export from {}: {err}",
+                    symbols_path.display()
+                ),
+            }
+        });
+
     let build_ordering = match config {
         BuildAndRunIfNoErrors => BuildOrdering::BuildIfChecks,
         _ => BuildOrdering::AlwaysBuild,
@@ -718,7 +1316,47 @@ pub fn build(
         emit_debug_info,
     };
 
-    let load_config = standard_load_config(&triple, build_ordering, threading);
+    let render = render_target_from_flags(matches);
+
+    let watch = matches
+        .try_get_one::<bool>(FLAG_WATCH)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+
+    if config == BuildAndRunIfNoErrors && watch {
+        let args: Vec<OsString> = matches
+            .get_many::<OsString>(ARGS_FOR_APP)
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+
+        return watch::dev(path, &args, |arena| {
+            let load_config =
+                standard_load_config(&triple, BuildOrdering::BuildIfChecks, threading);
+
+            build_file(
+                arena,
+                &triple,
+                path.to_owned(),
+                code_gen_options,
+                emit_timings,
+                link_type,
+                linking_strategy,
+                prebuilt,
+                wasm_dev_stack_bytes,
+                None,
+                roc_cache_dir,
+                load_config,
+            )
+        });
+    }
+
+    let load_config = LoadConfig {
+        render,
+        ..standard_load_config(&triple, build_ordering, threading)
+    };
 
     let res_binary_path = build_file(
         &arena,
@@ -730,6 +1368,7 @@ pub fn build(
         linking_strategy,
         prebuilt,
         wasm_dev_stack_bytes,
+        static_lib_exports.as_deref(),
         roc_cache_dir,
         load_config,
     );
@@ -740,6 +1379,7 @@ pub fn build(
             problems,
             total_time,
             expect_metadata,
+            source_paths: _,
         }) => {
             match config {
                 BuildOnly => {
@@ -754,6 +1394,11 @@ pub fn build(
                     // since the process is about to exit anyway.
                     // std::mem::forget(arena);
 
+                    if let RenderTarget::Json = render {
+                        // Tools reading JSON expect nothing but the reports themselves.
+                        return Ok(problems.exit_code());
+                    }
+
                     problems.print_to_stdout(total_time);
                     println!(" while successfully building:\n\n    {generated_filename}");
 
@@ -828,9 +1473,14 @@ pub fn build(
             }
         }
         Err(BuildFileError::ErrorModule { module, total_time }) => {
-            handle_error_module(module, total_time, path.as_os_str(), true)
+            handle_error_module(module, total_time, path.as_os_str(), true, render)
+        }
+        Err(BuildFileError::LoadingProblem(problem)) => handle_loading_problem(problem, render),
+        Err(BuildFileError::Io(err)) => {
+            eprintln!("Failed to build {}: {err}", path.display());
+
+            Ok(1)
         }
-        Err(BuildFileError::LoadingProblem(problem)) => handle_loading_problem(problem),
     }
 }
 
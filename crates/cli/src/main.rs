@@ -1,17 +1,19 @@
 //! The `roc` binary that brings together all functionality in the Roc toolset.
 use roc_build::link::LinkType;
-use roc_build::program::{check_file, CodeGenBackend};
+use roc_build::program::CodeGenBackend;
 use roc_cli::{
-    build_app, format, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CHECK, CMD_DEV, CMD_DOCS,
-    CMD_EDIT, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_REPL, CMD_RUN, CMD_TEST, CMD_VERSION,
-    DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB, FLAG_NO_LINK, FLAG_TARGET, FLAG_TIME,
-    GLUE_DIR, GLUE_SPEC, ROC_FILE,
+    build_app, check, deps, format, format_stdin, test, vendor, BuildConfig, DepsFormat,
+    FormatMode, CMD_BUILD, CMD_CHECK, CMD_DEPS, CMD_DEV, CMD_DOCS, CMD_EDIT, CMD_FORMAT,
+    CMD_GEN_STUB_LIB, CMD_GLUE, CMD_LSP, CMD_REPL, CMD_RUN, CMD_TEST, CMD_VENDOR, CMD_VERSION,
+    DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_FORMAT, FLAG_LIB, FLAG_NO_LINK, FLAG_OUTPUT,
+    FLAG_STATIC, FLAG_STDIN, FLAG_TARGET, FLAG_WHY, FLAG_WITH_DEPENDENCIES, GLUE_DIR, GLUE_SPEC,
+    ROC_FILE,
 };
 use roc_docs::generate_docs_html;
 use roc_error_macros::user_error;
 use roc_gen_dev::AssemblyBackendMode;
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
-use roc_load::{FunctionKind, LoadingProblem, Threading};
+use roc_load::FunctionKind;
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_target::Target;
 use std::fs::{self, FileType};
@@ -109,14 +111,23 @@ fn main() -> io::Result<()> {
                 false => CodeGenBackend::Llvm(LlvmBackendMode::BinaryGlue),
             };
 
-            if !output_path.exists() || output_path.is_dir() {
-                roc_glue::generate(input_path, output_path, spec_path, backend)
-            } else {
+            // A path to a spec takes precedence over a builtin spec with the same name
+            let builtin_spec = spec_path
+                .to_str()
+                .and_then(roc_glue::BuiltinSpec::from_name)
+                .filter(|_| !spec_path.exists());
+
+            if output_path.exists() && !output_path.is_dir() {
                 eprintln!("`roc glue` must be given a directory to output into, because the glue might generate multiple files.");
 
                 Ok(1)
+            } else if let Some(spec) = builtin_spec {
+                roc_glue::generate_builtin(input_path, output_path, spec)
+            } else {
+                roc_glue::generate(input_path, output_path, spec_path, backend)
             }
         }
+        Some((CMD_LSP, _)) => roc_language_server::run_stdio(),
         Some((CMD_GEN_STUB_LIB, matches)) => {
             let input_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
             let target = matches
@@ -137,6 +148,7 @@ fn main() -> io::Result<()> {
                 .and_then(|s| Target::from_str(s).ok())
                 .unwrap_or_default();
             let link_type = match (matches.get_flag(FLAG_LIB), matches.get_flag(FLAG_NO_LINK)) {
+                (true, false) if matches.get_flag(FLAG_STATIC) => LinkType::StaticLib,
                 (true, false) => LinkType::Dylib,
                 (true, true) => user_error!("build can only be one of `--lib` or `--no-link`"),
                 (false, true) => LinkType::None,
@@ -152,66 +164,10 @@ fn main() -> io::Result<()> {
                 link_type,
             )?)
         }
-        Some((CMD_CHECK, matches)) => {
-            let arena = bumpalo::Bump::new();
-
-            let emit_timings = matches.get_flag(FLAG_TIME);
-            let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
-            let threading = match matches.get_one::<usize>(roc_cli::FLAG_MAX_THREADS) {
-                None => Threading::AllAvailable,
-                Some(0) => user_error!("cannot build with at most 0 threads"),
-                Some(1) => Threading::Single,
-                Some(n) => Threading::AtMost(*n),
-            };
-
-            match check_file(
-                &arena,
-                roc_file_path.to_owned(),
-                emit_timings,
-                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-                threading,
-            ) {
-                Ok((problems, total_time)) => {
-                    println!(
-                        "\x1B[{}m{}\x1B[39m {} and \x1B[{}m{}\x1B[39m {} found in {} ms.",
-                        if problems.errors == 0 {
-                            32 // green
-                        } else {
-                            33 // yellow
-                        },
-                        problems.errors,
-                        if problems.errors == 1 {
-                            "error"
-                        } else {
-                            "errors"
-                        },
-                        if problems.warnings == 0 {
-                            32 // green
-                        } else {
-                            33 // yellow
-                        },
-                        problems.warnings,
-                        if problems.warnings == 1 {
-                            "warning"
-                        } else {
-                            "warnings"
-                        },
-                        total_time.as_millis(),
-                    );
-
-                    Ok(problems.exit_code())
-                }
-
-                Err(LoadingProblem::FormattedReport(report)) => {
-                    print!("{report}");
-
-                    Ok(1)
-                }
-                Err(other) => {
-                    panic!("build_file failed with error:\n{other:?}");
-                }
-            }
-        }
+        Some((CMD_CHECK, matches)) => check(
+            matches,
+            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        ),
         Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
         Some((CMD_EDIT, matches)) => {
             match matches
@@ -231,48 +187,80 @@ fn main() -> io::Result<()> {
         }
         Some((CMD_DOCS, matches)) => {
             let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let output_dir = matches.get_one::<PathBuf>(FLAG_OUTPUT).unwrap();
 
-            generate_docs_html(root_path.to_owned());
+            generate_docs_html(
+                root_path.to_owned(),
+                output_dir,
+                matches.get_flag(FLAG_WITH_DEPENDENCIES),
+            );
 
             Ok(0)
         }
+        Some((CMD_VENDOR, matches)) => {
+            let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let mirror_dir = matches.get_one::<PathBuf>(FLAG_OUTPUT).unwrap();
+
+            vendor(root_path, mirror_dir)
+        }
+        Some((CMD_DEPS, matches)) => {
+            let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let format = matches
+                .get_one::<String>(FLAG_FORMAT)
+                .and_then(|name| DepsFormat::from_name(name))
+                .unwrap();
+            let why = matches.get_one::<String>(FLAG_WHY);
+
+            deps(
+                root_path,
+                format,
+                why.map(String::as_str),
+                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+            )
+        }
         Some((CMD_FORMAT, matches)) => {
-            let maybe_values = matches.get_many::<OsString>(DIRECTORY_OR_FILES);
-
-            let mut values: Vec<OsString> = Vec::new();
-
-            match maybe_values {
-                None => {
-                    let mut os_string_values: Vec<OsString> = Vec::new();
-                    read_all_roc_files(
-                        &std::env::current_dir()?.as_os_str().to_os_string(),
-                        &mut os_string_values,
-                    )?;
-                    for os_string in os_string_values {
-                        values.push(os_string);
+            let format_mode = match matches.get_flag(FLAG_CHECK) {
+                true => FormatMode::CheckOnly,
+                false => FormatMode::Format,
+            };
+
+            let format_result = if matches.get_flag(FLAG_STDIN) {
+                format_stdin(format_mode)
+            } else {
+                let maybe_values = matches.get_many::<OsString>(DIRECTORY_OR_FILES);
+
+                let mut values: Vec<OsString> = Vec::new();
+
+                match maybe_values {
+                    None => {
+                        let mut os_string_values: Vec<OsString> = Vec::new();
+                        read_all_roc_files(
+                            &std::env::current_dir()?.as_os_str().to_os_string(),
+                            &mut os_string_values,
+                        )?;
+                        for os_string in os_string_values {
+                            values.push(os_string);
+                        }
                     }
-                }
-                Some(os_values) => {
-                    for os_string in os_values {
-                        values.push(os_string.to_owned());
+                    Some(os_values) => {
+                        for os_string in os_values {
+                            values.push(os_string.to_owned());
+                        }
                     }
                 }
-            }
 
-            let mut roc_files = Vec::new();
+                let mut roc_files = Vec::new();
 
-            // Populate roc_files
-            for os_str in values {
-                let metadata = fs::metadata(os_str.clone())?;
-                roc_files_recursive(os_str.as_os_str(), metadata.file_type(), &mut roc_files)?;
-            }
+                // Populate roc_files
+                for os_str in values {
+                    let metadata = fs::metadata(os_str.clone())?;
+                    roc_files_recursive(os_str.as_os_str(), metadata.file_type(), &mut roc_files)?;
+                }
 
-            let format_mode = match matches.get_flag(FLAG_CHECK) {
-                true => FormatMode::CheckOnly,
-                false => FormatMode::Format,
+                format(roc_files, format_mode)
             };
 
-            let format_exit_code = match format(roc_files, format_mode) {
+            let format_exit_code = match format_result {
                 Ok(_) => 0,
                 Err(message) => {
                     eprintln!("{message}");
@@ -0,0 +1,422 @@
+//! The `--watch` mode of `roc check` and `roc dev`, which starts over whenever one of the
+//! files that went into the previous run changes.
+//!
+//! We poll modification times rather than subscribing to file system events: a module graph is
+//! rarely more than a few dozen files, and polling behaves the same on every OS and file system
+//! (including network mounts and editors that save by renaming a temporary file).
+use bumpalo::Bump;
+use roc_build::program::{
+    handle_error_module, handle_loading_problem, module_source_paths, BuildFileError, BuiltFile,
+};
+use roc_load::Threading;
+use roc_packaging::cache::RocCacheDir;
+use roc_reporting::report::RenderTarget;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(not(windows))]
+use {
+    roc_collections::VecMap,
+    roc_load::{ExpectMetadata, Expectations},
+    roc_module::symbol::{Interns, ModuleId},
+    roc_mono::layout::GlobalLayoutInterner,
+    roc_repl_expect::run::{ChildProcessMsg, ExpectMemory},
+};
+
+/// How often we check the watched files for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Editors and tools like `git checkout` often write several files (or the same file several
+/// times) in quick succession. Once something changes, we wait until nothing has changed for
+/// this long before starting over, so that a burst of saves only causes one run.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How long to sleep in between checking on a running program (and its `expect`s and `dbg`s).
+/// A program waits for us to render each `expect` failure or `dbg`, so this is kept short.
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub(crate) fn check(
+    roc_file_path: &Path,
+    emit_timings: bool,
+    roc_cache_dir: RocCacheDir<'_>,
+    threading: Threading,
+    render: RenderTarget,
+) -> io::Result<i32> {
+    let mut watched = vec![roc_file_path.to_path_buf()];
+
+    loop {
+        let started = SystemTime::now();
+
+        {
+            let arena = Bump::new();
+            let (_, source_paths) = crate::check_and_report(
+                &arena,
+                roc_file_path,
+                emit_timings,
+                roc_cache_dir,
+                threading,
+                render,
+            );
+
+            update_watched(&mut watched, roc_file_path, source_paths);
+        }
+
+        print_watching(&watched);
+
+        let changed = wait_for_changes(&watched, started, thread::sleep);
+
+        print_changed(&changed, "Checking");
+    }
+}
+
+pub(crate) fn dev<F>(roc_file_path: &Path, args: &[OsString], mut build: F) -> io::Result<i32>
+where
+    F: for<'a> FnMut(&'a Bump) -> Result<BuiltFile<'a>, BuildFileError<'a>>,
+{
+    #[cfg(not(windows))]
+    let mut memory = {
+        // The program reports `expect` failures and `dbg`s through this shared memory, which it
+        // finds using our process id (since we are its parent).
+        let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
+
+        ExpectMemory::create_or_reuse_mmap(&shm_name)
+    };
+
+    let mut watched = vec![roc_file_path.to_path_buf()];
+
+    loop {
+        let started = SystemTime::now();
+        let arena = Bump::new();
+
+        let changed = match build(&arena) {
+            Ok(BuiltFile {
+                binary_path,
+                problems,
+                total_time,
+                expect_metadata,
+                source_paths,
+            }) => {
+                update_watched(&mut watched, roc_file_path, Some(source_paths));
+
+                if problems.fatally_errored {
+                    problems.print_to_stdout(total_time);
+                    println!(".\n\nCannot run program due to fatal error…");
+                    print_watching(&watched);
+
+                    wait_for_changes(&watched, started, thread::sleep)
+                } else {
+                    if problems.warnings > 0 {
+                        problems.print_to_stdout(total_time);
+                        println!(
+                            ".\n\nRunning program…\n\n\x1B[36m{}\x1B[39m",
+                            "─".repeat(80)
+                        );
+                    }
+
+                    #[cfg(not(windows))]
+                    memory.reset();
+
+                    let mut child = Command::new(&binary_path).args(args).spawn()?;
+                    let mut exited = false;
+
+                    #[cfg(not(windows))]
+                    let ExpectMetadata {
+                        mut expectations,
+                        interns,
+                        layout_interner,
+                    } = expect_metadata;
+
+                    #[cfg(not(windows))]
+                    let layout_interner = layout_interner.into_global();
+
+                    #[cfg(windows)]
+                    let _ = expect_metadata;
+
+                    let changed = wait_for_changes(&watched, started, |duration| {
+                        let deadline = Instant::now() + duration;
+
+                        while Instant::now() < deadline {
+                            #[cfg(not(windows))]
+                            render_messages_from_child(
+                                &arena,
+                                &mut expectations,
+                                &interns,
+                                &layout_interner,
+                                &mut memory,
+                            );
+
+                            if !exited {
+                                if let Ok(Some(status)) = child.try_wait() {
+                                    exited = true;
+
+                                    print_exited(status);
+                                    print_watching(&watched);
+                                }
+                            }
+
+                            thread::sleep(CHILD_POLL_INTERVAL);
+                        }
+                    });
+
+                    if !exited {
+                        // The program may have already exited since we last checked,
+                        // in which case there is nothing left to kill.
+                        let _ = child.kill();
+                        let _ = child.wait();
+                    }
+
+                    changed
+                }
+            }
+            Err(BuildFileError::ErrorModule { module, total_time }) => {
+                let source_paths = module_source_paths(module.sources.values());
+                update_watched(&mut watched, roc_file_path, Some(source_paths));
+
+                handle_error_module(
+                    module,
+                    total_time,
+                    roc_file_path.as_os_str(),
+                    false,
+                    RenderTarget::ColorTerminal,
+                )?;
+                println!();
+                print_watching(&watched);
+
+                wait_for_changes(&watched, started, thread::sleep)
+            }
+            Err(BuildFileError::LoadingProblem(problem)) => {
+                handle_loading_problem(problem, RenderTarget::ColorTerminal)?;
+                print_watching(&watched);
+
+                wait_for_changes(&watched, started, thread::sleep)
+            }
+            Err(BuildFileError::Io(err)) => {
+                eprintln!("Failed to build {}: {err}", roc_file_path.display());
+                print_watching(&watched);
+
+                wait_for_changes(&watched, started, thread::sleep)
+            }
+        };
+
+        print_changed(&changed, "Rebuilding");
+    }
+}
+
+#[cfg(not(windows))]
+fn render_messages_from_child<'a>(
+    arena: &'a Bump,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    memory: &mut ExpectMemory,
+) {
+    let mut writer = std::io::stdout();
+
+    match memory.try_recv_from_child() {
+        None | Some(ChildProcessMsg::Terminate) => {}
+        Some(ChildProcessMsg::Expect) => {
+            roc_repl_expect::run::render_expects_in_memory(
+                &mut writer,
+                arena,
+                expectations,
+                interns,
+                layout_interner,
+                memory,
+            )
+            .unwrap();
+
+            memory.reset();
+        }
+        Some(ChildProcessMsg::Dbg) => {
+            roc_repl_expect::run::render_dbgs_in_memory(
+                &mut writer,
+                arena,
+                expectations,
+                interns,
+                layout_interner,
+                memory,
+            )
+            .unwrap();
+
+            memory.reset();
+        }
+    }
+}
+
+/// Replaces the watched files with the ones that went into the latest run. When loading failed
+/// before the module graph was known (for example because of a syntax error in a header), we
+/// keep watching what we watched before, so that fixing the problem triggers another run.
+fn update_watched(
+    watched: &mut Vec<PathBuf>,
+    roc_file_path: &Path,
+    source_paths: Option<Vec<PathBuf>>,
+) {
+    if let Some(source_paths) = source_paths {
+        *watched = source_paths;
+    }
+
+    if !watched.iter().any(|path| path == roc_file_path) {
+        watched.push(roc_file_path.to_path_buf());
+    }
+}
+
+/// The modification time and size of a file, or `None` if it couldn't be read
+/// (most likely because it was deleted).
+type FileStamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn stamps(paths: &[PathBuf]) -> Vec<FileStamp> {
+    paths.iter().map(|path| stamp(path)).collect()
+}
+
+/// Blocks until at least one of the given files has changed since `since` (which should be when
+/// the previous run started, so that changes made during a run are not missed), and then until
+/// there have been no further changes for [`DEBOUNCE`]. Returns the files that changed.
+///
+/// `idle` is called in between polls, and must return once the given amount of time has passed.
+fn wait_for_changes(
+    paths: &[PathBuf],
+    since: SystemTime,
+    mut idle: impl FnMut(Duration),
+) -> Vec<PathBuf> {
+    let initial = stamps(paths);
+    let modified_since =
+        |stamp: &FileStamp| matches!(stamp, Some((modified, _)) if *modified > since);
+
+    let mut latest = if initial.iter().any(modified_since) {
+        initial.clone()
+    } else {
+        loop {
+            idle(POLL_INTERVAL);
+
+            let current = stamps(paths);
+
+            if current != initial {
+                break current;
+            }
+        }
+    };
+
+    loop {
+        idle(DEBOUNCE);
+
+        let current = stamps(paths);
+
+        if current == latest {
+            break;
+        }
+
+        latest = current;
+    }
+
+    paths
+        .iter()
+        .zip(initial.iter().zip(latest.iter()))
+        .filter(|(_, (before, after))| before != after || modified_since(after))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+fn print_watching(watched: &[PathBuf]) {
+    println!(
+        "\n\x1B[36mWatching {} {} for changes…\x1B[39m",
+        watched.len(),
+        if watched.len() == 1 { "file" } else { "files" }
+    );
+}
+
+fn print_changed(changed: &[PathBuf], action: &str) {
+    match changed {
+        [path] => println!("\n\x1B[36m{} changed. {action}…\x1B[39m\n", path.display()),
+        _ => println!(
+            "\n\x1B[36m{} files changed. {action}…\x1B[39m\n",
+            changed.len()
+        ),
+    }
+}
+
+fn print_exited(status: ExitStatus) {
+    match status.code() {
+        Some(code) => println!("\n\x1B[36mThe program exited with code {code}.\x1B[39m"),
+        None => println!("\n\x1B[36mThe program was terminated by a signal.\x1B[39m"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_files_changed_during_the_previous_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let unchanged = dir.path().join("Unchanged.roc");
+        let changed = dir.path().join("Changed.roc");
+
+        std::fs::write(&unchanged, "a").unwrap();
+        let since = SystemTime::now() + Duration::from_millis(1);
+        thread::sleep(Duration::from_millis(20));
+        std::fs::write(&changed, "b").unwrap();
+
+        let paths = vec![unchanged, changed.clone()];
+
+        assert_eq!(
+            wait_for_changes(&paths, since, thread::sleep),
+            vec![changed]
+        );
+    }
+
+    #[test]
+    fn waits_for_a_change_and_debounces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.roc");
+
+        std::fs::write(&path, "a").unwrap();
+        let since = SystemTime::now() + Duration::from_secs(60);
+
+        let mut polls = 0;
+        let changed = wait_for_changes(&[path.clone()], since, |duration| {
+            polls += 1;
+
+            // Simulate a burst of saves: a few writes, each within the debounce window.
+            if (2..5).contains(&polls) {
+                std::fs::write(&path, "a".repeat(polls)).unwrap();
+            }
+
+            thread::sleep(duration);
+        });
+
+        assert_eq!(changed, vec![path.clone()]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "aaaa");
+        assert!(polls >= 5);
+    }
+
+    #[test]
+    fn deleting_a_file_counts_as_a_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.roc");
+
+        std::fs::write(&path, "a").unwrap();
+        let since = SystemTime::now() + Duration::from_secs(60);
+
+        let mut removed = false;
+        let changed = wait_for_changes(&[path.clone()], since, |duration| {
+            if !removed {
+                std::fs::remove_file(&path).unwrap();
+                removed = true;
+            }
+
+            thread::sleep(duration);
+        });
+
+        assert_eq!(changed, vec![path]);
+    }
+}
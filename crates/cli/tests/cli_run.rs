@@ -50,8 +50,18 @@ mod cli_run {
     }
 
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
+    const DEV_FLAG: &str = concatcp!("--", roc_cli::FLAG_DEV);
+    const LIB_FLAG: &str = concatcp!("--", roc_cli::FLAG_LIB);
+    const STATIC_FLAG: &str = concatcp!("--", roc_cli::FLAG_STATIC);
+    const EXPORT_SYMBOLS_FLAG: &str = concatcp!("--", roc_cli::FLAG_EXPORT_SYMBOLS);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
+    const LIST_FLAG: &str = concatcp!("--", roc_cli::FLAG_LIST);
+    const FILTER_FLAG: &str = concatcp!("--", roc_cli::FLAG_FILTER);
+    const COVERAGE_FLAG: &str = concatcp!("--", roc_cli::FLAG_COVERAGE);
+    const LCOV_FLAG: &str = concatcp!("--", roc_cli::FLAG_LCOV);
+    const DOC_FLAG: &str = concatcp!("--", roc_cli::FLAG_DOC);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const STDIN_FLAG: &str = concatcp!("--", roc_cli::FLAG_STDIN);
     const PREBUILT_PLATFORM: &str = concatcp!("--", roc_cli::FLAG_PREBUILT);
     #[allow(dead_code)]
     const TARGET_FLAG: &str = concatcp!("--", roc_cli::FLAG_TARGET);
@@ -631,6 +641,260 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn expects_dev_backend() {
+        // `roc dev --dev` uses the dev backend, which must report dbg and expect like LLVM does.
+        // Not via test_roc_app, because the dev backend skips them when optimizing.
+        check_output_with_stdin(
+            &file_path_from_root("crates/cli_testing_examples/expects", "expects.roc"),
+            &[],
+            "expects-test",
+            &[DEV_FLAG],
+            &[],
+            &[],
+            indoc!(
+                r#"
+                This expectation failed:
+
+                18│      expect x != x
+                                ^^^^^^
+
+                When it failed, these variables had these values:
+
+                x : Num *
+                x = 42
+
+                [<ignored for tests> 19:9] 42
+                [<ignored for tests> 20:9] "Fjoer en ferdjer frieten oan dyn geve lea"
+                [<ignored for tests> 13:9] "abc"
+                [<ignored for tests> 13:9] 10
+                [<ignored for tests> 13:9] A (B C)
+                Program finished!
+                "#
+            ),
+            UseValgrind::No,
+            TestCliCommands::Dev,
+        );
+    }
+
+    /// The global symbols each member of a static library defines
+    fn static_lib_definitions(archive_path: &Path) -> Vec<Vec<String>> {
+        use object::{Object, ObjectSymbol};
+
+        let bytes = std::fs::read(archive_path).unwrap();
+        let archive = object::read::archive::ArchiveFile::parse(bytes.as_slice()).unwrap();
+
+        archive
+            .members()
+            .map(|member| {
+                let data = member.unwrap().data(bytes.as_slice()).unwrap();
+                let object = object::File::parse(data).unwrap();
+
+                object
+                    .symbols()
+                    .filter(|sym| sym.is_global() && !sym.is_undefined())
+                    .map(|sym| sym.name().unwrap().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    fn static_lib() {
+        // The dev backend keeps the builtins in their own object, so the archive gets both.
+        let path = file_path_from_root("crates/cli_testing_examples/algorithms", "fibonacci.roc");
+        let archive_path = path.with_file_name("fibonacci.a");
+        let symbols_path = path.with_file_name("fibonacci.symbols");
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                LIB_FLAG,
+                STATIC_FLAG,
+                DEV_FLAG,
+                path.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+        assert!(out.status.success(), "{}", out.stderr);
+
+        let symbols = std::fs::read_to_string(&symbols_path).unwrap();
+        let exported = |name: &String| symbols.lines().any(|line| line == name);
+        assert!(exported(&"roc__mainForHost_1_exposed".to_string()));
+
+        let members = static_lib_definitions(&archive_path);
+        assert_eq!(members.len(), 2);
+        assert!(members.iter().any(|defs| defs.iter().any(exported)));
+        assert!(members
+            .iter()
+            .any(|defs| defs.iter().any(|name| name.starts_with("roc_builtins."))));
+
+        // Restricting the exports merges the members, and only the listed symbols stay global.
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                LIB_FLAG,
+                STATIC_FLAG,
+                DEV_FLAG,
+                EXPORT_SYMBOLS_FLAG,
+                symbols_path.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+        assert!(out.status.success(), "{}", out.stderr);
+
+        let members = static_lib_definitions(&archive_path);
+        assert_eq!(members.len(), 1);
+        assert!(members[0].contains(&"roc__mainForHost_1_exposed".to_string()));
+        assert!(members[0].iter().all(exported), "{:?}", members[0]);
+
+        // A name which the library does not define is an error, rather than an empty export list.
+        std::fs::write(&symbols_path, "roc__mainForHost_1_exposd\n").unwrap();
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                LIB_FLAG,
+                STATIC_FLAG,
+                DEV_FLAG,
+                EXPORT_SYMBOLS_FLAG,
+                symbols_path.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+        assert!(!out.status.success());
+        assert!(
+            out.stderr.contains("roc__mainForHost_1_exposd"),
+            "{}",
+            out.stderr
+        );
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_list_and_filter() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "Labelled.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, LIST_FLAG, path], &[], &[]);
+        assert!(out.status.success());
+        assert_multiline_str_eq!(
+            out.stdout.as_str(),
+            indoc!(
+                r#"
+                Labelled:8 doubles zero
+                Labelled:11 doubles a positive number
+                Labelled:13
+                "#
+            )
+        );
+
+        let out = run_roc(
+            [CMD_TEST, LIST_FLAG, FILTER_FLAG, "positive", path],
+            &[],
+            &[],
+        );
+        assert!(out.status.success());
+        assert_multiline_str_eq!(
+            out.stdout.as_str(),
+            "Labelled:11 doubles a positive number\n"
+        );
+
+        let out = run_roc([CMD_TEST, FILTER_FLAG, "positive", path], &[], &[]);
+        assert!(out.status.success());
+        assert!(strip_colors(&out.stdout).contains("0 failed and 1 passed"));
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_coverage() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "Coverage.roc");
+        let tmp = roc_test_utils::TmpDir::new("tmp/test_coverage");
+        let lcov_path = tmp.path().join("Coverage.lcov");
+
+        let out = run_roc(
+            [
+                CMD_TEST,
+                COVERAGE_FLAG,
+                LCOV_FLAG,
+                lcov_path.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+        assert!(out.status.success(), "{stdout}{}", out.stderr);
+        assert!(stdout.contains("0 failed and 2 passed"), "{stdout}");
+
+        // Both expects call `classify` and take its first branch; nothing calls `unused`.
+        let lcov = std::fs::read_to_string(&lcov_path).unwrap();
+        let lcov_lines: Vec<&str> = lcov.lines().collect();
+
+        for expected in [
+            "FNDA:2,classify",
+            "FNDA:0,unused",
+            "FNF:2",
+            "FNH:1",
+            "BRF:2",
+            "BRH:1",
+        ] {
+            assert!(
+                lcov_lines.contains(&expected),
+                "missing {expected} in:\n{lcov}"
+            );
+        }
+
+        assert!(stdout.contains("These defs never ran:"), "{stdout}");
+        assert!(stdout.contains("Coverage.roc:10 unused"), "{stdout}");
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_doc_expects() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "DocTests.roc");
+        let path = path.to_str().unwrap();
+
+        // Without --doc, the doc comments' expects don't run
+        let out = run_roc([CMD_TEST, path], &[], &[]);
+        assert!(out.stdout.contains("No expectations were found."));
+
+        let out = run_roc([CMD_TEST, DOC_FLAG, LIST_FLAG, path], &[], &[]);
+        assert!(out.status.success());
+        assert_multiline_str_eq!(
+            out.stdout.as_str(),
+            indoc!(
+                r#"
+                DocTests:4
+                DocTests:14 doubles two
+                DocTests:17 doubles three
+                DocTests:29 doubles a def from the example
+                DocTests:31
+                "#
+            )
+        );
+
+        let out = run_roc([CMD_TEST, DOC_FLAG, path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert!(!out.status.success());
+        assert!(stdout.contains("16│>  ## # doubles three"), "{stdout}");
+        assert!(stdout.contains("17│>  ## expect double 3 == 7"), "{stdout}");
+        assert!(stdout.contains("1 failed and 4 passed"), "{stdout}");
+    }
+
     #[test]
     #[cfg_attr(
         windows,
@@ -1499,6 +1763,85 @@ mod cli_run {
         // This doesn't fail, since only "Formatted.roc" and non-roc files are present in this folder
         check_format_check_as_expected(&fixtures_dir("format/formatted_directory"), true);
     }
+
+    #[test]
+    fn format_check_prints_diff() {
+        let file = fixture_file("format", "NotFormatted.roc");
+        let out = run_roc([CMD_FORMAT, file.to_str().unwrap(), CHECK_FLAG], &[], &[]);
+
+        assert!(!out.status.success());
+        assert!(out
+            .stdout
+            .contains("-  provides [main] to pf\n+    provides [main] to pf\n"));
+    }
+
+    #[test]
+    fn format_skips_files_that_do_not_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_formatted = dir.path().join("NotFormatted.roc");
+        let broken = dir.path().join("Broken.roc");
+        let broken_src = "interface Broken exposes [] imports []\n\nx =\n";
+
+        std::fs::copy(fixture_file("format", "NotFormatted.roc"), &not_formatted).unwrap();
+        std::fs::write(&broken, broken_src).unwrap();
+
+        let out = run_roc([CMD_FORMAT, dir.path().to_str().unwrap()], &[], &[]);
+
+        assert!(!out.status.success());
+        assert!(out.stderr.contains("Broken.roc"));
+        assert!(out.stderr.contains(
+            "Reformatted 1 file, 0 files already formatted, and skipped 1 file which could not be parsed."
+        ));
+
+        // The file which does parse still got formatted, and the one which doesn't was left alone
+        assert!(std::fs::read_to_string(&not_formatted)
+            .unwrap()
+            .contains("\n    provides [main] to pf\n"));
+        assert_eq!(std::fs::read_to_string(&broken).unwrap(), broken_src);
+    }
+
+    #[test]
+    fn format_skips_files_that_cannot_be_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_formatted = dir.path().join("NotFormatted.roc");
+        let not_utf8 = dir.path().join("NotUtf8.roc");
+
+        std::fs::copy(fixture_file("format", "NotFormatted.roc"), &not_formatted).unwrap();
+        std::fs::write(&not_utf8, [0xff, 0xfe, 0xfd]).unwrap();
+
+        let out = run_roc([CMD_FORMAT, dir.path().to_str().unwrap()], &[], &[]);
+
+        assert!(!out.status.success());
+        assert!(out.stderr.contains("Unable to read"));
+        assert!(out.stderr.contains("NotUtf8.roc"));
+        assert!(out.stderr.contains(
+            "Reformatted 1 file, 0 files already formatted, and skipped 1 file which could not be read or written."
+        ));
+
+        // The other file still got formatted
+        assert!(std::fs::read_to_string(&not_formatted)
+            .unwrap()
+            .contains("\n    provides [main] to pf\n"));
+    }
+
+    #[test]
+    fn format_stdin() {
+        let src = std::fs::read_to_string(fixture_file("format", "NotFormatted.roc")).unwrap();
+        let out = run_roc([CMD_FORMAT, STDIN_FLAG], &[&src], &[]);
+
+        assert!(out.status.success());
+        assert!(out.stdout.contains("\n    provides [main] to pf\n"));
+
+        let out = run_roc(
+            [CMD_FORMAT, STDIN_FLAG],
+            &["interface Broken exposes [] imports []\n\nx =\n"],
+            &[],
+        );
+
+        assert!(!out.status.success());
+        assert!(out.stdout.is_empty());
+        assert!(out.stderr.contains("stdin.roc"));
+    }
 }
 
 #[cfg(feature = "wasm32-cli-run")]
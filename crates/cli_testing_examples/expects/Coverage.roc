@@ -0,0 +1,14 @@
+interface Coverage
+    exposes [classify, unused]
+    imports []
+
+classify = \n ->
+    when n is
+        0 -> "zero"
+        _ -> "other"
+
+unused = \n -> n + 1
+
+expect classify 0 == "zero"
+
+expect classify 0 != "other"
@@ -0,0 +1,34 @@
+## Doubling numbers, like this:
+##
+## ```roc
+## expect double 1 == 2
+## ```
+interface DocTests
+    exposes [double]
+    imports []
+
+## Doubles a number.
+##
+## ```roc
+## # doubles two
+## expect double 2 == 4
+##
+## # doubles three
+## expect double 3 == 7
+## ```
+##
+## ```roc,unchecked
+## expect double 4 == 9
+## ```
+##
+## ```roc
+## four = double 2
+## eight = double four
+##
+## # doubles a def from the example
+## expect eight == 8
+##
+## expect four == 4
+## ```
+double : I64 -> I64
+double = \n -> n * 2
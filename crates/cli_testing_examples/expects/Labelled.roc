@@ -0,0 +1,13 @@
+interface Labelled
+    exposes [double]
+    imports []
+
+double = \x -> x * 2
+
+# doubles zero
+expect double 0 == 0
+
+# doubles a positive number
+expect double 2 == 4
+
+expect double 3 == 6
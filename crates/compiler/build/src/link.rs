@@ -31,6 +31,10 @@ pub fn link(
     input_paths: &[&str],
     link_type: LinkType,
 ) -> io::Result<(Child, PathBuf)> {
+    if let LinkType::StaticLib = link_type {
+        return link_static_lib(target, output_path, input_paths);
+    }
+
     match target {
         Triple {
             architecture: Architecture::Wasm32,
@@ -427,6 +431,52 @@ pub fn build_swift_host_native(
     command
 }
 
+/// Extensions of the files [`rebuild_host`] may compile a host from.
+const HOST_SOURCE_EXTENSIONS: [&str; 5] = ["c", "h", "rs", "swift", "zig"];
+
+/// The host source files that [`rebuild_host`] would build from, for example so that they can be
+/// watched for changes. Hosts are often split across several files next to the platform's
+/// main.roc, so this includes every source file in that directory (and in `src/`, for Cargo hosts).
+pub fn host_source_paths(platform_main_roc: &Path) -> Vec<PathBuf> {
+    fn collect_sources(dir: &Path, recursive: bool, paths: &mut Vec<PathBuf>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                if recursive {
+                    collect_sources(&path, recursive, paths);
+                }
+            } else if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| HOST_SOURCE_EXTENSIONS.contains(&ext))
+            {
+                paths.push(path);
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+
+    if let Some(platform_dir) = platform_main_roc.parent() {
+        collect_sources(platform_dir, false, &mut paths);
+
+        let cargo_host_src = platform_dir.join("Cargo.toml");
+
+        if cargo_host_src.exists() {
+            collect_sources(&platform_dir.join("src"), true, &mut paths);
+            paths.push(cargo_host_src);
+        }
+    }
+
+    paths.sort();
+
+    paths
+}
+
 pub fn rebuild_host(
     opt_level: OptLevel,
     target: &Triple,
@@ -985,6 +1035,9 @@ fn link_linux(
             )
         }
         LinkType::None => internal_error!("link_linux should not be called with link type of none"),
+        LinkType::StaticLib => {
+            internal_error!("link_linux should not be called with link type of static lib")
+        }
     };
 
     let env_path = env::var("PATH").unwrap_or_else(|_| "".to_string());
@@ -1055,6 +1108,9 @@ fn link_macos(
             (vec!["-dylib", "-undefined", "dynamic_lookup"], output_path)
         }
         LinkType::None => internal_error!("link_macos should not be called with link type of none"),
+        LinkType::StaticLib => {
+            internal_error!("link_macos should not be called with link type of static lib")
+        }
     };
 
     let arch = match target.architecture {
@@ -1174,6 +1230,68 @@ fn get_macos_version() -> String {
         .join(".")
 }
 
+/// Bundle object files into a static library (a `.a`, or a `.lib` on Windows),
+/// so that Roc code can be linked into a host by an existing build system.
+fn link_static_lib(
+    target: &Triple,
+    output_path: PathBuf,
+    input_paths: &[&str],
+) -> io::Result<(Child, PathBuf)> {
+    let operating_system = roc_target::OperatingSystem::from(target.operating_system);
+
+    let mut output_path = output_path;
+    output_path.set_extension(operating_system.static_library_file_ext());
+
+    // The linkers on each platform expect a different flavor of archive
+    let format = match target.operating_system {
+        OperatingSystem::Darwin | OperatingSystem::MacOSX { .. } => "darwin",
+        OperatingSystem::Windows => "coff",
+        _ => "gnu",
+    };
+
+    // `ar` adds to an existing archive, so members of a previous build could otherwise linger
+    if output_path.exists() {
+        std::fs::remove_file(&output_path)?;
+    }
+
+    let child = zig()
+        .args(["ar", &format!("--format={format}"), "rcs"])
+        .arg(&output_path)
+        .args(input_paths)
+        .spawn()?;
+
+    Ok((child, output_path))
+}
+
+/// Merge the objects that go into a static library into one, in which only the `exported`
+/// symbols stay global. Merging first keeps the references between the objects intact.
+pub fn merge_objects_with_exports(
+    output_path: &Path,
+    input_paths: &[&str],
+    exported: &[String],
+) -> io::Result<()> {
+    let output = zig()
+        .args(["ld.lld", "-r", "-o"])
+        .arg(output_path)
+        .args(input_paths)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "`zig ld.lld -r` failed to merge the static library's objects:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+
+    let mut bytes = fs::read(output_path)?;
+    roc_linker::localize_symbols(&mut bytes, exported);
+
+    fs::write(output_path, bytes)
+}
+
 fn link_wasm32(
     _target: &Triple,
     output_path: PathBuf,
@@ -1254,6 +1372,9 @@ fn link_windows(
             Ok((child, output_path))
         }
         LinkType::None => todo!(),
+        LinkType::StaticLib => {
+            internal_error!("link_windows should not be called with link type of static lib")
+        }
     }
 }
 
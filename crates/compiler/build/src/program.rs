@@ -1,5 +1,6 @@
 use crate::link::{
-    legacy_host_filename, link, preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy,
+    host_source_paths, legacy_host_filename, link, merge_objects_with_exports,
+    preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy,
 };
 use bumpalo::Bump;
 use inkwell::memory_buffer::MemoryBuffer;
@@ -39,23 +40,28 @@ pub struct CodeGenTiming {
     pub total: Duration,
 }
 
-pub fn report_problems_monomorphized(loaded: &mut MonomorphizedModule) -> Problems {
+pub fn report_problems_monomorphized(
+    loaded: &mut MonomorphizedModule,
+    render: RenderTarget,
+) -> Problems {
     report_problems(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        render,
     )
 }
 
-pub fn report_problems_typechecked(loaded: &mut LoadedModule) -> Problems {
+pub fn report_problems_typechecked(loaded: &mut LoadedModule, render: RenderTarget) -> Problems {
     report_problems(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        render,
     )
 }
 
@@ -100,6 +106,7 @@ pub fn gen_from_mono_module<'a>(
     code_gen_options: CodeGenOptions,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
+    linking_strategy: LinkingStrategy,
 ) -> GenFromMono<'a> {
     let path = roc_file_path;
     let debug = code_gen_options.emit_debug_info;
@@ -122,15 +129,23 @@ pub fn gen_from_mono_module<'a>(
             wasm_dev_stack_bytes,
             backend_mode,
         ),
-        CodeGenBackend::Llvm(backend_mode) => {
-            gen_from_mono_module_llvm(arena, loaded, path, target, opt, backend_mode, debug)
-        }
+        CodeGenBackend::Llvm(backend_mode) => gen_from_mono_module_llvm(
+            arena,
+            loaded,
+            path,
+            target,
+            opt,
+            backend_mode,
+            debug,
+            linking_strategy,
+        ),
     }
 }
 
 // TODO how should imported modules factor into this? What if those use builtins too?
 // TODO this should probably use more helper functions
 // TODO make this polymorphic in the llvm functions so it can be reused for another backend.
+#[allow(clippy::too_many_arguments)]
 fn gen_from_mono_module_llvm<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
@@ -139,6 +154,7 @@ fn gen_from_mono_module_llvm<'a>(
     opt_level: OptLevel,
     backend_mode: LlvmBackendMode,
     emit_debug_info: bool,
+    linking_strategy: LinkingStrategy,
 ) -> GenFromMono<'a> {
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
@@ -433,6 +449,16 @@ fn gen_from_mono_module_llvm<'a>(
                     .write_to_memory_buffer(env.module, FileType::Object)
                     .expect("Writing .o file failed")
             }
+            Architecture::Wasm32 if linking_strategy == LinkingStrategy::Surgical => {
+                // The surgical linker needs a relocatable object, not bitcode
+                let reloc = RelocMode::Static;
+                let target_machine =
+                    target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
+
+                target_machine
+                    .write_to_memory_buffer(env.module, FileType::Object)
+                    .expect("Writing .o file failed")
+            }
             Architecture::Wasm32 => {
                 // Useful for debugging
                 // module.print_to_file(app_ll_file);
@@ -530,11 +556,25 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         .copied()
         .collect::<MutSet<_>>();
 
+    let mut source_positions = roc_gen_wasm::SourcePositions::default();
+    for (module_id, (path, src)) in loaded.sources.iter() {
+        source_positions.insert_module(*module_id, path, src);
+    }
+    for (module_id, expectations) in loaded.expectations.iter() {
+        for (symbol, dbg) in expectations.dbgs.iter() {
+            source_positions.insert_dbg(*symbol, dbg.region);
+        }
+        if !expectations.dbgs.is_empty() {
+            source_positions.insert_types(*module_id, expectations.subs.clone());
+        }
+    }
+
     let env = roc_gen_wasm::Env {
         arena,
         module_id,
         exposed_to_host,
         stack_bytes: wasm_dev_stack_bytes.unwrap_or(roc_gen_wasm::Env::DEFAULT_STACK_BYTES),
+        source_positions,
     };
 
     let host_bytes = std::fs::read(preprocessed_host_path).unwrap_or_else(|_| {
@@ -653,6 +693,28 @@ pub struct BuiltFile<'a> {
     pub problems: Problems,
     pub total_time: Duration,
     pub expect_metadata: ExpectMetadata<'a>,
+    /// Every file this build was made from: the .roc file of each module, plus the platform's
+    /// host source if the host was rebuilt.
+    pub source_paths: Vec<PathBuf>,
+}
+
+pub struct CheckedFile {
+    pub problems: Problems,
+    pub total_time: Duration,
+    /// The .roc file of each module that was checked.
+    pub source_paths: Vec<PathBuf>,
+}
+
+/// The .roc files of all the given modules' sources, in a stable order.
+pub fn module_source_paths<'s>(
+    sources: impl IntoIterator<Item = &'s (PathBuf, Box<str>)>,
+) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = sources.into_iter().map(|(path, _)| path.clone()).collect();
+
+    paths.sort();
+    paths.dedup();
+
+    paths
 }
 
 pub enum BuildOrdering {
@@ -670,6 +732,9 @@ pub enum BuildFileError<'a> {
         module: LoadedModule,
         total_time: Duration,
     },
+    /// Writing or linking the output failed, e.g. because a temporary file could not be created
+    /// or the linker exited with an error.
+    Io(std::io::Error),
 }
 
 impl<'a> BuildFileError<'a> {
@@ -691,10 +756,16 @@ pub fn handle_error_module(
     total_time: std::time::Duration,
     filename: &OsStr,
     print_run_anyway_hint: bool,
+    render: RenderTarget,
 ) -> std::io::Result<i32> {
     debug_assert!(module.total_problems() > 0);
 
-    let problems = report_problems_typechecked(&mut module);
+    let problems = report_problems_typechecked(&mut module, render);
+
+    if let RenderTarget::Json = render {
+        // Tools reading JSON expect nothing but the reports themselves.
+        return Ok(problems.exit_code());
+    }
 
     problems.print_to_stdout(total_time);
 
@@ -713,10 +784,18 @@ pub fn handle_error_module(
     Ok(problems.exit_code())
 }
 
-pub fn handle_loading_problem(problem: LoadingProblem) -> std::io::Result<i32> {
+pub fn handle_loading_problem(
+    problem: LoadingProblem,
+    render: RenderTarget,
+) -> std::io::Result<i32> {
     match problem {
         LoadingProblem::FormattedReport(report) => {
-            print!("{report}");
+            match render {
+                // JSON reports are printed one per line.
+                RenderTarget::Json => println!("{report}"),
+                _ => print!("{report}"),
+            }
+
             Ok(1)
         }
         _ => {
@@ -772,11 +851,14 @@ pub fn build_file<'a>(
     linking_strategy: LinkingStrategy,
     prebuilt_requested: bool,
     wasm_dev_stack_bytes: Option<u32>,
+    static_lib_exports: Option<&[String]>,
     roc_cache_dir: RocCacheDir<'_>,
     load_config: LoadConfig,
 ) -> Result<BuiltFile<'a>, BuildFileError<'a>> {
     let compilation_start = Instant::now();
 
+    let render = load_config.render;
+
     // Step 1: compile the app and generate the .o file
     let loaded =
         roc_load::load_and_monomorphize(arena, app_module_path.clone(), roc_cache_dir, load_config)
@@ -792,8 +874,10 @@ pub fn build_file<'a>(
         linking_strategy,
         prebuilt_requested,
         wasm_dev_stack_bytes,
+        static_lib_exports,
         loaded,
         compilation_start,
+        render,
     )
 }
 
@@ -808,8 +892,10 @@ fn build_loaded_file<'a>(
     linking_strategy: LinkingStrategy,
     prebuilt_requested: bool,
     wasm_dev_stack_bytes: Option<u32>,
+    static_lib_exports: Option<&[String]>,
     loaded: roc_load::MonomorphizedModule<'a>,
     compilation_start: Instant,
+    render: RenderTarget,
 ) -> Result<BuiltFile<'a>, BuildFileError<'a>> {
     let operating_system = roc_target::OperatingSystem::from(target.operating_system);
 
@@ -843,7 +929,10 @@ fn build_loaded_file<'a>(
     }
 
     // We don't need to spawn a rebuild thread when using a prebuilt host.
-    let rebuild_thread = if matches!(link_type, LinkType::Dylib | LinkType::None) {
+    let rebuild_thread = if matches!(
+        link_type,
+        LinkType::Dylib | LinkType::None | LinkType::StaticLib
+    ) {
         None
     } else if is_platform_prebuilt {
         if !preprocessed_host_path.exists() {
@@ -883,6 +972,12 @@ fn build_loaded_file<'a>(
         Some(join_handle)
     };
 
+    let mut source_paths = module_source_paths(loaded.sources.values());
+
+    if rebuild_thread.is_some() {
+        source_paths.extend(host_source_paths(&platform_main_roc));
+    }
+
     let buf = &mut String::with_capacity(1024);
 
     let mut it = loaded.timings.iter().peekable();
@@ -908,10 +1003,18 @@ fn build_loaded_file<'a>(
         }
     }
 
+    // A static library has no host to tell which symbols the app provides,
+    // so we list them next to the archive for whoever links it.
+    let static_lib_symbols = if let LinkType::StaticLib = link_type {
+        roc_linker::ExposedSymbols::from_exposed_to_host(&loaded.interns, &loaded.exposed_to_host)
+    } else {
+        Vec::new()
+    };
+
     // This only needs to be mutable for report_problems. This can't be done
     // inside a nested scope without causing a borrow error!
     let mut loaded = loaded;
-    let problems = report_problems_monomorphized(&mut loaded);
+    let problems = report_problems_monomorphized(&mut loaded, render);
     let loaded = loaded;
 
     enum HostRebuildTiming {
@@ -945,6 +1048,7 @@ fn build_loaded_file<'a>(
         code_gen_options,
         &preprocessed_host_path,
         wasm_dev_stack_bytes,
+        linking_strategy,
     );
 
     buf.push('\n');
@@ -996,6 +1100,9 @@ fn build_loaded_file<'a>(
                 &output_exe_path,
             );
         }
+        (LinkingStrategy::Additive, LinkType::StaticLib) => {
+            internal_error!("the additive linking strategy cannot produce a static library")
+        }
         (LinkingStrategy::Additive, _) | (LinkingStrategy::Legacy, LinkType::None) => {
             // Just copy the object file to the output folder.
             output_exe_path.set_extension(operating_system.object_file_ext());
@@ -1022,19 +1129,48 @@ fn build_loaded_file<'a>(
             let builtins_host_tempfile = roc_bitcode::host_tempfile()
                 .expect("failed to write host builtins object to tempfile");
 
+            let merged_o_file = match (link_type, static_lib_exports) {
+                (LinkType::StaticLib, Some(_)) => Some(
+                    tempfile::Builder::new()
+                        .prefix("roc_lib")
+                        .suffix(&format!(".{extension}"))
+                        .tempfile()
+                        .map_err(BuildFileError::Io)?,
+                ),
+                _ => None,
+            };
+
             let mut inputs = vec![app_o_file.to_str().unwrap()];
 
-            if !matches!(link_type, LinkType::Dylib | LinkType::None) {
+            if !matches!(
+                link_type,
+                LinkType::Dylib | LinkType::None | LinkType::StaticLib
+            ) {
                 // the host has been compiled into a .o or .obj file
                 inputs.push(preprocessed_host_path.as_path().to_str().unwrap());
             }
 
+            // The LLVM backend already links the builtins into the app object
             if matches!(code_gen_options.backend, CodeGenBackend::Assembly(_)) {
                 inputs.push(builtins_host_tempfile.path().to_str().unwrap());
             }
 
-            let (mut child, _) = link(target, output_exe_path.clone(), &inputs, link_type)
-                .map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
+            if let (Some(merged_o_file), Some(exported)) = (&merged_o_file, static_lib_exports) {
+                let merged_path = merged_o_file.path();
+
+                merge_objects_with_exports(merged_path, &inputs, exported)
+                    .map_err(BuildFileError::Io)?;
+
+                inputs = vec![merged_path.to_str().unwrap()];
+            }
+
+            let (mut child, linked_path) =
+                link(target, output_exe_path.clone(), &inputs, link_type)
+                    .map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
+
+            if let LinkType::StaticLib = link_type {
+                output_exe_path = linked_path;
+            }
 
             let exit_status = child
                 .wait()
@@ -1043,6 +1179,7 @@ fn build_loaded_file<'a>(
             // Extend the lifetime of the tempfile so it doesn't get dropped
             // (and thus deleted) before the child process is done using it!
             let _ = builtins_host_tempfile;
+            let _ = merged_o_file;
 
             if !exit_status.success() {
                 todo!(
@@ -1050,6 +1187,14 @@ fn build_loaded_file<'a>(
                     exit_status.code()
                 );
             }
+
+            if let LinkType::StaticLib = link_type {
+                let symbols_path = output_exe_path.with_extension("symbols");
+                let mut contents = static_lib_symbols.join("\n");
+                contents.push('\n');
+
+                std::fs::write(&symbols_path, contents).map_err(BuildFileError::Io)?;
+            }
         }
     }
 
@@ -1066,6 +1211,7 @@ fn build_loaded_file<'a>(
         problems,
         total_time,
         expect_metadata,
+        source_paths,
     })
 }
 
@@ -1183,6 +1329,24 @@ fn build_and_preprocess_host_lowlevel(
     preprocessed_host_path: &Path,
     stub_dll_symbols: &[String],
 ) {
+    if let target_lexicon::Architecture::Wasm32 = target.architecture {
+        // Wasm hosts are relocatable objects, not executables, so there is no stub library.
+        // Instead, combine the host with the builtins into the input the linker expects.
+        let host_dest = rebuild_host(opt_level, target, platform_main_roc, None);
+        let host_input = platform_main_roc.with_file_name("dynhost.wasm");
+        preprocess_host_wasm32(&host_dest, &host_input);
+
+        roc_linker::preprocess_host(
+            target,
+            platform_main_roc,
+            preprocessed_host_path,
+            &host_input,
+            stub_dll_symbols,
+        );
+
+        return;
+    }
+
     let stub_lib =
         roc_linker::generate_stub_lib_from_loaded(target, platform_main_roc, stub_dll_symbols);
 
@@ -1206,7 +1370,8 @@ pub fn check_file<'a>(
     emit_timings: bool,
     roc_cache_dir: RocCacheDir<'_>,
     threading: Threading,
-) -> Result<(Problems, Duration), LoadingProblem<'a>> {
+    render: RenderTarget,
+) -> Result<CheckedFile, LoadingProblem<'a>> {
     let compilation_start = Instant::now();
 
     // only used for generating errors. We don't do code generation, so hardcoding should be fine
@@ -1219,8 +1384,7 @@ pub fn check_file<'a>(
         target_info,
         // TODO: we may not want this for just checking.
         function_kind: FunctionKind::LambdaSet,
-        // TODO: expose this from CLI?
-        render: RenderTarget::ColorTerminal,
+        render,
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
@@ -1270,7 +1434,11 @@ pub fn check_file<'a>(
         println!("Finished checking in {} ms\n", compilation_end.as_millis(),);
     }
 
-    Ok((report_problems_typechecked(&mut loaded), compilation_end))
+    Ok(CheckedFile {
+        problems: report_problems_typechecked(&mut loaded, render),
+        total_time: compilation_end,
+        source_paths: module_source_paths(loaded.sources.values()),
+    })
 }
 
 pub fn build_str_test<'a>(
@@ -1297,6 +1465,7 @@ pub fn build_str_test<'a>(
     let threading = Threading::AtMost(2);
 
     let load_config = standard_load_config(&triple, build_ordering, threading);
+    let render = load_config.render;
 
     let compilation_start = std::time::Instant::now();
 
@@ -1321,7 +1490,9 @@ pub fn build_str_test<'a>(
         linking_strategy,
         assume_prebuild,
         wasm_dev_stack_bytes,
+        None,
         loaded,
         compilation_start,
+        render,
     )
 }
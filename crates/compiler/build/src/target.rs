@@ -132,7 +132,7 @@ pub fn arch_str(target: &Triple) -> &'static str {
         Architecture::X86_32(_) if cfg!(feature = "target-x86") => "x86",
         Architecture::Aarch64(_) if cfg!(feature = "target-aarch64") => "aarch64",
         Architecture::Arm(_) if cfg!(feature = "target-arm") => "arm",
-        Architecture::Wasm32 if cfg!(feature = "target-webassembly") => "wasm32",
+        Architecture::Wasm32 if cfg!(feature = "target-wasm32") => "wasm32",
         _ => internal_error!(
             "TODO gracefully handle unsupported target architecture: {:?}",
             target.architecture
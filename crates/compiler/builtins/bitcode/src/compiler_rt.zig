@@ -28,6 +28,10 @@ comptime {
         @export(__fixsfti_windows_x86_64, .{ .name = "__fixsfti", .linkage = .Weak });
         @export(__fixunsdfti_windows_x86_64, .{ .name = "__fixunsdfti", .linkage = .Weak });
         @export(__fixunssfti_windows_x86_64, .{ .name = "__fixunssfti", .linkage = .Weak });
+        @export(__floattidf_windows_x86_64, .{ .name = "__floattidf", .linkage = .Weak });
+        @export(__floattisf_windows_x86_64, .{ .name = "__floattisf", .linkage = .Weak });
+        @export(__floatuntidf_windows_x86_64, .{ .name = "__floatuntidf", .linkage = .Weak });
+        @export(__floatuntisf_windows_x86_64, .{ .name = "__floatuntisf", .linkage = .Weak });
     } else {
         @export(__divti3, .{ .name = "__divti3", .linkage = .Weak });
         @export(__modti3, .{ .name = "__modti3", .linkage = .Weak });
@@ -37,6 +41,10 @@ comptime {
         @export(__fixsfti, .{ .name = "__fixsfti", .linkage = .Weak });
         @export(__fixunsdfti, .{ .name = "__fixunsdfti", .linkage = .Weak });
         @export(__fixunssfti, .{ .name = "__fixunssfti", .linkage = .Weak });
+        @export(__floattidf, .{ .name = "__floattidf", .linkage = .Weak });
+        @export(__floattisf, .{ .name = "__floattisf", .linkage = .Weak });
+        @export(__floatuntidf, .{ .name = "__floatuntidf", .linkage = .Weak });
+        @export(__floatuntisf, .{ .name = "__floatuntisf", .linkage = .Weak });
     }
 }
 
@@ -139,6 +147,38 @@ pub fn __fixunssfti(a: f32) callconv(.C) u128 {
 fn __fixunssfti_windows_x86_64(a: f32) callconv(.C) v2u64 {
     return @bitCast(v2u64, floatToInt(u128, a));
 }
+
+pub fn __floattidf(a: i128) callconv(.C) f64 {
+    return intToFloat(f64, a);
+}
+
+fn __floattidf_windows_x86_64(a: v2u64) callconv(.C) f64 {
+    return intToFloat(f64, @bitCast(i128, a));
+}
+
+pub fn __floattisf(a: i128) callconv(.C) f32 {
+    return intToFloat(f32, a);
+}
+
+fn __floattisf_windows_x86_64(a: v2u64) callconv(.C) f32 {
+    return intToFloat(f32, @bitCast(i128, a));
+}
+
+pub fn __floatuntidf(a: u128) callconv(.C) f64 {
+    return intToFloat(f64, a);
+}
+
+fn __floatuntidf_windows_x86_64(a: v2u64) callconv(.C) f64 {
+    return intToFloat(f64, @bitCast(u128, a));
+}
+
+pub fn __floatuntisf(a: u128) callconv(.C) f32 {
+    return intToFloat(f32, a);
+}
+
+fn __floatuntisf_windows_x86_64(a: v2u64) callconv(.C) f32 {
+    return intToFloat(f32, @bitCast(u128, a));
+}
 // mulo - multiplication overflow
 // * return a*%b.
 // * return if a*b overflows => 1 else => 0
@@ -425,6 +465,57 @@ pub inline fn floatToInt(comptime I: type, a: anytype) I {
     return result;
 }
 
+// Round to nearest, ties to even, like compiler-rt's floattidf.c
+pub inline fn intToFloat(comptime F: type, a: anytype) F {
+    const Log2Int = math.Log2Int;
+    const Int = @import("std").meta.Int;
+    const I = @TypeOf(a);
+    const int_bits = @typeInfo(I).Int.bits;
+    const float_bits = @typeInfo(F).Float.bits;
+    const Z = Int(.unsigned, int_bits);
+    const rep_t = Int(.unsigned, float_bits);
+    const sig_bits = math.floatMantissaBits(F);
+    const mant_dig = sig_bits + 1;
+    const exp_bias = (1 << (math.floatExponentBits(F) - 1)) - 1;
+
+    if (a == 0) return 0;
+
+    const negative = @typeInfo(I).Int.signedness == .signed and a < 0;
+    var m: Z = math.absCast(a);
+    const sd: i32 = int_bits - @as(i32, @clz(Z, m)); // number of significant digits
+    var e: i32 = sd - 1; // exponent
+
+    if (sd > mant_dig) {
+        // Keep the significand plus two extra bits, Q and R, where R is sticky:
+        // it is set if any of the bits shifted out were set.
+        if (sd == mant_dig + 1) {
+            m <<= 1;
+        } else if (sd > mant_dig + 2) {
+            const dropped_mask = math.maxInt(Z) >> @intCast(Log2Int(Z), int_bits + mant_dig + 2 - sd);
+            const sticky = @boolToInt((m & dropped_mask) != 0);
+            m = (m >> @intCast(Log2Int(Z), sd - (mant_dig + 2))) | sticky;
+        }
+        m |= @boolToInt((m & 4) != 0); // Or P into R, so that ties round to even
+        m += 1; // Round. This step may add a significant bit
+        m >>= 2; // Dump Q and R
+        if ((m & (@as(Z, 1) << mant_dig)) != 0) {
+            m >>= 1;
+            e += 1;
+        }
+    } else {
+        m <<= @intCast(Log2Int(Z), mant_dig - sd);
+    }
+
+    if (e > exp_bias) {
+        return if (negative) -math.inf(F) else math.inf(F);
+    }
+
+    const sign: rep_t = if (negative) @as(rep_t, 1) << (float_bits - 1) else 0;
+    const exponent = @intCast(rep_t, e + exp_bias) << sig_bits;
+    const significand = @truncate(rep_t, m) & ((@as(rep_t, 1) << sig_bits) - 1);
+    return @bitCast(F, sign | exponent | significand);
+}
+
 /// Returns the number of fractional bits in the mantissa of floating point type T.
 pub inline fn floatFractionalBits(comptime T: type) comptime_int {
     comptime std.debug.assert(@typeInfo(T) == .Float);
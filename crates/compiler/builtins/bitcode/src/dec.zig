@@ -218,6 +218,10 @@ pub const RocDec = extern struct {
         return self.num;
     }
 
+    pub fn toF64(self: RocDec) f64 {
+        return @intToFloat(f64, self.num) / comptime @intToFloat(f64, one_point_zero_i128);
+    }
+
     pub fn eq(self: RocDec, other: RocDec) bool {
         return self.num == other.num;
     }
@@ -751,6 +755,11 @@ test "fromF64 overflow" {
     try expectEqual(dec, null);
 }
 
+test "toF64" {
+    var dec = RocDec{ .num = -25500000000000000000 };
+    try expectEqual(@as(f64, -25.5), dec.toF64());
+}
+
 test "fromStr: empty" {
     var roc_str = RocStr.init("", 0);
     var dec = RocDec.fromStr(roc_str);
@@ -1129,6 +1138,10 @@ pub fn toI128(arg: RocDec) callconv(.C) i128 {
     return @call(.{ .modifier = always_inline }, RocDec.toI128, .{arg});
 }
 
+pub fn toF64C(arg: RocDec) callconv(.C) f64 {
+    return @call(.{ .modifier = always_inline }, RocDec.toF64, .{arg});
+}
+
 pub fn eqC(arg1: RocDec, arg2: RocDec) callconv(.C) bool {
     return @call(.{ .modifier = always_inline }, RocDec.eq, .{ arg1, arg2 });
 }
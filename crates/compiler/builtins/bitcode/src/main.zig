@@ -22,6 +22,7 @@ comptime {
     exportDecFn(dec.toStr, "to_str");
     exportDecFn(dec.fromF64C, "from_f64");
     exportDecFn(dec.toI128, "to_i128");
+    exportDecFn(dec.toF64C, "to_f64");
     exportDecFn(dec.eqC, "eq");
     exportDecFn(dec.neqC, "neq");
     exportDecFn(dec.negateC, "negate");
@@ -81,6 +82,8 @@ comptime {
 
     exportNumFn(num.shiftRightZeroFillI128, "shift_right_zero_fill.i128");
     exportNumFn(num.shiftRightZeroFillU128, "shift_right_zero_fill.u128");
+    exportNumFn(num.shiftLeftByI128, "shift_left_by.i128");
+    exportNumFn(num.shiftRightByI128, "shift_right_by.i128");
 
     inline for (INTEGERS) |T, i| {
         num.exportPow(T, ROC_BUILTINS ++ "." ++ NUM ++ ".pow_int.");
@@ -111,6 +114,14 @@ comptime {
         num.exportCountLeadingZeroBits(T, ROC_BUILTINS ++ "." ++ NUM ++ ".count_leading_zero_bits.");
         num.exportCountTrailingZeroBits(T, ROC_BUILTINS ++ "." ++ NUM ++ ".count_trailing_zero_bits.");
         num.exportCountOneBits(T, ROC_BUILTINS ++ "." ++ NUM ++ ".count_one_bits.");
+
+        num.exportCompare(T, ROC_BUILTINS ++ "." ++ NUM ++ ".compare.");
+        num.exportNeg(T, ROC_BUILTINS ++ "." ++ NUM ++ ".neg.");
+        num.exportAbs(T, ROC_BUILTINS ++ "." ++ NUM ++ ".abs.");
+
+        num.exportBitwiseAnd(T, ROC_BUILTINS ++ "." ++ NUM ++ ".bitwise_and.");
+        num.exportBitwiseOr(T, ROC_BUILTINS ++ "." ++ NUM ++ ".bitwise_or.");
+        num.exportBitwiseXor(T, ROC_BUILTINS ++ "." ++ NUM ++ ".bitwise_xor.");
     }
 
     inline for (INTEGERS) |FROM| {
@@ -121,6 +132,12 @@ comptime {
         }
     }
 
+    inline for (NUMBERS) |FROM| {
+        inline for (FLOATS) |TO| {
+            num.exportToFloatChecked(FROM, TO, ROC_BUILTINS ++ "." ++ NUM ++ ".to_" ++ @typeName(TO) ++ "_checked.");
+        }
+    }
+
     inline for (FLOATS) |T| {
         num.exportAsin(T, ROC_BUILTINS ++ "." ++ NUM ++ ".asin.");
         num.exportAcos(T, ROC_BUILTINS ++ "." ++ NUM ++ ".acos.");
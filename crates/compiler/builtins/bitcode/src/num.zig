@@ -4,6 +4,7 @@ const math = std.math;
 const RocList = @import("list.zig").RocList;
 const RocStr = @import("str.zig").RocStr;
 const WithOverflow = @import("utils.zig").WithOverflow;
+const Ordering = @import("utils.zig").Ordering;
 const roc_panic = @import("panic.zig").panic_help;
 
 pub fn NumParseResult(comptime T: type) type {
@@ -263,6 +264,27 @@ pub fn exportToIntCheckingMaxAndMin(comptime From: type, comptime To: type, comp
     @export(f, .{ .name = name ++ @typeName(From), .linkage = .Strong });
 }
 
+pub fn exportToFloatChecked(comptime From: type, comptime To: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(input: From) callconv(.C) ToIntCheckedResult(To) {
+            const result = switch (@typeInfo(From)) {
+                .Int => @intToFloat(To, input),
+                else => @floatCast(To, input),
+            };
+            // Only a finite input can be out of bounds; infinities and NaN convert as themselves
+            const out_of_bounds = switch (@typeInfo(From)) {
+                .Int => math.isInf(result),
+                else => math.isInf(result) and !math.isInf(input),
+            };
+            if (out_of_bounds) {
+                return .{ .out_of_bounds = true, .value = 0 };
+            }
+            return .{ .out_of_bounds = false, .value = result };
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(From), .linkage = .Strong });
+}
+
 pub fn bytesToU16C(arg: RocList, position: usize) callconv(.C) u16 {
     return @call(.{ .modifier = always_inline }, bytesToU16, .{ arg, position });
 }
@@ -558,6 +580,22 @@ pub fn shiftRightZeroFillU128(self: u128, other: u8) callconv(.C) u128 {
     }
 }
 
+pub fn shiftLeftByI128(self: i128, other: u8) callconv(.C) i128 {
+    if (other & 0b1000_0000 > 0) {
+        return 0;
+    } else {
+        return self << @intCast(u7, other);
+    }
+}
+
+pub fn shiftRightByI128(self: i128, other: u8) callconv(.C) i128 {
+    if (other & 0b1000_0000 > 0) {
+        return self >> 127;
+    } else {
+        return self >> @intCast(u7, other);
+    }
+}
+
 pub fn exportMulOrPanic(comptime T: type, comptime W: type, comptime name: []const u8) void {
     comptime var f = struct {
         fn func(self: T, other: T) callconv(.C) T {
@@ -599,3 +637,77 @@ pub fn exportCountOneBits(comptime T: type, comptime name: []const u8) void {
     }.func;
     @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
 }
+
+pub fn exportCompare(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T, other: T) callconv(.C) Ordering {
+            if (self == other) {
+                return Ordering.EQ;
+            } else if (self > other) {
+                return Ordering.GT;
+            } else {
+                return Ordering.LT;
+            }
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportNeg(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T) callconv(.C) T {
+            const result = subWithOverflow(T, 0, self);
+            if (result.has_overflowed) {
+                roc_panic("integer negation overflowed because its argument is the minimum value", 0);
+                unreachable;
+            } else {
+                return result.value;
+            }
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportAbs(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T) callconv(.C) T {
+            if (comptime @typeInfo(T).Int.signedness == .unsigned) {
+                return self;
+            } else {
+                if (self == math.minInt(T)) {
+                    roc_panic("integer absolute overflowed because its argument is the minimum value", 0);
+                    unreachable;
+                }
+                return if (self < 0) -self else self;
+            }
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportBitwiseAnd(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T, other: T) callconv(.C) T {
+            return self & other;
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportBitwiseOr(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T, other: T) callconv(.C) T {
+            return self | other;
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportBitwiseXor(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T, other: T) callconv(.C) T {
+            return self ^ other;
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
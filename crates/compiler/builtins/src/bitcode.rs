@@ -257,6 +257,19 @@ macro_rules! int_intrinsic {
     }};
 }
 
+#[macro_export]
+macro_rules! int_and_float_intrinsic {
+    ($name:literal) => {{
+        let mut output = int_intrinsic!($name);
+
+        // The indices align with the `Index` impl for `IntrinsicName`.
+        output.options[1] = concat!($name, ".f32");
+        output.options[2] = concat!($name, ".f64");
+
+        output
+    }};
+}
+
 pub const NUM_SIN: IntrinsicName = float_intrinsic!("roc_builtins.num.sin");
 pub const NUM_COS: IntrinsicName = float_intrinsic!("roc_builtins.num.cos");
 pub const NUM_ASIN: IntrinsicName = float_intrinsic!("roc_builtins.num.asin");
@@ -303,6 +316,22 @@ pub const NUM_IS_MULTIPLE_OF: IntrinsicName = int_intrinsic!("roc_builtins.num.i
 pub const NUM_SHIFT_RIGHT_ZERO_FILL: IntrinsicName =
     int_intrinsic!("roc_builtins.num.shift_right_zero_fill");
 
+pub const NUM_SHIFT_LEFT_BY_I128: &str = "roc_builtins.num.shift_left_by.i128";
+pub const NUM_SHIFT_RIGHT_BY_I128: &str = "roc_builtins.num.shift_right_by.i128";
+
+pub const NUM_COMPARE: IntrinsicName = int_intrinsic!("roc_builtins.num.compare");
+pub const NUM_NEG: IntrinsicName = int_intrinsic!("roc_builtins.num.neg");
+pub const NUM_ABS: IntrinsicName = int_intrinsic!("roc_builtins.num.abs");
+
+pub const NUM_BITWISE_AND: IntrinsicName = int_intrinsic!("roc_builtins.num.bitwise_and");
+pub const NUM_BITWISE_OR: IntrinsicName = int_intrinsic!("roc_builtins.num.bitwise_or");
+pub const NUM_BITWISE_XOR: IntrinsicName = int_intrinsic!("roc_builtins.num.bitwise_xor");
+
+pub const NUM_TO_F32_CHECKED: IntrinsicName =
+    int_and_float_intrinsic!("roc_builtins.num.to_f32_checked");
+pub const NUM_TO_F64_CHECKED: IntrinsicName =
+    int_and_float_intrinsic!("roc_builtins.num.to_f64_checked");
+
 pub const NUM_COUNT_LEADING_ZERO_BITS: IntrinsicName =
     int_intrinsic!("roc_builtins.num.count_leading_zero_bits");
 pub const NUM_COUNT_TRAILING_ZERO_BITS: IntrinsicName =
@@ -375,6 +404,7 @@ pub const DEC_FROM_STR: &str = "roc_builtins.dec.from_str";
 pub const DEC_TO_STR: &str = "roc_builtins.dec.to_str";
 pub const DEC_FROM_F64: &str = "roc_builtins.dec.from_f64";
 pub const DEC_TO_I128: &str = "roc_builtins.dec.to_i128";
+pub const DEC_TO_F64: &str = "roc_builtins.dec.to_f64";
 pub const DEC_EQ: &str = "roc_builtins.dec.eq";
 pub const DEC_NEQ: &str = "roc_builtins.dec.neq";
 pub const DEC_NEGATE: &str = "roc_builtins.dec.negate";
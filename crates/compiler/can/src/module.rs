@@ -1254,6 +1254,21 @@ pub struct TypeState {
 
 impl TypeState {
     pub fn serialize(&self, writer: &mut impl std::io::Write) -> std::io::Result<usize> {
+        self.serialize_help(writer, false)
+    }
+
+    /// Like [`TypeState::serialize`], but every section is padded to a multiple of 8 bytes.
+    /// Each section aligns its contents relative to its own start, so this keeps them aligned
+    /// relative to the start of the whole buffer, too. The builtins keep the unpadded layout.
+    pub fn serialize_padded(&self, writer: &mut impl std::io::Write) -> std::io::Result<usize> {
+        self.serialize_help(writer, true)
+    }
+
+    fn serialize_help(
+        &self,
+        writer: &mut impl std::io::Write,
+        padded: bool,
+    ) -> std::io::Result<usize> {
         let Self {
             subs,
             exposed_vars_by_symbol,
@@ -1262,18 +1277,51 @@ impl TypeState {
         } = self;
 
         let written_subs = subs.serialize(exposed_vars_by_symbol, writer)?;
+        let written_subs = Self::pad_section(written_subs, writer, padded)?;
         let written_ab = abilities.serialize(writer)?;
+        let written_ab = Self::pad_section(written_ab, writer, padded)?;
         let written_solved_impls =
             crate::abilities::serialize_solved_implementations(solved_implementations, writer)?;
 
         Ok(written_subs + written_ab + written_solved_impls)
     }
 
+    fn pad_section(
+        written: usize,
+        writer: &mut impl std::io::Write,
+        padded: bool,
+    ) -> std::io::Result<usize> {
+        if padded {
+            roc_serialize::bytes::serialize_slice::<u64>(&[], writer, written)
+        } else {
+            Ok(written)
+        }
+    }
+
+    fn section_len(len: usize, padded: bool) -> usize {
+        if padded {
+            roc_serialize::bytes::next_multiple_of(len, std::mem::align_of::<u64>())
+        } else {
+            len
+        }
+    }
+
     pub fn deserialize(bytes: &[u8]) -> (Self, usize) {
+        Self::deserialize_help(bytes, false)
+    }
+
+    /// Reads a [`TypeState`] written by [`TypeState::serialize_padded`]
+    pub fn deserialize_padded(bytes: &[u8]) -> (Self, usize) {
+        Self::deserialize_help(bytes, true)
+    }
+
+    fn deserialize_help(bytes: &[u8], padded: bool) -> (Self, usize) {
         let ((subs, exposed_vars_by_symbol), len_subs) = Subs::deserialize(bytes);
+        let len_subs = Self::section_len(len_subs, padded);
         let bytes = &bytes[len_subs..];
 
         let (abilities, len_abilities) = AbilitiesStore::deserialize(bytes);
+        let len_abilities = Self::section_len(len_abilities, padded);
         let bytes = &bytes[len_abilities..];
 
         let (solved_implementations, len_solved_impls) =
@@ -1,7 +1,7 @@
 //! Traversals over the can ast.
 
 use roc_module::{ident::Lowercase, symbol::Symbol};
-use roc_region::all::{Loc, Region};
+use roc_region::all::{Loc, Position, Region};
 use roc_types::{subs::Variable, types::MemberImpl};
 
 use crate::{
@@ -164,7 +164,7 @@ pub fn walk_decls<V: Visitor>(visitor: &mut V, decls: &Declarations) {
     }
 }
 
-fn walk_decl<V: Visitor>(visitor: &mut V, decl: DeclarationInfo<'_>) {
+pub fn walk_decl<V: Visitor>(visitor: &mut V, decl: DeclarationInfo<'_>) {
     use DeclarationInfo::*;
 
     match decl {
@@ -876,3 +876,147 @@ pub fn find_declaration(symbol: Symbol, decls: &'_ Declarations) -> Option<Found
         }
     }
 }
+
+/// Finds the innermost expression or pattern (with a known type) that contains `position`,
+/// and returns its type along with its region.
+pub fn find_closest_type_at(position: Position, decls: &Declarations) -> Option<Loc<Variable>> {
+    let mut visitor = Finder {
+        position,
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    return visitor.found;
+
+    struct Finder {
+        position: Position,
+        found: Option<Loc<Variable>>,
+    }
+
+    impl Visitor for Finder {
+        fn should_visit(&mut self, region: Region) -> bool {
+            region.contains(&Region::from_pos(self.position))
+        }
+
+        fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+            if self.should_visit(region) {
+                // Anything we find while walking the expression is more specific, so it wins.
+                self.found = Some(Loc::at(region, var));
+                walk_expr(self, expr, var);
+            }
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, opt_var: Option<Variable>) {
+            if self.should_visit(region) {
+                if let Some(var) = opt_var {
+                    self.found = Some(Loc::at(region, var));
+                }
+                walk_pattern(self, pattern);
+            }
+        }
+    }
+}
+
+/// Finds the symbol that is referenced or introduced at `position`, if any.
+pub fn find_closest_symbol_at(position: Position, decls: &Declarations) -> Option<Loc<Symbol>> {
+    let mut visitor = Finder {
+        position,
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    return visitor.found;
+
+    struct Finder {
+        position: Position,
+        found: Option<Loc<Symbol>>,
+    }
+
+    impl Visitor for Finder {
+        fn should_visit(&mut self, region: Region) -> bool {
+            region.contains(&Region::from_pos(self.position))
+        }
+
+        fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+            if self.should_visit(region) {
+                match expr {
+                    Expr::Var(symbol, _) | Expr::AbilityMember(symbol, _, _) => {
+                        self.found = Some(Loc::at(region, *symbol));
+                    }
+                    _ => walk_expr(self, expr, var),
+                }
+            }
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+            if self.should_visit(region) {
+                match pattern {
+                    Pattern::Identifier(symbol)
+                    | Pattern::Shadowed(_, _, symbol)
+                    | Pattern::AbilityMemberSpecialization { ident: symbol, .. } => {
+                        self.found = Some(Loc::at(region, *symbol));
+                    }
+                    _ => walk_pattern(self, pattern),
+                }
+            }
+        }
+
+        fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+            if self.should_visit(region) {
+                match destruct.typ {
+                    DestructType::Required | DestructType::Optional(..) => {
+                        self.found = Some(Loc::at(region, destruct.symbol));
+                    }
+                    DestructType::Guard(..) => walk_record_destruct(self, destruct),
+                }
+            }
+        }
+    }
+}
+
+/// Finds the region of the pattern that introduces `symbol`, which may be a top-level
+/// declaration, a nested def, a function argument, or a pattern in a `when` branch.
+pub fn find_symbol_definition(symbol: Symbol, decls: &Declarations) -> Option<Region> {
+    let mut visitor = Finder {
+        symbol,
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    return visitor.found;
+
+    struct Finder {
+        symbol: Symbol,
+        found: Option<Region>,
+    }
+
+    impl Visitor for Finder {
+        fn should_visit(&mut self, _region: Region) -> bool {
+            self.found.is_none()
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+            if self.should_visit(region) {
+                match pattern {
+                    Pattern::Identifier(symbol)
+                    | Pattern::Shadowed(_, _, symbol)
+                    | Pattern::AbilityMemberSpecialization { ident: symbol, .. }
+                        if *symbol == self.symbol =>
+                    {
+                        self.found = Some(region);
+                    }
+                    _ => walk_pattern(self, pattern),
+                }
+            }
+        }
+
+        fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+            if self.should_visit(region) {
+                if destruct.symbol == self.symbol
+                    && !matches!(destruct.typ, DestructType::Guard(..))
+                {
+                    self.found = Some(region);
+                } else {
+                    walk_record_destruct(self, destruct);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Formatting part of a module, for editors which format the selection or the def being typed in.
+use crate::annotation::Formattable;
+use crate::Buf;
+use bumpalo::Bump;
+use roc_parse::module::{self, module_defs};
+use roc_parse::parser::{Parser, SyntaxError};
+use roc_parse::state::State;
+use std::ops::Range;
+
+/// Replace the bytes in `range` of the original source with `new_text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// Format the top-level defs which overlap the given byte range of the module's source, and return
+/// the edits which would do that. Everything outside of those defs (including the module header,
+/// and the comments and blank lines between defs) is left exactly as it is.
+///
+/// An empty range (e.g. the cursor position) selects the def it's in, if any. The edits are
+/// sorted and don't overlap, and each one is trimmed down to the part of the def that changes.
+pub fn format_range<'a>(
+    arena: &'a Bump,
+    src: &'a str,
+    range: Range<usize>,
+) -> Result<Vec<TextEdit>, SyntaxError<'a>> {
+    let (_, state) = module::parse_header(arena, State::new(src.as_bytes()))
+        .map_err(|fail| SyntaxError::Header(fail.problem))?;
+    let (_, defs, _) = module_defs()
+        .parse(arena, state, 0)
+        .map_err(|(_, fail)| fail)?;
+
+    let mut edits = Vec::new();
+
+    for (def, region) in defs.defs().zip(defs.regions.iter()) {
+        let def_range = region.start().offset as usize..region.end().offset as usize;
+
+        let overlaps = if range.is_empty() {
+            def_range.start <= range.start && range.start <= def_range.end
+        } else {
+            def_range.start < range.end && range.start < def_range.end
+        };
+
+        if !overlaps {
+            continue;
+        }
+
+        let mut buf = Buf::new_in(arena);
+
+        match def {
+            Ok(type_def) => type_def.format(&mut buf, 0),
+            Err(value_def) => value_def.format(&mut buf, 0),
+        }
+
+        if let Some(edit) = minimal_edit(src, def_range, buf.as_str()) {
+            edits.push(edit);
+        }
+    }
+
+    Ok(edits)
+}
+
+/// An edit replacing src[range] with new_text, without the prefix and suffix they have in common
+fn minimal_edit(src: &str, range: Range<usize>, new_text: &str) -> Option<TextEdit> {
+    let old_text = &src[range.clone()];
+
+    if old_text == new_text {
+        return None;
+    }
+
+    let prefix = common_len(old_text.chars(), new_text.chars());
+    let suffix = common_len(
+        old_text[prefix..].chars().rev(),
+        new_text[prefix..].chars().rev(),
+    );
+
+    Some(TextEdit {
+        range: range.start + prefix..range.end - suffix,
+        new_text: new_text[prefix..new_text.len() - suffix].to_string(),
+    })
+}
+
+/// The length in bytes of the chars both iterators start with
+fn common_len(old: impl Iterator<Item = char>, new: impl Iterator<Item = char>) -> usize {
+    old.zip(new)
+        .take_while(|(old_ch, new_ch)| old_ch == new_ch)
+        .map(|(ch, _)| ch.len_utf8())
+        .sum()
+}
@@ -1,11 +1,16 @@
 use crate::generic64::{storage::StorageManager, Assembler, CallConv, RegTrait};
-use crate::Relocation;
+use crate::{
+    pointer_layouts, single_register_floats, single_register_int_builtins,
+    single_register_integers, single_register_layouts, Relocation,
+};
 use bumpalo::collections::Vec;
 use packed_struct::prelude::*;
 use roc_builtins::bitcode::FloatWidth;
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
-use roc_mono::layout::{InLayout, STLayoutInterner};
+use roc_mono::layout::{
+    Builtin, InLayout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout,
+};
 
 use super::{CompareOperation, RegisterWidth};
 
@@ -203,6 +208,12 @@ pub struct AArch64Call {}
 
 const STACK_ALIGNMENT: u8 = 16;
 
+// The intra-procedure-call registers are never handed out by the storage manager.
+// The assembler uses them as scratch registers for large immediates and addresses.
+// IP1 may be clobbered by any load or store, so it must only be used for address computation.
+const TMP_REG: AArch64GeneralReg = AArch64GeneralReg::IP0;
+const ADDRESS_TMP_REG: AArch64GeneralReg = AArch64GeneralReg::IP1;
+
 impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64Call {
     const BASE_PTR_REG: AArch64GeneralReg = AArch64GeneralReg::FP;
     const STACK_PTR_REG: AArch64GeneralReg = AArch64GeneralReg::ZRSP;
@@ -227,6 +238,7 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         // Don't use platform register: AArch64GeneralReg::PR,
         // Don't use link register: AArch64GeneralReg::LR,
         // Don't use zero register/stack pointer: AArch64GeneralReg::ZRSP,
+        // Don't use the assembler scratch registers: AArch64GeneralReg::IP0, AArch64GeneralReg::IP1,
 
         // Use callee saved regs last.
         AArch64GeneralReg::X19,
@@ -255,12 +267,57 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         AArch64GeneralReg::X13,
         AArch64GeneralReg::X14,
         AArch64GeneralReg::X15,
-        AArch64GeneralReg::IP0,
-        AArch64GeneralReg::IP1,
     ];
-    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = &[];
+    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = &[
+        AArch64FloatReg::V0,
+        AArch64FloatReg::V1,
+        AArch64FloatReg::V2,
+        AArch64FloatReg::V3,
+        AArch64FloatReg::V4,
+        AArch64FloatReg::V5,
+        AArch64FloatReg::V6,
+        AArch64FloatReg::V7,
+    ];
     const FLOAT_RETURN_REGS: &'static [AArch64FloatReg] = Self::FLOAT_PARAM_REGS;
-    const FLOAT_DEFAULT_FREE_REGS: &'static [AArch64FloatReg] = &[];
+    const FLOAT_DEFAULT_FREE_REGS: &'static [AArch64FloatReg] = &[
+        // The regs we want to use first should be at the end of this vec.
+        // We will use pop to get which reg to use next
+
+        // Use callee saved regs last.
+        AArch64FloatReg::V8,
+        AArch64FloatReg::V9,
+        AArch64FloatReg::V10,
+        AArch64FloatReg::V11,
+        AArch64FloatReg::V12,
+        AArch64FloatReg::V13,
+        AArch64FloatReg::V14,
+        AArch64FloatReg::V15,
+        // Use caller saved regs first.
+        AArch64FloatReg::V0,
+        AArch64FloatReg::V1,
+        AArch64FloatReg::V2,
+        AArch64FloatReg::V3,
+        AArch64FloatReg::V4,
+        AArch64FloatReg::V5,
+        AArch64FloatReg::V6,
+        AArch64FloatReg::V7,
+        AArch64FloatReg::V16,
+        AArch64FloatReg::V17,
+        AArch64FloatReg::V18,
+        AArch64FloatReg::V19,
+        AArch64FloatReg::V20,
+        AArch64FloatReg::V21,
+        AArch64FloatReg::V22,
+        AArch64FloatReg::V23,
+        AArch64FloatReg::V24,
+        AArch64FloatReg::V25,
+        AArch64FloatReg::V26,
+        AArch64FloatReg::V27,
+        AArch64FloatReg::V28,
+        AArch64FloatReg::V29,
+        AArch64FloatReg::V30,
+        AArch64FloatReg::V31,
+    ];
 
     const SHADOW_SPACE_SIZE: u8 = 0;
 
@@ -281,8 +338,19 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         )
     }
     #[inline(always)]
-    fn float_callee_saved(_reg: &AArch64FloatReg) -> bool {
-        todo!("AArch64 FloatRegs");
+    fn float_callee_saved(reg: &AArch64FloatReg) -> bool {
+        // Only the bottom 64 bits are callee saved, but we never use more than that.
+        matches!(
+            reg,
+            AArch64FloatReg::V8
+                | AArch64FloatReg::V9
+                | AArch64FloatReg::V10
+                | AArch64FloatReg::V11
+                | AArch64FloatReg::V12
+                | AArch64FloatReg::V13
+                | AArch64FloatReg::V14
+                | AArch64FloatReg::V15
+        )
     }
 
     #[inline(always)]
@@ -293,9 +361,19 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         requested_stack_size: i32,
         fn_call_stack_size: i32,
     ) -> i32 {
-        // Full size is upcast to i64 to make sure we don't overflow here.
+        // Push the frame record and point the frame pointer at it.
+        // Arguments passed on the stack start right above it.
+        stp_reg64_reg64_pre_index(
+            buf,
+            AArch64GeneralReg::FP,
+            AArch64GeneralReg::LR,
+            AArch64GeneralReg::ZRSP,
+            -16,
+        );
+        AArch64Assembler::mov_reg64_reg64(buf, AArch64GeneralReg::FP, AArch64GeneralReg::ZRSP);
+
         let full_stack_size = match requested_stack_size
-            .checked_add(8 * (saved_general_regs.len() + saved_float_regs.len()) as i32 + 8) // The extra 8 is space to store the frame pointer.
+            .checked_add(8 * (saved_general_regs.len() + saved_float_regs.len()) as i32)
             .and_then(|size| size.checked_add(fn_call_stack_size))
         {
             Some(size) => size,
@@ -313,11 +391,6 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         };
         if let Some(aligned_stack_size) = full_stack_size.checked_add(offset as i32) {
             if aligned_stack_size > 0 {
-                AArch64Assembler::mov_reg64_reg64(
-                    buf,
-                    AArch64GeneralReg::FP,
-                    AArch64GeneralReg::ZRSP,
-                );
                 AArch64Assembler::sub_reg64_reg64_imm32(
                     buf,
                     AArch64GeneralReg::ZRSP,
@@ -325,21 +398,15 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
                     aligned_stack_size,
                 );
 
-                // All the following stores could be optimized by using `STP` to store pairs.
-                let mut offset = aligned_stack_size;
-                offset -= 8;
-                AArch64Assembler::mov_stack32_reg64(buf, offset, AArch64GeneralReg::LR);
-                offset -= 8;
-                AArch64Assembler::mov_stack32_reg64(buf, offset, AArch64GeneralReg::FP);
-
-                offset = aligned_stack_size - fn_call_stack_size;
+                // Put values at the top of the stack to avoid conflicts with previously saved variables.
+                let mut offset = aligned_stack_size - fn_call_stack_size;
                 for reg in saved_general_regs {
+                    AArch64Assembler::mov_base32_reg64(buf, -offset, *reg);
                     offset -= 8;
-                    AArch64Assembler::mov_base32_reg64(buf, offset, *reg);
                 }
                 for reg in saved_float_regs {
+                    AArch64Assembler::mov_base32_freg64(buf, -offset, *reg);
                     offset -= 8;
-                    AArch64Assembler::mov_base32_freg64(buf, offset, *reg);
                 }
                 aligned_stack_size
             } else {
@@ -359,21 +426,14 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         fn_call_stack_size: i32,
     ) {
         if aligned_stack_size > 0 {
-            // All the following stores could be optimized by using `STP` to store pairs.
-            let mut offset = aligned_stack_size;
-            offset -= 8;
-            AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::LR, offset);
-            offset -= 8;
-            AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::FP, offset);
-
-            offset = aligned_stack_size - fn_call_stack_size;
+            let mut offset = aligned_stack_size - fn_call_stack_size;
             for reg in saved_general_regs {
+                AArch64Assembler::mov_reg64_base32(buf, *reg, -offset);
                 offset -= 8;
-                AArch64Assembler::mov_reg64_base32(buf, *reg, offset);
             }
             for reg in saved_float_regs {
+                AArch64Assembler::mov_freg64_base32(buf, *reg, -offset);
                 offset -= 8;
-                AArch64Assembler::mov_freg64_base32(buf, *reg, offset);
             }
             AArch64Assembler::add_reg64_reg64_imm32(
                 buf,
@@ -382,90 +442,763 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
                 aligned_stack_size,
             );
         }
+        ldp_reg64_reg64_post_index(
+            buf,
+            AArch64GeneralReg::FP,
+            AArch64GeneralReg::LR,
+            AArch64GeneralReg::ZRSP,
+            16,
+        );
     }
 
     #[inline(always)]
     fn load_args<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
-            'a,
-            '_,
-            AArch64GeneralReg,
-            AArch64FloatReg,
-            AArch64Assembler,
-            AArch64Call,
-        >,
-        _layout_interner: &mut STLayoutInterner<'a>,
-        _args: &'a [(InLayout<'a>, Symbol)],
-        _ret_layout: &InLayout<'a>,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        args: &'a [(InLayout<'a>, Symbol)],
+        ret_layout: &InLayout<'a>,
     ) {
-        todo!("Loading args for AArch64");
+        let mut state = AArch64CallLoadArgs {
+            general_i: 0,
+            float_i: 0,
+            // 16 is the size of the pushed frame pointer and link register.
+            argument_offset: 16,
+        };
+
+        if Self::returns_via_arg_pointer(layout_interner, ret_layout) {
+            // The caller passes the address of the result in the indirect result location register.
+            storage_manager.ret_pointer_arg(AArch64GeneralReg::XR);
+        }
+
+        for (in_layout, sym) in args.iter() {
+            state.load_arg(buf, storage_manager, layout_interner, *sym, *in_layout);
+        }
     }
 
     #[inline(always)]
     fn store_args<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
-            'a,
-            '_,
-            AArch64GeneralReg,
-            AArch64FloatReg,
-            AArch64Assembler,
-            AArch64Call,
-        >,
-        _layout_interner: &mut STLayoutInterner<'a>,
-        _dst: &Symbol,
-        _args: &[Symbol],
-        _arg_layouts: &[InLayout<'a>],
-        _ret_layout: &InLayout<'a>,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        dst: &Symbol,
+        args: &[Symbol],
+        arg_layouts: &[InLayout<'a>],
+        ret_layout: &InLayout<'a>,
     ) {
-        todo!("Storing args for AArch64");
+        let mut state = AArch64CallStoreArgs {
+            general_i: 0,
+            float_i: 0,
+            tmp_stack_offset: Self::SHADOW_SPACE_SIZE as i32,
+        };
+
+        for (sym, in_layout) in args.iter().zip(arg_layouts.iter()) {
+            state.store_arg(buf, storage_manager, layout_interner, *sym, *in_layout);
+        }
+
+        if Self::returns_via_arg_pointer(layout_interner, ret_layout) {
+            // Save space on the stack for the result we will be return.
+            let base_offset =
+                storage_manager.claim_stack_area(dst, layout_interner.stack_size(*ret_layout));
+            // XR is not an argument register, so it is only set once all arguments are in place.
+            AArch64Assembler::add_reg64_reg64_imm32(
+                buf,
+                AArch64GeneralReg::XR,
+                AArch64GeneralReg::FP,
+                base_offset,
+            );
+        }
+
+        storage_manager.update_fn_call_stack_size(state.tmp_stack_offset as u32);
     }
 
     fn return_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
-            'a,
-            '_,
-            AArch64GeneralReg,
-            AArch64FloatReg,
-            AArch64Assembler,
-            AArch64Call,
-        >,
-        _layout_interner: &mut STLayoutInterner<'a>,
-        _sym: &Symbol,
-        _layout: &InLayout<'a>,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        sym: &Symbol,
+        layout: &InLayout<'a>,
     ) {
-        todo!("Returning complex symbols for AArch64");
+        if let Some((width, count)) = Self::homogeneous_float_aggregate(layout_interner, *layout) {
+            let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+            let regs = &Self::FLOAT_RETURN_REGS[..count];
+
+            return Self::load_hfa_members(buf, width, regs, base_offset);
+        }
+
+        match layout_interner.get_repr(*layout) {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            _ if layout_interner.stack_size(*layout) == 0 => {}
+            _ if !Self::returns_via_arg_pointer(layout_interner, layout) => {
+                let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
+                debug_assert_eq!(base_offset % 8, 0);
+                if size <= 8 {
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[0],
+                        base_offset,
+                    );
+                } else if size <= 16 {
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[0],
+                        base_offset,
+                    );
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[1],
+                        base_offset + 8,
+                    );
+                } else {
+                    internal_error!(
+                        "types that don't return via arg pointer must be less than 16 bytes"
+                    );
+                }
+            }
+            _ => {
+                // This is a large type returned via the arg pointer.
+                // Unlike x86, the arg pointer does not need to be returned as well.
+                storage_manager.copy_symbol_to_arg_pointer(buf, sym, layout);
+            }
+        }
     }
 
     fn load_returned_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
-            'a,
-            '_,
-            AArch64GeneralReg,
-            AArch64FloatReg,
-            AArch64Assembler,
-            AArch64Call,
-        >,
-        _layout_interner: &mut STLayoutInterner<'a>,
-        _sym: &Symbol,
-        _layout: &InLayout<'a>,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        sym: &Symbol,
+        layout: &InLayout<'a>,
     ) {
-        todo!("Loading returned complex symbols for AArch64");
+        if let Some((width, count)) = Self::homogeneous_float_aggregate(layout_interner, *layout) {
+            let size = layout_interner.stack_size(*layout);
+            let offset = storage_manager.claim_stack_area(sym, size);
+            let regs = &Self::FLOAT_RETURN_REGS[..count];
+
+            return Self::store_hfa_members(buf, width, regs, offset);
+        }
+
+        match layout_interner.get_repr(*layout) {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            _ if layout_interner.stack_size(*layout) == 0 => {
+                storage_manager.no_data(sym);
+            }
+            _ if !Self::returns_via_arg_pointer(layout_interner, layout) => {
+                let size = layout_interner.stack_size(*layout);
+                let offset = storage_manager.claim_stack_area(sym, size);
+                if size <= 8 {
+                    AArch64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
+                } else if size <= 16 {
+                    AArch64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
+                    AArch64Assembler::mov_base32_reg64(
+                        buf,
+                        offset + 8,
+                        Self::GENERAL_RETURN_REGS[1],
+                    );
+                } else {
+                    internal_error!(
+                        "types that don't return via arg pointer must be less than 16 bytes"
+                    );
+                }
+            }
+            _ => {
+                // This should have been recieved via an arg pointer.
+                // That means the value is already loaded onto the stack area we allocated before the call.
+                // Nothing to do.
+            }
+        }
+    }
+
+    fn setjmp(buf: &mut Vec<'_, u8>) {
+        use AArch64FloatReg::*;
+        use AArch64GeneralReg::*;
+
+        // based on the musl libc setjmp implementation
+        //
+        // 0000000000000000 <__setjmp>:
+        //    0:   a9005013        stp     x19, x20, [x0]
+        //    4:   a9015815        stp     x21, x22, [x0, #16]
+        //    8:   a9026017        stp     x23, x24, [x0, #32]
+        //    c:   a9036819        stp     x25, x26, [x0, #48]
+        //   10:   a904701b        stp     x27, x28, [x0, #64]
+        //   14:   a905781d        stp     x29, x30, [x0, #80]
+        //   18:   910003e2        mov     x2, sp
+        //   1c:   f9003402        str     x2, [x0, #104]
+        //   20:   6d072408        stp     d8, d9, [x0, #112]
+        //   24:   6d082c0a        stp     d10, d11, [x0, #128]
+        //   28:   6d09340c        stp     d12, d13, [x0, #144]
+        //   2c:   6d0a3c0e        stp     d14, d15, [x0, #160]
+        //   30:   d2800000        mov     x0, #0x0
+        //   34:   d65f03c0        ret
+
+        let env = X0;
+
+        // store callee-saved (i.e. non-volatile) registers
+        stp_reg64_reg64_offset(buf, X19, X20, env, 0x00);
+        stp_reg64_reg64_offset(buf, X21, X22, env, 0x10);
+        stp_reg64_reg64_offset(buf, X23, X24, env, 0x20);
+        stp_reg64_reg64_offset(buf, X25, X26, env, 0x30);
+        stp_reg64_reg64_offset(buf, X27, X28, env, 0x40);
+
+        // the frame pointer, and the address we'll resume at
+        stp_reg64_reg64_offset(buf, FP, LR, env, 0x50);
+
+        // store the stack pointer
+        AArch64Assembler::mov_reg64_reg64(buf, X2, ZRSP);
+        AArch64Assembler::mov_mem64_offset32_reg64(buf, env, 0x68, X2);
+
+        stp_freg64_freg64_offset(buf, V8, V9, env, 0x70);
+        stp_freg64_freg64_offset(buf, V10, V11, env, 0x80);
+        stp_freg64_freg64_offset(buf, V12, V13, env, 0x90);
+        stp_freg64_freg64_offset(buf, V14, V15, env, 0xA0);
+
+        // return 0
+        AArch64Assembler::mov_reg64_imm64(buf, X0, 0);
+
+        AArch64Assembler::ret(buf)
+    }
+
+    fn longjmp(buf: &mut Vec<'_, u8>) {
+        use AArch64FloatReg::*;
+        use AArch64GeneralReg::*;
+
+        // The buffer comes in x0. The two words to return from setjmp come in x1 and x2.
+        let env = TMP_REG;
+        AArch64Assembler::mov_reg64_reg64(buf, env, X0);
+        AArch64Assembler::mov_reg64_reg64(buf, X0, X1);
+        AArch64Assembler::mov_reg64_reg64(buf, X1, X2);
+
+        // load the callee-saved registers
+        ldp_reg64_reg64_offset(buf, X19, X20, env, 0x00);
+        ldp_reg64_reg64_offset(buf, X21, X22, env, 0x10);
+        ldp_reg64_reg64_offset(buf, X23, X24, env, 0x20);
+        ldp_reg64_reg64_offset(buf, X25, X26, env, 0x30);
+        ldp_reg64_reg64_offset(buf, X27, X28, env, 0x40);
+        ldp_reg64_reg64_offset(buf, FP, LR, env, 0x50);
+
+        // value of sp before the setjmp call
+        AArch64Assembler::mov_reg64_mem64_offset32(buf, X2, env, 0x68);
+        AArch64Assembler::mov_reg64_reg64(buf, ZRSP, X2);
+
+        ldp_freg64_freg64_offset(buf, V8, V9, env, 0x70);
+        ldp_freg64_freg64_offset(buf, V10, V11, env, 0x80);
+        ldp_freg64_freg64_offset(buf, V12, V13, env, 0x90);
+        ldp_freg64_freg64_offset(buf, V14, V15, env, 0xA0);
+
+        // resume right after the setjmp call
+        br_reg64(buf, LR)
+    }
+
+    fn roc_panic(buf: &mut Vec<'_, u8>, relocs: &mut Vec<'_, Relocation>) {
+        use AArch64GeneralReg::*;
+        type ASM = AArch64Assembler;
+
+        // The RocStr message is passed by reference in x0, the crash tag comes in w1.
+        let roc_str_ptr = X0;
+
+        // move the crash tag into the second return register. We add 1 to it because the 0 value
+        // is already used for "no crash occurred"
+        ASM::movzx_reg_reg(buf, RegisterWidth::W32, X2, X1);
+        ASM::add_reg64_reg64_imm32(buf, X2, X2, 1);
+
+        // the setlongjmp_buffer
+        let env = X3;
+        ASM::data_pointer(buf, relocs, String::from("setlongjmp_buffer"), env);
+
+        // move the roc_str bytes into the setlongjmp_buffer, right after the saved registers
+        for offset in [0, 8, 16] {
+            ASM::mov_reg64_mem64_offset32(buf, X4, roc_str_ptr, offset);
+            ASM::mov_mem64_offset32_reg64(buf, env, 0xB0 + offset, X4);
+        }
+
+        // the value to return from the longjmp. It is a pointer to the error message.
+        ASM::add_reg64_reg64_imm32(buf, X1, env, 0xB0);
+        ASM::mov_reg64_reg64(buf, X0, env);
+
+        Self::longjmp(buf)
+    }
+}
+
+impl AArch64Call {
+    fn returns_via_arg_pointer<'a>(
+        interner: &STLayoutInterner<'a>,
+        ret_layout: &InLayout<'a>,
+    ) -> bool {
+        // Composites over 16 bytes are written to memory pointed at by XR, unless they are
+        // homogeneous floating-point aggregates, which are returned in float registers.
+        // details here: https://github.com/ARM-software/abi-aa/blob/main/aapcs64/aapcs64.rst
+        interner.stack_size(*ret_layout) > 16
+            && Self::homogeneous_float_aggregate(interner, *ret_layout).is_none()
+    }
+
+    /// If this is a homogeneous floating-point aggregate (HFA), i.e. a struct of one to four
+    /// floats which all have the same width, that width and the number of floats.
+    /// These are passed and returned in consecutive float registers, one float in each.
+    fn homogeneous_float_aggregate<'a>(
+        interner: &STLayoutInterner<'a>,
+        layout: InLayout<'a>,
+    ) -> Option<(FloatWidth, usize)> {
+        fn members<'a>(
+            interner: &STLayoutInterner<'a>,
+            layout: InLayout<'a>,
+            width: &mut Option<FloatWidth>,
+            count: &mut usize,
+        ) -> bool {
+            match interner.get_repr(layout) {
+                LayoutRepr::Builtin(Builtin::Float(float_width)) => {
+                    *count += 1;
+                    *width.get_or_insert(float_width) == float_width
+                }
+                LayoutRepr::Struct(field_layouts) => field_layouts
+                    .iter()
+                    .all(|field| members(interner, *field, width, count)),
+                LayoutRepr::LambdaSet(lambda_set) => {
+                    members(interner, lambda_set.runtime_representation(), width, count)
+                }
+                _ => false,
+            }
+        }
+
+        // Single floats are passed on their own, rather than as an aggregate
+        if !matches!(
+            interner.get_repr(layout),
+            LayoutRepr::Struct(_) | LayoutRepr::LambdaSet(_)
+        ) {
+            return None;
+        }
+
+        let mut width = None;
+        let mut count = 0;
+
+        if !members(interner, layout, &mut width, &mut count) {
+            return None;
+        }
+
+        match width {
+            Some(width) if (1..=4).contains(&count) => Some((width, count)),
+            _ => None,
+        }
     }
 
-    fn setjmp(_buf: &mut Vec<'_, u8>) {
-        todo!()
+    /// Loads each float of the HFA at `base_offset` in the frame into its float register
+    fn load_hfa_members(
+        buf: &mut Vec<'_, u8>,
+        width: FloatWidth,
+        regs: &[AArch64FloatReg],
+        base_offset: i32,
+    ) {
+        let kind = match width {
+            FloatWidth::F32 => LoadStoreKind::LDR_S,
+            FloatWidth::F64 => LoadStoreKind::LDR_D,
+        };
+
+        Self::move_hfa_members(buf, kind, regs, base_offset)
     }
 
-    fn longjmp(_buf: &mut Vec<'_, u8>) {
-        todo!()
+    /// Stores each float of an HFA from its float register to `base_offset` in the frame
+    fn store_hfa_members(
+        buf: &mut Vec<'_, u8>,
+        width: FloatWidth,
+        regs: &[AArch64FloatReg],
+        base_offset: i32,
+    ) {
+        let kind = match width {
+            FloatWidth::F32 => LoadStoreKind::STR_S,
+            FloatWidth::F64 => LoadStoreKind::STR_D,
+        };
+
+        Self::move_hfa_members(buf, kind, regs, base_offset)
     }
 
-    fn roc_panic(_buf: &mut Vec<'_, u8>, _relocs: &mut Vec<'_, Relocation>) {
-        todo!()
+    fn move_hfa_members(
+        buf: &mut Vec<'_, u8>,
+        kind: LoadStoreKind,
+        regs: &[AArch64FloatReg],
+        base_offset: i32,
+    ) {
+        let member_size = kind.bytes();
+
+        for (i, reg) in regs.iter().enumerate() {
+            load_store_reg_base_offset(
+                buf,
+                kind,
+                reg.id(),
+                AArch64GeneralReg::FP,
+                base_offset + member_size * i as i32,
+            );
+        }
+    }
+}
+
+type AArch64StorageManager<'a, 'r> =
+    StorageManager<'a, 'r, AArch64GeneralReg, AArch64FloatReg, AArch64Assembler, AArch64Call>;
+
+struct AArch64CallStoreArgs {
+    general_i: usize,
+    float_i: usize,
+    tmp_stack_offset: i32,
+}
+
+impl AArch64CallStoreArgs {
+    const GENERAL_PARAM_REGS: &'static [AArch64GeneralReg] = AArch64Call::GENERAL_PARAM_REGS;
+    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = AArch64Call::FLOAT_PARAM_REGS;
+
+    fn store_arg<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        sym: Symbol,
+        in_layout: InLayout<'a>,
+    ) {
+        let stack_size = layout_interner.stack_size(in_layout);
+
+        if let Some((width, count)) =
+            AArch64Call::homogeneous_float_aggregate(layout_interner, in_layout)
+        {
+            return self.store_arg_hfa(buf, storage_manager, sym, width, count);
+        }
+
+        match layout_interner.get_repr(in_layout) {
+            single_register_integers!() => self.store_arg_general(buf, storage_manager, sym),
+            pointer_layouts!() => self.store_arg_general(buf, storage_manager, sym),
+            single_register_floats!() => self.store_arg_float(buf, storage_manager, sym),
+            LayoutRepr::I128 | LayoutRepr::U128 | LayoutRepr::DEC => {
+                self.store_arg_in_regs(buf, storage_manager, sym, true)
+            }
+            _ if stack_size == 0 => {}
+            _ if stack_size > 16 => {
+                // Large composites are passed by reference.
+                // Roc never mutates its arguments, so the callee can read our copy directly.
+                let (base_offset, _) = storage_manager.stack_offset_and_size(&sym);
+                match Self::GENERAL_PARAM_REGS.get(self.general_i) {
+                    Some(reg) => {
+                        AArch64Assembler::add_reg64_reg64_imm32(
+                            buf,
+                            *reg,
+                            AArch64GeneralReg::FP,
+                            base_offset,
+                        );
+                        self.general_i += 1;
+                    }
+                    None => {
+                        AArch64Assembler::add_reg64_reg64_imm32(
+                            buf,
+                            TMP_REG,
+                            AArch64GeneralReg::FP,
+                            base_offset,
+                        );
+                        AArch64Assembler::mov_stack32_reg64(buf, self.tmp_stack_offset, TMP_REG);
+                        self.tmp_stack_offset += 8;
+                    }
+                }
+            }
+            LayoutRepr::LambdaSet(lambda_set) => self.store_arg(
+                buf,
+                storage_manager,
+                layout_interner,
+                sym,
+                lambda_set.runtime_representation(),
+            ),
+            LayoutRepr::Struct { .. } | LayoutRepr::Union(UnionLayout::NonRecursive(_)) => {
+                let aligned_pair = layout_interner.alignment_bytes(in_layout) == 16;
+                self.store_arg_in_regs(buf, storage_manager, sym, aligned_pair)
+            }
+            _ => {
+                todo!(
+                    "calling with arg type, {:?}",
+                    layout_interner.dbg(in_layout)
+                );
+            }
+        }
+    }
+
+    fn store_arg_general<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+    ) {
+        match Self::GENERAL_PARAM_REGS.get(self.general_i) {
+            Some(reg) => {
+                storage_manager.load_to_specified_general_reg(buf, &sym, *reg);
+                self.general_i += 1;
+            }
+            None => {
+                storage_manager.load_to_specified_general_reg(buf, &sym, TMP_REG);
+                AArch64Assembler::mov_stack32_reg64(buf, self.tmp_stack_offset, TMP_REG);
+
+                self.tmp_stack_offset += 8;
+            }
+        }
+    }
+
+    fn store_arg_float<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+    ) {
+        match Self::FLOAT_PARAM_REGS.get(self.float_i) {
+            Some(reg) => {
+                storage_manager.load_to_specified_float_reg(buf, &sym, *reg);
+                self.float_i += 1;
+            }
+            None => {
+                // All float argument registers are taken, so borrow a free one as buffer.
+                let stack_offset = self.tmp_stack_offset;
+                storage_manager.with_tmp_float_reg(buf, |storage_manager, buf, tmp| {
+                    storage_manager.load_to_specified_float_reg(buf, &sym, tmp);
+                    AArch64Assembler::mov_stack32_freg64(buf, stack_offset, tmp);
+                });
+
+                self.tmp_stack_offset += 8;
+            }
+        }
+    }
+
+    /// Passes a homogeneous floating-point aggregate in consecutive float registers,
+    /// or on the stack if there are not enough of them left.
+    fn store_arg_hfa<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+        width: FloatWidth,
+        count: usize,
+    ) {
+        let (base_offset, size) = storage_manager.stack_offset_and_size(&sym);
+
+        if self.float_i + count <= Self::FLOAT_PARAM_REGS.len() {
+            let regs = &Self::FLOAT_PARAM_REGS[self.float_i..][..count];
+            AArch64Call::load_hfa_members(buf, width, regs, base_offset);
+            self.float_i += count;
+        } else {
+            // Once an HFA goes on the stack, no later value may use the float registers.
+            self.float_i = Self::FLOAT_PARAM_REGS.len();
+
+            let words = (size as i32 + 7) / 8;
+            for i in 0..words {
+                AArch64Assembler::mov_reg64_base32(buf, TMP_REG, base_offset + 8 * i);
+                AArch64Assembler::mov_stack32_reg64(buf, self.tmp_stack_offset + 8 * i, TMP_REG);
+            }
+            self.tmp_stack_offset += 8 * words;
+        }
+    }
+
+    /// Passes a value of at most 16 bytes in consecutive general registers,
+    /// or on the stack if there are not enough of them left.
+    /// Values with 16 byte alignment start at an even register.
+    fn store_arg_in_regs<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+        aligned_pair: bool,
+    ) {
+        let (base_offset, size) = storage_manager.stack_offset_and_size(&sym);
+        let words = if size > 8 { 2 } else { 1 };
+
+        if aligned_pair {
+            self.general_i += self.general_i % 2;
+        }
+
+        if self.general_i + words <= Self::GENERAL_PARAM_REGS.len() {
+            let regs = &Self::GENERAL_PARAM_REGS[self.general_i..][..words];
+            for (i, reg) in regs.iter().enumerate() {
+                AArch64Assembler::mov_reg64_base32(buf, *reg, base_offset + 8 * i as i32);
+            }
+            self.general_i += words;
+        } else {
+            // Once a value goes on the stack, no later value may use the registers.
+            self.general_i = Self::GENERAL_PARAM_REGS.len();
+            if aligned_pair {
+                self.tmp_stack_offset = (self.tmp_stack_offset + 15) & !15;
+            }
+
+            for i in 0..words as i32 {
+                AArch64Assembler::mov_reg64_base32(buf, TMP_REG, base_offset + 8 * i);
+                AArch64Assembler::mov_stack32_reg64(buf, self.tmp_stack_offset + 8 * i, TMP_REG);
+            }
+            self.tmp_stack_offset += 8 * words as i32;
+        }
+    }
+}
+
+struct AArch64CallLoadArgs {
+    general_i: usize,
+    float_i: usize,
+    argument_offset: i32,
+}
+
+impl AArch64CallLoadArgs {
+    const GENERAL_PARAM_REGS: &'static [AArch64GeneralReg] = AArch64Call::GENERAL_PARAM_REGS;
+    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = AArch64Call::FLOAT_PARAM_REGS;
+
+    fn load_arg<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        sym: Symbol,
+        in_layout: InLayout<'a>,
+    ) {
+        let stack_size = layout_interner.stack_size(in_layout);
+
+        if let Some((width, count)) =
+            AArch64Call::homogeneous_float_aggregate(layout_interner, in_layout)
+        {
+            return self.load_arg_hfa(buf, storage_manager, sym, stack_size, width, count);
+        }
+
+        match layout_interner.get_repr(in_layout) {
+            single_register_integers!() => self.load_arg_general(storage_manager, sym),
+            pointer_layouts!() => self.load_arg_general(storage_manager, sym),
+            single_register_floats!() => self.load_arg_float(storage_manager, sym),
+            LayoutRepr::I128 | LayoutRepr::U128 | LayoutRepr::DEC => {
+                self.load_arg_in_regs(buf, storage_manager, sym, stack_size, true)
+            }
+            _ if stack_size == 0 => {
+                storage_manager.no_data(&sym);
+            }
+            _ if stack_size > 16 => {
+                self.load_arg_by_reference(buf, storage_manager, sym, stack_size)
+            }
+            LayoutRepr::LambdaSet(lambda_set) => self.load_arg(
+                buf,
+                storage_manager,
+                layout_interner,
+                sym,
+                lambda_set.runtime_representation(),
+            ),
+            LayoutRepr::Struct { .. } | LayoutRepr::Union(UnionLayout::NonRecursive(_)) => {
+                let aligned_pair = layout_interner.alignment_bytes(in_layout) == 16;
+                self.load_arg_in_regs(buf, storage_manager, sym, stack_size, aligned_pair)
+            }
+            _ => {
+                todo!(
+                    "Loading args with layout {:?}",
+                    layout_interner.dbg(in_layout)
+                );
+            }
+        }
+    }
+
+    fn load_arg_general(
+        &mut self,
+        storage_manager: &mut AArch64StorageManager<'_, '_>,
+        sym: Symbol,
+    ) {
+        if let Some(reg) = Self::GENERAL_PARAM_REGS.get(self.general_i) {
+            storage_manager.general_reg_arg(&sym, *reg);
+            self.general_i += 1;
+        } else {
+            storage_manager.primitive_stack_arg(&sym, self.argument_offset);
+            self.argument_offset += 8;
+        }
+    }
+
+    fn load_arg_float(&mut self, storage_manager: &mut AArch64StorageManager<'_, '_>, sym: Symbol) {
+        if let Some(reg) = Self::FLOAT_PARAM_REGS.get(self.float_i) {
+            storage_manager.float_reg_arg(&sym, *reg);
+            self.float_i += 1;
+        } else {
+            storage_manager.primitive_stack_arg(&sym, self.argument_offset);
+            self.argument_offset += 8;
+        }
+    }
+
+    /// Loads a homogeneous floating-point aggregate that was passed in consecutive float
+    /// registers, or on the stack if there were not enough of them left.
+    fn load_arg_hfa<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+        stack_size: u32,
+        width: FloatWidth,
+        count: usize,
+    ) {
+        if self.float_i + count <= Self::FLOAT_PARAM_REGS.len() {
+            // Spill the registers so the value can be used like any other complex symbol.
+            let base_offset = storage_manager.claim_stack_area(&sym, stack_size);
+            let regs = &Self::FLOAT_PARAM_REGS[self.float_i..][..count];
+            AArch64Call::store_hfa_members(buf, width, regs, base_offset);
+            self.float_i += count;
+        } else {
+            self.float_i = Self::FLOAT_PARAM_REGS.len();
+
+            storage_manager.complex_stack_arg(&sym, self.argument_offset, stack_size);
+            self.argument_offset += (stack_size as i32 + 7) & !7;
+        }
+    }
+
+    /// Loads a value of at most 16 bytes that was passed in consecutive general registers,
+    /// or on the stack if there were not enough of them left.
+    fn load_arg_in_regs<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+        stack_size: u32,
+        aligned_pair: bool,
+    ) {
+        let words = if stack_size > 8 { 2 } else { 1 };
+
+        if aligned_pair {
+            self.general_i += self.general_i % 2;
+        }
+
+        if self.general_i + words <= Self::GENERAL_PARAM_REGS.len() {
+            // Spill the registers so the value can be used like any other complex symbol.
+            let base_offset = storage_manager.claim_stack_area(&sym, stack_size);
+            let regs = &Self::GENERAL_PARAM_REGS[self.general_i..][..words];
+            for (i, reg) in regs.iter().enumerate() {
+                AArch64Assembler::mov_base32_reg64(buf, base_offset + 8 * i as i32, *reg);
+            }
+            self.general_i += words;
+        } else {
+            self.general_i = Self::GENERAL_PARAM_REGS.len();
+            if aligned_pair {
+                self.argument_offset = (self.argument_offset + 15) & !15;
+            }
+
+            storage_manager.complex_stack_arg(&sym, self.argument_offset, stack_size);
+            self.argument_offset += 8 * words as i32;
+        }
+    }
+
+    /// Loads a large value that the caller passed a pointer to, copying it into our own stack area.
+    fn load_arg_by_reference<'a>(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut AArch64StorageManager<'a, '_>,
+        sym: Symbol,
+        stack_size: u32,
+    ) {
+        let ptr_reg = match Self::GENERAL_PARAM_REGS.get(self.general_i) {
+            Some(reg) => {
+                self.general_i += 1;
+                *reg
+            }
+            None => {
+                // Nothing lives in x9 while the arguments are loaded, so it can hold the pointer.
+                let reg = AArch64GeneralReg::X9;
+                AArch64Assembler::mov_reg64_base32(buf, reg, self.argument_offset);
+                self.argument_offset += 8;
+                reg
+            }
+        };
+
+        let base_offset = storage_manager.claim_stack_area(&sym, stack_size);
+        for offset in (0..stack_size as i32).step_by(8) {
+            AArch64Assembler::mov_reg64_mem64_offset32(buf, TMP_REG, ptr_reg, offset);
+            AArch64Assembler::mov_base32_reg64(buf, base_offset + offset, TMP_REG);
+        }
     }
 }
 
@@ -493,13 +1226,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         imm32: i32,
     ) {
-        if imm32 < 0 {
-            todo!("immediate addition with values less than 0");
-        } else if imm32 < 0xFFF {
-            add_reg64_reg64_imm12(buf, dst, src, imm32 as u16);
-        } else {
-            todo!("immediate additions with values greater than 12bits");
-        }
+        Self::add_or_sub_reg64_reg64_imm32(buf, false, dst, src, imm32);
     }
     #[inline(always)]
     fn add_reg64_reg64_reg64(
@@ -508,7 +1235,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        add_reg64_reg64_reg64(buf, dst, src1, src2);
+        // Set the flags so that `set_if_overflow` can pick up the result.
+        adds_reg64_reg64_reg64(buf, dst, src1, src2);
     }
     #[inline(always)]
     fn add_freg32_freg32_freg32(
@@ -530,28 +1258,44 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     #[inline(always)]
-    fn call(_buf: &mut Vec<'_, u8>, _relocs: &mut Vec<'_, Relocation>, _fn_name: String) {
-        todo!("calling functions literal for AArch64");
+    fn call(buf: &mut Vec<'_, u8>, relocs: &mut Vec<'_, Relocation>, fn_name: String) {
+        bl_imm26(buf, 0);
+        relocs.push(Relocation::LinkedFunction {
+            offset: buf.len() as u64 - 4,
+            name: fn_name,
+        });
     }
 
     #[inline(always)]
     fn function_pointer(
-        _buf: &mut Vec<'_, u8>,
-        _relocs: &mut Vec<'_, Relocation>,
-        _fn_name: String,
-        _dst: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        relocs: &mut Vec<'_, Relocation>,
+        fn_name: String,
+        dst: AArch64GeneralReg,
     ) {
-        todo!("function pointer for AArch64");
+        // The address is loaded from the GOT, so the relocation covers both instructions.
+        relocs.push(Relocation::LinkedFunctionPointer {
+            offset: buf.len() as u64,
+            name: fn_name,
+        });
+        adrp_reg64_imm32(buf, dst, 0);
+        ldr_reg64_reg64_imm12(buf, dst, dst, 0);
     }
 
     #[inline(always)]
     fn data_pointer(
-        _buf: &mut Vec<'_, u8>,
-        _relocs: &mut Vec<'_, Relocation>,
-        _fn_name: String,
-        _dst: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        relocs: &mut Vec<'_, Relocation>,
+        fn_name: String,
+        dst: AArch64GeneralReg,
     ) {
-        todo!("data pointer for AArch64");
+        // The address is loaded from the GOT, so the relocation covers both instructions.
+        relocs.push(Relocation::LinkedData {
+            offset: buf.len() as u64,
+            name: fn_name,
+        });
+        adrp_reg64_imm32(buf, dst, 0);
+        ldr_reg64_reg64_imm12(buf, dst, dst, 0);
     }
 
     #[inline(always)]
@@ -604,29 +1348,33 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     fn irem_reg64_reg64_reg64<'a, ASM, CC>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         _storage_manager: &mut StorageManager<'a, '_, AArch64GeneralReg, AArch64FloatReg, ASM, CC>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) where
         ASM: Assembler<AArch64GeneralReg, AArch64FloatReg>,
         CC: CallConv<AArch64GeneralReg, AArch64FloatReg, ASM>,
     {
-        todo!()
+        // There is no remainder instruction: src1 - (src1 / src2) * src2
+        sdiv_reg64_reg64_reg64(buf, TMP_REG, src1, src2);
+        msub_reg64_reg64_reg64_reg64(buf, dst, TMP_REG, src2, src1);
     }
 
     fn urem_reg64_reg64_reg64<'a, ASM, CC>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         _storage_manager: &mut StorageManager<'a, '_, AArch64GeneralReg, AArch64FloatReg, ASM, CC>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) where
         ASM: Assembler<AArch64GeneralReg, AArch64FloatReg>,
         CC: CallConv<AArch64GeneralReg, AArch64FloatReg, ASM>,
     {
-        todo!()
+        // There is no remainder instruction: src1 - (src1 / src2) * src2
+        udiv_reg64_reg64_reg64(buf, TMP_REG, src1, src2);
+        msub_reg64_reg64_reg64_reg64(buf, dst, TMP_REG, src2, src1);
     }
 
     #[inline(always)]
@@ -669,10 +1417,12 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
 
     #[inline(always)]
     fn jmp_imm32(buf: &mut Vec<'_, u8>, offset: i32) -> usize {
+        // The offset is relative to the end of the jump, but branches are relative to their start.
+        let offset = offset + 4;
         if (-(1 << 27)..(1 << 27)).contains(&offset) {
             b_imm26(buf, offset);
         } else {
-            todo!("jump offsets over 27 bits for AArch64: {:#x}", offset);
+            internal_error!("jump offsets over 27 bits for AArch64: {:#x}", offset);
         }
 
         buf.len()
@@ -680,8 +1430,9 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
 
     #[inline(always)]
     fn tail_call(buf: &mut Vec<'_, u8>) -> u64 {
-        Self::jmp_imm32(buf, 0);
-        buf.len() as u64 - 4 // TODO is 4 the correct offset in ARM?
+        // The branch is relocated to the called function, so the offset starts as zero.
+        b_imm26(buf, 0);
+        buf.len() as u64 - 4
     }
 
     #[inline(always)]
@@ -699,16 +1450,16 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         if imm < (1 << 12) {
             cmp_reg64_imm12(buf, reg, imm as u16);
         } else {
-            todo!(
-                "cmp immediate with value over 12 bits for AArch64: {:#x}",
-                imm
-            );
+            Self::mov_reg64_imm64(buf, TMP_REG, imm as i64);
+            cmp_reg64_reg64(buf, reg, TMP_REG);
         }
 
+        // The offset is relative to the end of the jump, but branches are relative to their start.
+        let offset = offset + 4;
         if (-(1 << 20)..(1 << 20)).contains(&offset) {
             b_cond_imm19(buf, ConditionCode::NE, offset);
         } else {
-            todo!("jump offsets over 20 bits for AArch64: {:#x}", offset);
+            internal_error!("jump offsets over 20 bits for AArch64: {:#x}", offset);
         }
 
         buf.len()
@@ -731,7 +1482,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
                 fmov_freg_imm8(buf, FloatWidth::F32, dst, imm8);
             }
             None => {
-                todo!("loading f32 literal over 8 bits for AArch64");
+                Self::mov_reg64_imm64(buf, TMP_REG, imm.to_bits() as i64);
+                fmov_freg_reg(buf, FloatWidth::F32, dst, TMP_REG);
             }
         }
     }
@@ -752,7 +1504,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
                 fmov_freg_imm8(buf, FloatWidth::F64, dst, imm8);
             }
             None => {
-                todo!("loading f64 literal over 8 bits for AArch64");
+                Self::mov_reg64_imm64(buf, TMP_REG, imm.to_bits() as i64);
+                fmov_freg_reg(buf, FloatWidth::F64, dst, TMP_REG);
             }
         }
     }
@@ -779,12 +1532,12 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     #[inline(always)]
-    fn mov_reg32_freg32(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _src: AArch64FloatReg) {
-        unimplemented!();
+    fn mov_reg32_freg32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64FloatReg) {
+        fmov_reg_freg(buf, FloatWidth::F32, dst, src);
     }
     #[inline(always)]
-    fn mov_reg64_freg64(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _src: AArch64FloatReg) {
-        unimplemented!();
+    fn mov_reg64_freg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64FloatReg) {
+        fmov_reg_freg(buf, FloatWidth::F64, dst, src);
     }
 
     #[inline(always)]
@@ -794,102 +1547,118 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         dst: AArch64GeneralReg,
         src: AArch64GeneralReg,
     ) {
+        // Like on x86, 32 bit moves clear the upper bits while 8 and 16 bit moves keep them.
         match register_width {
-            RegisterWidth::W8 => todo!(),
-            RegisterWidth::W16 => todo!(),
-            RegisterWidth::W32 => todo!(),
-            RegisterWidth::W64 => mov_reg64_reg64(buf, dst, src),
+            RegisterWidth::W8 => bfxil_reg64_reg64(buf, dst, src, 8),
+            RegisterWidth::W16 => bfxil_reg64_reg64(buf, dst, src, 16),
+            RegisterWidth::W32 => ubfx_reg64_reg64(buf, dst, src, 32),
+            RegisterWidth::W64 => {
+                if dst == AArch64GeneralReg::ZRSP || src == AArch64GeneralReg::ZRSP {
+                    // ORR treats register 31 as the zero register, ADD treats it as the stack pointer.
+                    add_reg64_reg64_imm12(buf, dst, src, 0);
+                } else {
+                    mov_reg64_reg64(buf, dst, src);
+                }
+            }
         }
     }
 
     #[inline(always)]
     fn movsx_reg_reg(
-        _buf: &mut Vec<'_, u8>,
-        _input_width: RegisterWidth,
-        _dst: AArch64GeneralReg,
-        _src: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        input_width: RegisterWidth,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
     ) {
-        todo!("move with sign extension");
+        match input_width {
+            RegisterWidth::W8 => sbfx_reg64_reg64(buf, dst, src, 8),
+            RegisterWidth::W16 => sbfx_reg64_reg64(buf, dst, src, 16),
+            RegisterWidth::W32 => sbfx_reg64_reg64(buf, dst, src, 32),
+            RegisterWidth::W64 => Self::mov_reg64_reg64(buf, dst, src),
+        }
     }
 
     #[inline(always)]
     fn movzx_reg_reg(
-        _buf: &mut Vec<'_, u8>,
-        _input_width: RegisterWidth,
-        _dst: AArch64GeneralReg,
-        _src: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        input_width: RegisterWidth,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
     ) {
-        todo!("move with zero extension");
+        match input_width {
+            RegisterWidth::W8 => ubfx_reg64_reg64(buf, dst, src, 8),
+            RegisterWidth::W16 => ubfx_reg64_reg64(buf, dst, src, 16),
+            RegisterWidth::W32 => ubfx_reg64_reg64(buf, dst, src, 32),
+            RegisterWidth::W64 => Self::mov_reg64_reg64(buf, dst, src),
+        }
     }
 
     #[inline(always)]
-    fn mov_freg64_base32(_buf: &mut Vec<'_, u8>, _dst: AArch64FloatReg, _offset: i32) {
-        todo!("loading floating point reg from base offset for AArch64");
+    fn mov_freg64_base32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, offset: i32) {
+        load_store_reg_base_offset(
+            buf,
+            LoadStoreKind::LDR_D,
+            dst.id(),
+            AArch64GeneralReg::FP,
+            offset,
+        );
     }
     #[inline(always)]
     fn mov_reg64_base32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
-        if offset < 0 {
-            todo!("negative base offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, AArch64GeneralReg::FP, (offset as u16) >> 3);
-        } else {
-            todo!("base offsets over 32k for AArch64");
-        }
+        Self::mov_reg64_mem64_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
-    fn mov_reg32_base32(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _offset: i32) {
-        todo!()
+    fn mov_reg32_base32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
+        Self::mov_reg32_mem32_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
-    fn mov_reg16_base32(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _offset: i32) {
-        todo!()
+    fn mov_reg16_base32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
+        Self::mov_reg16_mem16_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
-    fn mov_reg8_base32(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _offset: i32) {
-        todo!()
+    fn mov_reg8_base32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
+        Self::mov_reg8_mem8_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
-    fn mov_base32_freg64(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64FloatReg) {
-        todo!("saving floating point reg to base offset for AArch64");
+    fn mov_base32_freg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64FloatReg) {
+        Self::movesd_mem64_offset32_freg64(buf, AArch64GeneralReg::FP, offset, src);
     }
     #[inline(always)]
-    fn mov_base32_freg32(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64FloatReg) {
-        todo!("saving floating point reg to base offset for AArch64");
+    fn mov_base32_freg32(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64FloatReg) {
+        load_store_reg_base_offset(
+            buf,
+            LoadStoreKind::STR_S,
+            src.id(),
+            AArch64GeneralReg::FP,
+            offset,
+        );
     }
     #[inline(always)]
     fn movesd_mem64_offset32_freg64(
-        _buf: &mut Vec<'_, u8>,
-        _ptr: AArch64GeneralReg,
-        _offset: i32,
-        _src: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        ptr: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64FloatReg,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::STR_D, src.id(), ptr, offset);
     }
 
     #[inline(always)]
     fn mov_base32_reg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
-        if offset < 0 {
-            todo!("negative base offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            str_reg64_reg64_imm12(buf, src, AArch64GeneralReg::FP, (offset as u16) >> 3);
-        } else {
-            todo!("base offsets over 32k for AArch64");
-        }
+        Self::mov_mem64_offset32_reg64(buf, AArch64GeneralReg::FP, offset, src);
     }
 
     #[inline(always)]
-    fn mov_base32_reg32(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64GeneralReg) {
-        todo!()
+    fn mov_base32_reg32(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
+        Self::mov_mem32_offset32_reg32(buf, AArch64GeneralReg::FP, offset, src);
     }
     #[inline(always)]
-    fn mov_base32_reg16(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64GeneralReg) {
-        todo!()
+    fn mov_base32_reg16(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
+        Self::mov_mem16_offset32_reg16(buf, AArch64GeneralReg::FP, offset, src);
     }
     #[inline(always)]
-    fn mov_base32_reg8(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64GeneralReg) {
-        todo!()
+    fn mov_base32_reg8(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
+        Self::mov_mem8_offset32_reg8(buf, AArch64GeneralReg::FP, offset, src);
     }
 
     #[inline(always)]
@@ -899,14 +1668,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         offset: i32,
     ) {
-        if offset < 0 {
-            todo!("negative mem offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, src, (offset as u16) >> 3);
-        } else {
-            todo!("mem offsets over 32k for AArch64");
-        }
+        load_store_reg_base_offset(buf, LoadStoreKind::LDR_X, dst.id(), src, offset);
     }
     #[inline(always)]
     fn mov_reg32_mem32_offset32(
@@ -915,32 +1677,25 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         offset: i32,
     ) {
-        if offset < 0 {
-            todo!("negative mem offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, src, (offset as u16) >> 3);
-        } else {
-            todo!("mem offsets over 32k for AArch64");
-        }
+        load_store_reg_base_offset(buf, LoadStoreKind::LDR_W, dst.id(), src, offset);
     }
     #[inline(always)]
     fn mov_reg16_mem16_offset32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src: AArch64GeneralReg,
-        _offset: i32,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
+        offset: i32,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::LDRH, dst.id(), src, offset);
     }
     #[inline(always)]
     fn mov_reg8_mem8_offset32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src: AArch64GeneralReg,
-        _offset: i32,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
+        offset: i32,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::LDRB, dst.id(), src, offset);
     }
 
     #[inline(always)]
@@ -950,44 +1705,37 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         offset: i32,
         src: AArch64GeneralReg,
     ) {
-        if offset < 0 {
-            todo!("negative mem offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            str_reg64_reg64_imm12(buf, src, dst, (offset as u16) >> 3);
-        } else {
-            todo!("mem offsets over 32k for AArch64");
-        }
+        load_store_reg_base_offset(buf, LoadStoreKind::STR_X, src.id(), dst, offset);
     }
 
     #[inline(always)]
     fn mov_mem32_offset32_reg32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _offset: i32,
-        _src: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64GeneralReg,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::STR_W, src.id(), dst, offset);
     }
 
     #[inline(always)]
     fn mov_mem16_offset32_reg16(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _offset: i32,
-        _src: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64GeneralReg,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::STRH, src.id(), dst, offset);
     }
 
     #[inline(always)]
     fn mov_mem8_offset32_reg8(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _offset: i32,
-        _src: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64GeneralReg,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::STRB, src.id(), dst, offset);
     }
 
     #[inline(always)]
@@ -997,12 +1745,13 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         dst: AArch64GeneralReg,
         offset: i32,
     ) {
-        match register_width {
-            RegisterWidth::W8 => todo!("sign extend 1 byte values"),
-            RegisterWidth::W16 => todo!("sign extend 2 byte values"),
-            RegisterWidth::W32 => todo!("sign extend 4 byte values"),
-            RegisterWidth::W64 => Self::mov_reg64_base32(buf, dst, offset),
-        }
+        let kind = match register_width {
+            RegisterWidth::W8 => LoadStoreKind::LDRSB,
+            RegisterWidth::W16 => LoadStoreKind::LDRSH,
+            RegisterWidth::W32 => LoadStoreKind::LDRSW,
+            RegisterWidth::W64 => LoadStoreKind::LDR_X,
+        };
+        load_store_reg_base_offset(buf, kind, dst.id(), AArch64GeneralReg::FP, offset);
     }
 
     #[inline(always)]
@@ -1012,32 +1761,27 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         dst: AArch64GeneralReg,
         offset: i32,
     ) {
-        match register_width {
-            RegisterWidth::W8 => todo!("zero extend 1 byte values"),
-            RegisterWidth::W16 => todo!("zero extend 2 byte values"),
-            RegisterWidth::W32 => todo!("zero extend 4 byte values"),
-            RegisterWidth::W64 => Self::mov_reg64_base32(buf, dst, offset),
-        }
+        // Loads into a W register clear the upper 32 bits.
+        let kind = match register_width {
+            RegisterWidth::W8 => LoadStoreKind::LDRB,
+            RegisterWidth::W16 => LoadStoreKind::LDRH,
+            RegisterWidth::W32 => LoadStoreKind::LDR_W,
+            RegisterWidth::W64 => LoadStoreKind::LDR_X,
+        };
+        load_store_reg_base_offset(buf, kind, dst.id(), AArch64GeneralReg::FP, offset);
     }
 
     #[inline(always)]
-    fn mov_freg64_stack32(_buf: &mut Vec<'_, u8>, _dst: AArch64FloatReg, _offset: i32) {
-        todo!("loading floating point reg from stack for AArch64");
+    fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, offset: i32) {
+        Self::mov_freg64_mem64_offset32(buf, dst, AArch64GeneralReg::ZRSP, offset);
     }
     #[inline(always)]
     fn mov_reg64_stack32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
-        if offset < 0 {
-            todo!("negative stack offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, AArch64GeneralReg::ZRSP, (offset as u16) >> 3);
-        } else {
-            todo!("stack offsets over 32k for AArch64");
-        }
+        Self::mov_reg64_mem64_offset32(buf, dst, AArch64GeneralReg::ZRSP, offset);
     }
     #[inline(always)]
-    fn mov_stack32_freg64(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64FloatReg) {
-        todo!("saving floating point reg to stack for AArch64");
+    fn mov_stack32_freg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64FloatReg) {
+        Self::movesd_mem64_offset32_freg64(buf, AArch64GeneralReg::ZRSP, offset, src);
     }
     #[inline(always)]
     fn mov_stack32_reg(
@@ -1046,21 +1790,13 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         offset: i32,
         src: AArch64GeneralReg,
     ) {
-        match register_width {
-            RegisterWidth::W8 => todo!(),
-            RegisterWidth::W16 => todo!(),
-            RegisterWidth::W32 => todo!(),
-            RegisterWidth::W64 => {
-                if offset < 0 {
-                    todo!("negative stack offsets for AArch64");
-                } else if offset < (0xFFF << 8) {
-                    debug_assert!(offset % 8 == 0);
-                    str_reg64_reg64_imm12(buf, src, AArch64GeneralReg::ZRSP, (offset as u16) >> 3);
-                } else {
-                    todo!("stack offsets over 32k for AArch64");
-                }
-            }
-        }
+        let kind = match register_width {
+            RegisterWidth::W8 => LoadStoreKind::STRB,
+            RegisterWidth::W16 => LoadStoreKind::STRH,
+            RegisterWidth::W32 => LoadStoreKind::STR_W,
+            RegisterWidth::W64 => LoadStoreKind::STR_X,
+        };
+        load_store_reg_base_offset(buf, kind, src.id(), AArch64GeneralReg::ZRSP, offset);
     }
     #[inline(always)]
     fn neg_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
@@ -1074,13 +1810,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         imm32: i32,
     ) {
-        if imm32 < 0 {
-            todo!("immediate subtractions with values less than 0");
-        } else if imm32 < 0xFFF {
-            sub_reg64_reg64_imm12(buf, dst, src, imm32 as u16);
-        } else {
-            todo!("immediate subtractions with values greater than 12bits");
-        }
+        Self::add_or_sub_reg64_reg64_imm32(buf, true, dst, src, imm32);
     }
     #[inline(always)]
     fn sub_reg64_reg64_reg64(
@@ -1095,24 +1825,24 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     #[inline(always)]
     fn eq_reg_reg_reg(
         buf: &mut Vec<'_, u8>,
-        _register_width: RegisterWidth,
+        register_width: RegisterWidth,
         dst: AArch64GeneralReg,
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        cmp_reg64_reg64(buf, src1, src2);
+        Self::cmp_reg_reg(buf, register_width, false, src1, src2);
         cset_reg64_cond(buf, dst, ConditionCode::EQ);
     }
 
     #[inline(always)]
     fn neq_reg_reg_reg(
         buf: &mut Vec<'_, u8>,
-        _register_width: RegisterWidth,
+        register_width: RegisterWidth,
         dst: AArch64GeneralReg,
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        cmp_reg64_reg64(buf, src1, src2);
+        Self::cmp_reg_reg(buf, register_width, false, src1, src2);
         cset_reg64_cond(buf, dst, ConditionCode::NE);
     }
 
@@ -1189,8 +1919,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         fcvt_freg64_freg32(buf, dst, src);
     }
 
-    fn set_if_overflow(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg) {
-        todo!("set if overflow for AArch64");
+    fn set_if_overflow(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg) {
+        cset_reg64_cond(buf, dst, ConditionCode::VS);
     }
 
     #[inline(always)]
@@ -1274,13 +2004,13 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
 
     fn signed_compare_reg64(
         buf: &mut Vec<'_, u8>,
-        _register_width: RegisterWidth,
+        register_width: RegisterWidth,
         operation: CompareOperation,
         dst: AArch64GeneralReg,
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        cmp_reg64_reg64(buf, src1, src2);
+        Self::cmp_reg_reg(buf, register_width, true, src1, src2);
         let cond = match operation {
             CompareOperation::LessThan => ConditionCode::LT,
             CompareOperation::LessThanOrEqual => ConditionCode::LE,
@@ -1292,13 +2022,13 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
 
     fn unsigned_compare_reg64(
         buf: &mut Vec<'_, u8>,
-        _register_width: RegisterWidth,
+        register_width: RegisterWidth,
         operation: CompareOperation,
         dst: AArch64GeneralReg,
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        cmp_reg64_reg64(buf, src1, src2);
+        Self::cmp_reg_reg(buf, register_width, false, src1, src2);
         let cond = match operation {
             CompareOperation::LessThan => ConditionCode::CCLO,
             CompareOperation::LessThanOrEqual => ConditionCode::LS,
@@ -1309,25 +2039,96 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     fn mov_freg64_mem64_offset32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64GeneralReg,
-        _offset: i32,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src: AArch64GeneralReg,
+        offset: i32,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::LDR_D, dst.id(), src, offset);
     }
 
     fn mov_freg32_mem32_offset32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64GeneralReg,
-        _offset: i32,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src: AArch64GeneralReg,
+        offset: i32,
     ) {
-        todo!()
+        load_store_reg_base_offset(buf, LoadStoreKind::LDR_S, dst.id(), src, offset);
     }
 }
 
-impl AArch64Assembler {}
+impl AArch64Assembler {
+    /// Adds or subtracts an immediate of any size, splitting it up when it does not fit in 12 bits.
+    /// Immediates over 24 bits are loaded into IP1 first.
+    fn add_or_sub_reg64_reg64_imm32(
+        buf: &mut Vec<'_, u8>,
+        subtract: bool,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
+        imm32: i32,
+    ) {
+        // Adding a negative number is the same as subtracting a positive one.
+        let subtract = subtract != (imm32 < 0);
+        let imm = imm32.unsigned_abs();
+
+        let mut arithmetic_imm12 = |rn, imm12, sh| {
+            let inst = ArithmeticImmediate::new(ArithmeticImmediateParams {
+                op: subtract,
+                s: false,
+                rd: dst,
+                rn,
+                imm12,
+                sh,
+            });
+            buf.extend(inst.bytes());
+        };
+
+        if imm <= 0xFFF {
+            arithmetic_imm12(src, imm as u16, false);
+        } else if imm <= 0xFF_FFFF {
+            arithmetic_imm12(src, (imm >> 12) as u16, true);
+            if imm & 0xFFF != 0 {
+                arithmetic_imm12(dst, (imm & 0xFFF) as u16, false);
+            }
+        } else {
+            debug_assert_ne!(src, ADDRESS_TMP_REG);
+            Self::mov_reg64_imm64(buf, ADDRESS_TMP_REG, imm as i64);
+            if subtract {
+                sub_reg64_reg64_reg64_extended(buf, dst, src, ADDRESS_TMP_REG);
+            } else {
+                add_reg64_reg64_reg64_extended(buf, dst, src, ADDRESS_TMP_REG);
+            }
+        }
+    }
+
+    /// Compares the lower `register_width` bits of two registers.
+    /// Narrow values are extended into the scratch registers first, since their upper bits may be garbage.
+    fn cmp_reg_reg(
+        buf: &mut Vec<'_, u8>,
+        register_width: RegisterWidth,
+        signed: bool,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
+    ) {
+        let width = match register_width {
+            RegisterWidth::W8 => 8,
+            RegisterWidth::W16 => 16,
+            RegisterWidth::W32 => 32,
+            RegisterWidth::W64 => {
+                cmp_reg64_reg64(buf, src1, src2);
+                return;
+            }
+        };
+        let extend = if signed {
+            sbfx_reg64_reg64
+        } else {
+            ubfx_reg64_reg64
+        };
+        extend(buf, TMP_REG, src1, width);
+        extend(buf, ADDRESS_TMP_REG, src2, width);
+        cmp_reg64_reg64(buf, TMP_REG, ADDRESS_TMP_REG);
+    }
+}
 
 // Instructions
 // ARM manual section C3
@@ -1524,6 +2325,56 @@ impl ArithmeticShifted {
     }
 }
 
+// Always uses UXTX without a shift, which is the same as a plain 64 bit register.
+// Unlike the shifted register form, register 31 means SP for Xd and Xn.
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct ArithmeticExtendedRegister {
+    sf: bool,
+    op: bool, // add or subtract
+    s: bool,
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b01011,
+    opt: Integer<u8, packed_bits::Bits<2>>,   // = 0b00,
+    fixed2: bool,                             // = 0b1,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    option: Integer<u8, packed_bits::Bits<3>>,
+    imm3: Integer<u8, packed_bits::Bits<3>>,
+    reg_n: Integer<u8, packed_bits::Bits<5>>,
+    reg_d: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for ArithmeticExtendedRegister {}
+
+pub struct ArithmeticExtendedRegisterParams {
+    op: bool,
+    s: bool,
+    rm: AArch64GeneralReg,
+    rn: AArch64GeneralReg,
+    rd: AArch64GeneralReg,
+}
+
+impl ArithmeticExtendedRegister {
+    #[inline(always)]
+    fn new(
+        ArithmeticExtendedRegisterParams { op, s, rm, rn, rd }: ArithmeticExtendedRegisterParams,
+    ) -> Self {
+        Self {
+            reg_d: rd.id().into(),
+            reg_n: rn.id().into(),
+            imm3: 0b000.into(),
+            // UXTX
+            option: 0b011.into(),
+            reg_m: rm.id().into(),
+            fixed2: true,
+            opt: 0b00.into(),
+            fixed: 0b01011.into(),
+            s,
+            op,
+            sf: true,
+        }
+    }
+}
+
 // ARM manual section C1.2.4
 #[derive(Copy, Clone, PartialEq)]
 #[allow(dead_code)]
@@ -1747,6 +2598,91 @@ impl DataProcessingTwoSource {
     }
 }
 
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct Bitfield {
+    sf: bool,
+    opc: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<6>>, // = 0b100110,
+    n: bool,
+    immr: Integer<u8, packed_bits::Bits<6>>,
+    imms: Integer<u8, packed_bits::Bits<6>>,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rd: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for Bitfield {}
+
+pub struct BitfieldParams {
+    opc: u8,
+    immr: u8,
+    imms: u8,
+    rn: AArch64GeneralReg,
+    rd: AArch64GeneralReg,
+}
+
+impl Bitfield {
+    #[inline(always)]
+    fn new(
+        BitfieldParams {
+            opc,
+            immr,
+            imms,
+            rn,
+            rd,
+        }: BitfieldParams,
+    ) -> Self {
+        debug_assert!(opc <= 0b11);
+        debug_assert!(immr <= 0b111111);
+        debug_assert!(imms <= 0b111111);
+
+        Self {
+            // 64 bit registers require N to be set
+            sf: true,
+            n: true,
+            opc: opc.into(),
+            fixed: 0b100110.into(),
+            immr: immr.into(),
+            imms: imms.into(),
+            rn: rn.id().into(),
+            rd: rd.id().into(),
+        }
+    }
+}
+
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct PcRelativeAddressing {
+    op: bool, // false=ADR, true=ADRP
+    immlo: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b10000,
+    immhi: Integer<u32, packed_bits::Bits<19>>,
+    rd: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for PcRelativeAddressing {}
+
+pub struct PcRelativeAddressingParams {
+    op: bool,
+    imm21: u32,
+    rd: AArch64GeneralReg,
+}
+
+impl PcRelativeAddressing {
+    #[inline(always)]
+    fn new(PcRelativeAddressingParams { op, imm21, rd }: PcRelativeAddressingParams) -> Self {
+        debug_assert!(imm21 <= 0x1F_FFFF);
+
+        Self {
+            op,
+            immlo: ((imm21 & 0b11) as u8).into(),
+            fixed: 0b10000.into(),
+            immhi: (imm21 >> 2).into(),
+            rd: rd.id().into(),
+        }
+    }
+}
+
 #[derive(PackedStruct)]
 #[packed_struct(endian = "msb")]
 pub struct DataProcessingThreeSource {
@@ -1765,6 +2701,7 @@ impl Aarch64Bytes for DataProcessingThreeSource {}
 
 pub struct DataProcessingThreeSourceParams {
     op31: u8,
+    o0: bool,
     rm: AArch64GeneralReg,
     ra: AArch64GeneralReg,
     rn: AArch64GeneralReg,
@@ -1776,6 +2713,7 @@ impl DataProcessingThreeSource {
     fn new(
         DataProcessingThreeSourceParams {
             op31,
+            o0,
             rm,
             ra,
             rn,
@@ -1790,7 +2728,7 @@ impl DataProcessingThreeSource {
             fixed: 0b011011.into(),
             op31: op31.into(),
             rm: rm.id().into(),
-            o0: false,
+            o0,
             ra: ra.id().into(),
             rn: rn.id().into(),
             rd: rd.id().into(),
@@ -2009,6 +2947,165 @@ impl LoadStoreRegisterImmediate {
     fn new_store(params: LoadStoreRegisterImmediateParams) -> Self {
         Self::new(0b00, params)
     }
+
+    #[inline(always)]
+    fn new_with_kind(kind: LoadStoreKind, imm12: u16, rn: AArch64GeneralReg, rt: u8) -> Self {
+        debug_assert!(imm12 <= 0xFFF);
+
+        Self {
+            rt: rt.into(),
+            rn: rn.id().into(),
+            imm12: imm12.into(),
+            opc: kind.opc.into(),
+            fixed3: 0b01.into(),
+            fixed2: kind.v,
+            fixed: 0b111.into(),
+            size: kind.size.into(),
+        }
+    }
+}
+
+/// The size, register file, and direction of a load or store.
+/// These are shared between all the addressing modes.
+#[derive(Clone, Copy)]
+struct LoadStoreKind {
+    size: u8,
+    v: bool,
+    opc: u8,
+}
+
+impl LoadStoreKind {
+    const STR_X: Self = Self::new(0b11, false, 0b00);
+    const LDR_X: Self = Self::new(0b11, false, 0b01);
+    const STR_W: Self = Self::new(0b10, false, 0b00);
+    const LDR_W: Self = Self::new(0b10, false, 0b01);
+    const LDRSW: Self = Self::new(0b10, false, 0b10);
+    const STRH: Self = Self::new(0b01, false, 0b00);
+    const LDRH: Self = Self::new(0b01, false, 0b01);
+    const LDRSH: Self = Self::new(0b01, false, 0b10);
+    const STRB: Self = Self::new(0b00, false, 0b00);
+    const LDRB: Self = Self::new(0b00, false, 0b01);
+    const LDRSB: Self = Self::new(0b00, false, 0b10);
+    const STR_D: Self = Self::new(0b11, true, 0b00);
+    const LDR_D: Self = Self::new(0b11, true, 0b01);
+    const STR_S: Self = Self::new(0b10, true, 0b00);
+    const LDR_S: Self = Self::new(0b10, true, 0b01);
+
+    const fn new(size: u8, v: bool, opc: u8) -> Self {
+        Self { size, v, opc }
+    }
+
+    /// The number of bytes loaded or stored, which is also the scale of unsigned offsets.
+    const fn bytes(&self) -> i32 {
+        1 << self.size
+    }
+}
+
+// Uses an unscaled signed offset (LDUR/STUR)
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct LoadStoreRegisterUnscaledImmediate {
+    size: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<3>>, // = 0b111,
+    v: bool,
+    fixed2: Integer<u8, packed_bits::Bits<2>>, // = 0b00,
+    opc: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b0,
+    imm9: Integer<u16, packed_bits::Bits<9>>,
+    fixed4: Integer<u8, packed_bits::Bits<2>>, // = 0b00,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rt: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for LoadStoreRegisterUnscaledImmediate {}
+
+impl LoadStoreRegisterUnscaledImmediate {
+    #[inline(always)]
+    fn new(kind: LoadStoreKind, imm9: i16, rn: AArch64GeneralReg, rt: u8) -> Self {
+        debug_assert!((-256..256).contains(&imm9));
+
+        Self {
+            rt: rt.into(),
+            rn: rn.id().into(),
+            fixed4: 0b00.into(),
+            imm9: ((imm9 as u16) & 0x1FF).into(),
+            fixed3: false,
+            opc: kind.opc.into(),
+            fixed2: 0b00.into(),
+            v: kind.v,
+            fixed: 0b111.into(),
+            size: kind.size.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+enum PairIndexing {
+    PostIndex = 0b01,
+    SignedOffset = 0b10,
+    PreIndex = 0b11,
+}
+
+// Loads and stores two 64 bit registers.
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct LoadStoreRegisterPair {
+    opc: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<3>>, // = 0b101,
+    v: bool,
+    fixed2: bool, // = 0b0,
+    indexing: Integer<u8, packed_bits::Bits<2>>,
+    l: bool, // false=store, true=load
+    imm7: Integer<u8, packed_bits::Bits<7>>,
+    rt2: Integer<u8, packed_bits::Bits<5>>,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rt: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for LoadStoreRegisterPair {}
+
+pub struct LoadStoreRegisterPairParams {
+    v: bool,
+    indexing: PairIndexing,
+    l: bool,
+    offset: i32,
+    rt2: u8,
+    rn: AArch64GeneralReg,
+    rt: u8,
+}
+
+impl LoadStoreRegisterPair {
+    #[inline(always)]
+    fn new(
+        LoadStoreRegisterPairParams {
+            v,
+            indexing,
+            l,
+            offset,
+            rt2,
+            rn,
+            rt,
+        }: LoadStoreRegisterPairParams,
+    ) -> Self {
+        // The offset is scaled by the size of a register.
+        debug_assert!(offset % 8 == 0);
+        debug_assert!((-512..512).contains(&offset));
+
+        Self {
+            rt: rt.into(),
+            rn: rn.id().into(),
+            rt2: rt2.into(),
+            imm7: (((offset >> 3) as u8) & 0x7F).into(),
+            l,
+            indexing: (indexing as u8).into(),
+            fixed2: false,
+            v,
+            fixed: 0b101.into(),
+            // 64 bit registers are 0b10 for general registers and 0b01 for float registers
+            opc: if v { 0b01.into() } else { 0b10.into() },
+        }
+    }
 }
 
 #[derive(PackedStruct)]
@@ -2083,18 +3180,21 @@ pub struct ConversionBetweenFloatingPointAndInteger {
 
 impl Aarch64Bytes for ConversionBetweenFloatingPointAndInteger {}
 
+// rn and rd are register ids, since which of them is the float register depends on the opcode.
 pub struct ConversionBetweenFloatingPointAndIntegerParams {
+    sf: bool,
     ptype: FloatWidth,
     rmode: u8,
     opcode: u8,
-    rn: AArch64GeneralReg,
-    rd: AArch64FloatReg,
+    rn: u8,
+    rd: u8,
 }
 
 impl ConversionBetweenFloatingPointAndInteger {
     #[inline(always)]
     fn new(
         ConversionBetweenFloatingPointAndIntegerParams {
+            sf,
             ptype,
             rmode,
             opcode,
@@ -2106,7 +3206,7 @@ impl ConversionBetweenFloatingPointAndInteger {
         debug_assert!(opcode <= 0b111);
 
         Self {
-            sf: true,
+            sf,
             fixed: false,
             s: false,
             fixed2: 0b11110.into(),
@@ -2115,8 +3215,8 @@ impl ConversionBetweenFloatingPointAndInteger {
             rmode: rmode.into(),
             opcode: opcode.into(),
             fixed4: 0b000000.into(),
-            rn: rn.id().into(),
-            rd: rd.id().into(),
+            rn: rn.into(),
+            rd: rd.into(),
         }
     }
 }
@@ -2350,9 +3450,50 @@ fn add_reg64_reg64_imm12(
     buf.extend(inst.bytes());
 }
 
-/// `ADD Xd, Xm, Xn` -> Add Xm and Xn and place the result into Xd.
+/// `ADD Xd, Xm, Xn` -> Add Xm and Xn and place the result into Xd.
+#[inline(always)]
+fn add_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticShifted::new(ArithmeticShiftedParams {
+        op: false,
+        s: false,
+        shift: ShiftType::LSL,
+        imm6: 0,
+        rm: src2,
+        rn: src1,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADD Xd|SP, Xn|SP, Xm` -> Add Xn and Xm and place the result into Xd.
+/// Unlike `add_reg64_reg64_reg64`, ZRSP is SP for Xd and Xn.
+#[inline(always)]
+fn add_reg64_reg64_reg64_extended(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticExtendedRegister::new(ArithmeticExtendedRegisterParams {
+        op: false,
+        s: false,
+        rm: src2,
+        rn: src1,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADDS Xd, Xm, Xn` -> Add Xm and Xn, place the result into Xd, and set condition flags.
 #[inline(always)]
-fn add_reg64_reg64_reg64(
+fn adds_reg64_reg64_reg64(
     buf: &mut Vec<'_, u8>,
     dst: AArch64GeneralReg,
     src1: AArch64GeneralReg,
@@ -2360,7 +3501,7 @@ fn add_reg64_reg64_reg64(
 ) {
     let inst = ArithmeticShifted::new(ArithmeticShiftedParams {
         op: false,
-        s: false,
+        s: true,
         shift: ShiftType::LSL,
         imm6: 0,
         rm: src2,
@@ -2371,6 +3512,21 @@ fn add_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `ADRP Xd, #offset` -> Place the address of the 4KB page at PC + offset into Xd.
+/// Note: the offset must be a multiple of 4KB.
+#[inline(always)]
+fn adrp_reg64_imm32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
+    debug_assert!(offset & 0xFFF == 0, "page offset must be 4KB aligned");
+
+    let inst = PcRelativeAddressing::new(PcRelativeAddressingParams {
+        op: true,
+        imm21: ((offset >> 12) as u32) & 0x1F_FFFF,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `AND Xd, Xn, Xm` -> Bitwise AND Xn and Xm and place the result into Xd.
 #[inline(always)]
 fn and_reg64_reg64_reg64(
@@ -2409,6 +3565,27 @@ fn asr_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `BFXIL Xd, Xn, #0, #width` -> Copy the lower width bits of Xn into Xd, keeping the other bits of Xd.
+#[inline(always)]
+fn bfxil_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    width: u8,
+) {
+    debug_assert!((1..=64).contains(&width));
+
+    let inst = Bitfield::new(BitfieldParams {
+        opc: 0b01,
+        immr: 0,
+        imms: width - 1,
+        rn: src,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `B.cond imm19` -> Jump to PC + imm19 if cond is met.
 #[inline(always)]
 fn b_cond_imm19(buf: &mut Vec<'_, u8>, cond: ConditionCode, imm19: i32) {
@@ -2438,6 +3615,27 @@ fn b_cond_imm19(buf: &mut Vec<'_, u8>, cond: ConditionCode, imm19: i32) {
 /// `B imm26` -> Jump to PC + imm26.
 #[inline(always)]
 fn b_imm26(buf: &mut Vec<'_, u8>, imm26: i32) {
+    unconditional_branch_imm26(buf, false, imm26);
+}
+
+/// `BL imm26` -> Jump to PC + imm26, placing the return address into LR.
+#[inline(always)]
+fn bl_imm26(buf: &mut Vec<'_, u8>, imm26: i32) {
+    unconditional_branch_imm26(buf, true, imm26);
+}
+
+/// `BR Xn` -> Jump to the address stored in Xn.
+#[inline(always)]
+fn br_reg64(buf: &mut Vec<'_, u8>, xn: AArch64GeneralReg) {
+    let inst =
+        UnconditionalBranchRegister::new(UnconditionalBranchRegisterParams { op: 0b00, rn: xn });
+
+    buf.extend(inst.bytes());
+}
+
+/// Shared encoding of `B imm26` and `BL imm26`.
+#[inline(always)]
+fn unconditional_branch_imm26(buf: &mut Vec<'_, u8>, link: bool, imm26: i32) {
     // Since instructions are 4 bytes, the branch instructions assume the last 2 bits are 0
     debug_assert!(imm26 & 0b11 == 0, "branch location must be 4-byte aligned");
     let shifted = imm26 >> 2;
@@ -2454,7 +3652,7 @@ fn b_imm26(buf: &mut Vec<'_, u8>, imm26: i32) {
     }
 
     let inst = UnconditionalBranchImmediate::new(UnconditionalBranchImmediateParams {
-        op: false,
+        op: link,
         imm26: left_removed,
     });
 
@@ -2560,6 +3758,50 @@ fn eor_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `LDP Xt1, Xt2, [Xn, #offset]` -> Load Xn + Offset into Xt1 and Xn + Offset + 8 into Xt2. ZRSP is SP.
+#[inline(always)]
+fn ldp_reg64_reg64_offset(
+    buf: &mut Vec<'_, u8>,
+    dst1: AArch64GeneralReg,
+    dst2: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: false,
+        indexing: PairIndexing::SignedOffset,
+        l: true,
+        offset,
+        rt2: dst2.id(),
+        rn: base,
+        rt: dst1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDP Xt1, Xt2, [Xn], #offset` -> Load Xn into Xt1 and Xn + 8 into Xt2, then add offset to Xn. ZRSP is SP.
+#[inline(always)]
+fn ldp_reg64_reg64_post_index(
+    buf: &mut Vec<'_, u8>,
+    dst1: AArch64GeneralReg,
+    dst2: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: false,
+        indexing: PairIndexing::PostIndex,
+        l: true,
+        offset,
+        rt2: dst2.id(),
+        rn: base,
+        rt: dst1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `LDR Xt, [Xn, #offset]` -> Load Xn + Offset Xt. ZRSP is SP.
 /// Note: imm12 is the offest divided by 8.
 #[inline(always)]
@@ -2579,6 +3821,32 @@ fn ldr_reg64_reg64_imm12(
     buf.extend(inst.bytes());
 }
 
+/// Load or store the register with id `rt` at `base + offset`. ZRSP is SP.
+/// Uses a scaled unsigned offset if possible, then an unscaled signed one (LDUR/STUR).
+/// Any other offset is added to the base in IP1 first.
+#[inline(always)]
+fn load_store_reg_base_offset(
+    buf: &mut Vec<'_, u8>,
+    kind: LoadStoreKind,
+    rt: u8,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let scale = kind.bytes();
+    if offset >= 0 && offset % scale == 0 && offset / scale <= 0xFFF {
+        let inst =
+            LoadStoreRegisterImmediate::new_with_kind(kind, (offset / scale) as u16, base, rt);
+        buf.extend(inst.bytes());
+    } else if (-256..256).contains(&offset) {
+        let inst = LoadStoreRegisterUnscaledImmediate::new(kind, offset as i16, base, rt);
+        buf.extend(inst.bytes());
+    } else {
+        AArch64Assembler::add_reg64_reg64_imm32(buf, ADDRESS_TMP_REG, base, offset);
+        let inst = LoadStoreRegisterImmediate::new_with_kind(kind, 0, ADDRESS_TMP_REG, rt);
+        buf.extend(inst.bytes());
+    }
+}
+
 /// `LSL Xd, Xn, Xm` -> Logical shift Xn left by Xm and place the result into Xd.
 #[inline(always)]
 fn lsl_reg64_reg64_reg64(
@@ -2626,6 +3894,7 @@ fn madd_reg64_reg64_reg64_reg64(
 ) {
     let inst = DataProcessingThreeSource::new(DataProcessingThreeSourceParams {
         op31: 0b000000,
+        o0: false,
         rm: src2,
         ra: src3,
         rn: src1,
@@ -2670,6 +3939,27 @@ fn movz_reg64_imm16(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm16: u16, h
     buf.extend(inst.bytes());
 }
 
+/// `MSUB Xd, Xn, Xm, Xa` -> Multiply Xn and Xm, subtract the product from Xa, and place the result into Xd.
+#[inline(always)]
+fn msub_reg64_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+    src3: AArch64GeneralReg,
+) {
+    let inst = DataProcessingThreeSource::new(DataProcessingThreeSourceParams {
+        op31: 0b000000,
+        o0: true,
+        rm: src2,
+        ra: src3,
+        rn: src1,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `MUL Xd, Xn, Xm` -> Multiply Xn and Xm and place the result into Xd.
 #[inline(always)]
 fn mul_reg64_reg64_reg64(
@@ -2707,6 +3997,27 @@ fn orr_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `SBFX Xd, Xn, #0, #width` -> Sign extend the lower width bits of Xn and place the result into Xd.
+#[inline(always)]
+fn sbfx_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    width: u8,
+) {
+    debug_assert!((1..=64).contains(&width));
+
+    let inst = Bitfield::new(BitfieldParams {
+        opc: 0b00,
+        immr: 0,
+        imms: width - 1,
+        rn: src,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `SDIV Xd, Xn, Xm` -> Divide Xn by Xm and place the result into Xd.
 /// Xn, Xm, and Xd are signed integers.
 #[inline(always)]
@@ -2726,6 +4037,50 @@ fn sdiv_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `STP Xt1, Xt2, [Xn, #offset]` -> Store Xt1 to Xn + Offset and Xt2 to Xn + Offset + 8. ZRSP is SP.
+#[inline(always)]
+fn stp_reg64_reg64_offset(
+    buf: &mut Vec<'_, u8>,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: false,
+        indexing: PairIndexing::SignedOffset,
+        l: false,
+        offset,
+        rt2: src2.id(),
+        rn: base,
+        rt: src1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
+/// `STP Xt1, Xt2, [Xn, #offset]!` -> Add offset to Xn, then store Xt1 to Xn and Xt2 to Xn + 8. ZRSP is SP.
+#[inline(always)]
+fn stp_reg64_reg64_pre_index(
+    buf: &mut Vec<'_, u8>,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: false,
+        indexing: PairIndexing::PreIndex,
+        l: false,
+        offset,
+        rt2: src2.id(),
+        rn: base,
+        rt: src1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `STR Xt, [Xn, #offset]` -> Store Xt to Xn + Offset. ZRSP is SP.
 /// Note: imm12 is the offest divided by 8.
 #[inline(always)]
@@ -2786,6 +4141,26 @@ fn sub_reg64_reg64_reg64(
     buf.extend(inst.bytes());
 }
 
+/// `SUB Xd|SP, Xn|SP, Xm` -> Subtract Xm from Xn and place the result into Xd.
+/// Unlike `sub_reg64_reg64_reg64`, ZRSP is SP for Xd and Xn.
+#[inline(always)]
+fn sub_reg64_reg64_reg64_extended(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticExtendedRegister::new(ArithmeticExtendedRegisterParams {
+        op: true,
+        s: false,
+        rm: src2,
+        rn: src1,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `SUBS Xd, Xn, imm12` -> Subtract Xn and imm12 and place the result into Xd. Set condition flags.
 #[inline(always)]
 fn subs_reg64_reg64_imm12(
@@ -2836,6 +4211,27 @@ fn ret_reg64(buf: &mut Vec<'_, u8>, xn: AArch64GeneralReg) {
     buf.extend(inst.bytes());
 }
 
+/// `UBFX Xd, Xn, #0, #width` -> Zero extend the lower width bits of Xn and place the result into Xd.
+#[inline(always)]
+fn ubfx_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    width: u8,
+) {
+    debug_assert!((1..=64).contains(&width));
+
+    let inst = Bitfield::new(BitfieldParams {
+        opc: 0b10,
+        immr: 0,
+        imms: width - 1,
+        rn: src,
+        rd: dst,
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `UDIV Xd, Xn, Xm` -> Divide Xn by Xm and place the result into Xd.
 /// Xn, Xm, and Xd are unsigned integers.
 #[inline(always)]
@@ -3091,6 +4487,50 @@ fn fmov_freg_imm8(buf: &mut Vec<'_, u8>, ftype: FloatWidth, dst: AArch64FloatReg
     buf.extend(inst.bytes());
 }
 
+/// `FMOV Sd/Dd, Wn/Xn` -> Move the bits of Wn/Xn into Sd/Dd without conversion.
+#[inline(always)]
+fn fmov_freg_reg(
+    buf: &mut Vec<'_, u8>,
+    ftype: FloatWidth,
+    dst: AArch64FloatReg,
+    src: AArch64GeneralReg,
+) {
+    let inst = ConversionBetweenFloatingPointAndInteger::new(
+        ConversionBetweenFloatingPointAndIntegerParams {
+            sf: ftype == FloatWidth::F64,
+            opcode: 0b111,
+            rmode: 0b00,
+            ptype: ftype,
+            rd: dst.id(),
+            rn: src.id(),
+        },
+    );
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMOV Wd/Xd, Sn/Dn` -> Move the bits of Sn/Dn into Wd/Xd without conversion.
+#[inline(always)]
+fn fmov_reg_freg(
+    buf: &mut Vec<'_, u8>,
+    ftype: FloatWidth,
+    dst: AArch64GeneralReg,
+    src: AArch64FloatReg,
+) {
+    let inst = ConversionBetweenFloatingPointAndInteger::new(
+        ConversionBetweenFloatingPointAndIntegerParams {
+            sf: ftype == FloatWidth::F64,
+            opcode: 0b110,
+            rmode: 0b00,
+            ptype: ftype,
+            rd: dst.id(),
+            rn: src.id(),
+        },
+    );
+
+    buf.extend(inst.bytes());
+}
+
 /// `FMUL Sd/Dd, Sn/Dn, Sm/Dm` -> Multiply Sn/Dn by Sm/Dm and store the result in Sd/Dd.
 #[inline(always)]
 fn fmul_freg_freg_freg(
@@ -3132,6 +4572,28 @@ fn fsqrt_freg_freg(
 }
 
 /// Currently, we're only using MOVI to set a float register to 0.0.
+/// `LDP Dt1, Dt2, [Xn, #offset]` -> Load Xn + Offset into Dt1 and Xn + Offset + 8 into Dt2. ZRSP is SP.
+#[inline(always)]
+fn ldp_freg64_freg64_offset(
+    buf: &mut Vec<'_, u8>,
+    dst1: AArch64FloatReg,
+    dst2: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: true,
+        indexing: PairIndexing::SignedOffset,
+        l: true,
+        offset,
+        rt2: dst2.id(),
+        rn: base,
+        rt: dst1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
 /// `MOVI Dd, #0.0` -> Move 0.0 to Dd
 #[inline(always)]
 fn movi_freg_zero(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg) {
@@ -3150,17 +4612,40 @@ fn scvtf_freg_reg64(
 ) {
     let inst = ConversionBetweenFloatingPointAndInteger::new(
         ConversionBetweenFloatingPointAndIntegerParams {
+            sf: true,
             opcode: 0b010,
             rmode: 0b00,
             ptype: ftype,
-            rd: dst,
-            rn: src,
+            rd: dst.id(),
+            rn: src.id(),
         },
     );
 
     buf.extend(inst.bytes());
 }
 
+/// `STP Dt1, Dt2, [Xn, #offset]` -> Store Dt1 to Xn + Offset and Dt2 to Xn + Offset + 8. ZRSP is SP.
+#[inline(always)]
+fn stp_freg64_freg64_offset(
+    buf: &mut Vec<'_, u8>,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    offset: i32,
+) {
+    let inst = LoadStoreRegisterPair::new(LoadStoreRegisterPairParams {
+        v: true,
+        indexing: PairIndexing::SignedOffset,
+        l: false,
+        offset,
+        rt2: src2.id(),
+        rn: base,
+        rt: src1.id(),
+    });
+
+    buf.extend(inst.bytes());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3329,18 +4814,120 @@ mod tests {
     }
 
     #[test]
-    fn test_add_reg64_reg64_imm12() {
+    fn test_add_reg64_reg64_reg64_extended() {
+        disassembler_test!(
+            add_reg64_reg64_reg64_extended,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                // The extend is only left out when one of the registers is SP.
+                if reg1 == AArch64GeneralReg::ZRSP || reg2 == AArch64GeneralReg::ZRSP {
+                    format!(
+                        "add {}, {}, {}",
+                        reg1.capstone_string(UsesSP),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                } else {
+                    format!(
+                        "add {}, {}, {}, uxtx",
+                        reg1.capstone_string(UsesSP),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_imm12() {
+        disassembler_test!(
+            add_reg64_reg64_imm12,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "add {}, {}, #0x{:x}",
+                reg1.capstone_string(UsesSP),
+                reg2.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_imm32() {
+        disassembler_test!(
+            AArch64Assembler::add_reg64_reg64_imm32,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm: i32| {
+                let reg1 = reg1.capstone_string(UsesSP);
+                let reg2 = reg2.capstone_string(UsesSP);
+                match imm {
+                    0x123 => format!("add {reg1}, {reg2}, #0x123"),
+                    -0x123 => format!("sub {reg1}, {reg2}, #0x123"),
+                    0x12_3000 => format!("add {reg1}, {reg2}, #0x123, lsl #12"),
+                    0x12_3456 => {
+                        format!("add {reg1}, {reg2}, #0x123, lsl #12\nadd {reg1}, {reg1}, #0x456")
+                    }
+                    // Capstone only omits the extend when one of the operands is SP.
+                    _ if reg1 == "sp" || reg2 == "sp" => format!(
+                        "mov x17, #0x5678\nmovk x17, #0x1234, lsl #16\nsub {reg1}, {reg2}, x17"
+                    ),
+                    _ => format!(
+                        "mov x17, #0x5678\nmovk x17, #0x1234, lsl #16\nsub {reg1}, {reg2}, x17, uxtx"
+                    ),
+                }
+            },
+            [
+                AArch64GeneralReg::X0,
+                AArch64GeneralReg::X19,
+                AArch64GeneralReg::ZRSP
+            ],
+            [AArch64GeneralReg::FP, AArch64GeneralReg::ZRSP],
+            [0x123, -0x123, 0x12_3000, 0x12_3456, -0x1234_5678]
+        );
+    }
+
+    #[test]
+    fn test_adds_reg64_reg64_reg64() {
+        disassembler_test!(
+            adds_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                if reg1 == AArch64GeneralReg::ZRSP {
+                    // When the destination is ZR, ADDS is an alias for CMN
+                    format!(
+                        "cmn {}, {}",
+                        reg2.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR)
+                    )
+                } else {
+                    format!(
+                        "adds {}, {}, {}",
+                        reg1.capstone_string(UsesZR),
+                        reg2.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR)
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_adrp_reg64_imm32() {
         disassembler_test!(
-            add_reg64_reg64_imm12,
-            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
-                "add {}, {}, #0x{:x}",
-                reg1.capstone_string(UsesSP),
-                reg2.capstone_string(UsesSP),
-                imm
+            adrp_reg64_imm32,
+            |reg1: AArch64GeneralReg, imm: i32| format!(
+                "adrp {}, #0x{:x}",
+                reg1.capstone_string(UsesZR),
+                imm as i64
             ),
             ALL_GENERAL_REGS,
-            ALL_GENERAL_REGS,
-            [0x123]
+            [0x1000, 0x12_3000, -0x1000]
         );
     }
 
@@ -3395,6 +4982,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bfxil_reg64_reg64() {
+        disassembler_test!(
+            bfxil_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, width: u8| {
+                if reg2 == AArch64GeneralReg::ZRSP {
+                    // When the source is ZR, BFXIL is an alias for BFC
+                    format!("bfc {}, #0, #{:#x}", reg1.capstone_string(UsesZR), width)
+                } else {
+                    format!(
+                        "bfxil {}, {}, #0, #{:#x}",
+                        reg1.capstone_string(UsesZR),
+                        reg2.capstone_string(UsesZR),
+                        width
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [16, 32]
+        );
+    }
+
+    #[test]
+    fn test_bl_imm26() {
+        disassembler_test!(
+            bl_imm26,
+            |imm| format!("bl #0x{:x}", imm as i64),
+            [0x120, -0x120, (1 << 27) - 4, -(1 << 27)]
+        );
+    }
+
+    #[test]
+    fn test_br_reg64() {
+        disassembler_test!(
+            br_reg64,
+            |reg1: AArch64GeneralReg| format!("br {}", reg1.capstone_string(UsesZR)),
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_cmp_reg64_imm12() {
         disassembler_test!(
@@ -3560,6 +5188,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_homogeneous_float_aggregate() {
+        use roc_mono::layout::Layout;
+
+        let target_info = roc_target::TargetInfo::default_aarch64();
+        let mut interner = STLayoutInterner::with_capacity(8, target_info);
+        let mut classify = |fields: &'static [InLayout<'static>]| {
+            let layout = interner.insert_direct_no_semantic(LayoutRepr::Struct(fields));
+            AArch64Call::homogeneous_float_aggregate(&interner, layout)
+        };
+
+        assert_eq!(
+            classify(&[Layout::F64, Layout::F64, Layout::F64]),
+            Some((FloatWidth::F64, 3))
+        );
+        assert_eq!(
+            classify(&[Layout::F32, Layout::F32]),
+            Some((FloatWidth::F32, 2))
+        );
+        assert_eq!(classify(&[Layout::F64; 5]), None);
+        assert_eq!(classify(&[Layout::F64, Layout::F32]), None);
+        assert_eq!(classify(&[Layout::F64, Layout::I64]), None);
+        assert_eq!(
+            AArch64Call::homogeneous_float_aggregate(&interner, Layout::F64),
+            None
+        );
+    }
+
+    #[test]
+    fn test_jmp_imm32() {
+        disassembler_test!(
+            |buf: &mut Vec<'_, u8>, imm: i32| {
+                AArch64Assembler::jmp_imm32(buf, imm);
+            },
+            // The offset is relative to the end of the jump.
+            |imm: i32| format!("b #0x{:x}", imm as i64 + 4),
+            [0x120, -0x120]
+        );
+    }
+
+    #[test]
+    fn test_ldp_reg64_reg64_offset() {
+        disassembler_test!(
+            ldp_reg64_reg64_offset,
+            |reg1: AArch64GeneralReg,
+             reg2: AArch64GeneralReg,
+             reg3: AArch64GeneralReg,
+             imm: i32| format!(
+                "ldp {}, {}, [{}, #{:#x}]",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesSP),
+                imm
+            ),
+            [
+                AArch64GeneralReg::X0,
+                AArch64GeneralReg::X9,
+                AArch64GeneralReg::X19,
+                AArch64GeneralReg::FP
+            ],
+            [
+                AArch64GeneralReg::X1,
+                AArch64GeneralReg::X10,
+                AArch64GeneralReg::LR,
+                AArch64GeneralReg::ZRSP
+            ],
+            [AArch64GeneralReg::X0, AArch64GeneralReg::ZRSP],
+            [0x10, 0x1F8]
+        );
+    }
+
+    #[test]
+    fn test_ldp_reg64_reg64_post_index() {
+        disassembler_test!(
+            ldp_reg64_reg64_post_index,
+            |reg1: AArch64GeneralReg,
+             reg2: AArch64GeneralReg,
+             reg3: AArch64GeneralReg,
+             imm: i32| format!(
+                "ldp {}, {}, [{}], #{:#x}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesSP),
+                imm
+            ),
+            [
+                AArch64GeneralReg::X0,
+                AArch64GeneralReg::X9,
+                AArch64GeneralReg::X19,
+                AArch64GeneralReg::FP
+            ],
+            [
+                AArch64GeneralReg::X1,
+                AArch64GeneralReg::X10,
+                AArch64GeneralReg::LR,
+                AArch64GeneralReg::ZRSP
+            ],
+            [AArch64GeneralReg::ZRSP],
+            [0x10, 0x1F8]
+        );
+    }
+
     #[test]
     fn test_ldr_reg64_reg64_imm12() {
         disassembler_test!(
@@ -3576,6 +5306,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_load_store_freg_base_offset() {
+        disassembler_test!(
+            |buf: &mut Vec<'_, u8>,
+             (kind, _, _): (LoadStoreKind, &str, FloatWidth),
+             reg: AArch64FloatReg,
+             offset: i32| load_store_reg_base_offset(
+                buf,
+                kind,
+                reg.id(),
+                AArch64GeneralReg::ZRSP,
+                offset
+            ),
+            |(_, name, ftype): (LoadStoreKind, &str, FloatWidth),
+             reg: AArch64FloatReg,
+             offset: i32| {
+                let reg = reg.capstone_string(ftype);
+                match offset {
+                    0x40 => format!("{name} {reg}, [sp, #0x40]"),
+                    _ => format!("{}ur {reg}, [sp, #-0x10]", &name[..2]),
+                }
+            },
+            [
+                (LoadStoreKind::LDR_D, "ldr", FloatWidth::F64),
+                (LoadStoreKind::STR_D, "str", FloatWidth::F64),
+                (LoadStoreKind::LDR_S, "ldr", FloatWidth::F32),
+                (LoadStoreKind::STR_S, "str", FloatWidth::F32),
+            ],
+            ALL_FLOAT_REGS,
+            [0x40, -0x10]
+        );
+    }
+
+    #[test]
+    fn test_load_store_reg_base_offset() {
+        disassembler_test!(
+            |buf: &mut Vec<'_, u8>,
+             (kind, _): (LoadStoreKind, &str),
+             reg: AArch64GeneralReg,
+             offset: i32| load_store_reg_base_offset(
+                buf,
+                kind,
+                reg.id(),
+                AArch64GeneralReg::FP,
+                offset
+            ),
+            |(kind, name): (LoadStoreKind, &str), reg: AArch64GeneralReg, offset: i32| {
+                // Only 64 bit loads and stores use X registers.
+                let reg = if kind.size == 0b11 || name.starts_with("ldrs") {
+                    reg.capstone_string(UsesZR)
+                } else {
+                    reg.capstone_string(UsesZR).replacen('x', "w", 1)
+                };
+                match offset {
+                    0x40 => format!("{name} {reg}, [x29, #0x40]"),
+                    -0x10 => format!("{} {reg}, [x29, #-0x10]", name.replacen("r", "ur", 1)),
+                    _ => format!(
+                        "mov x17, #0x5678\nmovk x17, #0x1234, lsl #16\nadd x17, x29, x17, uxtx\n{name} {reg}, [x17]"
+                    ),
+                }
+            },
+            [
+                (LoadStoreKind::LDR_X, "ldr"),
+                (LoadStoreKind::STR_X, "str"),
+                (LoadStoreKind::LDR_W, "ldr"),
+                (LoadStoreKind::STR_W, "str"),
+                (LoadStoreKind::LDRSW, "ldrsw"),
+                (LoadStoreKind::LDRH, "ldrh"),
+                (LoadStoreKind::STRH, "strh"),
+                (LoadStoreKind::LDRSH, "ldrsh"),
+                (LoadStoreKind::LDRB, "ldrb"),
+                (LoadStoreKind::STRB, "strb"),
+                (LoadStoreKind::LDRSB, "ldrsb"),
+            ],
+            ALL_GENERAL_REGS,
+            [0x40, -0x10, 0x1234_5678]
+        );
+    }
+
     #[test]
     fn test_lsl_reg64_reg64_reg64() {
         disassembler_test!(
@@ -3690,6 +5499,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_msub_reg64_reg64_reg64_reg64() {
+        disassembler_test!(
+            msub_reg64_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg,
+             reg2: AArch64GeneralReg,
+             reg3: AArch64GeneralReg,
+             reg4: AArch64GeneralReg| {
+                if reg4 == AArch64GeneralReg::ZRSP {
+                    // msub with the zero register as the addend is an alias for mneg
+                    format!(
+                        "mneg {}, {}, {}",
+                        reg1.capstone_string(UsesZR),
+                        reg2.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR)
+                    )
+                } else {
+                    format!(
+                        "msub {}, {}, {}, {}",
+                        reg1.capstone_string(UsesZR),
+                        reg2.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR),
+                        reg4.capstone_string(UsesZR)
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_mul_reg64_reg64_reg64() {
         disassembler_test!(
@@ -3746,6 +5588,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sbfx_reg64_reg64() {
+        disassembler_test!(
+            sbfx_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, width: u8| {
+                let suffix = match width {
+                    8 => "b",
+                    16 => "h",
+                    _ => "w",
+                };
+                format!(
+                    "sxt{} {}, {}",
+                    suffix,
+                    reg1.capstone_string(UsesZR),
+                    reg2.capstone_string(UsesZR).replacen('x', "w", 1)
+                )
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [16, 32]
+        );
+    }
+
     #[test]
     fn test_sdiv_reg64_reg64_reg64() {
         disassembler_test!(
@@ -3762,6 +5627,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stp_reg64_reg64_offset() {
+        disassembler_test!(
+            stp_reg64_reg64_offset,
+            |reg1: AArch64GeneralReg,
+             reg2: AArch64GeneralReg,
+             reg3: AArch64GeneralReg,
+             imm: i32| format!(
+                "stp {}, {}, [{}, #{:#x}]",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [AArch64GeneralReg::X0, AArch64GeneralReg::ZRSP],
+            [0x10, 0x1F8]
+        );
+    }
+
+    #[test]
+    fn test_stp_reg64_reg64_pre_index() {
+        disassembler_test!(
+            stp_reg64_reg64_pre_index,
+            |reg1: AArch64GeneralReg,
+             reg2: AArch64GeneralReg,
+             reg3: AArch64GeneralReg,
+             imm: i32| format!(
+                "stp {}, {}, [{}, #{}]!",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesSP),
+                if imm < 0 {
+                    format!("-{:#x}", -imm)
+                } else {
+                    format!("{imm:#x}")
+                }
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [AArch64GeneralReg::ZRSP],
+            [-0x10, -0x200, 0x1F8]
+        );
+    }
+
     #[test]
     fn test_str_reg64_reg64_imm12() {
         disassembler_test!(
@@ -3822,6 +5733,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sub_reg64_reg64_reg64_extended() {
+        disassembler_test!(
+            sub_reg64_reg64_reg64_extended,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                // The extend is only left out when one of the registers is SP.
+                if reg1 == AArch64GeneralReg::ZRSP || reg2 == AArch64GeneralReg::ZRSP {
+                    format!(
+                        "sub {}, {}, {}",
+                        reg1.capstone_string(UsesSP),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                } else {
+                    format!(
+                        "sub {}, {}, {}, uxtx",
+                        reg1.capstone_string(UsesSP),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
 
     fn test_subs_reg64_reg64_imm12() {
@@ -3896,6 +5835,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ubfx_reg64_reg64() {
+        disassembler_test!(
+            ubfx_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, width: u8| format!(
+                "ubfx {}, {}, #0, #{:#x}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                width
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [16, 32]
+        );
+    }
+
     #[test]
     fn test_udiv_reg64_reg64_reg64() {
         disassembler_test!(
@@ -4177,6 +6132,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fmov_freg_reg() {
+        disassembler_test!(
+            fmov_freg_reg,
+            |ftype: FloatWidth, reg1: AArch64FloatReg, reg2: AArch64GeneralReg| {
+                let reg2 = match ftype {
+                    FloatWidth::F32 => reg2.capstone_string(UsesZR).replacen('x', "w", 1),
+                    FloatWidth::F64 => reg2.capstone_string(UsesZR),
+                };
+                format!("fmov {}, {}", reg1.capstone_string(ftype), reg2)
+            },
+            ALL_FLOAT_TYPES,
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmov_reg_freg() {
+        disassembler_test!(
+            fmov_reg_freg,
+            |ftype: FloatWidth, reg1: AArch64GeneralReg, reg2: AArch64FloatReg| {
+                let reg1 = match ftype {
+                    FloatWidth::F32 => reg1.capstone_string(UsesZR).replacen('x', "w", 1),
+                    FloatWidth::F64 => reg1.capstone_string(UsesZR),
+                };
+                format!("fmov {}, {}", reg1, reg2.capstone_string(ftype))
+            },
+            ALL_FLOAT_TYPES,
+            ALL_GENERAL_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
     #[test]
     fn test_fmul_freg_freg_freg() {
         disassembler_test!(
@@ -4212,6 +6201,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ldp_freg64_freg64_offset() {
+        disassembler_test!(
+            ldp_freg64_freg64_offset,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64GeneralReg, imm: i32| {
+                format!(
+                    "ldp {}, {}, [{}, #{:#x}]",
+                    reg1.capstone_string(FloatWidth::F64),
+                    reg2.capstone_string(FloatWidth::F64),
+                    reg3.capstone_string(UsesSP),
+                    imm
+                )
+            },
+            [
+                AArch64FloatReg::V0,
+                AArch64FloatReg::V8,
+                AArch64FloatReg::V30
+            ],
+            [
+                AArch64FloatReg::V1,
+                AArch64FloatReg::V9,
+                AArch64FloatReg::V31
+            ],
+            [AArch64GeneralReg::X0, AArch64GeneralReg::ZRSP],
+            [0x10, 0x1F8]
+        );
+    }
+
     #[test]
     fn test_movi_freg_zero() {
         disassembler_test!(
@@ -4238,4 +6255,24 @@ mod tests {
             ALL_GENERAL_REGS
         );
     }
+
+    #[test]
+    fn test_stp_freg64_freg64_offset() {
+        disassembler_test!(
+            stp_freg64_freg64_offset,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64GeneralReg, imm: i32| {
+                format!(
+                    "stp {}, {}, [{}, #{:#x}]",
+                    reg1.capstone_string(FloatWidth::F64),
+                    reg2.capstone_string(FloatWidth::F64),
+                    reg3.capstone_string(UsesSP),
+                    imm
+                )
+            },
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            [AArch64GeneralReg::X0, AArch64GeneralReg::ZRSP],
+            [0x10, 0x1F8]
+        );
+    }
 }
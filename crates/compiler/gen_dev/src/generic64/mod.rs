@@ -9,15 +9,17 @@ use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp, HelperOp};
 use roc_mono::ir::{
-    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, Param, ProcLayout,
-    SelfRecursive, Stmt,
+    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, LookupType, Param,
+    ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::low_level::HigherOrder;
+use roc_region::all::Region;
 use roc_target::TargetInfo;
+use roc_types::subs::Variable;
 use std::marker::PhantomData;
 
 pub(crate) mod aarch64;
@@ -242,6 +244,7 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         dst: GeneralReg,
     );
 
+    /// Loads the address of the linked data `fn_name` into `dst`.
     fn data_pointer(
         buf: &mut Vec<'_, u8>,
         relocs: &mut Vec<'_, Relocation>,
@@ -640,6 +643,7 @@ pub struct Backend64Bit<
     helper_proc_gen: CodeGenHelp<'a>,
     helper_proc_symbols: Vec<'a, (Symbol, ProcLayout<'a>)>,
     caller_procs: Vec<'a, CallerProc<'a>>,
+    expect_clone_helpers: Vec<'a, (InLayout<'a>, String)>,
     buf: Vec<'a, u8>,
     relocs: Vec<'a, Relocation>,
     proc_name: Option<String>,
@@ -678,6 +682,7 @@ pub fn new_backend_64bit<
         helper_proc_gen: CodeGenHelp::new(env.arena, target_info, env.module_id),
         helper_proc_symbols: bumpalo::vec![in env.arena],
         caller_procs: bumpalo::vec![in env.arena],
+        expect_clone_helpers: bumpalo::vec![in env.arena],
         proc_name: None,
         is_self_recursive: None,
         buf: bumpalo::vec![in env.arena],
@@ -757,6 +762,9 @@ impl<
     fn caller_procs(&self) -> &Vec<'a, CallerProc<'a>> {
         &self.caller_procs
     }
+    fn expect_clone_helpers(&self) -> &Vec<'a, (InLayout<'a>, String)> {
+        &self.expect_clone_helpers
+    }
 
     fn reset(&mut self, name: String, is_self_recursive: SelfRecursive) {
         self.proc_name = Some(name);
@@ -877,6 +885,12 @@ impl<
                         offset: offset + setup_offset as u64,
                         name,
                     },
+                    Relocation::LinkedFunctionPointer { offset, name } => {
+                        Relocation::LinkedFunctionPointer {
+                            offset: offset + setup_offset as u64,
+                            name,
+                        }
+                    }
                     Relocation::JmpToReturn { .. } => unreachable!(),
                 }),
         );
@@ -937,9 +951,6 @@ impl<
 
         // now, this gives a pointer to the value
         ASM::data_pointer(&mut self.buf, &mut self.relocs, data_name, reg);
-
-        // dereference
-        ASM::mov_reg64_mem64_offset32(&mut self.buf, reg, reg, 0);
     }
 
     fn build_fn_call(
@@ -1065,7 +1076,7 @@ impl<
             // Build unconditional jump to the end of this switch.
             // Since we don't know the offset yet, set it to 0 and overwrite later.
             let jmp_location = self.buf.len();
-            let jmp_offset = ASM::jmp_imm32(&mut self.buf, 0x1234_5678);
+            let jmp_offset = ASM::jmp_imm32(&mut self.buf, 0);
             ret_jumps.push((jmp_location, jmp_offset));
 
             // Overwrite the original jne with the correct offset.
@@ -1157,7 +1168,7 @@ impl<
             .setup_jump(self.layout_interner, &mut self.buf, id, args, arg_layouts);
 
         let jmp_location = self.buf.len();
-        let start_offset = ASM::jmp_imm32(&mut self.buf, 0x1234_5678);
+        let start_offset = ASM::jmp_imm32(&mut self.buf, 0);
 
         if let Some(vec) = self.join_map.get_mut(id) {
             vec.push((jmp_location as u64, start_offset as u64))
@@ -1166,6 +1177,149 @@ impl<
         }
     }
 
+    fn build_dbg(&mut self, layout_ids: &mut LayoutIds<'a>, symbol: Symbol, variable: Variable) {
+        // The region of a dbg is smuggled through its symbol
+        let region = unsafe { std::mem::transmute::<Symbol, Region>(symbol) };
+
+        // Put everything on the stack, so the temporaries we need can be dropped afterwards.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        let base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        let shared_memory =
+            self.clone_to_shared_memory(layout_ids, symbol, region, &[symbol], &[variable]);
+        self.notify_parent(bitcode::NOTIFY_PARENT_DBG, shared_memory);
+
+        self.restore_base_storage(base_storage, base_literal_map);
+    }
+
+    fn build_expect(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        condition: Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        variables: &'a [LookupType],
+        notify_parent: bool,
+    ) {
+        // The failure branch must not influence the storage of the code that follows it,
+        // so put everything on the stack and build the branch with a copy of the storage.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        let base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        let cond_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &condition);
+
+        // Skip the failure branch if the condition holds.
+        // Since we don't know the offset yet, set it to 0 and overwrite later.
+        let jne_location = self.buf.len();
+        let start_offset =
+            ASM::jne_reg64_imm64_imm32(&mut self.buf, &mut self.storage_manager, cond_reg, 0, 0);
+
+        let shared_memory =
+            self.clone_to_shared_memory(layout_ids, condition, region, lookups, variables);
+        if notify_parent {
+            self.notify_parent(bitcode::NOTIFY_PARENT_EXPECT, shared_memory);
+        }
+
+        // Overwrite the original jne with the correct offset.
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let jne_offset = self.buf.len() - start_offset;
+        ASM::jne_reg64_imm64_imm32(
+            &mut tmp,
+            &mut self.storage_manager,
+            cond_reg,
+            0,
+            jne_offset as i32,
+        );
+        self.buf[jne_location..][..tmp.len()].copy_from_slice(tmp.as_slice());
+
+        self.restore_base_storage(base_storage, base_literal_map);
+    }
+
+    fn build_clone_union_body(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+    ) {
+        let number_of_tags = match union_layout {
+            UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => tags.len(),
+            UnionLayout::NullableWrapped { other_tags, .. } => other_tags.len() + 1,
+            UnionLayout::NullableUnwrapped { .. } => 2,
+            UnionLayout::NonNullableUnwrapped(_) => 1,
+        };
+
+        if number_of_tags == 0 {
+            // we're cloning an empty tag union; this code is effectively unreachable
+            self.return_symbol(&extra, &Layout::U64);
+            return;
+        }
+
+        let tag_id = self.debug_symbol("tag_id");
+        self.get_tag_id(&tag_id, &value, &union_layout);
+        let tag_id_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &tag_id);
+
+        // Every branch returns, so unlike a switch there is nothing to merge afterwards.
+        let base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        for id in 0..number_of_tags {
+            let is_last = id + 1 == number_of_tags;
+
+            // Jump to the next branch if this is not the tag we are looking for.
+            // Since we don't know the offset yet, set it to 0 and overwrite later.
+            let jne_location = self.buf.len();
+            let start_offset = if is_last {
+                0
+            } else {
+                ASM::jne_reg64_imm64_imm32(
+                    &mut self.buf,
+                    &mut self.storage_manager,
+                    tag_id_reg,
+                    id as u64,
+                    0,
+                )
+            };
+
+            self.storage_manager = base_storage.clone();
+            self.literal_map = base_literal_map.clone();
+
+            let answer = self.build_clone_tag(
+                layout_ids,
+                ptr,
+                offset,
+                extra,
+                value,
+                union_layout,
+                id as TagIdIntType,
+            );
+            self.return_symbol(&answer, &Layout::U64);
+
+            if !is_last {
+                // Overwrite the original jne with the correct offset.
+                tmp.clear();
+                let jne_offset = self.buf.len() - start_offset;
+                ASM::jne_reg64_imm64_imm32(
+                    &mut tmp,
+                    &mut self.storage_manager,
+                    tag_id_reg,
+                    id as u64,
+                    jne_offset as i32,
+                );
+                self.buf[jne_location..][..tmp.len()].copy_from_slice(tmp.as_slice());
+            }
+        }
+    }
+
     fn build_num_abs(&mut self, dst: &Symbol, src: &Symbol, layout: &InLayout<'a>) {
         match self.interner().get_repr(*layout) {
             LayoutRepr::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
@@ -3706,7 +3860,7 @@ impl<
             )
         }
         let inst_loc = self.buf.len() as u64;
-        let offset = ASM::jmp_imm32(&mut self.buf, 0x1234_5678) as u64;
+        let offset = ASM::jmp_imm32(&mut self.buf, 0) as u64;
         self.relocs.push(Relocation::JmpToReturn {
             inst_loc,
             inst_size: self.buf.len() as u64 - inst_loc,
@@ -4549,6 +4703,632 @@ impl<
 
         self.load_literal(&symbol, &u64_layout, &width_literal);
     }
+
+    /// Restores the storage from before a branch, while keeping the stack space and
+    /// callee saved registers used by the code in the branch.
+    fn restore_base_storage(
+        &mut self,
+        mut base_storage: StorageManager<'a, 'r, GeneralReg, FloatReg, ASM, CC>,
+        base_literal_map: MutMap<Symbol, (*const Literal<'a>, *const InLayout<'a>)>,
+    ) {
+        base_storage.update_stack_size(self.storage_manager.stack_size());
+        base_storage.update_fn_call_stack_size(self.storage_manager.fn_call_stack_size());
+        base_storage.update_used_callee_saved_regs(&self.storage_manager);
+
+        self.storage_manager = base_storage;
+        self.literal_map = base_literal_map;
+    }
+
+    fn notify_parent(&mut self, function: &str, shared_memory: Symbol) {
+        self.build_fn_call(
+            &Symbol::DEV_TMP,
+            function.to_string(),
+            &[shared_memory],
+            &[Layout::U64],
+            &Layout::UNIT,
+        );
+
+        self.free_symbol(&Symbol::DEV_TMP);
+    }
+
+    /// Clones the `lookups` of an expect or dbg into the shared memory buffer, and returns the
+    /// pointer to that buffer. The buffer starts with the number of frames and the offset of the
+    /// first free byte. A frame has the following shape:
+    ///
+    /// ```text
+    ///     ===
+    ///     header: region start (u32), region end (u32), module id (u32)
+    ///     ===
+    /// /-- offset_lookup_1  (u64)
+    /// |   var_lookup_1     (u32)
+    /// |   ..
+    /// |   offset_lookup_n  (u64)
+    /// |   var_lookup_n     (u32)
+    /// \-> lookup_val_1     (varsize)
+    ///     ..
+    ///     lookup_val_n     (varsize)
+    /// ```
+    fn clone_to_shared_memory(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        condition: Symbol,
+        region: Region,
+        lookups: &[Symbol],
+        variables: &[LookupType],
+    ) -> Symbol {
+        const HEADER_SIZE: u32 = 3 * 4;
+        const LOOKUP_SIZE: u32 = 8 + 4;
+
+        let shared_memory = self.debug_symbol("shared_memory");
+        self.build_fn_call(
+            &shared_memory,
+            bitcode::UTILS_EXPECT_FAILED_START_SHARED_FILE.to_string(),
+            &[],
+            &[],
+            &Layout::U64,
+        );
+
+        let count = self.debug_symbol("count");
+        self.build_read(count, shared_memory, 0, Layout::U64);
+        let frame_offset = self.debug_symbol("frame_offset");
+        self.build_read(frame_offset, shared_memory, 8, Layout::U64);
+
+        let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
+        let header = [region.start().offset, region.end().offset, module_id];
+        for (i, value) in header.into_iter().enumerate() {
+            let sym = self.debug_symbol("header");
+            self.load_literal_i32(&sym, value as i32);
+            self.build_copy(shared_memory, frame_offset, 4 * i as i32, sym, Layout::U32);
+            self.free_symbol(&sym);
+        }
+
+        let mut offset = self.debug_symbol("value_offset");
+        let values_start = HEADER_SIZE + LOOKUP_SIZE * lookups.len() as u32;
+        self.build_offset_add(offset, frame_offset, values_start);
+
+        let mut lookup_starts = bumpalo::vec![in self.env.arena];
+        for lookup in lookups {
+            lookup_starts.push(offset);
+
+            let layout = match self.layout_map.get(lookup) {
+                Some(layout) => *layout,
+                None => internal_error!("the lookup, {:?}, has no known layout", lookup),
+            };
+
+            let extra = self.debug_symbol("extra_offset");
+            let stack_size = self.layout_interner.stack_size(layout);
+            self.build_offset_add(extra, offset, stack_size);
+
+            offset = self.build_clone(layout_ids, shared_memory, offset, extra, *lookup, layout);
+        }
+
+        for (i, (start, variable)) in lookup_starts.into_iter().zip(variables).enumerate() {
+            let displacement = (HEADER_SIZE + LOOKUP_SIZE * i as u32) as i32;
+            self.build_copy(
+                shared_memory,
+                frame_offset,
+                displacement,
+                start,
+                Layout::U64,
+            );
+
+            let sym = self.debug_symbol("variable");
+            self.load_literal_i32(&sym, variable.index() as i32);
+            self.build_copy(
+                shared_memory,
+                frame_offset,
+                displacement + 8,
+                sym,
+                Layout::U32,
+            );
+            self.free_symbol(&sym);
+        }
+
+        let new_count = self.debug_symbol("count");
+        self.build_offset_add(new_count, count, 1);
+        self.build_write(shared_memory, 0, new_count, Layout::U64);
+        self.build_write(shared_memory, 8, offset, Layout::U64);
+
+        shared_memory
+    }
+
+    /// Clones `value` to `ptr + offset`. Any data that `value` points to is cloned to
+    /// `ptr + extra`. Returns the extra offset after everything that was cloned.
+    fn build_clone(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        match self.layout_interner.get_repr(layout) {
+            LayoutRepr::Builtin(
+                Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+            ) => {
+                self.build_copy(ptr, offset, 0, value, layout);
+
+                extra
+            }
+            LayoutRepr::Builtin(Builtin::Str) => {
+                let new_extra = self.debug_symbol("extra_offset");
+                self.build_fn_call(
+                    &new_extra,
+                    bitcode::STR_CLONE_TO.to_string(),
+                    &[value, ptr, offset, extra],
+                    &[Layout::STR, Layout::U64, Layout::U64, Layout::U64],
+                    &Layout::U64,
+                );
+
+                new_extra
+            }
+            LayoutRepr::Builtin(Builtin::List(element_layout)) => {
+                self.build_clone_list(layout_ids, ptr, offset, extra, value, element_layout)
+            }
+            repr @ LayoutRepr::Struct(field_layouts) => {
+                if repr.safe_to_memcpy(self.layout_interner) {
+                    self.build_copy(ptr, offset, 0, value, layout);
+
+                    return extra;
+                }
+
+                let mut extra = extra;
+                let mut field_offset = 0;
+                for (i, field_layout) in field_layouts.iter().enumerate() {
+                    let field = self.debug_symbol("field");
+                    self.load_struct_at_index(&field, &value, i as u64, field_layouts);
+
+                    let offset_sym = self.debug_symbol("field_offset");
+                    self.build_offset_add(offset_sym, offset, field_offset);
+
+                    extra =
+                        self.build_clone(layout_ids, ptr, offset_sym, extra, field, *field_layout);
+                    field_offset += self.layout_interner.stack_size(*field_layout);
+                }
+
+                extra
+            }
+            // Since we will never actually display functions (and hence lambda sets)
+            // we just write nothing to the buffer
+            LayoutRepr::LambdaSet(_) => extra,
+            repr @ LayoutRepr::Union(_) => {
+                if repr.safe_to_memcpy(self.layout_interner) {
+                    self.build_copy(ptr, offset, 0, value, layout);
+
+                    extra
+                } else {
+                    self.build_clone_union(layout_ids, ptr, offset, extra, value, layout)
+                }
+            }
+            LayoutRepr::RecursivePointer(rec_layout) => {
+                self.build_clone_union(layout_ids, ptr, offset, extra, value, rec_layout)
+            }
+            LayoutRepr::Ptr(_) => unreachable!("for internal use only"),
+            LayoutRepr::FunctionPointer(_) | LayoutRepr::Erased(_) => todo_lambda_erasure!(),
+        }
+    }
+
+    /// Lists are cloned with their elements at `extra`, and a capacity equal to their length.
+    fn build_clone_list(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        list: Symbol,
+        element_layout: InLayout<'a>,
+    ) -> Symbol {
+        let (list_offset, _) = self.storage_manager.stack_offset_and_size(&list);
+
+        let elements = self.debug_symbol("elements");
+        let elements_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &elements);
+        ASM::mov_reg64_base32(&mut self.buf, elements_reg, list_offset);
+
+        let length = self.debug_symbol("length");
+        let length_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &length);
+        ASM::mov_reg64_base32(&mut self.buf, length_reg, list_offset + 8);
+
+        self.build_copy(ptr, offset, 0, extra, Layout::U64);
+        self.build_copy(ptr, offset, 8, length, Layout::U64);
+        self.build_copy(ptr, offset, 16, length, Layout::U64);
+
+        let element_width = self.layout_interner.stack_size(element_layout);
+        if element_width == 0 {
+            return extra;
+        }
+
+        // Anything the elements point to is cloned after the elements themselves.
+        let width = self.debug_symbol("element_width");
+        self.load_literal_i64(&width, element_width as i64);
+        let elements_width = self.debug_symbol("elements_width");
+        self.build_num_mul(&elements_width, &length, &width, &Layout::U64);
+        let rest = self.debug_symbol("rest_offset");
+        self.build_num_add(&rest, &extra, &elements_width, &Layout::U64);
+
+        // The loop is a join point with the loop variables as its parameters.
+        let id = JoinPointId(self.debug_symbol("clone_elements"));
+        let element_ptr = self.debug_symbol("element_ptr");
+        let element_offset = self.debug_symbol("element_offset");
+        let rest_offset = self.debug_symbol("rest_offset");
+        let remaining = self.debug_symbol("remaining");
+        let params = self.env.arena.alloc([
+            Param {
+                symbol: element_ptr,
+                layout: Layout::U64,
+            },
+            Param {
+                symbol: element_offset,
+                layout: Layout::U64,
+            },
+            Param {
+                symbol: rest_offset,
+                layout: Layout::U64,
+            },
+            Param {
+                symbol: remaining,
+                layout: Layout::U64,
+            },
+        ]);
+        let param_layouts = [Layout::U64; 4];
+
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        self.storage_manager
+            .setup_joinpoint(self.layout_interner, &mut self.buf, &id, params);
+        self.storage_manager.setup_jump(
+            self.layout_interner,
+            &mut self.buf,
+            &id,
+            &[elements, extra, rest, length],
+            &param_layouts,
+        );
+
+        // Every jump back to the start of the loop must find the same storage.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        let loop_start = self.buf.len();
+        let base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        // Exit the loop once no elements remain.
+        // Since we don't know the offsets yet, set them to 0 and overwrite later.
+        let remaining_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &remaining);
+        let jne_location = self.buf.len();
+        let jne_start_offset = ASM::jne_reg64_imm64_imm32(
+            &mut self.buf,
+            &mut self.storage_manager,
+            remaining_reg,
+            0,
+            0,
+        );
+        let exit_location = self.buf.len();
+        let exit_start_offset = ASM::jmp_imm32(&mut self.buf, 0);
+
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let jne_offset = self.buf.len() - jne_start_offset;
+        ASM::jne_reg64_imm64_imm32(
+            &mut tmp,
+            &mut self.storage_manager,
+            remaining_reg,
+            0,
+            jne_offset as i32,
+        );
+        self.buf[jne_location..][..tmp.len()].copy_from_slice(tmp.as_slice());
+
+        let element = self.debug_symbol("element");
+        self.build_ptr_load(element, element_ptr, element_layout);
+        let new_rest = self.build_clone(
+            layout_ids,
+            ptr,
+            element_offset,
+            rest_offset,
+            element,
+            element_layout,
+        );
+
+        let next_ptr = self.debug_symbol("element_ptr");
+        self.build_offset_add(next_ptr, element_ptr, element_width);
+        let next_offset = self.debug_symbol("element_offset");
+        self.build_offset_add(next_offset, element_offset, element_width);
+        let next_remaining = self.debug_symbol("remaining");
+        let one = self.debug_symbol("one");
+        self.load_literal_i64(&one, 1);
+        self.build_num_sub(&next_remaining, &remaining, &one, &Layout::U64);
+
+        self.storage_manager.setup_jump(
+            self.layout_interner,
+            &mut self.buf,
+            &id,
+            &[next_ptr, next_offset, new_rest, next_remaining],
+            &param_layouts,
+        );
+        let jmp_location = self.buf.len();
+        let jmp_start_offset = ASM::jmp_imm32(&mut self.buf, 0);
+        self.update_jmp_imm32_offset(
+            &mut tmp,
+            jmp_location as u64,
+            jmp_start_offset as u64,
+            loop_start as u64,
+        );
+
+        let loop_end = self.buf.len();
+        self.update_jmp_imm32_offset(
+            &mut tmp,
+            exit_location as u64,
+            exit_start_offset as u64,
+            loop_end as u64,
+        );
+
+        self.restore_base_storage(base_storage, base_literal_map);
+
+        rest_offset
+    }
+
+    /// Unions that are not safe to memcpy are cloned by a helper procedure, that is built later.
+    fn build_clone_union(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        union_layout: InLayout<'a>,
+    ) -> Symbol {
+        let repr = self.layout_interner.get_repr(union_layout);
+        let fn_name = layout_ids
+            .get(Symbol::CLONE, &repr)
+            .to_symbol_string(Symbol::CLONE, self.interns);
+
+        if !self
+            .expect_clone_helpers
+            .iter()
+            .any(|(_, name)| name == &fn_name)
+        {
+            self.expect_clone_helpers
+                .push((union_layout, fn_name.clone()));
+        }
+
+        let new_extra = self.debug_symbol("extra_offset");
+        self.build_fn_call(
+            &new_extra,
+            fn_name,
+            &[ptr, offset, extra, value],
+            &[Layout::U64, Layout::U64, Layout::U64, union_layout],
+            &Layout::U64,
+        );
+
+        new_extra
+    }
+
+    /// Clones the tag with id `tag_id` of the union `value`. Returns the new extra offset.
+    #[allow(clippy::too_many_arguments)]
+    fn build_clone_tag(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+        tag_id: TagIdIntType,
+    ) -> Symbol {
+        let field_layouts = match union_layout {
+            UnionLayout::NonRecursive(tags) => {
+                // the payload is stored inline, followed by the tag id
+                let answer = self.build_clone_tag_fields(
+                    layout_ids,
+                    ptr,
+                    offset,
+                    extra,
+                    value,
+                    union_layout,
+                    tag_id,
+                    tags[tag_id as usize],
+                );
+
+                let tag_id_offset = union_layout.tag_id_offset(self.layout_interner).unwrap();
+                self.build_write_tag_id(ptr, offset, tag_id_offset, union_layout, tag_id);
+
+                return answer;
+            }
+            UnionLayout::Recursive(tags) => tags[tag_id as usize],
+            UnionLayout::NonNullableUnwrapped(field_layouts) => field_layouts,
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => {
+                if tag_id == nullable_id {
+                    self.build_write_null(ptr, offset);
+
+                    return extra;
+                }
+
+                if tag_id > nullable_id {
+                    other_tags[tag_id as usize - 1]
+                } else {
+                    other_tags[tag_id as usize]
+                }
+            }
+            UnionLayout::NullableUnwrapped {
+                nullable_id,
+                other_fields,
+            } => {
+                if tag_id == nullable_id as TagIdIntType {
+                    self.build_write_null(ptr, offset);
+
+                    return extra;
+                }
+
+                other_fields
+            }
+        };
+
+        // write the "pointer" of the current offset
+        if union_layout.stores_tag_id_in_pointer(self.layout_interner.target_info()) {
+            let sym = self.debug_symbol("tag_id");
+            self.load_literal_i32(&sym, tag_id as i32);
+            self.build_copy(ptr, offset, 0, sym, Layout::U32);
+            self.free_symbol(&sym);
+
+            self.build_copy(ptr, offset, 4, extra, Layout::U32);
+        } else {
+            self.build_copy(ptr, offset, 0, extra, Layout::U64);
+        }
+
+        // the data is cloned to the extra offset, and anything it points to after that
+        let (data_width, _) = union_layout.data_size_and_alignment(self.layout_interner);
+        let data_extra = self.debug_symbol("extra_offset");
+        self.build_offset_add(data_extra, extra, data_width);
+
+        let answer = self.build_clone_tag_fields(
+            layout_ids,
+            ptr,
+            extra,
+            data_extra,
+            value,
+            union_layout,
+            tag_id,
+            field_layouts,
+        );
+
+        if union_layout.stores_tag_id_as_data(self.layout_interner.target_info()) {
+            let tag_id_offset = union_layout.tag_id_offset(self.layout_interner).unwrap();
+            self.build_write_tag_id(ptr, extra, tag_id_offset, union_layout, tag_id);
+        }
+
+        answer
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_clone_tag_fields(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+        tag_id: TagIdIntType,
+        field_layouts: &'a [InLayout<'a>],
+    ) -> Symbol {
+        let mut extra = extra;
+        let mut field_offset = 0;
+        for (index, field_layout) in field_layouts.iter().enumerate() {
+            let field = self.debug_symbol("field");
+            self.load_union_at_index(&field, &value, tag_id, index as u64, &union_layout);
+
+            let offset_sym = self.debug_symbol("field_offset");
+            self.build_offset_add(offset_sym, offset, field_offset);
+
+            extra = self.build_clone(layout_ids, ptr, offset_sym, extra, field, *field_layout);
+            field_offset += self.layout_interner.stack_size(*field_layout);
+        }
+
+        extra
+    }
+
+    fn build_write_tag_id(
+        &mut self,
+        ptr: Symbol,
+        offset: Symbol,
+        tag_id_offset: u32,
+        union_layout: UnionLayout<'a>,
+        tag_id: TagIdIntType,
+    ) {
+        let tag_id_layout = union_layout.tag_id_layout();
+        if self.layout_interner.stack_size(tag_id_layout) == 0 {
+            return;
+        }
+
+        let sym = self.debug_symbol("tag_id");
+        let literal = Literal::Int((tag_id as i128).to_ne_bytes());
+        self.load_literal(&sym, &tag_id_layout, &literal);
+        self.build_copy(ptr, offset, tag_id_offset as i32, sym, tag_id_layout);
+        self.free_symbol(&sym);
+    }
+
+    fn build_write_null(&mut self, ptr: Symbol, offset: Symbol) {
+        let sym = self.debug_symbol("null");
+        self.load_literal_i64(&sym, 0);
+        self.build_copy(ptr, offset, 0, sym, Layout::U64);
+        self.free_symbol(&sym);
+    }
+
+    /// Writes `value` to `ptr + offset + displacement`
+    fn build_copy(
+        &mut self,
+        ptr: Symbol,
+        offset: Symbol,
+        displacement: i32,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) {
+        let address = self.debug_symbol("address");
+        self.build_num_add(&address, &ptr, &offset, &Layout::U64);
+        self.build_write(address, displacement, value, layout);
+        self.free_symbol(&address);
+    }
+
+    /// Writes `value` to `address + displacement`
+    fn build_write(
+        &mut self,
+        address: Symbol,
+        displacement: i32,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) {
+        let address_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &address);
+
+        Self::ptr_write(
+            &mut self.buf,
+            &mut self.storage_manager,
+            self.layout_interner,
+            address_reg,
+            displacement,
+            self.layout_interner.stack_size(layout) as u64,
+            self.layout_interner.get_repr(layout),
+            value,
+        );
+    }
+
+    /// Reads a value of `layout` from `address + displacement` into `dst`
+    fn build_read(
+        &mut self,
+        dst: Symbol,
+        address: Symbol,
+        displacement: i32,
+        layout: InLayout<'a>,
+    ) {
+        let address_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &address);
+
+        Self::ptr_read(
+            &mut self.buf,
+            &mut self.storage_manager,
+            self.layout_interner,
+            address_reg,
+            displacement,
+            layout,
+            dst,
+        );
+    }
+
+    /// Stores `src + amount` in `dst`
+    fn build_offset_add(&mut self, dst: Symbol, src: Symbol, amount: u32) {
+        let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, &dst);
+        let src_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &src);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, dst_reg, src_reg, amount as i32);
+    }
 }
 
 #[macro_export]
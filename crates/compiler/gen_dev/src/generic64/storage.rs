@@ -1127,6 +1127,15 @@ impl<
         self.fn_call_stack_size = max(self.fn_call_stack_size, tmp_size);
     }
 
+    /// Marks the callee saved registers used by `other` as used as well.
+    /// This is needed when `other` was used to build a branch, and is then thrown away.
+    pub fn update_used_callee_saved_regs(&mut self, other: &Self) {
+        self.general_used_callee_saved_regs
+            .extend(other.general_used_callee_saved_regs.iter().copied());
+        self.float_used_callee_saved_regs
+            .extend(other.float_used_callee_saved_regs.iter().copied());
+    }
+
     fn joinpoint_argument_stack_storage(
         &mut self,
         layout_interner: &mut STLayoutInterner<'a>,
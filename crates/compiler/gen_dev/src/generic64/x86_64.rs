@@ -530,7 +530,6 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
 
         // the setlongjmp_buffer
         ASM::data_pointer(buf, relocs, String::from("setlongjmp_buffer"), RDI);
-        ASM::mov_reg64_mem64_offset32(buf, RDI, RDI, 0);
 
         // the value to return from the longjmp. It is a pointer to the last 3 words of the setlongjmp_buffer
         // they represent the errore message.
@@ -1456,7 +1455,6 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         // the setlongjmp_buffer
         let env = R8;
         ASM::data_pointer(buf, relocs, String::from("setlongjmp_buffer"), env);
-        ASM::mov_reg64_mem64_offset32(buf, env, env, 0);
 
         // move the roc_str bytes into the setlongjmp_buffer
         for offset in [0, 8, 16] {
@@ -1728,6 +1726,9 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
             offset: buf.len() as u64 - 4,
             name: fn_name,
         });
+
+        // The relocation points at the GOT entry, which holds the actual address.
+        Self::mov_reg64_mem64_offset32(buf, dst, dst, 0);
     }
 
     #[inline(always)]
@@ -17,13 +17,15 @@ use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp};
 use roc_mono::ir::{
     BranchInfo, CallType, CrashTag, Expr, HigherOrderLowLevel, JoinPointId, ListLiteralElement,
-    Literal, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, Stmt,
+    Literal, LookupType, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::list_element_layout;
+use roc_region::all::Region;
+use roc_types::subs::Variable;
 
 mod generic64;
 mod object_builder;
@@ -34,6 +36,8 @@ mod run_roc;
 pub enum AssemblyBackendMode {
     /// Assumes primitives (roc_alloc, roc_panic, etc) are provided by the host
     Binary,
+    /// Like `Binary`, but also runs `dbg` and `expect`, and reports them to the parent process
+    BinaryDev,
     /// Provides a testing implementation of primitives (roc_alloc, roc_panic, etc)
     Test,
 }
@@ -42,9 +46,18 @@ impl AssemblyBackendMode {
     fn generate_allocators(self) -> bool {
         match self {
             AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => false,
             AssemblyBackendMode::Test => true,
         }
     }
+
+    fn runs_expects(self) -> bool {
+        match self {
+            AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => true,
+            AssemblyBackendMode::Test => false,
+        }
+    }
 }
 
 pub struct Env<'a> {
@@ -70,6 +83,12 @@ pub enum Relocation {
         offset: u64,
         name: String,
     },
+    /// The address of a function, as opposed to a call to it.
+    /// Only needed on targets where the two are relocated differently.
+    LinkedFunctionPointer {
+        offset: u64,
+        name: String,
+    },
     LinkedData {
         offset: u64,
         name: String,
@@ -256,9 +275,30 @@ impl<'a> LastSeenMap<'a> {
                 }
             }
 
-            Stmt::Dbg { .. } => todo!("dbg not implemented in the dev backend"),
-            Stmt::Expect { .. } => todo!("expect is not implemented in the dev backend"),
-            Stmt::ExpectFx { .. } => todo!("expect-fx is not implemented in the dev backend"),
+            Stmt::Dbg {
+                symbol, remainder, ..
+            } => {
+                self.set_last_seen(*symbol, stmt);
+                self.scan_ast_help(remainder);
+            }
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            }
+            | Stmt::ExpectFx {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                self.set_last_seen(*condition, stmt);
+                for sym in *lookups {
+                    self.set_last_seen(*sym, stmt);
+                }
+                self.scan_ast_help(remainder);
+            }
 
             Stmt::Crash(msg, _crash_tag) => {
                 self.set_last_seen(*msg, stmt);
@@ -446,6 +486,7 @@ trait Backend<'a> {
 
     fn helper_proc_symbols(&self) -> &Vec<'a, (Symbol, ProcLayout<'a>)>;
     fn caller_procs(&self) -> &Vec<'a, CallerProc<'a>>;
+    fn expect_clone_helpers(&self) -> &Vec<'a, (InLayout<'a>, String)>;
 
     /// reset resets any registers or other values that may be occupied at the end of a procedure.
     /// It also passes basic procedure information to the builder for setup of the next function.
@@ -494,6 +535,54 @@ trait Backend<'a> {
         self.create_free_map();
         self.build_stmt(layout_ids, body, &proc.ret_layout);
 
+        let helper_proc_names = self.helper_proc_names(layout_ids);
+
+        let (bytes, relocs) = self.finalize();
+        (bytes, relocs, helper_proc_names)
+    }
+
+    /// build_expect_clone_helper creates the procedure that clones a value of the union `layout`
+    /// into the shared memory of an expect. Like `build_proc`, it returns the procedure bytes,
+    /// its relocations, and the names of the helpers it references.
+    fn build_expect_clone_helper(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        fn_name: String,
+        layout: InLayout<'a>,
+    ) -> (Vec<u8>, Vec<Relocation>, Vec<'a, (Symbol, String)>) {
+        let union_layout = match self.interner().get_repr(layout) {
+            LayoutRepr::Union(union_layout) => union_layout,
+            other => internal_error!("expect clone helpers are only used for unions: {other:?}"),
+        };
+
+        let ptr = self.debug_symbol("ptr");
+        let offset = self.debug_symbol("offset");
+        let extra = self.debug_symbol("extra_offset");
+        let value = self.debug_symbol("value");
+
+        let args = self.env().arena.alloc([
+            (Layout::U64, ptr),
+            (Layout::U64, offset),
+            (Layout::U64, extra),
+            (layout, value),
+        ]);
+
+        self.reset(fn_name, SelfRecursive::NotSelfRecursive);
+        self.load_args(args, &Layout::U64);
+        for (layout, sym) in args.iter() {
+            self.set_layout_map(*sym, layout);
+        }
+        self.build_clone_union_body(layout_ids, ptr, offset, extra, value, union_layout);
+
+        let helper_proc_names = self.helper_proc_names(layout_ids);
+
+        let (bytes, relocs) = self.finalize();
+        (bytes, relocs, helper_proc_names)
+    }
+
+    /// helper_proc_names gives the names of all the helper procedures (refcounting, caller procs
+    /// and expect clone helpers) that the procedure being built may reference.
+    fn helper_proc_names(&self, layout_ids: &mut LayoutIds<'a>) -> Vec<'a, (Symbol, String)> {
         let mut helper_proc_names = bumpalo::vec![in self.env().arena];
         helper_proc_names.reserve(self.helper_proc_symbols().len());
         for (rc_proc_sym, rc_proc_layout) in self.helper_proc_symbols() {
@@ -514,8 +603,11 @@ trait Backend<'a> {
             helper_proc_names.push((proc_symbol, name));
         }
 
-        let (bytes, relocs) = self.finalize();
-        (bytes, relocs, helper_proc_names)
+        for (_, name) in self.expect_clone_helpers() {
+            helper_proc_names.push((Symbol::CLONE, name.clone()));
+        }
+
+        helper_proc_names
     }
 
     /// build_stmt builds a statement and outputs at the end of the buffer.
@@ -633,8 +725,49 @@ trait Backend<'a> {
                 self.build_jump(id, args, arg_layouts.into_bump_slice(), ret_layout);
                 self.free_symbols(stmt);
             }
+            Stmt::Dbg {
+                symbol,
+                variable,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*symbol]);
+                    self.build_dbg(layout_ids, *symbol, *variable);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout)
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*condition]);
+                    self.load_literal_symbols(lookups);
+                    self.build_expect(layout_ids, *condition, *region, lookups, variables, true);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout)
+            }
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*condition]);
+                    self.load_literal_symbols(lookups);
+                    self.build_expect(layout_ids, *condition, *region, lookups, variables, false);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout)
+            }
             Stmt::Crash(msg, crash_tag) => self.roc_panic(*msg, *crash_tag),
-            x => todo!("the statement, {:?}", x),
         }
     }
 
@@ -673,6 +806,36 @@ trait Backend<'a> {
         ret_layout: &InLayout<'a>,
     );
 
+    /// build_dbg copies the value of `symbol` to the shared memory of the parent process,
+    /// and notifies the parent so it can print it.
+    fn build_dbg(&mut self, layout_ids: &mut LayoutIds<'a>, symbol: Symbol, variable: Variable);
+
+    /// build_clone_union_body generates the body of an expect clone helper. It copies `value` to
+    /// `ptr + offset`, anything it points to to `ptr + extra`, and returns the new extra offset.
+    fn build_clone_union_body(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        extra: Symbol,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+    );
+
+    /// build_expect checks `condition`, and if it is false copies the `lookups` to the
+    /// shared memory of the parent process. The parent is only notified if `notify_parent` is set;
+    /// otherwise the failure is picked up once the program finishes.
+    #[allow(clippy::too_many_arguments)]
+    fn build_expect(
+        &mut self,
+        layout_ids: &mut LayoutIds<'a>,
+        condition: Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        variables: &'a [LookupType],
+        notify_parent: bool,
+    );
+
     // build_join generates a instructions for a join statement.
     fn build_join(
         &mut self,
@@ -214,29 +214,25 @@ fn generate_roc_panic<'a, B: Backend<'a>>(backend: &mut B, output: &mut Object)
     let proc_offset = output.add_symbol_data(proc_id, text_section, proc_data, 16);
 
     for r in relocs {
-        let relocation = match r {
+        let relocations = match r {
             Relocation::LinkedData { offset, name } => {
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    write::Relocation {
-                        offset: offset + proc_offset,
-                        size: 32,
-                        kind: RelocationKind::GotRelative,
-                        encoding: RelocationEncoding::Generic,
-                        symbol: sym_id,
-                        addend: -4,
-                    }
+                    symbol_relocations(output, offset + proc_offset, sym_id, SymbolUse::Data)
                 } else {
                     internal_error!("failed to find data symbol for {:?}", name);
                 }
             }
             Relocation::LocalData { .. }
             | Relocation::LinkedFunction { .. }
+            | Relocation::LinkedFunctionPointer { .. }
             | Relocation::JmpToReturn { .. } => {
                 unreachable!("not currently created by build_roc_panic")
             }
         };
 
-        output.add_relocation(text_section, relocation).unwrap();
+        for relocation in relocations {
+            output.add_relocation(text_section, relocation).unwrap();
+        }
     }
 }
 
@@ -275,18 +271,11 @@ fn generate_wrapper<'a, B: Backend<'a>>(
     };
     output.add_symbol(symbol);
     if let Some(sym_id) = output.symbol_id(name) {
-        let reloc = write::Relocation {
-            offset: offset + proc_offset,
-            size: 32,
-            kind: RelocationKind::PltRelative,
-            encoding: RelocationEncoding::X86Branch,
-            symbol: sym_id,
-            addend: -4,
-        };
-
-        match output.add_relocation(text_section, reloc) {
-            Ok(obj) => obj,
-            Err(e) => internal_error!("{:?}", e),
+        for reloc in symbol_relocations(output, offset + proc_offset, sym_id, SymbolUse::Jump) {
+            match output.add_relocation(text_section, reloc) {
+                Ok(obj) => obj,
+                Err(e) => internal_error!("{:?}", e),
+            }
         }
     } else {
         internal_error!("failed to find fn symbol for {:?}", wraps);
@@ -551,6 +540,37 @@ fn build_object<'a, B: Backend<'a>>(
         )
     }
 
+    // Build helpers that clone values into the shared memory of expects.
+    // Building one helper can require another, so the list may grow while we go through it.
+    let mut index = 0;
+    while let Some((layout, fn_name)) = backend.expect_clone_helpers().get(index).cloned() {
+        index += 1;
+
+        let proc_id = match output.symbol_id(fn_name.as_bytes()) {
+            Some(proc_id) => proc_id,
+            None => internal_error!("failed to find fn symbol for {:?}", fn_name),
+        };
+        let section_id = match output.symbol(proc_id).section {
+            SymbolSection::Section(section_id) => section_id,
+            _ => internal_error!("expect clone helper {:?} has no section", fn_name),
+        };
+
+        let (proc_data, relocs, helper_names) =
+            backend.build_expect_clone_helper(&mut layout_ids, fn_name.clone(), layout);
+
+        add_proc_data(
+            &mut output,
+            &mut relocations,
+            data_section,
+            fn_name,
+            section_id,
+            proc_id,
+            &proc_data,
+            &relocs,
+            &helper_names,
+        );
+    }
+
     // Relocations for all procedures (user code & helpers)
     for (section_id, reloc) in relocations {
         match output.add_relocation(section_id, reloc) {
@@ -758,11 +778,39 @@ fn build_proc<'a, B: Backend<'a>>(
     proc_id: SymbolId,
     proc: Proc<'a>,
 ) {
-    let mut local_data_index = 0;
     let (proc_data, relocs, rc_proc_names) = backend.build_proc(proc, layout_ids);
-    let proc_offset = output.add_symbol_data(proc_id, section_id, &proc_data, 16);
+
+    add_proc_data(
+        output,
+        relocations,
+        data_section,
+        fn_name,
+        section_id,
+        proc_id,
+        &proc_data,
+        &relocs,
+        &rc_proc_names,
+    )
+}
+
+/// Adds the bytes of a procedure to its section, and the relocations for the data and
+/// functions it references. Undefined helper procedures among `rc_proc_names` get a symbol here.
+#[allow(clippy::too_many_arguments)]
+fn add_proc_data<'a>(
+    output: &mut Object,
+    relocations: &mut Vec<'a, (SectionId, object::write::Relocation)>,
+    data_section: SectionId,
+    fn_name: String,
+    section_id: SectionId,
+    proc_id: SymbolId,
+    proc_data: &[u8],
+    relocs: &[Relocation],
+    rc_proc_names: &[(symbol::Symbol, String)],
+) {
+    let mut local_data_index = 0;
+    let proc_offset = output.add_symbol_data(proc_id, section_id, proc_data, 16);
     for reloc in relocs.iter() {
-        let elfreloc = match reloc {
+        let elfrelocs = match reloc {
             Relocation::LocalData { offset, data } => {
                 let data_symbol = write::Symbol {
                     name: format!("{fn_name}.data{local_data_index}")
@@ -779,30 +827,24 @@ fn build_proc<'a, B: Backend<'a>>(
                 local_data_index += 1;
                 let data_id = output.add_symbol(data_symbol);
                 output.add_symbol_data(data_id, data_section, data, 4);
-                write::Relocation {
+                vec![write::Relocation {
                     offset: offset + proc_offset,
                     size: 32,
                     kind: RelocationKind::Relative,
                     encoding: RelocationEncoding::Generic,
                     symbol: data_id,
                     addend: -4,
-                }
+                }]
             }
             Relocation::LinkedData { offset, name } => {
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    write::Relocation {
-                        offset: offset + proc_offset,
-                        size: 32,
-                        kind: RelocationKind::GotRelative,
-                        encoding: RelocationEncoding::Generic,
-                        symbol: sym_id,
-                        addend: -4,
-                    }
+                    symbol_relocations(output, offset + proc_offset, sym_id, SymbolUse::Data)
                 } else {
                     internal_error!("failed to find data symbol for {:?}", name);
                 }
             }
-            Relocation::LinkedFunction { offset, name } => {
+            Relocation::LinkedFunction { offset, name }
+            | Relocation::LinkedFunctionPointer { offset, name } => {
                 // If the symbol is an undefined roc function, we need to add it here.
                 if output.symbol_id(name.as_bytes()).is_none() && name.starts_with("roc_") {
                     let builtin_symbol = Symbol {
@@ -843,21 +885,129 @@ fn build_proc<'a, B: Backend<'a>>(
                     }
                 }
 
+                let symbol_use = match reloc {
+                    Relocation::LinkedFunctionPointer { .. } => SymbolUse::FunctionPointer,
+                    _ => SymbolUse::Call,
+                };
+
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    write::Relocation {
-                        offset: offset + proc_offset,
-                        size: 32,
-                        kind: RelocationKind::PltRelative,
-                        encoding: RelocationEncoding::X86Branch,
-                        symbol: sym_id,
-                        addend: -4,
-                    }
+                    symbol_relocations(output, offset + proc_offset, sym_id, symbol_use)
                 } else {
                     internal_error!("failed to find fn symbol for {:?}", name);
                 }
             }
             Relocation::JmpToReturn { .. } => unreachable!(),
         };
-        relocations.push((section_id, elfreloc));
+        for elfreloc in elfrelocs {
+            relocations.push((section_id, elfreloc));
+        }
+    }
+}
+
+/// How generated code refers to a symbol. Each target needs different relocations for these.
+#[derive(Clone, Copy)]
+enum SymbolUse {
+    Call,
+    Jump,
+    FunctionPointer,
+    Data,
+}
+
+/// Builds the relocations for a reference to `symbol` from the instruction(s) at `offset`.
+/// On aarch64, addresses are loaded from the GOT with an `adrp` + `ldr` pair, which needs one relocation each.
+fn symbol_relocations(
+    output: &Object,
+    offset: u64,
+    symbol: SymbolId,
+    symbol_use: SymbolUse,
+) -> std::vec::Vec<write::Relocation> {
+    let relocation = |offset, size, kind, encoding, addend| write::Relocation {
+        offset,
+        size,
+        kind,
+        encoding,
+        symbol,
+        addend,
+    };
+
+    match (output.architecture(), output.format(), symbol_use) {
+        (Architecture::X86_64, _, SymbolUse::Data) => vec![relocation(
+            offset,
+            32,
+            RelocationKind::GotRelative,
+            RelocationEncoding::Generic,
+            -4,
+        )],
+        (Architecture::X86_64, _, _) => vec![relocation(
+            offset,
+            32,
+            RelocationKind::PltRelative,
+            RelocationEncoding::X86Branch,
+            -4,
+        )],
+        (Architecture::Aarch64, BinaryFormat::Elf, SymbolUse::Call) => vec![relocation(
+            offset,
+            26,
+            RelocationKind::PltRelative,
+            RelocationEncoding::AArch64Call,
+            0,
+        )],
+        (Architecture::Aarch64, BinaryFormat::Elf, SymbolUse::Jump) => vec![relocation(
+            offset,
+            26,
+            RelocationKind::Elf(object::elf::R_AARCH64_JUMP26),
+            RelocationEncoding::Generic,
+            0,
+        )],
+        (Architecture::Aarch64, BinaryFormat::Elf, _) => vec![
+            relocation(
+                offset,
+                21,
+                RelocationKind::Elf(object::elf::R_AARCH64_ADR_GOT_PAGE),
+                RelocationEncoding::Generic,
+                0,
+            ),
+            relocation(
+                offset + 4,
+                12,
+                RelocationKind::Elf(object::elf::R_AARCH64_LD64_GOT_LO12_NC),
+                RelocationEncoding::Generic,
+                0,
+            ),
+        ],
+        (Architecture::Aarch64, BinaryFormat::MachO, SymbolUse::Call | SymbolUse::Jump) => {
+            vec![relocation(
+                offset,
+                26,
+                RelocationKind::Relative,
+                RelocationEncoding::AArch64Call,
+                0,
+            )]
+        }
+        (Architecture::Aarch64, BinaryFormat::MachO, _) => vec![
+            relocation(
+                offset,
+                32,
+                RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_GOT_LOAD_PAGE21,
+                    relative: true,
+                },
+                RelocationEncoding::Generic,
+                0,
+            ),
+            relocation(
+                offset + 4,
+                32,
+                RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_GOT_LOAD_PAGEOFF12,
+                    relative: false,
+                },
+                RelocationEncoding::Generic,
+                0,
+            ),
+        ],
+        (arch, format, _) => {
+            internal_error!("relocations for {:?} {:?} are not supported", arch, format)
+        }
     }
 }
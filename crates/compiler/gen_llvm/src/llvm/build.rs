@@ -6,7 +6,7 @@ use crate::llvm::convert::{
 use crate::llvm::expect::{clone_to_shared_memory, SharedMemoryPointer};
 use crate::llvm::memcpy::build_memcpy;
 use crate::llvm::refcounting::{
-    build_reset, decrement_refcount_layout, increment_refcount_layout, PointerToRefcount,
+    build_reset, decrement_refcount_layout, increment_refcount_layout, Mode, PointerToRefcount,
 };
 use crate::llvm::struct_::{struct_from_fields, RocStruct};
 use crate::llvm::{erased, fn_ptr};
@@ -4924,6 +4924,10 @@ pub fn build_procedures<'a>(
     debug_output_file: Option<&Path>,
     glue_layouts: &GlueLayouts<'a>,
 ) {
+    // The glue refcounters need the same layout ids as the procedures, so that they call the
+    // same refcounting functions
+    let mut layout_ids = LayoutIds::default();
+
     let mod_solutions = build_procedures_help(
         env,
         layout_interner,
@@ -4931,6 +4935,7 @@ pub fn build_procedures<'a>(
         procedures,
         entry_point,
         debug_output_file,
+        &mut layout_ids,
     );
 
     let niche = Niche::NONE;
@@ -4968,6 +4973,80 @@ pub fn build_procedures<'a>(
             getter_name,
         );
     }
+
+    for refcounters in glue_layouts.refcounters.iter() {
+        for (symbol, mode) in [
+            (refcounters.incref, Mode::Inc),
+            (refcounters.decref, Mode::Dec),
+        ] {
+            expose_glue_refcounter(
+                env,
+                layout_interner,
+                &mut layout_ids,
+                symbol.as_str(&env.interns),
+                mode,
+                refcounters.layout,
+            );
+        }
+    }
+}
+
+/// Adds a C function which increfs or decrefs the value of this layout which its argument
+/// points to, for hosts which can't do that themselves because the layout depends on the app.
+fn expose_glue_refcounter<'a>(
+    env: &Env<'a, '_, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    layout_ids: &mut LayoutIds<'a>,
+    function_name: &str,
+    mode: Mode,
+    layout: InLayout<'a>,
+) {
+    // A layout can be exposed more than once, e.g. as both an argument and the result
+    if env.module.get_function(function_name).is_some() {
+        return;
+    }
+
+    let builder = env.builder;
+    let layout_repr = layout_interner.get_repr(layout);
+    let basic_type = basic_type_from_layout(env, layout_interner, layout_repr);
+    let argument_types = [basic_type
+        .ptr_type(AddressSpace::default())
+        .as_basic_type_enum()];
+    let function_spec = FunctionSpec::cconv(env, CCReturn::Void, None, &argument_types);
+
+    let function_value = add_func(
+        env.context,
+        env.module,
+        function_name,
+        function_spec,
+        Linkage::External,
+    );
+
+    let subprogram = env.new_subprogram(function_name);
+    function_value.set_subprogram(subprogram);
+
+    let entry = env.context.append_basic_block(function_value, "entry");
+
+    builder.position_at_end(entry);
+
+    debug_info_init!(env, function_value);
+
+    let ptr = function_value
+        .get_nth_param(0)
+        .unwrap()
+        .into_pointer_value();
+    let value = load_roc_value(env, layout_interner, layout_repr, ptr, "glue_value");
+
+    match mode {
+        Mode::Inc => {
+            increment_refcount_layout(env, layout_interner, layout_ids, 1, value, layout);
+        }
+        Mode::Dec => {
+            decrement_refcount_layout(env, layout_interner, layout_ids, value, layout);
+        }
+    }
+
+    builder.build_return(None);
 }
 
 pub fn build_wasm_test_wrapper<'a, 'ctx>(
@@ -4984,6 +5063,7 @@ pub fn build_wasm_test_wrapper<'a, 'ctx>(
         procedures,
         EntryPoint::Single(entry_point),
         Some(&std::env::temp_dir().join("test.ll")),
+        &mut LayoutIds::default(),
     );
 
     promote_to_wasm_test_wrapper(
@@ -5009,6 +5089,7 @@ pub fn build_procedures_return_main<'a, 'ctx>(
         procedures,
         EntryPoint::Single(entry_point),
         Some(&std::env::temp_dir().join("test.ll")),
+        &mut LayoutIds::default(),
     );
 
     promote_to_main_function(
@@ -5036,6 +5117,7 @@ pub fn build_procedures_expose_expects<'a>(
         procedures,
         entry_point,
         Some(&std::env::temp_dir().join("test.ll")),
+        &mut LayoutIds::default(),
     );
 
     let captures_niche = Niche::NONE;
@@ -5098,8 +5180,8 @@ fn build_procedures_help<'a>(
     procedures: MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
     entry_point: EntryPoint<'a>,
     debug_output_file: Option<&Path>,
+    layout_ids: &mut LayoutIds<'a>,
 ) -> &'a ModSolutions {
-    let mut layout_ids = roc_mono::layout::LayoutIds::default();
     let mut scope = Scope::default();
 
     let it = procedures.iter().map(|x| x.1);
@@ -5130,7 +5212,7 @@ fn build_procedures_help<'a>(
         mod_solutions,
         procedures,
         &mut scope,
-        &mut layout_ids,
+        layout_ids,
     );
 
     let (_, function_pass) = construct_optimization_passes(env.module, opt_level);
@@ -5148,7 +5230,7 @@ fn build_procedures_help<'a>(
                 env,
                 layout_interner,
                 mod_solutions,
-                &mut layout_ids,
+                layout_ids,
                 func_spec_solutions,
                 scope.clone(),
                 &proc,
@@ -4,8 +4,9 @@ use crate::llvm::build::{CCReturn, Env, FunctionSpec};
 use crate::llvm::convert::zig_str_type;
 use inkwell::module::Linkage;
 use inkwell::types::BasicType;
-use inkwell::AddressSpace;
+use inkwell::{AddressSpace, AtomicOrdering, AtomicRMWBinOp};
 use roc_builtins::bitcode;
+use roc_mono::coverage::COVERAGE_HIT;
 
 use super::build::get_sjlj_buffer;
 use super::intrinsics::LLVM_LONGJMP;
@@ -268,6 +269,71 @@ pub fn add_sjlj_roc_panic(env: &Env<'_, '_, '_>) {
     }
 }
 
+/// The host points this global at one `u64` counter per coverage point before running any code
+pub const COVERAGE_COUNTERS: &str = "roc__coverage_counters";
+
+/// Define the function that code instrumented for coverage calls whenever it reaches a point:
+/// it adds one to that point's counter, if the host has provided any counters.
+pub fn add_coverage_counters(env: &Env<'_, '_, '_>) {
+    let ctx = env.context;
+    let module = env.module;
+    let builder = env.builder;
+
+    // Nothing was instrumented, so nothing ever calls this function
+    let fn_val = match module.get_function(COVERAGE_HIT) {
+        Some(fn_val) => fn_val,
+        None => return,
+    };
+
+    fn_val.set_linkage(Linkage::Internal);
+
+    let counter_type = ctx.i64_type();
+    let counters_type = counter_type.ptr_type(AddressSpace::default());
+
+    let global = module.add_global(counters_type, None, COVERAGE_COUNTERS);
+    global.set_linkage(Linkage::External);
+    global.set_initializer(&counters_type.const_null());
+
+    let entry = ctx.append_basic_block(fn_val, "entry");
+    let increment = ctx.append_basic_block(fn_val, "increment");
+    let done = ctx.append_basic_block(fn_val, "done");
+
+    builder.position_at_end(entry);
+
+    let counters = builder
+        .new_build_load(counters_type, global.as_pointer_value(), "load_counters")
+        .into_pointer_value();
+    let no_counters = builder.build_is_null(counters, "no_counters");
+
+    builder.build_conditional_branch(no_counters, done, increment);
+
+    builder.position_at_end(increment);
+
+    let index = fn_val.get_first_param().unwrap().into_int_value();
+    let index = builder.build_int_z_extend(index, ctx.i64_type(), "index");
+    let counter_ptr =
+        unsafe { builder.new_build_in_bounds_gep(counter_type, counters, &[index], "counter_ptr") };
+
+    // Tests may run in several processes at once, which all share the counters
+    builder
+        .build_atomicrmw(
+            AtomicRMWBinOp::Add,
+            counter_ptr,
+            counter_type.const_int(1, false),
+            AtomicOrdering::Monotonic,
+        )
+        .unwrap();
+
+    builder.build_unconditional_branch(done);
+
+    builder.position_at_end(done);
+    builder.build_return(None);
+
+    if cfg!(debug_assertions) {
+        crate::llvm::build::verify_fn(fn_val);
+    }
+}
+
 pub fn build_longjmp_call(env: &Env) {
     let jmp_buf = get_sjlj_buffer(env);
     if cfg!(target_arch = "aarch64") {
@@ -6,7 +6,7 @@ use roc_collections::all::MutMap;
 use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::low_level::{LowLevel, LowLevelWrapperType};
 use roc_module::symbol::{Interns, Symbol};
-use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, REFCOUNT_MAX};
+use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, InspectTypes, REFCOUNT_MAX};
 use roc_mono::ir::{
     BranchInfo, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
     Param, Proc, ProcLayout, Stmt,
@@ -15,7 +15,9 @@ use roc_mono::layout::{
     Builtin, InLayout, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
+use roc_region::all::Region;
 use roc_std::RocDec;
+use roc_types::subs::Variable;
 
 use roc_wasm_module::linking::{DataSymbol, WasmObjectSymbol};
 use roc_wasm_module::sections::{
@@ -35,6 +37,11 @@ use crate::{
     TARGET_INFO,
 };
 
+/// Optional host function for `dbg`
+const ROC_DBG: &str = "roc_dbg";
+/// Optional host function for a failed `expect`
+const ROC_EXPECT_FAILED: &str = "roc_expect_failed";
+
 #[derive(Clone, Copy, Debug)]
 pub enum ProcSource {
     Roc,
@@ -724,9 +731,24 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                 _ => self.stmt_refcounting(modify, following),
             },
 
-            Stmt::Dbg { .. } => todo!("dbg is not implemented in the wasm backend"),
-            Stmt::Expect { .. } => todo!("expect is not implemented in the wasm backend"),
-            Stmt::ExpectFx { .. } => todo!("expect-fx is not implemented in the wasm backend"),
+            Stmt::Dbg {
+                symbol,
+                variable,
+                remainder,
+            } => self.stmt_dbg(*symbol, *variable, remainder),
+
+            Stmt::Expect {
+                condition,
+                region,
+                remainder,
+                ..
+            }
+            | Stmt::ExpectFx {
+                condition,
+                region,
+                remainder,
+                ..
+            } => self.stmt_expect(*condition, *region, remainder),
 
             Stmt::Crash(sym, tag) => self.stmt_crash(*sym, *tag),
         }
@@ -1039,6 +1061,136 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         self.stmt(following);
     }
 
+    fn host_provides(&self, name: &str) -> bool {
+        self.host_lookup.iter().any(|(fn_name, _)| *fn_name == name)
+    }
+
+    /// `dbg` calls `roc_dbg(loc: *RocStr, msg: *RocStr)` if the host provides it.
+    /// Otherwise it does nothing, as in an optimized build.
+    fn stmt_dbg(&mut self, symbol: Symbol, variable: Variable, following: &'a Stmt<'a>) {
+        if !self.host_provides(ROC_DBG) {
+            self.stmt(following);
+            return;
+        }
+
+        let loc = self.env.source_positions.describe_dbg(self.interns, symbol);
+        let loc_sym = self.create_str_literal("dbg_loc", &loc);
+        let msg_sym = self.dbg_msg(symbol, variable);
+
+        self.storage
+            .load_symbols(&mut self.code_builder, &[loc_sym, msg_sym]);
+        self.call_host_fn_after_loading_args(ROC_DBG, 2, false);
+
+        self.stmt_refcounting(&ModifyRc::Dec(msg_sym), following);
+    }
+
+    /// Render a value for `dbg` the way the REPL and `roc test` show it, using a helper proc
+    /// which is directed by the value's type. The `Str` it returns needs to be decremented.
+    fn dbg_msg(&mut self, symbol: Symbol, variable: Variable) -> Symbol {
+        let env = self.env;
+        let module_id = symbol.module_id();
+        let subs = match env.source_positions.types.get(&module_id) {
+            Some(subs) => subs,
+            None => internal_error!("No types for the dbg in module {:?}", module_id),
+        };
+
+        let empty_sym = self.create_str_literal("dbg_empty", "");
+        let msg_sym = self.create_symbol("dbg_msg");
+        let msg_storage = self.storage.allocate_var(
+            self.layout_interner,
+            Layout::STR,
+            msg_sym,
+            StoredVarKind::Variable,
+        );
+
+        // The names of opaque types are in `interns`, so we can't borrow it while adding symbols
+        let mut ident_ids = self
+            .interns
+            .all_ident_ids
+            .get(&self.env.module_id)
+            .unwrap()
+            .clone();
+        let types = InspectTypes {
+            module_id,
+            subs,
+            interns: self.interns,
+        };
+        let layout = self.storage.symbol_layouts[&symbol];
+        let arguments = self.env.arena.alloc([symbol, empty_sym]);
+        let (inspect_call_expr, new_specializations) = self.helper_proc_gen.call_inspect(
+            &mut ident_ids,
+            self.layout_interner,
+            types,
+            variable,
+            layout,
+            arguments,
+        );
+        self.interns
+            .all_ident_ids
+            .insert(self.env.module_id, ident_ids);
+
+        // If any new specializations were created, register their symbol data
+        for (spec_sym, spec_layout) in new_specializations.into_iter() {
+            self.register_helper_proc(spec_sym, spec_layout, ProcSource::Helper);
+        }
+
+        self.expr(
+            msg_sym,
+            self.env.arena.alloc(inspect_call_expr),
+            Layout::STR,
+            &msg_storage,
+        );
+
+        msg_sym
+    }
+
+    /// A failed `expect` calls `roc_expect_failed(loc: *RocStr)` if the host provides it.
+    /// Otherwise it does nothing, as in an optimized build.
+    fn stmt_expect(&mut self, condition: Symbol, region: Region, following: &'a Stmt<'a>) {
+        if !self.host_provides(ROC_EXPECT_FAILED) {
+            self.stmt(following);
+            return;
+        }
+
+        let loc = self
+            .env
+            .source_positions
+            .describe_expect(self.interns, condition, region);
+        let loc_sym = self.create_str_literal("expect_loc", &loc);
+
+        self.storage
+            .load_symbols(&mut self.code_builder, &[condition]);
+        self.code_builder.i32_eqz();
+        self.code_builder.if_();
+        {
+            self.storage
+                .load_symbols(&mut self.code_builder, &[loc_sym]);
+            self.call_host_fn_after_loading_args(ROC_EXPECT_FAILED, 1, false);
+        }
+        self.code_builder.end();
+
+        self.stmt(following);
+    }
+
+    /// Create a new `Str` symbol containing a string constant
+    fn create_str_literal(&mut self, debug_name: &str, string: &str) -> Symbol {
+        let sym = self.create_symbol(debug_name);
+        let storage = self.storage.allocate_var(
+            self.layout_interner,
+            Layout::STR,
+            sym,
+            StoredVarKind::Variable,
+        );
+        let (local_id, offset) = match storage {
+            StoredValue::StackMemory { location, .. } => {
+                location.local_and_offset(self.storage.stack_frame_pointer)
+            }
+            _ => internal_error!("String must always have stack memory"),
+        };
+        self.expr_string_literal(string, local_id, offset);
+        sym
+    }
+
     pub fn stmt_internal_error(&mut self, msg: &'a str) {
         let msg_sym = self.create_symbol("panic_str");
         let msg_storage = self.storage.allocate_var(
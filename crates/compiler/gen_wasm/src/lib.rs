@@ -12,13 +12,16 @@ pub mod wasm32_sized;
 use bitvec::prelude::BitVec;
 use bumpalo::collections::Vec;
 use bumpalo::{self, Bump};
+use std::path::{Path, PathBuf};
 
 use roc_collections::all::{MutMap, MutSet};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{Proc, ProcLayout};
 use roc_mono::layout::{LayoutIds, STLayoutInterner};
+use roc_region::all::{LineInfo, Region};
 use roc_target::TargetInfo;
+use roc_types::subs::Subs;
 use roc_wasm_module::parse::ParseError;
 use roc_wasm_module::{Align, LocalId, ValueType, WasmModule};
 
@@ -46,12 +49,61 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub stack_bytes: u32,
+    pub source_positions: SourcePositions,
 }
 
 impl Env<'_> {
     pub const DEFAULT_STACK_BYTES: u32 = 1024 * 1024;
 }
 
+/// Source files and `dbg` regions, used to tell the host where a `dbg` or failed `expect` is.
+/// Modules that were not inserted are reported by name only.
+/// Also the types of the values that modules `dbg`, which are needed to show them.
+#[derive(Default)]
+pub struct SourcePositions {
+    files: MutMap<ModuleId, (PathBuf, LineInfo)>,
+    dbgs: MutMap<Symbol, Region>,
+    types: MutMap<ModuleId, Subs>,
+}
+
+impl SourcePositions {
+    pub fn insert_module(&mut self, module_id: ModuleId, path: &Path, src: &str) {
+        self.files
+            .insert(module_id, (path.to_path_buf(), LineInfo::new(src)));
+    }
+
+    /// `dbg` statements only carry their symbol, so their regions are looked up separately
+    pub fn insert_dbg(&mut self, symbol: Symbol, region: Region) {
+        self.dbgs.insert(symbol, region);
+    }
+
+    /// The subs that the variables of a module's `dbg` statements refer to,
+    /// i.e. the `subs` of its expectations
+    pub fn insert_types(&mut self, module_id: ModuleId, subs: Subs) {
+        self.types.insert(module_id, subs);
+    }
+
+    /// Describe a position as `path line:column`, the same as the other backends
+    fn describe(&self, interns: &Interns, module_id: ModuleId, region: Option<Region>) -> String {
+        match (self.files.get(&module_id), region) {
+            (Some((path, line_info)), Some(region)) => {
+                let start = line_info.convert_pos(region.start());
+                format!("{} {}:{}", path.display(), start.line + 1, start.column + 1)
+            }
+            _ => interns.module_name(module_id).to_string(),
+        }
+    }
+
+    fn describe_dbg(&self, interns: &Interns, symbol: Symbol) -> String {
+        let region = self.dbgs.get(&symbol).copied();
+        self.describe(interns, symbol.module_id(), region)
+    }
+
+    fn describe_expect(&self, interns: &Interns, condition: Symbol, region: Region) -> String {
+        self.describe(interns, condition.module_id(), Some(region))
+    }
+}
+
 /// Parse the preprocessed host binary
 /// If successful, the module can be passed to build_app_binary
 pub fn parse_host<'a>(arena: &'a Bump, host_bytes: &[u8]) -> Result<WasmModule<'a>, ParseError> {
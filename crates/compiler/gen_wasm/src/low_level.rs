@@ -41,6 +41,10 @@ impl CodeGenNumType {
 
 const UPDATE_MODE_IMMUTABLE: i32 = 0;
 
+/// Tag IDs of the `[EQ, GT, LT]` value returned by `Num.compare`
+const ROC_ORDER_GT: i32 = 1;
+const ROC_ORDER_LT: i32 = 2;
+
 impl From<InLayout<'_>> for CodeGenNumType {
     fn from(layout: InLayout<'_>) -> CodeGenNumType {
         use CodeGenNumType::*;
@@ -121,6 +125,28 @@ fn symbol_is_signed_int(backend: &WasmBackend<'_, '_>, symbol: Symbol) -> bool {
     layout_is_signed_int(backend.storage.symbol_layouts[&symbol])
 }
 
+/// The width to use when calling a Zig integer builtin on a 128-bit number.
+/// Dec is an I128 with an implicit decimal point, so comparison and `abs` work the same way.
+fn int_width_128(layout: InLayout) -> IntWidth {
+    if layout == Layout::U128 {
+        IntWidth::U128
+    } else {
+        IntWidth::I128
+    }
+}
+
+fn stack_memory_local_and_offset(
+    backend: &WasmBackend<'_, '_>,
+    stored: &StoredValue,
+) -> (LocalId, u32) {
+    match stored {
+        StoredValue::StackMemory { location, .. } => {
+            location.local_and_offset(backend.storage.stack_frame_pointer)
+        }
+        _ => internal_error!("128-bit numbers and structs should be in stack memory"),
+    }
+}
+
 pub struct LowLevelCall<'a> {
     pub lowlevel: LowLevel,
     pub arguments: &'a [Symbol],
@@ -147,7 +173,19 @@ impl<'a> LowLevelCall<'a> {
     }
 
     fn load_args_and_call_zig(&self, backend: &mut WasmBackend<'a, '_>, name: &'a str) {
-        let (num_wasm_args, has_return_val, ret_zig_packed_struct) = self.load_args(backend);
+        let signature = self.load_args(backend);
+        self.call_zig_after_loading_args(backend, name, signature);
+    }
+
+    /// Call a Zig builtin when the arguments are already on the value stack,
+    /// using the signature returned by `load_args`
+    fn call_zig_after_loading_args(
+        &self,
+        backend: &mut WasmBackend<'a, '_>,
+        name: &'a str,
+        signature: (usize, bool, bool),
+    ) {
+        let (num_wasm_args, has_return_val, ret_zig_packed_struct) = signature;
         backend.call_host_fn_after_loading_args(name, num_wasm_args, has_return_val);
 
         if ret_zig_packed_struct {
@@ -1081,7 +1119,7 @@ impl<'a> LowLevelCall<'a> {
                 }
             }
             NumGt => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     I32 => {
                         if symbol_is_signed_int(backend, self.arguments[0]) {
@@ -1099,11 +1137,15 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_gt(),
                     F64 => backend.code_builder.f64_gt(),
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 | Decimal => {
+                        self.compare_128bit(backend, signature);
+                        backend.code_builder.i32_const(ROC_ORDER_GT);
+                        backend.code_builder.i32_eq();
+                    }
                 }
             }
             NumGte => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     I32 => {
                         if symbol_is_signed_int(backend, self.arguments[0]) {
@@ -1121,11 +1163,15 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_ge(),
                     F64 => backend.code_builder.f64_ge(),
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 | Decimal => {
+                        self.compare_128bit(backend, signature);
+                        backend.code_builder.i32_const(ROC_ORDER_LT);
+                        backend.code_builder.i32_ne();
+                    }
                 }
             }
             NumLt => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     I32 => {
                         if symbol_is_signed_int(backend, self.arguments[0]) {
@@ -1143,11 +1189,15 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_lt(),
                     F64 => backend.code_builder.f64_lt(),
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 | Decimal => {
+                        self.compare_128bit(backend, signature);
+                        backend.code_builder.i32_const(ROC_ORDER_LT);
+                        backend.code_builder.i32_eq();
+                    }
                 }
             }
             NumLte => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let layout = backend.storage.symbol_layouts[&self.arguments[0]];
                 match CodeGenNumType::from(layout) {
                     I32 => {
@@ -1166,7 +1216,11 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_le(),
                     F64 => backend.code_builder.f64_le(),
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 | Decimal => {
+                        self.compare_128bit(backend, signature);
+                        backend.code_builder.i32_const(ROC_ORDER_GT);
+                        backend.code_builder.i32_ne();
+                    }
                 }
             }
             NumCompare => {
@@ -1216,20 +1270,25 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.f64_lt();
                         backend.code_builder.i32_add();
                     }
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 | Decimal => {
+                        let signature = self.load_args(backend);
+                        self.compare_128bit(backend, signature);
+                    }
                 }
             }
             NumDivFrac => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     F32 => backend.code_builder.f32_div(),
                     F64 => backend.code_builder.f64_div(),
-                    Decimal => self.load_args_and_call_zig(backend, bitcode::DEC_DIV),
+                    Decimal => {
+                        self.call_zig_after_loading_args(backend, bitcode::DEC_DIV, signature)
+                    }
                     x => todo!("{:?} for {:?}", self.lowlevel, x),
                 }
             }
             NumDivTruncUnchecked => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let is_signed = symbol_is_signed_int(backend, self.arguments[0]);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     I32 => {
@@ -1246,6 +1305,11 @@ impl<'a> LowLevelCall<'a> {
                             backend.code_builder.i64_div_u()
                         }
                     }
+                    // from compiler_rt
+                    I128 if is_signed => {
+                        self.call_zig_after_loading_args(backend, "__divti3", signature)
+                    }
+                    I128 => self.call_zig_after_loading_args(backend, "__udivti3", signature),
                     x => todo!("{:?} for {:?}", self.lowlevel, x),
                 }
             }
@@ -1257,10 +1321,16 @@ impl<'a> LowLevelCall<'a> {
             },
 
             NumRemUnchecked => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
+                let is_signed = symbol_is_signed_int(backend, self.arguments[0]);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     I32 => backend.code_builder.i32_rem_s(),
                     I64 => backend.code_builder.i64_rem_s(),
+                    // from compiler_rt
+                    I128 if is_signed => {
+                        self.call_zig_after_loading_args(backend, "__modti3", signature)
+                    }
+                    I128 => self.call_zig_after_loading_args(backend, "__umodti3", signature),
                     _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
                 }
             }
@@ -1367,7 +1437,7 @@ impl<'a> LowLevelCall<'a> {
                 const PANIC_MSG: &str =
                     "integer absolute overflowed because its argument is the minimum value";
 
-                self.load_args(backend);
+                let signature = self.load_args(backend);
 
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => {
@@ -1424,14 +1494,21 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_abs(),
                     F64 => backend.code_builder.f64_abs(),
-                    _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
+                    I128 | Decimal => {
+                        let width = int_width_128(self.ret_layout);
+                        self.call_zig_after_loading_args(
+                            backend,
+                            &bitcode::NUM_ABS[width],
+                            signature,
+                        );
+                    }
                 }
             }
             NumNeg => {
                 const PANIC_MSG: &str =
                     "integer negation overflowed because its argument is the minimum value";
 
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => {
                         backend.code_builder.i32_const(i32::MIN);
@@ -1457,7 +1534,17 @@ impl<'a> LowLevelCall<'a> {
                     }
                     F32 => backend.code_builder.f32_neg(),
                     F64 => backend.code_builder.f64_neg(),
-                    _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
+                    I128 => {
+                        let width = int_width_128(self.ret_layout);
+                        self.call_zig_after_loading_args(
+                            backend,
+                            &bitcode::NUM_NEG[width],
+                            signature,
+                        );
+                    }
+                    Decimal => {
+                        self.call_zig_after_loading_args(backend, bitcode::DEC_NEGATE, signature)
+                    }
                 }
             }
             NumSin => match self.ret_layout_raw {
@@ -1491,7 +1578,7 @@ impl<'a> LowLevelCall<'a> {
                 _ => panic_ret_type(),
             },
             NumToFrac => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let ret_type = CodeGenNumType::from(self.ret_layout);
                 let arg_type = CodeGenNumType::for_symbol(backend, self.arguments[0]);
                 match (ret_type, arg_type) {
@@ -1505,6 +1592,10 @@ impl<'a> LowLevelCall<'a> {
                     (F64, F32) => backend.code_builder.f64_promote_f32(),
                     (F64, F64) => {}
 
+                    (F32 | F64, I128 | Decimal) => {
+                        self.num_128bit_to_float(backend, ret_type, signature)
+                    }
+
                     _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
                 }
             }
@@ -1549,27 +1640,48 @@ impl<'a> LowLevelCall<'a> {
                 _ => panic_ret_type(),
             },
             NumRound => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let arg_type = CodeGenNumType::for_symbol(backend, self.arguments[0]);
                 let ret_type = CodeGenNumType::from(self.ret_layout);
 
                 let width = match ret_type {
                     CodeGenNumType::I32 => IntWidth::I32,
                     CodeGenNumType::I64 => IntWidth::I64,
-                    CodeGenNumType::I128 => todo!("{:?} for I128", self.lowlevel),
+                    CodeGenNumType::I128 => int_width_128(self.ret_layout),
                     _ => internal_error!("Invalid return type for round: {:?}", ret_type),
                 };
 
                 match arg_type {
-                    F32 => self.load_args_and_call_zig(backend, &bitcode::NUM_ROUND_F32[width]),
-                    F64 => self.load_args_and_call_zig(backend, &bitcode::NUM_ROUND_F64[width]),
+                    F32 => self.call_zig_after_loading_args(
+                        backend,
+                        &bitcode::NUM_ROUND_F32[width],
+                        signature,
+                    ),
+                    F64 => self.call_zig_after_loading_args(
+                        backend,
+                        &bitcode::NUM_ROUND_F64[width],
+                        signature,
+                    ),
                     _ => internal_error!("Invalid argument type for round: {:?}", arg_type),
                 }
             }
             NumCeiling | NumFloor => {
-                self.load_args(backend);
                 let arg_type = CodeGenNumType::for_symbol(backend, self.arguments[0]);
                 let ret_type = CodeGenNumType::from(self.ret_layout);
+                if ret_type == I128 {
+                    let width = int_width_128(self.ret_layout);
+                    let name = match (arg_type, self.lowlevel) {
+                        (F32, NumCeiling) => &bitcode::NUM_CEILING_F32[width],
+                        (F64, NumCeiling) => &bitcode::NUM_CEILING_F64[width],
+                        (F32, NumFloor) => &bitcode::NUM_FLOOR_F32[width],
+                        (F64, NumFloor) => &bitcode::NUM_FLOOR_F64[width],
+                        _ => internal_error!("Invalid argument type for ceiling: {:?}", arg_type),
+                    };
+                    self.load_args_and_call_zig(backend, name);
+                    return;
+                }
+
+                self.load_args(backend);
                 match (arg_type, self.lowlevel) {
                     (F32, NumCeiling) => {
                         backend.code_builder.f32_ceil();
@@ -1591,12 +1703,11 @@ impl<'a> LowLevelCall<'a> {
                     (I32, F64) => backend.code_builder.i32_trunc_s_f64(),
                     (I64, F32) => backend.code_builder.i64_trunc_s_f32(),
                     (I64, F64) => backend.code_builder.i64_trunc_s_f64(),
-                    (I128, _) => todo!("{:?} for I128", self.lowlevel),
                     _ => panic_ret_type(),
                 }
             }
             NumPowInt => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let base_type = CodeGenNumType::for_symbol(backend, self.arguments[0]);
                 let exponent_type = CodeGenNumType::for_symbol(backend, self.arguments[1]);
                 let ret_type = CodeGenNumType::from(self.ret_layout);
@@ -1607,11 +1718,11 @@ impl<'a> LowLevelCall<'a> {
                 let width = match ret_type {
                     CodeGenNumType::I32 => IntWidth::I32,
                     CodeGenNumType::I64 => IntWidth::I64,
-                    CodeGenNumType::I128 => todo!("{:?} for I128", self.lowlevel),
+                    CodeGenNumType::I128 => int_width_128(self.ret_layout),
                     _ => internal_error!("Invalid return type for pow: {:?}", ret_type),
                 };
 
-                self.load_args_and_call_zig(backend, &bitcode::NUM_POW_INT[width])
+                self.call_zig_after_loading_args(backend, &bitcode::NUM_POW_INT[width], signature)
             }
 
             NumIsNan => num_is_nan(backend, self.arguments[0]),
@@ -1641,45 +1752,71 @@ impl<'a> LowLevelCall<'a> {
             NumBytesToU64 => self.load_args_and_call_zig(backend, bitcode::NUM_BYTES_TO_U64),
             NumBytesToU128 => self.load_args_and_call_zig(backend, bitcode::NUM_BYTES_TO_U128),
             NumBitwiseAnd => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => backend.code_builder.i32_and(),
                     I64 => backend.code_builder.i64_and(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => {
+                        let width = int_width_128(self.ret_layout);
+                        self.call_zig_after_loading_args(
+                            backend,
+                            &bitcode::NUM_BITWISE_AND[width],
+                            signature,
+                        );
+                    }
                     _ => panic_ret_type(),
                 }
             }
             NumBitwiseXor => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => backend.code_builder.i32_xor(),
                     I64 => backend.code_builder.i64_xor(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => {
+                        let width = int_width_128(self.ret_layout);
+                        self.call_zig_after_loading_args(
+                            backend,
+                            &bitcode::NUM_BITWISE_XOR[width],
+                            signature,
+                        );
+                    }
                     _ => panic_ret_type(),
                 }
             }
             NumBitwiseOr => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => backend.code_builder.i32_or(),
                     I64 => backend.code_builder.i64_or(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => {
+                        let width = int_width_128(self.ret_layout);
+                        self.call_zig_after_loading_args(
+                            backend,
+                            &bitcode::NUM_BITWISE_OR[width],
+                            signature,
+                        );
+                    }
                     _ => panic_ret_type(),
                 }
             }
             NumShiftLeftBy => {
                 let num = self.arguments[0];
                 let bits = self.arguments[1];
-                backend
-                    .storage
-                    .load_symbols(&mut backend.code_builder, &[num, bits]);
                 match CodeGenNumType::from(self.ret_layout) {
-                    I32 => backend.code_builder.i32_shl(),
+                    I32 => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[num, bits]);
+                        backend.code_builder.i32_shl();
+                    }
                     I64 => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[num, bits]);
                         backend.code_builder.i64_extend_u_i32();
                         backend.code_builder.i64_shl();
                     }
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => self.load_args_and_call_zig(backend, bitcode::NUM_SHIFT_LEFT_BY_I128),
                     _ => panic_ret_type(),
                 }
             }
@@ -1726,7 +1863,7 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.i64_extend_u_i32();
                         backend.code_builder.i64_shr_s();
                     }
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => self.load_args_and_call_zig(backend, bitcode::NUM_SHIFT_RIGHT_BY_I128),
                     _ => panic_ret_type(),
                 }
             }
@@ -1821,28 +1958,47 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.get_local(frame_ptr);
                         backend.code_builder.i64_load(Align::Bytes8, offset);
                     }
+                    (I128, I32) => {
+                        // Symbols are loaded as if for a call, so the i128 "return address" and i32 value are on the value stack
+                        self.load_args(backend);
+                        if arg_width.is_signed() {
+                            backend.code_builder.i64_extend_s_i32()
+                        } else {
+                            backend.code_builder.i64_extend_u_i32()
+                        }
+                        backend.code_builder.i64_store(Align::Bytes8, 0);
+                        self.extend_128bit_high_half(backend, arg_width.is_signed());
+                    }
                     (I128, I64) => {
                         // Symbols are loaded as if for a call, so the i128 "return address" and i64 value are on the value stack
                         self.load_args(backend);
                         backend.code_builder.i64_store(Align::Bytes8, 0);
-
-                        // Zero the most significant 64 bits
-                        let (frame_ptr, offset) = match &self.ret_storage {
-                            StoredValue::StackMemory { location, .. } => {
-                                location.local_and_offset(backend.storage.stack_frame_pointer)
-                            }
-                            _ => internal_error!("I128 should be in stack memory"),
-                        };
-                        backend.code_builder.get_local(frame_ptr);
-                        backend.code_builder.i64_const(0);
-                        backend.code_builder.i64_store(Align::Bytes8, offset + 8);
+                        self.extend_128bit_high_half(backend, arg_width.is_signed());
+                    }
+                    (I128, I128) => {
+                        // I128 and U128 have the same representation, so just copy the bytes
+                        let (ret_local, ret_offset) =
+                            stack_memory_local_and_offset(backend, &self.ret_storage);
+                        let arg_storage = backend.storage.get(&self.arguments[0]).to_owned();
+                        let (arg_local, arg_offset) =
+                            stack_memory_local_and_offset(backend, &arg_storage);
+                        for half in [0, 8] {
+                            backend.code_builder.get_local(ret_local);
+                            backend.code_builder.get_local(arg_local);
+                            backend
+                                .code_builder
+                                .i64_load(Align::Bytes8, arg_offset + half);
+                            backend
+                                .code_builder
+                                .i64_store(Align::Bytes8, ret_offset + half);
+                        }
                     }
 
                     _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
                 }
             }
             NumToFloatCast => {
-                self.load_args(backend);
+                let signature = self.load_args(backend);
                 let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
                 let arg_signed = match backend.layout_interner.get_repr(arg_layout) {
                     LayoutRepr::Builtin(Builtin::Int(w)) => w.is_signed(),
@@ -1871,6 +2027,7 @@ impl<'a> LowLevelCall<'a> {
                         }
                     }
                     (F64, F64) => {}
+                    (F64, F32) => backend.code_builder.f64_promote_f32(),
                     (F64, I32) => {
                         if arg_signed {
                             backend.code_builder.f64_convert_s_i32()
@@ -1885,6 +2042,9 @@ impl<'a> LowLevelCall<'a> {
                             backend.code_builder.f64_convert_u_i64()
                         }
                     }
+                    (F32 | F64, I128 | Decimal) => {
+                        self.num_128bit_to_float(backend, ret_type, signature)
+                    }
                     _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
                 }
             }
@@ -1930,7 +2090,69 @@ impl<'a> LowLevelCall<'a> {
                 }
             }
             NumToFloatChecked => {
-                todo!("implement toF32Checked and toF64Checked");
+                let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
+
+                let ret_width = match self.ret_layout_raw {
+                    LayoutRepr::Struct(&[ret, ..]) => match backend.layout_interner.get_repr(ret) {
+                        LayoutRepr::Builtin(Builtin::Float(ret_width)) => ret_width,
+                        _ => {
+                            internal_error!(
+                                "NumToFloatChecked is not defined for signature {:?} -> {:?}",
+                                arg_layout,
+                                self.ret_layout
+                            );
+                        }
+                    },
+                    _ => {
+                        internal_error!(
+                            "NumToFloatChecked is not defined for signature {:?} -> {:?}",
+                            arg_layout,
+                            self.ret_layout
+                        );
+                    }
+                };
+
+                let intrinsic = match ret_width {
+                    FloatWidth::F32 => &bitcode::NUM_TO_F32_CHECKED,
+                    FloatWidth::F64 => &bitcode::NUM_TO_F64_CHECKED,
+                };
+
+                match backend.layout_interner.get_repr(arg_layout) {
+                    LayoutRepr::Builtin(Builtin::Int(arg_width)) => {
+                        self.load_args_and_call_zig(backend, &intrinsic[arg_width])
+                    }
+                    LayoutRepr::Builtin(Builtin::Float(arg_width)) => {
+                        self.load_args_and_call_zig(backend, &intrinsic[arg_width])
+                    }
+                    LayoutRepr::Builtin(Builtin::Decimal) => {
+                        // Every Dec is within range of both F32 and F64, so this can't fail
+                        let (local_id, offset) =
+                            stack_memory_local_and_offset(backend, &self.ret_storage);
+
+                        backend.code_builder.get_local(local_id);
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, self.arguments);
+                        backend.call_host_fn_after_loading_args(bitcode::DEC_TO_F64, 2, true);
+                        match ret_width {
+                            FloatWidth::F32 => {
+                                backend.code_builder.f32_demote_f64();
+                                backend.code_builder.f32_store(Align::Bytes4, offset);
+                            }
+                            FloatWidth::F64 => {
+                                backend.code_builder.f64_store(Align::Bytes8, offset);
+                            }
+                        }
+
+                        // out_of_bounds = false
+                        backend.code_builder.get_local(local_id);
+                        backend.code_builder.i32_const(0);
+                        backend
+                            .code_builder
+                            .i32_store8(Align::Bytes1, offset + ret_width.stack_size());
+                    }
+                    x => internal_error!("NumToFloatChecked is not defined for {:?}", x),
+                }
             }
             I128OfDec => self.load_args_and_call_zig(backend, bitcode::DEC_TO_I128),
             And => {
@@ -1993,7 +2215,12 @@ impl<'a> LowLevelCall<'a> {
                 backend.code_builder.i32_and();
             }
 
-            Hash => todo!("{:?}", self.lowlevel),
+            Hash => {
+                unreachable!(
+                    "The {:?} operation is never generated; hashing is done by the Hash ability",
+                    self.lowlevel
+                )
+            }
 
             Eq | NotEq => self.eq_or_neq(backend),
 
@@ -2179,6 +2406,59 @@ impl<'a> LowLevelCall<'a> {
         backend.code_builder.i32_and();
     }
 
+    /// Compare two 128-bit numbers using a Zig builtin, leaving a `[EQ, GT, LT]` tag ID on the value stack
+    fn compare_128bit(&self, backend: &mut WasmBackend<'a, '_>, signature: (usize, bool, bool)) {
+        let width = int_width_128(backend.storage.symbol_layouts[&self.arguments[0]]);
+        self.call_zig_after_loading_args(backend, &bitcode::NUM_COMPARE[width], signature);
+    }
+
+    /// Convert an I128, U128 or Dec to a float using a Zig builtin
+    /// The argument must already be loaded, with `signature` returned by `load_args`
+    fn num_128bit_to_float(
+        &self,
+        backend: &mut WasmBackend<'a, '_>,
+        ret_type: CodeGenNumType,
+        signature: (usize, bool, bool),
+    ) {
+        let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
+        match backend.layout_interner.get_repr(arg_layout) {
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                self.call_zig_after_loading_args(backend, bitcode::DEC_TO_F64, signature);
+                if ret_type == CodeGenNumType::F32 {
+                    backend.code_builder.f32_demote_f64();
+                }
+            }
+            LayoutRepr::Builtin(Builtin::Int(width)) => {
+                // from compiler_rt
+                let name = match (ret_type, width.is_signed()) {
+                    (CodeGenNumType::F32, true) => "__floattisf",
+                    (CodeGenNumType::F32, false) => "__floatuntisf",
+                    (CodeGenNumType::F64, true) => "__floattidf",
+                    (CodeGenNumType::F64, false) => "__floatuntidf",
+                    _ => internal_error!("Cannot convert {:?} to {:?}", arg_layout, ret_type),
+                };
+                self.call_zig_after_loading_args(backend, name, signature);
+            }
+            x => internal_error!("{:?} is not defined for {:?}", self.lowlevel, x),
+        }
+    }
+
+    /// After storing the low half of a 128-bit return value, fill in the high half,
+    /// copying the sign bit of the low half for signed arguments.
+    fn extend_128bit_high_half(&self, backend: &mut WasmBackend<'a, '_>, is_signed: bool) {
+        let (local_id, offset) = stack_memory_local_and_offset(backend, &self.ret_storage);
+        backend.code_builder.get_local(local_id);
+        if is_signed {
+            backend.code_builder.get_local(local_id);
+            backend.code_builder.i64_load(Align::Bytes8, offset);
+            backend.code_builder.i64_const(63);
+            backend.code_builder.i64_shr_s();
+        } else {
+            backend.code_builder.i64_const(0);
+        }
+        backend.code_builder.i64_store(Align::Bytes8, offset + 8);
+    }
+
     fn num_to_str(&self, backend: &mut WasmBackend<'a, '_>) {
         let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
         match backend.layout_interner.get_repr(arg_layout) {
@@ -2186,12 +2466,7 @@ impl<'a> LowLevelCall<'a> {
                 self.load_args_and_call_zig(backend, &bitcode::STR_FROM_INT[width])
             }
             LayoutRepr::Builtin(Builtin::Float(width)) => match width {
-                FloatWidth::F32 => {
-                    self.load_args(backend);
-                    backend.code_builder.f64_promote_f32();
-                    self.load_args_and_call_zig(backend, &bitcode::STR_FROM_FLOAT[width]);
-                }
-                FloatWidth::F64 => {
+                FloatWidth::F32 | FloatWidth::F64 => {
                     self.load_args_and_call_zig(backend, &bitcode::STR_FROM_FLOAT[width]);
                 }
             },
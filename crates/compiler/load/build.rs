@@ -109,6 +109,7 @@ fn write_types_for_module_real(module_id: ModuleId, filename: &str, output_path:
         &module.interns,
         &mut module.can_problems,
         &mut module.type_problems,
+        roc_reporting::report::RenderTarget::ColorTerminal,
     );
 
     if problems.errors + problems.warnings > 0 {
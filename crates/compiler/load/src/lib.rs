@@ -18,11 +18,11 @@ const SKIP_SUBS_CACHE: bool = {
 
 pub use roc_load_internal::docs;
 pub use roc_load_internal::file::{
-    ExecutionMode, ExpectMetadata, LoadConfig, LoadResult, LoadStart, LoadingProblem, Phase,
-    Threading,
+    report_loading_problem, ExecutionMode, ExpectMetadata, LoadConfig, LoadResult, LoadStart,
+    LoadingProblem, Phase, TestOptions, Threading,
 };
 pub use roc_load_internal::module::{
-    EntryPoint, Expectations, ExposedToHost, LoadedModule, MonomorphizedModule,
+    EntryPoint, Expectations, ExposedToHost, LoadedModule, ModuleGraph, MonomorphizedModule,
 };
 pub use roc_solve::FunctionKind;
 
@@ -109,7 +109,14 @@ pub fn load_and_monomorphize_from_str<'a>(
 ) -> Result<MonomorphizedModule<'a>, LoadMonomorphizedError<'a>> {
     use LoadResult::*;
 
-    let load_start = LoadStart::from_str(arena, filename, src, roc_cache_dir, src_dir)?;
+    let load_start = LoadStart::from_str(
+        arena,
+        filename,
+        src,
+        load_config.render,
+        roc_cache_dir,
+        src_dir,
+    )?;
     let exposed_types = ExposedByModule::default();
 
     match load(arena, load_start, exposed_types, roc_cache_dir, load_config)? {
@@ -180,7 +187,7 @@ pub fn load_and_typecheck_str<'a>(
 ) -> Result<LoadedModule, LoadingProblem<'a>> {
     use LoadResult::*;
 
-    let load_start = LoadStart::from_str(arena, filename, source, roc_cache_dir, src_dir)?;
+    let load_start = LoadStart::from_str(arena, filename, source, render, roc_cache_dir, src_dir)?;
 
     // NOTE: this function is meant for tests, and so we use single-threaded
     // solving so we don't use too many threads per-test. That gives higher
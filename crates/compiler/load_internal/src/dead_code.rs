@@ -0,0 +1,73 @@
+//! Warnings which can only be given once the whole program has been canonicalized: values and
+//! types which a module exposes but no other module uses, and modules which nothing the app uses
+//! refers to.
+//!
+//! Reachability is tracked per module rather than per definition. A module is reachable if it is
+//! one of the entry points (the app and its platform), or if a reachable module refers to any of
+//! its values or types.
+use roc_collections::{MutMap, MutSet, VecSet};
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_problem::can::Problem;
+use roc_region::all::{Loc, Region};
+
+/// What a module exposes and refers to, recorded as it is parsed and canonicalized.
+#[derive(Debug, Default)]
+pub(crate) struct ModuleUsage {
+    /// The module's header, which is where we point when the whole module is unreachable
+    pub(crate) header_region: Region,
+    /// Exposed values and types, located in the header's `exposes` list
+    pub(crate) exposes: Vec<Loc<Symbol>>,
+    /// Every value and type the module refers to, including its own
+    pub(crate) references: VecSet<Symbol>,
+}
+
+/// Adds warnings for the unreachable modules and unused exposes of every module in `usage`
+/// for which `should_report` is true. The entry points themselves are never reported.
+pub(crate) fn report_dead_code(
+    usage: &MutMap<ModuleId, ModuleUsage>,
+    entry_points: &[ModuleId],
+    should_report: impl Fn(ModuleId) -> bool,
+    can_problems: &mut MutMap<ModuleId, Vec<Problem>>,
+) {
+    let mut reachable = MutSet::default();
+    let mut stack = entry_points.to_vec();
+
+    while let Some(module_id) = stack.pop() {
+        if reachable.insert(module_id) {
+            if let Some(module) = usage.get(&module_id) {
+                stack.extend(module.references.iter().map(|symbol| symbol.module_id()));
+            }
+        }
+    }
+
+    let mut used_elsewhere = MutSet::default();
+
+    for (module_id, module) in usage.iter() {
+        used_elsewhere.extend(
+            module
+                .references
+                .iter()
+                .filter(|symbol| symbol.module_id() != *module_id)
+                .copied(),
+        );
+    }
+
+    for (&module_id, module) in usage.iter() {
+        if entry_points.contains(&module_id) || !should_report(module_id) {
+            continue;
+        }
+
+        let problems = can_problems.entry(module_id).or_default();
+
+        if !reachable.contains(&module_id) {
+            // Every expose of an unreachable module is unused; one warning for the module will do.
+            problems.push(Problem::UnreachableModule(module_id, module.header_region));
+        } else {
+            for Loc { region, value } in module.exposes.iter() {
+                if !used_elsewhere.contains(value) {
+                    problems.push(Problem::UnusedExpose(*value, *region));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Finding the `expect`s in the fenced Roc code blocks of a module's doc comments, so that
+//! `roc test --doc` can run them in the scope of that module.
+use bumpalo::Bump;
+use roc_collections::MutSet;
+use roc_parse::ast::{Defs, Expr, ValueDef};
+use roc_parse::module::module_defs;
+use roc_parse::parser::Parser;
+use roc_parse::state::State;
+use roc_region::all::{Loc, Position, Region};
+use std::ops::Range;
+
+/// The top-level `expect`s in the fenced Roc code blocks of the module's `##` doc comments.
+///
+/// Their regions point into the doc comments in `src`, so failures get reported right where
+/// the example is. Blocks tagged with another language or `unchecked`, and blocks which don't
+/// parse as defs (e.g. ones which only show an expression), are skipped.
+///
+/// The other value defs in a block are only in scope for that block's `expect`s, so each
+/// `expect` gets the ones above it which it uses nested in its body.
+pub(crate) fn doc_test_expects<'a>(arena: &'a Bump, src: &str) -> Vec<(ValueDef<'a>, Region)> {
+    let mut expects = Vec::new();
+
+    for lines in code_blocks(src) {
+        let block_src = mask(arena, src, &lines);
+
+        if let Ok((_, defs, _)) = module_defs().parse(arena, State::new(block_src.as_bytes()), 0) {
+            for (index, (def, region)) in defs.defs().zip(defs.regions.iter()).enumerate() {
+                if let Err(ValueDef::Expect { condition, .. }) = def {
+                    let expect = ValueDef::Expect {
+                        condition: with_block_defs(arena, src, &defs, index, condition),
+                        preceding_comment: preceding_comment(src, &lines, region.start()),
+                    };
+
+                    expects.push((expect, *region));
+                }
+            }
+        }
+    }
+
+    expects
+}
+
+/// Wraps the condition of the `expect` at `expect_index` in the block's value defs above it
+/// which it needs, directly or through another of those defs.
+///
+/// Leaving out the ones it doesn't need avoids unused def warnings for a block with several
+/// examples. We only have the syntax here, so a def is needed if a name it defines shows up in
+/// the source of the condition or of another needed def.
+fn with_block_defs<'a>(
+    arena: &'a Bump,
+    src: &str,
+    block_defs: &Defs<'a>,
+    expect_index: usize,
+    condition: &'a Loc<Expr<'a>>,
+) -> &'a Loc<Expr<'a>> {
+    let above: Vec<_> = block_defs.defs().take(expect_index).collect();
+    let mut needed = words(src, condition.region);
+    let mut uses = vec![false; expect_index];
+
+    for (index, def) in above.iter().enumerate().rev() {
+        let region = block_defs.regions[index];
+        let pattern_region = match def {
+            Err(ValueDef::Body(pattern, _)) => pattern.region,
+            Err(ValueDef::AnnotatedBody { body_pattern, .. }) => body_pattern.region,
+            _ => continue,
+        };
+
+        if !words(src, pattern_region).is_disjoint(&needed) {
+            uses[index] = true;
+            needed.extend(words(src, region));
+        }
+    }
+
+    if !uses.contains(&true) {
+        return condition;
+    }
+
+    let mut defs = Defs::default();
+
+    for (index, def) in above.into_iter().enumerate() {
+        if let (true, Err(value_def)) = (uses[index], def) {
+            defs.push_value_def(*value_def, block_defs.regions[index], &[], &[]);
+        }
+    }
+
+    arena.alloc(Loc::at(
+        condition.region,
+        Expr::Defs(arena.alloc(defs), condition),
+    ))
+}
+
+/// The identifiers and other words in a region of the source
+fn words(src: &str, region: Region) -> MutSet<&str> {
+    src[region.start().offset as usize..region.end().offset as usize]
+        .split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+enum Fence {
+    Outside,
+    /// In a Roc code block; these are the byte ranges of its lines so far
+    Roc(Vec<Range<usize>>),
+    /// In a code block we don't run
+    Other,
+}
+
+/// The byte ranges of the code on each line of the Roc code blocks in `src`'s doc comments
+fn code_blocks(src: &str) -> Vec<Vec<Range<usize>>> {
+    let mut blocks = Vec::new();
+    let mut fence = Fence::Outside;
+    let mut line_start = 0;
+
+    for full_line in src.split_inclusive('\n') {
+        let start = line_start;
+        let line = full_line.trim_end_matches(['\n', '\r']);
+
+        line_start += full_line.len();
+
+        let doc = match line.trim_start().strip_prefix("##") {
+            Some(doc) => doc.strip_prefix(' ').unwrap_or(doc),
+            None => {
+                // The doc comment ended, and any code block in it ended along with it
+                if let Fence::Roc(lines) = std::mem::replace(&mut fence, Fence::Outside) {
+                    blocks.push(lines);
+                }
+
+                continue;
+            }
+        };
+
+        let is_fence = doc.trim_start().starts_with("```");
+
+        fence = match fence {
+            Fence::Outside if is_fence => {
+                let info = doc.trim_start().trim_start_matches('`');
+
+                if runs_as_roc(info) {
+                    Fence::Roc(Vec::new())
+                } else {
+                    Fence::Other
+                }
+            }
+            Fence::Roc(lines) if is_fence => {
+                blocks.push(lines);
+
+                Fence::Outside
+            }
+            Fence::Roc(mut lines) => {
+                let end = start + line.len();
+
+                lines.push(end - doc.len()..end);
+
+                Fence::Roc(lines)
+            }
+            Fence::Other if is_fence => Fence::Outside,
+            fence => fence,
+        };
+    }
+
+    if let Fence::Roc(lines) = fence {
+        blocks.push(lines);
+    }
+
+    blocks
+}
+
+/// Whether a code block with this info string (e.g. the `roc` in ```` ```roc ````) is Roc code
+/// we should run
+fn runs_as_roc(info: &str) -> bool {
+    let mut words = info
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|word| !word.is_empty());
+
+    match words.next() {
+        None => true,
+        Some("roc") => !words.any(|word| word == "unchecked"),
+        Some(_) => false,
+    }
+}
+
+/// The `#` comment lines right above the line of code which starts at `start`. The parser
+/// doesn't see the comment above the first def in a block, so we find these ourselves.
+fn preceding_comment(src: &str, lines: &[Range<usize>], start: Position) -> Region {
+    let above = match lines
+        .iter()
+        .position(|line| line.start == start.offset as usize)
+    {
+        Some(index) => &lines[..index],
+        None => &[],
+    };
+
+    let comment_start = above
+        .iter()
+        .rev()
+        .take_while(|line| src[line.start..line.end].trim_start().starts_with('#'))
+        .last()
+        .map_or(start, |line| Position::new(line.start as u32));
+
+    Region::new(comment_start, start)
+}
+
+/// A copy of `src` with only the given lines of code left in, at the same byte offsets.
+///
+/// Everything else becomes spaces, and each line of code starts right after the `##` which
+/// precedes it, so it's at column 0 (where top-level defs have to be) and any regions we parse
+/// from it are also regions of `src`.
+fn mask<'a>(arena: &'a Bump, src: &str, lines: &[Range<usize>]) -> &'a str {
+    let mut bytes = vec![b' '; src.len()];
+
+    for line in lines {
+        // There's always at least the `##` before the code
+        bytes[line.start - 1] = b'\n';
+        bytes[line.clone()].copy_from_slice(&src.as_bytes()[line.clone()]);
+    }
+
+    // We only copied whole lines into a string of ASCII whitespace, so this is still valid UTF-8
+    arena.alloc_str(std::str::from_utf8(&bytes).unwrap())
+}
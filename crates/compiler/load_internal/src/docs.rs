@@ -613,7 +613,8 @@ fn tag_to_doc(in_func_ann: bool, tag: ast::Tag) -> Option<Tag> {
     }
 }
 
-fn comments_or_new_lines_to_docs<'a>(
+/// The `##` doc comment at the end of these comments and newlines, if there is one
+pub fn comments_or_new_lines_to_docs<'a>(
     comments_or_new_lines: &'a [roc_parse::ast::CommentOrNewline<'a>],
 ) -> Option<String> {
     let mut docs = String::new();
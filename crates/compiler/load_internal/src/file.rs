@@ -1,12 +1,14 @@
 #![allow(clippy::too_many_arguments)]
 
+use crate::doc_tests::doc_test_expects;
 use crate::docs::ModuleDocumentation;
 use crate::module::{
     ConstrainedModule, EntryPoint, Expectations, ExposedToHost, FoundSpecializationsModule,
-    LateSpecializationsModule, LoadedModule, ModuleHeader, ModuleTiming, MonomorphizedModule,
-    ParsedModule, ToplevelExpects, TypeCheckedModule,
+    LateSpecializationsModule, LoadedModule, ModuleGraph, ModuleHeader, ModuleTiming,
+    MonomorphizedModule, ParsedModule, ToplevelExpects, TypeCheckedModule,
 };
 use crate::module_cache::ModuleCache;
+use crate::type_cache::{TypeCache, TypeCacheKey};
 use bumpalo::{collections::CollectIn, Bump};
 use crossbeam::channel::{bounded, Sender};
 use crossbeam::deque::{Injector, Stealer, Worker};
@@ -37,6 +39,7 @@ use roc_module::symbol::{
     IdentIds, IdentIdsByModule, Interns, ModuleId, ModuleIds, PQModuleName, PackageModuleIds,
     PackageQualified, Symbol,
 };
+use roc_mono::coverage::SharedCoveragePoints;
 use roc_mono::ir::{
     CapturedSymbols, ExternalSpecializations, GlueLayouts, PartialProc, Proc, ProcLayout, Procs,
     ProcsBase, UpdateModeIds, UsageTrackingMap,
@@ -58,7 +61,7 @@ use roc_parse::header::{
 use roc_parse::module::module_defs;
 use roc_parse::parser::{FileError, Parser, SourceError, SyntaxError};
 use roc_problem::Severity;
-use roc_region::all::{LineInfo, Loc, Region};
+use roc_region::all::{LineInfo, Loc, Position, Region};
 #[cfg(not(target_family = "wasm"))]
 use roc_reporting::report::to_https_problem_report_string;
 use roc_reporting::report::{to_file_problem_report_string, Palette, RenderTarget};
@@ -125,7 +128,17 @@ pub enum ExecutionMode {
     ExecutableIfCheck,
     /// Test is like [`ExecutionMode::ExecutableIfCheck`], but rather than producing a proper
     /// executable, run tests.
-    Test,
+    Test(TestOptions),
+}
+
+/// What to do besides running the root package's `expect`s, in [`ExecutionMode::Test`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TestOptions {
+    /// Instrument the root package's defs and `when` branches, so we can report how much of
+    /// them the tests reached.
+    pub coverage: bool,
+    /// Also run the `expect`s in the fenced Roc code blocks of the root package's doc comments.
+    pub doc_tests: bool,
 }
 
 impl ExecutionMode {
@@ -134,12 +147,29 @@ impl ExecutionMode {
 
         match self {
             Executable => Phase::MakeSpecializations,
-            Check | ExecutableIfCheck | Test => Phase::SolveTypes,
+            Check | ExecutableIfCheck | Test(_) => Phase::SolveTypes,
         }
     }
 
     fn build_if_checks(&self) -> bool {
-        matches!(self, Self::ExecutableIfCheck | Self::Test)
+        matches!(self, Self::ExecutableIfCheck | Self::Test(_))
+    }
+
+    fn is_test(&self) -> bool {
+        matches!(self, Self::Test(_))
+    }
+
+    /// Unused exposes and unreachable modules are worth a warning while checking, but must not
+    /// make building or running an app fail, since any warning gives a non-zero exit code.
+    fn reports_unused_code(&self) -> bool {
+        matches!(self, Self::Check)
+    }
+
+    fn test_options(&self) -> TestOptions {
+        match self {
+            Self::Test(options) => *options,
+            _ => TestOptions::default(),
+        }
     }
 }
 
@@ -196,8 +226,9 @@ fn start_phase<'a>(
             Phase::Parse => {
                 // parse the file
                 let header = state.module_cache.headers.remove(&module_id).unwrap();
+                let doc_tests = state.doc_tests_for(module_id);
 
-                BuildTask::Parse { header }
+                BuildTask::Parse { header, doc_tests }
             }
             Phase::CanonicalizeAndConstrain => {
                 // canonicalize the file
@@ -293,6 +324,8 @@ fn start_phase<'a>(
                     }
                 }
 
+                load_from_type_cache(state, &parsed, &dep_idents, &exposed_symbols);
+
                 let skip_constraint_gen = {
                     // Give this its own scope to make sure that the Guard from the lock() is dropped
                     // immediately after contains_key returns
@@ -330,6 +363,7 @@ fn start_phase<'a>(
                 } = constrained;
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
+                let type_cache = state.type_cache_entry(module_id);
 
                 #[cfg(debug_assertions)]
                 let checkmate = if roc_checkmate::is_checkmate_enabled() {
@@ -353,6 +387,7 @@ fn start_phase<'a>(
                     dep_idents,
                     declarations,
                     state.cached_types.clone(),
+                    type_cache,
                     derived_module,
                     //
                     #[cfg(debug_assertions)]
@@ -390,8 +425,12 @@ fn start_phase<'a>(
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
 
-                let build_expects =
-                    matches!(state.exec_mode, ExecutionMode::Test) && expectations.is_some();
+                let build_expects = state.exec_mode.is_test() && expectations.is_some();
+                let coverage = state.coverage_for(module_id);
+
+                if let Some(coverage) = &coverage {
+                    coverage.lock().unwrap().register_module(module_id, &decls);
+                }
 
                 BuildTask::BuildPendingSpecializations {
                     layout_cache,
@@ -408,6 +447,7 @@ fn start_phase<'a>(
                     derived_module,
                     expectations,
                     build_expects,
+                    coverage,
                 }
             }
             Phase::MakeSpecializations => {
@@ -528,6 +568,7 @@ fn start_phase<'a>(
                 }
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
+                let coverage = state.coverage_for(module_id);
 
                 BuildTask::MakeSpecializations {
                     module_id,
@@ -542,6 +583,7 @@ fn start_phase<'a>(
                     exposed_by_module: state.exposed_types.clone(),
                     derived_module,
                     expectations,
+                    coverage,
                 }
             }
         }
@@ -657,6 +699,67 @@ enum PlatformPath<'a> {
     RootIsPlatformModule,
 }
 
+/// Computes the type cache key of a module that's about to be canonicalized, and if the cache
+/// has an entry for that key, registers its solved types so we can skip constraint generation
+/// and solving for the module.
+fn load_from_type_cache(
+    state: &mut State<'_>,
+    parsed: &ParsedModule<'_>,
+    dep_idents: &IdentIdsByModule,
+    exposed_symbols: &VecSet<Symbol>,
+) {
+    let module_id = parsed.module_id;
+
+    let type_cache = match &state.type_cache {
+        Some(type_cache) => type_cache,
+        None => return,
+    };
+
+    let mut imports = Vec::new();
+
+    if let Some(imported) = state.module_cache.imports.get(&module_id) {
+        for import_id in imported.iter() {
+            match (
+                state.module_cache.type_cache_keys.get(import_id),
+                dep_idents.get(import_id),
+            ) {
+                (Some(import_key), Some(import_ident_ids)) => {
+                    imports.push((*import_id, *import_key, import_ident_ids));
+                }
+                _ => {
+                    // Without a key for every import, we can't tell whether an entry is still
+                    // valid; and neither can any module that imports this one.
+                    return;
+                }
+            }
+        }
+    }
+
+    let key = type_cache.module_key(
+        module_id,
+        parsed.src,
+        &parsed.exposed_ident_ids,
+        exposed_symbols,
+        &imports,
+    );
+
+    state.module_cache.type_cache_keys.insert(module_id, key);
+
+    if let Some((type_cache, key)) = state.type_cache_entry(module_id) {
+        let mut cached_types = state.cached_types.lock();
+
+        if let Vacant(vacant) = cached_types.entry(module_id) {
+            if let Some(type_state) = type_cache.read(key) {
+                log!(
+                    "loaded solved types for {:?} from the type cache",
+                    module_id
+                );
+                vacant.insert(type_state);
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PlatformData<'a> {
     module_id: ModuleId,
@@ -720,6 +823,8 @@ struct State<'a> {
     pub arc_modules: Arc<Mutex<PackageModuleIds<'a>>>,
     pub arc_shorthands: Arc<Mutex<MutMap<&'a str, ShorthandPath>>>,
     pub derived_module: SharedDerivedModule,
+    /// Only present when measuring coverage
+    pub coverage: Option<SharedCoveragePoints>,
 
     pub ident_ids_by_module: SharedIdentIdsByModule,
 
@@ -748,6 +853,9 @@ struct State<'a> {
     // cached types (used for builtin modules, could include packages in the future too)
     cached_types: CachedTypeState,
 
+    /// On-disk cache of solved types for user modules, if we're allowed to use one.
+    type_cache: Option<TypeCache>,
+
     layout_interner: GlobalLayoutInterner<'a>,
 }
 
@@ -758,6 +866,42 @@ impl<'a> State<'a> {
         self.exec_mode.goal_phase()
     }
 
+    /// Where the solved types of the given module should be read from and written to, if
+    /// anywhere.
+    fn type_cache_entry(&self, module_id: ModuleId) -> Option<(TypeCache, TypeCacheKey)> {
+        // Builtins already have their types cached in the compiler itself. The root module is
+        // the one that's most likely to have changed since the last run, and one-off roots
+        // (like REPL inputs) would only fill up the cache, so it is always solved from scratch.
+        if module_id.is_builtin() || module_id == self.root_id {
+            return None;
+        }
+
+        let type_cache = self.type_cache.as_ref()?;
+        let key = self.module_cache.type_cache_keys.get(&module_id)?;
+
+        Some((type_cache.clone(), *key))
+    }
+
+    fn is_root_package(&self, module_id: ModuleId) -> bool {
+        !module_id.is_builtin()
+            && self.arc_modules.lock().package_eq(module_id, self.root_id) == Some(true)
+    }
+
+    /// The coverage points to instrument the given module with, if we are measuring coverage
+    /// of it. We only measure the package that is being tested, not its dependencies.
+    fn coverage_for(&self, module_id: ModuleId) -> Option<SharedCoveragePoints> {
+        let coverage = self.coverage.as_ref()?;
+
+        self.is_root_package(module_id)
+            .then(|| SharedCoveragePoints::clone(coverage))
+    }
+
+    /// Whether to run the `expect`s in the given module's doc comments. Like coverage, this is
+    /// only for the package that is being tested.
+    fn doc_tests_for(&self, module_id: ModuleId) -> bool {
+        self.exec_mode.test_options().doc_tests && self.is_root_package(module_id)
+    }
+
     fn new(
         root_id: ModuleId,
         opt_platform_shorthand: Option<&'a str>,
@@ -767,6 +911,7 @@ impl<'a> State<'a> {
         arc_modules: Arc<Mutex<PackageModuleIds<'a>>>,
         ident_ids_by_module: SharedIdentIdsByModule,
         cached_types: MutMap<ModuleId, TypeState>,
+        type_cache: Option<TypeCache>,
         render: RenderTarget,
         palette: Palette,
         number_of_workers: usize,
@@ -796,6 +941,10 @@ impl<'a> State<'a> {
             arc_modules,
             arc_shorthands,
             derived_module: Default::default(),
+            coverage: exec_mode
+                .test_options()
+                .coverage
+                .then(SharedCoveragePoints::default),
             constrained_ident_ids: IdentIds::exposed_builtins(0),
             ident_ids_by_module,
             declarations_by_id: MutMap::default(),
@@ -803,6 +952,7 @@ impl<'a> State<'a> {
             timings: MutMap::default(),
             layout_caches: std::vec::Vec::with_capacity(number_of_workers),
             cached_types: Arc::new(Mutex::new(cached_types)),
+            type_cache,
             render,
             palette,
             exec_mode,
@@ -870,6 +1020,7 @@ enum BuildTask<'a> {
     },
     Parse {
         header: ModuleHeader<'a>,
+        doc_tests: bool,
     },
     CanonicalizeAndConstrain {
         parsed: ParsedModule<'a>,
@@ -895,6 +1046,7 @@ enum BuildTask<'a> {
         declarations: Declarations,
         dep_idents: IdentIdsByModule,
         cached_subs: CachedTypeState,
+        type_cache: Option<(TypeCache, TypeCacheKey)>,
         derived_module: SharedDerivedModule,
 
         #[cfg(debug_assertions)]
@@ -914,6 +1066,7 @@ enum BuildTask<'a> {
         derived_module: SharedDerivedModule,
         expectations: Option<Expectations>,
         build_expects: bool,
+        coverage: Option<SharedCoveragePoints>,
     },
     MakeSpecializations {
         module_id: ModuleId,
@@ -927,6 +1080,7 @@ enum BuildTask<'a> {
         world_abilities: WorldAbilities,
         derived_module: SharedDerivedModule,
         expectations: Option<Expectations>,
+        coverage: Option<SharedCoveragePoints>,
     },
 }
 
@@ -1005,7 +1159,7 @@ pub fn load_and_typecheck_str<'a>(
 ) -> Result<LoadedModule, LoadingProblem<'a>> {
     use LoadResult::*;
 
-    let load_start = LoadStart::from_str(arena, filename, source, roc_cache_dir, src_dir)?;
+    let load_start = LoadStart::from_str(arena, filename, source, render, roc_cache_dir, src_dir)?;
 
     // this function is used specifically in the case
     // where we want to regenerate the cached data
@@ -1074,6 +1228,7 @@ impl<'a> LoadStart<'a> {
                 Arc::clone(&arc_modules),
                 Arc::clone(&ident_ids_by_module),
                 roc_cache_dir,
+                render,
                 root_start_time,
             );
 
@@ -1136,6 +1291,7 @@ impl<'a> LoadStart<'a> {
         arena: &'a Bump,
         filename: PathBuf,
         src: &'a str,
+        render: RenderTarget,
         roc_cache_dir: RocCacheDir<'_>,
         src_dir: PathBuf,
     ) -> Result<Self, LoadingProblem<'a>> {
@@ -1158,6 +1314,7 @@ impl<'a> LoadStart<'a> {
                 Arc::clone(&arc_modules),
                 Arc::clone(&ident_ids_by_module),
                 roc_cache_dir,
+                render,
                 root_start_time,
             )?
         };
@@ -1335,6 +1492,7 @@ pub fn load_single_threaded<'a>(
         arc_modules,
         ident_ids_by_module,
         cached_types,
+        TypeCache::new(roc_cache_dir, function_kind),
         render,
         palette,
         number_of_workers,
@@ -1381,6 +1539,7 @@ pub fn load_single_threaded<'a>(
             &src_dir,
             roc_cache_dir,
             target_info,
+            render,
         );
 
         match control_flow {
@@ -1464,7 +1623,7 @@ fn state_thread_step<'a>(
                     Ok(ControlFlow::Break(LoadResult::Monomorphized(monomorphized)))
                 }
                 Msg::FailedToReadFile { filename, error } => {
-                    let buf = to_file_problem_report_string(&filename, error);
+                    let buf = to_file_problem_report_string(&filename, error, state.render);
                     Err(LoadingProblem::FormattedReport(buf))
                 }
 
@@ -1613,7 +1772,7 @@ pub fn report_loading_problem(
         }
         LoadingProblem::FormattedReport(report) => report,
         LoadingProblem::FileProblem { filename, error } => {
-            to_file_problem_report_string(&filename, error)
+            to_file_problem_report_string(&filename, error, render)
         }
         err => todo!("Loading error: {:?}", err),
     }
@@ -1673,6 +1832,7 @@ fn load_multi_threaded<'a>(
         arc_modules,
         ident_ids_by_module,
         cached_types,
+        TypeCache::new(roc_cache_dir, function_kind),
         render,
         palette,
         num_workers,
@@ -1739,6 +1899,7 @@ fn load_multi_threaded<'a>(
                             src_dir,
                             roc_cache_dir,
                             target_info,
+                            render,
                         )
                     });
 
@@ -1808,6 +1969,7 @@ fn worker_task_step<'a>(
     src_dir: &Path,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    render: RenderTarget,
 ) -> Result<ControlFlow<(), ()>, LoadingProblem<'a>> {
     match worker_msg_rx.try_recv() {
         Ok(msg) => {
@@ -1837,6 +1999,7 @@ fn worker_task_step<'a>(
                             msg_tx.clone(),
                             roc_cache_dir,
                             target_info,
+                            render,
                         );
 
                         match result {
@@ -1882,6 +2045,7 @@ fn worker_task<'a>(
     src_dir: &Path,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    render: RenderTarget,
 ) -> Result<(), LoadingProblem<'a>> {
     // Keep listening until we receive a Shutdown msg
     for msg in worker_msg_rx.iter() {
@@ -1910,7 +2074,7 @@ fn worker_task<'a>(
                             BuildTask::LoadModule { module_name, .. } => {
                                 format!("BuildTask::LoadModule({module_name:?})")
                             }
-                            BuildTask::Parse { header } => {
+                            BuildTask::Parse { header, .. } => {
                                 format!("BuildTask::Parse({})", header.module_path.display())
                             }
                             BuildTask::CanonicalizeAndConstrain { parsed, .. } => format!(
@@ -1936,6 +2100,7 @@ fn worker_task<'a>(
                         msg_tx.clone(),
                         roc_cache_dir,
                         target_info,
+                        render,
                     );
 
                     match result {
@@ -2056,6 +2221,58 @@ fn report_unused_imported_modules(
     }
 }
 
+/// Remember where an interface module's exposes are, so we can later report the ones that no
+/// other module uses
+fn record_module_exposes(state: &mut State<'_>, parsed: &ParsedModule) {
+    let exposes = match parsed.header_type {
+        HeaderType::Interface { exposes, .. } => exposes
+            .iter()
+            .filter_map(|loc_name| {
+                let ident_id = parsed.exposed_ident_ids.get_id(loc_name.value.as_str())?;
+
+                Some(Loc::at(
+                    loc_name.region,
+                    Symbol::new(parsed.module_id, ident_id),
+                ))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let usage = state
+        .module_cache
+        .usage
+        .entry(parsed.module_id)
+        .or_default();
+
+    usage.header_region = parsed.header_region;
+    usage.exposes = exposes;
+}
+
+/// Once every module has been canonicalized, report the modules in the app's own package which
+/// nothing reachable from the app uses, and the exposes of those modules that nobody imports.
+fn report_unused_code(state: &mut State<'_>) {
+    // Only an app has a `main` for everything else to be reachable from
+    if state.opt_platform_shorthand.is_none() {
+        return;
+    }
+
+    let root_id = state.root_id;
+    let mut entry_points = vec![root_id];
+
+    entry_points.extend(state.platform_data.as_ref().map(|data| data.module_id));
+
+    let usage = std::mem::take(&mut state.module_cache.usage);
+    let modules = state.arc_modules.lock();
+
+    crate::dead_code::report_dead_code(
+        &usage,
+        &entry_points,
+        |module_id| !module_id.is_builtin() && modules.package_eq(module_id, root_id) == Some(true),
+        &mut state.module_cache.can_problems,
+    );
+}
+
 fn extend_header_with_builtin(header: &mut ModuleHeader, module: ModuleId) {
     header
         .package_qualified_imported_modules
@@ -2130,6 +2347,7 @@ fn update<'a>(
                                     let buf = to_https_problem_report_string(
                                         url,
                                         Problem::InvalidUrl(url_err),
+                                        state.render,
                                     );
                                     return Err(LoadingProblem::FormattedReport(buf));
                                 }
@@ -2351,6 +2569,8 @@ fn update<'a>(
                 }
             }
 
+            record_module_exposes(&mut state, &parsed);
+
             let module_id = parsed.module_id;
 
             state.module_cache.parsed.insert(parsed.module_id, parsed);
@@ -2380,6 +2600,14 @@ fn update<'a>(
 
             report_unused_imported_modules(&mut state, module_id, &constrained_module);
 
+            let usage = state.module_cache.usage.entry(module_id).or_default();
+            usage
+                .references
+                .extend(constrained_module.module.referenced_values.iter().copied());
+            usage
+                .references
+                .extend(constrained_module.module.referenced_types.iter().copied());
+
             state
                 .module_cache
                 .aliases
@@ -2399,6 +2627,10 @@ fn update<'a>(
                 .dependencies
                 .notify(module_id, Phase::CanonicalizeAndConstrain);
 
+            if state.dependencies.canonicalized_all() && state.exec_mode.reports_unused_code() {
+                report_unused_code(&mut state);
+            }
+
             start_tasks(arena, &mut state, work, injector, worker_listeners)?;
 
             Ok(state)
@@ -2458,7 +2690,7 @@ fn update<'a>(
 
             let add_to_host_exposed = is_host_exposed &&
                 // During testing, we don't need to expose anything to the host.
-                !matches!(state.exec_mode, ExecutionMode::Test);
+                !state.exec_mode.is_test();
 
             if add_to_host_exposed {
                 state.exposed_to_host.top_level_values.extend(
@@ -2572,6 +2804,7 @@ fn update<'a>(
                         .typechecked
                         .insert(module_id, typechecked);
                 } else {
+                    state.declarations_by_id.insert(module_id, decls);
                     state.constrained_ident_ids.insert(module_id, ident_ids);
                     state.timings.insert(module_id, module_timing);
                 }
@@ -3008,6 +3241,13 @@ fn finish_specialization<'a>(
     ModuleId::DERIVED_SYNTH.register_debug_idents(&derived_synth_ident_ids);
     all_ident_ids.insert(ModuleId::DERIVED_SYNTH, derived_synth_ident_ids);
 
+    let coverage = state.coverage.map(|coverage| {
+        Arc::try_unwrap(coverage)
+            .unwrap_or_else(|_| internal_error!("Outstanding references to the coverage points"))
+            .into_inner()
+            .unwrap()
+    });
+
     let mut interns = Interns {
         module_ids,
         all_ident_ids,
@@ -3016,7 +3256,7 @@ fn finish_specialization<'a>(
     let entry_point = {
         let interns: &mut Interns = &mut interns;
         match state.exec_mode {
-            ExecutionMode::Test => Ok(EntryPoint::Test),
+            ExecutionMode::Test(_) => Ok(EntryPoint::Test),
             ExecutionMode::Executable | ExecutionMode::ExecutableIfCheck => {
                 use PlatformPath::*;
 
@@ -3029,7 +3269,7 @@ fn finish_specialization<'a>(
                     }
                     Valid(To::NewPackage(p_or_p)) => PathBuf::from(p_or_p.as_str()),
                     other => {
-                        let buf = to_missing_platform_report(state.root_id, other);
+                        let buf = to_missing_platform_report(state.root_id, other, state.render);
                         return Err(LoadingProblem::FormattedReport(buf));
                     }
                 };
@@ -3102,6 +3342,7 @@ fn finish_specialization<'a>(
 
     let module_id = state.root_id;
     let mut glue_getters = Vec::new();
+    let mut glue_refcounters = Vec::new();
 
     // the REPL does not have any platform data
     if let (
@@ -3145,6 +3386,13 @@ fn finish_specialization<'a>(
                         .iter()
                         .map(|glue_proc| (glue_proc.name, glue_proc.proc_layout))
                 }));
+
+                for (_, refcounters) in all_glue_procs.refcounters.iter() {
+                    exposed_to_host
+                        .refcounters
+                        .extend([refcounters.incref, refcounters.decref]);
+                    glue_refcounters.push(*refcounters);
+                }
             }
         }
     }
@@ -3178,8 +3426,10 @@ fn finish_specialization<'a>(
         toplevel_expects,
         glue_layouts: GlueLayouts {
             getters: glue_getters,
+            refcounters: glue_refcounters,
         },
         uses_prebuilt_platform,
+        coverage,
     })
 }
 
@@ -3215,10 +3465,20 @@ fn finish(
     //
     #[cfg(debug_assertions)] checkmate: Option<roc_checkmate::Collector>,
 ) -> LoadedModule {
-    let module_ids = Arc::try_unwrap(state.arc_modules)
+    let package_module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
-        .into_inner()
-        .into_module_ids();
+        .into_inner();
+
+    let module_graph = ModuleGraph {
+        imports: state.module_cache.imports,
+        shorthands: package_module_ids
+            .package_shorthands()
+            .map(|(module_id, shorthand)| (module_id, shorthand.to_string()))
+            .collect(),
+        platform: state.platform_data.as_ref().map(|data| data.module_id),
+    };
+
+    let module_ids = package_module_ids.into_module_ids();
 
     // Associate the ident IDs from the derived synth module
     let (_, derived_synth_ident_ids) = Arc::try_unwrap(state.derived_module)
@@ -3264,6 +3524,7 @@ fn finish(
         timings: state.timings,
         docs_by_module: documentation,
         abilities_store,
+        module_graph,
     }
 }
 
@@ -3490,6 +3751,7 @@ fn load_module<'a>(
     arc_shorthands: Arc<Mutex<MutMap<&'a str, ShorthandPath>>>,
     roc_cache_dir: RocCacheDir<'_>,
     ident_ids_by_module: SharedIdentIdsByModule,
+    render: RenderTarget,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let module_start_time = Instant::now();
 
@@ -3549,6 +3811,7 @@ fn load_module<'a>(
         module_ids,
         ident_ids_by_module,
         roc_cache_dir,
+        render,
         module_start_time,
     )
 }
@@ -3714,6 +3977,7 @@ fn parse_header<'a>(
     ident_ids_by_module: SharedIdentIdsByModule,
     src_bytes: &'a [u8],
     roc_cache_dir: RocCacheDir<'_>,
+    render: RenderTarget,
     start_time: Instant,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let parse_start = Instant::now();
@@ -3891,6 +4155,7 @@ fn parse_header<'a>(
                 module_id,
                 module_ids,
                 ident_ids_by_module,
+                render,
             );
 
             // Look at the app module's `to` keyword to determine which package was the platform.
@@ -3923,22 +4188,51 @@ fn parse_header<'a>(
             },
             parse_state,
         )) => {
-            let (module_id, _, header) = build_package_header(
+            let mut package_file_dir = filename.clone();
+            package_file_dir.pop();
+
+            let (module_id, _, resolved_header) = build_package_header(
                 arena,
                 None,
                 is_root_module,
                 filename,
                 parse_state,
-                module_ids,
-                ident_ids_by_module,
+                module_ids.clone(),
+                ident_ids_by_module.clone(),
                 &header,
                 comments,
                 module_timing,
             )?;
 
+            // When documenting or checking a package on its own, nothing else will load the
+            // packages it depends on, so the root package loads them itself.
+            let msg = if is_root_module {
+                let packages = unspace(arena, header.packages.item.items);
+                let mut messages = Vec::with_capacity(packages.len() + 1);
+
+                // The package header has to come before the packages it depends on
+                messages.push(Msg::Header(resolved_header));
+
+                load_packages(
+                    packages,
+                    &mut messages,
+                    roc_cache_dir,
+                    package_file_dir,
+                    arena,
+                    module_id,
+                    module_ids,
+                    ident_ids_by_module,
+                    render,
+                );
+
+                Msg::Many(messages)
+            } else {
+                Msg::Header(resolved_header)
+            };
+
             Ok(HeaderOutput {
                 module_id,
-                msg: Msg::Header(header),
+                msg,
                 opt_platform_shorthand: None,
             })
         }
@@ -3993,6 +4287,7 @@ fn load_packages<'a>(
     module_id: ModuleId,
     module_ids: Arc<Mutex<PackageModuleIds<'a>>>,
     ident_ids_by_module: SharedIdentIdsByModule,
+    render: RenderTarget,
 ) {
     // Load all the packages
     for Loc { value: entry, .. } in packages.iter() {
@@ -4030,7 +4325,7 @@ fn load_packages<'a>(
                         }
                     }
                     Err(problem) => {
-                        let buf = to_https_problem_report_string(src, problem);
+                        let buf = to_https_problem_report_string(src, problem, render);
 
                         load_messages.push(Msg::FailedToLoad(LoadingProblem::FormattedReport(buf)));
                         return;
@@ -4074,6 +4369,7 @@ fn load_filename<'a>(
     module_ids: Arc<Mutex<PackageModuleIds<'a>>>,
     ident_ids_by_module: SharedIdentIdsByModule,
     roc_cache_dir: RocCacheDir<'_>,
+    render: RenderTarget,
     module_start_time: Instant,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let file_io_start = Instant::now();
@@ -4092,6 +4388,7 @@ fn load_filename<'a>(
             ident_ids_by_module,
             arena.alloc(bytes),
             roc_cache_dir,
+            render,
             module_start_time,
         ),
         Err(err) => Err(LoadingProblem::FileProblem {
@@ -4110,6 +4407,7 @@ fn load_from_str<'a>(
     module_ids: Arc<Mutex<PackageModuleIds<'a>>>,
     ident_ids_by_module: SharedIdentIdsByModule,
     roc_cache_dir: RocCacheDir<'_>,
+    render: RenderTarget,
     module_start_time: Instant,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let file_io_start = Instant::now();
@@ -4126,6 +4424,7 @@ fn load_from_str<'a>(
         ident_ids_by_module,
         src.as_bytes(),
         roc_cache_dir,
+        render,
         module_start_time,
     )
 }
@@ -4480,6 +4779,7 @@ impl<'a> BuildTask<'a> {
         dep_idents: IdentIdsByModule,
         declarations: Declarations,
         cached_subs: CachedTypeState,
+        type_cache: Option<(TypeCache, TypeCacheKey)>,
         derived_module: SharedDerivedModule,
 
         #[cfg(debug_assertions)] checkmate: Option<roc_checkmate::Collector>,
@@ -4504,6 +4804,7 @@ impl<'a> BuildTask<'a> {
             dep_idents,
             module_timing,
             cached_subs,
+            type_cache,
             derived_module,
 
             #[cfg(debug_assertions)]
@@ -4907,6 +5208,7 @@ fn run_solve<'a>(
     decls: Declarations,
     dep_idents: IdentIdsByModule,
     cached_types: CachedTypeState,
+    type_cache: Option<(TypeCache, TypeCacheKey)>,
     derived_module: SharedDerivedModule,
 
     #[cfg(debug_assertions)] checkmate: Option<roc_checkmate::Collector>,
@@ -4923,41 +5225,12 @@ fn run_solve<'a>(
     let loc_dbgs = std::mem::take(&mut module.loc_dbgs);
     let module = module;
 
-    let solve_result = {
-        if module_id.is_builtin() {
-            match cached_types.lock().remove(&module_id) {
-                None => run_solve_solve(
-                    exposed_for_module,
-                    types,
-                    constraints,
-                    constraint,
-                    function_kind,
-                    pending_derives,
-                    var_store,
-                    module,
-                    derived_module,
-                    //
-                    #[cfg(debug_assertions)]
-                    checkmate,
-                ),
-                Some(TypeState {
-                    subs,
-                    exposed_vars_by_symbol,
-                    abilities,
-                    solved_implementations,
-                }) => SolveResult {
-                    solved: Solved(subs),
-                    solved_implementations,
-                    exposed_vars_by_symbol,
-                    problems: vec![],
-                    abilities_store: abilities,
+    // Give this its own binding so that the Guard from the lock() is dropped before we solve
+    let opt_cached_type_state = cached_types.lock().remove(&module_id);
 
-                    #[cfg(debug_assertions)]
-                    checkmate: None,
-                },
-            }
-        } else {
-            run_solve_solve(
+    let solve_result = match opt_cached_type_state {
+        None => {
+            let solve_result = run_solve_solve(
                 exposed_for_module,
                 types,
                 constraints,
@@ -4970,8 +5243,30 @@ fn run_solve<'a>(
                 //
                 #[cfg(debug_assertions)]
                 checkmate,
-            )
+            );
+
+            match type_cache {
+                Some((type_cache, key)) if solve_result.problems.is_empty() => {
+                    write_to_type_cache(&type_cache, key, solve_result)
+                }
+                _ => solve_result,
+            }
         }
+        Some(TypeState {
+            subs,
+            exposed_vars_by_symbol,
+            abilities,
+            solved_implementations,
+        }) => SolveResult {
+            solved: Solved(subs),
+            solved_implementations,
+            exposed_vars_by_symbol,
+            problems: vec![],
+            abilities_store: abilities,
+
+            #[cfg(debug_assertions)]
+            checkmate: None,
+        },
     };
 
     let SolveResult {
@@ -5023,6 +5318,52 @@ fn run_solve<'a>(
     }
 }
 
+/// Writes the solved types of a module that type-checked without problems to the type cache,
+/// handing the solve result back afterwards.
+fn write_to_type_cache(
+    type_cache: &TypeCache,
+    key: TypeCacheKey,
+    solve_result: SolveResult,
+) -> SolveResult {
+    let SolveResult {
+        solved,
+        solved_implementations,
+        exposed_vars_by_symbol,
+        problems,
+        abilities_store,
+
+        #[cfg(debug_assertions)]
+        checkmate,
+    } = solve_result;
+
+    let type_state = TypeState {
+        subs: solved.into_inner(),
+        exposed_vars_by_symbol,
+        abilities: abilities_store,
+        solved_implementations,
+    };
+
+    type_cache.write(key, &type_state);
+
+    let TypeState {
+        subs,
+        exposed_vars_by_symbol,
+        abilities,
+        solved_implementations,
+    } = type_state;
+
+    SolveResult {
+        solved: Solved(subs),
+        solved_implementations,
+        exposed_vars_by_symbol,
+        problems,
+        abilities_store: abilities,
+
+        #[cfg(debug_assertions)]
+        checkmate,
+    }
+}
+
 fn unspace<'a, T: Copy>(arena: &'a Bump, items: &[Loc<Spaced<'a, T>>]) -> &'a [Loc<T>] {
     bumpalo::collections::Vec::from_iter_in(
         items
@@ -5341,7 +5682,11 @@ fn canonicalize_and_constrain<'a>(
     }
 }
 
-fn parse<'a>(arena: &'a Bump, header: ModuleHeader<'a>) -> Result<Msg<'a>, LoadingProblem<'a>> {
+fn parse<'a>(
+    arena: &'a Bump,
+    header: ModuleHeader<'a>,
+    doc_tests: bool,
+) -> Result<Msg<'a>, LoadingProblem<'a>> {
     let mut module_timing = header.module_timing;
     let parse_start = Instant::now();
     let source = header.parse_state.original_bytes();
@@ -5372,6 +5717,16 @@ fn parse<'a>(arena: &'a Bump, header: ModuleHeader<'a>) -> Result<Msg<'a>, Loadi
     // we'd have bailed out before now.
     let src = unsafe { from_utf8_unchecked(source) };
 
+    if doc_tests {
+        for (expect, region) in doc_test_expects(arena, src) {
+            parsed_defs.push_value_def(expect, region, &[], &[]);
+        }
+    }
+
+    // Leave out any blank lines between the header and the first def
+    let header_end = src[..parse_state.pos().offset as usize].trim_end().len();
+    let header_region = Region::new(Position::zero(), Position::new(header_end as u32));
+
     let ModuleHeader {
         module_id,
         deps_by_name,
@@ -5396,6 +5751,7 @@ fn parse<'a>(arena: &'a Bump, header: ModuleHeader<'a>) -> Result<Msg<'a>, Loadi
         parsed_defs,
         symbols_from_requires,
         header_type,
+        header_region,
         header_comments: header_docs,
     };
 
@@ -5517,6 +5873,7 @@ fn make_specializations<'a>(
     exposed_by_module: &ExposedByModule,
     derived_module: SharedDerivedModule,
     mut expectations: Option<Expectations>,
+    coverage: Option<SharedCoveragePoints>,
 ) -> Msg<'a> {
     let make_specializations_start = Instant::now();
     let mut update_mode_ids = UpdateModeIds::new();
@@ -5535,6 +5892,7 @@ fn make_specializations<'a>(
         exposed_by_module,
         derived_module: &derived_module,
         struct_indexing: UsageTrackingMap::default(),
+        coverage: coverage.as_ref(),
     };
 
     let mut procs = Procs::new_in(arena);
@@ -5603,6 +5961,7 @@ fn build_pending_specializations<'a>(
     derived_module: SharedDerivedModule,
     mut expectations: Option<Expectations>,
     build_expects: bool,
+    coverage: Option<SharedCoveragePoints>,
 ) -> Msg<'a> {
     let find_specializations_start = Instant::now();
 
@@ -5636,6 +5995,7 @@ fn build_pending_specializations<'a>(
         exposed_by_module,
         derived_module: &derived_module,
         struct_indexing: UsageTrackingMap::default(),
+        coverage: coverage.as_ref(),
     };
 
     let layout_cache_snapshot = layout_cache.snapshot();
@@ -6118,6 +6478,7 @@ fn load_derived_partial_procs<'a>(
             exposed_by_module,
             derived_module,
             struct_indexing: UsageTrackingMap::default(),
+            coverage: None,
         };
 
         let partial_proc = match derived_expr {
@@ -6182,6 +6543,7 @@ fn run_task<'a>(
     msg_tx: MsgSender<'a>,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    render: RenderTarget,
 ) -> Result<(), LoadingProblem<'a>> {
     use BuildTask::*;
 
@@ -6199,9 +6561,10 @@ fn run_task<'a>(
             shorthands,
             roc_cache_dir,
             ident_ids_by_module,
+            render,
         )
         .map(|HeaderOutput { msg, .. }| msg),
-        Parse { header } => parse(arena, header),
+        Parse { header, doc_tests } => parse(arena, header, doc_tests),
         CanonicalizeAndConstrain {
             parsed,
             module_ids,
@@ -6240,6 +6603,7 @@ fn run_task<'a>(
             declarations,
             dep_idents,
             cached_subs,
+            type_cache,
             derived_module,
 
             #[cfg(debug_assertions)]
@@ -6258,6 +6622,7 @@ fn run_task<'a>(
             declarations,
             dep_idents,
             cached_subs,
+            type_cache,
             derived_module,
             //
             #[cfg(debug_assertions)]
@@ -6277,6 +6642,7 @@ fn run_task<'a>(
             derived_module,
             expectations,
             build_expects,
+            coverage,
         } => Ok(build_pending_specializations(
             arena,
             solved_subs,
@@ -6293,6 +6659,7 @@ fn run_task<'a>(
             derived_module,
             expectations,
             build_expects,
+            coverage,
         )),
         MakeSpecializations {
             module_id,
@@ -6306,6 +6673,7 @@ fn run_task<'a>(
             exposed_by_module,
             derived_module,
             expectations,
+            coverage,
         } => Ok(make_specializations(
             arena,
             module_id,
@@ -6320,6 +6688,7 @@ fn run_task<'a>(
             &exposed_by_module,
             derived_module,
             expectations,
+            coverage,
         )),
     }?;
 
@@ -6482,7 +6851,11 @@ fn to_parse_problem_report<'a>(
     buf
 }
 
-fn to_missing_platform_report(module_id: ModuleId, other: &PlatformPath) -> String {
+fn to_missing_platform_report(
+    module_id: ModuleId,
+    other: &PlatformPath,
+    render: RenderTarget,
+) -> String {
     use roc_reporting::report::{Report, RocDocAllocator, DEFAULT_PALETTE};
     use ven_pretty::DocAllocator;
     use PlatformPath::*;
@@ -6561,7 +6934,7 @@ fn to_missing_platform_report(module_id: ModuleId, other: &PlatformPath) -> Stri
 
     let palette = DEFAULT_PALETTE;
     let mut buf = String::new();
-    report.render_color_terminal(&mut buf, &alloc, &palette);
+    report.render(render, &mut buf, &alloc, &palette);
 
     buf
 }
@@ -4,10 +4,13 @@
 #![allow(clippy::large_enum_variant)]
 
 use roc_module::symbol::ModuleId;
+mod dead_code;
+mod doc_tests;
 pub mod docs;
 pub mod file;
 pub mod module;
 mod module_cache;
+mod type_cache;
 mod work;
 
 #[cfg(target_family = "wasm")]
@@ -11,6 +11,7 @@ use roc_module::ident::Ident;
 use roc_module::symbol::{
     IdentIds, IdentIdsByModule, Interns, ModuleId, PQModuleName, PackageQualified, Symbol,
 };
+use roc_mono::coverage::CoveragePoints;
 use roc_mono::ir::{GlueLayouts, LambdaSetId, Proc, ProcLayout, ProcsBase};
 use roc_mono::layout::{LayoutCache, STLayoutInterner};
 use roc_parse::ast::{CommentOrNewline, Defs, TypeAnnotation, ValueDef};
@@ -46,6 +47,19 @@ pub struct LoadedModule {
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub docs_by_module: VecMap<ModuleId, ModuleDocumentation>,
     pub abilities_store: AbilitiesStore,
+    pub module_graph: ModuleGraph,
+}
+
+/// How the loaded modules import each other, for tools like `roc deps`
+#[derive(Debug, Default)]
+pub struct ModuleGraph {
+    /// The modules each module directly imports (including builtins)
+    pub imports: MutMap<ModuleId, MutSet<ModuleId>>,
+    /// The package shorthand (e.g. `pf`) of each module which comes from a package,
+    /// rather than from the root module's own directory
+    pub shorthands: MutMap<ModuleId, String>,
+    /// The platform's main module, if there is one
+    pub platform: Option<ModuleId>,
 }
 
 impl LoadedModule {
@@ -175,6 +189,8 @@ pub struct MonomorphizedModule<'a> {
     pub expectations: VecMap<ModuleId, Expectations>,
     pub uses_prebuilt_platform: bool,
     pub glue_layouts: GlueLayouts<'a>,
+    /// The defs and `when` branches that were instrumented, if we are measuring coverage
+    pub coverage: Option<CoveragePoints>,
 }
 
 #[derive(Debug)]
@@ -190,6 +206,7 @@ pub struct ParsedModule<'a> {
     pub parsed_defs: Defs<'a>,
     pub symbols_from_requires: Vec<(Loc<Symbol>, Loc<TypeAnnotation<'a>>)>,
     pub header_type: HeaderType<'a>,
+    pub header_region: Region,
     pub header_comments: &'a [CommentOrNewline<'a>],
 }
 
@@ -220,6 +237,8 @@ pub struct ExposedToHost {
     /// lambda_sets
     pub lambda_sets: Vec<(Symbol, LambdaSetId)>,
     pub getters: Vec<Symbol>,
+    /// incref and decref functions for structs which capture closures
+    pub refcounters: Vec<Symbol>,
 }
 
 impl<'a> MonomorphizedModule<'a> {
@@ -1,8 +1,10 @@
+use crate::dead_code::ModuleUsage;
 use crate::docs::ModuleDocumentation;
 use crate::module::{
     ConstrainedModule, FoundSpecializationsModule, LateSpecializationsModule, ModuleHeader,
     ParsedModule, TypeCheckedModule,
 };
+use crate::type_cache::TypeCacheKey;
 use roc_can::abilities::PendingAbilitiesStore;
 use roc_collections::{MutMap, MutSet, VecMap};
 use roc_module::ident::ModuleName;
@@ -36,6 +38,8 @@ pub(crate) struct ModuleCache<'a> {
     pub(crate) documentation: VecMap<ModuleId, ModuleDocumentation>,
     pub(crate) can_problems: MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     pub(crate) type_problems: MutMap<ModuleId, Vec<TypeError>>,
+    pub(crate) type_cache_keys: MutMap<ModuleId, TypeCacheKey>,
+    pub(crate) usage: MutMap<ModuleId, ModuleUsage>,
 
     pub(crate) sources: MutMap<ModuleId, (PathBuf, &'a str)>,
 }
@@ -104,6 +108,8 @@ impl Default for ModuleCache<'_> {
             documentation: Default::default(),
             can_problems: Default::default(),
             type_problems: Default::default(),
+            type_cache_keys: Default::default(),
+            usage: Default::default(),
             sources: Default::default(),
         }
     }
@@ -0,0 +1,321 @@
+//! An on-disk cache of solved types for user modules.
+//!
+//! The builtins ship with their solved `Subs` baked into the compiler (see the `roc_load` build
+//! script). This extends the same idea to user modules: after a module has been solved without
+//! problems, its [`TypeState`] is written to the cache directory, and on a later run an unchanged
+//! module skips constraint generation and solving entirely.
+//!
+//! Entries are keyed by a hash of everything that can influence the solved types of a module:
+//! its source, the `ModuleId` and `IdentIds` it was assigned during this run, the symbols it
+//! exposes, and (recursively) the keys of all of its imports. A `Subs` refers to other modules
+//! through `Symbol`s, which are only meaningful relative to the ids handed out by this run of
+//! the loader, so any difference there is simply a cache miss.
+use roc_can::module::TypeState;
+use roc_collections::VecSet;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_packaging::cache::RocCacheDir;
+use roc_solve::FunctionKind;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// Bump this whenever the layout written by [`TypeState::serialize_padded`] changes.
+/// It is separate from the layout of the builtins, which are baked into the compiler.
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// The name of the directory (next to the packages directory) that cached types are written to.
+const TYPE_CACHE_DIR_NAME: &str = "types";
+
+const CHECKSUM_LEN: usize = blake3::OUT_LEN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TypeCacheKey(blake3::Hash);
+
+#[derive(Debug, Clone)]
+pub(crate) struct TypeCache {
+    dir: PathBuf,
+    /// Hash of everything about this compiler invocation that affects solving, but isn't
+    /// specific to any one module.
+    salt: TypeCacheKey,
+}
+
+/// Lets us feed anything that implements [`Hash`] into a BLAKE3 hasher, so that keys are
+/// stable across runs (unlike the in-memory hashers we use for maps).
+struct KeyHasher(blake3::Hasher);
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let hash = self.0.finalize();
+        let mut first_bytes = [0; 8];
+        first_bytes.copy_from_slice(&hash.as_bytes()[..8]);
+
+        u64::from_le_bytes(first_bytes)
+    }
+}
+
+impl KeyHasher {
+    fn new() -> Self {
+        Self(blake3::Hasher::new())
+    }
+
+    fn key(&self) -> TypeCacheKey {
+        TypeCacheKey(self.0.finalize())
+    }
+}
+
+impl TypeCache {
+    /// Only persistent Roc cache dirs get a type cache; tests and build scripts never write one.
+    pub(crate) fn new(roc_cache_dir: RocCacheDir<'_>, function_kind: FunctionKind) -> Option<Self> {
+        #[cfg(debug_assertions)]
+        if roc_debug_flags::dbg_set!(roc_debug_flags::ROC_SKIP_TYPE_CACHE) {
+            return None;
+        }
+
+        match roc_cache_dir {
+            RocCacheDir::Persistent(packages_dir) if !cfg!(target_family = "wasm") => {
+                let dir = packages_dir.parent()?.join(TYPE_CACHE_DIR_NAME);
+
+                let mut hasher = KeyHasher::new();
+                CACHE_FORMAT_VERSION.hash(&mut hasher);
+                std::mem::discriminant(&function_kind).hash(&mut hasher);
+                compiler_fingerprint().hash(&mut hasher);
+
+                Some(Self {
+                    dir,
+                    salt: hasher.key(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Computes the cache key of a module. The `imports` must include every module this one
+    /// depends on, along with that module's cache key and the `IdentIds` we resolve its
+    /// symbols with.
+    pub(crate) fn module_key(
+        &self,
+        module_id: ModuleId,
+        src: &str,
+        ident_ids: &IdentIds,
+        exposed_symbols: &VecSet<Symbol>,
+        imports: &[(ModuleId, TypeCacheKey, &IdentIds)],
+    ) -> TypeCacheKey {
+        let mut hasher = KeyHasher::new();
+
+        hasher.write(self.salt.0.as_bytes());
+        module_id.hash(&mut hasher);
+        src.hash(&mut hasher);
+        hash_ident_ids(&mut hasher, ident_ids);
+
+        let mut exposed: Vec<u64> = exposed_symbols.iter().map(|s| s.as_u64()).collect();
+        exposed.sort_unstable();
+        exposed.hash(&mut hasher);
+
+        // The order in which we discover imports is not deterministic, so hash each import
+        // separately and combine them in a canonical order.
+        let mut import_hashes: Vec<[u8; 32]> = imports
+            .iter()
+            .map(|(import_id, import_key, import_ident_ids)| {
+                let mut hasher = KeyHasher::new();
+                import_id.hash(&mut hasher);
+                hasher.write(import_key.0.as_bytes());
+                hash_ident_ids(&mut hasher, import_ident_ids);
+
+                *hasher.key().0.as_bytes()
+            })
+            .collect();
+        import_hashes.sort_unstable();
+
+        for import_hash in import_hashes {
+            hasher.write(&import_hash);
+        }
+
+        hasher.key()
+    }
+
+    fn path(&self, key: TypeCacheKey) -> PathBuf {
+        let mut path = self.dir.join(key.0.to_hex().as_str());
+        path.set_extension("dat");
+
+        path
+    }
+
+    /// Returns the cached types for the given key, if there is a valid entry for it.
+    pub(crate) fn read(&self, key: TypeCacheKey) -> Option<TypeState> {
+        let bytes = std::fs::read(self.path(key)).ok()?;
+
+        if bytes.len() < CHECKSUM_LEN {
+            return None;
+        }
+
+        let (checksum, payload) = bytes.split_at(CHECKSUM_LEN);
+
+        if blake3::hash(payload).as_bytes() != checksum {
+            return None;
+        }
+
+        // Deserialization reinterprets the bytes in place, so it needs the same alignment
+        // that serialization assumed.
+        let words = aligned_copy(payload);
+        let payload =
+            unsafe { std::slice::from_raw_parts(words.as_ptr() as *const u8, payload.len()) };
+
+        let (type_state, offset) = TypeState::deserialize_padded(payload);
+
+        if offset == payload.len() {
+            Some(type_state)
+        } else {
+            None
+        }
+    }
+
+    /// Writes an entry for the given key. This is best-effort: the cache is only an
+    /// optimization, so failing to write it is not an error.
+    pub(crate) fn write(&self, key: TypeCacheKey, type_state: &TypeState) {
+        // Symbols in the derived modules are generated on demand during each run,
+        // so they can't be reused by a later one.
+        let references_derived_module = type_state.subs.symbol_names.iter().any(|symbol| {
+            matches!(
+                symbol.module_id(),
+                ModuleId::DERIVED_SYNTH | ModuleId::DERIVED_GEN
+            )
+        });
+
+        if references_derived_module {
+            return;
+        }
+
+        let mut payload = Vec::new();
+
+        if type_state.serialize_padded(&mut payload).is_err() {
+            return;
+        }
+
+        let _ = self.write_atomically(key, &payload);
+    }
+
+    fn write_atomically(&self, key: TypeCacheKey, payload: &[u8]) -> std::io::Result<()> {
+        use std::io::Write;
+
+        std::fs::create_dir_all(&self.dir)?;
+
+        // Write to a temporary file first, so that concurrent runs of the compiler never
+        // observe a partially-written entry.
+        let mut file = tempfile::NamedTempFile::new_in(&self.dir)?;
+
+        file.write_all(blake3::hash(payload).as_bytes())?;
+        file.write_all(payload)?;
+        file.persist(self.path(key))?;
+
+        Ok(())
+    }
+}
+
+fn hash_ident_ids(hasher: &mut KeyHasher, ident_ids: &IdentIds) {
+    ident_ids.len().hash(hasher);
+
+    for (_, ident) in ident_ids.ident_strs() {
+        ident.hash(hasher);
+    }
+}
+
+fn aligned_copy(bytes: &[u8]) -> Vec<u64> {
+    let mut words = vec![0u64; (bytes.len() + 7) / 8];
+
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+        let mut word_bytes = [0; 8];
+        word_bytes[..chunk.len()].copy_from_slice(chunk);
+        *word = u64::from_ne_bytes(word_bytes);
+    }
+
+    words
+}
+
+/// Identifies the running compiler binary, so that entries written by one build of the compiler
+/// are never read by another (whose solver may behave differently on the same input).
+fn compiler_fingerprint() -> Option<(PathBuf, u64, std::time::SystemTime)> {
+    let exe = std::env::current_exe().ok()?;
+    let metadata = std::fs::metadata(&exe).ok()?;
+
+    Some((exe, metadata.len(), metadata.modified().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roc_types::subs::Subs;
+
+    fn test_cache(dir: &tempfile::TempDir) -> TypeCache {
+        TypeCache {
+            dir: dir.path().to_path_buf(),
+            salt: KeyHasher::new().key(),
+        }
+    }
+
+    fn key_for(
+        cache: &TypeCache,
+        src: &str,
+        imports: &[(ModuleId, TypeCacheKey, &IdentIds)],
+    ) -> TypeCacheKey {
+        cache.module_key(
+            ModuleId::ATTR,
+            src,
+            &IdentIds::default(),
+            &VecSet::default(),
+            imports,
+        )
+    }
+
+    #[test]
+    fn key_depends_on_source_and_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+
+        let key = key_for(&cache, "x = 1", &[]);
+
+        assert_eq!(key, key_for(&cache, "x = 1", &[]));
+        assert_ne!(key, key_for(&cache, "x = 2", &[]));
+
+        let ident_ids = IdentIds::default();
+        let import_a = (ModuleId::NUM, key_for(&cache, "a", &[]), &ident_ids);
+        let import_b = (ModuleId::STR, key_for(&cache, "b", &[]), &ident_ids);
+
+        assert_ne!(key, key_for(&cache, "x = 1", &[import_a]));
+        assert_eq!(
+            key_for(&cache, "x = 1", &[import_a, import_b]),
+            key_for(&cache, "x = 1", &[import_b, import_a])
+        );
+    }
+
+    #[test]
+    fn roundtrip_and_reject_corrupt_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir);
+        let key = key_for(&cache, "x = 1", &[]);
+
+        assert!(cache.read(key).is_none());
+
+        let type_state = TypeState {
+            subs: Subs::new(),
+            exposed_vars_by_symbol: vec![],
+            abilities: Default::default(),
+            solved_implementations: Default::default(),
+        };
+
+        cache.write(key, &type_state);
+
+        let read_back = cache.read(key).expect("entry was not written");
+        assert_eq!(read_back.subs.len(), type_state.subs.len());
+
+        let path = cache.path(key);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(cache.read(key).is_none());
+    }
+}
@@ -412,6 +412,20 @@ impl<'a> Dependencies<'a> {
         true
     }
 
+    /// Whether every module has been canonicalized, and no more modules can still be discovered
+    pub fn canonicalized_all(&self) -> bool {
+        let is_after_canonicalization = |job: &Job| match job {
+            Job::Step(_, phase) => *phase > Phase::CanonicalizeAndConstrain,
+            Job::ResolveShorthand(_) => false,
+        };
+
+        // A module whose header is still being loaded only shows up as a job that others wait on
+        self.notifies.keys().all(is_after_canonicalization)
+            && self.status.iter().all(|(job, status)| {
+                matches!(status, Status::Done) || is_after_canonicalization(job)
+            })
+    }
+
     pub fn prepare_start_phase(&mut self, module_id: ModuleId, phase: Phase) -> PrepareStartPhase {
         match self.status.get_mut(&Job::Step(module_id, phase)) {
             Some(current @ Status::NotStarted) => {
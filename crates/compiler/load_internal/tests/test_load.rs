@@ -18,7 +18,7 @@ use crate::helpers::fixtures_dir;
 use bumpalo::Bump;
 use roc_can::module::ExposedByModule;
 use roc_load_internal::file::{
-    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, Threading,
+    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, TestOptions, Threading,
 };
 use roc_load_internal::module::LoadedModule;
 use roc_module::ident::ModuleName;
@@ -34,7 +34,7 @@ use roc_target::TargetInfo;
 use roc_types::pretty_print::name_and_print_var;
 use roc_types::pretty_print::DebugPrint;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn load_and_typecheck(
     arena: &Bump,
@@ -43,13 +43,31 @@ fn load_and_typecheck(
     target_info: TargetInfo,
     function_kind: FunctionKind,
 ) -> Result<LoadedModule, LoadingProblem> {
+    load_and_typecheck_help(
+        arena,
+        filename,
+        exposed_types,
+        target_info,
+        function_kind,
+        RocCacheDir::Disallowed,
+    )
+}
+
+fn load_and_typecheck_help<'a>(
+    arena: &'a Bump,
+    filename: PathBuf,
+    exposed_types: ExposedByModule,
+    target_info: TargetInfo,
+    function_kind: FunctionKind,
+    roc_cache_dir: RocCacheDir<'_>,
+) -> Result<LoadedModule, LoadingProblem<'a>> {
     use LoadResult::*;
 
     let load_start = LoadStart::from_path(
         arena,
         filename,
         RenderTarget::Generic,
-        RocCacheDir::Disallowed,
+        roc_cache_dir,
         DEFAULT_PALETTE,
     )?;
     let load_config = LoadConfig {
@@ -66,7 +84,7 @@ fn load_and_typecheck(
         load_start,
         exposed_types,
         Default::default(), // these tests will re-compile the builtins
-        RocCacheDir::Disallowed,
+        roc_cache_dir,
         load_config,
     )? {
         Monomorphized(_) => unreachable!(""),
@@ -988,6 +1006,61 @@ fn import_builtin_in_platform_and_check_app() {
     assert!(result.is_ok(), "should check");
 }
 
+#[test]
+fn package_imports_from_its_packages() {
+    let modules = vec![
+        (
+            "dep/main",
+            indoc!(
+                r#"
+                    package "dep"
+                        exposes [Helper]
+                        packages {}
+                    "#
+            ),
+        ),
+        (
+            "dep/Helper",
+            indoc!(
+                r#"
+                    interface Helper
+                        exposes [double]
+                        imports []
+
+                    double : I64 -> I64
+                    double = \n -> n * 2
+                    "#
+            ),
+        ),
+        (
+            "Thing",
+            indoc!(
+                r#"
+                    interface Thing
+                        exposes [quad]
+                        imports [h.Helper]
+
+                    quad : I64 -> I64
+                    quad = \n -> Helper.double (Helper.double n)
+                    "#
+            ),
+        ),
+        (
+            "main.roc",
+            indoc!(
+                r#"
+                    package "test"
+                        exposes [Thing]
+                        packages { h: "dep/main.roc" }
+                    "#
+            ),
+        ),
+    ];
+
+    let result = multiple_modules("package_imports_from_its_packages", modules);
+    assert!(result.is_ok(), "should check");
+}
+
 #[test]
 fn module_doesnt_match_file_path() {
     let modules = vec![(
@@ -1148,3 +1221,435 @@ fn nested_module_has_incorrect_name() {
         err
     );
 }
+
+/// An app whose `Used` module exposes something nobody uses, and which never reaches `Unreachable`
+fn unused_code_modules() -> Vec<(&'static str, &'static str)> {
+    vec![
+        (
+            "platform/main.roc",
+            indoc!(
+                r#"
+                    platform "test-platform"
+                        requires {} { main : U64 }
+                        exposes []
+                        packages {}
+                        imports []
+                        provides [mainForHost]
+
+                    mainForHost : U64
+                    mainForHost = main
+                    "#
+            ),
+        ),
+        (
+            "Unreachable",
+            indoc!(
+                r#"
+                    interface Unreachable
+                        exposes [value]
+                        imports []
+
+                    value = 3
+                    "#
+            ),
+        ),
+        (
+            "Used",
+            indoc!(
+                r#"
+                    interface Used exposes [used, unused] imports [Unreachable]
+
+                    used = 1
+
+                    unused = 2
+                    "#
+            ),
+        ),
+        (
+            "Main",
+            indoc!(
+                r#"
+                    app "test"
+                        packages { pf: "platform/main.roc" }
+                        imports [Used]
+                        provides [main] to pf
+
+                    main = Used.used
+                    "#
+            ),
+        ),
+    ]
+}
+
+#[test]
+fn unused_exposes_and_unreachable_modules() {
+    let modules = unused_code_modules();
+
+    let mut loaded_module =
+        multiple_modules("unused_exposes_and_unreachable_modules", modules).unwrap();
+
+    let mut report_for = |module_name: &str| {
+        let module_id = loaded_module
+            .interns
+            .module_ids
+            .get_id(&module_name.into())
+            .unwrap();
+        let (filepath, src) = loaded_module.sources.get(&module_id).unwrap();
+        let problems = loaded_module.can_problems.remove(&module_id).unwrap();
+
+        format_can_problems(
+            problems,
+            module_id,
+            &loaded_module.interns,
+            filepath.clone(),
+            src,
+        )
+    };
+
+    assert_eq!(
+        report_for("Used"),
+        indoc!(
+            r#"
+            ── UNUSED IMPORT ───────── tmp/unused_exposes_and_unreachable_modules/Used.roc ─
+
+            Nothing from Unreachable is used in this module.
+
+            1│  interface Used exposes [used, unused] imports [Unreachable]
+                                                               ^^^^^^^^^^^
+
+            Since Unreachable isn't used, you don't need to import it.
+
+            ── UNUSED EXPOSE ───────── tmp/unused_exposes_and_unreachable_modules/Used.roc ─
+
+            `unused` is exposed, but no other module uses it:
+
+            1│  interface Used exposes [used, unused] imports [Unreachable]
+                                              ^^^^^^
+
+            If nothing outside this module needs `unused`, you can remove it from
+            the `exposes` list.
+            "#
+        ),
+    );
+
+    assert_eq!(
+        report_for("Unreachable"),
+        indoc!(
+            r#"
+            ── UNREACHABLE MODULE ─ ...sed_exposes_and_unreachable_modules/Unreachable.roc ─
+
+            Nothing in Unreachable is used by the app, not even through other
+            modules:
+
+            1│>  interface Unreachable
+            2│>      exposes [value]
+            3│>      imports []
+
+            Since Unreachable isn't used, you can delete it, along with any
+            imports of it.
+            "#
+        ),
+    );
+}
+
+#[test]
+fn unused_code_is_only_reported_when_checking() {
+    use std::fs;
+
+    let dir = roc_test_utils::TmpDir::new("tmp/unused_code_is_only_reported_when_checking");
+
+    for (name, source) in unused_code_modules() {
+        let mut file_path = dir.path().join(name);
+        file_path.set_extension("roc");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(file_path, source).unwrap();
+    }
+
+    let arena = Bump::new();
+    let load_start = LoadStart::from_path(
+        &arena,
+        dir.path().join("Main.roc"),
+        RenderTarget::Generic,
+        RocCacheDir::Disallowed,
+        DEFAULT_PALETTE,
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Executable,
+    };
+
+    let loaded = roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    );
+
+    let module = match loaded {
+        Ok(LoadResult::Monomorphized(module)) => module,
+        Ok(LoadResult::TypeChecked(_)) => panic!("expected the app to be monomorphized"),
+        Err(problem) => panic!("{problem:?}"),
+    };
+
+    // Building an app must not fail just because some of its code is unused
+    let unused_code_warnings = module
+        .can_problems
+        .values()
+        .flatten()
+        .filter(|problem| {
+            matches!(
+                problem,
+                Problem::UnusedExpose(..) | Problem::UnreachableModule(..)
+            )
+        })
+        .count();
+
+    assert_eq!(unused_code_warnings, 0);
+}
+
+#[test]
+fn doc_test_defs_are_scoped_to_their_block() {
+    let src = indoc!(
+        r#"
+        interface Doubles exposes [double, triple] imports []
+
+        ## ```
+        ## four = double 2
+        ## eight = double four
+        ##
+        ## expect eight == 8
+        ##
+        ## expect four == 4
+        ## ```
+        double = \n -> n * 2
+
+        ## ```
+        ## expect four == 4
+        ## ```
+        triple = \n -> n * 3
+        "#
+    );
+
+    let arena = Bump::new();
+    let load_start = LoadStart::from_str(
+        &arena,
+        PathBuf::from("Doubles.roc"),
+        src,
+        RenderTarget::Generic,
+        RocCacheDir::Disallowed,
+        PathBuf::from("."),
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Test(TestOptions {
+            doc_tests: true,
+            ..TestOptions::default()
+        }),
+    };
+
+    let loaded = roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    );
+
+    // The error keeps the module from getting monomorphized
+    let mut module = match loaded {
+        Ok(LoadResult::TypeChecked(module)) => module,
+        Ok(LoadResult::Monomorphized(_)) => panic!("expected a problem in the second block"),
+        Err(problem) => panic!("{problem:?}"),
+    };
+
+    let home = module.module_id;
+    let (filename, src) = module.sources.get(&home).unwrap();
+    let problems = module.can_problems.remove(&home).unwrap_or_default();
+    let problem_count = problems.len();
+    let report = format_can_problems(problems, home, &module.interns, filename.clone(), src);
+
+    // The first block's expects see its defs, without an unused def warning for the second
+    // expect, which only needs `four`. The second block can't see them.
+    assert!(report.contains("UNRECOGNIZED NAME"), "{report}");
+    assert!(report.contains("14│  ## expect four == 4"), "{report}");
+    assert!(!report.contains("UNUSED DEFINITION"), "{report}");
+    assert!(!report.contains("## expect eight == 8"), "{report}");
+    assert_eq!(problem_count, 1, "{report}");
+}
+
+#[test]
+fn https_package_problem_as_json() {
+    let arena = Bump::new();
+    let src = indoc!(
+        r#"
+        app "test"
+            packages { pf: "https://example.com/platform.zip" }
+            imports []
+            provides [main] to pf
+
+        main = ""
+        "#
+    );
+
+    let load_start = LoadStart::from_str(
+        &arena,
+        PathBuf::from("Main.roc"),
+        src,
+        RenderTarget::Json,
+        RocCacheDir::Disallowed,
+        PathBuf::from("."),
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::Json,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Check,
+    };
+
+    let result = roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    );
+
+    match result {
+        Err(LoadingProblem::FormattedReport(report)) => assert_eq!(
+            report,
+            r#"{"severity":"fatal","file":"UNKNOWN.roc","region":null,"title":"INVALID EXTENSION","message":"I was trying to download this URL:\n\n    <https://example.com/platform.zip>\n\nHowever, this file's extension is not `.tar`.\n\nThe supported extensions are `.tar`, `.tar.gz` and `.tar.br`","hints":[{"label":"Tip","message":"Check that you have the correct URL for this package/platform."}]}"#
+        ),
+        Err(problem) => panic!("expected a formatted report, but got {problem:?}"),
+        Ok(_) => panic!("expected the package URL to be rejected"),
+    }
+}
+
+/// The entries in a type cache directory, along with when each was last written
+fn type_cache_entries(types_dir: &Path) -> HashMap<PathBuf, std::time::SystemTime> {
+    std::fs::read_dir(types_dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+            (path, modified)
+        })
+        .collect()
+}
+
+#[test]
+fn type_cache_reuses_unchanged_modules() {
+    let dir = roc_test_utils::TmpDir::new("tmp/type_cache_reuses_unchanged_modules");
+    let packages_dir = dir.path().join("cache").join("packages");
+    let types_dir = dir.path().join("cache").join("types");
+
+    let write_module = |name: &str, src: &str| {
+        std::fs::write(dir.path().join(format!("{name}.roc")), src).unwrap();
+    };
+
+    let load = |expected_types: HashMap<&str, &str>| {
+        let arena = Bump::new();
+        let loaded_module = load_and_typecheck_help(
+            &arena,
+            dir.path().join("Main.roc"),
+            Default::default(),
+            TARGET_INFO,
+            FunctionKind::LambdaSet,
+            RocCacheDir::Persistent(&packages_dir),
+        )
+        .unwrap_or_else(|problem| panic!("{problem:?}"));
+
+        expect_types(loaded_module, expected_types);
+    };
+
+    write_module(
+        "Dep",
+        indoc!(
+            r#"
+            interface Dep exposes [value] imports []
+
+            value = 1
+            "#
+        ),
+    );
+    write_module(
+        "Other",
+        indoc!(
+            r#"
+            interface Other exposes [name] imports []
+
+            name = "other"
+            "#
+        ),
+    );
+    write_module(
+        "Mid",
+        indoc!(
+            r#"
+            interface Mid exposes [value] imports [Dep]
+
+            value = Dep.value
+            "#
+        ),
+    );
+    write_module(
+        "Main",
+        indoc!(
+            r#"
+            interface Main exposes [main, other] imports [Mid, Other]
+
+            main = Mid.value
+
+            other = Other.name
+            "#
+        ),
+    );
+
+    load(hashmap! { "main" => "Num *", "other" => "Str" });
+    let first_entries = type_cache_entries(&types_dir);
+
+    // Dep, Mid and Other
+    assert_eq!(first_entries.len(), 3);
+
+    // Nothing changed, so every module is served from the cache without being written again
+    load(hashmap! { "main" => "Num *", "other" => "Str" });
+    assert_eq!(type_cache_entries(&types_dir), first_entries);
+
+    write_module(
+        "Dep",
+        indoc!(
+            r#"
+            interface Dep exposes [value] imports []
+
+            value = "one"
+            "#
+        ),
+    );
+
+    // Dep and Mid, which imports it, get new entries. Other is still served from the cache.
+    // (The root module is always solved from scratch, so it never has an entry.)
+    load(hashmap! { "main" => "Str", "other" => "Str" });
+    let entries = type_cache_entries(&types_dir);
+
+    assert_eq!(entries.len(), first_entries.len() + 2);
+    for (path, modified) in first_entries {
+        assert_eq!(entries.get(&path), Some(&modified), "{}", path.display());
+    }
+}
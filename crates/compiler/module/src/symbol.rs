@@ -516,6 +516,19 @@ impl<'a> PackageModuleIds<'a> {
         self.by_id.iter()
     }
 
+    /// The package shorthand of each module which was imported from a package
+    pub fn package_shorthands(&self) -> impl Iterator<Item = (ModuleId, &'a str)> + '_ {
+        self.by_id
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| match name {
+                PQModuleName::Qualified(shorthand, _) => {
+                    Some((ModuleId::from_zero_indexed(index), *shorthand))
+                }
+                PQModuleName::Unqualified(_) => None,
+            })
+    }
+
     /// Returns true iff two modules belong to the same package.
     /// Returns [None] if one module is unknown.
     pub fn package_eq(&self, left: ModuleId, right: ModuleId) -> Option<bool> {
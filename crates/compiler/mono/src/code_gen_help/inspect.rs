@@ -0,0 +1,1201 @@
+//! Procs that render a value as a `Str` for `dbg`, for backends where the host can't render it
+//! from the value's memory. They show values the same way the REPL and `roc test` do.
+use bumpalo::collections::vec::Vec;
+use roc_builtins::bitcode::IntWidth;
+use roc_collections::all::MutMap;
+use roc_error_macros::internal_error;
+use roc_module::ident::TagName;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, Interns, ModuleId, Symbol};
+use roc_target::TargetInfo;
+use roc_types::pretty_print::chase_ext_tag_union;
+use roc_types::subs::{Content, FlatType, Subs, Variable};
+use roc_types::types::{AliasKind, RecordField};
+
+use crate::ir::{
+    BranchInfo, Call, CallSpecId, CallType, Expr, HostExposedLayouts, JoinPointId, Literal,
+    ModifyRc, Param, Proc, SelfRecursive, Stmt, UpdateModeId,
+};
+use crate::layout::{
+    self, cmp_fields, union_sorted_tags, Builtin, GlobalLayoutInterner, InLayout, LambdaName,
+    Layout, LayoutCache, LayoutInterner, LayoutRepr, STLayoutInterner, TagIdIntType, UnionLayout,
+    UnionVariant, WrappedVariant,
+};
+
+use super::{let_lowlevel, refcount, CodeGenHelp, Context, HelperOp, Specialization};
+
+const ARG_1: Symbol = Symbol::ARG_1;
+const ARG_2: Symbol = Symbol::ARG_2;
+
+/// The types of the values we render, which tell us the names of record fields, tags and
+/// opaque types. Layouts don't have them.
+pub struct InspectTypes<'r> {
+    /// The module whose expectation subs `subs` are
+    pub module_id: ModuleId,
+    pub subs: &'r Subs,
+    pub interns: &'r Interns,
+}
+
+/// What a rendering proc is specialized for, along with the layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InspectKey {
+    module_id: ModuleId,
+    var: Variable,
+    /// The value is an argument of a tag, so it needs parentheses if it has arguments itself
+    parens: bool,
+}
+
+pub(super) struct InspectEnv<'a, 'r> {
+    types: InspectTypes<'r>,
+    /// Layouts of the types in `types.subs`, which we need to know how they're ordered in memory
+    layout_cache: LayoutCache<'a>,
+}
+
+impl<'a, 'r> InspectEnv<'a, 'r> {
+    pub(super) fn new(types: InspectTypes<'r>, target_info: TargetInfo) -> Self {
+        let interner = GlobalLayoutInterner::with_capacity(64, target_info);
+
+        InspectEnv {
+            types,
+            layout_cache: LayoutCache::new(interner.fork(), target_info),
+        }
+    }
+
+    fn content(&self, var: Variable) -> &'r Content {
+        self.types.subs.get_content_without_compacting(var)
+    }
+
+    pub(super) fn key(&self, mut var: Variable, parens: bool) -> InspectKey {
+        while let Content::RecursionVar { structure, .. } = self.content(var) {
+            var = *structure;
+        }
+
+        InspectKey {
+            module_id: self.types.module_id,
+            var: self.types.subs.get_root_key_without_compacting(var),
+            parens,
+        }
+    }
+
+    fn layout(&mut self, arena: &'a bumpalo::Bump, var: Variable) -> InLayout<'a> {
+        self.layout_cache
+            .from_var(arena, var, self.types.subs)
+            .unwrap_or_else(|problem| internal_error!("no layout for a dbg value: {problem:?}"))
+    }
+
+    fn union_variant(&mut self, arena: &'a bumpalo::Bump, var: Variable) -> UnionVariant<'a> {
+        let target_info = self.layout_cache.target_info;
+        let mut env = layout::Env::from_components(
+            &mut self.layout_cache,
+            self.types.subs,
+            arena,
+            target_info,
+        );
+
+        union_sorted_tags(&mut env, var)
+            .unwrap_or_else(|problem| internal_error!("no layout for a dbg value: {problem:?}"))
+    }
+
+    /// The payload types of each tag in a tag union type, in the order they were written
+    fn payload_vars(&self, var: Variable) -> MutMap<TagName, std::vec::Vec<Variable>> {
+        let mut tags = std::vec::Vec::new();
+        chase_ext_tag_union(self.types.subs, var, &mut tags);
+
+        tags.into_iter().collect()
+    }
+
+    /// The order in memory of values of these types, e.g. the fields of a record.
+    /// `labels` are the tie breakers, in the order the values are shown.
+    fn memory_order<L: Ord + Copy>(
+        &mut self,
+        arena: &'a bumpalo::Bump,
+        labels_and_vars: &[(L, Variable)],
+    ) -> std::vec::Vec<usize> {
+        let mut sortables: std::vec::Vec<_> = labels_and_vars
+            .iter()
+            .enumerate()
+            .map(|(index, (label, var))| (index, *label, self.layout(arena, *var)))
+            .collect();
+
+        let interner = &self.layout_cache.interner;
+        sortables.sort_by(|(_, label1, layout1), (_, label2, layout2)| {
+            cmp_fields(interner, label1, *layout1, label2, *layout2)
+        });
+
+        // For each value in the order they're shown, its index in memory
+        let mut memory_indices = vec![0; sortables.len()];
+        for (memory_index, (index, _, _)) in sortables.into_iter().enumerate() {
+            memory_indices[index] = memory_index;
+        }
+
+        memory_indices
+    }
+}
+
+/// Types which are shown wrapping a value whose layout they share, outermost first
+enum Newtype<'a> {
+    Tag(TagName),
+    RecordField(&'a str),
+    Opaque(Symbol),
+}
+
+fn unroll_newtypes_and_aliases<'a>(
+    arena: &'a bumpalo::Bump,
+    env: &mut InspectEnv<'a, '_>,
+    mut var: Variable,
+) -> (std::vec::Vec<Newtype<'a>>, Variable) {
+    let mut newtypes = std::vec::Vec::new();
+
+    loop {
+        match env.content(var) {
+            Content::Structure(FlatType::TagUnion(..)) => {
+                let tag_name = match env.union_variant(arena, var) {
+                    UnionVariant::Newtype { tag_name, .. }
+                    | UnionVariant::NewtypeByVoid {
+                        data_tag_name: tag_name,
+                        ..
+                    } => tag_name.expect_tag(),
+                    _ => return (newtypes, var),
+                };
+
+                match env.payload_vars(var).remove(&tag_name).as_deref() {
+                    Some([inner_var]) => {
+                        newtypes.push(Newtype::Tag(tag_name));
+                        var = *inner_var;
+                    }
+                    _ => return (newtypes, var),
+                }
+            }
+            Content::Structure(FlatType::Record(fields, _)) if fields.len() == 1 => {
+                let (label, field) = fields
+                    .sorted_iterator(env.types.subs, Variable::EMPTY_RECORD)
+                    .next()
+                    .unwrap();
+
+                newtypes.push(Newtype::RecordField(arena.alloc_str(label.as_str())));
+                var = field.into_inner();
+            }
+            Content::Alias(name, _, real_var, kind) => {
+                if *name == Symbol::BOOL_BOOL || name.module_id() == ModuleId::NUM {
+                    return (newtypes, var);
+                }
+
+                if *kind == AliasKind::Opaque {
+                    newtypes.push(Newtype::Opaque(*name));
+                }
+
+                var = *real_var;
+            }
+            _ => return (newtypes, var),
+        }
+    }
+}
+
+/// Call the proc which renders the value in `arguments[0]` and appends it to the `Str` in
+/// `arguments[1]`, returning the result.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn call_inspect<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+    called_layout: InLayout<'a>,
+    arguments: &'a [Symbol],
+) -> Expr<'a> {
+    let layout = if let LayoutRepr::RecursivePointer(_) = layout_interner.get_repr(called_layout) {
+        let union_layout = ctx.recursive_union.unwrap();
+        layout_interner.insert_direct_no_semantic(LayoutRepr::Union(union_layout))
+    } else {
+        called_layout
+    };
+
+    let proc_name =
+        find_or_create_inspect_proc(root, ident_ids, ctx, layout_interner, env, key, layout);
+    let arg_layout = root.replace_rec_ptr(ctx, layout_interner, layout);
+
+    Expr::Call(Call {
+        call_type: CallType::ByName {
+            name: LambdaName::no_niche(proc_name),
+            ret_layout: Layout::STR,
+            arg_layouts: root.arena.alloc([arg_layout, Layout::STR]),
+            specialization_id: CallSpecId::BACKEND_DUMMY,
+        },
+        arguments,
+    })
+}
+
+fn find_or_create_inspect_proc<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+    layout: InLayout<'a>,
+) -> Symbol {
+    let layout = root.replace_rec_ptr(ctx, layout_interner, layout);
+    let op = HelperOp::Inspect(key);
+
+    let found = root
+        .specializations
+        .iter()
+        .find(|spec| spec.op == op && spec.layout == layout);
+
+    if let Some(spec) = found {
+        return spec.symbol;
+    }
+
+    // Create the symbol before the body, which can call this proc again, as in `find_or_create_proc`
+    let parent_op = std::mem::replace(&mut ctx.op, op);
+    let (proc_symbol, proc_layout) =
+        root.create_proc_symbol(ident_ids, layout_interner, ctx, layout);
+    ctx.new_linker_data.push((proc_symbol, proc_layout));
+    let spec_index = root.specializations.len();
+    root.specializations.push(Specialization {
+        op,
+        layout,
+        symbol: proc_symbol,
+        proc: None,
+    });
+
+    let body = inspect_generic(root, ident_ids, ctx, layout_interner, env, key, layout);
+    ctx.op = parent_op;
+
+    root.specializations[spec_index].proc = Some(Proc {
+        name: LambdaName::no_niche(proc_symbol),
+        args: root.arena.alloc([(layout, ARG_1), (Layout::STR, ARG_2)]),
+        body,
+        closure_data_layout: None,
+        ret_layout: Layout::STR,
+        is_self_recursive: SelfRecursive::NotSelfRecursive,
+        host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        is_erased: false,
+    });
+
+    proc_symbol
+}
+
+/// The body of a rendering proc. `ARG_1` is the value and `ARG_2` the `Str` to append it to.
+fn inspect_generic<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+    layout: InLayout<'a>,
+) -> Stmt<'a> {
+    let (newtypes, raw_var) = unroll_newtypes_and_aliases(root.arena, env, key.var);
+
+    if newtypes.is_empty() {
+        return inspect_structure(root, ident_ids, ctx, layout_interner, env, key, layout);
+    }
+
+    // Show the newtypes around the value, which has the same layout
+    let mut out = Appender::new(root, ARG_2);
+    let mut parens = key.parens;
+    let mut closing = std::vec::Vec::with_capacity(newtypes.len());
+
+    for newtype in newtypes {
+        match newtype {
+            Newtype::Tag(tag_name) => {
+                if parens {
+                    out.push_str(root, ident_ids, "(");
+                    closing.push(")");
+                }
+                out.push_str(root, ident_ids, &format!("{} ", tag_name.0.as_str()));
+                parens = true;
+            }
+            Newtype::Opaque(name) => {
+                if parens {
+                    out.push_str(root, ident_ids, "(");
+                    closing.push(")");
+                }
+                let name = name.as_str(env.types.interns);
+                out.push_str(root, ident_ids, &format!("@{name} "));
+                parens = true;
+            }
+            Newtype::RecordField(label) => {
+                out.push_str(root, ident_ids, &format!("{{ {label}: "));
+                closing.push(" }");
+                parens = false;
+            }
+        }
+    }
+
+    let inner_key = env.key(raw_var, parens);
+    out.push_value(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        env,
+        ARG_1,
+        inner_key,
+        layout,
+    );
+
+    for close in closing.into_iter().rev() {
+        out.push_str(root, ident_ids, close);
+    }
+
+    out.finish(root, ident_ids, ctx, layout_interner, Stmt::Ret)
+}
+
+/// Render a value whose type is not a newtype or alias
+fn inspect_structure<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+    layout: InLayout<'a>,
+) -> Stmt<'a> {
+    let content = env.content(key.var);
+    let mut out = Appender::new(root, ARG_2);
+
+    match (content, layout_interner.get_repr(layout)) {
+        (Content::Structure(FlatType::Func(..)), _)
+        | (_, LayoutRepr::LambdaSet(_) | LayoutRepr::FunctionPointer(_) | LayoutRepr::Erased(_)) => {
+            out.push_str(root, ident_ids, "<function>");
+        }
+        (_, LayoutRepr::Union(UnionLayout::NonRecursive(&[]))) => {
+            // There are no values of this type, but we need to generate valid code
+        }
+        (_, LayoutRepr::Builtin(Builtin::Bool)) => {
+            return inspect_bool(root, ident_ids, ctx, layout_interner, env, key);
+        }
+        (_, LayoutRepr::Builtin(Builtin::Int(IntWidth::U8))) if !is_number(content) => {
+            return inspect_byte(root, ident_ids, ctx, layout_interner, env, key);
+        }
+        (_, LayoutRepr::Builtin(Builtin::Int(_) | Builtin::Float(_) | Builtin::Decimal)) => {
+            out.push_num(root, ident_ids, ARG_1);
+        }
+        (_, LayoutRepr::Builtin(Builtin::Str)) => {
+            out.push_str(root, ident_ids, "\"");
+            out.push_str_symbol(root, ident_ids, ARG_1);
+            out.push_str(root, ident_ids, "\"");
+        }
+        (_, LayoutRepr::Builtin(Builtin::List(elem_layout))) => {
+            let elem_var = match content {
+                Content::Structure(FlatType::Apply(Symbol::LIST_LIST, vars)) => {
+                    env.types.subs[vars.into_iter().next().unwrap()]
+                }
+                other => internal_error!("List layout for non-list type {other:?}"),
+            };
+
+            return inspect_list(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                elem_var,
+                elem_layout,
+            );
+        }
+        (_, LayoutRepr::Struct(field_layouts)) => {
+            push_struct(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                &mut out,
+                key,
+                field_layouts,
+            );
+        }
+        (
+            Content::Structure(FlatType::Apply(Symbol::BOX_BOX_TYPE, vars)),
+            LayoutRepr::Union(union_layout @ UnionLayout::NonNullableUnwrapped(&[inner_layout])),
+        ) => {
+            let inner_var = env.types.subs[vars.into_iter().next().unwrap()];
+            let inner = root.create_symbol(ident_ids, "inner");
+            let inner_expr = Expr::UnionAtIndex {
+                structure: ARG_1,
+                tag_id: 0,
+                union_layout,
+                index: 0,
+            };
+
+            if key.parens {
+                out.push_str(root, ident_ids, "(");
+            }
+            out.push_str(root, ident_ids, "Box.box ");
+            out.push_let(inner, inner_expr, inner_layout);
+            let inner_key = env.key(inner_var, true);
+            out.push_value(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                inner,
+                inner_key,
+                inner_layout,
+            );
+            if key.parens {
+                out.push_str(root, ident_ids, ")");
+            }
+        }
+        (_, LayoutRepr::Union(union_layout)) => {
+            return inspect_tag_union(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                key,
+                union_layout,
+            );
+        }
+        (_, LayoutRepr::RecursivePointer(_) | LayoutRepr::Ptr(_)) => {
+            internal_error!("{layout:?} should have been replaced by the layout it points to")
+        }
+    }
+
+    out.finish(root, ident_ids, ctx, layout_interner, Stmt::Ret)
+}
+
+fn is_number(content: &Content) -> bool {
+    matches!(content, Content::Alias(name, ..) if name.module_id() == ModuleId::NUM)
+}
+
+/// Records, tuples and tags with several arguments or none
+#[allow(clippy::too_many_arguments)]
+fn push_struct<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    out: &mut Appender<'a>,
+    key: InspectKey,
+    field_layouts: &'a [InLayout<'a>],
+) {
+    let arena = root.arena;
+
+    let (open, close, labels, vars): (_, _, std::vec::Vec<_>, std::vec::Vec<_>) =
+        match env.content(key.var) {
+            Content::Structure(FlatType::Record(fields, _)) => {
+                let (labels, vars) = fields
+                    .sorted_iterator(env.types.subs, Variable::EMPTY_RECORD)
+                    .filter_map(|(label, field)| match field {
+                        RecordField::Required(var)
+                        | RecordField::Demanded(var)
+                        | RecordField::RigidRequired(var) => {
+                            Some((&*arena.alloc_str(label.as_str()), var))
+                        }
+                        // Optional fields don't have a place in memory
+                        RecordField::Optional(_) | RecordField::RigidOptional(_) => None,
+                    })
+                    .unzip();
+
+                ("{ ", " }", labels, vars)
+            }
+            Content::Structure(FlatType::EmptyRecord) => {
+                ("{", "}", std::vec::Vec::new(), std::vec::Vec::new())
+            }
+            Content::Structure(FlatType::Tuple(elems, _)) => {
+                let vars = elems
+                    .sorted_iterator(env.types.subs, Variable::EMPTY_TUPLE)
+                    .map(|(_, var)| var)
+                    .collect();
+
+                ("(", ")", std::vec::Vec::new(), vars)
+            }
+            Content::Structure(FlatType::TagUnion(..) | FlatType::FunctionOrTagUnion(..)) => {
+                let tag_name = match env.union_variant(arena, key.var) {
+                    UnionVariant::Newtype { tag_name, .. }
+                    | UnionVariant::NewtypeByVoid {
+                        data_tag_name: tag_name,
+                        ..
+                    } => tag_name.expect_tag(),
+                    UnionVariant::Unit => {
+                        let mut tags = env.payload_vars(key.var).into_keys();
+                        tags.next().unwrap()
+                    }
+                    other => internal_error!("Struct layout for tag union variant {other:?}"),
+                };
+
+                let vars = env.payload_vars(key.var).remove(&tag_name).unwrap();
+
+                return push_tag(
+                    root,
+                    ident_ids,
+                    ctx,
+                    layout_interner,
+                    env,
+                    out,
+                    key.parens,
+                    &tag_name,
+                    &vars,
+                    field_layouts,
+                    |index| Expr::StructAtIndex {
+                        index,
+                        field_layouts,
+                        structure: ARG_1,
+                    },
+                );
+            }
+            other => internal_error!("Struct layout for type {other:?}"),
+        };
+
+    if vars.len() != field_layouts.len() {
+        internal_error!(
+            "{} fields in the type but {field_layouts:?} in the layout",
+            vars.len()
+        );
+    }
+
+    // Records are shown in alphabetical order, and tuples in order
+    let labels_and_vars: std::vec::Vec<_> = if labels.is_empty() {
+        vars.iter()
+            .enumerate()
+            .map(|(i, var)| (("", i), *var))
+            .collect()
+    } else {
+        labels
+            .iter()
+            .zip(&vars)
+            .map(|(label, var)| ((*label, 0), *var))
+            .collect()
+    };
+    let memory_indices = env.memory_order(arena, &labels_and_vars);
+
+    out.push_str(root, ident_ids, open);
+    for (i, (var, memory_index)) in vars.iter().zip(memory_indices).enumerate() {
+        if i > 0 {
+            out.push_str(root, ident_ids, ", ");
+        }
+        if let Some(label) = labels.get(i) {
+            out.push_str(root, ident_ids, &format!("{label}: "));
+        }
+
+        let field_layout = field_layouts[memory_index];
+        let field = root.create_symbol(ident_ids, &format!("field_{i}"));
+        let field_expr = Expr::StructAtIndex {
+            index: memory_index as u64,
+            field_layouts,
+            structure: ARG_1,
+        };
+        out.push_let(field, field_expr, field_layout);
+
+        let field_key = env.key(*var, false);
+        out.push_value(
+            root,
+            ident_ids,
+            ctx,
+            layout_interner,
+            env,
+            field,
+            field_key,
+            field_layout,
+        );
+    }
+    out.push_str(root, ident_ids, close);
+}
+
+/// Show a tag and its arguments, which are loaded with `load_field`
+#[allow(clippy::too_many_arguments)]
+fn push_tag<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    out: &mut Appender<'a>,
+    parens: bool,
+    tag_name: &TagName,
+    payload_vars: &[Variable],
+    field_layouts: &'a [InLayout<'a>],
+    load_field: impl Fn(u64) -> Expr<'a>,
+) {
+    let arena = root.arena;
+    let parens = parens && !payload_vars.is_empty();
+
+    if parens {
+        out.push_str(root, ident_ids, "(");
+    }
+    out.push_str(root, ident_ids, tag_name.0.as_str());
+
+    if payload_vars.len() == field_layouts.len() {
+        let indices_and_vars: std::vec::Vec<_> = payload_vars.iter().copied().enumerate().collect();
+        let memory_indices = env.memory_order(arena, &indices_and_vars);
+
+        for (i, (var, memory_index)) in payload_vars.iter().zip(memory_indices).enumerate() {
+            let field_layout = field_layouts[memory_index];
+            let field = root.create_symbol(ident_ids, &format!("field_{i}"));
+            out.push_str(root, ident_ids, " ");
+            out.push_let(field, load_field(memory_index as u64), field_layout);
+
+            let field_key = env.key(*var, true);
+            out.push_value(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                field,
+                field_key,
+                field_layout,
+            );
+        }
+    } else if field_layouts.is_empty() {
+        // e.g. `Foo Bar`, where the unit struct of `Bar` was dropped from the layout
+        for (i, var) in payload_vars.iter().enumerate() {
+            let unit = root.create_symbol(ident_ids, &format!("unit_{i}"));
+            out.push_str(root, ident_ids, " ");
+            out.push_let(unit, Expr::Struct(&[]), Layout::UNIT);
+
+            let unit_key = env.key(*var, true);
+            out.push_value(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                env,
+                unit,
+                unit_key,
+                Layout::UNIT,
+            );
+        }
+    } else {
+        internal_error!(
+            "{tag_name:?} has arguments {payload_vars:?} but layouts {field_layouts:?}"
+        );
+    }
+
+    if parens {
+        out.push_str(root, ident_ids, ")");
+    }
+}
+
+/// Bools, and tag unions with two tags and no arguments
+fn inspect_bool<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+) -> Stmt<'a> {
+    let (if_false, if_true) = match env.content(key.var) {
+        Content::Alias(Symbol::BOOL_BOOL, ..) => ("Bool.false".into(), "Bool.true".into()),
+        _ => match env.union_variant(root.arena, key.var) {
+            UnionVariant::BoolUnion { ttrue, ffalse } => (
+                ffalse.expect_tag().0.as_str().to_string(),
+                ttrue.expect_tag().0.as_str().to_string(),
+            ),
+            other => internal_error!("Bool layout for tag union variant {other:?}"),
+        },
+    };
+
+    let branches = [if_false, if_true];
+    inspect_by_value(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        Layout::BOOL,
+        &branches,
+    )
+}
+
+/// Tag unions whose tags have no arguments, which are stored as a byte
+fn inspect_byte<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+) -> Stmt<'a> {
+    let tag_names: std::vec::Vec<_> = match env.union_variant(root.arena, key.var) {
+        UnionVariant::ByteUnion(tag_names) => tag_names
+            .into_iter()
+            .map(|tag_name| tag_name.expect_tag().0.as_str().to_string())
+            .collect(),
+        other => internal_error!("U8 layout for tag union variant {other:?}"),
+    };
+
+    inspect_by_value(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        Layout::U8,
+        &tag_names,
+    )
+}
+
+/// Show the string at the index given by the value
+fn inspect_by_value<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+    strings: &[String],
+) -> Stmt<'a> {
+    let arena = root.arena;
+    let mut branches = Vec::with_capacity_in(strings.len(), arena);
+
+    for (value, string) in strings.iter().enumerate() {
+        let mut out = Appender::new(root, ARG_2);
+        out.push_str(root, ident_ids, string);
+        let branch = out.finish(root, ident_ids, ctx, layout_interner, Stmt::Ret);
+        branches.push((value as u64, BranchInfo::None, branch));
+    }
+
+    let (_, _, default_branch) = branches.pop().unwrap();
+
+    Stmt::Switch {
+        cond_symbol: ARG_1,
+        cond_layout: layout,
+        branches: branches.into_bump_slice(),
+        default_branch: (BranchInfo::None, arena.alloc(default_branch)),
+        ret_layout: Layout::STR,
+    }
+}
+
+fn inspect_tag_union<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    key: InspectKey,
+    union_layout: UnionLayout<'a>,
+) -> Stmt<'a> {
+    use WrappedVariant::*;
+
+    let arena = root.arena;
+
+    let parent_rec_ptr_layout = ctx.recursive_union;
+    if !matches!(union_layout, UnionLayout::NonRecursive(_)) {
+        ctx.recursive_union = Some(union_layout);
+    }
+
+    // The name of each tag, by its ID
+    let variant = match env.union_variant(arena, key.var) {
+        UnionVariant::Wrapped(variant) => variant,
+        other => internal_error!("{union_layout:?} layout for tag union variant {other:?}"),
+    };
+    let tag_names: std::vec::Vec<(TagIdIntType, TagName)> = match variant {
+        Recursive { sorted_tag_layouts } | NonRecursive { sorted_tag_layouts } => {
+            sorted_tag_layouts
+                .into_iter()
+                .enumerate()
+                .map(|(tag_id, (tag_name, _))| (tag_id as _, tag_name.expect_tag()))
+                .collect()
+        }
+        NullableWrapped {
+            nullable_id,
+            nullable_name,
+            sorted_tag_layouts,
+        } => {
+            let others = sorted_tag_layouts
+                .into_iter()
+                .enumerate()
+                .map(|(i, (tag_name, _))| {
+                    let tag_id = if i >= nullable_id as usize { i + 1 } else { i };
+                    (tag_id as _, tag_name.expect_tag())
+                });
+
+            std::iter::once((nullable_id, nullable_name.expect_tag()))
+                .chain(others)
+                .collect()
+        }
+        NonNullableUnwrapped { tag_name, .. } => vec![(0, tag_name.expect_tag())],
+        NullableUnwrapped {
+            nullable_id,
+            nullable_name,
+            other_name,
+            ..
+        } => vec![
+            (nullable_id as _, nullable_name.expect_tag()),
+            (!nullable_id as _, other_name.expect_tag()),
+        ],
+    };
+    let mut payload_vars = env.payload_vars(key.var);
+
+    let tag_id_sym = root.create_symbol(ident_ids, "tag_id");
+    let tag_id_layout = union_layout.tag_id_layout();
+    let mut branches = Vec::with_capacity_in(tag_names.len(), arena);
+
+    for (tag_id, tag_name) in tag_names {
+        let vars = payload_vars.remove(&tag_name).unwrap_or_default();
+        let field_layouts = tag_field_layouts(union_layout, tag_id);
+
+        let mut out = Appender::new(root, ARG_2);
+        push_tag(
+            root,
+            ident_ids,
+            ctx,
+            layout_interner,
+            env,
+            &mut out,
+            key.parens,
+            &tag_name,
+            &vars,
+            field_layouts,
+            |index| Expr::UnionAtIndex {
+                structure: ARG_1,
+                tag_id,
+                union_layout,
+                index,
+            },
+        );
+        let branch = out.finish(root, ident_ids, ctx, layout_interner, Stmt::Ret);
+
+        branches.push((tag_id as u64, BranchInfo::None, branch));
+    }
+
+    ctx.recursive_union = parent_rec_ptr_layout;
+
+    let (_, _, default_branch) = branches.pop().unwrap();
+
+    Stmt::Let(
+        tag_id_sym,
+        Expr::GetTagId {
+            structure: ARG_1,
+            union_layout,
+        },
+        tag_id_layout,
+        arena.alloc(Stmt::Switch {
+            cond_symbol: tag_id_sym,
+            cond_layout: tag_id_layout,
+            branches: branches.into_bump_slice(),
+            default_branch: (BranchInfo::None, arena.alloc(default_branch)),
+            ret_layout: Layout::STR,
+        }),
+    )
+}
+
+fn tag_field_layouts<'a>(
+    union_layout: UnionLayout<'a>,
+    tag_id: TagIdIntType,
+) -> &'a [InLayout<'a>] {
+    use UnionLayout::*;
+
+    match union_layout {
+        NonRecursive(tags) | Recursive(tags) => tags[tag_id as usize],
+        NonNullableUnwrapped(fields) => fields,
+        NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => match tag_id.cmp(&nullable_id) {
+            std::cmp::Ordering::Less => other_tags[tag_id as usize],
+            std::cmp::Ordering::Equal => &[],
+            std::cmp::Ordering::Greater => other_tags[tag_id as usize - 1],
+        },
+        NullableUnwrapped {
+            nullable_id,
+            other_fields,
+        } => {
+            if tag_id == nullable_id as TagIdIntType {
+                &[]
+            } else {
+                other_fields
+            }
+        }
+    }
+}
+
+/// `[a, b, c]`, visiting the elements the same way as `==` on lists
+fn inspect_list<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    env: &mut InspectEnv<'a, '_>,
+    elem_var: Variable,
+    elem_layout: InLayout<'a>,
+) -> Stmt<'a> {
+    use LowLevel::*;
+    let layout_isize = root.layout_isize;
+    let arena = root.arena;
+
+    let ptr_layout = layout_interner.insert_direct_no_semantic(LayoutRepr::Ptr(elem_layout));
+
+    let len = root.create_symbol(ident_ids, "len");
+    let elements = root.create_symbol(ident_ids, "elements");
+    let start = root.create_symbol(ident_ids, "start");
+    let size = root.create_symbol(ident_ids, "size");
+    let list_size = root.create_symbol(ident_ids, "list_size");
+    let end = root.create_symbol(ident_ids, "end");
+
+    let elem_size = layout_interner
+        .get_repr(elem_layout)
+        .stack_size(layout_interner);
+
+    //
+    // Loop body, with the address of the next element and the `Str` so far
+    //
+
+    let elems_loop = JoinPointId(root.create_symbol(ident_ids, "elems_loop"));
+    let addr = root.create_symbol(ident_ids, "addr");
+    let loop_acc = root.create_symbol(ident_ids, "loop_acc");
+
+    let is_end = root.create_symbol(ident_ids, "is_end");
+
+    let mut at_end = Appender::new(root, loop_acc);
+    at_end.push_str(root, ident_ids, "]");
+    let at_end = at_end.finish(root, ident_ids, ctx, layout_interner, Stmt::Ret);
+
+    let ptr = root.create_symbol(ident_ids, "ptr");
+    let elem = root.create_symbol(ident_ids, "elem");
+    let next = root.create_symbol(ident_ids, "next");
+    let is_last = root.create_symbol(ident_ids, "is_last");
+
+    let mut elem_out = Appender::new(root, loop_acc);
+    elem_out.push_let(ptr, lowlevel(arena, PtrCast, &[addr]), ptr_layout);
+    elem_out.push_let(elem, Expr::ptr_load(arena.alloc(ptr)), elem_layout);
+    let elem_key = env.key(elem_var, false);
+    elem_out.push_value(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        env,
+        elem,
+        elem_key,
+        elem_layout,
+    );
+    elem_out.push_let(next, lowlevel(arena, NumAdd, &[addr, size]), layout_isize);
+    elem_out.push_let(is_last, lowlevel(arena, NumGte, &[next, end]), Layout::BOOL);
+
+    // Separate this element from the next one, if there is one
+    let separator = root.create_symbol(ident_ids, "separator");
+    let separated = root.create_symbol(ident_ids, "separated");
+    let after_elem = elem_out.finish(root, ident_ids, ctx, layout_interner, |acc| {
+        let jump_last = Stmt::Jump(elems_loop, arena.alloc([next, acc]));
+        let jump_separated = Stmt::Let(
+            separator,
+            Expr::Literal(Literal::Str(", ")),
+            Layout::STR,
+            arena.alloc(let_lowlevel(
+                arena,
+                Layout::STR,
+                separated,
+                StrConcat,
+                &[acc, separator],
+                arena.alloc(Stmt::Jump(elems_loop, arena.alloc([next, separated]))),
+            )),
+        );
+
+        Stmt::if_then_else(
+            arena,
+            is_last,
+            Layout::STR,
+            jump_last,
+            arena.alloc(jump_separated),
+        )
+    });
+
+    let loop_body = let_lowlevel(
+        arena,
+        Layout::BOOL,
+        is_end,
+        NumGte,
+        &[addr, end],
+        arena.alloc(Stmt::if_then_else(
+            arena,
+            is_end,
+            Layout::STR,
+            at_end,
+            arena.alloc(after_elem),
+        )),
+    );
+
+    //
+    // Before the loop
+    //
+
+    let mut out = Appender::new(root, ARG_2);
+    out.push_let(len, lowlevel(arena, ListLen, &[ARG_1]), layout_isize);
+    out.push_let(
+        elements,
+        Expr::StructAtIndex {
+            index: 0,
+            field_layouts: arena.alloc([ptr_layout, layout_isize]),
+            structure: ARG_1,
+        },
+        ptr_layout,
+    );
+    out.push_let(start, lowlevel(arena, PtrCast, &[elements]), layout_isize);
+    out.push_let(
+        size,
+        Expr::Literal(Literal::Int((elem_size as i128).to_ne_bytes())),
+        layout_isize,
+    );
+    out.push_let(
+        list_size,
+        lowlevel(arena, NumMul, &[len, size]),
+        layout_isize,
+    );
+    out.push_let(
+        end,
+        lowlevel(arena, NumAdd, &[start, list_size]),
+        layout_isize,
+    );
+    out.push_str(root, ident_ids, "[");
+
+    out.finish(root, ident_ids, ctx, layout_interner, |acc| Stmt::Join {
+        id: elems_loop,
+        parameters: arena.alloc([
+            Param {
+                symbol: addr,
+                layout: layout_isize,
+            },
+            Param {
+                symbol: loop_acc,
+                layout: Layout::STR,
+            },
+        ]),
+        body: arena.alloc(loop_body),
+        remainder: arena.alloc(Stmt::Jump(elems_loop, arena.alloc([start, acc]))),
+    })
+}
+
+fn lowlevel<'a>(arena: &'a bumpalo::Bump, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+    Expr::Call(Call {
+        call_type: CallType::LowLevel {
+            op,
+            update_mode: UpdateModeId::BACKEND_DUMMY,
+        },
+        arguments: arena.alloc_slice_copy(arguments),
+    })
+}
+
+enum Step<'a> {
+    Let(Symbol, Expr<'a>, InLayout<'a>),
+    /// Decrement a `Str` we made, once it has been appended
+    DecStr(Symbol),
+}
+
+/// Straight-line code which appends to a `Str`. Each `Str` we append to is consumed,
+/// and the one after it is the result.
+struct Appender<'a> {
+    acc: Symbol,
+    steps: Vec<'a, Step<'a>>,
+}
+
+impl<'a> Appender<'a> {
+    fn new(root: &CodeGenHelp<'a>, acc: Symbol) -> Self {
+        Appender {
+            acc,
+            steps: Vec::new_in(root.arena),
+        }
+    }
+
+    fn push_let(&mut self, symbol: Symbol, expr: Expr<'a>, layout: InLayout<'a>) {
+        self.steps.push(Step::Let(symbol, expr, layout));
+    }
+
+    fn push_str(&mut self, root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds, string: &str) {
+        let literal = root.create_symbol(ident_ids, "literal");
+        let literal_expr = Expr::Literal(Literal::Str(root.arena.alloc_str(string)));
+        self.push_let(literal, literal_expr, Layout::STR);
+        self.push_str_symbol(root, ident_ids, literal);
+    }
+
+    /// Append a `Str`, which is only borrowed
+    fn push_str_symbol(
+        &mut self,
+        root: &CodeGenHelp<'a>,
+        ident_ids: &mut IdentIds,
+        string: Symbol,
+    ) {
+        let acc = root.create_symbol(ident_ids, "acc");
+        let concat = lowlevel(root.arena, LowLevel::StrConcat, &[self.acc, string]);
+        self.push_let(acc, concat, Layout::STR);
+        self.acc = acc;
+    }
+
+    fn push_num(&mut self, root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds, num: Symbol) {
+        let string = root.create_symbol(ident_ids, "num_str");
+        self.push_let(
+            string,
+            lowlevel(root.arena, LowLevel::NumToStr, &[num]),
+            Layout::STR,
+        );
+        self.push_str_symbol(root, ident_ids, string);
+        self.steps.push(Step::DecStr(string));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_value(
+        &mut self,
+        root: &mut CodeGenHelp<'a>,
+        ident_ids: &mut IdentIds,
+        ctx: &mut Context<'a>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        env: &mut InspectEnv<'a, '_>,
+        value: Symbol,
+        key: InspectKey,
+        layout: InLayout<'a>,
+    ) {
+        let acc = root.create_symbol(ident_ids, "acc");
+        let arguments = root.arena.alloc([value, self.acc]);
+        let call = call_inspect(
+            root,
+            ident_ids,
+            ctx,
+            layout_interner,
+            env,
+            key,
+            layout,
+            arguments,
+        );
+        self.push_let(acc, call, Layout::STR);
+        self.acc = acc;
+    }
+
+    /// The statements, followed by `end`, which gets the `Str` built so far
+    fn finish(
+        self,
+        root: &mut CodeGenHelp<'a>,
+        ident_ids: &mut IdentIds,
+        ctx: &mut Context<'a>,
+        layout_interner: &mut STLayoutInterner<'a>,
+        end: impl FnOnce(Symbol) -> Stmt<'a>,
+    ) -> Stmt<'a> {
+        let arena = root.arena;
+        let mut stmt = end(self.acc);
+
+        for step in self.steps.into_iter().rev() {
+            stmt = match step {
+                Step::Let(symbol, expr, layout) => {
+                    Stmt::Let(symbol, expr, layout, arena.alloc(stmt))
+                }
+                Step::DecStr(symbol) => {
+                    let mut dec_ctx = Context {
+                        new_linker_data: Vec::new_in(arena),
+                        recursive_union: None,
+                        op: HelperOp::Dec,
+                    };
+                    let dec_stmt = refcount::refcount_stmt(
+                        root,
+                        ident_ids,
+                        &mut dec_ctx,
+                        layout_interner,
+                        Layout::STR,
+                        &ModifyRc::Dec(symbol),
+                        arena.alloc(stmt),
+                    );
+                    ctx.new_linker_data.extend(dec_ctx.new_linker_data);
+
+                    dec_stmt.clone()
+                }
+            };
+        }
+
+        stmt
+    }
+}
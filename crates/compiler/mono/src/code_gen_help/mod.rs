@@ -4,6 +4,7 @@ use bumpalo::Bump;
 use roc_module::low_level::LowLevel;
 use roc_module::symbol::{IdentIds, ModuleId, Symbol};
 use roc_target::TargetInfo;
+use roc_types::subs::Variable;
 
 use crate::ir::{
     BranchInfo, Call, CallSpecId, CallType, Expr, HostExposedLayouts, JoinPointId, Literal,
@@ -15,8 +16,11 @@ use crate::layout::{
 };
 
 mod equality;
+mod inspect;
 mod refcount;
 
+pub use inspect::{InspectKey, InspectTypes};
+
 const LAYOUT_BOOL: InLayout = Layout::BOOL;
 const LAYOUT_UNIT: InLayout = Layout::UNIT;
 
@@ -38,6 +42,7 @@ pub enum HelperOp {
     Reset,
     ResetRef,
     Eq,
+    Inspect(InspectKey),
 }
 
 impl HelperOp {
@@ -267,6 +272,40 @@ impl<'a> CodeGenHelp<'a> {
         (expr, ctx.new_linker_data)
     }
 
+    /// Render a value of type `var` as a `Str` the way the REPL shows it, for `dbg`.
+    /// The arguments are the value and a `Str` to append the rendering to, which is consumed.
+    /// The helper procs themselves are to be generated later with `generate_procs`
+    pub fn call_inspect(
+        &mut self,
+        ident_ids: &mut IdentIds,
+        layout_interner: &mut STLayoutInterner<'a>,
+        types: InspectTypes,
+        var: Variable,
+        layout: InLayout<'a>,
+        arguments: &'a [Symbol; 2],
+    ) -> (Expr<'a>, Vec<'a, (Symbol, ProcLayout<'a>)>) {
+        let mut env = inspect::InspectEnv::new(types, self.target_info);
+        let key = env.key(var, false);
+        let mut ctx = Context {
+            new_linker_data: Vec::new_in(self.arena),
+            recursive_union: None,
+            op: HelperOp::Inspect(key),
+        };
+
+        let expr = inspect::call_inspect(
+            self,
+            ident_ids,
+            &mut ctx,
+            layout_interner,
+            &mut env,
+            key,
+            layout,
+            arguments,
+        );
+
+        (expr, ctx.new_linker_data)
+    }
+
     // ============================================================================
     //
     //              CALL SPECIALIZED OP
@@ -311,6 +350,7 @@ impl<'a> CodeGenHelp<'a> {
                     IndirectDec => (LAYOUT_UNIT, arena.alloc([ptr_arg])),
                     IndirectInc => (LAYOUT_UNIT, arena.alloc([ptr_arg, self.layout_isize])),
                     Eq => (LAYOUT_BOOL, self.arena.alloc([arg, arg])),
+                    Inspect(_) => unreachable!("Inspect calls are made by inspect::call_inspect"),
                 }
             };
 
@@ -420,6 +460,7 @@ impl<'a> CodeGenHelp<'a> {
                 LAYOUT_BOOL,
                 equality::eq_generic(self, ident_ids, ctx, layout_interner, layout),
             ),
+            Inspect(_) => unreachable!("Inspect procs are made by inspect::call_inspect"),
         };
 
         let args: &'a [(InLayout<'a>, Symbol)] = {
@@ -442,6 +483,7 @@ impl<'a> CodeGenHelp<'a> {
                     self.arena.alloc([(ptr_layout, ARG_1)])
                 }
                 Eq => self.arena.alloc([roc_value, (layout, ARG_2)]),
+                Inspect(_) => unreachable!("Inspect procs are made by inspect::call_inspect"),
             }
         };
 
@@ -515,6 +557,11 @@ impl<'a> CodeGenHelp<'a> {
                 result: LAYOUT_BOOL,
                 niche: Niche::NONE,
             },
+            HelperOp::Inspect(_) => ProcLayout {
+                arguments: self.arena.alloc([layout, Layout::STR]),
+                result: Layout::STR,
+                niche: Niche::NONE,
+            },
         };
 
         (proc_symbol, proc_layout)
@@ -0,0 +1,177 @@
+//! Instrumentation for measuring which code `roc test` exercises.
+//!
+//! Before anything is specialized, every top-level def and every `when` branch in the modules
+//! being measured is registered as a [CoveragePoint]. While specializing, we then put a call to
+//! the [COVERAGE_HIT] foreign function at the start of each def's body and each branch, passing
+//! it the index of the point. It is up to the backend to implement that function, by adding one
+//! to a counter for each index.
+use std::sync::{Arc, Mutex};
+
+use roc_can::expr::{Declarations, Expr as CanExpr};
+use roc_can::traverse::{walk_decl, walk_expr, DeclarationInfo, Visitor};
+use roc_collections::MutMap;
+use roc_module::ident::ForeignSymbol;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_region::all::Region;
+use roc_types::subs::Variable;
+
+use crate::ir::{Call, CallType, Env, Expr, Literal, Stmt};
+use crate::layout::Layout;
+
+/// The foreign function that instrumented code calls with the index of each point it reaches
+pub const COVERAGE_HIT: &str = "roc_coverage_hit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageKind {
+    /// The body of a top-level def
+    Def(Symbol),
+    /// The body of a `when` branch
+    WhenBranch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoveragePoint {
+    pub module_id: ModuleId,
+    pub region: Region,
+    pub kind: CoverageKind,
+}
+
+#[derive(Debug, Default)]
+pub struct CoveragePoints {
+    points: Vec<CoveragePoint>,
+    defs: MutMap<Symbol, u32>,
+    branches: MutMap<(ModuleId, Region), u32>,
+}
+
+pub type SharedCoveragePoints = Arc<Mutex<CoveragePoints>>;
+
+impl CoveragePoints {
+    /// Register the top-level defs of a module, and the `when` branches inside them.
+    /// Expectations are what is doing the measuring, so they are not measured themselves.
+    pub fn register_module(&mut self, home: ModuleId, decls: &Declarations) {
+        let mut registrar = Registrar { home, points: self };
+
+        registrar.visit_decls(decls);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// All the points, in order of their index
+    pub fn points(&self) -> &[CoveragePoint] {
+        &self.points
+    }
+
+    fn add(&mut self, point: CoveragePoint) -> u32 {
+        let index = self.points.len() as u32;
+
+        self.points.push(point);
+
+        index
+    }
+}
+
+struct Registrar<'p> {
+    home: ModuleId,
+    points: &'p mut CoveragePoints,
+}
+
+impl Visitor for Registrar<'_> {
+    fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+        match &decl {
+            DeclarationInfo::Value { loc_symbol, .. }
+            | DeclarationInfo::Function { loc_symbol, .. } => {
+                let index = self.points.add(CoveragePoint {
+                    module_id: self.home,
+                    region: decl.region(),
+                    kind: CoverageKind::Def(loc_symbol.value),
+                });
+
+                self.points.defs.insert(loc_symbol.value, index);
+            }
+            DeclarationInfo::Destructure { .. } => {}
+            DeclarationInfo::Expectation { .. } => return,
+        }
+
+        walk_decl(self, decl);
+    }
+
+    fn visit_expr(&mut self, expr: &CanExpr, _region: Region, var: Variable) {
+        if let CanExpr::When { branches, .. } = expr {
+            for branch in branches {
+                let region = branch.value.region;
+                let index = self.points.add(CoveragePoint {
+                    module_id: self.home,
+                    region,
+                    kind: CoverageKind::WhenBranch,
+                });
+
+                self.points.branches.insert((self.home, region), index);
+            }
+        }
+
+        walk_expr(self, expr, var);
+    }
+}
+
+/// If we are measuring coverage of the def `symbol`, count a hit before running its body
+pub(crate) fn cover_def<'a>(env: &mut Env<'a, '_>, symbol: Symbol, body: Stmt<'a>) -> Stmt<'a> {
+    let opt_index = env
+        .coverage
+        .and_then(|coverage| coverage.lock().unwrap().defs.get(&symbol).copied());
+
+    match opt_index {
+        Some(index) => record_hit(env, index, body),
+        None => body,
+    }
+}
+
+/// If we are measuring coverage, count a hit of the `when` branch whose body is at `region`
+/// before running it
+pub(crate) fn cover_when_branch<'a>(
+    env: &mut Env<'a, '_>,
+    region: Region,
+    branch: Stmt<'a>,
+) -> Stmt<'a> {
+    let home = env.home;
+    let opt_index = env.coverage.and_then(|coverage| {
+        coverage
+            .lock()
+            .unwrap()
+            .branches
+            .get(&(home, region))
+            .copied()
+    });
+
+    match opt_index {
+        Some(index) => record_hit(env, index, branch),
+        None => branch,
+    }
+}
+
+fn record_hit<'a>(env: &mut Env<'a, '_>, index: u32, stmt: Stmt<'a>) -> Stmt<'a> {
+    let index_symbol = env.unique_symbol();
+    let unit_symbol = env.unique_symbol();
+
+    let call = Expr::Call(Call {
+        call_type: CallType::Foreign {
+            foreign_symbol: ForeignSymbol::from(COVERAGE_HIT),
+            ret_layout: Layout::UNIT,
+        },
+        arguments: env.arena.alloc([index_symbol]),
+    });
+
+    let hit = Stmt::Let(unit_symbol, call, Layout::UNIT, env.arena.alloc(stmt));
+
+    Stmt::Let(
+        index_symbol,
+        Expr::Literal(Literal::Int((index as i128).to_ne_bytes())),
+        Layout::U32,
+        env.arena.alloc(hit),
+    )
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::manual_map)]
 
+use crate::coverage::{cover_def, cover_when_branch, SharedCoveragePoints};
 use crate::ir::erased::{build_erased_function, ResolvedErasedLambda};
 use crate::ir::literal::{make_num_literal, IntOrFloatValue};
 use crate::layout::{
@@ -1361,6 +1362,8 @@ pub struct Env<'a, 'i> {
     pub exposed_by_module: &'i ExposedByModule,
     pub derived_module: &'i SharedDerivedModule,
     pub struct_indexing: UsageTrackingMap<(Symbol, u64), Symbol>,
+    /// The defs and `when` branches to count hits of. [None] unless we are measuring coverage.
+    pub coverage: Option<&'i SharedCoveragePoints>,
 }
 
 impl<'a, 'i> Env<'a, 'i> {
@@ -3218,6 +3221,7 @@ fn specialize_external_help<'a>(
 
                     let GlueProcs {
                         getters,
+                        refcounters: _,
                         legacy_layout_based_extern_names: _,
                     } = all_glue_procs;
 
@@ -3530,7 +3534,8 @@ fn specialize_proc_help<'a>(
     // host-exposed functions are tagged on later
     let host_exposed_layouts = HostExposedLayouts::NotHostExposed;
 
-    let mut specialized_body = from_can(env, body_var, body, procs, layout_cache);
+    let specialized_body = from_can(env, body_var, body, procs, layout_cache);
+    let mut specialized_body = cover_def(env, lambda_name.name(), specialized_body);
 
     let specialized_proc = match specialized {
         SpecializedLayout::FunctionPointerBody {
@@ -7171,17 +7176,22 @@ fn store_specialized_expectation_lookups(
     }
 }
 
+/// A branch's pattern, guard and body, and the region of the body in the source
+/// (which the branch added for a non-exhaustive `when` does not have)
+type OptBranch<'a> = (
+    Pattern<'a>,
+    Option<Loc<roc_can::expr::Expr>>,
+    roc_can::expr::Expr,
+    Option<Region>,
+);
+
 fn to_opt_branches<'a>(
     env: &mut Env<'a, '_>,
     procs: &mut Procs<'a>,
     branches: std::vec::Vec<roc_can::expr::WhenBranch>,
     exhaustive_mark: ExhaustiveMark,
     layout_cache: &mut LayoutCache<'a>,
-) -> std::vec::Vec<(
-    Pattern<'a>,
-    Option<Loc<roc_can::expr::Expr>>,
-    roc_can::expr::Expr,
-)> {
+) -> std::vec::Vec<OptBranch<'a>> {
     debug_assert!(!branches.is_empty());
 
     let mut opt_branches = std::vec::Vec::new();
@@ -7225,7 +7235,12 @@ fn to_opt_branches<'a>(
                     };
 
                     // TODO remove clone?
-                    opt_branches.push((mono_pattern, when_branch.guard.clone(), loc_expr.value));
+                    opt_branches.push((
+                        mono_pattern,
+                        when_branch.guard.clone(),
+                        loc_expr.value,
+                        Some(when_branch.value.region),
+                    ));
                 }
                 Err(runtime_error) => {
                     // TODO remove clone?
@@ -7233,6 +7248,7 @@ fn to_opt_branches<'a>(
                         Pattern::Underscore,
                         when_branch.guard.clone(),
                         roc_can::expr::Expr::RuntimeError(runtime_error),
+                        Some(when_branch.value.region),
                     ));
                 }
             }
@@ -7246,6 +7262,7 @@ fn to_opt_branches<'a>(
             Pattern::Underscore,
             None,
             roc_can::expr::Expr::RuntimeError(roc_problem::can::RuntimeError::NonExhaustivePattern),
+            None,
         ));
     }
 
@@ -7286,7 +7303,7 @@ fn from_can_when<'a>(
     let arena = env.arena;
     let it = opt_branches
         .into_iter()
-        .filter_map(|(pattern, opt_guard, can_expr)| {
+        .filter_map(|(pattern, opt_guard, can_expr, opt_region)| {
             // If the pattern has a void layout we can drop it; however, we must still perform the
             // work of building the body, because that may contain specializations we must
             // discover for use elsewhere. See
@@ -7314,6 +7331,11 @@ fn from_can_when<'a>(
                 }
             };
 
+            let branch_stmt = match opt_region {
+                Some(region) => cover_when_branch(env, region, branch_stmt),
+                None => branch_stmt,
+            };
+
             use decision_tree::Guard;
             let result = if let Some(loc_expr) = opt_guard {
                 let guard_spec = GuardStmtSpec {
@@ -9972,6 +9994,7 @@ where
 #[derive(Debug, Default)]
 pub struct GlueLayouts<'a> {
     pub getters: std::vec::Vec<(Symbol, ProcLayout<'a>)>,
+    pub refcounters: std::vec::Vec<GlueRefcounters<'a>>,
 }
 
 type GlueProcId = u16;
@@ -9983,8 +10006,19 @@ pub struct GlueProc<'a> {
     pub proc: Proc<'a>,
 }
 
+/// The functions an app exposes so that hosts can incref and decref a struct which captures
+/// closures. Hosts can't do that themselves, because the struct's layout depends on the app.
+#[derive(Debug, Clone, Copy)]
+pub struct GlueRefcounters<'a> {
+    pub incref: Symbol,
+    pub decref: Symbol,
+    /// The layout of the struct, which the host passes a pointer to
+    pub layout: InLayout<'a>,
+}
+
 pub struct GlueProcs<'a> {
     pub getters: Vec<'a, (Layout<'a>, Vec<'a, GlueProc<'a>>)>,
+    pub refcounters: Vec<'a, (Layout<'a>, GlueRefcounters<'a>)>,
     /// Lambda set IDs computed from the layout of the lambda set. Should be replaced by
     /// computation from type variable eventually.
     pub legacy_layout_based_extern_names: Vec<'a, (LambdaSetId, RawFunctionLayout<'a>)>,
@@ -10138,6 +10172,7 @@ where
 {
     let mut answer = GlueProcs {
         getters: Vec::new_in(arena),
+        refcounters: Vec::new_in(arena),
         legacy_layout_based_extern_names: Vec::new_in(arena),
     };
 
@@ -10201,6 +10236,26 @@ where
                     );
 
                     answer.getters.push((layout, procs));
+
+                    let refcounters = GlueRefcounters {
+                        incref: unique_glue_symbol(
+                            arena,
+                            &mut next_unique_id,
+                            home,
+                            ident_ids,
+                            "incref",
+                        ),
+                        decref: unique_glue_symbol(
+                            arena,
+                            &mut next_unique_id,
+                            home,
+                            ident_ids,
+                            "decref",
+                        ),
+                        layout: layout_interner.insert(layout),
+                    };
+
+                    answer.refcounters.push((layout, refcounters));
                 }
 
                 for in_layout in field_layouts.iter().rev() {
@@ -10303,7 +10358,7 @@ where
             niche: Niche::NONE,
         };
 
-        let symbol = unique_glue_symbol(arena, next_unique_id, home, ident_ids);
+        let symbol = unique_glue_symbol(arena, next_unique_id, home, ident_ids, "getter");
 
         let argument = Symbol::new(home, ident_ids.gen_unique());
         let unboxed = Symbol::new(home, ident_ids.gen_unique());
@@ -10356,6 +10411,7 @@ fn unique_glue_symbol(
     next_unique_id: &mut GlueProcId,
     home: ModuleId,
     ident_ids: &mut IdentIds,
+    kind: &str,
 ) -> Symbol {
     let unique_id = *next_unique_id;
 
@@ -10368,7 +10424,7 @@ fn unique_glue_symbol(
     use std::fmt::Write;
     let mut string = bumpalo::collections::String::with_capacity_in(32, arena);
 
-    let _result = write!(&mut string, "roc__getter_{module_name}_{unique_id}");
+    let _result = write!(&mut string, "roc__{kind}_{module_name}_{unique_id}");
     debug_assert_eq!(_result, Ok(())); // This should never fail, but doesn't hurt to debug-check!
 
     let bump_string = string.into_bump_str();
@@ -10404,7 +10460,7 @@ where
             result: *field,
             niche: Niche::NONE,
         };
-        let symbol = unique_glue_symbol(arena, next_unique_id, home, ident_ids);
+        let symbol = unique_glue_symbol(arena, next_unique_id, home, ident_ids, "getter");
 
         let argument = Symbol::new(home, ident_ids.gen_unique());
         let unboxed = Symbol::new(home, ident_ids.gen_unique());
@@ -10,6 +10,7 @@
 #![allow(clippy::too_many_arguments)]
 
 pub mod code_gen_help;
+pub mod coverage;
 pub mod drop_specialization;
 pub mod inc_dec;
 pub mod ir;
@@ -38,6 +38,10 @@ pub enum Problem {
     UnusedDef(Symbol, Region),
     UnusedImport(Symbol, Region),
     UnusedModuleImport(ModuleId, Region),
+    /// A module exposes this, but no other module uses it
+    UnusedExpose(Symbol, Region),
+    /// Nothing the app or platform uses refers to this module, even indirectly
+    UnreachableModule(ModuleId, Region),
     ExposedButNotDefined(Symbol),
     UnknownGeneratesWith(Loc<Ident>),
     /// First symbol is the name of the closure with that argument
@@ -221,6 +225,8 @@ impl Problem {
             Problem::UnusedDef(_, _) => Warning,
             Problem::UnusedImport(_, _) => Warning,
             Problem::UnusedModuleImport(_, _) => Warning,
+            Problem::UnusedExpose(_, _) => Warning,
+            Problem::UnreachableModule(_, _) => Warning,
             Problem::ExposedButNotDefined(_) => RuntimeError,
             Problem::UnknownGeneratesWith(_) => RuntimeError,
             Problem::UnusedArgument(_, _, _, _) => Warning,
@@ -295,6 +301,8 @@ impl Problem {
             }
             | Problem::UnusedImport(_, region)
             | Problem::UnusedModuleImport(_, region)
+            | Problem::UnusedExpose(_, region)
+            | Problem::UnreachableModule(_, region)
             | Problem::UnknownGeneratesWith(Loc { region, .. })
             | Problem::UnusedArgument(_, _, _, region)
             | Problem::UnusedBranchDef(_, region)
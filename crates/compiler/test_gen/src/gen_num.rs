@@ -3856,7 +3856,7 @@ fn num_abs_diff_int() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn num_abs_diff_large_bits() {
     assert_evals_to!(r#"Num.absDiff 0u128 0u128"#, 0, u128);
     assert_evals_to!(r#"Num.absDiff 1u128 2u128"#, 1, u128);
@@ -3889,7 +3889,7 @@ fn num_abs_int_min_overflow() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 #[should_panic(expected = r#"Roc failed with message: "integer subtraction overflowed!"#)]
 fn num_abs_large_bits_min_overflow() {
     assert_evals_to!(r#"Num.absDiff Num.minI128 0"#, 0, i128);
@@ -3901,6 +3901,110 @@ fn num_abs_float_overflow() {
     assert_evals_to!("Num.absDiff Num.maxF64 Num.minF64", f64::INFINITY, f64);
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn div_trunc_large_bits() {
+    assert_evals_to!(
+        "Num.divTrunc -100_000_000_000_000_000_000_000i128 7",
+        -14285714285714285714285,
+        i128
+    );
+    assert_evals_to!(
+        "Num.divTrunc 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_fffeu128 3",
+        113427455640312821154458202477256070484,
+        u128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn rem_large_bits() {
+    assert_evals_to!("Num.rem -100_000_000_000_000_000_000_000i128 7", -5, i128);
+    assert_evals_to!(
+        "Num.rem 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_fffeu128 1_000_000_007",
+        279632275,
+        u128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn bitwise_large_bits() {
+    let x = "0x1234_5678_9abc_def0_0fed_cba9_8765_4321i128";
+    let y = "-0x0f0f_0f0f_0f0f_0f0f_0f0f_0f0f_0f0f_0f10i128";
+    assert_evals_to!(
+        &format!("Num.bitwiseAnd {x} {y}"),
+        21518509692870418461906244443291271200,
+        i128
+    );
+    assert_evals_to!(
+        &format!("Num.bitwiseOr {x} {y}"),
+        -17337262308482416742181286503676251151,
+        i128
+    );
+    assert_evals_to!(
+        &format!("Num.bitwiseXor {x} {y}"),
+        -38855772001352835204087530946967522351,
+        i128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn dec_compare() {
+    assert_evals_to!("1.5dec > 0.5dec", true, bool);
+    assert_evals_to!("1.5dec >= 1.5dec", true, bool);
+    assert_evals_to!("1.5dec < -0.5dec", false, bool);
+    assert_evals_to!("-1.5dec <= -2.5dec", false, bool);
+    assert_evals_to!("Num.compare 1.5dec 2.5dec", RocOrder::Lt, RocOrder);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn dec_neg_and_abs() {
+    assert_evals_to!(
+        "Num.neg 1.5dec",
+        RocDec::from_str_to_i128_unsafe("-1.5"),
+        i128
+    );
+    assert_evals_to!(
+        "Num.abs -1.5dec",
+        RocDec::from_str_to_i128_unsafe("1.5"),
+        i128
+    );
+    assert_evals_to!(
+        "Num.abs 2.25dec",
+        RocDec::from_str_to_i128_unsafe("2.25"),
+        i128
+    );
+}
+
+// The LLVM backend does not implement checked float conversions yet
+#[test]
+#[cfg(feature = "gen-wasm")]
+fn to_f32_checked() {
+    assert_evals_to!("Result.withDefault (Num.toF32Checked 1.5f64) 23", 1.5, f32);
+    assert_evals_to!(
+        "Result.withDefault (Num.toF32Checked Num.maxF64) 23",
+        23.0,
+        f32
+    );
+    assert_evals_to!("Result.withDefault (Num.toF32Checked 15i128) 23", 15.0, f32);
+    assert_evals_to!("Result.withDefault (Num.toF32Checked 1.5dec) 23", 1.5, f32);
+}
+
+#[test]
+#[cfg(feature = "gen-wasm")]
+fn to_f64_checked() {
+    assert_evals_to!("Result.withDefault (Num.toF64Checked 1.5f32) 23", 1.5, f64);
+    assert_evals_to!("Result.withDefault (Num.toF64Checked 15u128) 23", 15.0, f64);
+    assert_evals_to!(
+        "Result.withDefault (Num.toF64Checked -1.5dec) 23",
+        -1.5,
+        f64
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn bool_in_switch() {
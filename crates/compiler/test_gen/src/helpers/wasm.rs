@@ -113,6 +113,8 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         mut interns,
         exposed_to_host,
         mut layout_interner,
+        sources,
+        expectations,
         ..
     } = loaded;
 
@@ -124,11 +126,23 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         .copied()
         .collect::<MutSet<_>>();
 
+    let mut source_positions = roc_gen_wasm::SourcePositions::default();
+    for (module_id, (path, src)) in sources.iter() {
+        source_positions.insert_module(*module_id, path, src);
+    }
+    for (module_id, expectations) in expectations {
+        for (symbol, dbg) in expectations.dbgs.iter() {
+            source_positions.insert_dbg(*symbol, dbg.region);
+        }
+        source_positions.insert_types(module_id, expectations.subs);
+    }
+
     let env = roc_gen_wasm::Env {
         arena,
         module_id,
         exposed_to_host,
         stack_bytes: roc_gen_wasm::Env::DEFAULT_STACK_BYTES,
+        source_positions,
     };
 
     let host_module = roc_gen_wasm::parse_host(env.arena, host_bytes).unwrap_or_else(|e| {
@@ -188,6 +202,17 @@ where
 
 struct TestDispatcher<'a> {
     wasi: WasiDispatcher<'a>,
+    /// Messages from `dbg` and failed `expect`s, in the order the host received them
+    host_messages: Vec<String>,
+}
+
+impl<'a> TestDispatcher<'a> {
+    fn new() -> Self {
+        TestDispatcher {
+            wasi: wasi::WasiDispatcher::default(),
+            host_messages: Vec::new(),
+        }
+    }
 }
 
 impl<'a> ImportDispatcher for TestDispatcher<'a> {
@@ -210,6 +235,15 @@ impl<'a> ImportDispatcher for TestDispatcher<'a> {
                 tag => format!(r#"Got an invald panic tag: "{}""#, tag),
             };
             panic!("{}", msg)
+        } else if module_name == "env" && function_name == "send_dbg_to_rust" {
+            let loc = RocStr::decode(memory, arguments[0].expect_i32().unwrap() as _);
+            let msg = RocStr::decode(memory, arguments[1].expect_i32().unwrap() as _);
+            self.host_messages.push(format!("[{}] {}", loc, msg));
+            None
+        } else if module_name == "env" && function_name == "send_expect_failed_to_rust" {
+            let loc = RocStr::decode(memory, arguments[0].expect_i32().unwrap() as _);
+            self.host_messages.push(format!("expect failed at {}", loc));
+            None
         } else {
             panic!(
                 "TestDispatcher does not implement {}.{}",
@@ -241,9 +275,7 @@ pub(crate) fn run_wasm_test_module<'a, T>(
 where
     T: FromWasm32Memory + Wasm32Result,
 {
-    let dispatcher = TestDispatcher {
-        wasi: wasi::WasiDispatcher::default(),
-    };
+    let dispatcher = TestDispatcher::new();
     let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
     let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
     let opt_value = inst.call_export(test_wrapper_name, [])?;
@@ -270,9 +302,7 @@ where
     let module = WasmModule::preload(&arena, &wasm_bytes, require_relocatable)
         .map_err(|e| format!("{:?}", e))?;
 
-    let dispatcher = TestDispatcher {
-        wasi: wasi::WasiDispatcher::default(),
-    };
+    let dispatcher = TestDispatcher::new();
     let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
     let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
 
@@ -322,6 +352,33 @@ where
     Ok(refcounts)
 }
 
+/// Run a test and return the messages the host received from `dbg` and failed `expect`s
+#[allow(dead_code)]
+pub fn assert_host_messages_help<T>(
+    src: &str,
+    phantom: PhantomData<T>,
+) -> Result<Vec<String>, String>
+where
+    T: FromWasm32Memory + Wasm32Result,
+{
+    let arena = bumpalo::Bump::new();
+
+    let wasm_bytes = crate::helpers::wasm::compile_to_wasm_bytes(&arena, src, phantom);
+
+    let require_relocatable = false;
+    let module = WasmModule::preload(&arena, &wasm_bytes, require_relocatable)
+        .map_err(|e| format!("{:?}", e))?;
+
+    let dispatcher = TestDispatcher::new();
+    let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
+    let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
+
+    // Run the test, ignoring the result
+    inst.call_export(TEST_WRAPPER_NAME, [])?;
+
+    Ok(std::mem::take(&mut inst.import_dispatcher.host_messages))
+}
+
 fn read_i32(memory: &[u8], addr: i32) -> i32 {
     let index = addr as usize;
     let mut bytes = [0; 4];
@@ -410,8 +467,24 @@ macro_rules! assert_refcounts {
     }};
 }
 
+#[allow(unused_macros)]
+macro_rules! assert_host_messages {
+    ($src: expr, $ty: ty, $expected_messages: expr) => {{
+        let phantom = std::marker::PhantomData;
+        match $crate::helpers::wasm::assert_host_messages_help::<$ty>($src, phantom) {
+            Err(msg) => panic!("{}", msg),
+            Ok(actual_messages) => {
+                assert_eq!(actual_messages, $expected_messages)
+            }
+        }
+    }};
+}
+
 #[allow(unused_imports)]
 pub(crate) use assert_evals_to;
 
+#[allow(unused_imports)]
+pub(crate) use assert_host_messages;
+
 #[allow(unused_imports)]
 pub(crate) use assert_refcounts;
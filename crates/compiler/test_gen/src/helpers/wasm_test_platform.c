@@ -134,6 +134,22 @@ void roc_panic(void* msg, unsigned int tag_id)
 
 //--------------------------
 
+extern void send_dbg_to_rust(void* loc, void* msg);
+
+void roc_dbg(void* loc, void* msg)
+{
+    send_dbg_to_rust(loc, msg);
+}
+
+extern void send_expect_failed_to_rust(void* loc);
+
+void roc_expect_failed(void* loc)
+{
+    send_expect_failed_to_rust(loc);
+}
+
+//--------------------------
+
 void *roc_memset(void *str, int c, size_t n)
 {
     return memset(str, c, n);
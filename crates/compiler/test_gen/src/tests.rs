@@ -21,6 +21,7 @@ pub mod gen_str;
 pub mod gen_tags;
 pub mod gen_tuples;
 mod helpers;
+pub mod wasm_expect;
 pub mod wasm_str;
 
 #[cfg(feature = "gen-wasm")]
@@ -0,0 +1,117 @@
+// The Wasm backend passes `dbg` and failed `expect` messages to the host,
+// which the test host records instead of printing.
+#![cfg(feature = "gen-wasm")]
+
+use crate::helpers::wasm::assert_host_messages;
+
+#[allow(unused_imports)]
+use indoc::indoc;
+
+#[test]
+fn dbg_int() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            x = 6
+            dbg x
+            x + 1
+            "#
+        ),
+        i64,
+        ["[Test.roc 5:9] 6"]
+    );
+}
+
+#[test]
+fn dbg_str_and_dec() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            name = "Roc"
+            dbg name
+            amount : Dec
+            amount = 1.5
+            dbg amount
+            Str.countUtf8Bytes name
+            "#
+        ),
+        usize,
+        ["[Test.roc 5:9] \"Roc\"", "[Test.roc 8:9] 1.5"]
+    );
+}
+
+#[test]
+fn dbg_record_and_list() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            user = { name: "Roc", age: 3u8, ok: Bool.true }
+            dbg user
+            pairs = [(1, 1.5f64), (2, 2.5f64)]
+            dbg pairs
+            List.len pairs
+            "#
+        ),
+        usize,
+        [
+            "[Test.roc 5:9] { age: 3, name: \"Roc\", ok: Bool.true }",
+            "[Test.roc 7:9] [(1, 1.5), (2, 2.5)]"
+        ]
+    );
+}
+
+#[test]
+fn dbg_tags() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            result : Result (List [Red, Green]) [Oops Str]
+            result = Ok [Green, Red]
+            dbg result
+            nested = Cons 1 (Cons 2 Nil)
+            dbg nested
+            1u8
+            "#
+        ),
+        u8,
+        [
+            "[Test.roc 6:9] Ok [Green, Red]",
+            "[Test.roc 8:9] Cons 1 (Cons 2 Nil)"
+        ]
+    );
+}
+
+#[test]
+fn expect_passes() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            x = 6
+            expect x == 6
+            x + 1
+            "#
+        ),
+        i64,
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn expect_fails() {
+    assert_host_messages!(
+        indoc!(
+            r#"
+            x = 6
+            expect x == 5
+            expect x == 6
+            expect x > 10
+            x + 1
+            "#
+        ),
+        i64,
+        [
+            "expect failed at Test.roc 5:12",
+            "expect failed at Test.roc 7:12"
+        ]
+    );
+}
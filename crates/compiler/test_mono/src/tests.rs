@@ -15,11 +15,11 @@ const EXPANDED_STACK_SIZE: usize = 8 * 1024 * 1024;
 
 use bumpalo::Bump;
 use roc_collections::all::MutMap;
-use roc_load::ExecutionMode;
 use roc_load::FunctionKind;
 use roc_load::LoadConfig;
 use roc_load::LoadMonomorphizedError;
 use roc_load::Threading;
+use roc_load::{ExecutionMode, TestOptions};
 use roc_module::symbol::Interns;
 use roc_module::symbol::Symbol;
 use roc_mono::ir::Proc;
@@ -83,7 +83,11 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
 
     let exec_mode = match mode {
         "exec" => ExecutionMode::Executable,
-        "test" => ExecutionMode::Test,
+        "test" => ExecutionMode::Test(TestOptions::default()),
+        "coverage" => ExecutionMode::Test(TestOptions {
+            coverage: true,
+            ..TestOptions::default()
+        }),
         _ => panic!("Invalid test_mono exec mode {mode}"),
     };
 
@@ -2239,6 +2243,23 @@ fn issue_4749() {
     )
 }
 
+#[mono_test(mode = "coverage")]
+fn coverage_counts_defs_and_when_branches() {
+    indoc!(
+        r###"
+        interface Test exposes [] imports []
+
+        classify : U8 -> Str
+        classify = \n ->
+            when n is
+                0 -> "zero"
+                _ -> "other"
+
+        expect classify 0 == "zero"
+        "###
+    )
+}
+
 #[mono_test(mode = "test")]
 fn lambda_set_with_imported_toplevels_issue_4733() {
     indoc!(
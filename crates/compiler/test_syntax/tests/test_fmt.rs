@@ -6003,6 +6003,92 @@ mod test_fmt {
         );
     }
 
+    fn format_range(src: &str, range: std::ops::Range<usize>) -> String {
+        let arena = Bump::new();
+        let edits = roc_fmt::range::format_range(&arena, src, range).unwrap();
+        let mut output = src.to_string();
+
+        // Apply them back to front, so the earlier edits' ranges stay valid
+        for edit in edits.iter().rev() {
+            output.replace_range(edit.range.clone(), &edit.new_text);
+        }
+
+        output
+    }
+
+    const RANGE_SRC: &str = indoc!(
+        r#"
+        interface Foo   exposes [a, b] imports []
+
+        # a comment   which stays as it is
+        a =   1
+
+
+        b =
+          x =  2
+          x
+        "#
+    );
+
+    #[test]
+    fn format_range_only_formats_overlapping_defs() {
+        let start = RANGE_SRC.find("b =").unwrap();
+
+        assert_multiline_str_eq!(
+            format_range(RANGE_SRC, start..start + 1).as_str(),
+            indoc!(
+                r#"
+                interface Foo   exposes [a, b] imports []
+
+                # a comment   which stays as it is
+                a =   1
+
+
+                b =
+                    x = 2
+                    x
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn format_range_at_cursor() {
+        let cursor = RANGE_SRC.find("1").unwrap();
+
+        assert_multiline_str_eq!(
+            format_range(RANGE_SRC, cursor..cursor).as_str(),
+            RANGE_SRC.replace("a =   1", "a = 1").as_str()
+        );
+    }
+
+    #[test]
+    fn format_range_edits_are_minimal() {
+        let arena = Bump::new();
+        let start = RANGE_SRC.find("a =").unwrap();
+        let edits = roc_fmt::range::format_range(&arena, RANGE_SRC, start..start + 1).unwrap();
+
+        // Only the extra spaces in `a =   1` get removed
+        assert_eq!(
+            edits,
+            [roc_fmt::range::TextEdit {
+                range: start + 4..start + 6,
+                new_text: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn format_range_outside_defs() {
+        let arena = Bump::new();
+        let comment = RANGE_SRC.find("# a comment").unwrap();
+
+        assert_eq!(
+            roc_fmt::range::format_range(&arena, RANGE_SRC, 0..comment + 5).unwrap(),
+            []
+        );
+    }
+
     // this is a parse error atm
     //    #[test]
     //    fn multiline_apply() {
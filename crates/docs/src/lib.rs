@@ -4,48 +4,146 @@ extern crate pulldown_cmark;
 extern crate roc_load;
 use bumpalo::Bump;
 use roc_can::scope::Scope;
-use roc_collections::VecSet;
-use roc_load::docs::{DocEntry, TypeAnnotation};
+use roc_collections::{MutMap, VecSet};
+use roc_load::docs::{comments_or_new_lines_to_docs, DocEntry, TypeAnnotation};
 use roc_load::docs::{ModuleDocumentation, RecordField};
 use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
-use roc_module::symbol::{Interns, Symbol};
+use roc_module::ident::ModuleName;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_packaging::cache::{self, RocCacheDir};
+use roc_parse::ast::{ExtractSpaces, Header, Module};
+use roc_parse::header::PackageEntry;
 use roc_parse::ident::{parse_ident, Accessor, Ident};
 use roc_parse::state::State;
 use roc_region::all::Region;
+use roc_reporting::report::{to_https_problem_report_string, RenderTarget};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-const BUILD_DIR: &str = "./generated-docs";
+pub const DEFAULT_OUTPUT_DIR: &str = "./generated-docs";
 
 const LINK_SVG: &str = include_str!("./static/link.svg");
 
-pub fn generate_docs_html(root_file: PathBuf) {
-    let build_dir = Path::new(BUILD_DIR);
-    let loaded_module = load_module_for_docs(root_file);
+/// The name, version, and description of a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: Option<String>,
+    pub description: Option<String>,
+}
+
+impl PackageInfo {
+    /// Read these from a `package` or `platform` header. The name can end in a version
+    /// (e.g. `package "json@0.4.0"`), and the description is the `##` doc comment right
+    /// before the header.
+    pub fn from_header(module: &Module) -> Self {
+        let name = match &module.header {
+            Header::Package(header) => header.name.value.to_str(),
+            Header::Platform(header) => header.name.value.to_str(),
+            _ => "Documentation",
+        };
 
-    // TODO get these from the platform's source file rather than hardcoding them!
-    // github.com/roc-lang/roc/issues/5712
-    let package_name = "Documentation".to_string();
+        let (name, version) = match name.rsplit_once('@') {
+            Some((name, version)) if !name.is_empty() && !version.is_empty() => {
+                (name, Some(version.to_string()))
+            }
+            _ => (name, None),
+        };
 
-    // Clear out the generated-docs dir (we'll create a fresh one at the end)
-    if build_dir.exists() {
-        fs::remove_dir_all(build_dir)
-            .expect("TODO gracefully handle being unable to delete build dir");
+        PackageInfo {
+            name: name.to_string(),
+            version,
+            description: comments_or_new_lines_to_docs(module.comments),
+        }
     }
+}
+
+/// A package we're generating docs for
+struct DocsPackage {
+    root_file: PathBuf,
+    info: PackageInfo,
+    /// The entries in its header's `packages` block, as (shorthand, root module) pairs
+    dependencies: Vec<(String, PathBuf)>,
+    /// Where its docs go, relative to the output dir (e.g. "json/0.4.0/").
+    /// This is empty unless we're generating docs for more than one package.
+    dir: String,
+}
+
+struct Assets<S: AsRef<str>> {
+    search_js: S,
+    styles_css: S,
+    favicon_svg: S,
+    raw_template_html: S,
+}
+
+impl<S: AsRef<str>> Assets<S> {
+    fn template_html(&self) -> &str {
+        self.raw_template_html.as_ref()
+    }
+
+    // Write CSS, JS, and favicon
+    // (The HTML requires more work!)
+    fn write_to(&self, dir: &Path) {
+        for (file, contents) in [
+            ("search.js", &self.search_js),
+            ("styles.css", &self.styles_css),
+            ("favicon.svg", &self.favicon_svg),
+        ] {
+            let path = dir.join(file);
+            fs::write(&path, contents.as_ref()).unwrap_or_else(|error| {
+                panic!(
+                    "Attempted to write {} but failed with this error: {}",
+                    path.display(),
+                    error
+                )
+            })
+        }
+    }
+}
+
+/// Generate docs for the package whose main module is `root_file` into `build_dir`.
+///
+/// With `include_dependencies`, this also generates docs for all the packages it depends on
+/// (directly or not), each in its own `<name>/<version>/` subdirectory, and links between them.
+pub fn generate_docs_html(root_file: PathBuf, build_dir: &Path, include_dependencies: bool) {
+    let roc_cache_dir = cache::roc_cache_dir();
+    let roc_cache_dir = RocCacheDir::Persistent(roc_cache_dir.as_path());
+    let mut packages = vec![read_package(root_file, roc_cache_dir)];
+
+    if include_dependencies {
+        let mut index = 0;
+
+        while index < packages.len() {
+            for (_, dep_file) in packages[index].dependencies.clone() {
+                if !packages
+                    .iter()
+                    .any(|package| same_file(&package.root_file, &dep_file))
+                {
+                    packages.push(read_package(dep_file, roc_cache_dir));
+                }
+            }
+
+            index += 1;
+        }
+
+        for index in 0..packages.len() {
+            let mut dir = package_dir(&packages[index].info);
+
+            // Two packages could have the same name and version, but they can't share a dir
+            if packages[..index].iter().any(|package| package.dir == dir) {
+                dir.insert_str(dir.len() - 1, &format!("-{index}"));
+            }
+
+            packages[index].dir = dir;
+        }
+    }
+
     fs::create_dir_all(build_dir).expect("TODO gracefully handle being unable to create build dir");
 
     // Copy over the assets
     // For debug builds, read assets from fs to speed up build
     // Otherwise, include as string literal
 
-    struct Assets<S: AsRef<str>> {
-        search_js: S,
-        styles_css: S,
-        favicon_svg: S,
-        raw_template_html: S,
-    }
-
     #[cfg(not(debug_assertions))]
     let assets = {
         let search_js = include_str!("./static/search.js");
@@ -81,26 +179,99 @@ pub fn generate_docs_html(root_file: PathBuf) {
         }
     };
 
-    // Write CSS, JS, and favicon
-    // (The HTML requires more work!)
-    for (file, contents) in [
-        ("search.js", assets.search_js),
-        ("styles.css", assets.styles_css),
-        ("favicon.svg", assets.favicon_svg),
-    ] {
-        let dir = build_dir.join(file);
-        fs::write(&dir, contents).unwrap_or_else(|error| {
-            panic!(
-                "Attempted to write {} but failed with this error: {}",
-                dir.display(),
-                error
+    for package in packages.iter() {
+        generate_package_docs(package, &packages, build_dir, &assets);
+    }
+
+    // Write an index.html listing all the packages (/index.html)
+    if include_dependencies {
+        assets.write_to(build_dir);
+
+        let rendered_index = assets
+            .template_html()
+            .replace("<!-- search.js -->", "search.js")
+            .replace("<!-- styles.css -->", "styles.css")
+            .replace("<!-- favicon.svg -->", "/favicon.svg")
+            .replace("<!-- Prefetch links -->", "")
+            .replace("<!-- base -->", &base_url())
+            .replace("<!-- Module links -->", "")
+            .replace("<!-- Page title -->", "<title>Packages</title>")
+            .replace("<!-- Meta description -->", "")
+            .replace(
+                "<!-- Package Name -->",
+                render_name_link("Packages", None, &base_url()).as_str(),
             )
-        })
+            .replace(
+                "<!-- Module Docs -->",
+                render_packages_index(packages.iter()).as_str(),
+            );
+
+        fs::write(build_dir.join("index.html"), rendered_index).unwrap_or_else(|error| {
+            panic!("Attempted to write index.html but failed with this error: {error}")
+        });
     }
 
+    println!("🎉 Docs generated in {}", build_dir.display());
+}
+
+fn generate_package_docs<S: AsRef<str>>(
+    package: &DocsPackage,
+    packages: &[DocsPackage],
+    build_dir: &Path,
+    assets: &Assets<S>,
+) {
+    let package_dir = build_dir.join(&package.dir);
+    let loaded_module = load_module_for_docs(package.root_file.clone());
+    let info = &package.info;
+
+    let package_url = format!("{}{}", base_url(), package.dir);
+
+    // The other packages we're generating docs for, which this one depends on directly
+    let dependencies: Vec<(&String, &DocsPackage)> = package
+        .dependencies
+        .iter()
+        .filter_map(|(shorthand, dep_file)| {
+            packages
+                .iter()
+                .find(|other| same_file(&other.root_file, dep_file))
+                .map(|other| (shorthand, other))
+        })
+        .collect();
+
+    // Link to modules from those packages in their docs, rather than in this package's
+    let dependency_urls: MutMap<ModuleId, String> = loaded_module
+        .module_graph
+        .shorthands
+        .iter()
+        .filter_map(|(module_id, module_shorthand)| {
+            dependencies
+                .iter()
+                .find(|(shorthand, _)| *shorthand == module_shorthand)
+                .map(|(_, dep)| (*module_id, format!("{}{}", base_url(), dep.dir)))
+        })
+        .collect();
+
+    let links = Links {
+        base_url: &package_url,
+        dependency_urls: &dependency_urls,
+    };
+
+    fs::create_dir_all(&package_dir)
+        .expect("TODO gracefully handle being unable to create the package's dir");
+
+    assets.write_to(&package_dir);
+
+    let meta_description = match &info.description {
+        Some(description) => format!(
+            r#"<meta name="description" content="{}">"#,
+            escape_html(first_paragraph(description).as_str())
+        ),
+        None => String::new(),
+    };
+
     // Insert asset urls & sidebar links
     let template_html = assets
-        .raw_template_html
+        .template_html()
         .replace("<!-- search.js -->", "search.js")
         .replace("<!-- styles.css -->", "styles.css")
         .replace("<!-- favicon.svg -->", "/favicon.svg")
@@ -118,10 +289,15 @@ pub fn generate_docs_html(root_file: PathBuf) {
                 .join("\n    ")
                 .as_str(),
         )
-        .replace("<!-- base -->", &base_url())
+        .replace("<!-- base -->", &package_url)
+        .replace("<!-- Meta description -->", &meta_description)
         .replace(
             "<!-- Module links -->",
             render_sidebar(loaded_module.docs_by_module.values()).as_str(),
+        )
+        .replace(
+            "<!-- Package Name -->",
+            render_name_link(&info.name, info.version.as_deref(), &package_url).as_str(),
         );
 
     let all_exposed_symbols = {
@@ -140,18 +316,14 @@ pub fn generate_docs_html(root_file: PathBuf) {
         let rendered_package = template_html
             .replace(
                 "<!-- Page title -->",
-                page_title(package_name.as_str(), "").as_str(),
-            )
-            .replace(
-                "<!-- Package Name -->",
-                render_name_link(package_name.as_str()).as_str(),
+                page_title(info.name.as_str(), "").as_str(),
             )
             .replace(
                 "<!-- Module Docs -->",
-                render_package_index(&loaded_module).as_str(),
+                render_package_index(&loaded_module, info, &dependencies).as_str(),
             );
 
-        fs::write(build_dir.join("index.html"), rendered_package).unwrap_or_else(|error| {
+        fs::write(package_dir.join("index.html"), rendered_package).unwrap_or_else(|error| {
             panic!("Attempted to write index.html but failed with this error: {error}")
         });
     }
@@ -159,7 +331,7 @@ pub fn generate_docs_html(root_file: PathBuf) {
     // Write each package module's index.html file
     for module_docs in loaded_module.docs_by_module.values() {
         let module_name = module_docs.name.as_str();
-        let module_dir = build_dir.join(module_name.replace('.', "/").as_str());
+        let module_dir = package_dir.join(module_name.replace('.', "/").as_str());
 
         fs::create_dir_all(&module_dir)
             .expect("TODO gracefully handle not being able to create the module dir");
@@ -167,30 +339,170 @@ pub fn generate_docs_html(root_file: PathBuf) {
         let rendered_module = template_html
             .replace(
                 "<!-- Page title -->",
-                page_title(package_name.as_str(), module_name).as_str(),
-            )
-            .replace(
-                "<!-- Package Name -->",
-                render_name_link(package_name.as_str()).as_str(),
+                page_title(info.name.as_str(), module_name).as_str(),
             )
             .replace(
                 "<!-- Module Docs -->",
-                render_module_documentation(module_docs, &loaded_module, &all_exposed_symbols)
-                    .as_str(),
+                render_module_documentation(
+                    module_docs,
+                    &loaded_module,
+                    &all_exposed_symbols,
+                    &links,
+                )
+                .as_str(),
             );
 
         fs::write(module_dir.join("index.html"), rendered_module)
             .expect("TODO gracefully handle failing to write index.html inside module's dir");
     }
+}
 
-    println!("🎉 Docs generated in {}", build_dir.display());
+/// Where to link to from one package's docs
+struct Links<'a> {
+    /// e.g. "/" or "/json/0.4.0/"
+    base_url: &'a str,
+    /// The base urls of the docs for modules from other packages
+    dependency_urls: &'a MutMap<ModuleId, String>,
+}
+
+/// Read the package's name, version, description, and dependencies from its header. (If
+/// the header doesn't parse, loading the package will report that later.)
+fn read_package(root_file: PathBuf, roc_cache_dir: RocCacheDir<'_>) -> DocsPackage {
+    let arena = Bump::new();
+    let src = fs::read(&root_file).unwrap_or_default();
+    let parsed = roc_parse::module::parse_header(&arena, State::new(&src));
+
+    let (info, entries) = match &parsed {
+        Ok((module, _)) => {
+            let entries: &[_] = match &module.header {
+                Header::Package(header) => header.packages.item.items,
+                Header::Platform(header) => header.packages.item.items,
+                _ => &[],
+            };
+
+            (PackageInfo::from_header(module), entries)
+        }
+        Err(_) => (
+            PackageInfo {
+                name: "Documentation".to_string(),
+                version: None,
+                description: None,
+            },
+            &[] as &[_],
+        ),
+    };
+
+    let root_dir = root_file.parent().unwrap_or_else(|| Path::new("."));
+    let dependencies = entries
+        .iter()
+        .map(|entry| {
+            let PackageEntry {
+                shorthand,
+                package_name,
+                ..
+            } = entry.value.extract_spaces().item;
+
+            (
+                shorthand.to_string(),
+                package_root_file(root_dir, package_name.value.to_str(), roc_cache_dir),
+            )
+        })
+        .collect();
+
+    DocsPackage {
+        root_file,
+        info,
+        dependencies,
+        dir: String::new(),
+    }
+}
+
+/// The main module of a package from a `packages` block, downloading it first if necessary
+fn package_root_file(root_dir: &Path, src: &str, roc_cache_dir: RocCacheDir<'_>) -> PathBuf {
+    if src.starts_with("https://") {
+        match cache::install_package(roc_cache_dir, src) {
+            // You can optionally specify the root module using the URL fragment,
+            // e.g. #foo.roc
+            // (defaults to main.roc)
+            Ok((package_dir, opt_root_module)) => {
+                package_dir.join(opt_root_module.unwrap_or("main.roc"))
+            }
+            Err(problem) => {
+                let report =
+                    to_https_problem_report_string(src, problem, RenderTarget::ColorTerminal);
+
+                eprintln!("{report}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        root_dir.join(src)
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// e.g. "json/0.4.0/", or just "json/" if it has no version
+fn package_dir(info: &PackageInfo) -> String {
+    fn sanitize(segment: &str) -> String {
+        segment
+            .chars()
+            .map(|ch| match ch {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => ch,
+                _ => '-',
+            })
+            .collect::<String>()
+            .trim_start_matches('.')
+            .to_string()
+    }
+
+    let mut dir = sanitize(&info.name);
+
+    if dir.is_empty() {
+        dir.push_str("package");
+    }
+
+    dir.push('/');
+
+    if let Some(version) = &info.version {
+        dir.push_str(&sanitize(version));
+        dir.push('/');
+    }
+
+    dir
+}
+
+fn first_paragraph(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 fn page_title(package_name: &str, module_name: &str) -> String {
     format!("<title>{module_name} - {package_name}</title>")
 }
 
-fn render_package_index(root_module: &LoadedModule) -> String {
+fn render_package_index(
+    root_module: &LoadedModule,
+    info: &PackageInfo,
+    dependencies: &[(&String, &DocsPackage)],
+) -> String {
     // The list items containing module links
     let mut module_list_buf = String::new();
 
@@ -211,6 +523,25 @@ fn render_package_index(root_module: &LoadedModule) -> String {
     // The HTML for the index page
     let mut index_buf = String::new();
 
+    if let Some(description) = &info.description {
+        let mut description_buf = String::new();
+
+        // Like in the rest of the docs, render raw HTML as text
+        let events = pulldown_cmark::Parser::new(description).map(|event| match event {
+            pulldown_cmark::Event::Html(html) => pulldown_cmark::Event::Text(html),
+            event => event,
+        });
+
+        pulldown_cmark::html::push_html(&mut description_buf, events);
+
+        push_html(
+            &mut index_buf,
+            "div",
+            vec![("class", "package-description")],
+            description_buf.as_str(),
+        );
+    }
+
     push_html(&mut index_buf, "h2", vec![], "Exposed Modules");
     push_html(
         &mut index_buf,
@@ -219,6 +550,77 @@ fn render_package_index(root_module: &LoadedModule) -> String {
         module_list_buf.as_str(),
     );
 
+    if !dependencies.is_empty() {
+        let mut dependency_list_buf = String::new();
+
+        for (shorthand, dep) in dependencies {
+            let href = format!("{}{}", base_url(), dep.dir);
+            let mut link_buf = String::new();
+
+            push_html(
+                &mut link_buf,
+                "a",
+                vec![("href", href.as_str())],
+                escape_html(&dep.info.name),
+            );
+
+            push_html(
+                &mut dependency_list_buf,
+                "li",
+                vec![],
+                format!("{shorthand}: {link_buf}"),
+            );
+        }
+
+        push_html(&mut index_buf, "h2", vec![], "Dependencies");
+        push_html(
+            &mut index_buf,
+            "ul",
+            vec![("class", "index-module-links")],
+            dependency_list_buf.as_str(),
+        );
+    }
+
+    index_buf
+}
+
+fn render_packages_index<'a>(packages: impl Iterator<Item = &'a DocsPackage>) -> String {
+    let mut package_list_buf = String::new();
+
+    for package in packages {
+        let href = format!("{}{}", base_url(), package.dir);
+        let mut item_buf = String::new();
+
+        push_html(
+            &mut item_buf,
+            "a",
+            vec![("href", href.as_str())],
+            escape_html(&package.info.name),
+        );
+
+        if let Some(version) = &package.info.version {
+            item_buf.push(' ');
+            push_html(
+                &mut item_buf,
+                "span",
+                vec![("class", "pkg-version")],
+                escape_html(version),
+            );
+        }
+
+        push_html(&mut package_list_buf, "li", vec![], item_buf.as_str());
+    }
+
+    let mut index_buf = String::new();
+
+    push_html(&mut index_buf, "h2", vec![], "Packages");
+    push_html(
+        &mut index_buf,
+        "ul",
+        vec![("class", "index-module-links")],
+        package_list_buf.as_str(),
+    );
+
     index_buf
 }
 
@@ -226,6 +628,7 @@ fn render_module_documentation(
     module: &ModuleDocumentation,
     root_module: &LoadedModule,
     all_exposed_symbols: &VecSet<Symbol>,
+    links: &Links,
 ) -> String {
     let mut buf = String::new();
     let module_name = module.name.as_str();
@@ -275,6 +678,7 @@ fn render_module_documentation(
                         markdown_to_html(
                             &mut buf,
                             all_exposed_symbols,
+                            links,
                             &module.scope,
                             docs,
                             root_module,
@@ -288,6 +692,7 @@ fn render_module_documentation(
                 markdown_to_html(
                     &mut buf,
                     all_exposed_symbols,
+                    links,
                     &module.scope,
                     docs,
                     root_module,
@@ -354,8 +759,7 @@ fn base_url() -> String {
     }
 }
 
-// TODO render version as well
-fn render_name_link(name: &str) -> String {
+fn render_name_link(name: &str, version: Option<&str>, base_url: &str) -> String {
     let mut buf = String::new();
 
     push_html(&mut buf, "h1", vec![("class", "pkg-full-name")], {
@@ -365,10 +769,19 @@ fn render_name_link(name: &str) -> String {
         push_html(
             &mut link_buf,
             "a",
-            vec![("href", base_url().as_str())],
-            name,
+            vec![("href", base_url)],
+            escape_html(name),
         );
 
+        if let Some(version) = version {
+            push_html(
+                &mut link_buf,
+                "span",
+                vec![("class", "pkg-version")],
+                escape_html(version),
+            );
+        }
+
         link_buf
     });
 
@@ -757,11 +1170,14 @@ struct DocUrl {
 
 fn doc_url<'a>(
     all_exposed_symbols: &VecSet<Symbol>,
+    links: &Links,
     scope: &Scope,
     interns: &'a Interns,
     mut module_name: &'a str,
     ident: &str,
 ) -> DocUrl {
+    let mut base_url = links.base_url;
+
     if module_name.is_empty() {
         // This is an unqualified lookup, so look for the ident
         // in scope!
@@ -772,6 +1188,10 @@ fn doc_url<'a>(
                 // module - for example, if this is in scope from an
                 // unqualified import.
                 module_name = symbol.module_string(interns);
+
+                if let Some(dependency_url) = links.dependency_urls.get(&symbol.module_id()) {
+                    base_url = dependency_url;
+                }
             }
             Err(_) => {
                 // TODO return Err here
@@ -781,7 +1201,7 @@ fn doc_url<'a>(
             }
         }
     } else {
-        match interns.module_ids.get_id(&module_name.into()) {
+        match lookup_module(interns, module_name, ident) {
             Some(module_id) => {
                 let symbol = interns.symbol(module_id, ident.into());
 
@@ -793,6 +1213,11 @@ fn doc_url<'a>(
                     // URL that will 404.
                     module_name = symbol.module_string(interns);
                 }
+                // This module comes from one of the other packages we're
+                // generating docs for, so link to its docs there.
+                else if let Some(dependency_url) = links.dependency_urls.get(&module_id) {
+                    base_url = dependency_url;
+                }
                 // Note: You can do qualified lookups on your own module, e.g.
                 // if I'm in the Foo module, I can do a `Foo.bar` lookup.
                 else if !all_exposed_symbols.contains(&symbol) {
@@ -803,9 +1228,6 @@ fn doc_url<'a>(
 
                 // This is a valid symbol for this dependency,
                 // so proceed using the current module's name.
-                //
-                // TODO: In the future, this is where we'll
-                // incorporate the package name into the link.
             }
             None => {
                 // TODO return Err here
@@ -814,7 +1236,7 @@ fn doc_url<'a>(
         }
     }
 
-    let mut url = base_url();
+    let mut url = base_url.to_string();
 
     // Example:
     //
@@ -829,9 +1251,29 @@ fn doc_url<'a>(
     }
 }
 
+/// The module with this name which defines `ident`. The same module name can be interned more
+/// than once when modules come from other packages, and only one of those will have idents.
+fn lookup_module(interns: &Interns, module_name: &str, ident: &str) -> Option<ModuleId> {
+    let module_name: ModuleName = module_name.into();
+
+    interns
+        .all_ident_ids
+        .keys()
+        .copied()
+        .find(|module_id| {
+            interns.module_ids.get_name(*module_id) == Some(&module_name)
+                && interns
+                    .all_ident_ids
+                    .get(module_id)
+                    .is_some_and(|ident_ids| ident_ids.get_id(ident).is_some())
+        })
+        .or_else(|| interns.module_ids.get_id(&module_name))
+}
+
 fn markdown_to_html(
     buf: &mut String,
     all_exposed_symbols: &VecSet<Symbol>,
+    links: &Links,
     scope: &Scope,
     markdown: &str,
     loaded_module: &LoadedModule,
@@ -862,6 +1304,7 @@ fn markdown_to_html(
                             Some(Accessor::RecordField(symbol_name)) if iter.next().is_none() => {
                                 let DocUrl { url, title } = doc_url(
                                     all_exposed_symbols,
+                                    links,
                                     scope,
                                     &loaded_module.interns,
                                     module_name,
@@ -883,6 +1326,7 @@ fn markdown_to_html(
                         // be a type alias that's in scope, e.g. [I64]
                         let DocUrl { url, title } = doc_url(
                             all_exposed_symbols,
+                            links,
                             scope,
                             &loaded_module.interns,
                             "",
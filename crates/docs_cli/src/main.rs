@@ -1,15 +1,32 @@
 //! Provides a binary that is only used for static build servers.
-use clap::{value_parser, Arg, Command};
-use roc_docs::generate_docs_html;
+use clap::{value_parser, Arg, ArgAction, Command};
+use roc_docs::{generate_docs_html, DEFAULT_OUTPUT_DIR};
 use std::io;
 use std::path::PathBuf;
 
 pub const ROC_FILE: &str = "ROC_FILE";
+pub const FLAG_OUTPUT: &str = "output";
+pub const FLAG_WITH_DEPENDENCIES: &str = "with-dependencies";
 const DEFAULT_ROC_FILENAME: &str = "main.roc";
 
 fn main() -> io::Result<()> {
     let matches = Command::new("roc-docs")
         .about("Generate documentation for a Roc package")
+        .arg(
+            Arg::new(FLAG_OUTPUT)
+                .long(FLAG_OUTPUT)
+                .help("The directory to write the docs into")
+                .value_parser(value_parser!(PathBuf))
+                .default_value(DEFAULT_OUTPUT_DIR),
+        )
+        .arg(
+            Arg::new(FLAG_WITH_DEPENDENCIES)
+                .long(FLAG_WITH_DEPENDENCIES)
+                .help(
+                    "Also generate docs for the packages this package depends on, and link to them",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(ROC_FILE)
                 .help("The package's main .roc file")
@@ -20,7 +37,11 @@ fn main() -> io::Result<()> {
         .get_matches();
 
     // Populate roc_files
-    generate_docs_html(matches.get_one::<PathBuf>(ROC_FILE).unwrap().to_owned());
+    generate_docs_html(
+        matches.get_one::<PathBuf>(ROC_FILE).unwrap().to_owned(),
+        matches.get_one::<PathBuf>(FLAG_OUTPUT).unwrap(),
+        matches.get_flag(FLAG_WITH_DEPENDENCIES),
+    );
 
     Ok(())
 }
@@ -0,0 +1,1366 @@
+use crate::types::{
+    File, RocFn, RocNum, RocSingleTagPayload, RocStructFields, RocTagUnion, RocType, TypeId, Types,
+};
+use roc_collections::MutSet;
+use roc_target::Architecture;
+use std::fmt::Write;
+
+pub static HEADER: &[u8] = include_bytes!("../templates/header.h");
+pub static ROC_STD: &[u8] = include_bytes!("../templates/roc_std.h");
+const INDENT: &str = "    ";
+
+const RESERVED_KEYWORDS: &[&str] = &[
+    "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch",
+    "true", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+/// The declarations for one target, split up so that they can be output in an order C accepts.
+#[derive(Default)]
+struct Decls {
+    /// A `typedef` for every type, so that declarations can refer to types declared after them
+    forward: String,
+    /// Recursive tag unions are just a pointer, so they can be defined before anything else.
+    /// That way, their payloads can contain them.
+    recursive: String,
+    /// A prototype for every function, so that functions can call each other in any order
+    prototypes: String,
+    definitions: String,
+}
+
+impl Decls {
+    fn typedef(&mut self, kind: &str, name: &str) {
+        writeln!(self.forward, "typedef {kind} {name} {name};").unwrap();
+    }
+
+    fn function(&mut self, signature: String, body: &str) {
+        writeln!(self.prototypes, "static inline {signature};").unwrap();
+        write!(
+            self.definitions,
+            "\nstatic inline {signature} {{\n{body}}}\n"
+        )
+        .unwrap();
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Refcount {
+    Incref,
+    Decref,
+}
+
+pub fn emit(types: &[Types]) -> Vec<File> {
+    let mut buf = std::str::from_utf8(HEADER).unwrap().to_string();
+
+    // Sizes and alignments vary by target, so each target gets its own declarations,
+    // and targets whose declarations come out the same share them.
+    let mut decls_by_target: Vec<(String, Vec<Architecture>)> = Vec::new();
+
+    for types in types {
+        let decls = declarations(types);
+        let architecture = types.target().architecture;
+
+        match decls_by_target
+            .iter_mut()
+            .find(|(existing, _)| *existing == decls)
+        {
+            Some((_, architectures)) => architectures.push(architecture),
+            None => decls_by_target.push((decls, vec![architecture])),
+        }
+    }
+
+    match decls_by_target.as_slice() {
+        [] => {}
+        [(decls, _)] => buf.push_str(decls),
+        _ => {
+            for (index, (decls, architectures)) in decls_by_target.iter().enumerate() {
+                let directive = if index == 0 { "#if" } else { "#elif" };
+                let conditions: Vec<_> = architectures
+                    .iter()
+                    .map(|architecture| arch_condition(*architecture))
+                    .collect();
+
+                write!(buf, "\n{directive} {}\n", conditions.join(" || ")).unwrap();
+                buf.push_str(decls);
+            }
+
+            buf.push_str(
+                "\n#else\n#error \"roc glue did not generate declarations for this target\"\n#endif\n",
+            );
+        }
+    }
+
+    buf.push_str("\n#endif // ROC_APP_H\n");
+
+    vec![
+        File {
+            name: "roc_app.h".to_string(),
+            content: buf,
+        },
+        File {
+            name: "roc_std.h".to_string(),
+            content: std::str::from_utf8(ROC_STD).unwrap().to_string(),
+        },
+    ]
+}
+
+fn declarations(types: &Types) -> String {
+    let mut decls = Decls::default();
+    let mut added = MutSet::default();
+
+    for id in types.sorted_ids() {
+        add_type_and_contents(id, types, &mut added, &mut decls);
+    }
+
+    for (name, id) in types.entry_points() {
+        add_entry_point(name, *id, types, &mut decls);
+    }
+
+    let sections = [
+        decls.forward,
+        decls.recursive,
+        decls.prototypes,
+        decls.definitions,
+    ];
+    let mut buf = String::new();
+
+    for section in sections.iter().filter(|section| !section.is_empty()) {
+        // The other sections are a single block, but definitions each start with a blank line.
+        if !section.starts_with('\n') {
+            buf.push('\n');
+        }
+
+        buf.push_str(section);
+    }
+
+    buf
+}
+
+/// C needs a type to be defined before it can be stored by value in another type,
+/// so add the types a type contains before adding the type itself.
+fn add_type_and_contents(id: TypeId, types: &Types, added: &mut MutSet<TypeId>, decls: &mut Decls) {
+    if !added.insert(id) {
+        return;
+    }
+
+    for content_id in contents(id, types) {
+        add_type_and_contents(content_id, types, added, decls);
+    }
+
+    add_type(id, types, decls);
+}
+
+/// The types which need to be defined before this one, because it (or its refcounting code)
+/// uses them by value
+fn contents(id: TypeId, types: &Types) -> Vec<TypeId> {
+    match types.get_type(id) {
+        RocType::Struct { fields, .. } | RocType::TagUnionPayload { fields, .. } => match fields {
+            RocStructFields::HasNoClosure { fields } => fields.iter().map(|(_, id)| *id).collect(),
+            RocStructFields::HasClosure { .. } => Vec::new(),
+        },
+        RocType::TagUnion(tag_union) => match tag_union {
+            RocTagUnion::Enumeration { .. } => Vec::new(),
+            RocTagUnion::NonRecursive { tags, .. }
+            | RocTagUnion::Recursive { tags, .. }
+            | RocTagUnion::NullableWrapped { tags, .. } => {
+                tags.iter().filter_map(|(_, payload)| *payload).collect()
+            }
+            RocTagUnion::NullableUnwrapped {
+                non_null_payload, ..
+            } => vec![*non_null_payload],
+            RocTagUnion::NonNullableUnwrapped { payload, .. } => vec![*payload],
+            RocTagUnion::SingleTagStruct { payload, .. } => match payload {
+                RocSingleTagPayload::HasNoClosure { payload_fields } => payload_fields.clone(),
+                RocSingleTagPayload::HasClosure { .. } => Vec::new(),
+            },
+        },
+        RocType::RocResult(ok_id, err_id) | RocType::RocDict(ok_id, err_id) => {
+            vec![*ok_id, *err_id]
+        }
+        RocType::RocList(elem_id) | RocType::RocSet(elem_id) | RocType::RocBox(elem_id) => {
+            vec![*elem_id]
+        }
+        RocType::Function(roc_fn) => {
+            let mut contents = roc_fn.args.clone();
+
+            contents.push(roc_fn.lambda_set);
+            contents.push(roc_fn.ret);
+
+            contents
+        }
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::Unsized
+        | RocType::RecursivePointer(_) => Vec::new(),
+    }
+}
+
+fn add_type(id: TypeId, types: &Types, decls: &mut Decls) {
+    match types.get_type(id) {
+        RocType::Struct { name, fields } => {
+            add_struct(&escape_kw(name), id, fields, false, types, decls)
+        }
+        RocType::TagUnionPayload { name, fields } => {
+            add_struct(&escape_kw(name), id, fields, true, types, decls)
+        }
+        RocType::TagUnion(tag_union) => match tag_union {
+            RocTagUnion::Enumeration { name, tags, size } => {
+                add_enumeration(&escape_kw(name), tags, *size, decls)
+            }
+            RocTagUnion::NonRecursive {
+                name,
+                tags,
+                discriminant_offset,
+                discriminant_size,
+            } => {
+                // Empty tag unions can never come up at runtime,
+                // and so don't need declared types.
+                if !tags.is_empty() {
+                    add_non_recursive_tag_union(
+                        &escape_kw(name),
+                        id,
+                        tags,
+                        *discriminant_size,
+                        *discriminant_offset,
+                        types,
+                        decls,
+                    )
+                }
+            }
+            RocTagUnion::Recursive {
+                name,
+                tags,
+                discriminant_offset,
+                discriminant_size,
+            } => {
+                if !tags.is_empty() {
+                    add_recursive_tag_union(
+                        &escape_kw(name),
+                        id,
+                        tags,
+                        None,
+                        *discriminant_size,
+                        *discriminant_offset,
+                        types,
+                        decls,
+                    )
+                }
+            }
+            RocTagUnion::NullableWrapped {
+                name,
+                index_of_null_tag,
+                tags,
+                discriminant_size,
+                discriminant_offset,
+            } => add_recursive_tag_union(
+                &escape_kw(name),
+                id,
+                tags,
+                Some(*index_of_null_tag as usize),
+                *discriminant_size,
+                *discriminant_offset,
+                types,
+                decls,
+            ),
+            RocTagUnion::NullableUnwrapped {
+                name,
+                null_tag,
+                non_null_tag,
+                non_null_payload,
+                null_represents_first_tag: _,
+            } => add_unwrapped_tag_union(
+                &escape_kw(name),
+                id,
+                Some(null_tag),
+                non_null_tag,
+                *non_null_payload,
+                types,
+                decls,
+            ),
+            RocTagUnion::NonNullableUnwrapped {
+                name,
+                tag_name,
+                payload,
+            } => add_unwrapped_tag_union(
+                &escape_kw(name),
+                id,
+                None,
+                tag_name,
+                *payload,
+                types,
+                decls,
+            ),
+            RocTagUnion::SingleTagStruct {
+                name,
+                tag_name: _,
+                payload,
+            } => match payload {
+                RocSingleTagPayload::HasNoClosure { payload_fields } => {
+                    let fields: Vec<_> = payload_fields
+                        .iter()
+                        .enumerate()
+                        .map(|(index, id)| (format!("f{index}"), *id))
+                        .collect();
+
+                    add_struct_help(&escape_kw(name), id, &fields, types, decls);
+                }
+                RocSingleTagPayload::HasClosure { payload_getters } => {
+                    let getters = payload_getters.iter().map(|(_, getter)| getter.as_str());
+
+                    add_opaque_struct(&escape_kw(name), id, getters, types, decls);
+                }
+            },
+        },
+        RocType::RocResult(ok, err) => add_result(id, *ok, *err, types, decls),
+        RocType::Function(roc_fn) => {
+            // Top-level functions are the entry points, which are added separately.
+            if !roc_fn.is_toplevel {
+                add_function(id, roc_fn, types, decls)
+            }
+        }
+        // These are all declared in roc_std.h
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::Unsized => {}
+        RocType::RecursivePointer { .. } => {
+            // This is recursively pointing to a type that should already have been added,
+            // so no extra work needs to happen.
+        }
+    }
+}
+
+fn add_struct(
+    name: &str,
+    id: TypeId,
+    fields: &RocStructFields,
+    is_tag_union_payload: bool,
+    types: &Types,
+    decls: &mut Decls,
+) {
+    match fields {
+        RocStructFields::HasNoClosure { fields } => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(label, id)| {
+                    // Tag union payloads have numbered fields, so we prefix them
+                    // with an "f" because C doesn't allow struct fields to be numbers.
+                    let label = if is_tag_union_payload {
+                        format!("f{label}")
+                    } else {
+                        escape_kw(label)
+                    };
+
+                    (label, *id)
+                })
+                .collect();
+
+            add_struct_help(name, id, &fields, types, decls);
+        }
+        RocStructFields::HasClosure { fields } => {
+            let getters = fields
+                .iter()
+                .map(|(_, _, accessors)| accessors.getter.as_str());
+
+            add_opaque_struct(name, id, getters, types, decls);
+        }
+    }
+}
+
+fn add_struct_help(
+    name: &str,
+    id: TypeId,
+    fields: &[(String, TypeId)],
+    types: &Types,
+    decls: &mut Decls,
+) {
+    decls.typedef("struct", name);
+
+    let mut buf = format!("\nstruct {name} {{\n");
+
+    if fields.is_empty() {
+        // C doesn't allow empty structs
+        writeln!(buf, "{INDENT}RocUnit _unused;").unwrap();
+    }
+
+    for (label, field_id) in fields {
+        writeln!(buf, "{INDENT}{}", field_decl(*field_id, label, types)).unwrap();
+    }
+
+    buf.push_str("};\n");
+
+    // The sizes reported for the payloads of recursive tag unions don't include their
+    // recursive fields, so they can't be checked.
+    if !matches!(types.get_type(id), RocType::TagUnionPayload { .. }) {
+        assert_layout(name, id, types, &mut buf);
+    }
+
+    decls.definitions.push_str(&buf);
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let mut body = String::new();
+
+            for (label, field_id) in fields {
+                refcount_code(
+                    op,
+                    *field_id,
+                    &format!("self->{label}"),
+                    1,
+                    types,
+                    &mut body,
+                );
+            }
+
+            decls.function(refcount_signature(op, name), &body);
+        }
+    }
+}
+
+/// Structs containing closures have a size and layout that depend on the app,
+/// so hosts can only use them through pointers, and the app refcounts them.
+fn add_opaque_struct<'a>(
+    name: &str,
+    id: TypeId,
+    getters: impl Iterator<Item = &'a str>,
+    types: &Types,
+    decls: &mut Decls,
+) {
+    decls.typedef("struct", name);
+
+    let getters: Vec<_> = getters.collect();
+
+    write!(
+        decls.definitions,
+        "\n// {name} contains a closure, so its size and layout depend on the app.\n// The app exposes these functions to read its fields: {}\n",
+        getters.join(", ")
+    )
+    .unwrap();
+
+    // Whatever the closures capture is only known to the app, so it increfs and decrefs them
+    let Some(refcounters) = types.refcounters(id) else {
+        unreachable!("{name} contains a closure, so the app exposes refcounters for it")
+    };
+
+    for (op, extern_name) in [
+        (Refcount::Incref, &refcounters.incref),
+        (Refcount::Decref, &refcounters.decref),
+    ] {
+        writeln!(
+            decls.definitions,
+            "extern void {extern_name}(const {name} *self);"
+        )
+        .unwrap();
+
+        decls.function(
+            refcount_signature(op, name),
+            &format!("{INDENT}{extern_name}(self);\n"),
+        );
+    }
+}
+
+fn add_enumeration<S: AsRef<str>>(name: &str, tags: &[S], size: u32, decls: &mut Decls) {
+    writeln!(decls.forward, "typedef {} {name};", int_type(size)).unwrap();
+
+    let mut buf = "\nenum {\n".to_string();
+
+    for (index, tag) in tags.iter().enumerate() {
+        writeln!(buf, "{INDENT}{name}_{} = {index},", tag.as_ref()).unwrap();
+    }
+
+    buf.push_str("};\n");
+    decls.definitions.push_str(&buf);
+}
+
+fn add_discriminant(
+    name: &str,
+    tags: &[(String, Option<TypeId>)],
+    size: u32,
+    decls: &mut Decls,
+) -> String {
+    let discriminant_name = format!("discriminant_{name}");
+    let tag_names: Vec<_> = tags.iter().map(|(tag_name, _)| tag_name).collect();
+
+    // Recursive tag unions which store their discriminant in the pointer report a size of 0
+    add_enumeration(&discriminant_name, &tag_names, size.max(1), decls);
+
+    discriminant_name
+}
+
+fn add_non_recursive_tag_union(
+    name: &str,
+    id: TypeId,
+    tags: &[(String, Option<TypeId>)],
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    types: &Types,
+    decls: &mut Decls,
+) {
+    let discriminant_name = add_discriminant(name, tags, discriminant_size, decls);
+
+    decls.typedef("union", name);
+
+    // As in the Rust glue, the whole tag union is a C union, and the discriminant lives in its
+    // bytes at discriminant_offset. Putting the discriminant in a struct next to the union
+    // wouldn't work, because Roc puts it in the padding after the payloads whenever it can.
+    let mut buf = format!("\nunion {name} {{\n");
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            writeln!(buf, "{INDENT}{}", field_decl(*payload_id, tag_name, types)).unwrap();
+        }
+    }
+
+    writeln!(
+        buf,
+        "{INDENT}uint8_t _sizer[{}];",
+        types.size_rounded_to_alignment(id)
+    )
+    .unwrap();
+    buf.push_str("};\n");
+    assert_layout(name, id, types, &mut buf);
+    decls.definitions.push_str(&buf);
+
+    decls.function(
+        format!("{discriminant_name} {name}_discriminant(const {name} *self)"),
+        &format!(
+            "{INDENT}{discriminant_name} discriminant;\n{INDENT}memcpy(&discriminant, (const uint8_t *)self + {discriminant_offset}, sizeof(discriminant));\n{INDENT}return discriminant;\n"
+        ),
+    );
+
+    decls.function(
+        format!("void {name}_set_discriminant({name} *self, {discriminant_name} discriminant)"),
+        &format!(
+            "{INDENT}memcpy((uint8_t *)self + {discriminant_offset}, &discriminant, sizeof(discriminant));\n"
+        ),
+    );
+
+    for (tag_name, opt_payload_id) in tags {
+        let (params, set_payload) = match opt_payload_id {
+            Some(payload_id) => (
+                format!("{} payload", type_name(*payload_id, types)),
+                format!("{INDENT}self.{tag_name} = payload;\n"),
+            ),
+            None => ("void".to_string(), String::new()),
+        };
+
+        decls.function(
+            format!("{name} {name}_make_{tag_name}({params})"),
+            &format!(
+                "{INDENT}{name} self;\n{INDENT}memset(&self, 0, sizeof(self));\n{set_payload}{INDENT}{name}_set_discriminant(&self, {discriminant_name}_{tag_name});\n{INDENT}return self;\n"
+            ),
+        );
+    }
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let body = tag_switch(
+                op,
+                &format!("{name}_discriminant(self)"),
+                &discriminant_name,
+                tags,
+                "self->",
+                1,
+                types,
+            );
+
+            decls.function(refcount_signature(op, name), &body);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_recursive_tag_union(
+    name: &str,
+    id: TypeId,
+    tags: &[(String, Option<TypeId>)],
+    null_tag_index: Option<usize>,
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    types: &Types,
+    decls: &mut Decls,
+) {
+    let union_name = format!("union_{name}");
+    let discriminant_name = add_discriminant(name, tags, discriminant_size, decls);
+
+    // Roc stores the discriminant in the unused low bits of the pointer, as long as there are
+    // few enough (non-null) tags for it to fit. Otherwise it goes in the heap allocation.
+    let ptr_size = types.target().ptr_size();
+    let non_null_tags = tags.len() - null_tag_index.iter().count();
+    let discriminant_in_pointer = non_null_tags < ptr_size;
+    let bitmask = ptr_size - 1;
+
+    decls.typedef("struct", name);
+    decls.typedef("union", &union_name);
+
+    let mut buf = format!("\nstruct {name} {{\n{INDENT}{union_name} *pointer;\n}};\n");
+    assert_layout(name, id, types, &mut buf);
+    decls.recursive.push_str(&buf);
+
+    let mut buf = format!("\nunion {union_name} {{\n");
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            writeln!(buf, "{INDENT}{}", field_decl(*payload_id, tag_name, types)).unwrap();
+        }
+    }
+
+    if !discriminant_in_pointer {
+        writeln!(
+            buf,
+            "{INDENT}uint8_t _sizer[{}];",
+            discriminant_offset + discriminant_size
+        )
+        .unwrap();
+    } else if tags.iter().all(|(_, payload)| payload.is_none()) {
+        // C doesn't allow empty unions
+        writeln!(buf, "{INDENT}RocUnit _unused;").unwrap();
+    }
+
+    buf.push_str("};\n");
+    decls.definitions.push_str(&buf);
+
+    let union_pointer = if discriminant_in_pointer {
+        format!(
+            "{INDENT}// The discriminant is stored in the unused bits at the end of the pointer\n{INDENT}return ({union_name} *)((uintptr_t)self->pointer & ~(uintptr_t){bitmask:#b});\n"
+        )
+    } else {
+        format!("{INDENT}return self->pointer;\n")
+    };
+
+    decls.function(
+        format!("{union_name} *{name}_union(const {name} *self)"),
+        &union_pointer,
+    );
+
+    let mut discriminant = String::new();
+
+    if let Some(null_tag_index) = null_tag_index {
+        let null_tag = &tags[null_tag_index].0;
+
+        write!(
+            discriminant,
+            "{INDENT}if (self->pointer == NULL) {{\n{INDENT}{INDENT}return {discriminant_name}_{null_tag};\n{INDENT}}}\n\n"
+        )
+        .unwrap();
+    }
+
+    if discriminant_in_pointer {
+        writeln!(
+            discriminant,
+            "{INDENT}return ({discriminant_name})((uintptr_t)self->pointer & {bitmask:#b});"
+        )
+        .unwrap();
+    } else {
+        write!(
+            discriminant,
+            "{INDENT}{discriminant_name} discriminant;\n{INDENT}memcpy(&discriminant, (const uint8_t *)self->pointer + {discriminant_offset}, sizeof(discriminant));\n{INDENT}return discriminant;\n"
+        )
+        .unwrap();
+    }
+
+    decls.function(
+        format!("{discriminant_name} {name}_discriminant(const {name} *self)"),
+        &discriminant,
+    );
+
+    for (index, (tag_name, opt_payload_id)) in tags.iter().enumerate() {
+        let constructor = format!("{name} {name}_make_{tag_name}");
+
+        if Some(index) == null_tag_index {
+            decls.function(
+                format!("{constructor}(void)"),
+                &format!(
+                    "{INDENT}{name} self;\n{INDENT}self.pointer = NULL;\n{INDENT}return self;\n"
+                ),
+            );
+
+            continue;
+        }
+
+        let mut body = format!(
+            "{INDENT}{union_name} *pointer = ({union_name} *)roc_alloc_refcounted(sizeof({union_name}), _Alignof({union_name}));\n"
+        );
+
+        let params = match opt_payload_id {
+            Some(payload_id) => {
+                writeln!(body, "{INDENT}pointer->{tag_name} = payload;").unwrap();
+
+                format!("{} payload", type_name(*payload_id, types))
+            }
+            None => "void".to_string(),
+        };
+
+        let tag = format!("{discriminant_name}_{tag_name}");
+
+        if discriminant_in_pointer {
+            write!(
+                body,
+                "\n{INDENT}{name} self;\n{INDENT}self.pointer = ({union_name} *)((uintptr_t)pointer | {tag});\n{INDENT}return self;\n"
+            )
+            .unwrap();
+        } else {
+            write!(
+                body,
+                "{INDENT}{discriminant_name} discriminant = {tag};\n{INDENT}memcpy((uint8_t *)pointer + {discriminant_offset}, &discriminant, sizeof(discriminant));\n\n{INDENT}{name} self;\n{INDENT}self.pointer = pointer;\n{INDENT}return self;\n"
+            )
+            .unwrap();
+        }
+
+        decls.function(format!("{constructor}({params})"), &body);
+    }
+
+    decls.function(
+        refcount_signature(Refcount::Incref, name),
+        &format!("{INDENT}roc_incref({name}_union(self));\n"),
+    );
+
+    let mut decref = format!(
+        "{INDENT}{union_name} *pointer = {name}_union(self);\n\n{INDENT}if (roc_release(pointer)) {{\n"
+    );
+    decref.push_str(&tag_switch(
+        Refcount::Decref,
+        &format!("{name}_discriminant(self)"),
+        &discriminant_name,
+        tags,
+        "pointer->",
+        2,
+        types,
+    ));
+    write!(
+        decref,
+        "{INDENT}{INDENT}roc_free(pointer, _Alignof({union_name}));\n{INDENT}}}\n"
+    )
+    .unwrap();
+
+    decls.function(refcount_signature(Refcount::Decref, name), &decref);
+}
+
+/// A recursive tag union with only one tag that has a payload, so the pointer doesn't need a
+/// discriminant. If there's another tag, it has no payload and is represented by NULL.
+fn add_unwrapped_tag_union(
+    name: &str,
+    id: TypeId,
+    null_tag: Option<&str>,
+    non_null_tag: &str,
+    payload_id: TypeId,
+    types: &Types,
+    decls: &mut Decls,
+) {
+    let payload_name = type_name(payload_id, types);
+
+    decls.typedef("struct", name);
+
+    let mut buf = format!("\nstruct {name} {{\n{INDENT}{payload_name} *pointer;\n}};\n");
+    assert_layout(name, id, types, &mut buf);
+    decls.recursive.push_str(&buf);
+
+    if let Some(null_tag) = null_tag {
+        let mut tag_names = [null_tag, non_null_tag];
+        tag_names.sort();
+
+        let discriminant_name = format!("discriminant_{name}");
+        add_enumeration(&discriminant_name, &tag_names, 1, decls);
+
+        decls.function(
+            format!("{discriminant_name} {name}_discriminant(const {name} *self)"),
+            &format!(
+                "{INDENT}return self->pointer == NULL ? {discriminant_name}_{null_tag} : {discriminant_name}_{non_null_tag};\n"
+            ),
+        );
+
+        decls.function(
+            format!("{name} {name}_make_{null_tag}(void)"),
+            &format!("{INDENT}{name} self;\n{INDENT}self.pointer = NULL;\n{INDENT}return self;\n"),
+        );
+    }
+
+    decls.function(
+        format!("{name} {name}_make_{non_null_tag}({payload_name} payload)"),
+        &format!(
+            "{INDENT}{name} self;\n{INDENT}self.pointer = ({payload_name} *)roc_alloc_refcounted(sizeof({payload_name}), _Alignof({payload_name}));\n{INDENT}*self.pointer = payload;\n{INDENT}return self;\n"
+        ),
+    );
+
+    decls.function(
+        refcount_signature(Refcount::Incref, name),
+        &format!("{INDENT}roc_incref(self->pointer);\n"),
+    );
+
+    let mut decref = format!("{INDENT}if (roc_release(self->pointer)) {{\n");
+    refcount_code(
+        Refcount::Decref,
+        payload_id,
+        "(*self->pointer)",
+        2,
+        types,
+        &mut decref,
+    );
+    write!(
+        decref,
+        "{INDENT}{INDENT}roc_free(self->pointer, _Alignof({payload_name}));\n{INDENT}}}\n"
+    )
+    .unwrap();
+
+    decls.function(refcount_signature(Refcount::Decref, name), &decref);
+}
+
+fn add_result(id: TypeId, ok: TypeId, err: TypeId, types: &Types, decls: &mut Decls) {
+    let name = type_name(id, types);
+    let ok_name = type_name(ok, types);
+    let err_name = type_name(err, types);
+
+    decls.typedef("struct", &name);
+
+    let mut buf = format!(
+        "\nstruct {name} {{\n{INDENT}union {{\n{INDENT}{INDENT}{ok_name} ok;\n{INDENT}{INDENT}{err_name} err;\n{INDENT}}} payload;\n{INDENT}uint8_t is_ok;\n}};\n"
+    );
+    assert_layout(&name, id, types, &mut buf);
+    decls.definitions.push_str(&buf);
+
+    for (tag, payload_name, is_ok) in [("ok", &ok_name, 1), ("err", &err_name, 0)] {
+        decls.function(
+            format!("{name} {name}_{tag}({payload_name} payload)"),
+            &format!(
+                "{INDENT}{name} self;\n{INDENT}memset(&self, 0, sizeof(self));\n{INDENT}self.payload.{tag} = payload;\n{INDENT}self.is_ok = {is_ok};\n{INDENT}return self;\n"
+            ),
+        );
+    }
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let mut ok_code = String::new();
+            let mut err_code = String::new();
+
+            refcount_code(op, ok, "self->payload.ok", 2, types, &mut ok_code);
+            refcount_code(op, err, "self->payload.err", 2, types, &mut err_code);
+
+            let body = match (ok_code.is_empty(), err_code.is_empty()) {
+                (false, true) => format!("{INDENT}if (self->is_ok) {{\n{ok_code}{INDENT}}}\n"),
+                (true, false) => format!("{INDENT}if (!self->is_ok) {{\n{err_code}{INDENT}}}\n"),
+                _ => format!(
+                    "{INDENT}if (self->is_ok) {{\n{ok_code}{INDENT}}} else {{\n{err_code}{INDENT}}}\n"
+                ),
+            };
+
+            decls.function(refcount_signature(op, &name), &body);
+        }
+    }
+}
+
+fn add_function(id: TypeId, roc_fn: &RocFn, types: &Types, decls: &mut Decls) {
+    let name = escape_kw(&roc_fn.function_name);
+    let extern_name = &roc_fn.extern_name;
+    let ret = type_name(roc_fn.ret, types);
+
+    decls.typedef("struct", &name);
+
+    let mut buf = format!(
+        "\nstruct {name} {{\n{INDENT}{} closure_data;\n}};\n",
+        type_name(roc_fn.lambda_set, types)
+    );
+    assert_layout(&name, id, types, &mut buf);
+
+    // extern_name(const arg0_type *arg0, ..., void *closure_data, return_type *output);
+    let mut extern_params: Vec<_> = roc_fn
+        .args
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| format!("const {} *arg{index}", type_name(*arg, types)))
+        .collect();
+
+    if extern_params.is_empty() {
+        // These always have a first argument that's a pointer, even if it's to nothing.
+        extern_params.push("const RocUnit *arg0".to_string());
+    }
+
+    writeln!(
+        buf,
+        "\nextern void {extern_name}({}, void *closure_data, {ret} *output);",
+        extern_params.join(", ")
+    )
+    .unwrap();
+    decls.definitions.push_str(&buf);
+
+    let mut params = vec![format!("{name} self")];
+    let mut args = Vec::new();
+    let mut body = String::new();
+
+    for (index, arg) in roc_fn.args.iter().enumerate() {
+        params.push(format!("{} arg{index}", type_name(*arg, types)));
+        args.push(format!("&arg{index}"));
+    }
+
+    if args.is_empty() {
+        writeln!(body, "{INDENT}RocUnit unit = 0;").unwrap();
+        args.push("&unit".to_string());
+    }
+
+    write!(
+        body,
+        "{INDENT}{ret} output;\n{INDENT}{extern_name}({}, &self.closure_data, &output);\n{INDENT}return output;\n",
+        args.join(", ")
+    )
+    .unwrap();
+
+    decls.function(format!("{ret} {name}_call({})", params.join(", ")), &body);
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let mut body = String::new();
+
+            refcount_code(
+                op,
+                roc_fn.lambda_set,
+                "self->closure_data",
+                1,
+                types,
+                &mut body,
+            );
+
+            decls.function(refcount_signature(op, &name), &body);
+        }
+    }
+}
+
+fn add_entry_point(name: &str, id: TypeId, types: &Types, decls: &mut Decls) {
+    let (args, ret) = match types.get_type(id) {
+        RocType::Function(roc_fn) => (roc_fn.args.as_slice(), roc_fn.ret),
+        _ => (&[][..], id),
+    };
+    let ret = type_name(ret, types);
+
+    let mut extern_params = vec![format!("{ret} *output")];
+    let mut params = Vec::new();
+    let mut call_args = vec!["&output".to_string()];
+
+    for (index, arg) in args.iter().enumerate() {
+        let arg_type = type_name(*arg, types);
+
+        // Values which own heap allocations are passed by pointer
+        if types.is_refcounted(*arg) {
+            extern_params.push(format!("{arg_type} *arg{index}"));
+            call_args.push(format!("&arg{index}"));
+        } else {
+            extern_params.push(format!("{arg_type} arg{index}"));
+            call_args.push(format!("arg{index}"));
+        }
+
+        params.push(format!("{arg_type} arg{index}"));
+    }
+
+    if params.is_empty() {
+        params.push("void".to_string());
+    }
+
+    writeln!(
+        decls.definitions,
+        "\nextern void roc__{name}_1_exposed_generic({});",
+        extern_params.join(", ")
+    )
+    .unwrap();
+
+    decls.function(
+        format!("{ret} roc_{name}({})", params.join(", ")),
+        &format!(
+            "{INDENT}{ret} output;\n{INDENT}roc__{name}_1_exposed_generic({});\n{INDENT}return output;\n",
+            call_args.join(", ")
+        ),
+    );
+}
+
+/// A switch statement which increfs or decrefs the payload of whichever tag is active
+fn tag_switch(
+    op: Refcount,
+    discriminant: &str,
+    discriminant_name: &str,
+    tags: &[(String, Option<TypeId>)],
+    payload_prefix: &str,
+    indent: usize,
+    types: &Types,
+) -> String {
+    let indentation = INDENT.repeat(indent);
+    let mut buf = format!("{indentation}switch ({discriminant}) {{\n");
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            if types.is_refcounted(*payload_id) {
+                writeln!(buf, "{indentation}case {discriminant_name}_{tag_name}:").unwrap();
+                refcount_code(
+                    op,
+                    *payload_id,
+                    &format!("{payload_prefix}{tag_name}"),
+                    indent + 1,
+                    types,
+                    &mut buf,
+                );
+                writeln!(buf, "{indentation}{INDENT}break;").unwrap();
+            }
+        }
+    }
+
+    write!(
+        buf,
+        "{indentation}default:\n{indentation}{INDENT}break;\n{indentation}}}\n"
+    )
+    .unwrap();
+
+    buf
+}
+
+fn refcount_signature(op: Refcount, name: &str) -> String {
+    match op {
+        Refcount::Incref => format!("void {name}_incref(const {name} *self)"),
+        Refcount::Decref => format!("void {name}_decref({name} *self)"),
+    }
+}
+
+/// Statements which incref or decref the value at `lvalue`, if it's refcounted
+fn refcount_code(
+    op: Refcount,
+    id: TypeId,
+    lvalue: &str,
+    indent: usize,
+    types: &Types,
+    buf: &mut String,
+) {
+    if !types.is_refcounted(id) {
+        return;
+    }
+
+    let indentation = INDENT.repeat(indent);
+
+    let (elem_type, elem_lvalue): (String, String) = match (op, types.get_type(id)) {
+        (Refcount::Incref, RocType::RocStr) => {
+            writeln!(buf, "{indentation}roc_str_incref(&{lvalue});").unwrap();
+            return;
+        }
+        (Refcount::Decref, RocType::RocStr) => {
+            writeln!(buf, "{indentation}roc_str_decref(&{lvalue});").unwrap();
+            return;
+        }
+        (
+            Refcount::Incref,
+            RocType::RocList(_) | RocType::RocDict(_, _) | RocType::RocSet(_) | RocType::Unsized,
+        ) => {
+            // Sharing a list shares its elements too, so they don't need increfs of their own
+            writeln!(buf, "{indentation}roc_list_incref(&{lvalue});").unwrap();
+            return;
+        }
+        (Refcount::Incref, RocType::RocBox(_)) => {
+            writeln!(buf, "{indentation}roc_box_incref(&{lvalue});").unwrap();
+            return;
+        }
+        (Refcount::Decref, RocType::Unsized) => {
+            writeln!(buf, "{indentation}roc_list_decref(&{lvalue}, 1);").unwrap();
+            return;
+        }
+        (Refcount::Decref, RocType::RocList(elem_id) | RocType::RocSet(elem_id)) => {
+            let elem_type = type_name(*elem_id, types);
+            let elements = format!("elements_{indent}");
+            let index = format!("i_{indent}");
+
+            if !types.is_refcounted(*elem_id) {
+                writeln!(
+                    buf,
+                    "{indentation}roc_list_decref(&{lvalue}, _Alignof({elem_type}));"
+                )
+                .unwrap();
+                return;
+            }
+
+            write!(
+                buf,
+                "{indentation}if (roc_list_release(&{lvalue})) {{\n{indentation}{INDENT}{elem_type} *{elements} = ({elem_type} *){lvalue}.elements;\n\n{indentation}{INDENT}for (size_t {index} = 0; {index} < roc_list_len(&{lvalue}); {index}++) {{\n"
+            )
+            .unwrap();
+            refcount_code(
+                op,
+                *elem_id,
+                &format!("{elements}[{index}]"),
+                indent + 2,
+                types,
+                buf,
+            );
+            write!(
+                buf,
+                "{indentation}{INDENT}}}\n\n{indentation}{INDENT}roc_list_free(&{lvalue}, _Alignof({elem_type}));\n{indentation}}}\n"
+            )
+            .unwrap();
+            return;
+        }
+        (Refcount::Decref, RocType::RocDict(key_id, value_id)) => {
+            decref_dict(*key_id, *value_id, lvalue, indent, types, buf);
+            return;
+        }
+        (Refcount::Decref, RocType::RocBox(contents_id)) => {
+            let contents_type = type_name(*contents_id, types);
+
+            (
+                contents_type.clone(),
+                format!("(*({contents_type} *){lvalue}.contents)"),
+            )
+        }
+        (_, RocType::RecursivePointer(target_id)) => {
+            let target_name = type_name(*target_id, types);
+
+            writeln!(
+                buf,
+                "{indentation}{target_name}_{}(&{lvalue});",
+                op_name(op)
+            )
+            .unwrap();
+            return;
+        }
+        _ => {
+            // Everything else has its own function
+            let name = type_name(id, types);
+
+            writeln!(buf, "{indentation}{name}_{}(&{lvalue});", op_name(op)).unwrap();
+            return;
+        }
+    };
+
+    // Only boxes get this far
+    if types.is_refcounted(match types.get_type(id) {
+        RocType::RocBox(contents_id) => *contents_id,
+        _ => unreachable!(),
+    }) {
+        writeln!(buf, "{indentation}if (roc_box_release(&{lvalue})) {{").unwrap();
+
+        if let RocType::RocBox(contents_id) = types.get_type(id) {
+            refcount_code(op, *contents_id, &elem_lvalue, indent + 1, types, buf);
+        }
+
+        write!(
+            buf,
+            "{indentation}{INDENT}roc_box_free(&{lvalue}, _Alignof({elem_type}));\n{indentation}}}\n"
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            buf,
+            "{indentation}roc_box_decref(&{lvalue}, _Alignof({elem_type}));"
+        )
+        .unwrap();
+    }
+}
+
+/// A Dict is a list of entries, where the key comes first unless the value has a larger alignment.
+fn decref_dict(
+    key_id: TypeId,
+    value_id: TypeId,
+    lvalue: &str,
+    indent: usize,
+    types: &Types,
+    buf: &mut String,
+) {
+    let indentation = INDENT.repeat(indent);
+    let key_align = types.align(key_id);
+    let value_align = types.align(value_id);
+    let entry_align = key_align.max(value_align);
+
+    if !types.is_refcounted(key_id) && !types.is_refcounted(value_id) {
+        writeln!(
+            buf,
+            "{indentation}roc_list_decref(&{lvalue}, {entry_align});"
+        )
+        .unwrap();
+        return;
+    }
+
+    let key_size = types.size_rounded_to_alignment(key_id);
+    let value_size = types.size_rounded_to_alignment(value_id);
+    let (key_offset, value_offset, entry_size) = if key_align >= value_align {
+        let value_offset = round_up(key_size, value_align);
+
+        (
+            0,
+            value_offset,
+            round_up(value_offset + value_size, entry_align),
+        )
+    } else {
+        let key_offset = round_up(value_size, key_align);
+
+        (key_offset, 0, round_up(key_offset + key_size, entry_align))
+    };
+
+    let entries = format!("entries_{indent}");
+    let index = format!("i_{indent}");
+
+    write!(
+        buf,
+        "{indentation}if (roc_list_release(&{lvalue})) {{\n{indentation}{INDENT}uint8_t *{entries} = (uint8_t *){lvalue}.elements;\n\n{indentation}{INDENT}for (size_t {index} = 0; {index} < roc_list_len(&{lvalue}); {index}++) {{\n"
+    )
+    .unwrap();
+
+    for (id, offset) in [(key_id, key_offset), (value_id, value_offset)] {
+        let entry_lvalue = format!(
+            "(*({} *)({entries} + {index} * {entry_size} + {offset}))",
+            type_name(id, types)
+        );
+
+        refcount_code(Refcount::Decref, id, &entry_lvalue, indent + 2, types, buf);
+    }
+
+    write!(
+        buf,
+        "{indentation}{INDENT}}}\n\n{indentation}{INDENT}roc_list_free(&{lvalue}, {entry_align});\n{indentation}}}\n"
+    )
+    .unwrap();
+}
+
+fn op_name(op: Refcount) -> &'static str {
+    match op {
+        Refcount::Incref => "incref",
+        Refcount::Decref => "decref",
+    }
+}
+
+fn round_up(size: u32, alignment: u32) -> u32 {
+    (size + alignment - 1) / alignment * alignment
+}
+
+fn assert_layout(name: &str, id: TypeId, types: &Types, buf: &mut String) {
+    let size = types.size_rounded_to_alignment(id);
+    let align = types.align(id);
+
+    writeln!(
+        buf,
+        "_Static_assert(sizeof({name}) == {size}, \"{name} should be {size} bytes\");"
+    )
+    .unwrap();
+    writeln!(
+        buf,
+        "_Static_assert(_Alignof({name}) == {align}, \"{name} should be aligned to {align} bytes\");"
+    )
+    .unwrap();
+}
+
+/// A field declaration, with a comment saying what's in it if the C type doesn't
+fn field_decl(id: TypeId, label: &str, types: &Types) -> String {
+    let decl = format!("{} {label};", type_name(id, types));
+
+    let contents = match types.get_type(id) {
+        RocType::RocList(elem_id) => format!("List {}", type_name(*elem_id, types)),
+        RocType::RocSet(elem_id) => format!("Set {}", type_name(*elem_id, types)),
+        RocType::RocBox(elem_id) => format!("Box {}", type_name(*elem_id, types)),
+        RocType::RocDict(key_id, value_id) => format!(
+            "Dict {} {}",
+            type_name(*key_id, types),
+            type_name(*value_id, types)
+        ),
+        _ => return decl,
+    };
+
+    format!("{decl} // {contents}")
+}
+
+fn type_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "RocUnit".to_string(),
+        RocType::RocStr => "RocStr".to_string(),
+        RocType::Bool => "bool".to_string(),
+        RocType::Num(RocNum::U8) => "uint8_t".to_string(),
+        RocType::Num(RocNum::U16) => "uint16_t".to_string(),
+        RocType::Num(RocNum::U32) => "uint32_t".to_string(),
+        RocType::Num(RocNum::U64) => "uint64_t".to_string(),
+        RocType::Num(RocNum::U128) => "RocU128".to_string(),
+        RocType::Num(RocNum::I8) => "int8_t".to_string(),
+        RocType::Num(RocNum::I16) => "int16_t".to_string(),
+        RocType::Num(RocNum::I32) => "int32_t".to_string(),
+        RocType::Num(RocNum::I64) => "int64_t".to_string(),
+        RocType::Num(RocNum::I128) => "RocI128".to_string(),
+        RocType::Num(RocNum::F32) => "float".to_string(),
+        RocType::Num(RocNum::F64) => "double".to_string(),
+        RocType::Num(RocNum::Dec) => "RocDec".to_string(),
+        RocType::RocDict(_, _) => "RocDict".to_string(),
+        RocType::RocSet(_) => "RocSet".to_string(),
+        RocType::RocList(_) | RocType::Unsized => "RocList".to_string(),
+        RocType::RocBox(_) => "RocBox".to_string(),
+        RocType::RocResult(ok_id, err_id) => {
+            // C has no generics, so each kind of Result gets its own struct
+            format!(
+                "RocResult_{}_{}",
+                mangled_name(*ok_id, types),
+                mangled_name(*err_id, types)
+            )
+        }
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::NonRecursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => escape_kw(name),
+        RocType::RecursivePointer(content) => type_name(*content, types),
+        RocType::Function(RocFn { function_name, .. }) => escape_kw(function_name),
+    }
+}
+
+/// A name for a type which includes its type parameters, e.g. `List_Str`
+/// rather than `RocList`, so that different Results get different names.
+fn mangled_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "Unit".to_string(),
+        RocType::RocStr => "Str".to_string(),
+        RocType::Bool => "Bool".to_string(),
+        RocType::Num(num) => format!("{num:?}"),
+        RocType::RocList(elem_id) => format!("List_{}", mangled_name(*elem_id, types)),
+        RocType::RocSet(elem_id) => format!("Set_{}", mangled_name(*elem_id, types)),
+        RocType::RocBox(elem_id) => format!("Box_{}", mangled_name(*elem_id, types)),
+        RocType::RocDict(key_id, value_id) => format!(
+            "Dict_{}_{}",
+            mangled_name(*key_id, types),
+            mangled_name(*value_id, types)
+        ),
+        RocType::RocResult(ok_id, err_id) => format!(
+            "Result_{}_{}",
+            mangled_name(*ok_id, types),
+            mangled_name(*err_id, types)
+        ),
+        RocType::Unsized => "Unsized".to_string(),
+        _ => type_name(id, types),
+    }
+}
+
+fn int_type(size: u32) -> &'static str {
+    match size {
+        1 => "uint8_t",
+        2 => "uint16_t",
+        4 => "uint32_t",
+        8 => "uint64_t",
+        _ => unreachable!("A discriminant can't be {size} bytes"),
+    }
+}
+
+fn arch_condition(architecture: Architecture) -> &'static str {
+    match architecture {
+        Architecture::X86_64 => "defined(__x86_64__) || defined(_M_X64)",
+        Architecture::X86_32 => "defined(__i386__) || defined(_M_IX86)",
+        Architecture::Aarch64 => "defined(__aarch64__) || defined(_M_ARM64)",
+        Architecture::Aarch32 => "defined(__arm__) || defined(_M_ARM)",
+        Architecture::Wasm32 => "defined(__wasm32__)",
+    }
+}
+
+/// Escape a C reserved keyword, if necessary.
+fn escape_kw(input: &str) -> String {
+    if RESERVED_KEYWORDS.contains(&input) {
+        format!("{input}_")
+    } else {
+        input.to_string()
+    }
+}
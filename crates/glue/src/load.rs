@@ -1,5 +1,5 @@
-use crate::roc_type;
-use crate::types::Types;
+use crate::types::{self, Refcounters, Types};
+use crate::{c_glue, roc_type, zig_glue};
 use bumpalo::Bump;
 use libloading::Library;
 use roc_build::{
@@ -81,6 +81,7 @@ pub fn generate(
                 linking_strategy,
                 true,
                 None,
+                None,
                 RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
                 load_config,
             );
@@ -91,6 +92,7 @@ pub fn generate(
                     problems,
                     total_time,
                     expect_metadata: _,
+                    source_paths: _,
                 }) => {
                     // TODO: Should binary_path be update to deal with extensions?
                     use target_lexicon::OperatingSystem;
@@ -143,76 +145,143 @@ pub fn generate(
 
                         process::exit(1);
                     });
-                    for roc_type::File { name, content } in &files {
-                        let valid_name = PathBuf::from(name.as_str())
-                            .components()
-                            .all(|comp| matches!(comp, Component::CurDir | Component::Normal(_)));
-                        if !valid_name {
-                            eprintln!("File name was invalid: {}", &name);
-
-                            process::exit(1);
-                        }
-                        let full_path = output_path.join(name.as_str());
-                        if let Some(dir_path) = full_path.parent() {
-                            std::fs::create_dir_all(dir_path).unwrap_or_else(|err| {
-                                eprintln!(
-                                    "Unable to create output directory {} - {:?}",
-                                    dir_path.display(),
-                                    err
-                                );
-
-                                process::exit(1);
-                            });
-                        }
-                        let mut file = File::create(&full_path).unwrap_or_else(|err| {
-                            eprintln!(
-                                "Unable to create output file {} - {:?}",
-                                full_path.display(),
-                                err
-                            );
-
-                            process::exit(1);
-                        });
-
-                        file.write_all(content.as_bytes()).unwrap_or_else(|err| {
-                            eprintln!(
-                                "Unable to write bindings to output file {} - {:?}",
-                                full_path.display(),
-                                err
-                            );
-
-                            process::exit(1);
-                        });
-                    }
-
-                    println!(
-                        "🎉 Generated type declarations in:\n\n\t{}",
-                        output_path.display()
+                    write_glue_files(
+                        output_path,
+                        files.iter().map(|roc_type::File { name, content }| {
+                            (name.as_str(), content.as_str())
+                        }),
                     );
 
                     Ok(0)
                 }
-                Err(BuildFileError::ErrorModule { module, total_time }) => {
-                    handle_error_module(module, total_time, spec_path.as_os_str(), true)
+                Err(BuildFileError::ErrorModule { module, total_time }) => handle_error_module(
+                    module,
+                    total_time,
+                    spec_path.as_os_str(),
+                    true,
+                    RenderTarget::ColorTerminal,
+                ),
+                Err(BuildFileError::LoadingProblem(problem)) => {
+                    handle_loading_problem(problem, RenderTarget::ColorTerminal)
                 }
-                Err(BuildFileError::LoadingProblem(problem)) => handle_loading_problem(problem),
+                Err(BuildFileError::Io(err)) => Err(err),
             }
         }
-        Err(err) => match err.kind() {
-            ErrorKind::NotFound => {
-                eprintln!("Platform module file not found: {}", input_path.display());
-                process::exit(1);
-            }
-            error => {
+        Err(err) => report_load_error(input_path, err),
+    }
+}
+
+/// Glue specs which are built into the compiler, so they don't need to be compiled from Roc code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinSpec {
+    C,
+    Zig,
+}
+
+impl BuiltinSpec {
+    /// The builtin spec with this name, as it would be passed to `roc glue` instead of a path
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "c" => Some(BuiltinSpec::C),
+            "zig" => Some(BuiltinSpec::Zig),
+            _ => None,
+        }
+    }
+}
+
+pub fn generate_builtin(
+    input_path: &Path,
+    output_path: &Path,
+    spec: BuiltinSpec,
+) -> io::Result<i32> {
+    match load_types(
+        input_path.to_path_buf(),
+        Threading::AllAvailable,
+        IgnoreErrors::NONE,
+    ) {
+        Ok(types) => {
+            let files = match spec {
+                BuiltinSpec::C => c_glue::emit(&types),
+                BuiltinSpec::Zig => zig_glue::emit(&types),
+            };
+
+            write_glue_files(
+                output_path,
+                files
+                    .iter()
+                    .map(|types::File { name, content }| (name.as_str(), content.as_str())),
+            );
+
+            Ok(0)
+        }
+        Err(err) => report_load_error(input_path, err),
+    }
+}
+
+fn report_load_error(input_path: &Path, err: io::Error) -> ! {
+    match err.kind() {
+        ErrorKind::NotFound => {
+            eprintln!("Platform module file not found: {}", input_path.display());
+            process::exit(1);
+        }
+        error => {
+            eprintln!(
+                "Error loading platform module file {} - {:?}",
+                input_path.display(),
+                error
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn write_glue_files<'a>(output_path: &Path, files: impl IntoIterator<Item = (&'a str, &'a str)>) {
+    for (name, content) in files {
+        let valid_name = PathBuf::from(name)
+            .components()
+            .all(|comp| matches!(comp, Component::CurDir | Component::Normal(_)));
+        if !valid_name {
+            eprintln!("File name was invalid: {name}");
+
+            process::exit(1);
+        }
+        let full_path = output_path.join(name);
+        if let Some(dir_path) = full_path.parent() {
+            std::fs::create_dir_all(dir_path).unwrap_or_else(|err| {
                 eprintln!(
-                    "Error loading platform module file {} - {:?}",
-                    input_path.display(),
-                    error
+                    "Unable to create output directory {} - {:?}",
+                    dir_path.display(),
+                    err
                 );
+
                 process::exit(1);
-            }
-        },
+            });
+        }
+        let mut file = File::create(&full_path).unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to create output file {} - {:?}",
+                full_path.display(),
+                err
+            );
+
+            process::exit(1);
+        });
+
+        file.write_all(content.as_bytes()).unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to write bindings to output file {} - {:?}",
+                full_path.display(),
+                err
+            );
+
+            process::exit(1);
+        });
     }
+
+    println!(
+        "🎉 Generated type declarations in:\n\n\t{}",
+        output_path.display()
+    );
 }
 
 fn number_lambda_sets(subs: &Subs, initial: Variable) -> Vec<Variable> {
@@ -405,6 +474,7 @@ pub fn load_types(
         let layout_interner = GlobalLayoutInterner::with_capacity(128, target_info);
         let mut layout_cache = LayoutCache::new(layout_interner.fork(), target_info);
         let mut glue_procs_by_layout = MutMap::default();
+        let mut glue_refcounters_by_layout = MutMap::default();
 
         let mut extern_names = MutMap::default();
 
@@ -455,6 +525,15 @@ pub fn load_types(
 
                     glue_procs_by_layout.insert(layout, names.into_bump_slice());
                 }
+
+                for (layout, refcounters) in answer.refcounters {
+                    let refcounters = Refcounters {
+                        incref: refcounters.incref.as_str(&interns).to_string(),
+                        decref: refcounters.decref.as_str(&interns).to_string(),
+                    };
+
+                    glue_refcounters_by_layout.insert(layout, refcounters);
+                }
             }
         }
 
@@ -463,6 +542,7 @@ pub fn load_types(
             subs,
             arena.alloc(interns),
             glue_procs_by_layout,
+            glue_refcounters_by_layout,
             layout_cache,
             target_info,
             exposed_to_host.clone(),
@@ -65,6 +65,8 @@ pub struct Types {
     /// This is important for declaration order in C; we need to output a
     /// type declaration earlier in the file than where it gets referenced by another type.
     deps: VecMap<TypeId, Vec<TypeId>>,
+    /// The functions the app exposes to incref and decref the types which contain closures
+    refcounters: VecMap<TypeId, Refcounters>,
     target: TargetInfo,
 }
 
@@ -88,6 +90,7 @@ impl Types {
             types_by_name: FnvHashMap::with_capacity_and_hasher(10, Default::default()),
             entry_points: Vec::new(),
             deps: VecMap::with_capacity(cap),
+            refcounters: VecMap::default(),
         }
     }
 
@@ -97,6 +100,7 @@ impl Types {
         subs: &'a Subs,
         interns: &'a Interns,
         glue_procs_by_layout: MutMap<Layout<'a>, &'a [String]>,
+        glue_refcounters_by_layout: MutMap<Layout<'a>, Refcounters>,
         layout_cache: LayoutCache<'a>,
         target: TargetInfo,
         mut entry_points: MutMap<Symbol, Variable>,
@@ -108,6 +112,7 @@ impl Types {
             interns,
             layout_cache.interner,
             glue_procs_by_layout,
+            glue_refcounters_by_layout,
             target,
         );
 
@@ -643,6 +648,68 @@ impl Types {
     pub fn target(&self) -> TargetInfo {
         self.target
     }
+
+    /// The functions the app exposes to incref and decref this type, if it contains closures
+    pub fn refcounters(&self, id: TypeId) -> Option<&Refcounters> {
+        self.refcounters.get(&id)
+    }
+
+    /// Whether values of this type point to (or contain something that points to) a refcounted
+    /// heap allocation, so that hosts need to incref and decref them.
+    ///
+    /// Structs containing closures always report true, because what they capture depends on
+    /// the app. Hosts refcount them with the app's [`Types::refcounters`].
+    pub fn is_refcounted(&self, id: TypeId) -> bool {
+        match self.get_type(id) {
+            RocType::RocStr
+            | RocType::RocList(_)
+            | RocType::RocDict(_, _)
+            | RocType::RocSet(_)
+            | RocType::RocBox(_)
+            | RocType::Unsized
+            | RocType::RecursivePointer(_)
+            | RocType::TagUnion(
+                RocTagUnion::Recursive { .. }
+                | RocTagUnion::NullableWrapped { .. }
+                | RocTagUnion::NullableUnwrapped { .. }
+                | RocTagUnion::NonNullableUnwrapped { .. },
+            )
+            | RocType::Struct {
+                fields: RocStructFields::HasClosure { .. },
+                ..
+            }
+            | RocType::TagUnionPayload {
+                fields: RocStructFields::HasClosure { .. },
+                ..
+            }
+            | RocType::TagUnion(RocTagUnion::SingleTagStruct {
+                payload: RocSingleTagPayload::HasClosure { .. },
+                ..
+            }) => true,
+            RocType::Unit
+            | RocType::EmptyTagUnion
+            | RocType::Bool
+            | RocType::Num(_)
+            | RocType::TagUnion(RocTagUnion::Enumeration { .. }) => false,
+            RocType::Struct {
+                fields: RocStructFields::HasNoClosure { fields },
+                ..
+            }
+            | RocType::TagUnionPayload {
+                fields: RocStructFields::HasNoClosure { fields },
+                ..
+            } => fields.iter().any(|(_, id)| self.is_refcounted(*id)),
+            RocType::TagUnion(RocTagUnion::SingleTagStruct {
+                payload: RocSingleTagPayload::HasNoClosure { payload_fields },
+                ..
+            }) => payload_fields.iter().any(|id| self.is_refcounted(*id)),
+            RocType::TagUnion(RocTagUnion::NonRecursive { tags, .. }) => tags
+                .iter()
+                .any(|(_, payload)| matches!(payload, Some(id) if self.is_refcounted(*id))),
+            RocType::RocResult(ok, err) => self.is_refcounted(*ok) || self.is_refcounted(*err),
+            RocType::Function(roc_fn) => self.is_refcounted(roc_fn.lambda_set),
+        }
+    }
 }
 
 impl From<&Types> for roc_type::Types {
@@ -943,6 +1010,14 @@ enum RocTypeOrPending<'a> {
     Pending,
 }
 
+/// The names of the functions an app exposes to incref and decref a type which contains
+/// closures, since only the app knows its layout. Both take a pointer to the value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Refcounters {
+    pub incref: String,
+    pub decref: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Accessors {
     // The name of the extern
@@ -1165,6 +1240,7 @@ struct Env<'a> {
     subs: &'a Subs,
     layout_cache: LayoutCache<'a>,
     glue_procs_by_layout: MutMap<Layout<'a>, &'a [String]>,
+    glue_refcounters_by_layout: MutMap<Layout<'a>, Refcounters>,
     lambda_set_ids: MutMap<Variable, LambdaSetId>,
     interns: &'a Interns,
     struct_names: Structs,
@@ -1180,6 +1256,7 @@ impl<'a> Env<'a> {
         interns: &'a Interns,
         layout_interner: TLLayoutInterner<'a>,
         glue_procs_by_layout: MutMap<Layout<'a>, &'a [String]>,
+        glue_refcounters_by_layout: MutMap<Layout<'a>, Refcounters>,
         target: TargetInfo,
     ) -> Self {
         Env {
@@ -1191,11 +1268,21 @@ impl<'a> Env<'a> {
             pending_recursive_types: Default::default(),
             known_recursive_types: Default::default(),
             glue_procs_by_layout,
+            glue_refcounters_by_layout,
             lambda_set_ids: Default::default(),
             layout_cache: LayoutCache::new(layout_interner, target),
         }
     }
 
+    /// Records the functions the app exposes to incref and decref this type, if it has any
+    fn add_refcounters(&self, type_id: TypeId, in_layout: InLayout<'a>, types: &mut Types) {
+        let layout = self.layout_cache.interner.get(in_layout);
+
+        if let Some(refcounters) = self.glue_refcounters_by_layout.get(&layout) {
+            types.refcounters.insert(type_id, refcounters.clone());
+        }
+    }
+
     fn resolve_pending_recursive_types(&mut self, types: &mut Types) {
         // TODO if VecMap gets a drain() method, use that instead of doing take() and into_iter
         let pending = core::mem::take(&mut self.pending_recursive_types);
@@ -1873,12 +1960,16 @@ where
         }
     };
 
-    types.add_named(
+    let type_id = types.add_named(
         &env.layout_cache.interner,
         name.clone(),
         to_type(name, struct_fields),
         in_layout,
-    )
+    );
+
+    env.add_refcounters(type_id, in_layout, types);
+
+    type_id
 }
 
 trait UnionTag: Label + std::fmt::Debug {
@@ -2132,6 +2223,8 @@ fn add_tag_union<'a>(
     let typ = RocType::TagUnion(tag_union_type);
     let type_id = types.add_named(&env.layout_cache.interner, name, typ, layout);
 
+    env.add_refcounters(type_id, layout, types);
+
     if let Some(rec_var) = rec_root {
         env.known_recursive_types.insert(rec_var, type_id);
     }
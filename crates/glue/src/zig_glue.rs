@@ -0,0 +1,1038 @@
+use crate::types::{
+    File, RocFn, RocNum, RocSingleTagPayload, RocStructFields, RocTagUnion, RocType, TypeId, Types,
+};
+use roc_target::Architecture;
+use std::fmt::Write;
+
+pub static HEADER: &[u8] = include_bytes!("../templates/header.zig");
+pub static ROC_STD: &[u8] = include_bytes!("../templates/roc_std.zig");
+const INDENT: &str = "    ";
+
+const RESERVED_KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anytype",
+    "asm",
+    "async",
+    "await",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "fn",
+    "for",
+    "if",
+    "inline",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "try",
+    "type",
+    "union",
+    "unreachable",
+    "usingnamespace",
+    "var",
+    "volatile",
+    "while",
+];
+
+pub fn emit(types: &[Types]) -> Vec<File> {
+    let mut buf = std::str::from_utf8(HEADER).unwrap().to_string();
+
+    // Sizes and alignments vary by target, so each target gets its own declarations,
+    // and targets whose declarations come out the same share them.
+    let mut decls_by_target: Vec<(String, Vec<Architecture>)> = Vec::new();
+
+    for types in types {
+        let decls = declarations(types);
+        let architecture = types.target().architecture;
+
+        match decls_by_target
+            .iter_mut()
+            .find(|(existing, _)| *existing == decls)
+        {
+            Some((_, architectures)) => architectures.push(architecture),
+            None => decls_by_target.push((decls, vec![architecture])),
+        }
+    }
+
+    match decls_by_target.as_slice() {
+        [] => {}
+        [(decls, _)] => buf.push_str(decls),
+        _ => {
+            buf.push_str("\npub usingnamespace switch (builtin.cpu.arch) {\n");
+
+            for (decls, architectures) in decls_by_target.iter() {
+                let arches: Vec<_> = architectures
+                    .iter()
+                    .map(|architecture| arch_name(*architecture))
+                    .collect();
+
+                writeln!(buf, "{INDENT}{} => struct {{", arches.join(", ")).unwrap();
+
+                for line in decls.lines().skip_while(|line| line.is_empty()) {
+                    if line.is_empty() {
+                        buf.push('\n');
+                    } else {
+                        writeln!(buf, "{INDENT}{INDENT}{line}").unwrap();
+                    }
+                }
+
+                writeln!(buf, "{INDENT}}},").unwrap();
+            }
+
+            writeln!(
+                buf,
+                "{INDENT}else => @compileError(\"roc glue did not generate declarations for this target\"),\n}};"
+            )
+            .unwrap();
+        }
+    }
+
+    vec![
+        File {
+            name: "roc_app.zig".to_string(),
+            content: buf,
+        },
+        File {
+            name: "roc_std.zig".to_string(),
+            content: std::str::from_utf8(ROC_STD).unwrap().to_string(),
+        },
+    ]
+}
+
+fn declarations(types: &Types) -> String {
+    let mut buf = String::new();
+
+    for id in types.sorted_ids() {
+        add_type(id, types, &mut buf);
+    }
+
+    for (name, id) in types.entry_points() {
+        add_entry_point(name, *id, types, &mut buf);
+    }
+
+    buf
+}
+
+fn add_type(id: TypeId, types: &Types, buf: &mut String) {
+    match types.get_type(id) {
+        RocType::Struct { name, fields } => add_struct(name, id, fields, false, types, buf),
+        RocType::TagUnionPayload { name, fields } => add_struct(name, id, fields, true, types, buf),
+        RocType::TagUnion(tag_union) => match tag_union {
+            RocTagUnion::Enumeration { name, tags, size } => {
+                write!(buf, "\npub const {} = ", escape_kw(name)).unwrap();
+                add_enum_body(tags, *size, "", buf);
+                buf.push_str(";\n");
+            }
+            RocTagUnion::NonRecursive {
+                name,
+                tags,
+                discriminant_offset,
+                discriminant_size,
+            } => {
+                // Empty tag unions can never come up at runtime,
+                // and so don't need declared types.
+                if !tags.is_empty() {
+                    add_non_recursive_tag_union(
+                        name,
+                        id,
+                        tags,
+                        *discriminant_size,
+                        *discriminant_offset,
+                        types,
+                        buf,
+                    )
+                }
+            }
+            RocTagUnion::Recursive {
+                name,
+                tags,
+                discriminant_offset,
+                discriminant_size,
+            } => {
+                if !tags.is_empty() {
+                    add_recursive_tag_union(
+                        name,
+                        id,
+                        tags,
+                        None,
+                        *discriminant_size,
+                        *discriminant_offset,
+                        types,
+                        buf,
+                    )
+                }
+            }
+            RocTagUnion::NullableWrapped {
+                name,
+                index_of_null_tag,
+                tags,
+                discriminant_size,
+                discriminant_offset,
+            } => add_recursive_tag_union(
+                name,
+                id,
+                tags,
+                Some(*index_of_null_tag as usize),
+                *discriminant_size,
+                *discriminant_offset,
+                types,
+                buf,
+            ),
+            RocTagUnion::NullableUnwrapped {
+                name,
+                null_tag,
+                non_null_tag,
+                non_null_payload,
+                null_represents_first_tag: _,
+            } => add_unwrapped_tag_union(
+                name,
+                id,
+                Some(null_tag),
+                non_null_tag,
+                *non_null_payload,
+                types,
+                buf,
+            ),
+            RocTagUnion::NonNullableUnwrapped {
+                name,
+                tag_name,
+                payload,
+            } => add_unwrapped_tag_union(name, id, None, tag_name, *payload, types, buf),
+            RocTagUnion::SingleTagStruct {
+                name,
+                tag_name: _,
+                payload,
+            } => match payload {
+                RocSingleTagPayload::HasNoClosure { payload_fields } => {
+                    let fields: Vec<_> = payload_fields
+                        .iter()
+                        .enumerate()
+                        .map(|(index, id)| (format!("f{index}"), *id))
+                        .collect();
+
+                    add_struct_help(&escape_kw(name), id, &fields, types, buf);
+                }
+                RocSingleTagPayload::HasClosure { payload_getters } => {
+                    let getters = payload_getters.iter().map(|(_, getter)| getter.as_str());
+
+                    add_opaque_struct(&escape_kw(name), id, getters, types, buf);
+                }
+            },
+        },
+        RocType::Function(roc_fn) => {
+            // Top-level functions are the entry points, which are added separately.
+            if !roc_fn.is_toplevel {
+                add_function(id, roc_fn, types, buf)
+            }
+        }
+        // These are all declared in roc_std.zig
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocResult(_, _)
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::Unsized => {}
+        RocType::RecursivePointer { .. } => {
+            // This is recursively pointing to a type that should already have been added,
+            // so no extra work needs to happen.
+        }
+    }
+}
+
+fn add_struct(
+    name: &str,
+    id: TypeId,
+    fields: &RocStructFields,
+    is_tag_union_payload: bool,
+    types: &Types,
+    buf: &mut String,
+) {
+    let name = escape_kw(name);
+
+    match fields {
+        RocStructFields::HasNoClosure { fields } => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(label, id)| {
+                    // Tag union payloads have numbered fields, so we prefix them with an "f"
+                    // so that they can be accessed without @"" quoting.
+                    let label = if is_tag_union_payload {
+                        format!("f{label}")
+                    } else {
+                        escape_kw(label)
+                    };
+
+                    (label, *id)
+                })
+                .collect();
+
+            add_struct_help(&name, id, &fields, types, buf);
+        }
+        RocStructFields::HasClosure { fields } => {
+            let getters = fields
+                .iter()
+                .map(|(_, _, accessors)| accessors.getter.as_str());
+
+            add_opaque_struct(&name, id, getters, types, buf);
+        }
+    }
+}
+
+fn add_struct_help(
+    name: &str,
+    id: TypeId,
+    fields: &[(String, TypeId)],
+    types: &Types,
+    buf: &mut String,
+) {
+    write!(buf, "\npub const {name} = extern struct {{\n").unwrap();
+
+    for (label, field_id) in fields {
+        writeln!(buf, "{INDENT}{label}: {},", type_name(*field_id, types)).unwrap();
+    }
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let mut body = String::new();
+
+            for (label, field_id) in fields {
+                refcount_code(op, *field_id, &format!("self.{label}"), 2, types, &mut body);
+            }
+
+            add_method(&refcount_signature(op, name), &body, buf);
+        }
+    }
+
+    buf.push_str("};\n");
+
+    // The sizes reported for the payloads of recursive tag unions don't include their
+    // recursive fields, so they can't be checked.
+    if !matches!(types.get_type(id), RocType::TagUnionPayload { .. }) {
+        assert_layout(name, id, types, buf);
+    }
+}
+
+/// Structs containing closures have a size and layout that depend on the app,
+/// so hosts can only use them through pointers.
+fn add_opaque_struct<'a>(
+    name: &str,
+    id: TypeId,
+    getters: impl Iterator<Item = &'a str>,
+    types: &Types,
+    buf: &mut String,
+) {
+    let getters: Vec<_> = getters.collect();
+
+    write!(
+        buf,
+        "\n// {name} contains a closure, so its size and layout depend on the app.\n// The app exposes these functions to read its fields: {}\npub const {name} = opaque {{\n",
+        getters.join(", ")
+    )
+    .unwrap();
+
+    // Whatever the closures capture is only known to the app, so it increfs and decrefs them
+    let Some(refcounters) = types.refcounters(id) else {
+        unreachable!("{name} contains a closure, so the app exposes refcounters for it")
+    };
+
+    for (op, extern_name) in [
+        (Refcount::Incref, &refcounters.incref),
+        (Refcount::Decref, &refcounters.decref),
+    ] {
+        writeln!(
+            buf,
+            "{INDENT}extern fn {extern_name}(self: *const {name}) callconv(.C) void;"
+        )
+        .unwrap();
+
+        add_method(
+            &refcount_signature(op, name),
+            &format!("{INDENT}{INDENT}{extern_name}(self);\n"),
+            buf,
+        );
+    }
+
+    buf.push_str("};\n");
+}
+
+/// The body of an `enum`, starting from `enum(uN)` and ending with its closing brace.
+fn add_enum_body<S: AsRef<str>>(tags: &[S], size: u32, indent: &str, buf: &mut String) {
+    writeln!(buf, "enum(u{}) {{", size * 8).unwrap();
+
+    for tag in tags {
+        writeln!(buf, "{indent}{INDENT}{},", escape_kw(tag.as_ref())).unwrap();
+    }
+
+    write!(buf, "{indent}}}").unwrap();
+}
+
+fn add_discriminant(tags: &[(String, Option<TypeId>)], size: u32, buf: &mut String) {
+    let tag_names: Vec<_> = tags.iter().map(|(tag_name, _)| tag_name).collect();
+
+    buf.push_str("\n    pub const Discriminant = ");
+    // Recursive tag unions which store their discriminant in the pointer report a size of 0
+    add_enum_body(&tag_names, size.max(1), INDENT, buf);
+    buf.push_str(";\n");
+}
+
+fn add_non_recursive_tag_union(
+    name: &str,
+    id: TypeId,
+    tags: &[(String, Option<TypeId>)],
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    types: &Types,
+    buf: &mut String,
+) {
+    let name = escape_kw(name);
+    let int = format!("u{}", discriminant_size * 8);
+
+    // As in the Rust glue, the whole tag union is an extern union, and the discriminant lives in
+    // its bytes at discriminant_offset, because Roc puts it in the padding after the payloads
+    // whenever it can.
+    write!(buf, "\npub const {name} = extern union {{\n").unwrap();
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            writeln!(
+                buf,
+                "{INDENT}{}: {},",
+                escape_kw(tag_name),
+                type_name(*payload_id, types)
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(
+        buf,
+        "{INDENT}_sizer: [{}]u8,",
+        types.size_rounded_to_alignment(id)
+    )
+    .unwrap();
+
+    add_discriminant(tags, discriminant_size, buf);
+
+    add_method(
+        &format!("pub fn discriminant(self: *const {name}) Discriminant"),
+        &format!(
+            "{INDENT}{INDENT}const bytes = @ptrCast([*]const u8, self);\n\n{INDENT}{INDENT}return @intToEnum(Discriminant, @ptrCast(*align(1) const {int}, bytes + {discriminant_offset}).*);\n"
+        ),
+        buf,
+    );
+
+    add_method(
+        &format!("fn setDiscriminant(self: *{name}, tag: Discriminant) void"),
+        &format!(
+            "{INDENT}{INDENT}const bytes = @ptrCast([*]u8, self);\n\n{INDENT}{INDENT}@ptrCast(*align(1) {int}, bytes + {discriminant_offset}).* = @enumToInt(tag);\n"
+        ),
+        buf,
+    );
+
+    for (tag_name, opt_payload_id) in tags {
+        let (params, set_payload) = match opt_payload_id {
+            Some(payload_id) => (
+                format!("payload: {}", type_name(*payload_id, types)),
+                format!("{INDENT}{INDENT}self.{} = payload;\n", escape_kw(tag_name)),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        add_method(
+            &format!("pub fn make{tag_name}({params}) {name}"),
+            &format!(
+                "{INDENT}{INDENT}var self = {name}{{ ._sizer = [_]u8{{0}} ** {} }};\n{set_payload}{INDENT}{INDENT}self.setDiscriminant(.{});\n\n{INDENT}{INDENT}return self;\n",
+                types.size_rounded_to_alignment(id),
+                escape_kw(tag_name)
+            ),
+            buf,
+        );
+    }
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let body = tag_switch(op, "self.discriminant()", tags, "self.", 2, types);
+
+            add_method(&refcount_signature(op, &name), &body, buf);
+        }
+    }
+
+    buf.push_str("};\n");
+    assert_layout(&name, id, types, buf);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_recursive_tag_union(
+    name: &str,
+    id: TypeId,
+    tags: &[(String, Option<TypeId>)],
+    null_tag_index: Option<usize>,
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    types: &Types,
+    buf: &mut String,
+) {
+    let name = escape_kw(name);
+
+    // Roc stores the discriminant in the unused low bits of the pointer, as long as there are
+    // few enough (non-null) tags for it to fit. Otherwise it goes in the heap allocation.
+    let ptr_size = types.target().ptr_size();
+    let non_null_tags = tags.len() - null_tag_index.iter().count();
+    let discriminant_in_pointer = non_null_tags < ptr_size;
+    let bitmask = ptr_size - 1;
+    let int = format!("u{}", discriminant_size.max(1) * 8);
+
+    write!(
+        buf,
+        "\npub const {name} = extern struct {{\n{INDENT}pointer: ?*anyopaque,\n\n{INDENT}pub const Union = extern union {{\n"
+    )
+    .unwrap();
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            writeln!(
+                buf,
+                "{INDENT}{INDENT}{}: {},",
+                escape_kw(tag_name),
+                type_name(*payload_id, types)
+            )
+            .unwrap();
+        }
+    }
+
+    if !discriminant_in_pointer {
+        writeln!(
+            buf,
+            "{INDENT}{INDENT}_sizer: [{}]u8,",
+            discriminant_offset + discriminant_size
+        )
+        .unwrap();
+    } else if tags.iter().all(|(_, payload)| payload.is_none()) {
+        writeln!(buf, "{INDENT}{INDENT}_unused: u8,").unwrap();
+    }
+
+    writeln!(buf, "{INDENT}}};").unwrap();
+
+    add_discriminant(tags, discriminant_size, buf);
+
+    let union_pointer = if discriminant_in_pointer {
+        format!(
+            "{INDENT}{INDENT}// The discriminant is stored in the unused bits at the end of the pointer\n{INDENT}{INDENT}return @intToPtr(?*Union, @ptrToInt(self.pointer) & ~@as(usize, {bitmask:#b}));\n"
+        )
+    } else {
+        format!("{INDENT}{INDENT}return @ptrCast(?*Union, @alignCast(@alignOf(Union), self.pointer));\n")
+    };
+
+    add_method(
+        &format!("pub fn unionPointer(self: {name}) ?*Union"),
+        &union_pointer,
+        buf,
+    );
+
+    let mut discriminant = String::new();
+
+    if let Some(null_tag_index) = null_tag_index {
+        let null_tag = escape_kw(&tags[null_tag_index].0);
+
+        write!(
+            discriminant,
+            "{INDENT}{INDENT}if (self.pointer == null) {{\n{INDENT}{INDENT}{INDENT}return .{null_tag};\n{INDENT}{INDENT}}}\n\n"
+        )
+        .unwrap();
+    }
+
+    if discriminant_in_pointer {
+        writeln!(
+            discriminant,
+            "{INDENT}{INDENT}return @intToEnum(Discriminant, @truncate({int}, @ptrToInt(self.pointer) & {bitmask:#b}));"
+        )
+        .unwrap();
+    } else {
+        write!(
+            discriminant,
+            "{INDENT}{INDENT}const bytes = @ptrCast([*]const u8, self.pointer.?);\n\n{INDENT}{INDENT}return @intToEnum(Discriminant, @ptrCast(*align(1) const {int}, bytes + {discriminant_offset}).*);\n"
+        )
+        .unwrap();
+    }
+
+    add_method(
+        &format!("pub fn discriminant(self: {name}) Discriminant"),
+        &discriminant,
+        buf,
+    );
+
+    for (index, (tag_name, opt_payload_id)) in tags.iter().enumerate() {
+        let tag = escape_kw(tag_name);
+
+        if Some(index) == null_tag_index {
+            add_method(
+                &format!("pub fn make{tag_name}() {name}"),
+                &format!("{INDENT}{INDENT}return {name}{{ .pointer = null }};\n"),
+                buf,
+            );
+
+            continue;
+        }
+
+        let mut body = format!(
+            "{INDENT}{INDENT}const pointer = @ptrCast(*Union, @alignCast(@alignOf(Union), roc_std.allocRefcounted(@sizeOf(Union), @alignOf(Union))));\n"
+        );
+
+        let params = match opt_payload_id {
+            Some(payload_id) => {
+                writeln!(body, "{INDENT}{INDENT}pointer.{tag} = payload;").unwrap();
+
+                format!("payload: {}", type_name(*payload_id, types))
+            }
+            None => String::new(),
+        };
+
+        if discriminant_in_pointer {
+            write!(
+                body,
+                "\n{INDENT}{INDENT}return {name}{{ .pointer = @intToPtr(?*anyopaque, @ptrToInt(pointer) | @enumToInt(Discriminant.{tag})) }};\n"
+            )
+            .unwrap();
+        } else {
+            write!(
+                body,
+                "{INDENT}{INDENT}@ptrCast(*align(1) {int}, @ptrCast([*]u8, pointer) + {discriminant_offset}).* = @enumToInt(Discriminant.{tag});\n\n{INDENT}{INDENT}return {name}{{ .pointer = pointer }};\n"
+            )
+            .unwrap();
+        }
+
+        add_method(
+            &format!("pub fn make{tag_name}({params}) {name}"),
+            &body,
+            buf,
+        );
+    }
+
+    add_method(
+        &refcount_signature(Refcount::Incref, &name),
+        &format!("{INDENT}{INDENT}roc_std.increfPtr(self.unionPointer());\n"),
+        buf,
+    );
+
+    let mut decref = format!(
+        "{INDENT}{INDENT}const pointer = self.unionPointer() orelse return;\n\n{INDENT}{INDENT}if (roc_std.releasePtr(pointer)) {{\n"
+    );
+    decref.push_str(&tag_switch(
+        Refcount::Decref,
+        "self.discriminant()",
+        tags,
+        "pointer.",
+        3,
+        types,
+    ));
+    write!(
+        decref,
+        "\n{INDENT}{INDENT}{INDENT}roc_std.freePtr(pointer, @alignOf(Union));\n{INDENT}{INDENT}}}\n"
+    )
+    .unwrap();
+
+    add_method(&refcount_signature(Refcount::Decref, &name), &decref, buf);
+
+    buf.push_str("};\n");
+    assert_layout(&name, id, types, buf);
+}
+
+/// A recursive tag union with only one tag that has a payload, so the pointer doesn't need a
+/// discriminant. If there's another tag, it has no payload and is represented by null.
+fn add_unwrapped_tag_union(
+    name: &str,
+    id: TypeId,
+    null_tag: Option<&str>,
+    non_null_tag: &str,
+    payload_id: TypeId,
+    types: &Types,
+    buf: &mut String,
+) {
+    let name = escape_kw(name);
+    let payload_name = type_name(payload_id, types);
+
+    write!(
+        buf,
+        "\npub const {name} = extern struct {{\n{INDENT}pointer: ?*{payload_name},\n"
+    )
+    .unwrap();
+
+    if let Some(null_tag) = null_tag {
+        let mut tag_names = [null_tag, non_null_tag];
+        tag_names.sort();
+
+        buf.push_str("\n    pub const Discriminant = ");
+        add_enum_body(&tag_names, 1, INDENT, buf);
+        buf.push_str(";\n");
+
+        add_method(
+            &format!("pub fn discriminant(self: {name}) Discriminant"),
+            &format!(
+                "{INDENT}{INDENT}return if (self.pointer == null) .{} else .{};\n",
+                escape_kw(null_tag),
+                escape_kw(non_null_tag)
+            ),
+            buf,
+        );
+
+        add_method(
+            &format!("pub fn make{null_tag}() {name}"),
+            &format!("{INDENT}{INDENT}return {name}{{ .pointer = null }};\n"),
+            buf,
+        );
+    }
+
+    add_method(
+        &format!("pub fn make{non_null_tag}(payload: {payload_name}) {name}"),
+        &format!(
+            "{INDENT}{INDENT}const pointer = @ptrCast(*{payload_name}, @alignCast(@alignOf({payload_name}), roc_std.allocRefcounted(@sizeOf({payload_name}), @alignOf({payload_name}))));\n{INDENT}{INDENT}pointer.* = payload;\n\n{INDENT}{INDENT}return {name}{{ .pointer = pointer }};\n"
+        ),
+        buf,
+    );
+
+    add_method(
+        &refcount_signature(Refcount::Incref, &name),
+        &format!("{INDENT}{INDENT}roc_std.increfPtr(self.pointer);\n"),
+        buf,
+    );
+
+    let mut decref = format!(
+        "{INDENT}{INDENT}const pointer = self.pointer orelse return;\n\n{INDENT}{INDENT}if (roc_std.releasePtr(pointer)) {{\n"
+    );
+    refcount_code(
+        Refcount::Decref,
+        payload_id,
+        "pointer",
+        3,
+        types,
+        &mut decref,
+    );
+    write!(
+        decref,
+        "{INDENT}{INDENT}{INDENT}roc_std.freePtr(pointer, @alignOf({payload_name}));\n{INDENT}{INDENT}}}\n"
+    )
+    .unwrap();
+
+    add_method(&refcount_signature(Refcount::Decref, &name), &decref, buf);
+
+    buf.push_str("};\n");
+    assert_layout(&name, id, types, buf);
+}
+
+fn add_function(id: TypeId, roc_fn: &RocFn, types: &Types, buf: &mut String) {
+    let name = escape_kw(&roc_fn.function_name);
+    let extern_name = &roc_fn.extern_name;
+    let ret = type_name(roc_fn.ret, types);
+
+    // extern_name(arg0: *const Arg0, ..., closure_data: *anyopaque, output: *Ret);
+    let mut extern_params: Vec<_> = roc_fn
+        .args
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| format!("arg{index}: *const {}", type_name(*arg, types)))
+        .collect();
+
+    if extern_params.is_empty() {
+        // These always have a first argument that's a pointer, even if it's to nothing.
+        extern_params.push("arg0: *const roc_std.RocUnit".to_string());
+    }
+
+    write!(
+        buf,
+        "\nextern fn {extern_name}({}, closure_data: *anyopaque, output: *{ret}) callconv(.C) void;\n",
+        extern_params.join(", ")
+    )
+    .unwrap();
+
+    write!(
+        buf,
+        "\npub const {name} = extern struct {{\n{INDENT}closure_data: {},\n",
+        type_name(roc_fn.lambda_set, types)
+    )
+    .unwrap();
+
+    let mut params = vec![format!("self: *{name}")];
+    let mut args = Vec::new();
+    let mut body = String::new();
+
+    for (index, arg) in roc_fn.args.iter().enumerate() {
+        params.push(format!("arg{index}: {}", type_name(*arg, types)));
+        args.push(format!("&arg{index}"));
+    }
+
+    if args.is_empty() {
+        writeln!(body, "{INDENT}{INDENT}const unit: roc_std.RocUnit = 0;").unwrap();
+        args.push("&unit".to_string());
+    }
+
+    write!(
+        body,
+        "{INDENT}{INDENT}var output: {ret} = undefined;\n{INDENT}{INDENT}{extern_name}({}, &self.closure_data, &output);\n\n{INDENT}{INDENT}return output;\n",
+        args.join(", ")
+    )
+    .unwrap();
+
+    add_method(
+        &format!("pub fn call({}) {ret}", params.join(", ")),
+        &body,
+        buf,
+    );
+
+    if types.is_refcounted(id) {
+        for op in [Refcount::Incref, Refcount::Decref] {
+            let mut body = String::new();
+
+            refcount_code(
+                op,
+                roc_fn.lambda_set,
+                "self.closure_data",
+                2,
+                types,
+                &mut body,
+            );
+
+            add_method(&refcount_signature(op, &name), &body, buf);
+        }
+    }
+
+    buf.push_str("};\n");
+    assert_layout(&name, id, types, buf);
+}
+
+fn add_entry_point(name: &str, id: TypeId, types: &Types, buf: &mut String) {
+    let (args, ret) = match types.get_type(id) {
+        RocType::Function(roc_fn) => (roc_fn.args.as_slice(), roc_fn.ret),
+        _ => (&[][..], id),
+    };
+    let ret = type_name(ret, types);
+
+    let mut extern_params = vec![format!("output: *{ret}")];
+    let mut params = Vec::new();
+    let mut call_args = vec!["&output".to_string()];
+
+    for (index, arg) in args.iter().enumerate() {
+        let arg_type = type_name(*arg, types);
+
+        // Values which own heap allocations are passed by pointer
+        if types.is_refcounted(*arg) {
+            extern_params.push(format!("arg{index}: *{arg_type}"));
+            call_args.push(format!("&arg{index}"));
+            params.push(format!("arg{index}_: {arg_type}"));
+        } else {
+            extern_params.push(format!("arg{index}: {arg_type}"));
+            call_args.push(format!("arg{index}"));
+            params.push(format!("arg{index}: {arg_type}"));
+        }
+    }
+
+    write!(
+        buf,
+        "\nextern fn roc__{name}_1_exposed_generic({}) callconv(.C) void;\n\npub fn {}({}) {ret} {{\n",
+        extern_params.join(", "),
+        escape_kw(name),
+        params.join(", ")
+    )
+    .unwrap();
+
+    // Parameters are immutable, so copy the ones which are passed by pointer into variables.
+    for (index, arg) in args.iter().enumerate() {
+        if types.is_refcounted(*arg) {
+            writeln!(buf, "{INDENT}var arg{index} = arg{index}_;").unwrap();
+        }
+    }
+
+    write!(
+        buf,
+        "{INDENT}var output: {ret} = undefined;\n{INDENT}roc__{name}_1_exposed_generic({});\n\n{INDENT}return output;\n}}\n",
+        call_args.join(", ")
+    )
+    .unwrap();
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Refcount {
+    Incref,
+    Decref,
+}
+
+fn refcount_signature(op: Refcount, name: &str) -> String {
+    match op {
+        Refcount::Incref => format!("pub fn incref(self: *const {name}) void"),
+        Refcount::Decref => format!("pub fn decref(self: *{name}) void"),
+    }
+}
+
+/// A statement which increfs or decrefs the value at `lvalue`, if it's refcounted.
+/// Every refcounted type has `incref` and `decref` methods, including the ones in roc_std.zig.
+fn refcount_code(
+    op: Refcount,
+    id: TypeId,
+    lvalue: &str,
+    indent: usize,
+    types: &Types,
+    buf: &mut String,
+) {
+    if types.is_refcounted(id) {
+        let method = match op {
+            Refcount::Incref => "incref",
+            Refcount::Decref => "decref",
+        };
+
+        writeln!(buf, "{}{lvalue}.{method}();", INDENT.repeat(indent)).unwrap();
+    }
+}
+
+/// A switch statement which increfs or decrefs the payload of whichever tag is active
+fn tag_switch(
+    op: Refcount,
+    discriminant: &str,
+    tags: &[(String, Option<TypeId>)],
+    payload_prefix: &str,
+    indent: usize,
+    types: &Types,
+) -> String {
+    let indentation = INDENT.repeat(indent);
+    let mut buf = format!("{indentation}switch ({discriminant}) {{\n");
+    let mut handled = 0;
+
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            if types.is_refcounted(*payload_id) {
+                let tag = escape_kw(tag_name);
+
+                writeln!(buf, "{indentation}{INDENT}.{tag} => {{").unwrap();
+                refcount_code(
+                    op,
+                    *payload_id,
+                    &format!("{payload_prefix}{tag}"),
+                    indent + 2,
+                    types,
+                    &mut buf,
+                );
+                writeln!(buf, "{indentation}{INDENT}}},").unwrap();
+
+                handled += 1;
+            }
+        }
+    }
+
+    // Zig rejects an else prong when every tag is already handled
+    if handled < tags.len() {
+        writeln!(buf, "{indentation}{INDENT}else => {{}},").unwrap();
+    }
+
+    writeln!(buf, "{indentation}}}").unwrap();
+
+    buf
+}
+
+fn add_method(signature: &str, body: &str, buf: &mut String) {
+    write!(buf, "\n{INDENT}{signature} {{\n{body}{INDENT}}}\n").unwrap();
+}
+
+fn assert_layout(name: &str, id: TypeId, types: &Types, buf: &mut String) {
+    write!(
+        buf,
+        "\ncomptime {{\n{INDENT}std.debug.assert(@sizeOf({name}) == {});\n{INDENT}std.debug.assert(@alignOf({name}) == {});\n}}\n",
+        types.size_rounded_to_alignment(id),
+        types.align(id)
+    )
+    .unwrap();
+}
+
+fn type_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "roc_std.RocUnit".to_string(),
+        RocType::RocStr => "roc_std.RocStr".to_string(),
+        RocType::Bool => "bool".to_string(),
+        RocType::Num(RocNum::U8) => "u8".to_string(),
+        RocType::Num(RocNum::U16) => "u16".to_string(),
+        RocType::Num(RocNum::U32) => "u32".to_string(),
+        RocType::Num(RocNum::U64) => "u64".to_string(),
+        RocType::Num(RocNum::U128) => "u128".to_string(),
+        RocType::Num(RocNum::I8) => "i8".to_string(),
+        RocType::Num(RocNum::I16) => "i16".to_string(),
+        RocType::Num(RocNum::I32) => "i32".to_string(),
+        RocType::Num(RocNum::I64) => "i64".to_string(),
+        RocType::Num(RocNum::I128) => "i128".to_string(),
+        RocType::Num(RocNum::F32) => "f32".to_string(),
+        RocType::Num(RocNum::F64) => "f64".to_string(),
+        RocType::Num(RocNum::Dec) => "roc_std.RocDec".to_string(),
+        RocType::RocDict(key_id, val_id) => format!(
+            "roc_std.RocDict({}, {})",
+            type_name(*key_id, types),
+            type_name(*val_id, types)
+        ),
+        RocType::RocSet(elem_id) => format!("roc_std.RocSet({})", type_name(*elem_id, types)),
+        RocType::RocList(elem_id) => format!("roc_std.RocList({})", type_name(*elem_id, types)),
+        RocType::RocBox(elem_id) => format!("roc_std.RocBox({})", type_name(*elem_id, types)),
+        RocType::Unsized => "roc_std.RocList(u8)".to_string(),
+        RocType::RocResult(ok_id, err_id) => format!(
+            "roc_std.RocResult({}, {})",
+            type_name(*ok_id, types),
+            type_name(*err_id, types)
+        ),
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::NonRecursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => escape_kw(name),
+        RocType::RecursivePointer(content) => type_name(*content, types),
+        RocType::Function(RocFn { function_name, .. }) => escape_kw(function_name),
+    }
+}
+
+fn arch_name(architecture: Architecture) -> &'static str {
+    match architecture {
+        Architecture::X86_64 => ".x86_64",
+        Architecture::X86_32 => ".x86",
+        Architecture::Aarch64 => ".aarch64",
+        Architecture::Aarch32 => ".arm",
+        Architecture::Wasm32 => ".wasm32",
+    }
+}
+
+/// Escape a Zig reserved keyword, if necessary.
+fn escape_kw(input: &str) -> String {
+    if RESERVED_KEYWORDS.contains(&input) {
+        format!("@\"{input}\"")
+    } else {
+        input.to_string()
+    }
+}
@@ -0,0 +1,9 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+//
+// Constructors take ownership of the values passed to them. To keep using a value after passing
+// it to Roc, call its _incref function first, and call its _decref function once you are done.
+
+#ifndef ROC_APP_H
+#define ROC_APP_H
+
+#include "roc_std.h"
@@ -0,0 +1,8 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+//
+// Constructors take ownership of the values passed to them. To keep using a value after passing
+// it to Roc, call its incref method first, and call its decref method once you are done.
+
+const std = @import("std");
+const builtin = @import("builtin");
+const roc_std = @import("roc_std.zig");
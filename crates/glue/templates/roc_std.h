@@ -0,0 +1,276 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+//
+// C representations of Roc's builtin types, laid out the same way Roc lays them out in memory.
+//
+// The host must provide roc_alloc, roc_realloc and roc_dealloc, just like it does for the app.
+
+#ifndef ROC_STD_H
+#define ROC_STD_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+extern void *roc_alloc(size_t size, uint32_t alignment);
+extern void *roc_realloc(void *ptr, size_t new_size, size_t old_size, uint32_t alignment);
+extern void roc_dealloc(void *ptr, uint32_t alignment);
+
+// Zero-sized values, like `{}`, still take up a byte when they are passed around.
+typedef uint8_t RocUnit;
+
+typedef struct RocI128 {
+    _Alignas(16) uint8_t bytes[16];
+} RocI128;
+
+typedef struct RocU128 {
+    _Alignas(16) uint8_t bytes[16];
+} RocU128;
+
+// A fixed-point decimal: an I128 which is 10^18 times the number it represents.
+typedef struct RocDec {
+    _Alignas(16) uint8_t bytes[16];
+} RocDec;
+
+// Reference counting
+//
+// Every heap allocation starts with a reference count, which sits right before the data that
+// values point to. A refcount of ROC_REFCOUNT_ONE means there is exactly one reference, and
+// each extra reference adds one to it. Readonly allocations (e.g. string literals in the app's
+// binary) have a refcount of zero, and are never freed.
+
+#define ROC_REFCOUNT_READONLY ((intptr_t)0)
+#define ROC_REFCOUNT_ONE INTPTR_MIN
+
+// The top bit of a size_t, which Roc uses to mark small strings and seamless slices
+#define ROC_SIZE_TOP_BIT ((size_t)1 << (sizeof(size_t) * 8 - 1))
+
+static inline uint32_t roc_allocation_alignment(uint32_t element_alignment) {
+    return element_alignment > _Alignof(intptr_t) ? element_alignment : _Alignof(intptr_t);
+}
+
+static inline intptr_t *roc_refcount_ptr(const void *data) {
+    return (intptr_t *)data - 1;
+}
+
+// Allocate `size` bytes with a refcount of one, and return a pointer to the data after the refcount
+static inline void *roc_alloc_refcounted(size_t size, uint32_t element_alignment) {
+    uint32_t alignment = roc_allocation_alignment(element_alignment);
+    uint8_t *allocation = (uint8_t *)roc_alloc(size + alignment, alignment);
+    uint8_t *data = allocation + alignment;
+
+    *roc_refcount_ptr(data) = ROC_REFCOUNT_ONE;
+
+    return data;
+}
+
+// Add a reference to a refcounted allocation. Does nothing for NULL.
+static inline void roc_incref(const void *data) {
+    if (data != NULL) {
+        intptr_t *refcount = roc_refcount_ptr(data);
+
+        if (*refcount != ROC_REFCOUNT_READONLY) {
+            *refcount += 1;
+        }
+    }
+}
+
+// Remove a reference to a refcounted allocation. Returns true if that was the last one, in which
+// case the caller must release whatever the allocation refers to and then call roc_free.
+static inline bool roc_release(const void *data) {
+    if (data == NULL) {
+        return false;
+    }
+
+    intptr_t *refcount = roc_refcount_ptr(data);
+
+    if (*refcount == ROC_REFCOUNT_READONLY) {
+        return false;
+    } else if (*refcount == ROC_REFCOUNT_ONE) {
+        return true;
+    } else {
+        *refcount -= 1;
+
+        return false;
+    }
+}
+
+static inline void roc_free(const void *data, uint32_t element_alignment) {
+    uint32_t alignment = roc_allocation_alignment(element_alignment);
+
+    roc_dealloc((uint8_t *)data - alignment, alignment);
+}
+
+// Str
+
+typedef struct RocStr {
+    uint8_t *bytes;
+    size_t length;
+    size_t capacity_or_ref_ptr;
+} RocStr;
+
+// Strings shorter than this are stored inline, with no heap allocation
+#define ROC_SMALL_STR_CAPACITY (sizeof(RocStr) - 1)
+
+static inline bool roc_str_is_small(const RocStr *str) {
+    return (str->capacity_or_ref_ptr & ROC_SIZE_TOP_BIT) != 0;
+}
+
+static inline bool roc_str_is_seamless_slice(const RocStr *str) {
+    return !roc_str_is_small(str) && (str->length & ROC_SIZE_TOP_BIT) != 0;
+}
+
+static inline size_t roc_str_len(const RocStr *str) {
+    if (roc_str_is_small(str)) {
+        return ((const uint8_t *)str)[sizeof(RocStr) - 1] & 0x7f;
+    } else {
+        return str->length & ~ROC_SIZE_TOP_BIT;
+    }
+}
+
+// The UTF-8 bytes of the string. Note that they are not nul-terminated!
+static inline const uint8_t *roc_str_bytes(const RocStr *str) {
+    if (roc_str_is_small(str)) {
+        return (const uint8_t *)str;
+    } else {
+        return str->bytes;
+    }
+}
+
+// Copy `length` bytes of UTF-8 into a new string
+static inline RocStr roc_str_from_bytes(const uint8_t *bytes, size_t length) {
+    RocStr str;
+
+    if (length <= ROC_SMALL_STR_CAPACITY) {
+        memset(&str, 0, sizeof(str));
+        memcpy(&str, bytes, length);
+        ((uint8_t *)&str)[sizeof(RocStr) - 1] = (uint8_t)length | 0x80;
+    } else {
+        str.bytes = (uint8_t *)roc_alloc_refcounted(length, 1);
+        str.length = length;
+        str.capacity_or_ref_ptr = length;
+        memcpy(str.bytes, bytes, length);
+    }
+
+    return str;
+}
+
+static inline RocStr roc_str_from_cstr(const char *cstr) {
+    return roc_str_from_bytes((const uint8_t *)cstr, strlen(cstr));
+}
+
+// The pointer right after the string's refcount, or NULL if it doesn't have one
+static inline void *roc_str_refcounted_ptr(const RocStr *str) {
+    if (roc_str_is_small(str)) {
+        return NULL;
+    } else if (roc_str_is_seamless_slice(str)) {
+        return (void *)(str->capacity_or_ref_ptr << 1);
+    } else {
+        return str->bytes;
+    }
+}
+
+static inline void roc_str_incref(const RocStr *str) {
+    roc_incref(roc_str_refcounted_ptr(str));
+}
+
+static inline void roc_str_decref(RocStr *str) {
+    void *data = roc_str_refcounted_ptr(str);
+
+    if (roc_release(data)) {
+        roc_free(data, 1);
+    }
+}
+
+// List
+//
+// Lists don't know the type of their elements, so the functions that need to know how they are
+// laid out take their size and alignment.
+
+typedef struct RocList {
+    void *elements;
+    size_t length;
+    size_t capacity_or_ref_ptr;
+} RocList;
+
+static inline bool roc_list_is_seamless_slice(const RocList *list) {
+    return (list->capacity_or_ref_ptr & ROC_SIZE_TOP_BIT) != 0;
+}
+
+static inline size_t roc_list_len(const RocList *list) {
+    return list->length & ~ROC_SIZE_TOP_BIT;
+}
+
+static inline RocList roc_list_with_capacity(size_t capacity, size_t element_size, uint32_t element_alignment) {
+    RocList list;
+
+    list.elements = capacity == 0 ? NULL : roc_alloc_refcounted(capacity * element_size, element_alignment);
+    list.length = 0;
+    list.capacity_or_ref_ptr = capacity;
+
+    return list;
+}
+
+// The pointer right after the list's refcount, or NULL if it doesn't have one
+static inline void *roc_list_refcounted_ptr(const RocList *list) {
+    if (roc_list_is_seamless_slice(list)) {
+        return (void *)(list->capacity_or_ref_ptr << 1);
+    } else {
+        return list->elements;
+    }
+}
+
+static inline void roc_list_incref(const RocList *list) {
+    roc_incref(roc_list_refcounted_ptr(list));
+}
+
+// Returns true if this was the last reference. The caller must then release the elements and
+// call roc_list_free.
+static inline bool roc_list_release(const RocList *list) {
+    return roc_release(roc_list_refcounted_ptr(list));
+}
+
+static inline void roc_list_free(RocList *list, uint32_t element_alignment) {
+    roc_free(roc_list_refcounted_ptr(list), element_alignment);
+}
+
+// For lists whose elements don't need to be released
+static inline void roc_list_decref(RocList *list, uint32_t element_alignment) {
+    if (roc_list_release(list)) {
+        roc_list_free(list, element_alignment);
+    }
+}
+
+// Dicts and Sets are lists of their entries. A Dict's entries have the key first, unless the
+// value has a larger alignment than the key.
+typedef RocList RocDict;
+typedef RocList RocSet;
+
+// Box
+
+typedef struct RocBox {
+    void *contents;
+} RocBox;
+
+static inline void roc_box_incref(const RocBox *box) {
+    roc_incref(box->contents);
+}
+
+// Returns true if this was the last reference. The caller must then release the contents and
+// call roc_box_free.
+static inline bool roc_box_release(const RocBox *box) {
+    return roc_release(box->contents);
+}
+
+static inline void roc_box_free(RocBox *box, uint32_t contents_alignment) {
+    roc_free(box->contents, contents_alignment);
+}
+
+// For boxes whose contents don't need to be released
+static inline void roc_box_decref(RocBox *box, uint32_t contents_alignment) {
+    if (roc_box_release(box)) {
+        roc_box_free(box, contents_alignment);
+    }
+}
+
+#endif // ROC_STD_H
@@ -0,0 +1,361 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+//
+// Zig representations of Roc's builtin types, laid out the same way Roc lays them out in memory.
+//
+// The host must export roc_alloc, roc_realloc and roc_dealloc, just like it does for the app.
+
+const std = @import("std");
+
+extern fn roc_alloc(size: usize, alignment: u32) callconv(.C) ?*anyopaque;
+extern fn roc_dealloc(c_ptr: *anyopaque, alignment: u32) callconv(.C) void;
+
+/// Zero-sized values, like `{}`, still take up a byte when they are passed around.
+pub const RocUnit = u8;
+
+/// A fixed-point decimal: an i128 which is 10^18 times the number it represents.
+pub const RocDec = extern struct {
+    num: i128,
+};
+
+// Reference counting
+//
+// Every heap allocation starts with a reference count, which sits right before the data that
+// values point to. A refcount of REFCOUNT_ONE means there is exactly one reference, and each
+// extra reference adds one to it. Readonly allocations (e.g. string literals in the app's
+// binary) have a refcount of zero, and are never freed.
+
+pub const REFCOUNT_READONLY: isize = 0;
+pub const REFCOUNT_ONE: isize = std.math.minInt(isize);
+
+/// The top bit of a usize, which Roc uses to mark small strings and seamless slices
+const SIZE_TOP_BIT: usize = @as(usize, 1) << (@bitSizeOf(usize) - 1);
+
+fn allocationAlignment(element_alignment: u32) u32 {
+    return std.math.max(element_alignment, @alignOf(isize));
+}
+
+fn refcountPtr(data: *anyopaque) *isize {
+    return @intToPtr(*isize, @ptrToInt(data) - @sizeOf(isize));
+}
+
+/// Allocate `size` bytes with a refcount of one, and return a pointer to the data after the refcount
+pub fn allocRefcounted(size: usize, element_alignment: u32) *anyopaque {
+    const alignment = allocationAlignment(element_alignment);
+    const allocation = roc_alloc(size + alignment, alignment) orelse @panic("roc_alloc returned null");
+    const data = @intToPtr(*anyopaque, @ptrToInt(allocation) + alignment);
+
+    refcountPtr(data).* = REFCOUNT_ONE;
+
+    return data;
+}
+
+/// Add a reference to a refcounted allocation. Does nothing for null.
+pub fn increfPtr(data: ?*anyopaque) void {
+    if (data) |ptr| {
+        const refcount = refcountPtr(ptr);
+
+        if (refcount.* != REFCOUNT_READONLY) {
+            refcount.* += 1;
+        }
+    }
+}
+
+/// Remove a reference to a refcounted allocation. Returns true if that was the last one, in which
+/// case the caller must release whatever the allocation refers to and then call `freePtr`.
+pub fn releasePtr(data: ?*anyopaque) bool {
+    const ptr = data orelse return false;
+    const refcount = refcountPtr(ptr);
+
+    if (refcount.* == REFCOUNT_READONLY) {
+        return false;
+    } else if (refcount.* == REFCOUNT_ONE) {
+        return true;
+    } else {
+        refcount.* -= 1;
+
+        return false;
+    }
+}
+
+pub fn freePtr(data: *anyopaque, element_alignment: u32) void {
+    const alignment = allocationAlignment(element_alignment);
+
+    roc_dealloc(@intToPtr(*anyopaque, @ptrToInt(data) - alignment), alignment);
+}
+
+/// Whether values of this type own refcounted allocations, and so have a `decref` method
+fn needsDecref(comptime T: type) bool {
+    return switch (@typeInfo(T)) {
+        .Struct, .Union => @hasDecl(T, "decref"),
+        else => false,
+    };
+}
+
+pub const RocStr = extern struct {
+    bytes: ?[*]u8,
+    length: usize,
+    capacity_or_ref_ptr: usize,
+
+    /// Strings shorter than this are stored inline, with no heap allocation
+    pub const SMALL_STR_CAPACITY = @sizeOf(RocStr) - 1;
+
+    pub fn empty() RocStr {
+        return fromSlice("");
+    }
+
+    /// Copy some UTF-8 bytes into a new string
+    pub fn fromSlice(slice: []const u8) RocStr {
+        var str: RocStr = undefined;
+
+        if (slice.len <= SMALL_STR_CAPACITY) {
+            const bytes = @ptrCast([*]u8, &str);
+
+            std.mem.set(u8, bytes[0..@sizeOf(RocStr)], 0);
+            std.mem.copy(u8, bytes[0..slice.len], slice);
+            bytes[@sizeOf(RocStr) - 1] = @intCast(u8, slice.len) | 0x80;
+        } else {
+            const bytes = @ptrCast([*]u8, allocRefcounted(slice.len, 1));
+
+            std.mem.copy(u8, bytes[0..slice.len], slice);
+            str = RocStr{ .bytes = bytes, .length = slice.len, .capacity_or_ref_ptr = slice.len };
+        }
+
+        return str;
+    }
+
+    pub fn isSmall(self: *const RocStr) bool {
+        return self.capacity_or_ref_ptr & SIZE_TOP_BIT != 0;
+    }
+
+    pub fn isSeamlessSlice(self: *const RocStr) bool {
+        return !self.isSmall() and self.length & SIZE_TOP_BIT != 0;
+    }
+
+    pub fn len(self: *const RocStr) usize {
+        if (self.isSmall()) {
+            return @ptrCast([*]const u8, self)[@sizeOf(RocStr) - 1] & 0x7f;
+        } else {
+            return self.length & ~SIZE_TOP_BIT;
+        }
+    }
+
+    /// The string's UTF-8 bytes. These are borrowed from the string, so they are only valid as
+    /// long as it is.
+    pub fn asSlice(self: *const RocStr) []const u8 {
+        if (self.isSmall()) {
+            return @ptrCast([*]const u8, self)[0..self.len()];
+        } else if (self.bytes) |bytes| {
+            return bytes[0..self.len()];
+        } else {
+            return "";
+        }
+    }
+
+    fn refcountedPtr(self: *const RocStr) ?*anyopaque {
+        if (self.isSmall()) {
+            return null;
+        } else if (self.isSeamlessSlice()) {
+            return @intToPtr(?*anyopaque, self.capacity_or_ref_ptr << 1);
+        } else {
+            return @ptrCast(?*anyopaque, self.bytes);
+        }
+    }
+
+    pub fn incref(self: *const RocStr) void {
+        increfPtr(self.refcountedPtr());
+    }
+
+    pub fn decref(self: *RocStr) void {
+        const data = self.refcountedPtr();
+
+        if (releasePtr(data)) {
+            freePtr(data.?, 1);
+        }
+    }
+};
+
+pub fn RocList(comptime T: type) type {
+    return extern struct {
+        elements: ?[*]T,
+        length: usize,
+        capacity_or_ref_ptr: usize,
+
+        const Self = @This();
+
+        pub fn empty() Self {
+            return Self{ .elements = null, .length = 0, .capacity_or_ref_ptr = 0 };
+        }
+
+        /// Copy some elements into a new list. This doesn't incref them, so the list takes
+        /// ownership of them.
+        pub fn fromSlice(slice: []const T) Self {
+            if (slice.len == 0) {
+                return empty();
+            }
+
+            const elements = @ptrCast([*]T, @alignCast(@alignOf(T), allocRefcounted(slice.len * @sizeOf(T), @alignOf(T))));
+
+            std.mem.copy(T, elements[0..slice.len], slice);
+
+            return Self{ .elements = elements, .length = slice.len, .capacity_or_ref_ptr = slice.len };
+        }
+
+        pub fn isSeamlessSlice(self: *const Self) bool {
+            return self.capacity_or_ref_ptr & SIZE_TOP_BIT != 0;
+        }
+
+        pub fn len(self: *const Self) usize {
+            return self.length & ~SIZE_TOP_BIT;
+        }
+
+        /// The list's elements. These are borrowed from the list, so they are only valid as long
+        /// as it is.
+        pub fn asSlice(self: *const Self) []T {
+            if (self.elements) |elements| {
+                return elements[0..self.len()];
+            } else {
+                return &[_]T{};
+            }
+        }
+
+        fn refcountedPtr(self: *const Self) ?*anyopaque {
+            if (self.isSeamlessSlice()) {
+                return @intToPtr(?*anyopaque, self.capacity_or_ref_ptr << 1);
+            } else {
+                return @ptrCast(?*anyopaque, self.elements);
+            }
+        }
+
+        pub fn incref(self: *const Self) void {
+            increfPtr(self.refcountedPtr());
+        }
+
+        pub fn decref(self: *Self) void {
+            const data = self.refcountedPtr();
+
+            if (releasePtr(data)) {
+                if (comptime needsDecref(T)) {
+                    for (self.asSlice()) |*element| {
+                        element.decref();
+                    }
+                }
+
+                freePtr(data.?, @alignOf(T));
+            }
+        }
+    };
+}
+
+/// A Dict's entries have the key first, unless the value has a larger alignment than the key.
+pub fn RocDictEntry(comptime K: type, comptime V: type) type {
+    if (@alignOf(K) >= @alignOf(V)) {
+        return extern struct { key: K, value: V };
+    } else {
+        return extern struct { value: V, key: K };
+    }
+}
+
+pub fn RocDict(comptime K: type, comptime V: type) type {
+    return extern struct {
+        entries: RocList(RocDictEntry(K, V)),
+
+        const Self = @This();
+
+        pub fn incref(self: *const Self) void {
+            self.entries.incref();
+        }
+
+        pub fn decref(self: *Self) void {
+            const data = self.entries.refcountedPtr();
+
+            if (releasePtr(data)) {
+                for (self.entries.asSlice()) |*entry| {
+                    if (comptime needsDecref(K)) entry.key.decref();
+                    if (comptime needsDecref(V)) entry.value.decref();
+                }
+
+                freePtr(data.?, @alignOf(RocDictEntry(K, V)));
+            }
+        }
+    };
+}
+
+pub fn RocSet(comptime T: type) type {
+    return extern struct {
+        elements: RocList(T),
+
+        const Self = @This();
+
+        pub fn incref(self: *const Self) void {
+            self.elements.incref();
+        }
+
+        pub fn decref(self: *Self) void {
+            self.elements.decref();
+        }
+    };
+}
+
+pub fn RocBox(comptime T: type) type {
+    return extern struct {
+        contents: *T,
+
+        const Self = @This();
+
+        /// Move a value into a new box
+        pub fn init(value: T) Self {
+            const contents = @ptrCast(*T, @alignCast(@alignOf(T), allocRefcounted(@sizeOf(T), @alignOf(T))));
+
+            contents.* = value;
+
+            return Self{ .contents = contents };
+        }
+
+        pub fn incref(self: *const Self) void {
+            increfPtr(self.contents);
+        }
+
+        pub fn decref(self: *Self) void {
+            if (releasePtr(self.contents)) {
+                if (comptime needsDecref(T)) {
+                    self.contents.decref();
+                }
+
+                freePtr(self.contents, @alignOf(T));
+            }
+        }
+    };
+}
+
+pub fn RocResult(comptime T: type, comptime E: type) type {
+    return extern struct {
+        payload: extern union { ok: T, err: E },
+        is_ok: bool,
+
+        const Self = @This();
+
+        pub fn ok(payload: T) Self {
+            return Self{ .payload = .{ .ok = payload }, .is_ok = true };
+        }
+
+        pub fn err(payload: E) Self {
+            return Self{ .payload = .{ .err = payload }, .is_ok = false };
+        }
+
+        pub fn incref(self: *const Self) void {
+            if (self.is_ok) {
+                if (comptime needsDecref(T)) self.payload.ok.incref();
+            } else {
+                if (comptime needsDecref(E)) self.payload.err.incref();
+            }
+        }
+
+        pub fn decref(self: *Self) void {
+            if (self.is_ok) {
+                if (comptime needsDecref(T)) self.payload.ok.decref();
+            } else {
+                if (comptime needsDecref(E)) self.payload.err.decref();
+            }
+        }
+    };
+}
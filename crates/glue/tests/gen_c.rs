@@ -0,0 +1,283 @@
+mod helpers;
+
+#[cfg(test)]
+mod test_gen_c {
+    use crate::helpers::load_platform_types;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use roc_glue::c_glue::{self, HEADER, ROC_STD};
+    use roc_glue::types::File;
+    use std::process::Command;
+
+    fn generate_bindings(decl_src: &str) -> Vec<File> {
+        c_glue::emit(&load_platform_types(decl_src))
+    }
+
+    fn expected_files(declarations: &str) -> Vec<File> {
+        let header = std::str::from_utf8(HEADER).unwrap();
+
+        vec![
+            File {
+                name: "roc_app.h".to_string(),
+                content: format!("{header}{declarations}\n#endif // ROC_APP_H\n"),
+            },
+            File {
+                name: "roc_std.h".to_string(),
+                content: std::str::from_utf8(ROC_STD).unwrap().to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn basic_record_aliased() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U64, b : I128 }
+
+            main : MyRcd
+            main = { a: 1u64, b: 2i128 }
+            "#
+        );
+
+        assert_eq!(
+            generate_bindings(module),
+            expected_files(indoc!(
+                r#"
+
+                typedef struct MyRcd MyRcd;
+
+                static inline MyRcd roc_main(void);
+
+                struct MyRcd {
+                    RocI128 b;
+                    uint64_t a;
+                };
+                _Static_assert(sizeof(MyRcd) == 32, "MyRcd should be 32 bytes");
+                _Static_assert(_Alignof(MyRcd) == 16, "MyRcd should be aligned to 16 bytes");
+
+                extern void roc__main_1_exposed_generic(MyRcd *output);
+
+                static inline MyRcd roc_main(void) {
+                    MyRcd output;
+                    roc__main_1_exposed_generic(&output);
+                    return output;
+                }
+                "#
+            ))
+        );
+    }
+
+    #[test]
+    fn tag_union_enumeration() {
+        let module = indoc!(
+            r#"
+            MyEnum : [Foo, Bar, Baz]
+
+            main : MyEnum
+            main = Foo
+            "#
+        );
+
+        assert_eq!(
+            generate_bindings(module),
+            expected_files(indoc!(
+                r#"
+
+                typedef uint8_t MyEnum;
+
+                static inline MyEnum roc_main(void);
+
+                enum {
+                    MyEnum_Bar = 0,
+                    MyEnum_Baz = 1,
+                    MyEnum_Foo = 2,
+                };
+
+                extern void roc__main_1_exposed_generic(MyEnum *output);
+
+                static inline MyEnum roc_main(void) {
+                    MyEnum output;
+                    roc__main_1_exposed_generic(&output);
+                    return output;
+                }
+                "#
+            ))
+        );
+    }
+
+    #[test]
+    fn declarations_differ_by_target() {
+        let module = indoc!(
+            r#"
+            Shape : [Circle F64, Label Str]
+
+            main : Shape
+            main = Circle 1.0
+            "#
+        );
+
+        let files = generate_bindings(module);
+        let conditions: Vec<_> = files[0]
+            .content
+            .lines()
+            .filter(|line| line.starts_with("#if ") || line.starts_with("#elif "))
+            .collect();
+
+        assert_eq!(
+            conditions,
+            [
+                "#if defined(__arm__) || defined(_M_ARM) || defined(__i386__) || defined(_M_IX86)",
+                "#elif defined(__aarch64__) || defined(_M_ARM64) || defined(__x86_64__) || defined(_M_X64)",
+                "#elif defined(__wasm32__)",
+            ]
+        );
+        assert!(files[0].content.contains(indoc!(
+            r#"
+            static inline void Shape_decref(Shape *self) {
+                switch (Shape_discriminant(self)) {
+                case discriminant_Shape_Label:
+                    roc_str_decref(&self->Label);
+                    break;
+                default:
+                    break;
+                }
+            }
+            "#
+        )));
+    }
+
+    #[test]
+    fn closure_captures_are_refcounted_by_the_app() {
+        let module = indoc!(
+            r#"
+            Op : { run : Str -> Str, name : Str }
+
+            main : Op
+            main = { run: \s -> Str.concat s "!", name: "shout" }
+            "#
+        );
+
+        let files = generate_bindings(module);
+        let header = &files[0].content;
+
+        assert!(header.contains("extern void roc__incref__"));
+        assert!(header.contains("extern void roc__decref__"));
+        assert!(
+            header.contains("static inline void Op_incref(const Op *self) {\n    roc__incref__")
+        );
+        assert!(header.contains("static inline void Op_decref(Op *self) {\n    roc__decref__"));
+    }
+
+    /// Compile the generated header with the host's C compiler, and check that values built with
+    /// it can be shared and released without leaking or double freeing anything.
+    #[test]
+    fn compiles_and_refcounts() {
+        let module = indoc!(
+            r#"
+            Outer : { x : Inner, y : Str, z : List U8, names : List Str }
+
+            Inner : { a : U16, b : F32 }
+
+            Shape : [Circle F64, Rect { w : F64, h : F64 }, Label Str, Empty]
+
+            Expr : [Num I64, Add Expr Expr, Neg Expr, Var Str]
+
+            Root : { outer : Outer, shape : Shape, expr : Expr, res : Result Str U32 }
+
+            main : Root -> Root
+            main = \r -> r
+            "#
+        );
+
+        let host = indoc!(
+            r#"
+            #include <stdlib.h>
+            #include "roc_app.h"
+
+            static int live_allocations = 0;
+
+            void *roc_alloc(size_t size, uint32_t alignment) {
+                live_allocations++;
+                return aligned_alloc(alignment, (size + alignment - 1) / alignment * alignment);
+            }
+
+            void *roc_realloc(void *ptr, size_t new_size, size_t old_size, uint32_t alignment) {
+                (void)old_size;
+                (void)alignment;
+                return realloc(ptr, new_size);
+            }
+
+            void roc_dealloc(void *ptr, uint32_t alignment) {
+                (void)alignment;
+                live_allocations--;
+                free(ptr);
+            }
+
+            int main(void) {
+                Expr_Var var = { roc_str_from_cstr("a string which is too long to be a small string") };
+                Expr_Neg neg = { Expr_make_Var(var) };
+                Expr expr = Expr_make_Neg(neg);
+
+                if (Expr_discriminant(&expr) != discriminant_Expr_Neg) return 1;
+                if (Expr_discriminant(&Expr_union(&expr)->Neg.f0) != discriminant_Expr_Var) return 2;
+
+                Expr_incref(&expr);
+                Expr_decref(&expr);
+                if (live_allocations != 3) return 3;
+
+                RocList names = roc_list_with_capacity(2, sizeof(RocStr), _Alignof(RocStr));
+                ((RocStr *)names.elements)[0] = roc_str_from_cstr("another string which is too long to be small");
+                ((RocStr *)names.elements)[1] = roc_str_from_cstr("small");
+                names.length = 2;
+
+                Root root;
+                root.expr = expr;
+                root.outer.names = names;
+                root.outer.x.a = 1;
+                root.outer.x.b = 2.0f;
+                root.outer.y = roc_str_from_cstr("y");
+                root.outer.z = roc_list_with_capacity(0, sizeof(uint8_t), _Alignof(uint8_t));
+                root.res = RocResult_Str_U32_ok(roc_str_from_cstr("an ok string which is too long to be small"));
+                root.shape = Shape_make_Label(roc_str_from_cstr("a label which is too long to be a small string"));
+
+                if (Shape_discriminant(&root.shape) != discriminant_Shape_Label) return 4;
+                if (roc_str_len(&root.outer.y) != 1) return 5;
+
+                Root_incref(&root);
+                Root_decref(&root);
+                if (live_allocations != 7) return 6;
+
+                Root_decref(&root);
+
+                return live_allocations;
+            }
+            "#
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+
+        for File { name, content } in generate_bindings(module) {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+
+        std::fs::write(dir.path().join("host.c"), host).unwrap();
+
+        let exe_path = dir.path().join("host");
+        let output = Command::new("cc")
+            .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-o"])
+            .arg(&exe_path)
+            .arg(dir.path().join("host.c"))
+            .output()
+            .expect("failed to run cc");
+
+        assert!(
+            output.status.success(),
+            "cc failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let status = Command::new(&exe_path).status().unwrap();
+
+        assert_eq!(status.code(), Some(0));
+    }
+}
@@ -0,0 +1,151 @@
+mod helpers;
+
+#[cfg(test)]
+mod test_gen_zig {
+    use crate::helpers::load_platform_types;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use roc_glue::types::File;
+    use roc_glue::zig_glue::{self, HEADER, ROC_STD};
+
+    fn generate_bindings(decl_src: &str) -> Vec<File> {
+        zig_glue::emit(&load_platform_types(decl_src))
+    }
+
+    fn expected_files(declarations: &str) -> Vec<File> {
+        let header = std::str::from_utf8(HEADER).unwrap();
+
+        vec![
+            File {
+                name: "roc_app.zig".to_string(),
+                content: format!("{header}{declarations}"),
+            },
+            File {
+                name: "roc_std.zig".to_string(),
+                content: std::str::from_utf8(ROC_STD).unwrap().to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn basic_record_aliased() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U64, b : I128 }
+
+            main : MyRcd
+            main = { a: 1u64, b: 2i128 }
+            "#
+        );
+
+        assert_eq!(
+            generate_bindings(module),
+            expected_files(indoc!(
+                r#"
+
+                pub const MyRcd = extern struct {
+                    b: i128,
+                    a: u64,
+                };
+
+                comptime {
+                    std.debug.assert(@sizeOf(MyRcd) == 32);
+                    std.debug.assert(@alignOf(MyRcd) == 16);
+                }
+
+                extern fn roc__main_1_exposed_generic(output: *MyRcd) callconv(.C) void;
+
+                pub fn main() MyRcd {
+                    var output: MyRcd = undefined;
+                    roc__main_1_exposed_generic(&output);
+
+                    return output;
+                }
+                "#
+            ))
+        );
+    }
+
+    #[test]
+    fn tag_union_enumeration() {
+        let module = indoc!(
+            r#"
+            MyEnum : [Foo, Bar, Baz]
+
+            main : MyEnum
+            main = Foo
+            "#
+        );
+
+        assert_eq!(
+            generate_bindings(module),
+            expected_files(indoc!(
+                r#"
+
+                pub const MyEnum = enum(u8) {
+                    Bar,
+                    Baz,
+                    Foo,
+                };
+
+                extern fn roc__main_1_exposed_generic(output: *MyEnum) callconv(.C) void;
+
+                pub fn main() MyEnum {
+                    var output: MyEnum = undefined;
+                    roc__main_1_exposed_generic(&output);
+
+                    return output;
+                }
+                "#
+            ))
+        );
+    }
+
+    #[test]
+    fn declarations_differ_by_target() {
+        let module = indoc!(
+            r#"
+            Shape : [Circle F64, Label Str]
+
+            main : Shape
+            main = Circle 1.0
+            "#
+        );
+
+        let files = generate_bindings(module);
+        let content = &files[0].content;
+        let prongs: Vec<_> = content
+            .lines()
+            .filter(|line| line.ends_with("=> struct {") || line.contains("else => @compileError"))
+            .map(str::trim)
+            .collect();
+
+        assert!(content.contains("\npub usingnamespace switch (builtin.cpu.arch) {\n"));
+        assert_eq!(
+            prongs,
+            [
+                ".arm, .x86 => struct {",
+                ".aarch64, .x86_64 => struct {",
+                ".wasm32 => struct {",
+                "else => @compileError(\"roc glue did not generate declarations for this target\"),",
+            ]
+        );
+        // Everything is nested inside a struct for each target
+        assert!(content.contains(concat!(
+            "        pub const Shape = extern union {\n",
+            "            Circle: f64,\n",
+            "            Label: roc_std.RocStr,\n",
+            "            _sizer: [32]u8,\n",
+        )));
+        assert!(content.contains(concat!(
+            "            pub fn decref(self: *Shape) void {\n",
+            "                switch (self.discriminant()) {\n",
+            "                    .Label => {\n",
+            "                        self.Label.decref();\n",
+            "                    },\n",
+            "                    else => {},\n",
+            "                }\n",
+            "            }\n",
+        )));
+    }
+}
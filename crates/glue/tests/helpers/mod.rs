@@ -1,6 +1,7 @@
 use indoc::indoc;
 use roc_glue::load::{load_types, IgnoreErrors};
 use roc_glue::rust_glue;
+use roc_glue::types::Types;
 use roc_load::Threading;
 use std::env;
 use std::fs::File;
@@ -9,6 +10,13 @@ use std::path::PathBuf;
 
 #[allow(dead_code)]
 pub fn generate_bindings(decl_src: &str) -> Vec<roc_glue::types::File> {
+    rust_glue::emit(&load_platform_types(decl_src))
+}
+
+/// Load the types of a platform module consisting of the given declarations,
+/// which must include a `main` for the platform to provide.
+#[allow(dead_code)]
+pub fn load_platform_types(decl_src: &str) -> Vec<Types> {
     use tempfile::tempdir;
 
     let mut src = indoc!(
@@ -26,27 +34,23 @@ pub fn generate_bindings(decl_src: &str) -> Vec<roc_glue::types::File> {
 
     src.push_str(decl_src);
 
-    let types = {
-        let dir = tempdir().expect("Unable to create tempdir");
-        let filename = PathBuf::from("platform.roc");
-        let file_path = dir.path().join(filename);
-        let full_file_path = file_path.clone();
-        let mut file = File::create(file_path).unwrap();
-        writeln!(file, "{}", &src).unwrap();
-
-        let result = load_types(
-            full_file_path,
-            Threading::Single,
-            // required `nothing` is unused; that error is okay
-            IgnoreErrors { can: true },
-        );
+    let dir = tempdir().expect("Unable to create tempdir");
+    let filename = PathBuf::from("platform.roc");
+    let file_path = dir.path().join(filename);
+    let full_file_path = file_path.clone();
+    let mut file = File::create(file_path).unwrap();
+    writeln!(file, "{}", &src).unwrap();
 
-        dir.close().expect("Unable to close tempdir");
+    let result = load_types(
+        full_file_path,
+        Threading::Single,
+        // required `nothing` is unused; that error is okay
+        IgnoreErrors { can: true },
+    );
 
-        result.expect("had problems loading")
-    };
+    dir.close().expect("Unable to close tempdir");
 
-    rust_glue::emit(&types)
+    result.expect("had problems loading")
 }
 
 #[allow(dead_code)]
@@ -13,28 +13,32 @@ pub fn highlight_roc_code_inline(code: &str) -> String {
     format!("<code>{}</code>", buf.join(""))
 }
 
-pub fn highlight(code: &str) -> Vec<String> {
-    let locations: Vec<Loc<Token>> = roc_parse::highlight::highlight(code);
-    let mut buf: Vec<String> = Vec::new();
-    let mut offset = 0;
-
-    for location in locations {
-        let current_text = &code[offset..location.byte_range().end];
+/// The kinds of syntax we highlight differently. In HTML, these are the classes of the `span`s
+/// we wrap the code in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Comment,
+    Literal,
+    Keyword,
+    Operator,
+    Delimiter,
+    UpperIdent,
+    LowerIdent,
+}
 
-        match location.value {
+impl HighlightKind {
+    /// Returns [None] for tokens we don't highlight.
+    pub fn of(token: Token) -> Option<Self> {
+        match token {
             // Comments `#` and Documentation comments `##`
-            Token::LineComment | Token::DocComment => {
-                buf = push_html_span(buf, current_text, "comment");
-            }
+            Token::LineComment | Token::DocComment => Some(HighlightKind::Comment),
             // Number, String, Tag, Type literals
             Token::SingleQuote
             | Token::String
             | Token::UnicodeEscape
             | Token::EscapedChar
             | Token::Interpolated
-            | Token::Number => {
-                buf = push_html_span(buf, current_text, "literal");
-            }
+            | Token::Number => Some(HighlightKind::Literal),
             // Keywords and punctuation
             Token::Keyword
             | Token::Equals
@@ -46,9 +50,7 @@ pub fn highlight(code: &str) -> Vec<String> {
             | Token::Colon
             | Token::And
             | Token::AtSign
-            | Token::QuestionMark => {
-                buf = push_html_span(buf, current_text, "kw");
-            }
+            | Token::QuestionMark => Some(HighlightKind::Keyword),
             // Operators
             Token::Percent
             | Token::Caret
@@ -66,28 +68,49 @@ pub fn highlight(code: &str) -> Vec<String> {
             | Token::DoubleBar
             | Token::Multiply
             | Token::Plus
-            | Token::DoubleAnd => {
-                buf = push_html_span(buf, current_text, "op");
-            }
+            | Token::DoubleAnd => Some(HighlightKind::Operator),
             // Delimieters
             Token::Paren
             | Token::Bracket
             | Token::Brace
             | Token::Comma
             | Token::Bar
-            | Token::Decimal => {
-                buf = push_html_span(buf, current_text, "delimeter");
-            }
+            | Token::Decimal => Some(HighlightKind::Delimiter),
             // Types, Tags, and Modules
-            Token::UpperIdent => {
-                buf = push_html_span(buf, current_text, "upperident");
-            }
+            Token::UpperIdent => Some(HighlightKind::UpperIdent),
             // Variables modules and field names
-            Token::LowerIdent | Token::Underscore => {
-                buf = push_html_span(buf, current_text, "lowerident");
-            }
+            Token::LowerIdent | Token::Underscore => Some(HighlightKind::LowerIdent),
             // Anyting else that wasn't tokenised
-            Token::Error | Token::Other => {
+            Token::Error | Token::Other => None,
+        }
+    }
+
+    pub fn html_class(self) -> &'static str {
+        match self {
+            HighlightKind::Comment => "comment",
+            HighlightKind::Literal => "literal",
+            HighlightKind::Keyword => "kw",
+            HighlightKind::Operator => "op",
+            HighlightKind::Delimiter => "delimeter",
+            HighlightKind::UpperIdent => "upperident",
+            HighlightKind::LowerIdent => "lowerident",
+        }
+    }
+}
+
+pub fn highlight(code: &str) -> Vec<String> {
+    let locations: Vec<Loc<Token>> = roc_parse::highlight::highlight(code);
+    let mut buf: Vec<String> = Vec::new();
+    let mut offset = 0;
+
+    for location in locations {
+        let current_text = &code[offset..location.byte_range().end];
+
+        match HighlightKind::of(location.value) {
+            Some(kind) => {
+                buf = push_html_span(buf, current_text, kind.html_class());
+            }
+            None => {
                 buf = push_html(buf, current_text);
             }
         }
@@ -0,0 +1,306 @@
+//! Everything we know about a document comes from running the loader on it (with its unsaved
+//! text, and everything it imports read from disk), the same way `roc check` would.
+use crate::convert::{path_to_uri, LineIndex};
+use crate::protocol::{
+    Diagnostic, DiagnosticSeverity, Hover, Location, MarkupContent, Position, Range,
+};
+use bumpalo::Bump;
+use roc_can::traverse::{find_closest_symbol_at, find_closest_type_at, find_symbol_definition};
+use roc_load::{FunctionKind, LoadedModule, LoadingProblem};
+use roc_module::symbol::ModuleIds;
+use roc_packaging::cache::RocCacheDir;
+use roc_region::all::{LineInfo, Position as RocPosition};
+use roc_reporting::report::{
+    can_problem, type_problem, RenderTarget, Report, RocDocAllocator, DEFAULT_PALETTE,
+};
+use roc_target::TargetInfo;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// The diagnostics for every file involved in checking a document (which includes the modules
+/// it imports), including files that no longer have any problems.
+pub(crate) type Diagnostics = BTreeMap<PathBuf, Vec<Diagnostic>>;
+
+pub(crate) struct Analysis {
+    module: LoadedModule,
+}
+
+pub(crate) struct Checked {
+    /// [None] if loading failed before type checking, for example because of a syntax error.
+    pub analysis: Option<Analysis>,
+    pub diagnostics: Diagnostics,
+}
+
+/// Returns [None] if the compiler crashed. Compiler bugs are much more likely to be hit by the
+/// half-written code a language server sees than by `roc check`, and the server should survive
+/// them, so we catch the panic (its message still goes to stderr, which clients usually log).
+pub(crate) fn check(path: &Path, src: &str, roc_cache_dir: RocCacheDir<'_>) -> Option<Checked> {
+    catch_unwind(AssertUnwindSafe(|| check_help(path, src, roc_cache_dir))).ok()
+}
+
+fn check_help(path: &Path, src: &str, roc_cache_dir: RocCacheDir<'_>) -> Checked {
+    let arena = Bump::new();
+    let src_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let loaded = roc_load::load_and_typecheck_str(
+        &arena,
+        path.to_path_buf(),
+        arena.alloc_str(src),
+        src_dir,
+        // Only used for reporting problems, since we don't generate any code.
+        TargetInfo::default_x86_64(),
+        FunctionKind::LambdaSet,
+        // Problems that stop the loader are only ever available as rendered reports,
+        // so we ask for them as JSON, and handle all the other problems the same way.
+        RenderTarget::Json,
+        roc_cache_dir,
+        DEFAULT_PALETTE,
+    );
+
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.insert(path.to_path_buf(), Vec::new());
+
+    match loaded {
+        Ok(mut module) => {
+            for (home, (module_path, module_src)) in module.sources.iter() {
+                let src_lines: Vec<&str> = module_src.split('\n').collect();
+                let lines = LineInfo::new(module_src);
+                let alloc = RocDocAllocator::new(&src_lines, *home, &module.interns);
+                let line_index = LineIndex::new(module_src);
+
+                let can_problems = module.can_problems.remove(home).unwrap_or_default();
+                let type_problems = module.type_problems.remove(home).unwrap_or_default();
+
+                let reports = can_problems
+                    .into_iter()
+                    .map(|problem| can_problem(&alloc, &lines, module_path.clone(), problem))
+                    .chain(type_problems.into_iter().filter_map(|problem| {
+                        type_problem(&alloc, &lines, module_path.clone(), problem)
+                    }));
+
+                for report in reports {
+                    let report = JsonReport::from_report(report);
+                    let diagnostic = report.to_diagnostic(&line_index);
+
+                    diagnostics
+                        .entry(module_path.clone())
+                        .or_default()
+                        .push(diagnostic);
+                }
+            }
+
+            Checked {
+                analysis: Some(Analysis { module }),
+                diagnostics,
+            }
+        }
+        Err(problem) => {
+            let rendered = match problem {
+                LoadingProblem::FormattedReport(report) => report,
+                LoadingProblem::ParsingFailed(_)
+                | LoadingProblem::ImportCycle(..)
+                | LoadingProblem::IncorrectModuleName(_)
+                | LoadingProblem::FileProblem { .. } => roc_load::report_loading_problem(
+                    problem,
+                    ModuleIds::default(),
+                    RenderTarget::Json,
+                    DEFAULT_PALETTE,
+                ),
+                other => format!("{other:?}"),
+            };
+
+            match serde_json::from_str::<JsonReport>(&rendered) {
+                Ok(report) => {
+                    let report_src = if report.file == path {
+                        Some(src.to_string())
+                    } else {
+                        std::fs::read_to_string(&report.file).ok()
+                    };
+                    let line_index = LineIndex::new(report_src.as_deref().unwrap_or_default());
+                    let diagnostic = report.to_diagnostic(&line_index);
+
+                    diagnostics.entry(report.file).or_default().push(diagnostic);
+                }
+                Err(_) => {
+                    // A few problems can't be rendered as JSON yet,
+                    // so show them at the start of the document.
+                    diagnostics
+                        .entry(path.to_path_buf())
+                        .or_default()
+                        .push(Diagnostic {
+                            range: LineIndex::new("").full_range(),
+                            severity: DiagnosticSeverity::Error,
+                            code: String::new(),
+                            source: "roc",
+                            message: rendered,
+                        });
+                }
+            }
+
+            Checked {
+                analysis: None,
+                diagnostics,
+            }
+        }
+    }
+}
+
+impl Analysis {
+    fn src(&self) -> &str {
+        &self.module.sources[&self.module.module_id].1
+    }
+
+    /// Shows the type of the innermost expression or pattern under the cursor.
+    pub(crate) fn hover(&mut self, position: Position) -> Option<Hover> {
+        let module = &mut self.module;
+        let home = module.module_id;
+        let src = &module.sources[&home].1;
+        let line_index = LineIndex::new(src);
+        let offset = line_index.offset(position)?;
+
+        let decls = module.declarations_by_id.get(&home)?;
+        let position = RocPosition::new(offset as u32);
+        let loc_var = find_closest_type_at(position, decls)?;
+
+        let subs = module.solved.inner_mut();
+        let snapshot = subs.snapshot();
+        let type_str = name_and_print_var(
+            loc_var.value,
+            subs,
+            home,
+            &module.interns,
+            DebugPrint::NOTHING,
+        );
+        subs.rollback_to(snapshot);
+
+        let value = match find_closest_symbol_at(position, decls) {
+            Some(loc_symbol) if loc_symbol.region == loc_var.region => {
+                let name = loc_symbol.value.as_str(&module.interns);
+
+                format!("```roc\n{name} : {type_str}\n```")
+            }
+            _ => format!("```roc\n{type_str}\n```"),
+        };
+
+        Some(Hover {
+            contents: MarkupContent {
+                kind: "markdown",
+                value,
+            },
+            range: line_index.range(loc_var.region),
+        })
+    }
+
+    /// Finds where the symbol under the cursor was introduced, which may be in another module.
+    pub(crate) fn definition(&self, position: Position) -> Option<Location> {
+        let module = &self.module;
+        let offset = LineIndex::new(self.src()).offset(position)?;
+
+        let decls = module.declarations_by_id.get(&module.module_id)?;
+        let symbol = find_closest_symbol_at(RocPosition::new(offset as u32), decls)?.value;
+
+        // Builtins have no declarations or sources, so there is nowhere to go.
+        let home = symbol.module_id();
+        let region = find_symbol_definition(symbol, module.declarations_by_id.get(&home)?)?;
+        let (path, src) = module.sources.get(&home)?;
+
+        Some(Location {
+            uri: path_to_uri(path),
+            range: LineIndex::new(src).range(region),
+        })
+    }
+}
+
+/// A report, either rendered by [`Report::render_json`] (which is all we get for problems that
+/// stop the loader), or taken from a [`Report`] with [`Report::json_diagnostic`].
+#[derive(Deserialize)]
+struct JsonReport {
+    severity: String,
+    file: PathBuf,
+    region: Option<JsonRegion>,
+    title: String,
+    message: String,
+    hints: Vec<JsonHint>,
+}
+
+#[derive(Deserialize)]
+struct JsonRegion {
+    start: JsonPosition,
+    end: JsonPosition,
+}
+
+#[derive(Deserialize)]
+struct JsonPosition {
+    line: u32,
+    column: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonHint {
+    label: String,
+    message: String,
+}
+
+impl JsonReport {
+    fn from_report(report: Report<'_>) -> Self {
+        let diagnostic = report.json_diagnostic();
+
+        JsonReport {
+            severity: diagnostic.severity.to_string(),
+            file: report.filename.clone(),
+            region: diagnostic.region.map(|region| JsonRegion {
+                start: JsonPosition {
+                    line: region.start.line,
+                    column: region.start.column,
+                },
+                end: JsonPosition {
+                    line: region.end.line,
+                    column: region.end.column,
+                },
+            }),
+            title: report.title.clone(),
+            message: diagnostic.message,
+            hints: diagnostic
+                .hints
+                .into_iter()
+                .map(|hint| JsonHint {
+                    label: hint.label.to_string(),
+                    message: hint.message,
+                })
+                .collect(),
+        }
+    }
+
+    fn to_diagnostic(&self, line_index: &LineIndex) -> Diagnostic {
+        let range = match &self.region {
+            Some(JsonRegion { start, end }) => Range {
+                start: line_index.report_position(start.line, start.column),
+                end: line_index.report_position(end.line, end.column),
+            },
+            None => Range {
+                start: line_index.report_position(1, 1),
+                end: line_index.report_position(1, 1),
+            },
+        };
+
+        let mut message = self.message.clone();
+
+        for hint in self.hints.iter() {
+            message.push_str(&format!("\n\n{}: {}", hint.label, hint.message));
+        }
+
+        Diagnostic {
+            range,
+            severity: match self.severity.as_str() {
+                "warning" => DiagnosticSeverity::Warning,
+                _ => DiagnosticSeverity::Error,
+            },
+            code: self.title.clone(),
+            source: "roc",
+            message,
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Conversions between the compiler's representations of source locations and file paths,
+//! and the protocol's.
+use crate::protocol::{Position, Range};
+use roc_region::all::Region;
+use std::path::{Path, PathBuf};
+
+/// Converts between byte offsets (which is what the compiler's [`Region`]s are made of) and
+/// protocol [`Position`]s, which count UTF-16 code units from the start of a line.
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    /// The byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+
+        Self { text, line_starts }
+    }
+
+    /// Returns [None] if the position is past the end of the document. A character past the end
+    /// of its line is clamped to the end of the line, like the protocol says it should be.
+    pub(crate) fn offset(&self, position: Position) -> Option<usize> {
+        let line_start = *self.line_starts.get(position.line as usize)?;
+        let line = self.line(line_start);
+
+        let mut character = 0;
+
+        for (index, ch) in line.char_indices() {
+            if character >= position.character {
+                return Some(line_start + index);
+            }
+
+            character += ch.len_utf16() as u32;
+        }
+
+        Some(line_start + line.len())
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+
+        let character = self.text[line_start..offset]
+            .chars()
+            .map(|ch| ch.len_utf16() as u32)
+            .sum();
+
+        Position {
+            line: line as u32,
+            character,
+        }
+    }
+
+    pub(crate) fn range(&self, region: Region) -> Range {
+        Range {
+            start: self.position(region.start().offset as usize),
+            end: self.position(region.end().offset as usize),
+        }
+    }
+
+    /// The range of the whole document.
+    pub(crate) fn full_range(&self) -> Range {
+        Range {
+            start: Position {
+                line: 0,
+                character: 0,
+            },
+            end: self.position(self.text.len()),
+        }
+    }
+
+    /// The text of the line starting at `line_start`, without its line ending.
+    fn line(&self, line_start: usize) -> &'a str {
+        let rest = &self.text[line_start..];
+        let line = rest.split('\n').next().unwrap_or_default();
+
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// Converts a position from a report (where lines and columns start at 1,
+    /// and columns count bytes) into a protocol position.
+    pub(crate) fn report_position(&self, line: u32, column: u32) -> Position {
+        let line_index = line.saturating_sub(1) as usize;
+
+        match self.line_starts.get(line_index) {
+            Some(line_start) => {
+                let line_end = line_start + self.line(*line_start).len();
+                let offset = line_start + column.saturating_sub(1) as usize;
+
+                self.position(offset.min(line_end))
+            }
+            None => self.position(self.text.len()),
+        }
+    }
+}
+
+/// Only `file://` URIs have paths, and those are the only documents we can load.
+pub(crate) fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+
+    // Skip the authority, which is almost always empty (as in `file:///home/...`).
+    let path = &path[path.find('/')?..];
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        match (byte, tail) {
+            (b'%', [high, low, tail @ ..]) => {
+                let hex = [*high, *low];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                rest = tail;
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    let path = String::from_utf8(bytes).ok()?;
+
+    // Windows paths look like `/C:/Users/...` in URIs.
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        Some(PathBuf::from(&path[1..]))
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+pub(crate) fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn positions_count_utf16_code_units() {
+        let text = "a = \"é😀\"\r\nb = 1\n";
+        let lines = LineIndex::new(text);
+
+        // `é` is 2 bytes and 1 code unit, and `😀` is 4 bytes and 2 code units.
+        let after_emoji = text.find('😀').unwrap() + '😀'.len_utf8();
+
+        assert_eq!(lines.position(after_emoji), position(0, 8));
+        assert_eq!(lines.offset(position(0, 8)), Some(after_emoji));
+        assert_eq!(lines.offset(position(1, 0)), text.find('b'));
+        assert_eq!(lines.offset(position(0, 100)), text.find('\r'));
+        assert_eq!(lines.offset(position(5, 0)), None);
+        assert_eq!(lines.full_range().end, position(2, 0));
+    }
+
+    #[test]
+    fn uris_roundtrip() {
+        let path = if cfg!(windows) {
+            PathBuf::from(r"C:\code\my app\main.roc")
+        } else {
+            PathBuf::from("/code/my app/main.roc")
+        };
+
+        let uri = path_to_uri(&path);
+
+        assert!(uri.contains("my%20app"));
+        assert_eq!(uri_to_path(&uri), Some(path));
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+}
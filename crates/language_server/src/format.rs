@@ -0,0 +1,42 @@
+use bumpalo::Bump;
+use roc_fmt::def::fmt_defs;
+use roc_fmt::module::fmt_module;
+use roc_fmt::{Ast, Buf};
+use roc_parse::{
+    module::{self, module_defs},
+    parser::{Parser, SyntaxError},
+    state::State,
+};
+
+/// Formats a whole module, the same way `roc format` does. Returns [None] if the module doesn't
+/// parse (the user is probably in the middle of typing something), or if formatting it would
+/// produce code that doesn't parse; unlike `roc format`, we have no good way to report that
+/// formatting bug, and leaving the document alone is better than breaking it.
+pub(crate) fn format(src: &str) -> Option<String> {
+    let arena = Bump::new();
+
+    let ast = arena.alloc(parse_all(&arena, src).ok()?);
+    let mut buf = Buf::new_in(&arena);
+    fmt_all(&mut buf, ast);
+
+    parse_all(&arena, buf.as_str()).ok()?;
+
+    Some(buf.as_str().to_string())
+}
+
+fn parse_all<'a>(arena: &'a Bump, src: &'a str) -> Result<Ast<'a>, SyntaxError<'a>> {
+    let (module, state) = module::parse_header(arena, State::new(src.as_bytes()))
+        .map_err(|e| SyntaxError::Header(e.problem))?;
+
+    let (_, defs, _) = module_defs().parse(arena, state, 0).map_err(|(_, e)| e)?;
+
+    Ok(Ast { module, defs })
+}
+
+fn fmt_all<'a>(buf: &mut Buf<'a>, ast: &'a Ast) {
+    fmt_module(buf, &ast.module);
+
+    fmt_defs(buf, &ast.defs, 0);
+
+    buf.fmt_end_of_file();
+}
@@ -0,0 +1,32 @@
+//! A language server for Roc, which editors talk to using the
+//! [Language Server Protocol](https://microsoft.github.io/language-server-protocol/) over stdin
+//! and stdout. It publishes diagnostics whenever a document changes, and answers requests for
+//! hover types, go-to-definition, formatting, and semantic highlighting.
+//!
+//! Requests are handled one at a time, in the order they arrive. Checking a document is fast
+//! enough that doing it on every change keeps up with typing, and answering requests from the
+//! results of the latest check means every answer is consistent with the latest text.
+mod analysis;
+mod convert;
+mod format;
+mod protocol;
+mod rpc;
+mod semantic_tokens;
+mod server;
+
+pub use server::Server;
+
+use roc_packaging::cache::{self, RocCacheDir};
+use std::io;
+
+/// Serves requests over stdin and stdout until the client tells us to exit. Returns the exit code
+/// the process should exit with.
+pub fn run_stdio() -> io::Result<i32> {
+    let roc_cache_dir = cache::roc_cache_dir();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let mut server = Server::new(stdout.lock(), RocCacheDir::Persistent(&roc_cache_dir));
+
+    server.serve(stdin.lock())
+}
@@ -0,0 +1,116 @@
+//! Reading and writing JSON-RPC messages, which the protocol frames with HTTP-style headers:
+//!
+//! ```text
+//! Content-Length: 58\r\n
+//! \r\n
+//! {"jsonrpc":"2.0","id":1,"method":"shutdown","params":null}
+//! ```
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+pub(crate) const PARSE_ERROR: i64 = -32700;
+pub(crate) const INVALID_REQUEST: i64 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+pub(crate) const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+#[derive(Debug)]
+pub(crate) enum Message {
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+    /// A response to a request of ours. We never send any requests, so these are ignored.
+    Response,
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    id: Option<Value>,
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+}
+
+impl Message {
+    fn from_raw(raw: RawMessage) -> Self {
+        match (raw.id, raw.method) {
+            (Some(id), Some(method)) => Message::Request {
+                id,
+                method,
+                params: raw.params,
+            },
+            (None, Some(method)) => Message::Notification {
+                method,
+                params: raw.params,
+            },
+            (_, None) => Message::Response,
+        }
+    }
+}
+
+/// Reads the next message, or returns `Ok(None)` once the input has been closed.
+/// Returns an error with [`io::ErrorKind::InvalidData`] for malformed messages.
+pub(crate) fn read_message(input: &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut content_length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            // An empty line ends the headers, unless there haven't been any yet.
+            if content_length.is_some() {
+                break;
+            }
+
+            continue;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            // Content-Type is the only other header, and it has only ever had one valid value.
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    input.read_exact(&mut content)?;
+
+    match serde_json::from_slice::<RawMessage>(&content) {
+        Ok(raw) => Ok(Some(Message::from_raw(raw))),
+        Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+    }
+}
+
+pub(crate) fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = serde_json::to_string(message)?;
+
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+pub(crate) fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub(crate) fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub(crate) fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
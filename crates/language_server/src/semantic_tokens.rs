@@ -0,0 +1,106 @@
+use crate::convert::LineIndex;
+use crate::protocol::SemanticTokens;
+use roc_highlight::HighlightKind;
+use roc_parse::highlight::Token;
+
+/// The token types we report, in the order the client should index them by.
+pub(crate) const TOKEN_TYPES: [&str; 7] = [
+    "comment", "string", "number", "keyword", "operator", "type", "variable",
+];
+
+fn token_type(token: Token) -> Option<u32> {
+    let name = match HighlightKind::of(token)? {
+        HighlightKind::Comment => "comment",
+        HighlightKind::Literal if token == Token::Number => "number",
+        HighlightKind::Literal => "string",
+        HighlightKind::Keyword => "keyword",
+        HighlightKind::Operator => "operator",
+        HighlightKind::UpperIdent => "type",
+        HighlightKind::LowerIdent => "variable",
+        // Editors already make punctuation look like punctuation.
+        HighlightKind::Delimiter => return None,
+    };
+
+    TOKEN_TYPES
+        .iter()
+        .position(|token_type| *token_type == name)
+        .map(|index| index as u32)
+}
+
+/// Tokens are encoded as five numbers each: the line (relative to the previous token), the start
+/// character (relative to the previous token, if it is on the same line), the length, the token
+/// type, and its modifiers (of which we have none).
+pub(crate) fn semantic_tokens(src: &str) -> SemanticTokens {
+    let lines = LineIndex::new(src);
+    let mut data = Vec::new();
+    let mut previous_line = 0;
+    let mut previous_character = 0;
+
+    for loc_token in roc_parse::highlight::highlight(src) {
+        let token_type = match token_type(loc_token.value) {
+            Some(token_type) => token_type,
+            None => continue,
+        };
+
+        let mut start = loc_token.byte_range().start;
+        let end = loc_token.byte_range().end;
+
+        // Not every client supports tokens that span lines (like multiline strings),
+        // so we split those up into one token per line.
+        while start < end {
+            let line_end = src[start..end]
+                .find('\n')
+                .map_or(end, |index| start + index);
+
+            let start_position = lines.position(start);
+            let end_position = lines.position(line_end);
+            let length = end_position.character - start_position.character;
+
+            if length > 0 {
+                let delta_line = start_position.line - previous_line;
+                let delta_character = if delta_line == 0 {
+                    start_position.character - previous_character
+                } else {
+                    start_position.character
+                };
+
+                data.extend([delta_line, delta_character, length, token_type, 0]);
+
+                previous_line = start_position.line;
+                previous_character = start_position.character;
+            }
+
+            start = line_end + 1;
+        }
+    }
+
+    SemanticTokens { data }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encodes_tokens_relative_to_each_other() {
+        let tokens = semantic_tokens("x = 1\n# hi\ny = \"a\"\n");
+        let number = TOKEN_TYPES.iter().position(|t| *t == "number").unwrap() as u32;
+        let comment = TOKEN_TYPES.iter().position(|t| *t == "comment").unwrap() as u32;
+        let variable = TOKEN_TYPES.iter().position(|t| *t == "variable").unwrap() as u32;
+        let keyword = TOKEN_TYPES.iter().position(|t| *t == "keyword").unwrap() as u32;
+        let string = TOKEN_TYPES.iter().position(|t| *t == "string").unwrap() as u32;
+
+        assert_eq!(
+            tokens.data,
+            vec![
+                0, 0, 1, variable, 0, //
+                0, 2, 1, keyword, 0, //
+                0, 2, 1, number, 0, //
+                1, 0, 4, comment, 0, //
+                1, 0, 1, variable, 0, //
+                0, 2, 1, keyword, 0, //
+                0, 2, 3, string, 0, //
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use crate::analysis::{self, Analysis, Diagnostics};
+use crate::convert::{path_to_uri, uri_to_path, LineIndex};
+use crate::protocol::{
+    Diagnostic, DidChangeTextDocumentParams, DidOpenTextDocumentParams, PublishDiagnosticsParams,
+    TextDocumentParams, TextDocumentPositionParams, TextEdit,
+};
+use crate::rpc::{self, Message};
+use crate::semantic_tokens::{semantic_tokens, TOKEN_TYPES};
+use roc_packaging::cache::RocCacheDir;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+/// A document the client has opened, whose contents may not have been saved yet.
+struct Document {
+    text: String,
+    analysis: Option<Analysis>,
+    /// The files we last published diagnostics for on behalf of this document, so that we can
+    /// clear them once they no longer have any problems.
+    published: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Uninitialized,
+    Running,
+    ShuttingDown,
+}
+
+/// A failed request, which is reported back to the client as an error response.
+struct RequestError {
+    code: i64,
+    message: String,
+}
+
+impl RequestError {
+    fn invalid_params(error: serde_json::Error) -> Self {
+        Self {
+            code: rpc::INVALID_PARAMS,
+            message: error.to_string(),
+        }
+    }
+}
+
+pub struct Server<'a, W: Write> {
+    output: W,
+    roc_cache_dir: RocCacheDir<'a>,
+    state: State,
+    documents: HashMap<String, Document>,
+}
+
+impl<'a, W: Write> Server<'a, W> {
+    pub fn new(output: W, roc_cache_dir: RocCacheDir<'a>) -> Self {
+        Self {
+            output,
+            roc_cache_dir,
+            state: State::Uninitialized,
+            documents: HashMap::default(),
+        }
+    }
+
+    /// Handles messages until the client sends `exit` (or closes the input), and returns the exit
+    /// code the server process should use: 0 if the client asked us to shut down first, 1 if not.
+    pub fn serve(&mut self, mut input: impl BufRead) -> io::Result<i32> {
+        loop {
+            let message = match rpc::read_message(&mut input) {
+                Ok(Some(message)) => message,
+                Ok(None) => return Ok(1),
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    let response =
+                        rpc::error_response(Value::Null, rpc::PARSE_ERROR, &error.to_string());
+                    rpc::write_message(&mut self.output, &response)?;
+
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            match message {
+                Message::Request { id, method, params } => {
+                    let response = match self.handle_request(&method, params) {
+                        Ok(result) => rpc::response(id, result),
+                        Err(RequestError { code, message }) => {
+                            rpc::error_response(id, code, &message)
+                        }
+                    };
+
+                    rpc::write_message(&mut self.output, &response)?;
+                }
+                Message::Notification { method, .. } if method == "exit" => {
+                    return Ok(if self.state == State::ShuttingDown {
+                        0
+                    } else {
+                        1
+                    });
+                }
+                Message::Notification { method, params } => {
+                    // Notifications have no response, so there is nowhere to report invalid ones.
+                    if self.state == State::Running {
+                        self.handle_notification(&method, params)?;
+                    }
+                }
+                Message::Response => {}
+            }
+        }
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, RequestError> {
+        match (self.state, method) {
+            (State::Uninitialized, "initialize") => {
+                self.state = State::Running;
+
+                Ok(initialize_result())
+            }
+            (State::Uninitialized, _) => Err(RequestError {
+                code: rpc::SERVER_NOT_INITIALIZED,
+                message: "The server has not been initialized yet.".to_string(),
+            }),
+            (State::ShuttingDown, _) => Err(RequestError {
+                code: rpc::INVALID_REQUEST,
+                message: "The server is shutting down.".to_string(),
+            }),
+            (State::Running, "shutdown") => {
+                self.state = State::ShuttingDown;
+
+                Ok(Value::Null)
+            }
+            (State::Running, "textDocument/hover") => {
+                let params: TextDocumentPositionParams = parse_params(params)?;
+                let hover = self
+                    .analysis_mut(&params.text_document.uri)
+                    .and_then(|analysis| analysis.hover(params.position));
+
+                Ok(json!(hover))
+            }
+            (State::Running, "textDocument/definition") => {
+                let params: TextDocumentPositionParams = parse_params(params)?;
+                let location = self
+                    .analysis_mut(&params.text_document.uri)
+                    .and_then(|analysis| analysis.definition(params.position));
+
+                Ok(json!(location))
+            }
+            (State::Running, "textDocument/formatting") => {
+                let params: TextDocumentParams = parse_params(params)?;
+                let edits = self
+                    .documents
+                    .get(&params.text_document.uri)
+                    .and_then(|document| format_edits(&document.text));
+
+                Ok(json!(edits))
+            }
+            (State::Running, "textDocument/semanticTokens/full") => {
+                let params: TextDocumentParams = parse_params(params)?;
+                let tokens = self
+                    .documents
+                    .get(&params.text_document.uri)
+                    .map(|document| semantic_tokens(&document.text));
+
+                Ok(json!(tokens))
+            }
+            (State::Running, _) => Err(RequestError {
+                code: rpc::METHOD_NOT_FOUND,
+                message: format!("Unsupported method: {method}"),
+            }),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) -> io::Result<()> {
+        match method {
+            "textDocument/didOpen" => {
+                if let Ok(params) = serde_json::from_value::<DidOpenTextDocumentParams>(params) {
+                    let item = params.text_document;
+
+                    self.update_document(item.uri, item.text)?;
+                }
+            }
+            "textDocument/didChange" => {
+                if let Ok(params) = serde_json::from_value::<DidChangeTextDocumentParams>(params) {
+                    // With full document sync, only the last change matters.
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.update_document(params.text_document.uri, change.text)?;
+                    }
+                }
+            }
+            "textDocument/didClose" => {
+                if let Ok(params) = serde_json::from_value::<TextDocumentParams>(params) {
+                    if let Some(document) = self.documents.remove(&params.text_document.uri) {
+                        for path in document.published {
+                            self.publish_diagnostics(path_to_uri(&path), Vec::new())?;
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Everything else (like `initialized` and `didSave`) needs no action.
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_document(&mut self, uri: String, text: String) -> io::Result<()> {
+        let previous = self.documents.remove(&uri);
+
+        // We can only check documents that are files (as opposed to, say, unsaved buffers),
+        // because the loader needs to know where to look for the modules they import.
+        let checked =
+            uri_to_path(&uri).and_then(|path| analysis::check(&path, &text, self.roc_cache_dir));
+
+        let (analysis, published) = match (checked, previous) {
+            (Some(checked), previous) => {
+                let previously_published = previous.map(|doc| doc.published).unwrap_or_default();
+                let published = self.publish_all(checked.diagnostics, previously_published)?;
+
+                (checked.analysis, published)
+            }
+            // Either the compiler crashed (in which case what we knew about the previous version
+            // of the document is better than nothing, since the user is likely to keep typing),
+            // or the document isn't a file, and there never was anything to know.
+            (None, Some(previous)) => (previous.analysis, previous.published),
+            (None, None) => (None, Vec::new()),
+        };
+
+        self.documents.insert(
+            uri,
+            Document {
+                text,
+                analysis,
+                published,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Publishes the given diagnostics, and clears the ones we previously published for files
+    /// that are no longer involved. Returns the files we published diagnostics for.
+    fn publish_all(
+        &mut self,
+        diagnostics: Diagnostics,
+        previously_published: Vec<PathBuf>,
+    ) -> io::Result<Vec<PathBuf>> {
+        for path in previously_published {
+            if !diagnostics.contains_key(&path) {
+                self.publish_diagnostics(path_to_uri(&path), Vec::new())?;
+            }
+        }
+
+        let mut published = Vec::with_capacity(diagnostics.len());
+
+        for (path, file_diagnostics) in diagnostics {
+            self.publish_diagnostics(path_to_uri(&path), file_diagnostics)?;
+            published.push(path);
+        }
+
+        Ok(published)
+    }
+
+    fn publish_diagnostics(&mut self, uri: String, diagnostics: Vec<Diagnostic>) -> io::Result<()> {
+        let params = PublishDiagnosticsParams { uri, diagnostics };
+        let notification = rpc::notification("textDocument/publishDiagnostics", json!(params));
+
+        rpc::write_message(&mut self.output, &notification)
+    }
+
+    fn analysis_mut(&mut self, uri: &str) -> Option<&mut Analysis> {
+        self.documents.get_mut(uri)?.analysis.as_mut()
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RequestError> {
+    serde_json::from_value(params).map_err(RequestError::invalid_params)
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            // Full document sync: every change notification contains the whole document.
+            "textDocumentSync": 1,
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "documentFormattingProvider": true,
+            "semanticTokensProvider": {
+                "legend": {
+                    "tokenTypes": TOKEN_TYPES,
+                    "tokenModifiers": [],
+                },
+                "full": true,
+            },
+        },
+        "serverInfo": {
+            "name": "roc_language_server",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// Formatting replaces the whole document, since that's what the formatter produces.
+fn format_edits(text: &str) -> Option<Vec<TextEdit>> {
+    let formatted = crate::format::format(text)?;
+
+    if formatted == text {
+        Some(Vec::new())
+    } else {
+        Some(vec![TextEdit {
+            range: LineIndex::new(text).full_range(),
+            new_text: formatted,
+        }])
+    }
+}
@@ -0,0 +1,477 @@
+//! Drives the language server the way an editor would: by sending it a script of messages,
+//! and checking the messages it sends back.
+#[macro_use]
+extern crate indoc;
+#[macro_use]
+extern crate pretty_assertions;
+
+#[cfg(test)]
+mod test_language_server {
+    use roc_language_server::Server;
+    use roc_packaging::cache::RocCacheDir;
+    use serde_json::{json, Value};
+    use std::io::Cursor;
+    use std::path::Path;
+
+    /// A scripted client, which records the messages it will send.
+    struct Client {
+        input: Vec<u8>,
+        next_id: u64,
+    }
+
+    impl Client {
+        fn new() -> Self {
+            let mut client = Client {
+                input: Vec::new(),
+                next_id: 0,
+            };
+
+            client.request("initialize", json!({ "capabilities": {} }));
+            client.notify("initialized", json!({}));
+
+            client
+        }
+
+        fn send(&mut self, message: Value) {
+            let content = message.to_string();
+
+            self.input
+                .extend(format!("Content-Length: {}\r\n\r\n{}", content.len(), content).bytes());
+        }
+
+        /// Returns the id of the request, so that its response can be found.
+        fn request(&mut self, method: &str, params: Value) -> u64 {
+            self.next_id += 1;
+            self.send(
+                json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params }),
+            );
+
+            self.next_id
+        }
+
+        fn notify(&mut self, method: &str, params: Value) {
+            self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+        }
+
+        fn open(&mut self, uri: &str, text: &str) {
+            self.notify(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": { "uri": uri, "languageId": "roc", "version": 1, "text": text }
+                }),
+            );
+        }
+
+        fn at(&mut self, method: &str, uri: &str, line: u32, character: u32) -> u64 {
+            self.request(
+                method,
+                json!({
+                    "textDocument": { "uri": uri },
+                    "position": { "line": line, "character": character }
+                }),
+            )
+        }
+
+        /// Shuts the server down, and returns its exit code and everything it sent back.
+        fn finish(mut self) -> (i32, Vec<Value>) {
+            self.request("shutdown", Value::Null);
+            self.notify("exit", Value::Null);
+
+            let mut output = Vec::new();
+            let exit_code = Server::new(&mut output, RocCacheDir::Disallowed)
+                .serve(Cursor::new(self.input))
+                .unwrap();
+
+            (exit_code, parse_output(&output))
+        }
+    }
+
+    fn parse_output(mut output: &[u8]) -> Vec<Value> {
+        let mut messages = Vec::new();
+
+        while !output.is_empty() {
+            let header_end = output
+                .windows(4)
+                .position(|window| window == b"\r\n\r\n")
+                .expect("every message has a header");
+            let header = std::str::from_utf8(&output[..header_end]).unwrap();
+            let length: usize = header
+                .strip_prefix("Content-Length: ")
+                .expect("the only header is the content length")
+                .parse()
+                .unwrap();
+
+            let content = &output[header_end + 4..header_end + 4 + length];
+            messages.push(serde_json::from_slice(content).unwrap());
+
+            output = &output[header_end + 4 + length..];
+        }
+
+        messages
+    }
+
+    fn result(messages: &[Value], id: u64) -> &Value {
+        let response = messages
+            .iter()
+            .find(|message| message["id"] == json!(id))
+            .unwrap_or_else(|| panic!("no response to request {id}"));
+
+        &response["result"]
+    }
+
+    fn diagnostics<'a>(messages: &'a [Value], uri: &str) -> &'a Value {
+        let notification = messages
+            .iter()
+            .rev()
+            .find(|message| {
+                message["method"] == "textDocument/publishDiagnostics"
+                    && message["params"]["uri"] == uri
+            })
+            .unwrap_or_else(|| panic!("no diagnostics were published for {uri}"));
+
+        &notification["params"]["diagnostics"]
+    }
+
+    fn file_uri(dir: &Path, name: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, "").unwrap();
+
+        // Canonicalize to get the same path the loader reports, e.g. on macOS where /tmp is a link.
+        let path = path.canonicalize().unwrap();
+
+        format!("file://{}", path.to_str().unwrap())
+    }
+
+    #[test]
+    fn initializes_and_exits() {
+        let client = Client::new();
+        let (exit_code, messages) = client.finish();
+
+        assert_eq!(exit_code, 0);
+
+        let capabilities = &result(&messages, 1)["capabilities"];
+        assert_eq!(capabilities["hoverProvider"], json!(true));
+        assert_eq!(capabilities["definitionProvider"], json!(true));
+        assert_eq!(capabilities["documentFormattingProvider"], json!(true));
+        assert_eq!(capabilities["textDocumentSync"], json!(1));
+    }
+
+    #[test]
+    fn exit_without_shutdown_fails() {
+        let mut client = Client::new();
+        client.notify("exit", Value::Null);
+
+        let mut output = Vec::new();
+        let exit_code = Server::new(&mut output, RocCacheDir::Disallowed)
+            .serve(Cursor::new(client.input))
+            .unwrap();
+
+        assert_eq!(exit_code, 1);
+    }
+
+    #[test]
+    fn requests_before_initialize_fail() {
+        let mut client = Client {
+            input: Vec::new(),
+            next_id: 0,
+        };
+        let id = client.request("textDocument/hover", json!({}));
+        client.request("initialize", json!({ "capabilities": {} }));
+
+        let (_, messages) = client.finish();
+        let response = messages
+            .iter()
+            .find(|message| message["id"] == json!(id))
+            .unwrap();
+
+        assert_eq!(response["error"]["code"], json!(-32002));
+    }
+
+    #[test]
+    fn unknown_requests_fail() {
+        let mut client = Client::new();
+        let id = client.request("textDocument/frobnicate", json!({}));
+
+        let (_, messages) = client.finish();
+        let response = messages
+            .iter()
+            .find(|message| message["id"] == json!(id))
+            .unwrap();
+
+        assert_eq!(response["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    fn publishes_no_diagnostics_for_valid_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            indoc!(
+                r#"
+                interface Main
+                    exposes [x]
+                    imports []
+
+                x = 1
+                "#
+            ),
+        );
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(diagnostics(&messages, &uri), &json!([]));
+    }
+
+    #[test]
+    fn publishes_type_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            indoc!(
+                r#"
+                interface Main
+                    exposes [x]
+                    imports []
+
+                x : Str
+                x = 1
+                "#
+            ),
+        );
+
+        let (_, messages) = client.finish();
+        let diagnostics = diagnostics(&messages, &uri).as_array().unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["severity"], json!(1));
+        assert_eq!(diagnostics[0]["code"], json!("TYPE MISMATCH"));
+        assert_eq!(diagnostics[0]["source"], json!("roc"));
+        assert_eq!(
+            diagnostics[0]["range"],
+            json!({
+                "start": { "line": 5, "character": 4 },
+                "end": { "line": 5, "character": 5 }
+            })
+        );
+    }
+
+    #[test]
+    fn publishes_syntax_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            indoc!(
+                r#"
+                interface Main
+                    exposes [x]
+                    imports []
+
+                x = (1
+                "#
+            ),
+        );
+
+        let (_, messages) = client.finish();
+        let diagnostics = diagnostics(&messages, &uri).as_array().unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["severity"], json!(1));
+    }
+
+    #[test]
+    fn clears_fixed_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            "interface Main exposes [x] imports []\n\nx : Str\nx = 1\n",
+        );
+        client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": "interface Main exposes [x] imports []\n\nx : Str\nx = \"\"\n" }]
+            }),
+        );
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(diagnostics(&messages, &uri), &json!([]));
+    }
+
+    #[test]
+    fn hover_shows_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            indoc!(
+                r#"
+                interface Main
+                    exposes [greet]
+                    imports []
+
+                greet = \name -> Str.concat "Hi, " name
+                "#
+            ),
+        );
+        let on_def = client.at("textDocument/hover", &uri, 4, 1);
+        let on_arg = client.at("textDocument/hover", &uri, 4, 35);
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(
+            result(&messages, on_def)["contents"]["value"],
+            json!("```roc\ngreet : Str -> Str\n```")
+        );
+        assert_eq!(
+            result(&messages, on_arg)["contents"]["value"],
+            json!("```roc\nname : Str\n```")
+        );
+    }
+
+    #[test]
+    fn definition_finds_local_definitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            indoc!(
+                r#"
+                interface Main
+                    exposes [y]
+                    imports []
+
+                x = 1
+
+                y = x + 1
+                "#
+            ),
+        );
+        let id = client.at("textDocument/definition", &uri, 6, 4);
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(
+            result(&messages, id),
+            &json!({
+                "uri": uri,
+                "range": {
+                    "start": { "line": 4, "character": 0 },
+                    "end": { "line": 4, "character": 1 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn definition_finds_imported_definitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let dep_uri = file_uri(dir.path(), "Dep.roc");
+        std::fs::write(
+            dir.path().join("Dep.roc"),
+            "interface Dep exposes [answer] imports []\n\nanswer = 42\n",
+        )
+        .unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(
+            &uri,
+            "interface Main exposes [x] imports [Dep]\n\nx = Dep.answer\n",
+        );
+        let id = client.at("textDocument/definition", &uri, 2, 6);
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(
+            result(&messages, id),
+            &json!({
+                "uri": dep_uri,
+                "range": {
+                    "start": { "line": 2, "character": 0 },
+                    "end": { "line": 2, "character": 6 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn formatting_replaces_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+        let text = "interface Main exposes [x] imports []\n\nx=1\n";
+
+        let mut client = Client::new();
+        client.open(&uri, text);
+        let id = client.request(
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": uri }, "options": { "tabSize": 4, "insertSpaces": true } }),
+        );
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(
+            result(&messages, id),
+            &json!([{
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 3, "character": 0 }
+                },
+                "newText": "interface Main exposes [x] imports []\n\nx = 1\n"
+            }])
+        );
+    }
+
+    #[test]
+    fn formatting_leaves_broken_documents_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(&uri, "interface Main exposes [x] imports []\n\nx = (1\n");
+        let id = client.request(
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": uri }, "options": { "tabSize": 4, "insertSpaces": true } }),
+        );
+
+        let (_, messages) = client.finish();
+
+        assert_eq!(result(&messages, id), &Value::Null);
+    }
+
+    #[test]
+    fn semantic_tokens_cover_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = file_uri(dir.path(), "Main.roc");
+
+        let mut client = Client::new();
+        client.open(&uri, "interface Main exposes [x] imports []\n\nx = 1\n");
+        let id = client.request(
+            "textDocument/semanticTokens/full",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+
+        let (_, messages) = client.finish();
+        let data = result(&messages, id)["data"].as_array().unwrap();
+
+        // Five numbers per token.
+        assert!(!data.is_empty());
+        assert_eq!(data.len() % 5, 0);
+    }
+}
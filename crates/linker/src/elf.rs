@@ -7,7 +7,7 @@ use object::{
     ObjectSymbol, RelocationKind, RelocationTarget, Section, SectionIndex, SectionKind, Symbol,
     SymbolIndex, SymbolSection,
 };
-use roc_collections::all::MutMap;
+use roc_collections::all::{MutMap, MutSet};
 use roc_error_macros::{internal_error, user_error};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -20,7 +20,8 @@ use std::{
 
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -28,6 +29,66 @@ const MIN_SECTION_ALIGNMENT: usize = 0x40;
 // TODO: Analyze if this offset is always correct.
 const PLT_ADDRESS_OFFSET: u64 = 0x10;
 
+/// The instruction sets we can perform surgery on.
+/// The relocation types, PLT layout and branch encodings all depend on this.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+enum ElfArch {
+    #[default]
+    X86_64,
+    Aarch64,
+}
+
+impl ElfArch {
+    fn r_none(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_NONE,
+            ElfArch::Aarch64 => elf::R_AARCH64_NONE,
+        }
+    }
+
+    fn r_relative(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_RELATIVE,
+            ElfArch::Aarch64 => elf::R_AARCH64_RELATIVE,
+        }
+    }
+
+    fn r_glob_dat(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_GLOB_DAT,
+            ElfArch::Aarch64 => elf::R_AARCH64_GLOB_DAT,
+        }
+    }
+
+    fn r_jump_slot(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_JUMP_SLOT,
+            ElfArch::Aarch64 => elf::R_AARCH64_JUMP_SLOT,
+        }
+    }
+
+    /// The first PLT entry is special: it calls the dynamic linker to resolve symbols lazily.
+    fn plt_header_size(self) -> u64 {
+        match self {
+            ElfArch::X86_64 => 0x10,
+            ElfArch::Aarch64 => 0x20,
+        }
+    }
+
+    /// The amount we shift the host's contents by to make room for more program headers.
+    /// On aarch64, `adrp` instructions compute addresses relative to the 4KiB page they are in,
+    /// so everything must move by whole pages for them to stay correct.
+    fn shift_alignment(self) -> u64 {
+        match self {
+            ElfArch::X86_64 => MIN_SECTION_ALIGNMENT as u64,
+            ElfArch::Aarch64 => AARCH64_PAGE_SIZE,
+        }
+    }
+}
+
+const AARCH64_PAGE_SIZE: u64 = 0x1000;
+const AARCH64_NOP: u32 = 0xd503201f;
+
 struct ElfDynamicDeps {
     got_app_syms: Vec<(String, usize)>,
     got_sections: Vec<(usize, usize)>,
@@ -72,6 +133,7 @@ struct Metadata {
     symbol_table_section_offset: u64,
     symbol_table_size: u64,
     _macho_cmd_loc: u64,
+    architecture: ElfArch,
 }
 
 impl Metadata {
@@ -165,10 +227,15 @@ struct Surgeries<'a> {
     surgeries: MutMap<String, Vec<SurgeryEntry>>,
     app_func_addresses: MutMap<u64, &'a str>,
     indirect_warning_given: bool,
+    architecture: ElfArch,
 }
 
 impl<'a> Surgeries<'a> {
-    fn new(application_symbols: &[Symbol], app_func_addresses: MutMap<u64, &'a str>) -> Self {
+    fn new(
+        application_symbols: &[Symbol],
+        app_func_addresses: MutMap<u64, &'a str>,
+        architecture: ElfArch,
+    ) -> Self {
         let mut surgeries = MutMap::default();
 
         // for each symbol that the host expects from the application
@@ -182,6 +249,7 @@ impl<'a> Surgeries<'a> {
             surgeries,
             app_func_addresses,
             indirect_warning_given: false,
+            architecture,
         }
     }
 
@@ -239,6 +307,19 @@ impl<'a> Surgeries<'a> {
                 internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
             }
         };
+
+        if let ElfArch::Aarch64 = self.architecture {
+            self.append_aarch64_branches(
+                object_bytes,
+                sec,
+                file_offset,
+                compressed,
+                &data,
+                verbose,
+            );
+            return;
+        }
+
         let mut decoder = Decoder::with_ip(64, &data, sec.address(), DecoderOptions::NONE);
         let mut inst = Instruction::default();
 
@@ -329,6 +410,111 @@ impl<'a> Surgeries<'a> {
             }
         }
     }
+
+    /// Every aarch64 instruction is 4 bytes, and `b`/`bl` are the only ones that can branch
+    /// directly to a PLT entry, so we don't need a full disassembler here.
+    fn append_aarch64_branches(
+        &mut self,
+        object_bytes: &[u8],
+        sec: &Section,
+        file_offset: u64,
+        compressed: bool,
+        data: &[u8],
+        verbose: bool,
+    ) {
+        for (i, word) in data.chunks_exact(4).enumerate() {
+            let inst = u32::from_le_bytes(word.try_into().unwrap());
+            let branch_offset = match aarch64_decode_branch26(inst) {
+                Some(branch_offset) => branch_offset,
+                None => continue,
+            };
+
+            let inst_address = sec.address() + 4 * i as u64;
+            let target = (inst_address as i64 + branch_offset) as u64;
+            if let Some(func_name) = self.app_func_addresses.get(&target) {
+                if compressed {
+                    internal_error!(
+                        "Surgical linking does not work with compressed text sections: {:+x?}",
+                        sec
+                    );
+                }
+
+                let offset = file_offset + 4 * i as u64;
+                if verbose {
+                    println!("Found branch from {inst_address:+x} to {target:+x}({func_name})");
+                    println!("\tNeed to surgically replace 4 bytes at file offset {offset:+x}");
+                    println!(
+                        "\tIts current value is {:+x?}",
+                        &object_bytes[offset as usize..][..4]
+                    )
+                }
+
+                // aarch64 branches are relative to the address of the branch itself
+                self.surgeries
+                    .get_mut(*func_name)
+                    .unwrap()
+                    .push(SurgeryEntry {
+                        file_offset: offset,
+                        virtual_offset: VirtualOffset::Relative(inst_address),
+                        size: 4,
+                    });
+            }
+        }
+    }
+}
+
+/// Decode the offset of a `b` or `bl` instruction, in bytes
+pub(crate) fn aarch64_decode_branch26(inst: u32) -> Option<i64> {
+    // b is 0b000101, bl is 0b100101, followed by a 26-bit immediate
+    if inst & 0x7c00_0000 == 0x1400_0000 {
+        let imm26 = ((inst << 6) as i32 >> 6) as i64;
+        Some(imm26 * 4)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn aarch64_encode_branch26(inst: u32, offset: i64) -> u32 {
+    if offset % 4 != 0 || !(-(1 << 27)..(1 << 27)).contains(&offset) {
+        internal_error!("aarch64 branch offset {offset:+x} is out of range");
+    }
+
+    (inst & 0xfc00_0000) | ((offset >> 2) as u32 & 0x03ff_ffff)
+}
+
+/// Set the immediate of an `adrp` instruction, given the distance between two 4KiB pages
+pub(crate) fn aarch64_encode_adrp(inst: u32, page_delta: i64) -> u32 {
+    let imm = page_delta >> 12;
+    if !(-(1 << 20)..(1 << 20)).contains(&imm) {
+        internal_error!("aarch64 adrp page offset {page_delta:+x} is out of range");
+    }
+
+    let immlo = (imm as u32 & 0x3) << 29;
+    let immhi = ((imm >> 2) as u32 & 0x7ffff) << 5;
+    (inst & !((0x3 << 29) | (0x7ffff << 5))) | immlo | immhi
+}
+
+/// Set the 12-bit unsigned immediate of an `add` or load/store instruction
+pub(crate) fn aarch64_encode_imm12(inst: u32, imm12: u32) -> u32 {
+    (inst & !(0xfff << 10)) | ((imm12 & 0xfff) << 10)
+}
+
+/// We don't create a GOT for the app, so we turn a load of a symbol's address from the GOT
+/// (`ldr xd, [xn, :got_lo12:sym]`) into a calculation of the address (`add xd, xn, :lo12:sym`).
+fn aarch64_relax_got_load(inst: u32) -> u32 {
+    const LDR_X_IMM: u32 = 0xf940_0000;
+    const ADD_X_IMM: u32 = 0x9100_0000;
+
+    if inst & 0xffc0_0000 != LDR_X_IMM {
+        internal_error!("Expected a 64-bit ldr for a GOT relocation, but found {inst:#010x}");
+    }
+
+    // keep the source and destination registers
+    ADD_X_IMM | (inst & 0x3ff)
+}
+
+pub(crate) fn aarch64_page(address: i64) -> i64 {
+    address & !(AARCH64_PAGE_SIZE as i64 - 1)
 }
 
 /// Constructs a `Metadata` from a host executable binary, and writes it to disk
@@ -351,8 +537,17 @@ pub(crate) fn preprocess_elf(
         }
     };
 
+    let architecture = match exec_obj.architecture() {
+        object::Architecture::X86_64 => ElfArch::X86_64,
+        object::Architecture::Aarch64 => ElfArch::Aarch64,
+        other => {
+            internal_error!("The surgical linker does not support {:?} ELF hosts", other);
+        }
+    };
+
     let mut md = Metadata {
         roc_symbol_vaddresses: collect_roc_definitions(&exec_obj),
+        architecture,
         ..Default::default()
     };
 
@@ -427,7 +622,7 @@ pub(crate) fn preprocess_elf(
                 }
             })
             .filter_map(|(_, reloc)| {
-                if let RelocationKind::Elf(7) = reloc.kind() {
+                if RelocationKind::Elf(architecture.r_jump_slot()) == reloc.kind() {
                     Some(reloc)
                 } else {
                     None
@@ -436,8 +631,10 @@ pub(crate) fn preprocess_elf(
     for (i, reloc) in plt_relocs.enumerate() {
         for symbol in app_syms.iter() {
             if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
-                let func_address = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_address;
-                let func_offset = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_offset;
+                let plt_entry_offset =
+                    i as u64 * PLT_ADDRESS_OFFSET + architecture.plt_header_size();
+                let func_address = plt_entry_offset + plt_address;
+                let func_offset = plt_entry_offset + plt_offset;
                 app_func_addresses.insert(func_address, symbol.name().unwrap());
                 md.plt_addresses.insert(
                     symbol.name().unwrap().to_string(),
@@ -473,7 +670,7 @@ pub(crate) fn preprocess_elf(
     // look at the text (i.e. code) sections and see collect work needs to be done
     let text_disassembly_start = Instant::now();
 
-    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses);
+    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses, architecture);
     surgeries.append_text_sections(exec_data, &exec_obj, verbose);
     md.surgeries = surgeries.surgeries;
 
@@ -600,11 +797,14 @@ fn gen_elf_le(
         println!("SH Entry Count: {sh_num}");
     }
 
+    let arch = md.architecture;
+
     // Copy header and shift everything to enable more program sections.
     let added_header_count = 3;
+    let shift_alignment = arch.shift_alignment();
     md.added_byte_count = ph_ent_size as u64 * added_header_count;
-    md.added_byte_count = md.added_byte_count
-        + (MIN_SECTION_ALIGNMENT as u64 - md.added_byte_count % MIN_SECTION_ALIGNMENT as u64);
+    md.added_byte_count =
+        md.added_byte_count + (shift_alignment - md.added_byte_count % shift_alignment);
     let ph_end = ph_offset as usize + ph_num as usize * ph_ent_size as usize;
     let physical_shift_start = ph_end as u64;
 
@@ -742,18 +942,18 @@ fn gen_elf_le(
                 rel.r_offset.set(LE, r_offset + md.added_byte_count);
                 // Deal with potential adjusts to absolute jumps.
                 // TODO: Verify other relocation types.
-                if rel.r_type(LE, false) == elf::R_X86_64_RELATIVE {
+                if rel.r_type(LE, false) == arch.r_relative() {
                     let r_addend = rel.r_addend.get(LE);
                     rel.r_addend.set(LE, r_addend + md.added_byte_count as i64);
                 }
             }
             // If the relocation goes to a roc function, we need to surgically link it and change it to relative.
             let r_type = rel.r_type(LE, false);
-            if r_type == elf::R_X86_64_GLOB_DAT {
+            if r_type == arch.r_glob_dat() {
                 let r_sym = rel.r_sym(LE, false);
                 for (name, index) in got_app_syms.iter() {
                     if *index as u32 == r_sym {
-                        rel.set_r_info(LE, false, 0, elf::R_X86_64_RELATIVE);
+                        rel.set_r_info(LE, false, 0, arch.r_relative());
                         let addend_addr = sec_offset as usize
                             + i * mem::size_of::<elf::Rela64<LE>>()
                             // This 16 skips the first 2 fields and gets to the addend field.
@@ -778,8 +978,7 @@ fn gen_elf_le(
             .filter_map(|(i, rel)| {
                 let r_type = rel.r_type(LE, false);
                 let r_sym = rel.r_sym(LE, false);
-                if r_type == elf::R_X86_64_JUMP_SLOT && app_sym_indices.contains(&(r_sym as usize))
-                {
+                if r_type == arch.r_jump_slot() && app_sym_indices.contains(&(r_sym as usize)) {
                     Some(i)
                 } else {
                     None
@@ -795,7 +994,7 @@ fn gen_elf_le(
         for i in to_remove.iter() {
             relocations.swap(*i, j);
             let r_sym = relocations[j].r_sym(LE, false);
-            relocations[j].set_r_info(LE, false, r_sym, elf::R_X86_64_NONE);
+            relocations[j].set_r_info(LE, false, r_sym, arch.r_none());
             j -= 1;
         }
 
@@ -1103,7 +1302,7 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(elf::R_X86_64_GLOB_DAT) = reloc.kind() {
+        if RelocationKind::Elf(md.architecture.r_glob_dat()) == reloc.kind() {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some((symbol.name().unwrap().to_string(), symbol.index().0));
@@ -1122,7 +1321,7 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(elf::R_X86_64_JUMP_SLOT) = reloc.kind() {
+        if RelocationKind::Elf(md.architecture.r_jump_slot()) == reloc.kind() {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some(symbol.index().0);
@@ -1455,32 +1654,28 @@ fn surgery_elf_help(
                     if let Some(target_offset) = target_offset {
                         let virt_base = section_virtual_offset + rel.0 as usize;
                         let base = section_offset + rel.0 as usize;
-                        let target: i64 = match rel.1.kind() {
-                            RelocationKind::Relative | RelocationKind::PltRelative => {
-                                target_offset - virt_base as i64 + rel.1.addend()
-                            }
-                            x => {
-                                internal_error!("Relocation Kind not yet support: {:?}", x);
-                            }
-                        };
                         if verbose {
                             println!(
                                 "\t\tRelocation base location: {base:+x} (virt: {virt_base:+x})",
                             );
-                            println!("\t\tFinal relocation target offset: {target:+x}");
                         }
-                        match rel.1.size() {
-                            32 => {
-                                let data = (target as i32).to_le_bytes();
-                                exec_mmap[base..][..4].copy_from_slice(&data);
-                            }
-                            64 => {
-                                let data = target.to_le_bytes();
-                                exec_mmap[base..][..8].copy_from_slice(&data);
-                            }
-                            other => {
-                                internal_error!("Relocation size not yet supported: {other}");
-                            }
+                        match md.architecture {
+                            ElfArch::X86_64 => apply_x86_64_relocation(
+                                exec_mmap,
+                                base,
+                                virt_base as i64,
+                                target_offset,
+                                &rel.1,
+                                verbose,
+                            ),
+                            ElfArch::Aarch64 => apply_aarch64_relocation(
+                                exec_mmap,
+                                base,
+                                virt_base as i64,
+                                target_offset,
+                                &rel.1,
+                                verbose,
+                            ),
                         }
                     } else {
                         internal_error!(
@@ -1654,8 +1849,19 @@ fn surgery_elf_help(
                 VirtualOffset::Relative(vs) => (vs + md.added_byte_count) as i64,
                 VirtualOffset::Absolute => 0,
             };
-            match s.size {
-                4 => {
+            match (md.architecture, s.size) {
+                (ElfArch::Aarch64, 4) => {
+                    let target = func_virt_offset as i64 - surgery_virt_offset;
+                    if verbose {
+                        println!("\tTarget Jump: {target:+x}");
+                    }
+                    let inst_bytes =
+                        &mut exec_mmap[(s.file_offset + md.added_byte_count) as usize..][..4];
+                    let inst = u32::from_le_bytes((&*inst_bytes).try_into().unwrap());
+                    let data = aarch64_encode_branch26(inst, target).to_le_bytes();
+                    inst_bytes.copy_from_slice(&data);
+                }
+                (ElfArch::X86_64, 4) => {
                     let target = (func_virt_offset as i64 - surgery_virt_offset) as i32;
                     if verbose {
                         println!("\tTarget Jump: {target:+x}");
@@ -1664,7 +1870,7 @@ fn surgery_elf_help(
                     exec_mmap[(s.file_offset + md.added_byte_count) as usize..][..4]
                         .copy_from_slice(&data);
                 }
-                8 => {
+                (_, 8) => {
                     let target = func_virt_offset as i64 - surgery_virt_offset;
                     if verbose {
                         println!("\tTarget Jump: {target:+x}");
@@ -1673,7 +1879,7 @@ fn surgery_elf_help(
                     exec_mmap[(s.file_offset + md.added_byte_count) as usize..][..8]
                         .copy_from_slice(&data);
                 }
-                x => {
+                (_, x) => {
                     internal_error!("Surgery size not yet supported: {}", x);
                 }
             }
@@ -1684,18 +1890,32 @@ fn surgery_elf_help(
         if let Some((plt_off, plt_vaddr)) = md.plt_addresses.get(func_name) {
             let plt_off = (*plt_off + md.added_byte_count) as usize;
             let plt_vaddr = *plt_vaddr + md.added_byte_count;
-            let jmp_inst_len = 5;
-            let target =
-                (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
-            if verbose {
-                println!("\tPLT: {plt_off:+x}, {plt_vaddr:+x}");
-                println!("\tTarget Jump: {target:+x}");
-            }
-            let data = target.to_le_bytes();
-            exec_mmap[plt_off] = 0xE9;
-            exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
-            for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
-                exec_mmap[plt_off + i] = 0x90;
+
+            if let ElfArch::Aarch64 = md.architecture {
+                let target = func_virt_offset as i64 - plt_vaddr as i64;
+                if verbose {
+                    println!("\tPLT: {plt_off:+x}, {plt_vaddr:+x}");
+                    println!("\tTarget Jump: {target:+x}");
+                }
+                let b = aarch64_encode_branch26(0x1400_0000 /* b */, target);
+                exec_mmap[plt_off..][..4].copy_from_slice(&b.to_le_bytes());
+                for i in (4..PLT_ADDRESS_OFFSET as usize).step_by(4) {
+                    exec_mmap[plt_off + i..][..4].copy_from_slice(&AARCH64_NOP.to_le_bytes());
+                }
+            } else {
+                let jmp_inst_len = 5;
+                let target =
+                    (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
+                if verbose {
+                    println!("\tPLT: {plt_off:+x}, {plt_vaddr:+x}");
+                    println!("\tTarget Jump: {target:+x}");
+                }
+                let data = target.to_le_bytes();
+                exec_mmap[plt_off] = 0xE9;
+                exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
+                for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
+                    exec_mmap[plt_off + i] = 0x90;
+                }
             }
         }
 
@@ -1737,6 +1957,301 @@ fn surgery_elf_help(
     *offset_ref = offset;
 }
 
+fn apply_x86_64_relocation(
+    exec_mmap: &mut [u8],
+    base: usize,
+    virt_base: i64,
+    target_offset: i64,
+    rel: &object::Relocation,
+    verbose: bool,
+) {
+    let target: i64 = match rel.kind() {
+        RelocationKind::Relative | RelocationKind::PltRelative => {
+            target_offset - virt_base + rel.addend()
+        }
+        x => {
+            internal_error!("Relocation Kind not yet support: {:?}", x);
+        }
+    };
+    if verbose {
+        println!("\t\tFinal relocation target offset: {target:+x}");
+    }
+    match rel.size() {
+        32 => {
+            let data = (target as i32).to_le_bytes();
+            exec_mmap[base..][..4].copy_from_slice(&data);
+        }
+        64 => {
+            let data = target.to_le_bytes();
+            exec_mmap[base..][..8].copy_from_slice(&data);
+        }
+        other => {
+            internal_error!("Relocation size not yet supported: {other}");
+        }
+    }
+}
+
+fn apply_aarch64_relocation(
+    exec_mmap: &mut [u8],
+    base: usize,
+    virt_base: i64,
+    target_offset: i64,
+    rel: &object::Relocation,
+    verbose: bool,
+) {
+    // In the usual notation for relocations: S + A is the address of the target, P is the
+    // address of the place being relocated.
+    let s_plus_a = target_offset + rel.addend();
+    let p = virt_base;
+
+    // Data relocations
+    match (rel.kind(), rel.size()) {
+        (RelocationKind::Relative, 32) => {
+            let target = s_plus_a - p;
+            if verbose {
+                println!("\t\tFinal relocation target offset: {target:+x}");
+            }
+            exec_mmap[base..][..4].copy_from_slice(&(target as i32).to_le_bytes());
+            return;
+        }
+        (RelocationKind::Relative, 64) => {
+            let target = s_plus_a - p;
+            if verbose {
+                println!("\t\tFinal relocation target offset: {target:+x}");
+            }
+            exec_mmap[base..][..8].copy_from_slice(&target.to_le_bytes());
+            return;
+        }
+        _ => {}
+    }
+
+    // Everything else patches the immediate of an instruction
+    let inst = u32::from_le_bytes(exec_mmap[base..][..4].try_into().unwrap());
+    let lo12 = (s_plus_a & 0xfff) as u32;
+    let patched = match rel.kind() {
+        RelocationKind::PltRelative | RelocationKind::Elf(elf::R_AARCH64_JUMP26) => {
+            aarch64_encode_branch26(inst, s_plus_a - p)
+        }
+        RelocationKind::Elf(
+            elf::R_AARCH64_ADR_PREL_PG_HI21
+            | elf::R_AARCH64_ADR_PREL_PG_HI21_NC
+            | elf::R_AARCH64_ADR_GOT_PAGE,
+        ) => aarch64_encode_adrp(inst, aarch64_page(s_plus_a) - aarch64_page(p)),
+        RelocationKind::Elf(elf::R_AARCH64_ADD_ABS_LO12_NC | elf::R_AARCH64_LDST8_ABS_LO12_NC) => {
+            aarch64_encode_imm12(inst, lo12)
+        }
+        // Load and store offsets are scaled by the size of the access
+        RelocationKind::Elf(elf::R_AARCH64_LDST16_ABS_LO12_NC) => {
+            aarch64_encode_imm12(inst, lo12 >> 1)
+        }
+        RelocationKind::Elf(elf::R_AARCH64_LDST32_ABS_LO12_NC) => {
+            aarch64_encode_imm12(inst, lo12 >> 2)
+        }
+        RelocationKind::Elf(elf::R_AARCH64_LDST64_ABS_LO12_NC) => {
+            aarch64_encode_imm12(inst, lo12 >> 3)
+        }
+        RelocationKind::Elf(elf::R_AARCH64_LDST128_ABS_LO12_NC) => {
+            aarch64_encode_imm12(inst, lo12 >> 4)
+        }
+        RelocationKind::Elf(elf::R_AARCH64_LD64_GOT_LO12_NC) => {
+            aarch64_encode_imm12(aarch64_relax_got_load(inst), lo12)
+        }
+        x => {
+            internal_error!("Relocation Kind not yet support: {:?}", x);
+        }
+    };
+
+    if verbose {
+        println!("\t\tFinal relocation target address: {s_plus_a:+x}");
+        println!("\t\tPatched instruction: {inst:#010x} -> {patched:#010x}");
+    }
+    exec_mmap[base..][..4].copy_from_slice(&patched.to_le_bytes());
+}
+
+// LLVM's tables of symbol indices. The ELF crate doesn't name them.
+const SHT_LLVM_CALL_GRAPH_PROFILE: u32 = 0x6fff_4c09;
+const SHT_LLVM_ADDRSIG: u32 = 0x6fff_4c03;
+
+/// Make every symbol a relocatable object defines local, unless it is in `exported`.
+///
+/// The symbol table has to list local symbols before global ones, so this reorders it
+/// and updates everything that refers to a symbol by its index.
+/// A typo in the list of exported symbols would otherwise silently produce a library which
+/// exports nothing.
+fn report_unknown_exports<'a>(unknown: impl Iterator<Item = &'a str>) {
+    let unknown: Vec<&str> = unknown.collect();
+
+    if !unknown.is_empty() {
+        user_error!(
+            "These symbols were listed as exported, but the library does not define them: {}",
+            unknown.join(", ")
+        );
+    }
+}
+
+pub(crate) fn localize_elf_symbols(object_bytes: &mut [u8], exported: &[String]) {
+    let file_header = load_struct_inplace::<elf::FileHeader64<LE>>(object_bytes, 0);
+
+    if file_header.e_ident.data != elf::ELFDATA2LSB {
+        internal_error!("Only little-endian ELF objects are supported");
+    }
+
+    let sh_offset = file_header.e_shoff.get(LE) as usize;
+    let sh_num = file_header.e_shnum.get(LE) as usize;
+
+    if sh_num == 0 {
+        internal_error!("ELF objects with extended section numbering are not supported");
+    }
+
+    let section_headers =
+        load_structs_inplace::<elf::SectionHeader64<LE>>(object_bytes, sh_offset, sh_num).to_vec();
+
+    let symtab_index = match section_headers
+        .iter()
+        .position(|header| header.sh_type.get(LE) == elf::SHT_SYMTAB)
+    {
+        Some(index) => index,
+        None => {
+            report_unknown_exports(exported.iter().map(String::as_str));
+            return;
+        }
+    };
+
+    let symtab = &section_headers[symtab_index];
+    let symtab_offset = symtab.sh_offset.get(LE) as usize;
+    let symbol_count = symtab.sh_size.get(LE) as usize / mem::size_of::<elf::Sym64<LE>>();
+    let first_global = symtab.sh_info.get(LE) as usize;
+    let strtab_offset = section_headers[symtab.sh_link.get(LE) as usize]
+        .sh_offset
+        .get(LE) as usize;
+
+    let symbols =
+        load_structs_inplace::<elf::Sym64<LE>>(object_bytes, symtab_offset, symbol_count).to_vec();
+
+    let exported_names: MutSet<&[u8]> = exported.iter().map(|name| name.as_bytes()).collect();
+    let mut found_names: MutSet<&[u8]> = MutSet::default();
+
+    let localize: Vec<bool> = symbols
+        .iter()
+        .enumerate()
+        .map(|(index, symbol)| {
+            if index < first_global
+                || matches!(symbol.st_shndx.get(LE), elf::SHN_UNDEF | elf::SHN_COMMON)
+            {
+                return false;
+            }
+
+            let name_offset = strtab_offset + symbol.st_name.get(LE) as usize;
+            let name = &object_bytes[name_offset..];
+            let name = &name[..name.iter().position(|b| *b == 0).unwrap_or(name.len())];
+
+            if exported_names.contains(name) {
+                found_names.insert(name);
+                false
+            } else {
+                true
+            }
+        })
+        .collect();
+
+    report_unknown_exports(
+        exported
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !found_names.contains(name.as_bytes())),
+    );
+
+    // Locals keep their place, the newly local symbols follow them, and the globals come last.
+    let order: Vec<usize> = (0..first_global)
+        .chain((first_global..symbol_count).filter(|index| localize[*index]))
+        .chain((first_global..symbol_count).filter(|index| !localize[*index]))
+        .collect();
+
+    let mut new_indices = vec![0u32; symbol_count];
+    for (new_index, old_index) in order.iter().enumerate() {
+        new_indices[*old_index] = new_index as u32;
+    }
+
+    let new_symbols =
+        load_structs_inplace_mut::<elf::Sym64<LE>>(object_bytes, symtab_offset, symbol_count);
+    for (new_symbol, old_index) in new_symbols.iter_mut().zip(order.iter()) {
+        *new_symbol = symbols[*old_index];
+
+        if localize[*old_index] {
+            new_symbol.st_info = (elf::STB_LOCAL << 4) | (new_symbol.st_info & 0xf);
+        }
+    }
+
+    let new_first_global = first_global + localize.iter().filter(|x| **x).count();
+    let section_header_size = mem::size_of::<elf::SectionHeader64<LE>>();
+
+    load_struct_inplace_mut::<elf::SectionHeader64<LE>>(
+        object_bytes,
+        sh_offset + symtab_index * section_header_size,
+    )
+    .sh_info
+    .set(LE, new_first_global as u32);
+
+    let remap_info = |info: u64| {
+        let new_index = new_indices[(info >> 32) as usize] as u64;
+        (new_index << 32) | (info & 0xffff_ffff)
+    };
+
+    for (index, header) in section_headers.iter().enumerate() {
+        if header.sh_link.get(LE) as usize != symtab_index {
+            continue;
+        }
+
+        let offset = header.sh_offset.get(LE) as usize;
+        let size = header.sh_size.get(LE) as usize;
+
+        match header.sh_type.get(LE) {
+            elf::SHT_RELA => {
+                let count = size / mem::size_of::<elf::Rela64<LE>>();
+                for rela in load_structs_inplace_mut::<elf::Rela64<LE>>(object_bytes, offset, count)
+                {
+                    rela.r_info.set(LE, remap_info(rela.r_info.get(LE)));
+                }
+            }
+            elf::SHT_REL => {
+                let count = size / mem::size_of::<elf::Rel64<LE>>();
+                for rel in load_structs_inplace_mut::<elf::Rel64<LE>>(object_bytes, offset, count) {
+                    rel.r_info.set(LE, remap_info(rel.r_info.get(LE)));
+                }
+            }
+            elf::SHT_GROUP => {
+                // A group's signature is the symbol its sh_info points at
+                let header = load_struct_inplace_mut::<elf::SectionHeader64<LE>>(
+                    object_bytes,
+                    sh_offset + index * section_header_size,
+                );
+                let signature = header.sh_info.get(LE) as usize;
+                header.sh_info.set(LE, new_indices[signature]);
+            }
+            elf::SHT_SYMTAB_SHNDX => {
+                let count = size / mem::size_of::<endian::U32<LE>>();
+                let entries =
+                    load_structs_inplace_mut::<endian::U32<LE>>(object_bytes, offset, count);
+                let old_entries = entries.to_vec();
+                for (entry, old_index) in entries.iter_mut().zip(order.iter()) {
+                    *entry = old_entries[*old_index];
+                }
+            }
+            SHT_LLVM_ADDRSIG | SHT_LLVM_CALL_GRAPH_PROFILE => {
+                // These hold symbol indices as ULEB128, so they can change size when remapped.
+                // They only feed optimizations, so mark them inactive and linkers skip them.
+                load_struct_inplace_mut::<elf::SectionHeader64<LE>>(
+                    object_bytes,
+                    sh_offset + index * section_header_size,
+                )
+                .sh_type
+                .set(LE, elf::SHT_NULL);
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1825,6 +2340,11 @@ mod tests {
         );
 
         let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
+        let zig_target = match target.architecture {
+            target_lexicon::Architecture::X86_64 => "x86_64-linux-gnu",
+            target_lexicon::Architecture::Aarch64(_) => "aarch64-linux-gnu",
+            _ => unreachable!(),
+        };
 
         std::fs::write(dir.join("host.zig"), host_zig.as_bytes()).unwrap();
         std::fs::write(dir.join("app.zig"), app_zig.as_bytes()).unwrap();
@@ -1837,7 +2357,7 @@ mod tests {
                 "app.zig",
                 "-fPIC",
                 "-target",
-                "x86_64-linux-gnu",
+                zig_target,
                 "-OReleaseFast",
             ])
             .output()
@@ -1866,7 +2386,7 @@ mod tests {
                 .collect()
         };
 
-        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names).unwrap();
+        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names, target).unwrap();
         std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
 
         // now we can compile the host (it uses libapp.so, hence the order here)
@@ -1879,7 +2399,7 @@ mod tests {
                 "-fPIE",
                 "-lc",
                 "-target",
-                "x86_64-linux-gnu",
+                zig_target,
                 "-OReleaseFast",
             ])
             .output()
@@ -1945,4 +2465,283 @@ mod tests {
 
         assert_eq!("Hello foo\n", output);
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zig_host_app_aarch64() {
+        use std::str::FromStr;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        let target = Triple::from_str("aarch64-unknown-linux-gnu").unwrap();
+        zig_host_app_help(dir, &target);
+
+        // We can't run the result on this machine, so check that the host's calls
+        // now branch straight to the app's definition of roc_magic1.
+        let final_bytes = std::fs::read(dir.join("final")).unwrap();
+        let object = object::File::parse(final_bytes.as_slice()).unwrap();
+        assert_eq!(object.architecture(), object::Architecture::Aarch64);
+
+        let roc_magic1 = object
+            .symbols()
+            .find(|sym| sym.name() == Ok("roc_magic1"))
+            .unwrap()
+            .address();
+
+        let calls_to_app = object
+            .sections()
+            .filter(|sec| sec.kind() == SectionKind::Text)
+            .flat_map(|sec| {
+                let address = sec.address();
+                let data = sec.data().unwrap().to_vec();
+                data.chunks_exact(4)
+                    .enumerate()
+                    .filter_map(|(i, word)| {
+                        let inst = u32::from_le_bytes(word.try_into().unwrap());
+                        let offset = aarch64_decode_branch26(inst)?;
+                        Some((address + 4 * i as u64) as i64 + offset)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|target| *target == roc_magic1 as i64)
+            .count();
+
+        assert!(calls_to_app > 0);
+    }
+
+    #[test]
+    fn aarch64_branch_encoding() {
+        // bl +4, bl -4
+        assert_eq!(aarch64_decode_branch26(0x9400_0001), Some(4));
+        assert_eq!(aarch64_decode_branch26(0x97ff_ffff), Some(-4));
+        // b +8
+        assert_eq!(aarch64_decode_branch26(0x1400_0002), Some(8));
+        // ret is not a branch we can patch
+        assert_eq!(aarch64_decode_branch26(0xd65f_03c0), None);
+
+        assert_eq!(aarch64_encode_branch26(0x9400_0000, 0x100), 0x9400_0040);
+        assert_eq!(aarch64_encode_branch26(0x9400_0000, -8), 0x97ff_fffe);
+        assert_eq!(aarch64_encode_branch26(0x1400_0000, 0x100), 0x1400_0040);
+    }
+
+    #[test]
+    fn aarch64_immediate_encoding() {
+        // adrp x0, #0x1000
+        assert_eq!(aarch64_encode_adrp(0x9000_0000, 0x1000), 0xb000_0000);
+        // adrp x3, #-0x1000
+        assert_eq!(aarch64_encode_adrp(0x9000_0003, -0x1000), 0xf0ff_ffe3);
+        // add x0, x0, #0x10
+        assert_eq!(aarch64_encode_imm12(0x9100_0000, 0x10), 0x9100_4000);
+        // ldr x0, [x1, #8] becomes add x0, x1, #0
+        assert_eq!(aarch64_relax_got_load(0xf940_0420), 0x9100_0020);
+    }
+
+    /// Build an aarch64 object file with the relocations LLVM emits for position-independent code,
+    /// then check that relocating it produces the right instructions.
+    #[test]
+    fn aarch64_app_relocations() {
+        use object::write;
+
+        let mut app = write::Object::new(
+            object::BinaryFormat::Elf,
+            object::Architecture::Aarch64,
+            object::Endianness::Little,
+        );
+
+        let text = app.section_id(write::StandardSection::Text);
+        let instructions: [u32; 6] = [
+            0x9400_0000, // bl roc_fx_host
+            0x9000_0000, // adrp x0, data
+            0x9100_0000, // add x0, x0, :lo12:data
+            0xf940_0000, // ldr x0, [x0, :lo12:data]
+            0x9000_0021, // adrp x1, :got:roc_fx_host
+            0xf940_0021, // ldr x1, [x1, :got_lo12:roc_fx_host]
+        ];
+        let code: Vec<u8> = instructions.iter().flat_map(|i| i.to_le_bytes()).collect();
+        app.append_section_data(text, &code, 4);
+
+        let host_fn = app.add_symbol(write::Symbol {
+            name: b"roc_fx_host".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Dynamic,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+        let data = app.add_symbol(write::Symbol {
+            name: b"data".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Data,
+            scope: object::SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+
+        let relocs = [
+            (0, elf::R_AARCH64_CALL26, host_fn),
+            (4, elf::R_AARCH64_ADR_PREL_PG_HI21, data),
+            (8, elf::R_AARCH64_ADD_ABS_LO12_NC, data),
+            (12, elf::R_AARCH64_LDST64_ABS_LO12_NC, data),
+            (16, elf::R_AARCH64_ADR_GOT_PAGE, host_fn),
+            (20, elf::R_AARCH64_LD64_GOT_LO12_NC, host_fn),
+        ];
+        for (offset, r_type, symbol) in relocs {
+            app.add_relocation(
+                text,
+                write::Relocation {
+                    offset,
+                    size: 0,
+                    kind: RelocationKind::Elf(r_type),
+                    encoding: object::RelocationEncoding::Generic,
+                    symbol,
+                    addend: 0,
+                },
+            )
+            .unwrap();
+        }
+
+        let app_bytes = app.write().unwrap();
+        let app_obj = object::File::parse(app_bytes.as_slice()).unwrap();
+        let text = app_obj.section_by_name(".text").unwrap();
+
+        // Pretend the app code ends up at 0x20_0000, calling a host function at 0x1_1000,
+        // and referencing data at 0x21_2348.
+        let text_vaddr = 0x20_0000;
+        let host_fn_vaddr = 0x1_1000;
+        let data_vaddr = 0x21_2348;
+
+        let mut output = text.data().unwrap().to_vec();
+        for (offset, rel) in text.relocations() {
+            let symbol = match rel.target() {
+                RelocationTarget::Symbol(index) => app_obj.symbol_by_index(index).unwrap(),
+                _ => unreachable!(),
+            };
+            let target = match symbol.name().unwrap() {
+                "roc_fx_host" => host_fn_vaddr,
+                "data" => data_vaddr,
+                other => unreachable!("{}", other),
+            };
+
+            apply_aarch64_relocation(
+                &mut output,
+                offset as usize,
+                text_vaddr + offset as i64,
+                target,
+                &rel,
+                false,
+            );
+        }
+
+        let relocated: Vec<u32> = output
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        let branch_offset = host_fn_vaddr - text_vaddr;
+        assert_eq!(
+            relocated,
+            [
+                aarch64_encode_branch26(0x9400_0000, branch_offset),
+                aarch64_encode_adrp(0x9000_0000, 0x1_2000),
+                0x9100_0000 | (0x348 << 10),
+                0xf940_0000 | ((0x348 >> 3) << 10),
+                aarch64_encode_adrp(0x9000_0021, 0x1000 - 0x20_0000 + 0x1_0000),
+                0x9100_0021,
+            ]
+        );
+    }
+
+    /// Build an object that defines an exported and an internal function, then check that only
+    /// the exported one stays global and that relocations still point at the same symbols.
+    #[test]
+    fn localize_unexported_symbols() {
+        use object::write;
+
+        let mut app = write::Object::new(
+            object::BinaryFormat::Elf,
+            object::Architecture::X86_64,
+            object::Endianness::Little,
+        );
+
+        let text = app.section_id(write::StandardSection::Text);
+        app.append_section_data(text, &[0xe8, 0, 0, 0, 0, 0xe8, 0, 0, 0, 0, 0xc3], 16);
+
+        let mut add_symbol = |name: &[u8], section, scope| {
+            app.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind: object::SymbolKind::Text,
+                scope,
+                weak: false,
+                section,
+                flags: object::SymbolFlags::None,
+            })
+        };
+
+        let defined = write::SymbolSection::Section(text);
+        let undefined = write::SymbolSection::Undefined;
+        let global = object::SymbolScope::Dynamic;
+
+        add_symbol(b"roc__mainForHost_1_exposed", defined, global);
+        let internal = add_symbol(b"roc_builtins.str.concat", defined, global);
+        let host_fn = add_symbol(b"roc_alloc", undefined, global);
+
+        for (offset, symbol) in [(1, internal), (6, host_fn)] {
+            app.add_relocation(
+                text,
+                write::Relocation {
+                    offset,
+                    size: 32,
+                    kind: RelocationKind::PltRelative,
+                    encoding: object::RelocationEncoding::X86Branch,
+                    symbol,
+                    addend: -4,
+                },
+            )
+            .unwrap();
+        }
+
+        let mut app_bytes = app.write().unwrap();
+        localize_elf_symbols(&mut app_bytes, &["roc__mainForHost_1_exposed".to_string()]);
+
+        let app_obj = object::File::parse(app_bytes.as_slice()).unwrap();
+        let scope = |name: &str| {
+            let symbol = app_obj.symbols().find(|s| s.name() == Ok(name)).unwrap();
+            (symbol.is_global(), symbol.is_undefined())
+        };
+
+        assert_eq!(scope("roc__mainForHost_1_exposed"), (true, false));
+        assert_eq!(scope("roc_builtins.str.concat"), (false, false));
+        assert_eq!(scope("roc_alloc"), (true, true));
+
+        // Local symbols have to come before the first global one
+        let first_global = app_obj.symbols().position(|s| s.is_global()).unwrap();
+        assert!(app_obj.symbols().skip(first_global).all(|s| s.is_global()));
+
+        let text = app_obj.section_by_name(".text").unwrap();
+        let targets: Vec<_> = text
+            .relocations()
+            .map(|(offset, rel)| match rel.target() {
+                RelocationTarget::Symbol(index) => {
+                    let symbol = app_obj.symbol_by_index(index).unwrap();
+                    (offset, symbol.name().unwrap().to_string())
+                }
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(
+            targets,
+            [
+                (1, "roc_builtins.str.concat".to_string()),
+                (6, "roc_alloc".to_string())
+            ]
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use object::pe;
 use object::LittleEndian as LE;
+use target_lexicon::Triple;
 
 pub(crate) const APP_DLL: &str = "libapp.dll";
 
@@ -122,7 +123,17 @@ fn synthetic_export_dir(virtual_address: u32, custom_names: &[String]) -> Vec<u8
     vec
 }
 
-pub fn synthetic_dll(custom_names: &[String]) -> Vec<u8> {
+pub fn synthetic_dll(custom_names: &[String], triple: &Triple) -> Vec<u8> {
+    // The host will be linked against this library, so it has to match the host's architecture
+    let machine = match triple.architecture {
+        target_lexicon::Architecture::X86_64 => pe::IMAGE_FILE_MACHINE_AMD64,
+        target_lexicon::Architecture::Aarch64(_) => pe::IMAGE_FILE_MACHINE_ARM64,
+        _ => {
+            // We should have verified this via supported() before calling this function
+            unreachable!()
+        }
+    };
+
     let mut out_data = Vec::new();
     let mut writer = object::write::pe::Writer::new(true, 8, 8, &mut out_data);
 
@@ -169,7 +180,7 @@ pub fn synthetic_dll(custom_names: &[String]) -> Vec<u8> {
 
     // the header on my machine
     let headers = object::write::pe::NtHeaders {
-        machine: 34404,
+        machine,
         time_date_stamp: 0,
         characteristics: 8226,
         major_linker_version: 14,
@@ -5,7 +5,7 @@
 //! practical to use a regular linker.
 use memmap2::{Mmap, MmapMut};
 use object::Object;
-use roc_error_macros::internal_error;
+use roc_error_macros::{internal_error, user_error};
 use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, Threading};
 use roc_module::symbol::Interns;
 use roc_packaging::cache::RocCacheDir;
@@ -20,15 +20,17 @@ use target_lexicon::Triple;
 mod elf;
 mod macho;
 mod pe;
+mod wasm;
 
 mod generate_dylib;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LinkType {
-    // These numbers correspond to the --lib and --no-link flags
+    // These numbers correspond to the --lib, --no-link and --static flags
     Executable = 0,
     Dylib = 1,
     None = 2,
+    StaticLib = 3,
 }
 
 pub fn supported(link_type: LinkType, target: &Triple) -> bool {
@@ -41,6 +43,13 @@ pub fn supported(link_type: LinkType, target: &Triple) -> bool {
                 ..
             } => true,
 
+            Triple {
+                architecture: target_lexicon::Architecture::Aarch64(_),
+                operating_system: target_lexicon::OperatingSystem::Linux,
+                binary_format: target_lexicon::BinaryFormat::Elf,
+                ..
+            } => true,
+
             // macho support is incomplete
             Triple {
                 operating_system: target_lexicon::OperatingSystem::Darwin,
@@ -55,6 +64,19 @@ pub fn supported(link_type: LinkType, target: &Triple) -> bool {
                 ..
             } => true,
 
+            Triple {
+                architecture: target_lexicon::Architecture::Aarch64(_),
+                operating_system: target_lexicon::OperatingSystem::Windows,
+                binary_format: target_lexicon::BinaryFormat::Coff,
+                ..
+            } => true,
+
+            Triple {
+                architecture: target_lexicon::Architecture::Wasm32,
+                binary_format: target_lexicon::BinaryFormat::Wasm,
+                ..
+            } => true,
+
             _ => false,
         }
     } else {
@@ -84,6 +106,16 @@ pub fn link_preprocessed_host(
     surgery(roc_app_bytes, &metadata, binary_path, false, false, target)
 }
 
+/// Make every symbol an object file defines local to it, except the `exported` ones,
+/// so a static library built from it only exposes those to whatever links it.
+pub fn localize_symbols(object_bytes: &mut [u8], exported: &[String]) {
+    match object::FileKind::parse(&*object_bytes) {
+        Ok(object::FileKind::Elf64) => elf::localize_elf_symbols(object_bytes, exported),
+        Ok(kind) => internal_error!("Restricting exported symbols is not supported for {kind:?}"),
+        Err(e) => internal_error!("Failed to parse object file: {e}"),
+    }
+}
+
 // Exposed function to load a platform file and generate a stub lib for it.
 pub fn generate_stub_lib(
     input_path: &Path,
@@ -212,6 +244,10 @@ impl ExposedSymbols {
             ]);
         }
 
+        for x in &exposed_to_host.refcounters {
+            custom_names.push(x.as_str(interns).to_string());
+        }
+
         for (top_level_value, lambda_set_id) in &exposed_to_host.lambda_sets {
             let sym = top_level_value.as_str(interns);
             let id = lambda_set_id.0;
@@ -395,6 +431,8 @@ pub fn preprocess_host(
     let metadata_path = platform_main_roc.with_file_name(metadata_file_name(target));
     let host_exe_path = if let target_lexicon::OperatingSystem::Windows = target.operating_system {
         platform_main_roc.with_file_name("dynhost.exe")
+    } else if let target_lexicon::BinaryFormat::Wasm = target.binary_format {
+        platform_main_roc.with_file_name("dynhost.wasm")
     } else {
         platform_main_roc.with_file_name("dynhost")
     };
@@ -457,6 +495,16 @@ fn preprocess(
         }
 
         target_lexicon::BinaryFormat::Coff => {
+            if !matches!(
+                target.architecture,
+                target_lexicon::Architecture::X86_64 | target_lexicon::Architecture::Aarch64(_)
+            ) {
+                user_error!(
+                    "The surgical linker does not support {} Windows hosts yet, only x86_64 and aarch64 ones. Use `--linker=legacy` instead.",
+                    target.architecture
+                );
+            }
+
             crate::pe::preprocess_windows(
                 host_exe_path,
                 metadata_path,
@@ -469,7 +517,7 @@ fn preprocess(
         }
 
         target_lexicon::BinaryFormat::Wasm => {
-            todo!("Roc does not yet support web assembly hosts!");
+            crate::wasm::preprocess_wasm(host_exe_path, preprocessed_path, verbose, time);
         }
         target_lexicon::BinaryFormat::Unknown => {
             internal_error!("Roc does not support unknown host binary formats!");
@@ -514,7 +562,7 @@ fn surgery(
         }
 
         target_lexicon::BinaryFormat::Wasm => {
-            todo!("Roc does not yet support web assembly hosts!");
+            crate::wasm::surgery_wasm(roc_app_bytes, executable_path, verbose, time);
         }
         target_lexicon::BinaryFormat::Unknown => {
             internal_error!("Roc does not support unknown host binary formats!");
@@ -19,8 +19,13 @@ use roc_collections::{MutMap, VecMap};
 use roc_error_macros::internal_error;
 
 use crate::{
-    generate_dylib::APP_DLL, load_struct_inplace, load_struct_inplace_mut,
-    load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    elf::{
+        aarch64_decode_branch26, aarch64_encode_adrp, aarch64_encode_branch26,
+        aarch64_encode_imm12, aarch64_page,
+    },
+    generate_dylib::APP_DLL,
+    load_struct_inplace, load_struct_inplace_mut, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut,
 };
 
 /// The metadata stores information about/from the host .exe because
@@ -285,10 +290,92 @@ fn relocate_to(
     }
 }
 
+/// Apply a relocation of the app, given the virtual addresses of its target and of the place
+/// being relocated
+fn relocate_app(
+    executable: &mut [u8],
+    architecture: object::Architecture,
+    file_offset: usize,
+    target: i64,
+    place: i64,
+    relocation: &object::Relocation,
+) {
+    match architecture {
+        object::Architecture::Aarch64 => {
+            relocate_aarch64(executable, file_offset, target, place, relocation)
+        }
+        _ => relocate_to(executable, file_offset, target - place, relocation),
+    }
+}
+
+/// Most aarch64 relocations patch the immediate of an instruction. Unlike in ELF, the addend of
+/// such a relocation is stored in that immediate.
+fn relocate_aarch64(
+    executable: &mut [u8],
+    file_offset: usize,
+    target: i64,
+    place: i64,
+    relocation: &object::Relocation,
+) {
+    if let object::RelocationKind::Relative = relocation.kind() {
+        relocate_to(executable, file_offset, target - place, relocation);
+        return;
+    }
+
+    let inst = u32::from_le_bytes(executable[file_offset..][..4].try_into().unwrap());
+
+    let patched = match relocation.kind() {
+        object::RelocationKind::Coff(pe::IMAGE_REL_ARM64_BRANCH26) => {
+            let addend = aarch64_decode_branch26(inst).unwrap_or_else(|| {
+                internal_error!(
+                    "Expected a b or bl for a BRANCH26 relocation, but found {inst:#010x}"
+                )
+            });
+
+            aarch64_encode_branch26(inst, target + addend - place)
+        }
+        object::RelocationKind::Coff(pe::IMAGE_REL_ARM64_PAGEBASE_REL21) => {
+            let addend = aarch64_decode_adr_immediate(inst);
+
+            aarch64_encode_adrp(inst, aarch64_page(target + addend) - aarch64_page(place))
+        }
+        object::RelocationKind::Coff(pe::IMAGE_REL_ARM64_PAGEOFFSET_12A) => {
+            let addend = ((inst >> 10) & 0xfff) as i64;
+
+            aarch64_encode_imm12(inst, ((target + addend) & 0xfff) as u32)
+        }
+        object::RelocationKind::Coff(pe::IMAGE_REL_ARM64_PAGEOFFSET_12L) => {
+            // the offset of a load or store is scaled by the size of the access
+            let mut scale = inst >> 30;
+            if inst & 0x0480_0000 == 0x0480_0000 {
+                // a 128-bit access to a SIMD&FP register
+                scale += 4;
+            }
+
+            let addend = (((inst >> 10) & 0xfff) as i64) << scale;
+
+            aarch64_encode_imm12(inst, ((target + addend) & 0xfff) as u32 >> scale)
+        }
+        other => internal_error!("Relocation Kind not yet support: {other:?}"),
+    };
+
+    executable[file_offset..][..4].copy_from_slice(&patched.to_le_bytes());
+}
+
+/// Decode the (unscaled) 21-bit immediate of an `adr` or `adrp` instruction
+fn aarch64_decode_adr_immediate(inst: u32) -> i64 {
+    let immlo = (inst >> 29) & 0x3;
+    let immhi = (inst >> 5) & 0x7ffff;
+
+    ((((immhi << 2) | immlo) << 11) as i32 >> 11) as i64
+}
+
 pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_bytes: &[u8]) {
     let md = PeMetadata::read_from_file(metadata_path);
 
     let app_obj_sections = AppSections::from_data(roc_app_bytes);
+    let architecture = app_obj_sections.architecture;
+    let (stack_probe_name, stack_probe_bytes) = stack_probe(architecture);
 
     let mut symbols = app_obj_sections.roc_symbols;
 
@@ -397,40 +484,48 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
                     address,
                 } = app_relocation;
 
+                let file_offset = offset + *offset_in_section as usize;
+                let place = section_virtual_address as i64 + *offset_in_section as i64;
+
                 if let Some(destination) = md.exports.get(name) {
-                    match relocation.kind() {
-                        object::RelocationKind::Relative => {
-                            relocate_to(
+                    match (architecture, relocation.kind()) {
+                        (object::Architecture::Aarch64, _)
+                        | (_, object::RelocationKind::Relative) => {
+                            relocate_app(
                                 executable,
-                                offset + *offset_in_section as usize,
-                                destination
-                                    - section_virtual_address as i64
-                                    - *offset_in_section as i64,
+                                architecture,
+                                file_offset,
+                                *destination,
+                                place,
                                 relocation,
                             );
                         }
                         _ => todo!(),
                     }
                 } else if let Some(destination) = inter_app_relocations.get(name) {
-                    relocate_to(
+                    relocate_app(
                         executable,
-                        offset + *offset_in_section as usize,
-                        destination - section_virtual_address as i64 - *offset_in_section as i64,
+                        architecture,
+                        file_offset,
+                        *destination,
+                        place,
                         relocation,
                     );
-                } else if name == "___chkstk_ms" {
+                } else if name == stack_probe_name {
                     // this is a stack probe that is inserted when a function uses more than 2
                     // pages of stack space. The source of this function is not linked in, so we
                     // have to do it ourselves. We patch in the bytes as a separate section, and
                     // here just need to jump to those bytes
 
-                    // This relies on the ___CHKSTK_MS section being the last text section in the list of sections
-                    let destination = length - ___CHKSTK_MS.len();
+                    // This relies on the stack probe section being the last text section in the list of sections
+                    let destination = length - stack_probe_bytes.len();
 
-                    relocate_to(
+                    relocate_app(
                         executable,
-                        offset + *offset_in_section as usize,
-                        destination as i64 - *offset_in_section as i64,
+                        architecture,
+                        file_offset,
+                        section_virtual_address as i64 + destination as i64,
+                        place,
                         relocation,
                     );
                 } else {
@@ -454,12 +549,15 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
                         );
                     }
 
-                    match relocation.kind() {
-                        object::RelocationKind::Relative => {
-                            relocate_to(
+                    match (architecture, relocation.kind()) {
+                        (object::Architecture::Aarch64, _)
+                        | (_, object::RelocationKind::Relative) => {
+                            relocate_app(
                                 executable,
-                                offset + *offset_in_section as usize,
-                                *address as i64 - *offset_in_section as i64,
+                                architecture,
+                                file_offset,
+                                section_virtual_address as i64 + *address as i64,
+                                place,
                                 relocation,
                             );
                         }
@@ -1049,8 +1147,9 @@ struct AppSymbol {
     offset_in_section: usize,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct AppSections<'a> {
+    architecture: object::Architecture,
     sections: Vec<Section<'a>>,
     roc_symbols: Vec<AppSymbol>,
     other_symbols: Vec<(SectionIndex, AppSymbol)>,
@@ -1165,8 +1264,9 @@ impl<'a> AppSections<'a> {
         }
 
         // add a fake section that contains code for a stack probe that some app functions need
+        let (_, stack_probe_bytes) = stack_probe(file.architecture());
         let stack_check_section = Section {
-            bytes: &___CHKSTK_MS,
+            bytes: stack_probe_bytes,
             kind: SectionKind::Text,
             relocations: Default::default(),
             app_section_index: object::SectionIndex(0),
@@ -1206,6 +1306,7 @@ impl<'a> AppSections<'a> {
         }
 
         AppSections {
+            architecture: file.architecture(),
             sections,
             roc_symbols,
             other_symbols,
@@ -1325,6 +1426,14 @@ pub(crate) fn redirect_libc_functions(name: &str) -> Option<&str> {
     }
 }
 
+/// The name of the stack probe that app functions with big stack frames call, and its code
+fn stack_probe(architecture: object::Architecture) -> (&'static str, &'static [u8]) {
+    match architecture {
+        object::Architecture::Aarch64 => ("__chkstk", &__CHKSTK_AARCH64),
+        _ => ("___chkstk_ms", &___CHKSTK_MS),
+    }
+}
+
 // 0000000000000000 <.text>:
 //    0:	51                   	push   rcx
 //    1:	50                   	push   rax
@@ -1359,6 +1468,26 @@ const ___CHKSTK_MS: [u8; 48] = [
     0xc3, // ret
 ];
 
+// The size of the stack frame is passed in x15, in units of 16 bytes
+//
+// 0000000000000000 <__chkstk>:
+//    0:	d37cedf0 	lsl	x16, x15, #4
+//    4:	910003f1 	mov	x17, sp
+//    8:	d1400631 	sub	x17, x17, #1, lsl #12
+//    c:	f1400610 	subs	x16, x16, #1, lsl #12
+//   10:	f940023f 	ldr	xzr, [x17]
+//   14:	54ffffac 	b.gt	8 <__chkstk+0x8>
+//   18:	d65f03c0 	ret
+const __CHKSTK_AARCH64: [u8; 28] = [
+    0xf0, 0xed, 0x7c, 0xd3, // lsl    x16, x15, #4
+    0xf1, 0x03, 0x00, 0x91, // mov    x17, sp
+    0x31, 0x06, 0x40, 0xd1, // sub    x17, x17, #1, lsl #12
+    0x10, 0x06, 0x40, 0xf1, // subs   x16, x16, #1, lsl #12
+    0x3f, 0x02, 0x40, 0xf9, // ldr    xzr, [x17]
+    0xac, 0xff, 0xff, 0x54, // b.gt   0x8
+    0xc0, 0x03, 0x5f, 0xd6, // ret
+];
+
 #[cfg(test)]
 mod test {
     const PE_DYNHOST: &[u8] = include_bytes!("../dynhost_benchmarks_windows.exe") as &[_];
@@ -1651,6 +1780,125 @@ mod test {
         increase_number_of_sections_help(PE_DYNHOST, &new_sections, &path);
     }
 
+    /// Build an aarch64 object file with the relocations LLVM emits for Windows, then check that
+    /// relocating it produces the right instructions.
+    #[test]
+    fn aarch64_app_relocations() {
+        use object::{write, ObjectSection, ObjectSymbol};
+
+        let mut app = write::Object::new(
+            object::BinaryFormat::Coff,
+            object::Architecture::Aarch64,
+            object::Endianness::Little,
+        );
+
+        // COFF stores the addends of these relocations in the instructions
+        let text = app.section_id(write::StandardSection::Text);
+        let instructions: [u32; 6] = [
+            0x9400_0000, // bl roc_fx_host
+            0x9000_0040, // adrp x0, data+8
+            0x9100_2000, // add x0, x0, :lo12:data+8
+            0xf940_0801, // ldr x1, [x0, :lo12:data+16]
+            0xb940_0002, // ldr w2, [x0, :lo12:data]
+            0x1400_0001, // b roc_fx_host+4
+        ];
+        let code: Vec<u8> = instructions.iter().flat_map(|i| i.to_le_bytes()).collect();
+        app.append_section_data(text, &code, 4);
+
+        let host_fn = app.add_symbol(write::Symbol {
+            name: b"roc_fx_host".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Dynamic,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+        let data = app.add_symbol(write::Symbol {
+            name: b"data".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Data,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+
+        let relocs = [
+            (0, pe::IMAGE_REL_ARM64_BRANCH26, host_fn),
+            (4, pe::IMAGE_REL_ARM64_PAGEBASE_REL21, data),
+            (8, pe::IMAGE_REL_ARM64_PAGEOFFSET_12A, data),
+            (12, pe::IMAGE_REL_ARM64_PAGEOFFSET_12L, data),
+            (16, pe::IMAGE_REL_ARM64_PAGEOFFSET_12L, data),
+            (20, pe::IMAGE_REL_ARM64_BRANCH26, host_fn),
+        ];
+        for (offset, typ, symbol) in relocs {
+            app.add_relocation(
+                text,
+                write::Relocation {
+                    offset,
+                    size: 0,
+                    kind: object::RelocationKind::Coff(typ),
+                    encoding: object::RelocationEncoding::Generic,
+                    symbol,
+                    addend: 0,
+                },
+            )
+            .unwrap();
+        }
+
+        let app_bytes = app.write().unwrap();
+        let app_obj = object::File::parse(app_bytes.as_slice()).unwrap();
+        let text = app_obj.section_by_name(".text").unwrap();
+
+        // Pretend the app code ends up at 0x20_0000, calling a host function at 0x1_1000,
+        // and referencing data at 0x21_2348.
+        let text_vaddr = 0x20_0000;
+        let host_fn_vaddr = 0x1_1000;
+        let data_vaddr = 0x21_2348;
+
+        let mut output = text.data().unwrap().to_vec();
+        for (offset, rel) in text.relocations() {
+            let symbol = match rel.target() {
+                RelocationTarget::Symbol(index) => app_obj.symbol_by_index(index).unwrap(),
+                _ => unreachable!(),
+            };
+            let target = match symbol.name().unwrap() {
+                "roc_fx_host" => host_fn_vaddr,
+                "data" => data_vaddr,
+                other => unreachable!("{}", other),
+            };
+
+            relocate_app(
+                &mut output,
+                object::Architecture::Aarch64,
+                offset as usize,
+                target,
+                text_vaddr + offset as i64,
+                &rel,
+            );
+        }
+
+        let relocated: Vec<u32> = output
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        assert_eq!(
+            relocated,
+            [
+                aarch64_encode_branch26(0x9400_0000, host_fn_vaddr - text_vaddr),
+                aarch64_encode_adrp(0x9000_0000, 0x1_2000),
+                0x9100_0000 | (0x350 << 10),
+                0xf940_0001 | ((0x358 >> 3) << 10),
+                0xb940_0002 | ((0x348 >> 2) << 10),
+                aarch64_encode_branch26(0x1400_0000, host_fn_vaddr + 4 - (text_vaddr + 20)),
+            ]
+        );
+    }
+
     fn zig_host_app(dir: &Path, host_zig: &str, app_zig: &str) {
         let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
 
@@ -1690,7 +1938,8 @@ mod test {
 
         // make the dummy dylib based on the app object
         let names: Vec<_> = symbols.iter().map(|s| s.name.clone()).collect();
-        let dylib_bytes = crate::generate_dylib::synthetic_dll(&names);
+        let target: Triple = "x86_64-pc-windows-gnu".parse().unwrap();
+        let dylib_bytes = crate::generate_dylib::synthetic_dll(&names, &target);
         std::fs::write(dir.join("libapp.dll"), dylib_bytes).unwrap();
 
         // now we can compile the host (it uses libapp.dll, hence the order here)
@@ -0,0 +1,1447 @@
+//! Surgical linking for WebAssembly hosts.
+//!
+//! Both the preprocessed host and the Roc app are relocatable Wasm object files, with
+//! `linking` and `reloc.*` custom sections. To link them, we concatenate their types,
+//! functions and data, resolve each module's imports against the other module's
+//! definitions, and patch every relocation with its final index or address.
+//! This is the part of `wasm-ld`'s job that Roc hosts and apps actually need.
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_error_macros::internal_error;
+use roc_wasm_module::linking::{
+    DataSymbol, IndexRelocType, OffsetRelocType, RelocationEntry, SymInfo, WasmObjectSymbol,
+    WASM_SYM_BINDING_LOCAL, WASM_SYM_BINDING_WEAK, WASM_SYM_EXPORTED, WASM_SYM_UNDEFINED,
+};
+use roc_wasm_module::opcodes::OpCode;
+use roc_wasm_module::parse::{Parse, ParseError};
+use roc_wasm_module::sections::{
+    CodeSection, ConstExpr, DataMode, DataSegment, ElementSegment, Export, ExportType, Global,
+    GlobalType, Import, ImportDesc, Limits, MemorySection, SectionId, Signature,
+};
+use roc_wasm_module::serialize::{overwrite_padded_i32, overwrite_padded_u32};
+use roc_wasm_module::{SerialBuffer, ValueType, WasmModule};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Lowest address used for data, like `wasm-ld --global-base`. Keeps null pointers invalid.
+const GLOBAL_BASE: u32 = 1024;
+const STACK_SIZE: u32 = 1024 * 1024;
+const STACK_ALIGNMENT: u32 = 16;
+
+const STACK_POINTER_NAME: &str = "__stack_pointer";
+const CALL_CTORS_NAME: &str = "__wasm_call_ctors";
+const MEMORY_NAME: &str = "memory";
+
+/// Segment flag for thread-local data. We don't support threads.
+const WASM_SEG_FLAG_TLS: u32 = 2;
+
+const HOST: usize = 0;
+const APP: usize = 1;
+
+fn report_timing(label: &str, duration: Duration) {
+    println!("\t{:9.3} ms   {}", duration.as_secs_f64() * 1000.0, label,);
+}
+
+pub(crate) fn preprocess_wasm(
+    host_input_path: &Path,
+    preprocessed_path: &Path,
+    verbose: bool,
+    time: bool,
+) {
+    let total_start = Instant::now();
+
+    let host_bytes = std::fs::read(host_input_path).unwrap_or_else(|e| {
+        internal_error!(
+            "Failed to read host file {}: {}",
+            host_input_path.display(),
+            e
+        )
+    });
+
+    let parsing_start = Instant::now();
+    let arena = Bump::new();
+    let host = ObjectFile::parse(&arena, &host_bytes, "host").unwrap_or_else(|e| {
+        internal_error!("{}", e);
+    });
+    let parsing_duration = parsing_start.elapsed();
+
+    if verbose {
+        println!(
+            "Host has {} imported functions, {} defined functions, {} data segments and {} symbols",
+            host.import_fn_names.len(),
+            host.module.code.function_count,
+            host.data_segments.len(),
+            host.module.linking.symbol_table.len(),
+        );
+        for name in host.import_fn_names.iter() {
+            println!("\tImports {name}");
+        }
+    }
+
+    // The host is already a relocatable object, so it is stored as-is.
+    // Parsing it here means we find any problems once, when the platform is built,
+    // rather than every time an app is linked against it.
+    let writing_start = Instant::now();
+    std::fs::write(preprocessed_path, &host_bytes).unwrap_or_else(|e| {
+        internal_error!(
+            "Failed to write preprocessed host {}: {}",
+            preprocessed_path.display(),
+            e
+        )
+    });
+    let writing_duration = writing_start.elapsed();
+
+    let total_duration = total_start.elapsed();
+
+    if verbose || time {
+        println!();
+        println!("Timings");
+        report_timing("Host Parsing", parsing_duration);
+        report_timing("Writing Preprocessed Host", writing_duration);
+        report_timing(
+            "Other",
+            total_duration.saturating_sub(parsing_duration + writing_duration),
+        );
+        report_timing("Total", total_duration);
+    }
+}
+
+pub(crate) fn surgery_wasm(
+    roc_app_bytes: &[u8],
+    executable_path: &Path,
+    verbose: bool,
+    time: bool,
+) {
+    let total_start = Instant::now();
+
+    // The preprocessed host has already been copied to the executable path
+    let host_bytes = std::fs::read(executable_path).unwrap_or_else(|e| {
+        internal_error!(
+            "Failed to read preprocessed host {}: {}",
+            executable_path.display(),
+            e
+        )
+    });
+
+    let linking_start = Instant::now();
+    let arena = Bump::new();
+    let module = link_wasm(&arena, &host_bytes, roc_app_bytes).unwrap_or_else(|e| {
+        internal_error!("{}", e);
+    });
+    let linking_duration = linking_start.elapsed();
+
+    if verbose {
+        println!(
+            "Linked module has {} imported functions and {} defined functions",
+            module.import.function_count(),
+            module.code.function_count,
+        );
+    }
+
+    let writing_start = Instant::now();
+    let mut buffer = std::vec::Vec::with_capacity(module.size());
+    module.serialize(&mut buffer);
+    std::fs::write(executable_path, &buffer).unwrap_or_else(|e| {
+        internal_error!("Failed to write {}: {}", executable_path.display(), e)
+    });
+    let writing_duration = writing_start.elapsed();
+
+    let total_duration = total_start.elapsed();
+
+    if verbose || time {
+        println!();
+        println!("Timings");
+        report_timing("Parsing and Linking", linking_duration);
+        report_timing("Writing Executable", writing_duration);
+        report_timing(
+            "Other",
+            total_duration.saturating_sub(linking_duration + writing_duration),
+        );
+        report_timing("Total", total_duration);
+    }
+}
+
+/// A data segment, as it appears in the object file
+struct RawDataSegment<'a> {
+    /// Offset of the initializer bytes from the start of the Data section body.
+    /// Data relocations are expressed relative to the same position.
+    section_offset: u32,
+    init: &'a [u8],
+}
+
+/// A parsed relocatable Wasm object file
+struct ObjectFile<'a> {
+    module: &'a WasmModule<'a>,
+    data_segments: Vec<'a, RawDataSegment<'a>>,
+    /// Names to link imported functions by. Explicit symbol names override the import names.
+    import_fn_names: Vec<'a, &'a str>,
+    import_global_names: Vec<'a, &'a str>,
+}
+
+impl<'a> ObjectFile<'a> {
+    fn parse(arena: &'a Bump, bytes: &[u8], description: &str) -> Result<Self, String> {
+        let bytes = arena.alloc_slice_copy(bytes);
+
+        let format_error = |e: ParseError| {
+            format!(
+                "I ran into a problem with the {} object file at offset 0x{:x}:\n{}",
+                description, e.offset, e.message
+            )
+        };
+
+        let require_relocatable = true;
+        let module =
+            WasmModule::preload(arena, bytes, require_relocatable).map_err(format_error)?;
+        let data_segments = parse_data_segments(arena, bytes).map_err(format_error)?;
+
+        let mut import_fn_names = Vec::new_in(arena);
+        let mut import_global_names = Vec::new_in(arena);
+        for import in module.import.imports.iter() {
+            match import.description {
+                ImportDesc::Func { .. } => import_fn_names.push(import.name),
+                ImportDesc::Global { .. } => import_global_names.push(import.name),
+                ImportDesc::Mem { .. } | ImportDesc::Table { .. } => {}
+            }
+        }
+
+        for sym in module.linking.symbol_table.iter() {
+            match sym {
+                SymInfo::Function(WasmObjectSymbol::ExplicitlyNamed { flags, index, name })
+                    if flags & WASM_SYM_UNDEFINED != 0 =>
+                {
+                    import_fn_names[*index as usize] = name;
+                }
+                SymInfo::Global(WasmObjectSymbol::ExplicitlyNamed { flags, index, name })
+                    if flags & WASM_SYM_UNDEFINED != 0 =>
+                {
+                    import_global_names[*index as usize] = name;
+                }
+                SymInfo::Event(_) => {
+                    return Err(format!(
+                        "The {description} object file uses exceptions, which are not supported yet."
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(name) = import_global_names
+            .iter()
+            .find(|name| **name != STACK_POINTER_NAME)
+        {
+            return Err(format!(
+                "The {description} object file imports the global `{name}`, but only `{STACK_POINTER_NAME}` is supported. Was it compiled as position-independent code?"
+            ));
+        }
+
+        if let Some(segment) = module
+            .linking
+            .segment_info
+            .iter()
+            .find(|seg| seg.flags & WASM_SEG_FLAG_TLS != 0)
+        {
+            return Err(format!(
+                "The {} object file has thread-local data in `{}`, which is not supported.",
+                description, segment.name
+            ));
+        }
+
+        Ok(ObjectFile {
+            module: arena.alloc(module),
+            data_segments,
+            import_fn_names,
+            import_global_names,
+        })
+    }
+
+    fn import_fn_count(&self) -> u32 {
+        self.import_fn_names.len() as u32
+    }
+
+    fn symbol(&self, sym_index: u32) -> Result<&'a SymInfo<'a>, String> {
+        self.module
+            .linking
+            .symbol_table
+            .get(sym_index as usize)
+            .ok_or_else(|| format!("Relocation refers to nonexistent symbol #{sym_index}"))
+    }
+
+    fn segment_alignment(&self, segment_index: usize) -> u32 {
+        self.module
+            .linking
+            .segment_info
+            .get(segment_index)
+            .map(|seg| 1 << seg.align_bytes_pow2)
+            .unwrap_or(1)
+    }
+}
+
+/// The Data section stores segments as opaque bytes, so find their positions ourselves.
+fn parse_data_segments<'a>(
+    arena: &'a Bump,
+    bytes: &'a [u8],
+) -> Result<Vec<'a, RawDataSegment<'a>>, ParseError> {
+    let mut segments = Vec::new_in(arena);
+
+    // skip the magic number and version
+    let mut cursor = 8;
+    while cursor < bytes.len() {
+        let id = bytes[cursor];
+        cursor += 1;
+        let size = u32::parse((), bytes, &mut cursor)? as usize;
+        let body_start = cursor;
+
+        if id == SectionId::Data as u8 {
+            let count = u32::parse((), bytes, &mut cursor)?;
+            for _ in 0..count {
+                DataMode::parse((), bytes, &mut cursor)?;
+                let len = u32::parse((), bytes, &mut cursor)? as usize;
+                segments.push(RawDataSegment {
+                    section_offset: (cursor - body_start) as u32,
+                    init: &bytes[cursor..][..len],
+                });
+                cursor += len;
+            }
+            break;
+        }
+
+        cursor = body_start + size;
+    }
+
+    Ok(segments)
+}
+
+#[derive(Clone, Copy)]
+struct FunctionDef {
+    object: usize,
+    fn_index: u32,
+    is_weak: bool,
+}
+
+#[derive(Clone, Copy)]
+struct DataDef {
+    object: usize,
+    segment_index: u32,
+    segment_offset: u32,
+    is_weak: bool,
+}
+
+trait Definition: Copy {
+    fn is_weak(&self) -> bool;
+}
+
+impl Definition for FunctionDef {
+    fn is_weak(&self) -> bool {
+        self.is_weak
+    }
+}
+
+impl Definition for DataDef {
+    fn is_weak(&self) -> bool {
+        self.is_weak
+    }
+}
+
+/// Strong definitions win over weak ones, and the first of several weak definitions wins.
+/// Two strong definitions are an error, like in any other linker: silently picking one
+/// would let the host and app each call their own version of the same symbol.
+fn insert_definition<'a, D: Definition>(
+    defs: &mut MutMap<&'a str, D>,
+    name: &'a str,
+    def: D,
+) -> Result<(), String> {
+    match defs.get(name) {
+        Some(existing) if !existing.is_weak() && !def.is_weak() => {
+            Err(format!("Duplicate definition of symbol `{name}`"))
+        }
+        Some(existing) if !existing.is_weak() || def.is_weak() => Ok(()),
+        _ => {
+            defs.insert(name, def);
+            Ok(())
+        }
+    }
+}
+
+fn is_global_definition(flags: u32) -> bool {
+    flags & (WASM_SYM_UNDEFINED | WASM_SYM_BINDING_LOCAL) == 0
+}
+
+fn align_up(value: u32, alignment: u32) -> u32 {
+    (value + alignment - 1) / alignment * alignment
+}
+
+fn write_u32_le(target: &mut [u8], value: u32) {
+    target[..4].copy_from_slice(&value.to_le_bytes());
+}
+
+struct Linker<'a> {
+    arena: &'a Bump,
+    objects: [ObjectFile<'a>; 2],
+    function_defs: MutMap<&'a str, FunctionDef>,
+    data_defs: MutMap<&'a str, DataDef>,
+    /// Final function index for each function index in each object file
+    fn_indices: [Vec<'a, u32>; 2],
+    /// Final memory address for each data segment in each object file
+    segment_addrs: [Vec<'a, u32>; 2],
+    type_indices: MutMap<(usize, u32), u32>,
+    data_end: u32,
+    stack_low: u32,
+    stack_high: u32,
+    output: WasmModule<'a>,
+}
+
+/// Link a relocatable Roc app into a relocatable host, producing an executable module
+fn link_wasm<'a>(
+    arena: &'a Bump,
+    host_bytes: &[u8],
+    app_bytes: &[u8],
+) -> Result<WasmModule<'a>, String> {
+    let host = ObjectFile::parse(arena, host_bytes, "host")?;
+    let app = ObjectFile::parse(arena, app_bytes, "app")?;
+
+    let mut linker = Linker {
+        arena,
+        objects: [host, app],
+        function_defs: MutMap::default(),
+        data_defs: MutMap::default(),
+        fn_indices: [Vec::new_in(arena), Vec::new_in(arena)],
+        segment_addrs: [Vec::new_in(arena), Vec::new_in(arena)],
+        type_indices: MutMap::default(),
+        data_end: 0,
+        stack_low: 0,
+        stack_high: 0,
+        output: WasmModule::new(arena),
+    };
+
+    linker.collect_definitions()?;
+    let needs_ctors = linker.resolve_functions();
+    linker.layout_memory();
+
+    // Table index 0 is reserved for null function pointers
+    let mut table_segment = ElementSegment::new(arena);
+    table_segment.offset = ConstExpr::I32(1);
+    linker.output.element.segments.push(table_segment);
+
+    linker.link_code()?;
+    if needs_ctors {
+        linker.synthesize_call_ctors()?;
+    }
+    linker.link_data()?;
+    linker.finish_module();
+
+    Ok(linker.output)
+}
+
+impl<'a> Linker<'a> {
+    fn collect_definitions(&mut self) -> Result<(), String> {
+        for (object_index, object) in self.objects.iter().enumerate() {
+            for sym in object.module.linking.symbol_table.iter() {
+                match sym {
+                    SymInfo::Function(WasmObjectSymbol::ExplicitlyNamed { flags, index, name })
+                        if is_global_definition(*flags) =>
+                    {
+                        let def = FunctionDef {
+                            object: object_index,
+                            fn_index: *index,
+                            is_weak: flags & WASM_SYM_BINDING_WEAK != 0,
+                        };
+                        insert_definition(&mut self.function_defs, name, def)?;
+                    }
+                    SymInfo::Data(DataSymbol::Defined {
+                        flags,
+                        name,
+                        segment_index,
+                        segment_offset,
+                        ..
+                    }) if is_global_definition(*flags) => {
+                        let def = DataDef {
+                            object: object_index,
+                            segment_index: *segment_index,
+                            segment_offset: *segment_offset,
+                            is_weak: flags & WASM_SYM_BINDING_WEAK != 0,
+                        };
+                        insert_definition(&mut self.data_defs, name, def)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the output type index for a type index in one of the object files
+    fn type_index(&mut self, object_index: usize, sig_index: u32) -> u32 {
+        if let Some(index) = self.type_indices.get(&(object_index, sig_index)) {
+            return *index;
+        }
+
+        let (params, ret_type) = self.objects[object_index].module.types.look_up(sig_index);
+        let signature = Signature {
+            param_types: Vec::from_iter_in(params, self.arena),
+            ret_type,
+        };
+        let index = self.output.types.insert(signature);
+        self.type_indices.insert((object_index, sig_index), index);
+        index
+    }
+
+    /// Decide the final index of every function. Imports that one object file provides
+    /// to the other are resolved, and the rest remain imports in the output.
+    /// Returns whether we need to generate `__wasm_call_ctors`.
+    fn resolve_functions(&mut self) -> bool {
+        enum Resolution {
+            Defined(FunctionDef),
+            Imported(u32),
+            CallCtors,
+        }
+
+        let mut resolutions = [Vec::new_in(self.arena), Vec::new_in(self.arena)];
+        let mut output_imports: MutMap<(&'a str, &'a str), u32> = MutMap::default();
+        let mut needs_ctors = false;
+
+        for object_index in [HOST, APP] {
+            let object = &self.objects[object_index];
+            let fn_imports = object
+                .module
+                .import
+                .imports
+                .iter()
+                .filter(|import| import.is_function());
+
+            for (import, link_name) in fn_imports.zip(object.import_fn_names.iter()) {
+                let resolution = if let Some(def) = self.function_defs.get(link_name) {
+                    Resolution::Defined(*def)
+                } else if *link_name == CALL_CTORS_NAME {
+                    needs_ctors = true;
+                    Resolution::CallCtors
+                } else {
+                    let key = (import.module, import.name);
+                    let next_index = output_imports.len() as u32;
+                    let index = *output_imports.entry(key).or_insert_with(|| {
+                        self.output.import.imports.push(Import {
+                            module: import.module,
+                            name: import.name,
+                            description: ImportDesc::Func {
+                                signature_index: 0, // filled in below
+                            },
+                        });
+                        next_index
+                    });
+                    Resolution::Imported(index)
+                };
+                resolutions[object_index].push((resolution, import));
+            }
+        }
+
+        // Now that we know the number of imports, we can assign indices to defined functions
+        let import_count = self.output.import.imports.len() as u32;
+        let host_fn_count = self.objects[HOST].module.code.function_count;
+        let app_fn_count = self.objects[APP].module.code.function_count;
+        let first_defined = [import_count, import_count + host_fn_count];
+        let call_ctors_index = import_count + host_fn_count + app_fn_count;
+
+        let defined_index = |def: &FunctionDef, objects: &[ObjectFile<'a>; 2]| {
+            first_defined[def.object] + def.fn_index - objects[def.object].import_fn_count()
+        };
+
+        for object_index in [HOST, APP] {
+            let mut fn_indices = Vec::new_in(self.arena);
+
+            for (resolution, import) in resolutions[object_index].iter() {
+                let final_index = match resolution {
+                    Resolution::Defined(def) => defined_index(def, &self.objects),
+                    Resolution::CallCtors => call_ctors_index,
+                    Resolution::Imported(index) => {
+                        if let ImportDesc::Func { signature_index } = import.description {
+                            let sig = self.type_index(object_index, signature_index);
+                            self.output.import.imports[*index as usize].description =
+                                ImportDesc::Func {
+                                    signature_index: sig,
+                                };
+                        }
+                        *index
+                    }
+                };
+                fn_indices.push(final_index);
+            }
+
+            let object = &self.objects[object_index];
+            let fn_count = object.module.code.function_count;
+            fn_indices.extend((0..fn_count).map(|i| first_defined[object_index] + i));
+
+            let signatures = object.module.function.signatures.iter().copied();
+            for sig in Vec::from_iter_in(signatures, self.arena) {
+                let sig = self.type_index(object_index, sig);
+                self.output.function.add_sig(sig);
+            }
+
+            self.fn_indices[object_index] = fn_indices;
+        }
+
+        needs_ctors
+    }
+
+    /// Lay out linear memory: data from both object files, followed by the stack.
+    /// The heap starts where the stack ends.
+    fn layout_memory(&mut self) {
+        let mut address = GLOBAL_BASE;
+        for object_index in [HOST, APP] {
+            let object = &self.objects[object_index];
+            let mut addrs = Vec::with_capacity_in(object.data_segments.len(), self.arena);
+            for (i, segment) in object.data_segments.iter().enumerate() {
+                address = align_up(address, object.segment_alignment(i));
+                addrs.push(address);
+                address += segment.init.len() as u32;
+            }
+            self.segment_addrs[object_index] = addrs;
+        }
+
+        self.data_end = address;
+        self.stack_low = align_up(address, STACK_ALIGNMENT);
+        self.stack_high = self.stack_low + STACK_SIZE;
+    }
+
+    fn function_index(&self, object_index: usize, sym_index: u32) -> Result<u32, String> {
+        let object = &self.objects[object_index];
+        match object.symbol(sym_index)? {
+            SymInfo::Function(WasmObjectSymbol::ExplicitlyNamed { flags, index, name }) => {
+                // A weak definition may have been overridden by the other object file
+                match self.function_defs.get(name) {
+                    Some(def) if is_global_definition(*flags) => {
+                        Ok(self.fn_indices[def.object][def.fn_index as usize])
+                    }
+                    _ => Ok(self.fn_indices[object_index][*index as usize]),
+                }
+            }
+            SymInfo::Function(WasmObjectSymbol::ImplicitlyNamed { index, .. }) => {
+                Ok(self.fn_indices[object_index][*index as usize])
+            }
+            other => Err(format!(
+                "Expected a function symbol for a function relocation, but found {other:?}"
+            )),
+        }
+    }
+
+    fn global_index(&self, object_index: usize, sym_index: u32) -> Result<u32, String> {
+        let object = &self.objects[object_index];
+        let name = match object.symbol(sym_index)? {
+            SymInfo::Global(WasmObjectSymbol::ExplicitlyNamed { name, .. }) => *name,
+            SymInfo::Global(WasmObjectSymbol::ImplicitlyNamed { index, .. }) => {
+                object.import_global_names[*index as usize]
+            }
+            other => {
+                return Err(format!(
+                    "Expected a global symbol for a global relocation, but found {other:?}"
+                ))
+            }
+        };
+
+        if name == STACK_POINTER_NAME {
+            Ok(roc_wasm_module::STACK_POINTER_GLOBAL_ID)
+        } else {
+            Err(format!("Unsupported global `{name}`"))
+        }
+    }
+
+    fn data_address(&self, object_index: usize, sym_index: u32) -> Result<u32, String> {
+        let object = &self.objects[object_index];
+        match object.symbol(sym_index)? {
+            SymInfo::Data(DataSymbol::Defined {
+                flags,
+                name,
+                segment_index,
+                segment_offset,
+                ..
+            }) => match self.data_defs.get(name) {
+                Some(def) if is_global_definition(*flags) => Ok(self.def_address(def)),
+                _ => Ok(self.segment_addrs[object_index][*segment_index as usize] + segment_offset),
+            },
+            SymInfo::Data(DataSymbol::Imported { name, .. }) => {
+                if let Some(def) = self.data_defs.get(name) {
+                    return Ok(self.def_address(def));
+                }
+
+                // Symbols that a linker is expected to define
+                match *name {
+                    "__heap_base" | "__stack_high" => Ok(self.stack_high),
+                    "__stack_low" => Ok(self.stack_low),
+                    "__data_end" => Ok(self.data_end),
+                    "__global_base" | "__dso_handle" => Ok(GLOBAL_BASE),
+                    _ => Err(format!(
+                        "Linking failed! `{name}` is used but neither the host nor the app defines it"
+                    )),
+                }
+            }
+            other => Err(format!(
+                "Expected a data symbol for a memory relocation, but found {other:?}"
+            )),
+        }
+    }
+
+    fn def_address(&self, def: &DataDef) -> u32 {
+        self.segment_addrs[def.object][def.segment_index as usize] + def.segment_offset
+    }
+
+    /// Overwrite the bytes at the start of `target` with the final value for a relocation
+    fn apply_relocation(
+        &mut self,
+        object_index: usize,
+        entry: &RelocationEntry,
+        target: &mut [u8],
+    ) -> Result<(), String> {
+        match *entry {
+            RelocationEntry::Index {
+                type_id,
+                symbol_index,
+                ..
+            } => {
+                use IndexRelocType::*;
+                match type_id {
+                    FunctionIndexLeb => {
+                        let fn_index = self.function_index(object_index, symbol_index)?;
+                        overwrite_padded_u32(target, fn_index);
+                    }
+                    TableIndexSleb => {
+                        let fn_index = self.function_index(object_index, symbol_index)?;
+                        let table_index = self.output.element.get_or_insert_fn(fn_index);
+                        overwrite_padded_i32(target, table_index);
+                    }
+                    TableIndexI32 => {
+                        let fn_index = self.function_index(object_index, symbol_index)?;
+                        let table_index = self.output.element.get_or_insert_fn(fn_index);
+                        write_u32_le(target, table_index as u32);
+                    }
+                    TypeIndexLeb => {
+                        // For type relocations, the "symbol" is the type index itself
+                        let type_index = self.type_index(object_index, symbol_index);
+                        overwrite_padded_u32(target, type_index);
+                    }
+                    GlobalIndexLeb => {
+                        let global_index = self.global_index(object_index, symbol_index)?;
+                        overwrite_padded_u32(target, global_index);
+                    }
+                    GlobalIndexI32 => {
+                        let global_index = self.global_index(object_index, symbol_index)?;
+                        write_u32_le(target, global_index);
+                    }
+                    TableNumberLeb => {
+                        // There is only one table, the indirect function table
+                        overwrite_padded_u32(target, 0);
+                    }
+                    EventIndexLeb | TableIndexSleb64 | TableIndexI64 => {
+                        return Err(format!("Unsupported relocation type {type_id:?}"));
+                    }
+                }
+            }
+            RelocationEntry::Offset {
+                type_id,
+                symbol_index,
+                addend,
+                ..
+            } => {
+                use OffsetRelocType::*;
+                match type_id {
+                    MemoryAddrLeb => {
+                        let address = self.data_address(object_index, symbol_index)?;
+                        overwrite_padded_u32(target, address.wrapping_add(addend as u32));
+                    }
+                    MemoryAddrSleb => {
+                        let address = self.data_address(object_index, symbol_index)?;
+                        overwrite_padded_i32(target, (address as i32).wrapping_add(addend));
+                    }
+                    MemoryAddrI32 => {
+                        let address = self.data_address(object_index, symbol_index)?;
+                        write_u32_le(target, address.wrapping_add(addend as u32));
+                    }
+                    FunctionOffsetI32 | SectionOffsetI32 => {
+                        // Only used in debug info sections, which we don't keep
+                    }
+                    MemoryAddrLeb64 | MemoryAddrSleb64 | MemoryAddrI64 => {
+                        return Err(format!("Unsupported relocation type {type_id:?}"));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy the function bodies from both object files into the output, relocating as we go
+    fn link_code(&mut self) -> Result<(), String> {
+        let mut code = CodeSection::new(self.arena);
+
+        for object_index in [HOST, APP] {
+            let module = self.objects[object_index].module;
+            if module.code.function_offsets.is_empty() {
+                continue;
+            }
+
+            let mut bytes = module.code.bytes.clone();
+            for entry in module.reloc_code.entries.iter() {
+                let offset = match entry {
+                    RelocationEntry::Index { offset, .. } => *offset,
+                    RelocationEntry::Offset { offset, .. } => *offset,
+                };
+                self.apply_relocation(object_index, entry, &mut bytes[offset as usize..])?;
+            }
+
+            let first_fn_start = module.code.function_offsets[0];
+            let shift = code.bytes.len() as u32;
+            code.function_offsets.extend(
+                module
+                    .code
+                    .function_offsets
+                    .iter()
+                    .map(|offset| offset - first_fn_start + shift),
+            );
+            code.bytes
+                .extend_from_slice(&bytes[first_fn_start as usize..]);
+            code.function_count += module.code.function_count;
+        }
+
+        self.output.code = code;
+        Ok(())
+    }
+
+    /// Generate the function that calls all static constructors, in priority order.
+    /// Hosts built with wasi-libc call this from `_start`.
+    fn synthesize_call_ctors(&mut self) -> Result<(), String> {
+        let mut init_funcs = std::vec::Vec::new();
+        for object_index in [HOST, APP] {
+            for init_func in self.objects[object_index].module.linking.init_funcs.iter() {
+                let fn_index = self.function_index(object_index, init_func.symbol_index)?;
+                init_funcs.push((init_func.priority, fn_index));
+            }
+        }
+        init_funcs.sort_by_key(|(priority, _)| *priority);
+
+        let mut body = Vec::new_in(self.arena);
+        body.encode_u32(0); // no local variable declarations
+        for (_, fn_index) in init_funcs {
+            body.append_u8(OpCode::CALL as u8);
+            body.encode_u32(fn_index);
+        }
+        body.append_u8(OpCode::END as u8);
+
+        let code = &mut self.output.code;
+        code.function_offsets.push(code.bytes.len() as u32);
+        code.bytes.encode_u32(body.len() as u32);
+        code.bytes.extend_from_slice(&body);
+        code.function_count += 1;
+
+        let signature = Signature {
+            param_types: Vec::new_in(self.arena),
+            ret_type: None,
+        };
+        self.output.add_function_signature(signature);
+
+        Ok(())
+    }
+
+    fn link_data(&mut self) -> Result<(), String> {
+        for object_index in [HOST, APP] {
+            let object = &self.objects[object_index];
+            let module = object.module;
+
+            let mut segments = Vec::from_iter_in(
+                object
+                    .data_segments
+                    .iter()
+                    .map(|seg| Vec::from_iter_in(seg.init.iter().copied(), self.arena)),
+                self.arena,
+            );
+            let offsets = Vec::from_iter_in(
+                object
+                    .data_segments
+                    .iter()
+                    .map(|seg| seg.section_offset..seg.section_offset + seg.init.len() as u32),
+                self.arena,
+            );
+
+            for entry in module.reloc_data.entries.iter() {
+                let offset = match entry {
+                    RelocationEntry::Index { offset, .. } => *offset,
+                    RelocationEntry::Offset { offset, .. } => *offset,
+                };
+                let segment_index = offsets
+                    .iter()
+                    .position(|range| range.contains(&offset))
+                    .ok_or_else(|| {
+                        format!("Data relocation at offset 0x{offset:x} is outside of any segment")
+                    })?;
+                let position = (offset - offsets[segment_index].start) as usize;
+                self.apply_relocation(
+                    object_index,
+                    entry,
+                    &mut segments[segment_index][position..],
+                )?;
+            }
+
+            for (init, address) in segments
+                .into_iter()
+                .zip(self.segment_addrs[object_index].iter())
+            {
+                // Memory starts zeroed, so there's no need to initialise zeroes (.bss)
+                if init.iter().all(|byte| *byte == 0) {
+                    continue;
+                }
+                self.output.data.append_segment(DataSegment {
+                    mode: DataMode::active_at(*address),
+                    init,
+                });
+            }
+        }
+
+        self.output.data.end_addr = self.data_end;
+        Ok(())
+    }
+
+    fn finish_module(&mut self) {
+        let output = &mut self.output;
+
+        output.memory = MemorySection::new(self.arena, self.stack_high);
+        output.global.append(Global {
+            ty: GlobalType {
+                value_type: ValueType::I32,
+                is_mutable: true,
+            },
+            init: ConstExpr::I32(self.stack_high as i32),
+        });
+
+        let table_size = output.element.max_table_index();
+        output.table.function_table.limits = Limits::MinMax(table_size, table_size);
+
+        output.export.append(Export {
+            name: MEMORY_NAME,
+            ty: ExportType::Mem,
+            index: 0,
+        });
+
+        // Export `_start` for WASI, anything the object files asked to export,
+        // and any app functions that the host doesn't call (they must be called from JS).
+        let host_imports: MutSet<&str> =
+            self.objects[HOST].import_fn_names.iter().copied().collect();
+        let mut exported: MutSet<&str> = MutSet::default();
+        for object_index in [HOST, APP] {
+            for sym in self.objects[object_index]
+                .module
+                .linking
+                .symbol_table
+                .iter()
+            {
+                if let SymInfo::Function(WasmObjectSymbol::ExplicitlyNamed {
+                    flags, name, ..
+                }) = sym
+                {
+                    let should_export = is_global_definition(*flags)
+                        && (flags & WASM_SYM_EXPORTED != 0
+                            || (object_index == HOST && *name == "_start")
+                            || (object_index == APP
+                                && name.starts_with("roc__")
+                                && !host_imports.contains(name)));
+
+                    if should_export && exported.insert(name) {
+                        let def = self.function_defs[name];
+                        output.export.append(Export {
+                            name,
+                            ty: ExportType::Func,
+                            index: self.fn_indices[def.object][def.fn_index as usize],
+                        });
+                    }
+                }
+            }
+        }
+
+        // Debug names
+        let import_names = output.import.imports.iter().map(|import| import.name);
+        for (index, name) in import_names.enumerate() {
+            output.names.append_function(index as u32, name);
+        }
+        for object_index in [HOST, APP] {
+            let mut named: MutSet<u32> = MutSet::default();
+            for sym in self.objects[object_index]
+                .module
+                .linking
+                .symbol_table
+                .iter()
+            {
+                if let SymInfo::Function(WasmObjectSymbol::ExplicitlyNamed { flags, index, name }) =
+                    sym
+                {
+                    let final_index = self.fn_indices[object_index][*index as usize];
+                    if flags & WASM_SYM_UNDEFINED == 0 && named.insert(final_index) {
+                        output.names.append_function(final_index, name);
+                    }
+                }
+            }
+        }
+        output
+            .names
+            .function_names
+            .sort_by_key(|(index, _name)| *index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roc_wasm_interp::{DefaultImportDispatcher, Instance, Value};
+
+    // Minimal builder for relocatable object files, like the ones LLVM emits
+
+    #[derive(Default)]
+    struct TestObject {
+        types: std::vec::Vec<(std::vec::Vec<ValueType>, Option<ValueType>)>,
+        /// (name, type index)
+        fn_imports: std::vec::Vec<(&'static str, u32)>,
+        /// (type index, body bytes). Each body is a list of instructions ending with `end`.
+        functions: std::vec::Vec<(u32, std::vec::Vec<u8>)>,
+        data: std::vec::Vec<std::vec::Vec<u8>>,
+        symbols: std::vec::Vec<std::vec::Vec<u8>>,
+        /// (type, offset within a function body, symbol, function index) for code relocations
+        code_relocs: std::vec::Vec<(u8, usize, u32, usize)>,
+        imports_stack_pointer: bool,
+    }
+
+    const FN_INDEX_LEB: u8 = 0;
+    const TABLE_INDEX_SLEB: u8 = 1;
+    const MEMORY_ADDR_SLEB: u8 = 4;
+    const GLOBAL_INDEX_LEB: u8 = 7;
+
+    fn leb(value: u32) -> std::vec::Vec<u8> {
+        let mut bytes = std::vec::Vec::new();
+        bytes.encode_u32(value);
+        bytes
+    }
+
+    fn padded(value: u32) -> std::vec::Vec<u8> {
+        let mut bytes = vec![0; 5];
+        overwrite_padded_u32(&mut bytes, value);
+        bytes
+    }
+
+    fn name(s: &str) -> std::vec::Vec<u8> {
+        let mut bytes = leb(s.len() as u32);
+        bytes.extend_from_slice(s.as_bytes());
+        bytes
+    }
+
+    fn section(buffer: &mut std::vec::Vec<u8>, id: u8, body: &[u8]) {
+        buffer.push(id);
+        buffer.extend(leb(body.len() as u32));
+        buffer.extend_from_slice(body);
+    }
+
+    impl TestObject {
+        fn fn_symbol(&mut self, flags: u32, index: u32, sym_name: &str) -> u32 {
+            let mut sym = vec![0]; // function
+            sym.extend(leb(flags));
+            sym.extend(leb(index));
+            if flags & WASM_SYM_UNDEFINED == 0 {
+                sym.extend(name(sym_name));
+            }
+            self.symbols.push(sym);
+            self.symbols.len() as u32 - 1
+        }
+
+        fn data_symbol(&mut self, flags: u32, sym_name: &str, segment: u32) -> u32 {
+            let mut sym = vec![1]; // data
+            sym.extend(leb(flags));
+            sym.extend(name(sym_name));
+            if flags & WASM_SYM_UNDEFINED == 0 {
+                sym.extend(leb(segment));
+                sym.extend(leb(0));
+                sym.extend(leb(self.data[segment as usize].len() as u32));
+            }
+            self.symbols.push(sym);
+            self.symbols.len() as u32 - 1
+        }
+
+        fn stack_pointer_symbol(&mut self) -> u32 {
+            self.imports_stack_pointer = true;
+            let mut sym = vec![2]; // global
+            sym.extend(leb(WASM_SYM_UNDEFINED));
+            sym.extend(leb(0));
+            self.symbols.push(sym);
+            self.symbols.len() as u32 - 1
+        }
+
+        fn serialize(&self) -> std::vec::Vec<u8> {
+            let mut module = b"\0asm".to_vec();
+            module.extend(1u32.to_le_bytes());
+
+            let mut types = leb(self.types.len() as u32);
+            for (params, ret) in self.types.iter() {
+                types.push(0x60);
+                types.extend(leb(params.len() as u32));
+                types.extend(params.iter().map(|t| *t as u8));
+                match ret {
+                    Some(t) => types.extend([1, *t as u8]),
+                    None => types.push(0),
+                }
+            }
+            section(&mut module, SectionId::Type as u8, &types);
+
+            let import_count = 1 + self.fn_imports.len() + self.imports_stack_pointer as usize;
+            let mut imports = leb(import_count as u32);
+            imports.extend(name("env"));
+            imports.extend(name("__linear_memory"));
+            imports.extend([2, 0, 0]); // memory, min 0
+            for (fn_name, sig) in self.fn_imports.iter() {
+                imports.extend(name("env"));
+                imports.extend(name(fn_name));
+                imports.push(0);
+                imports.extend(leb(*sig));
+            }
+            if self.imports_stack_pointer {
+                imports.extend(name("env"));
+                imports.extend(name(STACK_POINTER_NAME));
+                imports.extend([3, ValueType::I32 as u8, 1]);
+            }
+            section(&mut module, SectionId::Import as u8, &imports);
+
+            let mut functions = leb(self.functions.len() as u32);
+            for (sig, _) in self.functions.iter() {
+                functions.extend(leb(*sig));
+            }
+            section(&mut module, SectionId::Function as u8, &functions);
+
+            // Code section, remembering where each function body starts for relocations
+            let mut code = leb(self.functions.len() as u32);
+            let mut body_starts = vec![];
+            for (_, body) in self.functions.iter() {
+                let mut full_body = vec![0]; // no locals
+                full_body.extend_from_slice(body);
+                code.extend(leb(full_body.len() as u32));
+                body_starts.push(code.len() + 1);
+                code.extend(full_body);
+            }
+            section(&mut module, SectionId::Code as u8, &code);
+
+            let mut data = leb(self.data.len() as u32);
+            let mut object_addr = 0;
+            for init in self.data.iter() {
+                data.extend(active_data_mode(object_addr));
+                data.extend(leb(init.len() as u32));
+                data.extend_from_slice(init);
+                object_addr += init.len() as u32;
+            }
+            if !self.data.is_empty() {
+                section(&mut module, SectionId::Data as u8, &data);
+            }
+
+            let mut linking = name("linking");
+            linking.push(2); // version
+            let mut symbol_table = leb(self.symbols.len() as u32);
+            for sym in self.symbols.iter() {
+                symbol_table.extend_from_slice(sym);
+            }
+            linking.push(8); // symbol table
+            linking.extend(leb(symbol_table.len() as u32));
+            linking.extend(symbol_table);
+            if !self.data.is_empty() {
+                let mut segment_info = leb(self.data.len() as u32);
+                for i in 0..self.data.len() {
+                    segment_info.extend(name(&format!(".data.{i}")));
+                    segment_info.extend(leb(2)); // 4-byte alignment
+                    segment_info.extend(leb(0));
+                }
+                linking.push(5); // segment info
+                linking.extend(leb(segment_info.len() as u32));
+                linking.extend(segment_info);
+            }
+            section(&mut module, SectionId::Custom as u8, &linking);
+
+            let code_section_index = 3;
+            let mut reloc_code = name("reloc.CODE");
+            reloc_code.extend(leb(code_section_index));
+            reloc_code.extend(leb(self.code_relocs.len() as u32));
+            for (type_id, offset, symbol, fn_index) in self.code_relocs.iter() {
+                reloc_code.push(*type_id);
+                reloc_code.extend(leb((body_starts[*fn_index] + offset) as u32));
+                reloc_code.extend(leb(*symbol));
+                if *type_id == MEMORY_ADDR_SLEB {
+                    reloc_code.extend(leb(0)); // addend
+                }
+            }
+            section(&mut module, SectionId::Custom as u8, &reloc_code);
+
+            module
+        }
+    }
+
+    fn active_data_mode(addr: u32) -> std::vec::Vec<u8> {
+        let mut bytes = vec![0, OpCode::I32CONST as u8];
+        bytes.extend(leb(addr));
+        bytes.push(OpCode::END as u8);
+        bytes
+    }
+
+    fn call(fn_index: u32) -> std::vec::Vec<u8> {
+        let mut bytes = vec![OpCode::CALL as u8];
+        bytes.extend(padded(fn_index));
+        bytes
+    }
+
+    fn i32_const_padded(value: u32) -> std::vec::Vec<u8> {
+        let mut bytes = vec![OpCode::I32CONST as u8];
+        bytes.extend(padded(value));
+        bytes
+    }
+
+    /// A host whose `main` calls the app's `roc__mainForHost_1_exposed`, adds 1,
+    /// and exports a `roc_alloc` function for the app to call.
+    fn test_host() -> std::vec::Vec<u8> {
+        let mut host = TestObject {
+            types: vec![
+                (vec![], Some(ValueType::I32)),
+                (vec![ValueType::I32], Some(ValueType::I32)),
+            ],
+            fn_imports: vec![("roc__mainForHost_1_exposed", 0)],
+            ..Default::default()
+        };
+
+        let main_import = host.fn_symbol(WASM_SYM_UNDEFINED, 0, "");
+
+        // main: roc__mainForHost_1_exposed() + 1
+        let mut main_body = call(0);
+        main_body.extend([OpCode::I32CONST as u8, 1, OpCode::I32ADD as u8, 0x0b]);
+        host.functions.push((0, main_body));
+        host.fn_symbol(WASM_SYM_EXPORTED, 1, "main");
+        host.code_relocs.push((FN_INDEX_LEB, 1, main_import, 0));
+
+        // roc_alloc: returns its argument times 2
+        host.functions.push((
+            1,
+            vec![
+                OpCode::GETLOCAL as u8,
+                0,
+                OpCode::I32CONST as u8,
+                2,
+                OpCode::I32MUL as u8,
+                0x0b,
+            ],
+        ));
+        host.fn_symbol(0, 2, "roc_alloc");
+
+        host.serialize()
+    }
+
+    /// An app whose `roc__mainForHost_1_exposed` calls the host's `roc_alloc`, and
+    /// adds a value loaded from its own data segment.
+    fn test_app() -> std::vec::Vec<u8> {
+        let mut app = TestObject {
+            types: vec![
+                (vec![ValueType::I32], Some(ValueType::I32)),
+                (vec![], Some(ValueType::I32)),
+            ],
+            fn_imports: vec![("roc_alloc", 0)],
+            data: vec![vec![0, 0, 0, 0], 40u32.to_le_bytes().to_vec()],
+            ..Default::default()
+        };
+
+        let alloc_import = app.fn_symbol(WASM_SYM_UNDEFINED, 0, "");
+        let data_sym = app.data_symbol(WASM_SYM_BINDING_LOCAL, "forty", 1);
+        let sp_sym = app.stack_pointer_symbol();
+
+        // roc__mainForHost_1_exposed: roc_alloc(1) + load(&forty)
+        //   (also touches the stack pointer, to check global relocations)
+        let mut body = vec![OpCode::GETGLOBAL as u8];
+        body.extend(padded(0));
+        body.push(OpCode::DROP as u8);
+        body.extend([OpCode::I32CONST as u8, 1]);
+        let call_offset = body.len() + 1;
+        body.extend(call(0));
+        let addr_offset = body.len() + 1;
+        body.extend(i32_const_padded(0));
+        body.extend([OpCode::I32LOAD as u8, 2, 0]);
+        body.extend([OpCode::I32ADD as u8, 0x0b]);
+
+        app.functions.push((1, body));
+        app.fn_symbol(0, 1, "roc__mainForHost_1_exposed");
+        app.code_relocs.push((GLOBAL_INDEX_LEB, 1, sp_sym, 0));
+        app.code_relocs
+            .push((FN_INDEX_LEB, call_offset, alloc_import, 0));
+        app.code_relocs
+            .push((MEMORY_ADDR_SLEB, addr_offset, data_sym, 0));
+
+        app.serialize()
+    }
+
+    fn run_main(module: &WasmModule<'_>) -> Value {
+        let arena = Bump::new();
+        let mut buffer = std::vec::Vec::with_capacity(module.size());
+        module.serialize(&mut buffer);
+
+        let mut instance =
+            Instance::from_bytes(&arena, &buffer, DefaultImportDispatcher::default(), false)
+                .unwrap();
+        instance.call_export("main", []).unwrap().unwrap()
+    }
+
+    #[test]
+    fn link_host_and_app() {
+        let arena = Bump::new();
+        let module = link_wasm(&arena, &test_host(), &test_app()).unwrap();
+
+        // No imports left: the host and app provided everything to each other
+        assert_eq!(module.import.function_count(), 0);
+        assert_eq!(module.code.function_count, 3);
+
+        // roc_alloc(1) = 2, plus 40 from the app's data, plus 1 from the host
+        assert_eq!(run_main(&module), Value::I32(43));
+    }
+
+    #[test]
+    fn link_data_layout() {
+        let arena = Bump::new();
+        let module = link_wasm(&arena, &test_host(), &test_app()).unwrap();
+
+        // The app's first segment is all zeroes, so only the second one is written out
+        let mut memory = vec![0; module.memory.min_bytes().unwrap() as usize];
+        module.data.load_into(&mut memory).unwrap();
+        assert_eq!(
+            &memory[GLOBAL_BASE as usize..][..8],
+            &[0, 0, 0, 0, 40, 0, 0, 0]
+        );
+
+        // The stack comes after the data, and the stack pointer starts at the top
+        let stack_pointer = module.global.parse_u32_at_index(0).unwrap();
+        assert_eq!(
+            stack_pointer,
+            align_up(GLOBAL_BASE + 8, STACK_ALIGNMENT) + STACK_SIZE
+        );
+    }
+
+    #[test]
+    fn link_exports() {
+        let arena = Bump::new();
+        let module = link_wasm(&arena, &test_host(), &test_app()).unwrap();
+
+        let exports: std::vec::Vec<_> = module.export.exports.iter().map(|e| e.name).collect();
+
+        // The host calls roc__mainForHost_1_exposed itself, so it is not exported
+        assert_eq!(exports, [MEMORY_NAME, "main"]);
+    }
+
+    #[test]
+    fn unresolved_imports_stay_imported() {
+        let mut app = TestObject {
+            types: vec![(vec![], Some(ValueType::I32))],
+            fn_imports: vec![("js_random", 0)],
+            ..Default::default()
+        };
+        let js_import = app.fn_symbol(WASM_SYM_UNDEFINED, 0, "");
+        app.functions
+            .push((0, call(0).into_iter().chain([0x0b]).collect()));
+        app.fn_symbol(0, 1, "roc__mainForHost_1_exposed");
+        app.code_relocs.push((FN_INDEX_LEB, 1, js_import, 0));
+
+        let arena = Bump::new();
+        let module = link_wasm(&arena, &test_host(), &app.serialize()).unwrap();
+
+        assert_eq!(module.import.imports.len(), 1);
+        assert_eq!(module.import.imports[0].name, "js_random");
+    }
+
+    #[test]
+    fn function_pointers_go_in_the_table() {
+        let mut app = TestObject {
+            types: vec![(vec![], Some(ValueType::I32))],
+            ..Default::default()
+        };
+
+        // roc__mainForHost_1_exposed: call_indirect(&seven)
+        let mut body = i32_const_padded(0);
+        body.extend([OpCode::CALLINDIRECT as u8, 0, 0, 0x0b]);
+        app.functions.push((0, body));
+        app.functions
+            .push((0, vec![OpCode::I32CONST as u8, 7, 0x0b]));
+
+        app.fn_symbol(0, 0, "roc__mainForHost_1_exposed");
+        let seven = app.fn_symbol(WASM_SYM_BINDING_LOCAL, 1, "seven");
+        app.code_relocs.push((TABLE_INDEX_SLEB, 1, seven, 0));
+
+        let arena = Bump::new();
+        let module = link_wasm(&arena, &test_host(), &app.serialize()).unwrap();
+
+        assert_eq!(module.element.max_table_index(), 2);
+        assert_eq!(run_main(&module), Value::I32(8));
+    }
+
+    #[test]
+    fn missing_data_symbol_is_an_error() {
+        let mut app = TestObject {
+            types: vec![(vec![], Some(ValueType::I32))],
+            ..Default::default()
+        };
+        let missing = app.data_symbol(WASM_SYM_UNDEFINED, "some_global", 0);
+        app.functions
+            .push((0, i32_const_padded(0).into_iter().chain([0x0b]).collect()));
+        app.fn_symbol(0, 0, "roc__mainForHost_1_exposed");
+        app.code_relocs.push((MEMORY_ADDR_SLEB, 1, missing, 0));
+
+        let arena = Bump::new();
+        let result = link_wasm(&arena, &test_host(), &app.serialize());
+
+        assert!(result.unwrap_err().contains("some_global"));
+    }
+
+    /// An app that defines its own `roc_alloc`, returning its argument plus 100
+    fn app_with_roc_alloc(flags: u32) -> std::vec::Vec<u8> {
+        let mut app = TestObject {
+            types: vec![
+                (vec![], Some(ValueType::I32)),
+                (vec![ValueType::I32], Some(ValueType::I32)),
+            ],
+            ..Default::default()
+        };
+
+        // roc__mainForHost_1_exposed: roc_alloc(1)
+        let mut body = vec![OpCode::I32CONST as u8, 1];
+        body.extend(call(1));
+        body.push(0x0b);
+        app.functions.push((0, body));
+        app.functions.push((
+            1,
+            vec![
+                OpCode::GETLOCAL as u8,
+                0,
+                OpCode::I32CONST as u8,
+                100,
+                OpCode::I32ADD as u8,
+                0x0b,
+            ],
+        ));
+
+        app.fn_symbol(0, 0, "roc__mainForHost_1_exposed");
+        let alloc = app.fn_symbol(flags, 1, "roc_alloc");
+        app.code_relocs.push((FN_INDEX_LEB, 3, alloc, 0));
+
+        app.serialize()
+    }
+
+    #[test]
+    fn duplicate_strong_definition_is_an_error() {
+        let arena = Bump::new();
+        let result = link_wasm(&arena, &test_host(), &app_with_roc_alloc(0));
+
+        assert!(result.unwrap_err().contains("roc_alloc"));
+    }
+
+    #[test]
+    fn strong_definition_overrides_weak_one() {
+        let arena = Bump::new();
+        let module = link_wasm(
+            &arena,
+            &test_host(),
+            &app_with_roc_alloc(WASM_SYM_BINDING_WEAK),
+        )
+        .unwrap();
+
+        // The app calls the host's roc_alloc: roc_alloc(1) = 2, plus 1 from the host
+        assert_eq!(run_main(&module), Value::I32(3));
+    }
+}
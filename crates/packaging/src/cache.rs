@@ -1,11 +1,12 @@
 #[cfg(not(target_family = "wasm"))]
 use {
     crate::https::{self, PackageMetadata, Problem},
+    crate::{mirror, signature},
     roc_error_macros::internal_error,
     std::fs,
 };
 #[cfg(not(target_family = "wasm"))]
-const MAX_DOWNLOAD_BYTES: u64 = 32 * 1_000_000_000; // GB
+pub(crate) const MAX_DOWNLOAD_BYTES: u64 = 32 * 1_000_000_000; // GB
 
 use std::path::{Path, PathBuf};
 
@@ -70,11 +71,12 @@ pub fn install_package<'a>(
     roc_cache_dir: RocCacheDir<'_>,
     url: &'a str,
 ) -> Result<(PathBuf, Option<&'a str>), Problem> {
+    let metadata = PackageMetadata::try_from(url).map_err(Problem::InvalidUrl)?;
     let PackageMetadata {
         cache_subdir,
         content_hash,
         root_module_filename,
-    } = PackageMetadata::try_from(url).map_err(Problem::InvalidUrl)?;
+    } = metadata;
 
     match roc_cache_dir {
         RocCacheDir::Persistent(cache_dir) => {
@@ -95,14 +97,40 @@ pub fn install_package<'a>(
                 Ok((dest_dir, root_module_filename))
             } else {
                 // Download into a tempdir; only move it to dest_dir if hash verification passes.
-                println!(
-                    "Downloading \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
-                    cache_dir.display()
-                );
+                let trusted_keys = signature::trusted_keys_from_env().map_err(Problem::IoErr)?;
                 let tempdir = tempfile::tempdir().map_err(Problem::IoErr)?;
                 let tempdir_path = tempdir.path();
-                let downloaded_hash =
-                    https::download_and_hash(url, tempdir_path, MAX_DOWNLOAD_BYTES)?;
+                let downloaded_hash = match mirror::mirror_dir_from_env() {
+                    Some(mirror_dir) => {
+                        // Installing from a mirror (e.g. on a machine without network access)
+                        // gets the same hash and signature verification as downloading.
+                        println!(
+                            "Installing \u{001b}[36m{url}\u{001b}[0m\n    from the mirror in {}\n    into {}\n",
+                            mirror_dir.display(),
+                            cache_dir.display()
+                        );
+
+                        mirror::unpack_and_hash(
+                            &mirror_dir,
+                            &metadata,
+                            tempdir_path,
+                            &trusted_keys,
+                        )?
+                    }
+                    None => {
+                        println!(
+                            "Downloading \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
+                            cache_dir.display()
+                        );
+
+                        https::download_and_hash(
+                            url,
+                            tempdir_path,
+                            MAX_DOWNLOAD_BYTES,
+                            &trusted_keys,
+                        )?
+                    }
+                };
 
                 // Download the tarball into memory and verify it.
                 // The tarball name is the hash of its contents.
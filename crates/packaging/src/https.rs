@@ -1,8 +1,9 @@
 use std::{
     io::{self, Read},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+use crate::signature::{PublicKey, Signature, SignatureProblem, SIGNATURE_FILE_EXT};
 use crate::tarball::Compression;
 
 // gzip should be the most widely supported, and brotli offers the highest compression.
@@ -13,7 +14,10 @@ use crate::tarball::Compression;
 // let's try to avoid doing that.
 const BROTLI_BUFFER_BYTES: usize = 8 * 1_000_000; // MB
 
-#[derive(Debug, PartialEq, Eq)]
+/// A signature file is a single short line, so anything bigger than this isn't one.
+const MAX_SIGNATURE_BYTES: u64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PackageMetadata<'a> {
     /// The BLAKE3 hash of the tarball's contents. Also the .tar filename on disk.
     pub content_hash: &'a str,
@@ -240,13 +244,46 @@ pub enum Problem {
     InvalidUrl(UrlProblem),
     /// The Content-Length header of the response exceeded max_download_bytes
     DownloadTooBig(u64),
+    InvalidSignature(SignatureProblem),
+    /// A package mirror is configured, but it doesn't have the package at this path
+    NotInMirror(PathBuf),
 }
 
+/// Download the tarball at the given URL, unpack it into dest_dir, and return its content hash.
+///
+/// If any trusted keys are given, this also downloads the detached signature next to the
+/// tarball (the same URL, plus `.sig`) and verifies that one of those keys signed the hash.
 pub fn download_and_hash(
     url: &str,
     dest_dir: &Path,
     max_download_bytes: u64,
+    trusted_keys: &[PublicKey],
 ) -> Result<String, Problem> {
+    let (encoding, reader) = request_tarball(url, max_download_bytes)?;
+    let hash = decompress_into(dest_dir, encoding, reader)?;
+
+    if !trusted_keys.is_empty() {
+        download_signature(url)?
+            .verify(&hash, trusted_keys)
+            .map_err(Problem::InvalidSignature)?;
+    }
+
+    Ok(hash)
+}
+
+/// Download the tarball at the given URL and return its uncompressed bytes, without unpacking it.
+pub fn download_tarball(url: &str, max_download_bytes: u64) -> Result<Vec<u8>, Problem> {
+    let (encoding, reader) = request_tarball(url, max_download_bytes)?;
+    let mut bytes = Vec::new();
+
+    decompressor(encoding, reader)
+        .read_to_end(&mut bytes)
+        .map_err(Problem::IoErr)?;
+
+    Ok(bytes)
+}
+
+fn request_tarball(url: &str, max_download_bytes: u64) -> Result<(Encoding, impl Read), Problem> {
     // TODO apparently it really improves performance to construct a Client once and then reuse it,
     // instead of making a new Client for every request.
     // Per https://github.com/seanmonstar/reqwest/issues/1454#issuecomment-1026076701
@@ -277,7 +314,33 @@ pub fn download_and_hash(
 
     // Use .take to prevent a malicious server from sending back bytes
     // until system resources are exhausted!
-    decompress_into(dest_dir, encoding, resp.take(max_download_bytes))
+    Ok((encoding, resp.take(max_download_bytes)))
+}
+
+/// Download the detached signature which lives next to the tarball at the given URL.
+pub(crate) fn download_signature(url: &str) -> Result<Signature, Problem> {
+    // The signature lives next to the tarball, so drop the URL fragment (if any)
+    let end_of_tarball = url.rfind('#').unwrap_or(url.len());
+    let signature_url = format!("{}{SIGNATURE_FILE_EXT}", &url[0..end_of_tarball]);
+
+    let resp = reqwest::blocking::Client::new()
+        .get(signature_url)
+        .send()
+        .map_err(Problem::HttpErr)?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Problem::InvalidSignature(SignatureProblem::Missing));
+    }
+
+    let mut contents = String::new();
+
+    resp.error_for_status()
+        .map_err(Problem::HttpErr)?
+        .take(MAX_SIGNATURE_BYTES)
+        .read_to_string(&mut contents)
+        .map_err(Problem::IoErr)?;
+
+    Signature::parse(&contents).map_err(Problem::InvalidSignature)
 }
 
 /// The content encodings we support
@@ -338,7 +401,7 @@ fn encoding_from_tar_br() {
     assert_eq!(Encoding::Brotli, actual);
 }
 
-fn hash_and_unpack(dest_dir: &Path, reader: impl Read) -> Result<String, Problem> {
+pub(crate) fn hash_and_unpack(dest_dir: &Path, reader: impl Read) -> Result<String, Problem> {
     let mut hash_reader = HashReader::new(reader);
 
     tar::Archive::new(&mut hash_reader)
@@ -377,6 +440,15 @@ fn decompress_into(
     }
 }
 
+fn decompressor<'a>(encoding: Encoding, reader: impl Read + 'a) -> Box<dyn Read + 'a> {
+    match encoding {
+        Encoding::Brotli => Box::new(brotli::Decompressor::new(reader, BROTLI_BUFFER_BYTES)),
+        Encoding::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        Encoding::Deflate => Box::new(flate2::read::DeflateDecoder::new(reader)),
+        Encoding::Uncompressed => Box::new(reader),
+    }
+}
+
 /// Read something while calculating its BLAKE3 hash
 struct HashReader<R: Read> {
     reader: R,
@@ -1,4 +1,10 @@
 pub mod cache;
 #[cfg(not(target_family = "wasm"))]
 pub mod https;
+#[cfg(not(target_family = "wasm"))]
+pub mod lock;
+#[cfg(not(target_family = "wasm"))]
+pub mod mirror;
+#[cfg(not(target_family = "wasm"))]
+pub mod signature;
 pub mod tarball;
@@ -0,0 +1,275 @@
+use crate::cache::{self, RocCacheDir};
+use crate::https::{PackageMetadata, Problem};
+use crate::mirror::package_urls;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The lockfile `roc build` writes next to the root module
+pub const LOCKFILE_NAME: &str = "roc.lock";
+
+const LOCKFILE_HEADER: &str = "\
+# This file is generated by `roc build` to record every package this module depends on.
+# Each line is a package URL, its content hash, and the module which depends on it.
+# Use `roc build --locked` to fail instead of updating it when these change.
+";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LockedPackage {
+    pub url: String,
+    pub content_hash: String,
+    /// The file name of the root module, or the URL of the package whose header
+    /// listed this package
+    pub required_by: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    /// Sorted, so that the same dependencies always give the same file
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug)]
+pub enum LockProblem {
+    /// Installing a package failed
+    Install {
+        url: String,
+        problem: Problem,
+    },
+    Io(io::Error),
+    Malformed(String),
+    /// In --locked mode, a package that isn't in the lockfile would be needed
+    NotLocked(LockedPackage),
+    /// In --locked mode, the lockfile has packages which are no longer needed
+    NoLongerNeeded(Vec<LockedPackage>),
+}
+
+impl Lockfile {
+    pub fn parse(contents: &str) -> Result<Self, LockProblem> {
+        let mut packages = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // The module comes last, because it's a file name which might contain spaces.
+            let mut parts = line.splitn(3, ' ');
+
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(url), Some(content_hash), Some(required_by)) => {
+                    packages.push(LockedPackage {
+                        url: url.to_string(),
+                        content_hash: content_hash.to_string(),
+                        required_by: required_by.to_string(),
+                    });
+                }
+                _ => return Err(LockProblem::Malformed(line.to_string())),
+            }
+        }
+
+        packages.sort();
+
+        Ok(Self { packages })
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let mut buf = String::from(LOCKFILE_HEADER);
+
+        for LockedPackage {
+            url,
+            content_hash,
+            required_by,
+        } in self.packages.iter()
+        {
+            writeln!(buf, "{url} {content_hash} {required_by}").unwrap();
+        }
+
+        buf
+    }
+
+    /// Read the lockfile at the given path, if there is one
+    pub fn read(path: &Path) -> Result<Option<Self>, LockProblem> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(LockProblem::Io(err)),
+        }
+    }
+}
+
+/// Install every package the given root module depends on (including the packages those
+/// depend on, and so on), and return a lockfile listing them.
+///
+/// If a lockfile is given, this fails instead of installing a package that isn't in it,
+/// and also if any of the packages in it turn out to be unused.
+pub fn resolve(
+    roc_cache_dir: RocCacheDir<'_>,
+    root_module: &Path,
+    locked: Option<&Lockfile>,
+) -> Result<Lockfile, LockProblem> {
+    let root_name = root_module
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut stack: Vec<(String, String)> = package_urls(root_module)
+        .map_err(LockProblem::Io)?
+        .into_iter()
+        .map(|url| (url, root_name.clone()))
+        .collect();
+    let mut packages = Vec::new();
+
+    while let Some((url, required_by)) = stack.pop() {
+        let content_hash = PackageMetadata::try_from(url.as_str())
+            .map_err(|url_problem| LockProblem::Install {
+                url: url.clone(),
+                problem: Problem::InvalidUrl(url_problem),
+            })?
+            .content_hash
+            .to_string();
+        let package = LockedPackage {
+            url,
+            content_hash,
+            required_by,
+        };
+
+        if packages.contains(&package) {
+            continue;
+        }
+
+        // Check this before installing, so we never download anything the lockfile doesn't list
+        if let Some(locked) = locked {
+            if !locked.packages.contains(&package) {
+                return Err(LockProblem::NotLocked(package));
+            }
+        }
+
+        let (package_dir, opt_root_module) = cache::install_package(roc_cache_dir, &package.url)
+            .map_err(|problem| LockProblem::Install {
+                url: package.url.clone(),
+                problem,
+            })?;
+        let package_root = package_dir.join(opt_root_module.unwrap_or("main.roc"));
+
+        if package_root.exists() {
+            for dependency in package_urls(&package_root).map_err(LockProblem::Io)? {
+                stack.push((dependency, package.url.clone()));
+            }
+        }
+
+        packages.push(package);
+    }
+
+    packages.sort();
+
+    if let Some(locked) = locked {
+        let no_longer_needed: Vec<_> = locked
+            .packages
+            .iter()
+            .filter(|package| !packages.contains(package))
+            .cloned()
+            .collect();
+
+        if !no_longer_needed.is_empty() {
+            return Err(LockProblem::NoLongerNeeded(no_longer_needed));
+        }
+    }
+
+    Ok(Lockfile { packages })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const URL: &str = "https://example.com/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar.br";
+
+    fn write_app(dir: &Path) -> std::path::PathBuf {
+        let path = dir.join("main.roc");
+
+        fs::write(
+            &path,
+            format!("app \"example\"\n    packages {{ pf: \"{URL}\" }}\n    imports []\n    provides [main] to pf\n"),
+        )
+        .unwrap();
+
+        path
+    }
+
+    fn locked_package() -> LockedPackage {
+        LockedPackage {
+            url: URL.to_string(),
+            content_hash: "jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE".to_string(),
+            required_by: "main.roc".to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let lockfile = Lockfile {
+            packages: vec![LockedPackage {
+                required_by: "my app.roc".to_string(),
+                ..locked_package()
+            }],
+        };
+
+        assert_eq!(
+            Lockfile::parse(&lockfile.to_file_contents()).unwrap(),
+            lockfile
+        );
+    }
+
+    #[test]
+    fn resolve_records_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let app = write_app(dir.path());
+
+        let lockfile = resolve(RocCacheDir::Temp(&cache_dir), &app, None).unwrap();
+
+        assert_eq!(lockfile.packages, [locked_package()]);
+
+        // Resolving again with that lockfile gives the same result
+        let relocked = resolve(RocCacheDir::Temp(&cache_dir), &app, Some(&lockfile)).unwrap();
+
+        assert_eq!(relocked, lockfile);
+    }
+
+    #[test]
+    fn locked_rejects_new_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let app = write_app(dir.path());
+
+        assert!(matches!(
+            resolve(
+                RocCacheDir::Temp(&cache_dir),
+                &app,
+                Some(&Lockfile::default())
+            ),
+            Err(LockProblem::NotLocked(package)) if package == locked_package()
+        ));
+    }
+
+    #[test]
+    fn locked_rejects_unused_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let app = write_app(dir.path());
+        let unused = LockedPackage {
+            url: "https://example.com/tE4xS_zLdmmxmHwHih9kHWQ7fsXtJr7W7h3425-eZFk.tar.br"
+                .to_string(),
+            content_hash: "tE4xS_zLdmmxmHwHih9kHWQ7fsXtJr7W7h3425-eZFk".to_string(),
+            required_by: "main.roc".to_string(),
+        };
+        let locked = Lockfile {
+            packages: vec![locked_package(), unused.clone()],
+        };
+
+        assert!(matches!(
+            resolve(RocCacheDir::Temp(&cache_dir), &app, Some(&locked)),
+            Err(LockProblem::NoLongerNeeded(packages)) if packages == [unused]
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::cache::MAX_DOWNLOAD_BYTES;
+use crate::https::{self, PackageMetadata, Problem};
+use crate::signature::{self, PublicKey, Signature, SignatureProblem};
+use bumpalo::Bump;
+use roc_parse::ast::{ExtractSpaces, Header};
+use roc_parse::module::parse_header;
+use roc_parse::state::State;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The environment variable pointing to a package mirror (for example one created by
+/// `roc vendor`). When it's set, packages get installed from there instead of the network.
+pub const MIRROR_ENV_VAR: &str = "ROC_PACKAGE_MIRROR";
+
+/// The mirror directory from the ROC_PACKAGE_MIRROR environment variable, if it's set.
+/// This can be either a plain path or a `file://` URL.
+pub fn mirror_dir_from_env() -> Option<PathBuf> {
+    std::env::var_os(MIRROR_ENV_VAR).map(|dir| {
+        let dir = PathBuf::from(dir);
+
+        match dir.to_str().and_then(|dir| dir.strip_prefix("file://")) {
+            Some(without_scheme) => PathBuf::from(without_scheme),
+            None => dir,
+        }
+    })
+}
+
+/// Mirrors use the same layout as package URLs, except that tarballs are stored uncompressed,
+/// so the file's BLAKE3 hash is the content hash in its name.
+///
+/// e.g. <mirror>/example.com/roc-packages/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar
+pub fn tarball_path(mirror_dir: &Path, metadata: &PackageMetadata) -> PathBuf {
+    mirror_dir
+        .join(metadata.cache_subdir)
+        .join(format!("{}.tar", metadata.content_hash))
+}
+
+/// Unpack a package from the mirror into dest_dir, and return its content hash.
+///
+/// Like https::download_and_hash, if any trusted keys are given this also verifies that one
+/// of them signed the package.
+pub fn unpack_and_hash(
+    mirror_dir: &Path,
+    metadata: &PackageMetadata,
+    dest_dir: &Path,
+    trusted_keys: &[PublicKey],
+) -> Result<String, Problem> {
+    let tarball_path = tarball_path(mirror_dir, metadata);
+    let file = File::open(&tarball_path).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => Problem::NotInMirror(tarball_path.clone()),
+        _ => Problem::IoErr(err),
+    })?;
+    let hash = https::hash_and_unpack(dest_dir, io::BufReader::new(file))?;
+
+    if !trusted_keys.is_empty() {
+        let contents = match fs::read_to_string(signature::signature_path(&tarball_path)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(Problem::InvalidSignature(SignatureProblem::Missing));
+            }
+            Err(err) => return Err(Problem::IoErr(err)),
+        };
+
+        Signature::parse(&contents)
+            .and_then(|signature| signature.verify(&hash, trusted_keys))
+            .map_err(Problem::InvalidSignature)?;
+    }
+
+    Ok(hash)
+}
+
+/// Download the given packages into the mirror directory, along with the packages
+/// they depend on, and so on. Returns the URLs of all the packages that were downloaded.
+///
+/// If something goes wrong, returns the URL that caused it along with the problem.
+pub fn vendor(urls: Vec<String>, mirror_dir: &Path) -> Result<Vec<String>, (String, Problem)> {
+    let mut stack = urls;
+    let mut vendored = Vec::new();
+
+    while let Some(url) = stack.pop() {
+        if vendored.contains(&url) {
+            continue;
+        }
+
+        match vendor_package(mirror_dir, &url) {
+            Ok(dependencies) => {
+                stack.extend(dependencies);
+                vendored.push(url);
+            }
+            Err(problem) => return Err((url, problem)),
+        }
+    }
+
+    Ok(vendored)
+}
+
+/// Download one package into the mirror, and return the URLs of the packages it depends on.
+fn vendor_package(mirror_dir: &Path, url: &str) -> Result<Vec<String>, Problem> {
+    let metadata = PackageMetadata::try_from(url).map_err(Problem::InvalidUrl)?;
+    let bytes = https::download_tarball(url, MAX_DOWNLOAD_BYTES)?;
+    let hash = base64_url::encode(blake3::hash(&bytes).as_bytes());
+
+    if hash != metadata.content_hash {
+        return Err(Problem::InvalidContentHash {
+            expected: metadata.content_hash.to_string(),
+            actual: hash,
+        });
+    }
+
+    let tarball_path = tarball_path(mirror_dir, &metadata);
+
+    fs::create_dir_all(tarball_path.parent().unwrap()).map_err(Problem::IoErr)?;
+    fs::write(&tarball_path, &bytes).map_err(Problem::IoErr)?;
+
+    // Keep the package's signature (if it has one), so it can still be verified offline
+    match https::download_signature(url) {
+        Ok(signature) => fs::write(
+            signature::signature_path(&tarball_path),
+            signature.to_file_contents(),
+        )
+        .map_err(Problem::IoErr)?,
+        Err(Problem::InvalidSignature(SignatureProblem::Missing)) => {}
+        Err(problem) => return Err(problem),
+    }
+
+    // Unpack it to see which packages its root module depends on
+    let tempdir = tempfile::tempdir().map_err(Problem::IoErr)?;
+
+    https::hash_and_unpack(tempdir.path(), bytes.as_slice())?;
+
+    let root_module = tempdir
+        .path()
+        .join(metadata.root_module_filename.unwrap_or("main.roc"));
+
+    if root_module.exists() {
+        package_urls(&root_module).map_err(Problem::IoErr)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// The URLs of the packages in the given .roc file's header.
+/// Packages referenced by a local path are skipped, since there's nothing to download.
+pub fn package_urls(path: &Path) -> io::Result<Vec<String>> {
+    let arena = Bump::new();
+    let src = fs::read(path)?;
+    let (module, _) = parse_header(&arena, State::new(&src)).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("couldn't parse the header of {}", path.display()),
+        )
+    })?;
+
+    let packages = match module.header {
+        Header::App(header) => header
+            .packages
+            .map_or(&[][..], |packages| packages.item.items),
+        Header::Package(header) => header.packages.item.items,
+        Header::Platform(header) => header.packages.item.items,
+        Header::Interface(_) | Header::Hosted(_) => &[],
+    };
+
+    Ok(packages
+        .iter()
+        .map(|entry| {
+            entry
+                .value
+                .extract_spaces()
+                .item
+                .package_name
+                .value
+                .to_str()
+        })
+        .filter(|package_name| package_name.starts_with("https://"))
+        .map(String::from)
+        .collect())
+}
+
+#[test]
+fn app_package_urls() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("main.roc");
+
+    fs::write(
+        &path,
+        r#"app "example"
+    packages {
+        pf: "https://example.com/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar.br",
+        json: "../json/main.roc",
+    }
+    imports [pf.Stdout]
+    provides [main] to pf
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        package_urls(&path).unwrap(),
+        ["https://example.com/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar.br"]
+    );
+}
+
+#[test]
+fn unpack_from_mirror() {
+    let package_dir = tempfile::tempdir().unwrap();
+    let main_path = package_dir.path().join("main.roc");
+
+    fs::write(
+        &main_path,
+        "package \"example\"\n    exposes []\n    packages {}\n",
+    )
+    .unwrap();
+
+    let filename =
+        crate::tarball::build(&main_path, crate::tarball::Compression::Uncompressed).unwrap();
+    let url = format!("https://example.com/packages/{filename}");
+    let metadata = PackageMetadata::try_from(url.as_str()).unwrap();
+
+    let mirror_dir = tempfile::tempdir().unwrap();
+    let tarball = tarball_path(mirror_dir.path(), &metadata);
+
+    fs::create_dir_all(tarball.parent().unwrap()).unwrap();
+    fs::copy(main_path.with_file_name(&filename), &tarball).unwrap();
+
+    let dest_dir = tempfile::tempdir().unwrap();
+    let hash = unpack_and_hash(mirror_dir.path(), &metadata, dest_dir.path(), &[]).unwrap();
+
+    assert_eq!(hash, metadata.content_hash);
+    assert!(dest_dir.path().join("main.roc").exists());
+
+    // With trusted keys, an unsigned package in the mirror isn't good enough
+    let key = "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"
+        .parse()
+        .unwrap();
+
+    assert!(matches!(
+        unpack_and_hash(mirror_dir.path(), &metadata, dest_dir.path(), &[key]),
+        Err(Problem::InvalidSignature(SignatureProblem::Missing))
+    ));
+}
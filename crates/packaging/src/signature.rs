@@ -0,0 +1,243 @@
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The extension of the detached signature file `roc build --bundle --sign` writes next to the
+/// bundle, e.g. `jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar.br.sig`
+pub const SIGNATURE_FILE_EXT: &str = ".sig";
+
+/// The environment variable pointing to a file of trusted public keys, one per line.
+/// When it's set, every downloaded package must have a signature by one of those keys.
+pub const TRUSTED_KEYS_ENV_VAR: &str = "ROC_TRUSTED_KEYS";
+
+/// Signature files start with this, so we can support other algorithms later if we need to.
+const ALGORITHM: &str = "ed25519";
+
+const PUBLIC_KEY_BYTES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey([u8; PUBLIC_KEY_BYTES]);
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // base64url, for the same reasons we use it for content hashes
+        write!(f, "{}", base64_url::encode(&self.0))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = SignatureProblem;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        base64_url::decode(string)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(PublicKey)
+            .ok_or_else(|| SignatureProblem::Malformed(format!("invalid public key {string:?}")))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureProblem {
+    /// We have trusted keys, but the server had no signature for the package
+    Missing,
+    Malformed(String),
+    /// The signature was made by a key which isn't in the trusted keys list
+    UntrustedKey(String),
+    /// The signature doesn't match the package's content hash
+    Mismatch,
+}
+
+/// A detached signature of a bundle's content hash.
+///
+/// Signing the (uncompressed) content hash rather than the file itself means one signature
+/// covers every compressed version of the same bundle.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub public_key: PublicKey,
+    bytes: Vec<u8>,
+}
+
+impl Signature {
+    /// Sign a content hash with an ed25519 key in PKCS#8 DER format, for example one created by
+    /// `openssl genpkey -algorithm ed25519 -outform DER -out key.der`
+    pub fn new(pkcs8_key: &[u8], content_hash: &str) -> io::Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8_key).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not a valid ed25519 PKCS#8 signing key: {err}"),
+            )
+        })?;
+        let public_key = key_pair
+            .public_key()
+            .as_ref()
+            .try_into()
+            .map(PublicKey)
+            .unwrap();
+
+        Ok(Self {
+            public_key,
+            bytes: key_pair.sign(content_hash.as_bytes()).as_ref().to_vec(),
+        })
+    }
+
+    /// Verify that this is a signature of the given content hash, made by one of the trusted keys.
+    pub fn verify(
+        &self,
+        content_hash: &str,
+        trusted_keys: &[PublicKey],
+    ) -> Result<(), SignatureProblem> {
+        if !trusted_keys.contains(&self.public_key) {
+            return Err(SignatureProblem::UntrustedKey(self.public_key.to_string()));
+        }
+
+        UnparsedPublicKey::new(&ED25519, &self.public_key.0)
+            .verify(content_hash.as_bytes(), &self.bytes)
+            .map_err(|_| SignatureProblem::Mismatch)
+    }
+
+    /// Parse the contents of a signature file, which look like this:
+    ///
+    /// ed25519 <base64url public key> <base64url signature>
+    pub fn parse(contents: &str) -> Result<Self, SignatureProblem> {
+        let malformed = || SignatureProblem::Malformed(contents.trim().to_string());
+
+        match contents.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [ALGORITHM, public_key, signature] => Ok(Self {
+                public_key: public_key.parse()?,
+                bytes: base64_url::decode(signature).map_err(|_| malformed())?,
+            }),
+            _ => Err(malformed()),
+        }
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        format!(
+            "{ALGORITHM} {} {}\n",
+            self.public_key,
+            base64_url::encode(&self.bytes)
+        )
+    }
+}
+
+/// Sign the bundle at the given path, writing the signature next to it.
+/// Returns the path to the signature file.
+pub fn sign_bundle(
+    signing_key_path: &Path,
+    bundle_path: &Path,
+    content_hash: &str,
+) -> io::Result<(PathBuf, PublicKey)> {
+    let signature = Signature::new(&fs::read(signing_key_path)?, content_hash)?;
+    let signature_path = signature_path(bundle_path);
+
+    fs::write(&signature_path, signature.to_file_contents())?;
+
+    Ok((signature_path, signature.public_key))
+}
+
+/// The path of the detached signature for the tarball at the given path
+pub(crate) fn signature_path(tarball_path: &Path) -> PathBuf {
+    let mut signature_path = tarball_path.as_os_str().to_owned();
+
+    signature_path.push(SIGNATURE_FILE_EXT);
+
+    PathBuf::from(signature_path)
+}
+
+/// Parse a list of trusted keys: one base64url-encoded ed25519 public key per line.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_trusted_keys(contents: &str) -> Result<Vec<PublicKey>, SignatureProblem> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::parse)
+        .collect()
+}
+
+/// Read the trusted keys from the file named by the ROC_TRUSTED_KEYS environment variable.
+/// Returns an empty list if it isn't set, in which case only content hashes get verified.
+pub fn trusted_keys_from_env() -> io::Result<Vec<PublicKey>> {
+    match std::env::var_os(TRUSTED_KEYS_ENV_VAR) {
+        Some(path) => parse_trusted_keys(&fs::read_to_string(&path)?).map_err(|problem| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "couldn't read the trusted keys in {} ({problem:?})",
+                    Path::new(&path).display()
+                ),
+            )
+        }),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ring::rand::SystemRandom;
+
+    const CONTENT_HASH: &str = "jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE";
+
+    fn new_key() -> Vec<u8> {
+        Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let signature = Signature::new(&new_key(), CONTENT_HASH).unwrap();
+        let parsed = Signature::parse(&signature.to_file_contents()).unwrap();
+
+        assert_eq!(parsed, signature);
+        assert_eq!(parsed.verify(CONTENT_HASH, &[signature.public_key]), Ok(()));
+    }
+
+    #[test]
+    fn wrong_content_hash() {
+        let signature = Signature::new(&new_key(), CONTENT_HASH).unwrap();
+
+        assert_eq!(
+            signature.verify(
+                "tE4xS_zLdmmxmHwHih9kHWQ7fsXtJr7W7h3425-eZFk",
+                &[signature.public_key]
+            ),
+            Err(SignatureProblem::Mismatch)
+        );
+    }
+
+    #[test]
+    fn untrusted_key() {
+        let signature = Signature::new(&new_key(), CONTENT_HASH).unwrap();
+        let other = Signature::new(&new_key(), CONTENT_HASH).unwrap();
+
+        assert_eq!(
+            signature.verify(CONTENT_HASH, &[other.public_key]),
+            Err(SignatureProblem::UntrustedKey(
+                signature.public_key.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn trusted_keys_file() {
+        let key = Signature::new(&new_key(), CONTENT_HASH).unwrap().public_key;
+        let contents = format!("# release key\n{key}\n\n");
+
+        assert_eq!(parse_trusted_keys(&contents), Ok(vec![key]));
+        assert!(parse_trusted_keys("not a key").is_err());
+    }
+
+    #[test]
+    fn malformed_signature() {
+        assert!(matches!(
+            Signature::parse("rsa abc def"),
+            Err(SignatureProblem::Malformed(_))
+        ));
+    }
+}
@@ -8,7 +8,7 @@ use roc_parse::state::State;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tar;
 use walkdir::WalkDir;
 
@@ -57,7 +57,9 @@ impl<'a> TryFrom<&'a str> for Compression {
 /// Given a path to a .roc file, write a .tar file to disk.
 ///
 /// The .tar file will be in the same directory, and its filename
-/// will be the hash of its contents. This function returns
+/// will be the hash of its contents. The archive is deterministic: building the
+/// same files twice gives the same bytes (and therefore the same filename),
+/// regardless of filesystem ordering, timestamps, or file ownership. This function returns
 /// the name of that filename (including the .tar extension),
 /// so the caller can obtain the path to the file by calling
 /// Path::with_file_name(returned_string) on the Path argument it provided.
@@ -124,6 +126,7 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
     let mut builder = tar::Builder::new(writer);
     let arena = Bump::new();
     let mut buf = Vec::new();
+    let mut entries = Vec::new();
 
     // TODO use this when finding .roc files by discovering them from the root module.
     // let other_modules: &[Module<'_>] =
@@ -141,7 +144,7 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
             // TODO report error
         }
         Header::Package(_) => {
-            add_dot_roc_files(root_dir, &mut builder)?;
+            add_dot_roc_files(root_dir, &mut entries)?;
         }
         Header::Platform(PlatformHeader { imports: _, .. }) => {
             // Add all the prebuilt host files to the archive.
@@ -164,17 +167,16 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
                 ]
                 .contains(&path.extension().and_then(OsStr::to_str))
                 {
-                    builder.append_path_with_name(
-                        &path,
-                        // Store it without the root path, so that (for example) we don't store
-                        // `examples/cli/main.roc` and therefore end up with the root of the tarball
-                        // being an `examples/cli/` dir instead of having `main.roc` in the root.
-                        path.strip_prefix(root_dir).unwrap(),
-                    )?;
+                    // Store it without the root path, so that (for example) we don't store
+                    // `examples/cli/main.roc` and therefore end up with the root of the tarball
+                    // being an `examples/cli/` dir instead of having `main.roc` in the root.
+                    let name = path.strip_prefix(root_dir).unwrap().to_path_buf();
+
+                    entries.push((name, path));
                 }
             }
 
-            add_dot_roc_files(root_dir, &mut builder)?;
+            add_dot_roc_files(root_dir, &mut entries)?;
         }
     };
 
@@ -216,13 +218,59 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
     //     }
     // }
 
+    // The order in which the filesystem gives us entries isn't stable across machines,
+    // so sort them to make sure the same files always produce the same archive.
+    entries.sort();
+    entries.dedup_by(|(a, _), (b, _)| a == b);
+
+    for (name, path) in entries {
+        append_normalized(&mut builder, &name, &path)?;
+    }
+
     builder.finish()
 }
 
-fn add_dot_roc_files<W: Write>(
-    root_dir: &Path,
+/// Append a file to the archive with all the metadata that varies between machines
+/// (timestamps, owners, and permissions other than the executable bit) normalized.
+fn append_normalized<W: Write>(
     builder: &mut tar::Builder<W>,
-) -> Result<(), io::Error> {
+    name: &Path,
+    path: &Path,
+) -> io::Result<()> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let mut header = tar::Header::new_gnu();
+
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(metadata.len());
+    header.set_mode(if is_executable(&metadata) {
+        0o755
+    } else {
+        0o644
+    });
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_username("")?;
+    header.set_groupname("")?;
+
+    // This also sets the header's checksum
+    builder.append_data(&mut header, name, file)
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+fn add_dot_roc_files(root_dir: &Path, entries: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
     for entry in WalkDir::new(root_dir).into_iter().filter_entry(|entry| {
         let path = entry.path();
 
@@ -237,13 +285,12 @@ fn add_dot_roc_files<W: Write>(
         // added based on the paths of the files inside anyway. (In fact, if we don't
         // filter out directories in this step, then empty ones can sometimes be added!)
         if path.is_file() {
-            builder.append_path_with_name(
-                path,
-                // Store it without the root path, so that (for example) we don't store
-                // `examples/cli/main.roc` and therefore end up with the root of the tarball
-                // being an `examples/cli/` dir instead of having `main.roc` in the root.
-                path.strip_prefix(root_dir).unwrap(),
-            )?;
+            // Store it without the root path, so that (for example) we don't store
+            // `examples/cli/main.roc` and therefore end up with the root of the tarball
+            // being an `examples/cli/` dir instead of having `main.roc` in the root.
+            let name = path.strip_prefix(root_dir).unwrap().to_path_buf();
+
+            entries.push((name, path.to_path_buf()));
         }
     }
 
@@ -274,3 +321,30 @@ fn read_header<'a>(
 
     Ok(module)
 }
+
+#[test]
+fn deterministic_archive() {
+    let files = [
+        (
+            "main.roc",
+            "package \"example\"\n    exposes [Foo, Bar]\n    packages {}\n",
+        ),
+        ("Foo.roc", "interface Foo exposes [] imports []\n"),
+        ("Bar.roc", "interface Bar exposes [] imports []\n"),
+    ];
+
+    let build_in_tempdir = |order: &[usize]| {
+        let dir = tempfile::tempdir().unwrap();
+
+        for &index in order {
+            let (name, contents) = files[index];
+
+            std::fs::write(dir.path().join(name), contents).unwrap();
+        }
+
+        build(&dir.path().join("main.roc"), Compression::Uncompressed).unwrap()
+    };
+
+    // Writing the files in a different order (and at a different time) must not change the hash
+    assert_eq!(build_in_tempdir(&[0, 1, 2]), build_in_tempdir(&[2, 1, 0]));
+}
@@ -0,0 +1,404 @@
+//! Counting how often `roc test` reaches each instrumented def and `when` branch, and reporting
+//! the results as a terminal summary or in the lcov format.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use roc_collections::MutMap;
+use roc_error_macros::internal_error;
+use roc_gen_llvm::llvm::externs::COVERAGE_COUNTERS;
+use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::coverage::{CoverageKind, CoveragePoint};
+use roc_region::all::LineInfo;
+
+/// One counter per coverage point, in memory that is shared with any child processes, so that
+/// the hits of effectful expects (which run in a fork) are counted too.
+pub struct CoverageCounters {
+    ptr: *mut u64,
+    len: usize,
+}
+
+impl CoverageCounters {
+    pub fn new(len: usize) -> Self {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                Self::byte_len(len),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            internal_error!("failed to mmap the coverage counters");
+        }
+
+        Self {
+            ptr: ptr.cast(),
+            len,
+        }
+    }
+
+    /// Point the instrumented code in `lib` at these counters
+    pub fn install(&mut self, lib: &libloading::Library) {
+        let symbol = format!("{COVERAGE_COUNTERS}\0");
+
+        unsafe {
+            // If nothing was instrumented, there is nothing to count
+            if let Ok(global) = lib.get::<*mut *mut u64>(symbol.as_bytes()) {
+                **global = self.ptr;
+            }
+        }
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    fn byte_len(len: usize) -> usize {
+        // mmap does not accept a length of 0
+        len.max(1) * std::mem::size_of::<u64>()
+    }
+}
+
+impl Drop for CoverageCounters {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.cast(), Self::byte_len(self.len));
+        }
+    }
+}
+
+struct FileCoverage<'a> {
+    path: &'a Path,
+    /// Name, line and hits of each def
+    defs: Vec<(&'a str, u32, u64)>,
+    /// Line and hits of each `when` branch
+    branches: Vec<(u32, u64)>,
+}
+
+impl FileCoverage<'_> {
+    fn defs_hit(&self) -> usize {
+        self.defs.iter().filter(|(_, _, hits)| *hits > 0).count()
+    }
+
+    fn branches_hit(&self) -> usize {
+        self.branches.iter().filter(|(_, hits)| *hits > 0).count()
+    }
+
+    /// The hits of every line that a def or branch starts on
+    fn lines(&self) -> Vec<(u32, u64)> {
+        let mut lines: Vec<(u32, u64)> = Vec::new();
+
+        let starts = self.defs.iter().map(|(_, line, hits)| (*line, *hits));
+        let starts = starts.chain(self.branches.iter().copied());
+
+        for (line, hits) in starts {
+            match lines.iter_mut().find(|(l, _)| *l == line) {
+                Some((_, most_hits)) => *most_hits = (*most_hits).max(hits),
+                None => lines.push((line, hits)),
+            }
+        }
+
+        lines.sort_unstable();
+
+        lines
+    }
+}
+
+/// Groups the points by the file they are in, sorted by path and then by position in the file
+fn by_file<'a>(
+    sources: &'a MutMap<ModuleId, (PathBuf, Box<str>)>,
+    interns: &'a Interns,
+    points: &[CoveragePoint],
+    counts: &[u64],
+) -> Vec<FileCoverage<'a>> {
+    debug_assert_eq!(points.len(), counts.len());
+
+    let mut modules: MutMap<ModuleId, (LineInfo, Vec<(CoveragePoint, u64)>)> = MutMap::default();
+
+    for (point, hits) in points.iter().zip(counts) {
+        let (_, src) = &sources[&point.module_id];
+
+        modules
+            .entry(point.module_id)
+            .or_insert_with(|| (LineInfo::new(src), Vec::new()))
+            .1
+            .push((*point, *hits));
+    }
+
+    let mut files: Vec<_> = modules
+        .into_iter()
+        .map(|(module_id, (line_info, mut points))| {
+            points.sort_by_key(|(point, _)| point.region.start());
+
+            let mut file = FileCoverage {
+                path: &sources[&module_id].0,
+                defs: Vec::new(),
+                branches: Vec::new(),
+            };
+
+            for (point, hits) in points {
+                // lcov numbers lines from 1
+                let line = line_info.convert_pos(point.region.start()).line + 1;
+
+                match point.kind {
+                    CoverageKind::Def(symbol) => {
+                        file.defs.push((symbol.as_str(interns), line, hits));
+                    }
+                    CoverageKind::WhenBranch => file.branches.push((line, hits)),
+                }
+            }
+
+            file
+        })
+        .collect();
+
+    files.sort_by(|a, b| a.path.cmp(b.path));
+
+    files
+}
+
+fn percentage(hit: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        100.0 * hit as f64 / total as f64
+    }
+}
+
+/// A table of how many defs and `when` branches were reached in each file, followed by the
+/// defs which never were.
+pub fn render_summary(
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    interns: &Interns,
+    points: &[CoveragePoint],
+    counts: &[u64],
+) -> String {
+    let files = by_file(sources, interns, points, counts);
+
+    let rows: Vec<_> = files
+        .iter()
+        .map(|file| {
+            (
+                file.path.display().to_string(),
+                (file.defs_hit(), file.defs.len()),
+                (file.branches_hit(), file.branches.len()),
+            )
+        })
+        .collect();
+
+    let total = rows.iter().fold(((0, 0), (0, 0)), |(defs, branches), row| {
+        (
+            (defs.0 + row.1 .0, defs.1 + row.1 .1),
+            (branches.0 + row.2 .0, branches.1 + row.2 .1),
+        )
+    });
+
+    let width = rows
+        .iter()
+        .map(|(path, _, _)| path.len())
+        .max()
+        .unwrap_or(0)
+        .max("Total".len());
+
+    let mut buf = String::new();
+
+    let _ = writeln!(buf, "{:width$}  {:>16}  {:>16}", "File", "Defs", "Branches");
+
+    let mut write_row = |name: &str, (defs_hit, defs), (branches_hit, branches)| {
+        let _ = writeln!(
+            buf,
+            "{name:width$}  {:>16}  {:>16}",
+            format!("{defs_hit}/{defs} {:5.1}%", percentage(defs_hit, defs)),
+            format!(
+                "{branches_hit}/{branches} {:5.1}%",
+                percentage(branches_hit, branches)
+            ),
+        );
+    };
+
+    for (path, defs, branches) in rows.iter() {
+        write_row(path, *defs, *branches);
+    }
+
+    write_row("Total", total.0, total.1);
+
+    let never_run: Vec<_> = files
+        .iter()
+        .flat_map(|file| {
+            file.defs
+                .iter()
+                .filter(|(_, _, hits)| *hits == 0)
+                .map(move |(name, line, _)| format!("{}:{line} {name}", file.path.display()))
+        })
+        .collect();
+
+    if !never_run.is_empty() {
+        let _ = writeln!(buf, "\nThese defs never ran:\n");
+
+        for def in never_run {
+            let _ = writeln!(buf, "    {def}");
+        }
+    }
+
+    buf
+}
+
+/// The coverage of each file in the lcov tracefile format, which many editors and CI services
+/// can display.
+pub fn render_lcov(
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    interns: &Interns,
+    points: &[CoveragePoint],
+    counts: &[u64],
+) -> String {
+    let mut buf = String::new();
+
+    for file in by_file(sources, interns, points, counts) {
+        let _ = writeln!(buf, "TN:");
+        let _ = writeln!(buf, "SF:{}", file.path.display());
+
+        for (name, line, _) in file.defs.iter() {
+            let _ = writeln!(buf, "FN:{line},{name}");
+        }
+
+        for (name, _, hits) in file.defs.iter() {
+            let _ = writeln!(buf, "FNDA:{hits},{name}");
+        }
+
+        let _ = writeln!(buf, "FNF:{}", file.defs.len());
+        let _ = writeln!(buf, "FNH:{}", file.defs_hit());
+
+        // We don't keep track of which `when` each branch belongs to, so they share one block
+        for (index, (line, hits)) in file.branches.iter().enumerate() {
+            let _ = writeln!(buf, "BRDA:{line},0,{index},{hits}");
+        }
+
+        let _ = writeln!(buf, "BRF:{}", file.branches.len());
+        let _ = writeln!(buf, "BRH:{}", file.branches_hit());
+
+        let lines = file.lines();
+
+        for (line, hits) in lines.iter() {
+            let _ = writeln!(buf, "DA:{line},{hits}");
+        }
+
+        let _ = writeln!(buf, "LF:{}", lines.len());
+        let _ = writeln!(
+            buf,
+            "LH:{}",
+            lines.iter().filter(|(_, hits)| *hits > 0).count()
+        );
+        let _ = writeln!(buf, "end_of_record");
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use roc_module::symbol::Symbol;
+    use roc_region::all::{Position, Region};
+
+    #[test]
+    fn lcov_and_summary() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [used, unused] imports []
+
+            used = \x ->
+                when x is
+                    0 -> "zero"
+                    _ -> "other"
+
+            unused = 42
+            "#
+        );
+
+        let region = |start: usize, end: usize| {
+            Region::new(Position::new(start as u32), Position::new(end as u32))
+        };
+        let offset = |needle: &str| src.find(needle).unwrap();
+
+        let mut interns = Interns::default();
+        let home = interns.module_ids.get_or_insert(&"Foo".into());
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+        let used = Symbol::new(home, ident_ids.add_str("used"));
+        let unused = Symbol::new(home, ident_ids.add_str("unused"));
+
+        let mut sources = MutMap::default();
+        sources.insert(home, (PathBuf::from("Foo.roc"), src.into()));
+
+        let points = [
+            CoveragePoint {
+                module_id: home,
+                region: region(offset("used ="), offset("unused =")),
+                kind: CoverageKind::Def(used),
+            },
+            CoveragePoint {
+                module_id: home,
+                region: region(offset("\"zero\""), offset("\"zero\"") + 6),
+                kind: CoverageKind::WhenBranch,
+            },
+            CoveragePoint {
+                module_id: home,
+                region: region(offset("\"other\""), offset("\"other\"") + 7),
+                kind: CoverageKind::WhenBranch,
+            },
+            CoveragePoint {
+                module_id: home,
+                region: region(offset("unused ="), src.len()),
+                kind: CoverageKind::Def(unused),
+            },
+        ];
+        let counts = [3, 1, 2, 0];
+
+        assert_eq!(
+            render_lcov(&sources, &interns, &points, &counts),
+            indoc!(
+                r#"
+                TN:
+                SF:Foo.roc
+                FN:3,used
+                FN:8,unused
+                FNDA:3,used
+                FNDA:0,unused
+                FNF:2
+                FNH:1
+                BRDA:5,0,0,1
+                BRDA:6,0,1,2
+                BRF:2
+                BRH:2
+                DA:3,3
+                DA:5,1
+                DA:6,2
+                DA:8,0
+                LF:4
+                LH:3
+                end_of_record
+                "#
+            )
+        );
+
+        assert_eq!(
+            render_summary(&sources, &interns, &points, &counts),
+            indoc!(
+                r#"
+                File                 Defs          Branches
+                Foo.roc        1/2  50.0%        2/2 100.0%
+                Total          1/2  50.0%        2/2 100.0%
+
+                These defs never ran:
+
+                    Foo.roc:8 unused
+                "#
+            )
+        );
+    }
+}
@@ -15,6 +15,8 @@ use {
 #[cfg(not(windows))]
 mod app;
 #[cfg(not(windows))]
+pub mod coverage;
+#[cfg(not(windows))]
 pub mod run;
 
 #[cfg(not(windows))]
@@ -95,7 +97,9 @@ mod test {
     use pretty_assertions::assert_eq;
     use roc_error_macros::internal_error;
     use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult, run_roc_dylib};
-    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, Threading};
+    use roc_load::{
+        ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, TestOptions, Threading,
+    };
     use roc_packaging::cache::RocCacheDir;
     use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
     use target_lexicon::Triple;
@@ -127,7 +131,7 @@ mod test {
             render: RenderTarget::ColorTerminal,
             palette: DEFAULT_PALETTE,
             threading: Threading::Single,
-            exec_mode: ExecutionMode::Test,
+            exec_mode: ExecutionMode::Test(TestOptions::default()),
         };
         let loaded = match roc_load::load_and_monomorphize_from_str(
             arena,
@@ -173,7 +177,7 @@ mod test {
         unsafe { set_shared_buffer((shared_buffer.as_mut_ptr(), BUFFER_SIZE), &mut result) };
 
         let mut writer = Vec::with_capacity(1024);
-        let (_failed, _passed) = crate::run::run_expects_with_memory(
+        let _outcomes = crate::run::run_expects_with_memory(
             &mut writer,
             RenderTarget::ColorTerminal,
             arena,
@@ -1253,4 +1257,62 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn toplevel_expect_labels() {
+        use crate::run::describe_toplevel_expect;
+        use roc_region::all::{Position, Region};
+
+        let arena = bumpalo::Bump::new();
+        let region_from = |src: &str, needle: &str| {
+            let start = src.find(needle).unwrap() as u32;
+
+            Region::new(Position::new(start), Position::new(src.len() as u32))
+        };
+
+        let src = indoc!(
+            r#"
+            x = 1
+
+            # not about the expect
+
+            # adds one
+            ## to two
+            expect x + 2 == 3
+            "#
+        );
+
+        assert_eq!(
+            describe_toplevel_expect(&arena, src, region_from(src, "# not")),
+            (7, Some("adds one to two"))
+        );
+
+        let src = indoc!(
+            r#"
+            x = 1
+            expect x == 1
+            "#
+        );
+
+        assert_eq!(
+            describe_toplevel_expect(&arena, src, region_from(src, "expect")),
+            (2, None)
+        );
+
+        // in the code block of a doc comment
+        let src = indoc!(
+            r#"
+            ## ```roc
+            ## # doubles two
+            ## expect double 2 == 4
+            ## ```
+            double = \n -> n * 2
+            "#
+        );
+
+        assert_eq!(
+            describe_toplevel_expect(&arena, src, region_from(src, "# doubles")),
+            (3, Some("doubles two"))
+        );
+    }
 }
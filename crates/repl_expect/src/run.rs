@@ -1,9 +1,10 @@
 use std::{
     os::unix::process::parent_id,
     sync::{
-        atomic::{AtomicBool, AtomicU32},
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
 use bumpalo::collections::Vec as BumpVec;
@@ -14,7 +15,10 @@ use roc_can::expr::ExpectLookup;
 use roc_collections::{MutSet, VecMap};
 use roc_error_macros::internal_error;
 use roc_gen_llvm::{
-    llvm::{build::LlvmBackendMode, externs::add_default_roc_externs},
+    llvm::{
+        build::LlvmBackendMode,
+        externs::{add_coverage_counters, add_default_roc_externs},
+    },
     run_roc::RocCallResult,
     run_roc_dylib,
 };
@@ -119,10 +123,34 @@ impl<'a> ExpectMemory<'a> {
         sequence.wait_for_child(sigchld)
     }
 
+    /// Like [`ExpectMemory::wait_for_child`], but returns `None` immediately if the child
+    /// has not sent anything, instead of waiting for it.
+    pub fn try_recv_from_child(&self) -> Option<ChildProcessMsg> {
+        let sequence = ExpectSequence { ptr: self.ptr };
+        sequence.try_recv_from_child()
+    }
+
     pub fn reset(&mut self) {
         let mut sequence = ExpectSequence { ptr: self.ptr };
         sequence.reset();
     }
+
+    /// Removes the name of the shared memory when the returned guard is dropped, once no other
+    /// process needs to find it
+    fn unlink_on_drop(&self) -> UnlinkOnDrop {
+        UnlinkOnDrop(self.shm_name.clone())
+    }
+}
+
+/// Unlinks the named shared memory when dropped, so it's also removed when we return early
+struct UnlinkOnDrop(Option<std::ffi::CString>);
+
+impl Drop for UnlinkOnDrop {
+    fn drop(&mut self) {
+        if let Some(shm_name) = &self.0 {
+            unsafe { libc::shm_unlink(shm_name.as_ptr()) };
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -139,7 +167,7 @@ pub fn run_inline_expects<'a, W: std::io::Write>(
     let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
     let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
 
-    run_expects_with_memory(
+    let outcomes = run_expects_with_memory(
         writer,
         render_target,
         arena,
@@ -149,11 +177,17 @@ pub fn run_inline_expects<'a, W: std::io::Write>(
         expectations,
         expects,
         &mut memory,
-    )
+    )?;
+
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+
+    Ok((failed, outcomes.len() - failed))
 }
 
+/// Runs the given top-level expects, in up to `workers` processes at once, and writes the
+/// failures to `writer` in the order of `expects`.
 #[allow(clippy::too_many_arguments)]
-pub fn run_toplevel_expects<'a, W: std::io::Write>(
+pub fn run_toplevel_expects<'a, 'e, W: std::io::Write>(
     writer: &mut W,
     render_target: RenderTarget,
     arena: &'a Bump,
@@ -161,8 +195,23 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
     layout_interner: &GlobalLayoutInterner<'a>,
     lib: &libloading::Library,
     expectations: &mut VecMap<ModuleId, Expectations>,
-    expects: ExpectFunctions<'_>,
-) -> std::io::Result<(usize, usize)> {
+    expects: ExpectFunctions<'e>,
+    workers: usize,
+) -> std::io::Result<Vec<ExpectOutcome<'e>>> {
+    if workers > 1 && expects.len() > 1 {
+        return run_expects_in_workers(
+            writer,
+            render_target,
+            arena,
+            interns,
+            layout_interner,
+            lib,
+            expectations,
+            expects,
+            workers,
+        );
+    }
+
     let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
     let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
 
@@ -180,7 +229,7 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
+pub(crate) fn run_expects_with_memory<'a, 'e, W: std::io::Write>(
     writer: &mut W,
     render_target: RenderTarget,
     arena: &'a Bump,
@@ -188,14 +237,15 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
     layout_interner: &GlobalLayoutInterner<'a>,
     lib: &libloading::Library,
     expectations: &mut VecMap<ModuleId, Expectations>,
-    expects: ExpectFunctions<'_>,
+    expects: ExpectFunctions<'e>,
     memory: &mut ExpectMemory,
-) -> std::io::Result<(usize, usize)> {
-    let mut failed = 0;
-    let mut passed = 0;
+) -> std::io::Result<Vec<ExpectOutcome<'e>>> {
+    let mut outcomes = Vec::with_capacity(expects.len());
 
     for expect in expects.fx {
-        let result = run_expect_fx(
+        let start = Instant::now();
+
+        let passed = run_expect_fx(
             writer,
             render_target,
             arena,
@@ -207,16 +257,19 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
             expect,
         )?;
 
-        match result {
-            true => passed += 1,
-            false => failed += 1,
-        }
+        outcomes.push(ExpectOutcome {
+            expect,
+            passed,
+            duration: start.elapsed(),
+        });
     }
 
     memory.set_shared_buffer(lib);
 
     for expect in expects.pure {
-        let result = run_expect_pure(
+        let start = Instant::now();
+
+        let passed = run_expect_pure(
             writer,
             render_target,
             arena,
@@ -228,13 +281,222 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
             expect,
         )?;
 
-        match result {
-            true => passed += 1,
-            false => failed += 1,
+        outcomes.push(ExpectOutcome {
+            expect,
+            passed,
+            duration: start.elapsed(),
+        });
+    }
+
+    Ok(outcomes)
+}
+
+/// Runs the expects in up to `workers` processes at once, which take the next expect to run
+/// from a counter they share.
+///
+/// Expects can't run on several threads of one process, because the memory that failures are
+/// reported through and the buffer that a `crash` jumps to are globals in the generated code.
+/// So each worker is a fork of this process, and sends its results back through a pipe.
+#[allow(clippy::too_many_arguments)]
+fn run_expects_in_workers<'a, 'e, W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    lib: &libloading::Library,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expects: ExpectFunctions<'e>,
+    workers: usize,
+) -> std::io::Result<Vec<ExpectOutcome<'e>>> {
+    use std::io::Write;
+    use std::os::unix::io::FromRawFd;
+
+    // Like when running in one process, effectful expects go first
+    let fx = expects.fx.iter().map(|expect| (true, *expect));
+    let all: Vec<(bool, ToplevelExpect)> = fx
+        .chain(expects.pure.iter().map(|expect| (false, *expect)))
+        .collect();
+
+    let next = SharedCounter::new();
+
+    // Otherwise, anything still buffered would be written by every worker
+    std::io::stdout().flush()?;
+    writer.flush()?;
+
+    let mut children = Vec::with_capacity(workers);
+
+    for _ in 0..workers.min(all.len()) {
+        let mut fds = [0; 2];
+
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let [read_fd, write_fd] = fds;
+
+        match unsafe { libc::fork() } {
+            0 => {
+                // we are the worker
+                unsafe { libc::close(read_fd) };
+
+                let mut pipe = unsafe { std::fs::File::from_raw_fd(write_fd) };
+
+                let result = run_worker(
+                    &mut pipe,
+                    render_target,
+                    arena,
+                    interns,
+                    layout_interner,
+                    lib,
+                    expectations,
+                    &all,
+                    next.get(),
+                );
+
+                std::process::exit(result.is_err() as i32)
+            }
+            -1 => return Err(std::io::Error::last_os_error()),
+            pid => {
+                unsafe { libc::close(write_fd) };
+
+                let pipe = unsafe { std::fs::File::from_raw_fd(read_fd) };
+
+                children.push((pid, pipe));
+            }
         }
     }
 
-    Ok((failed, passed))
+    let reports: Vec<WorkerReport> = std::thread::scope(|scope| {
+        let readers: Vec<_> = children
+            .iter_mut()
+            .map(|(_, pipe)| scope.spawn(move || WorkerReport::read_all(pipe)))
+            .collect();
+
+        readers
+            .into_iter()
+            .flat_map(|reader| reader.join().unwrap())
+            .collect()
+    });
+
+    for (pid, _) in children {
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+
+        // A worker which crashed didn't get to drop its guard, so unlink its memory for it
+        if libc::WIFSIGNALED(status) {
+            let shm_name = format!("/roc_expect_buffer_{pid}");
+
+            drop(UnlinkOnDrop(std::ffi::CString::new(shm_name).ok()));
+        }
+    }
+
+    let mut by_index: Vec<Option<WorkerReport>> = all.iter().map(|_| None).collect();
+
+    for report in reports {
+        let index = report.index;
+        by_index[index] = Some(report);
+    }
+
+    let mut outcomes = Vec::with_capacity(all.len());
+
+    for ((_, expect), report) in all.into_iter().zip(by_index) {
+        match report {
+            Some(report) => {
+                writer.write_all(&report.output)?;
+
+                outcomes.push(ExpectOutcome {
+                    expect,
+                    passed: report.passed,
+                    duration: report.duration,
+                });
+            }
+            None => {
+                writeln!(
+                    writer,
+                    "The process running the expect at {} crashed before it finished.\n",
+                    expect.display_name(interns),
+                )?;
+
+                outcomes.push(ExpectOutcome {
+                    expect,
+                    passed: false,
+                    duration: Duration::ZERO,
+                });
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_worker<'a>(
+    pipe: &mut std::fs::File,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    lib: &libloading::Library,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    all: &[(bool, ToplevelExpect)],
+    next: &AtomicUsize,
+) -> std::io::Result<()> {
+    // every worker needs its own memory, so the children of its effectful expects can find it
+    let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
+    let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
+    let _unlink = memory.unlink_on_drop();
+
+    memory.set_shared_buffer(lib);
+
+    loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+
+        let (is_fx, expect) = match all.get(index) {
+            Some(next_expect) => *next_expect,
+            None => break,
+        };
+
+        let mut output = Vec::new();
+        let start = Instant::now();
+
+        let passed = if is_fx {
+            run_expect_fx(
+                &mut output,
+                render_target,
+                arena,
+                interns,
+                layout_interner,
+                lib,
+                expectations,
+                &mut memory,
+                expect,
+            )?
+        } else {
+            run_expect_pure(
+                &mut output,
+                render_target,
+                arena,
+                interns,
+                layout_interner,
+                lib,
+                expectations,
+                &mut memory,
+                expect,
+            )?
+        };
+
+        let report = WorkerReport {
+            index,
+            passed,
+            duration: start.elapsed(),
+            output,
+        };
+
+        report.write(pipe)?;
+    }
+
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -625,24 +887,33 @@ impl ExpectSequence {
 
     fn wait_for_child(&self, sigchld: Arc<AtomicBool>) -> ChildProcessMsg {
         use std::sync::atomic::Ordering;
-        let ptr = self.ptr as *const u32;
-        let atomic_ptr: *const AtomicU32 = unsafe { ptr.add(5).cast() };
-        let atomic = unsafe { &*atomic_ptr };
 
         loop {
             if sigchld.load(Ordering::Relaxed) {
                 break ChildProcessMsg::Terminate;
             }
 
-            match atomic.load(Ordering::Acquire) {
-                0 => std::hint::spin_loop(),
-                1 => break ChildProcessMsg::Expect,
-                2 => break ChildProcessMsg::Dbg,
-                n => internal_error!("invalid atomic value set by the child: {n:#x}"),
+            match self.try_recv_from_child() {
+                None => std::hint::spin_loop(),
+                Some(msg) => break msg,
             }
         }
     }
 
+    fn try_recv_from_child(&self) -> Option<ChildProcessMsg> {
+        use std::sync::atomic::Ordering;
+        let ptr = self.ptr as *const u32;
+        let atomic_ptr: *const AtomicU32 = unsafe { ptr.add(5).cast() };
+        let atomic = unsafe { &*atomic_ptr };
+
+        match atomic.load(Ordering::Acquire) {
+            0 => None,
+            1 => Some(ChildProcessMsg::Expect),
+            2 => Some(ChildProcessMsg::Dbg),
+            n => internal_error!("invalid atomic value set by the child: {n:#x}"),
+        }
+    }
+
     fn reset(&mut self) {
         unsafe {
             let ptr = self.ptr as *mut usize;
@@ -690,6 +961,22 @@ pub struct ToplevelExpect<'a> {
     pub name: &'a str,
     pub symbol: Symbol,
     pub region: Region,
+    /// The line of the `expect` keyword, counting from 1
+    pub line: u32,
+    /// The comment directly above the expect, which we use to tell expects apart
+    pub label: Option<&'a str>,
+}
+
+impl ToplevelExpect<'_> {
+    /// The module and line of the expect, followed by its label if it has one
+    pub fn display_name(&self, interns: &Interns) -> String {
+        let module_name = interns.module_name(self.symbol.module_id());
+
+        match self.label {
+            Some(label) => format!("{}:{} {}", module_name, self.line, label),
+            None => format!("{}:{}", module_name, self.line),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -698,6 +985,33 @@ pub struct ExpectFunctions<'a> {
     pub fx: BumpVec<'a, ToplevelExpect<'a>>,
 }
 
+impl<'a> ExpectFunctions<'a> {
+    pub fn len(&self) -> usize {
+        self.pure.len() + self.fx.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ToplevelExpect<'a>> {
+        self.fx.iter().chain(self.pure.iter())
+    }
+
+    /// Only keep the expects for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&ToplevelExpect<'a>) -> bool) {
+        self.pure.retain(|expect| keep(expect));
+        self.fx.retain(|expect| keep(expect));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectOutcome<'a> {
+    pub expect: ToplevelExpect<'a>,
+    pub passed: bool,
+    pub duration: Duration,
+}
+
 pub fn expect_mono_module_to_dylib<'a>(
     arena: &'a Bump,
     target: Triple,
@@ -719,6 +1033,7 @@ pub fn expect_mono_module_to_dylib<'a>(
         procedures,
         interns,
         layout_interner,
+        sources,
         ..
     } = loaded;
 
@@ -767,15 +1082,25 @@ pub fn expect_mono_module_to_dylib<'a>(
         procedures,
     );
 
+    // Only does anything if the procedures were instrumented to measure coverage
+    add_coverage_counters(&env);
+
     let expects_fx = bumpalo::collections::Vec::from_iter_in(
         toplevel_expects
             .fx
             .into_iter()
             .zip(expect_names.iter().skip(toplevel_expects.pure.len()))
-            .map(|((symbol, region), name)| ToplevelExpect {
-                symbol,
-                region,
-                name,
+            .map(|((symbol, region), name)| {
+                let (_, src) = &sources[&symbol.module_id()];
+                let (line, label) = describe_toplevel_expect(arena, src, region);
+
+                ToplevelExpect {
+                    name,
+                    symbol,
+                    region,
+                    line,
+                    label,
+                }
             }),
         env.arena,
     );
@@ -785,10 +1110,17 @@ pub fn expect_mono_module_to_dylib<'a>(
             .pure
             .into_iter()
             .zip(expect_names.iter())
-            .map(|((symbol, region), name)| ToplevelExpect {
-                symbol,
-                region,
-                name,
+            .map(|((symbol, region), name)| {
+                let (_, src) = &sources[&symbol.module_id()];
+                let (line, label) = describe_toplevel_expect(arena, src, region);
+
+                ToplevelExpect {
+                    name,
+                    symbol,
+                    region,
+                    line,
+                    label,
+                }
             }),
         env.arena,
     );
@@ -823,3 +1155,146 @@ pub fn expect_mono_module_to_dylib<'a>(
 
     llvm_module_to_dylib(env.module, &target, opt_level).map(|lib| (lib, expects, layout_interner))
 }
+
+/// The line of a top-level expect's `expect` keyword, and the comment directly above it (if any).
+/// The region of a top-level expect starts at that comment.
+pub(crate) fn describe_toplevel_expect<'a>(
+    arena: &'a Bump,
+    src: &str,
+    region: Region,
+) -> (u32, Option<&'a str>) {
+    let start = region.start().offset as usize;
+    let mut line = src[..start].matches('\n').count() as u32 + 1;
+    let mut comment = Vec::new();
+
+    // Expects from the code blocks in doc comments (see `roc test --doc`) start after the `## `
+    // on each of their lines, rather than at the start of the line
+    let column = start - src[..start].rfind('\n').map_or(0, |index| index + 1);
+
+    for (index, text) in src[start..].lines().enumerate() {
+        let text = match index {
+            0 => text,
+            _ => text.get(column..).unwrap_or_default(),
+        };
+        let text = text.trim();
+
+        if let Some(text) = text.strip_prefix('#') {
+            // doc comments work too
+            let text = text.trim_start_matches('#').trim();
+
+            if !text.is_empty() {
+                comment.push(text);
+            }
+        } else if text.is_empty() {
+            // a blank line between a comment and the expect means it is not about the expect
+            comment.clear();
+        } else {
+            break;
+        }
+
+        line += 1;
+    }
+
+    let label = match comment.as_slice() {
+        [] => None,
+        lines => Some(&*arena.alloc_str(&lines.join(" "))),
+    };
+
+    (line, label)
+}
+
+/// What a worker sends back after running an expect
+struct WorkerReport {
+    index: usize,
+    passed: bool,
+    duration: Duration,
+    /// The rendered failures, if any
+    output: Vec<u8>,
+}
+
+impl WorkerReport {
+    fn write(&self, pipe: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut bytes = Vec::with_capacity(25 + self.output.len());
+
+        bytes.extend((self.index as u64).to_le_bytes());
+        bytes.push(self.passed as u8);
+        bytes.extend((self.duration.as_nanos() as u64).to_le_bytes());
+        bytes.extend((self.output.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.output);
+
+        pipe.write_all(&bytes)
+    }
+
+    /// Reads reports until the worker closes the pipe. If the worker crashed halfway through
+    /// sending a report, that report is dropped.
+    fn read_all(pipe: &mut impl std::io::Read) -> Vec<Self> {
+        let mut reports = Vec::new();
+
+        while let Some(report) = Self::read(pipe) {
+            reports.push(report);
+        }
+
+        reports
+    }
+
+    fn read(pipe: &mut impl std::io::Read) -> Option<Self> {
+        fn read_bytes<const N: usize>(pipe: &mut impl std::io::Read) -> Option<[u8; N]> {
+            let mut bytes = [0; N];
+            pipe.read_exact(&mut bytes).ok()?;
+            Some(bytes)
+        }
+
+        let index = u64::from_le_bytes(read_bytes(pipe)?) as usize;
+        let [passed] = read_bytes(pipe)?;
+        let duration = Duration::from_nanos(u64::from_le_bytes(read_bytes(pipe)?));
+
+        let mut output = vec![0; u64::from_le_bytes(read_bytes(pipe)?) as usize];
+        pipe.read_exact(&mut output).ok()?;
+
+        Some(Self {
+            index,
+            passed: passed != 0,
+            duration,
+            output,
+        })
+    }
+}
+
+/// A counter in memory that is shared with forked processes
+struct SharedCounter {
+    ptr: *mut AtomicUsize,
+}
+
+impl SharedCounter {
+    fn new() -> Self {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                std::mem::size_of::<AtomicUsize>(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            internal_error!("failed to mmap a shared counter");
+        }
+
+        // anonymous mappings are zeroed, so the counter starts at 0
+        Self { ptr: ptr.cast() }
+    }
+
+    fn get(&self) -> &AtomicUsize {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl Drop for SharedCounter {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.cast(), std::mem::size_of::<AtomicUsize>());
+        }
+    }
+}
@@ -243,6 +243,7 @@ pub async fn entrypoint_from_js(src: String) -> Result<String, String> {
             arena,
             module_id,
             stack_bytes: roc_gen_wasm::Env::DEFAULT_STACK_BYTES,
+            source_positions: Default::default(),
             exposed_to_host: exposed_to_host
                 .top_level_values
                 .keys()
@@ -5,6 +5,8 @@ use roc_module::symbol::{Interns, ModuleId};
 use roc_region::all::LineInfo;
 use roc_solve_problem::TypeError;
 
+use crate::report::RenderTarget;
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Problems {
     pub fatally_errored: bool,
@@ -57,6 +59,7 @@ pub fn report_problems(
     interns: &Interns,
     can_problems: &mut MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: &mut MutMap<ModuleId, Vec<TypeError>>,
+    render: RenderTarget,
 ) -> Problems {
     use crate::report::{can_problem, type_problem, Report, RocDocAllocator, DEFAULT_PALETTE};
     use roc_problem::Severity::*;
@@ -85,7 +88,7 @@ pub fn report_problems(
             let severity = report.severity;
             let mut buf = String::new();
 
-            report.render_color_terminal(&mut buf, &alloc, &palette);
+            report.render(render, &mut buf, &alloc, &palette);
 
             match severity {
                 Warning => {
@@ -108,7 +111,7 @@ pub fn report_problems(
                 let severity = report.severity;
                 let mut buf = String::new();
 
-                report.render_color_terminal(&mut buf, &alloc, &palette);
+                report.render(render, &mut buf, &alloc, &palette);
 
                 match severity {
                     Warning => {
@@ -128,8 +131,16 @@ pub fn report_problems(
 
     let problems_reported;
 
-    // Only print warnings if there are no errors
-    if errors.is_empty() {
+    if let RenderTarget::Json = render {
+        // Tools consuming JSON can decide for themselves which problems to show,
+        // so print all of them, one per line, and nothing else (not even the horizontal rule).
+        for problem in errors.iter().chain(warnings.iter()) {
+            println!("{problem}");
+        }
+
+        problems_reported = 0;
+    } else if errors.is_empty() {
+        // Only print warnings if there are no errors
         problems_reported = warnings.len();
 
         for warning in warnings.iter() {
@@ -20,6 +20,8 @@ const NAMING_PROBLEM: &str = "NAMING PROBLEM";
 const UNRECOGNIZED_NAME: &str = "UNRECOGNIZED NAME";
 const UNUSED_DEF: &str = "UNUSED DEFINITION";
 const UNUSED_IMPORT: &str = "UNUSED IMPORT";
+const UNUSED_EXPOSE: &str = "UNUSED EXPOSE";
+const UNREACHABLE_MODULE: &str = "UNREACHABLE MODULE";
 const UNUSED_ALIAS_PARAM: &str = "UNUSED TYPE ALIAS PARAMETER";
 const UNBOUND_TYPE_VARIABLE: &str = "UNBOUND TYPE VARIABLE";
 const UNUSED_ARG: &str = "UNUSED ARGUMENT";
@@ -121,6 +123,41 @@ pub fn can_problem<'b>(
 
             title = UNUSED_IMPORT.to_string();
         }
+        Problem::UnusedExpose(symbol, region) => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(" is exposed, but no other module uses it:"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("If nothing outside this module needs "),
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(", you can remove it from the "),
+                    alloc.keyword("exposes"),
+                    alloc.reflow(" list."),
+                ]),
+            ]);
+
+            title = UNUSED_EXPOSE.to_string();
+        }
+        Problem::UnreachableModule(module_id, region) => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.reflow("Nothing in "),
+                    alloc.module(module_id),
+                    alloc.reflow(" is used by the app, not even through other modules:"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("Since "),
+                    alloc.module(module_id),
+                    alloc.reflow(" isn't used, you can delete it, along with any imports of it."),
+                ]),
+            ]);
+
+            title = UNREACHABLE_MODULE.to_string();
+        }
         Problem::DefsOnlyUsedInRecursion(1, region) => {
             doc = alloc.stack([
                 alloc.reflow("This definition is only used in recursion with itself:"),
@@ -2,7 +2,7 @@ use roc_module::ident::Ident;
 use roc_module::ident::{Lowercase, ModuleName, TagName, Uppercase};
 use roc_module::symbol::{Interns, ModuleId, ModuleIds, PQModuleName, PackageQualified, Symbol};
 use roc_problem::Severity;
-use roc_region::all::LineColumnRegion;
+use roc_region::all::{LineColumn, LineColumnRegion};
 use std::path::{Path, PathBuf};
 use std::{fmt, io};
 use ven_pretty::{text, BoxAllocator, DocAllocator, DocBuilder, Render, RenderAnnotated};
@@ -109,6 +109,8 @@ pub fn pretty_header_with_path(title: &str, path: &Path) -> String {
 pub enum RenderTarget {
     ColorTerminal,
     Generic,
+    /// One JSON object per report, for editors and other tools to consume.
+    Json,
 }
 
 /// A textual report.
@@ -123,18 +125,19 @@ impl<'b> Report<'b> {
     pub fn render(
         self,
         target: RenderTarget,
-        buf: &'b mut String,
+        buf: &mut String,
         alloc: &'b RocDocAllocator<'b>,
         palette: &'b Palette,
     ) {
         match target {
             RenderTarget::Generic => self.render_ci(buf, alloc),
             RenderTarget::ColorTerminal => self.render_color_terminal(buf, alloc, palette),
+            RenderTarget::Json => self.render_json(buf),
         }
     }
 
     /// Render to CI console output, where no colors are available.
-    pub fn render_ci(self, buf: &'b mut String, alloc: &'b RocDocAllocator<'b>) {
+    pub fn render_ci(self, buf: &mut String, alloc: &'b RocDocAllocator<'b>) {
         let err_msg = "<buffer is not a utf-8 encoded string>";
 
         self.pretty(alloc)
@@ -159,6 +162,91 @@ impl<'b> Report<'b> {
             .expect(err_msg);
     }
 
+    /// Render as a JSON object on a single line, which looks like this (formatted for readability):
+    ///
+    /// ```json
+    /// {
+    ///   "severity": "error",
+    ///   "file": "/code/proj/Main.roc",
+    ///   "region": { "start": { "line": 3, "column": 1 }, "end": { "line": 3, "column": 9 } },
+    ///   "title": "UNRECOGNIZED NAME",
+    ///   "message": "Nothing is named `theAdmin` in this scope.\n\n3│  theAdmin\n    ^^^^^^^^",
+    ///   "hints": [{ "label": "Tip", "message": "..." }]
+    /// }
+    /// ```
+    ///
+    /// The severity is one of "warning", "error", or "fatal". The region is the one highlighted
+    /// by the first code snippet in the report (or `null` if there is none); like the line numbers
+    /// in snippets, lines and columns start at 1, and the end column is exclusive. Paragraphs of
+    /// the report that start with "Tip:", "Note:", or "Hint:" are moved from the message into the
+    /// hints, and the message is rendered like [`Report::render_ci`] (without the header).
+    pub fn render_json(self, buf: &mut String) {
+        let err_msg = "<buffer is not a utf-8 encoded string>";
+
+        buf.push_str(&serde_json::to_string(&self.json_diagnostic()).expect(err_msg));
+    }
+
+    /// The contents of the JSON object that [`Report::render_json`] renders, for tools that use
+    /// reports in the same process.
+    pub fn json_diagnostic(&self) -> JsonDiagnostic<'_> {
+        let err_msg = "<buffer is not a utf-8 encoded string>";
+
+        let mut text = String::new();
+        let mut writer = JsonWrite::new(&mut text);
+
+        self.doc.1.render_raw(70, &mut writer).expect(err_msg);
+
+        let region = writer.region.map(|region| JsonRegion {
+            start: JsonPosition::from(region.start()),
+            end: JsonPosition::from(region.end()),
+        });
+
+        let mut message = Vec::new();
+        let mut hints: Vec<JsonHint> = Vec::new();
+        let mut in_hint = false;
+
+        for paragraph in text.split("\n\n").map(str::trim_end) {
+            if paragraph.trim().is_empty() {
+                continue;
+            }
+
+            let label = JSON_HINT_LABELS.iter().copied().find(|label| {
+                paragraph
+                    .strip_prefix(label)
+                    .map_or(false, |rest| rest.starts_with(':'))
+            });
+
+            if let Some(label) = label {
+                hints.push(JsonHint {
+                    label,
+                    message: paragraph[label.len() + 1..].trim_start().to_string(),
+                });
+                in_hint = true;
+            } else if in_hint && paragraph.starts_with(' ') {
+                // Indented paragraphs right after a hint (like code examples) belong to it.
+                let hint = hints.last_mut().unwrap();
+                hint.message.push_str("\n\n");
+                hint.message.push_str(paragraph);
+            } else {
+                message.push(paragraph);
+                in_hint = false;
+            }
+        }
+
+        JsonDiagnostic {
+            severity: match self.severity {
+                Severity::Warning => "warning",
+                Severity::RuntimeError => "error",
+                Severity::Fatal => "fatal",
+            },
+            file: self.filename.to_string_lossy(),
+            region,
+            title: &self.title,
+            message: message.join("\n\n"),
+            hints,
+        }
+    }
+
     pub fn pretty(self, alloc: &'b RocDocAllocator<'b>) -> RocDocBuilder<'b> {
         if self.title.is_empty() {
             self.doc
@@ -640,7 +728,12 @@ impl<'a> RocDocAllocator<'a> {
             result = result.append(highlight_line);
         }
 
-        result.annotate(Annotation::CodeBlock)
+        result
+            .annotate(Annotation::CodeBlock)
+            .annotate(Annotation::Snippet(LineColumnRegion::span_across(
+                &sub_region1,
+                &sub_region2,
+            )))
     }
 
     pub fn region_with_subregion(
@@ -739,7 +832,7 @@ impl<'a> RocDocAllocator<'a> {
             result = result.append(highlight_line);
         }
 
-        result
+        result.annotate(Annotation::Snippet(sub_region))
     }
 
     pub fn region(&'a self, region: LineColumnRegion) -> DocBuilder<'a, Self, Annotation> {
@@ -847,6 +940,8 @@ pub enum Annotation {
     Tip,
     Header,
     ParserSuggestion,
+    /// A snippet of source code, which highlights the given region.
+    Snippet(LineColumnRegion),
 }
 
 /// Render with minimal formatting
@@ -1054,7 +1149,9 @@ where
             ParserSuggestion => {
                 self.write_str(self.palette.parser_suggestion)?;
             }
-            TypeBlock | InlineTypeBlock | Tag | RecordField | TupleElem => { /* nothing yet */ }
+            TypeBlock | InlineTypeBlock | Tag | RecordField | TupleElem | Snippet(_) => {
+                /* nothing yet */
+            }
         }
         self.style_stack.push(*annotation);
         Ok(())
@@ -1072,16 +1169,108 @@ where
                     self.write_str(self.palette.reset)?;
                 }
 
-                TypeBlock | InlineTypeBlock | Tag | Opaque | RecordField | TupleElem => { /* nothing yet */
-                }
+                TypeBlock | InlineTypeBlock | Tag | Opaque | RecordField | TupleElem
+                | Snippet(_) => { /* nothing yet */ }
             },
         }
         Ok(())
     }
 }
 
+/// The labels of the paragraphs that [`Report::render_json`] reports as hints.
+const JSON_HINT_LABELS: [&str; 3] = ["Tip", "Note", "Hint"];
+
+/// A report as [`Report::render_json`] renders it; see there for what each field means.
+#[derive(serde::Serialize)]
+pub struct JsonDiagnostic<'a> {
+    pub severity: &'static str,
+    pub file: std::borrow::Cow<'a, str>,
+    pub region: Option<JsonRegion>,
+    pub title: &'a str,
+    pub message: String,
+    pub hints: Vec<JsonHint>,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonRegion {
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl From<LineColumn> for JsonPosition {
+    fn from(position: LineColumn) -> Self {
+        Self {
+            line: position.line + 1,
+            column: position.column + 1,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonHint {
+    pub label: &'static str,
+    pub message: String,
+}
+
+/// Render like [`CiWrite`], while keeping track of the region of the first code snippet
+struct JsonWrite<W> {
+    ci: CiWrite<W>,
+    region: Option<LineColumnRegion>,
+}
+
+impl<W> JsonWrite<W> {
+    fn new(upstream: W) -> JsonWrite<W> {
+        JsonWrite {
+            ci: CiWrite::new(upstream),
+            region: None,
+        }
+    }
+}
+
+impl<W> Render for JsonWrite<W>
+where
+    W: fmt::Write,
+{
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<usize, fmt::Error> {
+        self.ci.write_str(s)
+    }
+
+    fn write_str_all(&mut self, s: &str) -> fmt::Result {
+        self.ci.write_str_all(s)
+    }
+}
+
+impl<W> RenderAnnotated<Annotation> for JsonWrite<W>
+where
+    W: fmt::Write,
+{
+    fn push_annotation(&mut self, annotation: &Annotation) -> Result<(), Self::Error> {
+        if let Annotation::Snippet(region) = annotation {
+            self.region.get_or_insert(*region);
+        }
+
+        self.ci.push_annotation(annotation)
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), Self::Error> {
+        self.ci.pop_annotation()
+    }
+}
+
 #[cfg(not(target_family = "wasm"))]
-pub fn to_https_problem_report_string(url: &str, https_problem: Problem) -> String {
+pub fn to_https_problem_report_string(
+    url: &str,
+    https_problem: Problem,
+    render: RenderTarget,
+) -> String {
     let src_lines: Vec<&str> = Vec::new();
 
     let mut module_ids = ModuleIds::default();
@@ -1096,7 +1285,7 @@ pub fn to_https_problem_report_string(url: &str, https_problem: Problem) -> Stri
     let mut buf = String::new();
     let palette = DEFAULT_PALETTE;
     let report = to_https_problem_report(&alloc, url, https_problem);
-    report.render_color_terminal(&mut buf, &alloc, &palette);
+    report.render(render, &mut buf, &alloc, &palette);
 
     buf
 }
@@ -1542,10 +1731,121 @@ pub fn to_https_problem_report<'b>(
                 severity: Severity::Fatal,
             }
         }
+        Problem::NotInMirror(tarball_path) => {
+            use roc_packaging::mirror::MIRROR_ENV_VAR;
+
+            let doc = alloc.stack([
+                alloc.reflow(r"I was trying to install this URL:"),
+                alloc
+                    .string((&url).to_string())
+                    .annotate(Annotation::Url)
+                    .indent(4),
+                alloc.concat([
+                    alloc.reflow(r"Since the "),
+                    alloc.keyword(MIRROR_ENV_VAR),
+                    alloc.reflow(r" environment variable is set, I looked for it in that "),
+                    alloc.reflow(r"package mirror instead of downloading it. However, "),
+                    alloc.reflow(r"there was nothing at this path:"),
+                ]),
+                alloc
+                    .string(tarball_path.display().to_string())
+                    .annotate(Annotation::PlainText)
+                    .indent(4),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc.reflow(r"Run "),
+                    alloc.keyword(r"roc vendor"),
+                    alloc.reflow(r" on a machine with network access to add the "),
+                    alloc.reflow(r"packages your app needs to the mirror."),
+                ]),
+            ]);
+
+            Report {
+                filename: "UNKNOWN.roc".into(),
+                doc,
+                title: "PACKAGE NOT IN MIRROR".to_string(),
+                severity: Severity::Fatal,
+            }
+        }
+        Problem::InvalidSignature(signature_problem) => {
+            use roc_packaging::signature::{SignatureProblem, TRUSTED_KEYS_ENV_VAR};
+
+            let (title, explanation) = match signature_problem {
+                SignatureProblem::Missing => (
+                    "MISSING SIGNATURE",
+                    alloc.concat([
+                        alloc.reflow(r"However, I could not find a signature for it. "),
+                        alloc.reflow(r"I looked for one at the same URL with "),
+                        alloc.keyword(r".sig"),
+                        alloc.reflow(r" added to the end."),
+                    ]),
+                ),
+                SignatureProblem::Malformed(contents) => (
+                    "INVALID SIGNATURE",
+                    alloc.stack([
+                        alloc.reflow(r"However, its signature file is not in a format I understand:"),
+                        alloc
+                            .string(contents)
+                            .annotate(Annotation::PlainText)
+                            .indent(4),
+                    ]),
+                ),
+                SignatureProblem::UntrustedKey(public_key) => (
+                    "UNTRUSTED SIGNATURE",
+                    alloc.stack([
+                        alloc.reflow(r"However, it was signed by a key which is not one of your trusted keys:"),
+                        alloc
+                            .string(public_key)
+                            .annotate(Annotation::Emphasized)
+                            .indent(4),
+                    ]),
+                ),
+                SignatureProblem::Mismatch => (
+                    "INVALID SIGNATURE",
+                    alloc.concat([
+                        alloc.reflow(r"However, its signature does not match its contents. "),
+                        alloc.reflow(r"This could happen if the file has been tampered with."),
+                    ]),
+                ),
+            };
+
+            let doc = alloc.stack([
+                alloc.reflow(r"I was able to download this URL:"),
+                alloc
+                    .string((&url).to_string())
+                    .annotate(Annotation::Url)
+                    .indent(4),
+                alloc.concat([
+                    alloc.reflow(r"Since the "),
+                    alloc.keyword(TRUSTED_KEYS_ENV_VAR),
+                    alloc.reflow(r" environment variable is set, I only use packages "),
+                    alloc.reflow(r"signed by one of the keys it lists."),
+                ]),
+                explanation,
+                alloc.reflow(r"To keep you secure, I will not execute this untrusted code."),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc
+                        .reflow(r"Check that you have the correct URL for this package/platform, "),
+                    alloc.reflow(r"and ask its author which key they sign their releases with."),
+                ]),
+            ]);
+
+            Report {
+                filename: "UNKNOWN.roc".into(),
+                doc,
+                title: title.to_string(),
+                severity: Severity::Fatal,
+            }
+        }
     }
 }
 
-pub fn to_file_problem_report_string(filename: &Path, error: io::ErrorKind) -> String {
+pub fn to_file_problem_report_string(
+    filename: &Path,
+    error: io::ErrorKind,
+    render: RenderTarget,
+) -> String {
     let src_lines: Vec<&str> = Vec::new();
 
     let mut module_ids = ModuleIds::default();
@@ -1560,7 +1860,7 @@ pub fn to_file_problem_report_string(filename: &Path, error: io::ErrorKind) -> S
     let mut buf = String::new();
     let palette = DEFAULT_PALETTE;
     let report = to_file_problem_report(&alloc, filename, error);
-    report.render_color_terminal(&mut buf, &alloc, &palette);
+    report.render(render, &mut buf, &alloc, &palette);
 
     buf
 }
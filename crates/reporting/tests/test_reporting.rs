@@ -822,6 +822,33 @@ mod test_reporting {
         );
     }
 
+    #[test]
+    fn report_region_as_json() {
+        let src: &str = "[1, 2.2, 0x3]";
+
+        let arena = Bump::new();
+        let (type_problems, _can_problems, home, interns) =
+            infer_expr_help(&arena, src).expect("parse error");
+
+        let src_lines: Vec<&str> = src.split('\n').collect();
+        let lines = LineInfo::new(src);
+        let filename = filename_from_string(r"/code/proj/Main.roc");
+        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+        let mut buf = String::new();
+
+        for problem in type_problems {
+            if let Some(report) = type_problem(&alloc, &lines, filename.clone(), problem) {
+                report.render(RenderTarget::Json, &mut buf, &alloc, &DEFAULT_PALETTE);
+                buf.push('\n');
+            }
+        }
+
+        insta::assert_snapshot!(buf, @r###"
+        {"severity":"error","file":"/code/proj/Main.roc","region":{"start":{"line":1,"column":10},"end":{"line":1,"column":13}},"title":"TYPE MISMATCH","message":"This list contains elements with different types:\n\n1│  [1, 2.2, 0x3]\n             ^^^\n\nIts 3rd element is an integer of type:\n\n    Int *\n\nHowever, the preceding elements in the list all have the type:\n\n    Frac *\n\nEvery element in a list must have the same type!","hints":[{"label":"Tip","message":"You can convert between Int and Frac using functions like\n`Num.toFrac` and `Num.round`."}]}
+        "###);
+    }
+
     test_report!(
         if_condition_not_bool,
         indoc!(
@@ -112,6 +112,7 @@ fn valgrind_test_linux(source: &str) {
             problems,
             total_time: _,
             expect_metadata: _,
+            source_paths: _,
         }) => {
             if problems.exit_code() != 0 {
                 panic!("there are problems")
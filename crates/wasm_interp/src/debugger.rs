@@ -0,0 +1,369 @@
+use std::fmt::Debug;
+use std::io::{BufRead, Write};
+
+use roc_wasm_module::opcodes::OpCode;
+use roc_wasm_module::parse::Parse;
+use roc_wasm_module::{ValueType, WasmModule};
+
+use crate::frame::Frame;
+use crate::{ImportDispatcher, Instance};
+
+const HELP: &str = "\
+Commands:
+  s, step [N]           Run N instructions (default 1)
+  c, continue           Run until the next breakpoint
+  b, break FUNCTION     Pause when a function is called, by name or index
+  b, break 0xOFFSET     Pause at the instruction at a file offset, as shown by --debug
+  d, delete [N]         Delete breakpoint N, or all breakpoints
+  breakpoints           List breakpoints
+  stack                 Print the value stack of the current function
+  l, locals             Print the arguments and local variables of the current function
+  globals               Print the global variables
+  x, memory ADDR [LEN]  Print LEN bytes of memory (default 64) from ADDR
+  bt, backtrace         Print the call stack
+  h, help               Print this message
+  q, quit               Stop the program
+An empty line repeats the previous command.";
+
+/// What the [Instance] should do after the debugger returns control to it
+pub(crate) enum Control {
+    Continue,
+    Quit,
+}
+
+struct Breakpoint {
+    /// What the user asked to break on
+    label: String,
+    /// Offset in the code section of the instruction to pause at
+    addr: usize,
+}
+
+/// Interactive debugger, for stepping through a program one instruction at a time and inspecting
+/// its state. It starts out paused at the first instruction.
+pub struct Debugger<'a> {
+    input: Box<dyn BufRead + 'a>,
+    output: Box<dyn Write + 'a>,
+    breakpoints: Vec<Breakpoint>,
+    /// Number of instructions to run before pausing, or [None] to run until a breakpoint
+    steps_remaining: Option<u32>,
+    previous_command: String,
+}
+
+impl Debug for Debugger<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let breakpoints: Vec<_> = self.breakpoints.iter().map(|b| &b.label).collect();
+        f.debug_struct("Debugger")
+            .field("breakpoints", &breakpoints)
+            .field("steps_remaining", &self.steps_remaining)
+            .finish()
+    }
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(input: impl BufRead + 'a, output: impl Write + 'a) -> Self {
+        Debugger {
+            input: Box::new(input),
+            output: Box::new(output),
+            breakpoints: Vec::new(),
+            steps_remaining: Some(0),
+            previous_command: String::new(),
+        }
+    }
+
+    /// Called before every instruction, to decide whether to pause
+    pub(crate) fn before_instruction<I: ImportDispatcher>(
+        &mut self,
+        instance: &Instance<'_, I>,
+    ) -> Control {
+        let breakpoint = self
+            .breakpoints
+            .iter()
+            .position(|b| b.addr == instance.program_counter);
+
+        let done_stepping = match self.steps_remaining {
+            Some(0) => true,
+            Some(n) => {
+                self.steps_remaining = Some(n - 1);
+                false
+            }
+            None => false,
+        };
+
+        if let Some(index) = breakpoint {
+            let label = &self.breakpoints[index].label;
+            self.print(format!("Breakpoint {index}: {label}"));
+        } else if !done_stepping {
+            return Control::Continue;
+        }
+
+        self.print_location(instance);
+        self.prompt(instance)
+    }
+
+    /// Called when the program traps, to let the user inspect its state before it stops
+    pub(crate) fn on_error<I: ImportDispatcher>(
+        &mut self,
+        instance: &Instance<'_, I>,
+        message: &str,
+    ) {
+        self.print(message.lines().next().unwrap_or_default());
+        self.print("The program can't continue, but you can still inspect it.");
+
+        // Any command that would resume execution stops it instead
+        self.prompt(instance);
+    }
+
+    fn prompt<I: ImportDispatcher>(&mut self, instance: &Instance<'_, I>) -> Control {
+        loop {
+            write!(self.output, "(wasm) ").unwrap();
+            self.output.flush().unwrap();
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line).unwrap() == 0 {
+                // End of input, e.g. Ctrl+D
+                self.print("");
+                return Control::Quit;
+            }
+
+            let line = match line.trim() {
+                "" => self.previous_command.clone(),
+                command => command.to_string(),
+            };
+            self.previous_command = line.clone();
+
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or_default();
+            let args: Vec<&str> = words.collect();
+
+            match (command, args.as_slice()) {
+                ("s" | "step", [] | [_]) => {
+                    let count = match args.first().map(|n| n.parse::<u32>()) {
+                        None => 1,
+                        Some(Ok(n)) if n > 0 => n,
+                        Some(_) => {
+                            self.print("The number of steps must be a positive number");
+                            continue;
+                        }
+                    };
+                    self.steps_remaining = Some(count - 1);
+                    return Control::Continue;
+                }
+                ("c" | "continue", []) => {
+                    self.steps_remaining = None;
+                    return Control::Continue;
+                }
+                ("b" | "break", [target]) => self.add_breakpoint(instance, target),
+                ("d" | "delete", []) => {
+                    self.breakpoints.clear();
+                    self.print("Deleted all breakpoints");
+                }
+                ("d" | "delete", [index]) => match index.parse::<usize>() {
+                    Ok(index) if index < self.breakpoints.len() => {
+                        let breakpoint = self.breakpoints.remove(index);
+                        self.print(format!("Deleted breakpoint {index}: {}", breakpoint.label));
+                    }
+                    _ => self.print(format!("There is no breakpoint {index}")),
+                },
+                ("breakpoints", []) => {
+                    if self.breakpoints.is_empty() {
+                        self.print("No breakpoints");
+                    }
+                    let lines: Vec<String> = (self.breakpoints.iter().enumerate())
+                        .map(|(index, b)| format!("{index}: {}", b.label))
+                        .collect();
+                    for line in lines {
+                        self.print(line);
+                    }
+                }
+                ("stack", []) => {
+                    let frame = &instance.current_frame;
+                    let start = frame.locals_start + frame.locals_count;
+                    let depth = instance.value_store.depth();
+                    let values = (start..depth).map(|i| instance.value_store.get(i).unwrap());
+                    self.print(format!("{:x?}", values.collect::<Vec<_>>()));
+                }
+                ("l" | "locals", []) => self.print_locals(instance, &instance.current_frame),
+                ("globals", []) => {
+                    if instance.globals.is_empty() {
+                        self.print("No globals");
+                    }
+                    for (index, value) in instance.globals.iter().enumerate() {
+                        self.print(format!("{index}: {value:x?}"));
+                    }
+                }
+                ("x" | "memory", [addr] | [addr, _]) => {
+                    let len = args.get(1).map_or(Some(64), |len| parse_number(len));
+                    match (parse_number(addr), len) {
+                        (Some(addr), Some(len)) => self.print_memory(&instance.memory, addr, len),
+                        _ => {
+                            self.print("Addresses and lengths must be numbers, like 1024 or 0x400")
+                        }
+                    }
+                }
+                ("bt" | "backtrace", []) => self.print_backtrace(instance),
+                ("h" | "help", _) => self.print(HELP),
+                ("q" | "quit", []) => return Control::Quit,
+                _ => self.print(format!(
+                    "I don't understand `{line}`. Type `help` to see what I do understand."
+                )),
+            }
+        }
+    }
+
+    fn add_breakpoint<I: ImportDispatcher>(&mut self, instance: &Instance<'_, I>, target: &str) {
+        let module = instance.module;
+        let section_offset = module.code.section_offset as usize;
+
+        let addr = if let Some(hex) = target.strip_prefix("0x") {
+            match usize::from_str_radix(hex, 16) {
+                Ok(file_offset) if file_offset >= section_offset => file_offset - section_offset,
+                _ => {
+                    self.print(format!("{target} is not in the code section"));
+                    return;
+                }
+            }
+        } else {
+            let fn_index = target.parse::<usize>().ok().or_else(|| {
+                let mut names = module.names.function_names.iter();
+                names
+                    .find(|(_, name)| *name == target)
+                    .map(|(index, _)| *index as usize)
+            });
+
+            match fn_index {
+                Some(fn_index) if fn_index < instance.import_count => {
+                    self.print(format!(
+                        "func[{fn_index}] is an import, so it has no code to pause in"
+                    ));
+                    return;
+                }
+                Some(fn_index)
+                    if fn_index - instance.import_count < module.code.function_offsets.len() =>
+                {
+                    body_start(module, fn_index - instance.import_count)
+                }
+                _ => {
+                    self.print(format!("I couldn't find a function called {target}"));
+                    return;
+                }
+            }
+        };
+
+        self.print(format!(
+            "Breakpoint {}: {target} at {:06x}",
+            self.breakpoints.len(),
+            addr + section_offset
+        ));
+        self.breakpoints.push(Breakpoint {
+            label: target.to_string(),
+            addr,
+        });
+    }
+
+    fn print_location<I: ImportDispatcher>(&mut self, instance: &Instance<'_, I>) {
+        let module = instance.module;
+        let pc = instance.program_counter;
+        let fn_index = instance.current_frame.fn_index;
+
+        self.print(format!(
+            "{:06x} {:?} in func[{fn_index}] {}",
+            pc + module.code.section_offset as usize,
+            OpCode::from(module.code.bytes[pc]),
+            instance.function_name(fn_index)
+        ));
+    }
+
+    fn print_locals<I: ImportDispatcher>(&mut self, instance: &Instance<'_, I>, frame: &Frame) {
+        let arg_count = instance.function_arg_count(frame.fn_index);
+
+        let mut args = Vec::new();
+        let mut locals = Vec::new();
+        for index in 0..frame.locals_count {
+            let value = frame.get_local(&instance.value_store, index as u32);
+            let entry = format!("{index}: {value:x?}");
+            if index < arg_count {
+                args.push(entry);
+            } else {
+                locals.push(entry);
+            }
+        }
+
+        self.print(format!("args     {}", args.join(", ")).trim_end());
+        self.print(format!("locals   {}", locals.join(", ")).trim_end());
+    }
+
+    fn print_memory(&mut self, memory: &[u8], addr: usize, len: usize) {
+        let end = addr.saturating_add(len).min(memory.len());
+        if addr >= end {
+            self.print(format!(
+                "Memory is only {:#x} bytes, so there's nothing to show at {addr:#x}",
+                memory.len()
+            ));
+            return;
+        }
+
+        for line_start in (addr..end).step_by(16) {
+            let bytes = &memory[line_start..end.min(line_start + 16)];
+            let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = bytes
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+                .collect();
+            self.print(format!("{line_start:08x}  {:47}  {ascii}", hex.join(" ")));
+        }
+    }
+
+    /// The innermost call is first, like in gdb
+    fn print_backtrace<I: ImportDispatcher>(&mut self, instance: &Instance<'_, I>) {
+        let section_offset = instance.module.code.section_offset as usize;
+        let frames: Vec<&Frame> = instance
+            .previous_frames
+            .iter()
+            .chain(std::iter::once(&instance.current_frame))
+            .collect();
+
+        for (depth, frame) in frames.iter().rev().enumerate() {
+            // The current function is at the program counter, and each of the others is at the
+            // call to the function after it.
+            let addr = match depth {
+                0 => instance.program_counter,
+                _ => {
+                    let callee = frames[frames.len() - depth];
+                    instance.debug_return_addr_to_call_addr(callee.return_addr)
+                }
+            };
+
+            self.print(format!(
+                "#{depth} {:06x} func[{}] {}",
+                addr + section_offset,
+                frame.fn_index,
+                instance.function_name(frame.fn_index)
+            ));
+        }
+    }
+
+    fn print(&mut self, message: impl AsRef<str>) {
+        writeln!(self.output, "{}", message.as_ref()).unwrap();
+    }
+}
+
+/// Offset in the code section of the first instruction of a function, after its locals
+fn body_start(module: &WasmModule<'_>, internal_fn_index: usize) -> usize {
+    let bytes = &module.code.bytes;
+    let mut cursor = module.code.function_offsets[internal_fn_index] as usize;
+
+    let _function_byte_length = u32::parse((), bytes, &mut cursor).unwrap();
+    let local_group_count = u32::parse((), bytes, &mut cursor).unwrap();
+    for _ in 0..local_group_count {
+        <(u32, ValueType)>::parse((), bytes, &mut cursor).unwrap();
+    }
+
+    cursor
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
@@ -8,6 +8,7 @@ use roc_wasm_module::sections::{ImportDesc, MemorySection, SignatureParamsIter};
 use roc_wasm_module::{ExportType, WasmModule};
 use roc_wasm_module::{Value, ValueType};
 
+use crate::debugger::{Control, Debugger};
 use crate::frame::Frame;
 use crate::value_store::ValueStore;
 use crate::{Error, ImportDispatcher};
@@ -47,7 +48,7 @@ pub struct Instance<'a, I: ImportDispatcher> {
     /// The current call frame
     pub(crate) current_frame: Frame,
     /// Previous call frames
-    previous_frames: Vec<'a, Frame>,
+    pub(crate) previous_frames: Vec<'a, Frame>,
     /// The WebAssembly stack machine's stack of values
     pub(crate) value_store: ValueStore<'a>,
     /// Values of any global variables
@@ -59,13 +60,15 @@ pub struct Instance<'a, I: ImportDispatcher> {
     /// Cache for branching instructions, split into buckets for each function.
     branch_cache: Vec<'a, Vec<'a, BranchCacheEntry>>,
     /// Number of imports in the module
-    import_count: usize,
+    pub(crate) import_count: usize,
     /// Import dispatcher from user code
     pub import_dispatcher: I,
     /// Temporary storage for import arguments
     import_arguments: Vec<'a, Value>,
     /// temporary storage for output using the --debug option
     debug_string: Option<String>,
+    /// Interactive debugger, if one is attached
+    debugger: Option<Debugger<'a>>,
 }
 
 impl<'a, I: ImportDispatcher> Instance<'a, I> {
@@ -95,6 +98,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string: Some(String::new()),
+            debugger: None,
         }
     }
 
@@ -163,9 +167,15 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string,
+            debugger: None,
         })
     }
 
+    /// Pause before the first instruction, and let the user step through the program
+    pub fn attach_debugger(&mut self, debugger: Debugger<'a>) {
+        self.debugger = Some(debugger);
+    }
+
     pub fn call_export<A>(&mut self, fn_name: &str, arg_values: A) -> Result<Option<Value>, String>
     where
         A: IntoIterator<Item = Value>,
@@ -318,6 +328,15 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         });
 
         loop {
+            // Take the debugger out of the instance while it runs, so it can look at everything else
+            if let Some(mut debugger) = self.debugger.take() {
+                let control = debugger.before_instruction(self);
+                self.debugger = Some(debugger);
+                if let Control::Quit = control {
+                    return Err("The program was stopped in the debugger.".to_string());
+                }
+            }
+
             match self.execute_next_instruction(module) {
                 Ok(Action::Continue) => {}
                 Ok(Action::Break) => {
@@ -326,6 +345,10 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 Err(e) => {
                     let file_offset = self.program_counter + module.code.section_offset as usize;
                     let mut message = e.to_string_at(file_offset);
+                    if let Some(mut debugger) = self.debugger.take() {
+                        debugger.on_error(self, &message);
+                        self.debugger = Some(debugger);
+                    }
                     self.debug_stack_trace(&mut message).unwrap();
                     return Err(message);
                 }
@@ -1841,26 +1864,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 ..
             } = frame;
 
-            let arg_count = {
-                let signature_index = if *fn_index < self.import_count {
-                    match self.module.import.imports[*fn_index].description {
-                        ImportDesc::Func { signature_index } => signature_index,
-                        _ => unreachable!(),
-                    }
-                } else {
-                    self.module.function.signatures[fn_index - self.import_count]
-                };
-                self.module.types.look_up(signature_index).0.len()
-            };
-
-            let fn_name = self
-                .module
-                .names
-                .function_names
-                .iter()
-                .find(|(idx, _)| *idx == *fn_index as u32)
-                .map(|(_, name)| *name)
-                .unwrap_or("");
+            let arg_count = self.function_arg_count(*fn_index);
+            let fn_name = self.function_name(*fn_index);
 
             // Function and address match wasm-objdump formatting, for easy copy & find
             writeln!(buffer, "func[{fn_index}]  {fn_name}")?;
@@ -1896,8 +1901,31 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         Ok(())
     }
 
+    pub(crate) fn function_arg_count(&self, fn_index: usize) -> usize {
+        let signature_index = if fn_index < self.import_count {
+            match self.module.import.imports[fn_index].description {
+                ImportDesc::Func { signature_index } => signature_index,
+                _ => unreachable!(),
+            }
+        } else {
+            self.module.function.signatures[fn_index - self.import_count]
+        };
+        self.module.types.look_up(signature_index).0.len()
+    }
+
+    /// Function name from the "name" section, or an empty string if it doesn't have one
+    pub(crate) fn function_name(&self, fn_index: usize) -> &'a str {
+        self.module
+            .names
+            .function_names
+            .iter()
+            .find(|(idx, _)| *idx == fn_index as u32)
+            .map(|(_, name)| *name)
+            .unwrap_or("")
+    }
+
     // Call address is more intuitive than the return address in the stack trace. Search backward for it.
-    fn debug_return_addr_to_call_addr(&self, return_addr: usize) -> usize {
+    pub(crate) fn debug_return_addr_to_call_addr(&self, return_addr: usize) -> usize {
         // return_addr is pointing at the next instruction after the CALL/CALLINDIRECT.
         // Just before that is the LEB-128 function index or type index.
         // The last LEB-128 byte is <128, but the others are >=128 so we can't mistake them for CALL/CALLINDIRECT
@@ -6,12 +6,14 @@ use std::io;
 use std::iter::once;
 use std::process;
 
-use roc_wasm_interp::{DefaultImportDispatcher, Instance};
+use roc_wasm_interp::{Debugger, DefaultImportDispatcher, Instance};
 use roc_wasm_module::WasmModule;
 
 pub const FLAG_FUNCTION: &str = "function";
 pub const FLAG_DEBUG: &str = "debug";
+pub const FLAG_DEBUGGER: &str = "debugger";
 pub const FLAG_HEX: &str = "hex";
+pub const FLAG_DIR: &str = "dir";
 pub const WASM_FILE: &str = "WASM_FILE";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
 
@@ -32,12 +34,24 @@ fn main() -> io::Result<()> {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_debugger = Arg::new(FLAG_DEBUGGER)
+        .long(FLAG_DEBUGGER)
+        .help("Pause at the first instruction, and step through the program interactively.\nType `help` at the prompt to see the commands.")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_hex = Arg::new(FLAG_HEX)
         .long(FLAG_HEX)
         .help("If the called function returns a value, print it in hexadecimal format.")
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_dir = Arg::new(FLAG_DIR)
+        .long(FLAG_DIR)
+        .help("Give the app access to a directory on the host.\nUse GUEST::HOST to give it a different name inside the app, e.g. `--dir data::tests/data`")
+        .action(ArgAction::Append)
+        .required(false);
+
     let wasm_file_to_run = Arg::new(WASM_FILE)
         .help("The .wasm file to run")
         .required(true);
@@ -50,7 +64,9 @@ fn main() -> io::Result<()> {
         .about("Run the given .wasm file")
         .arg(flag_function)
         .arg(flag_debug)
+        .arg(flag_debugger)
         .arg(flag_hex)
+        .arg(flag_dir)
         .arg(wasm_file_to_run)
         .arg(args_for_app);
 
@@ -59,6 +75,7 @@ fn main() -> io::Result<()> {
     let matches = app.get_matches();
     let start_fn_name = matches.get_one::<String>(FLAG_FUNCTION).unwrap();
     let is_debug_mode = matches.get_flag(FLAG_DEBUG);
+    let is_debugger_mode = matches.get_flag(FLAG_DEBUGGER);
     let is_hex_format = matches.get_flag(FLAG_HEX);
     let start_arg_strings = matches.get_many::<String>(ARGS_FOR_APP).unwrap_or_default();
     let wasm_path = matches.get_one::<String>(WASM_FILE).unwrap();
@@ -87,13 +104,22 @@ fn main() -> io::Result<()> {
 
     // Create an execution instance
 
-    let dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    let mut dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    for dir in matches.get_many::<String>(FLAG_DIR).unwrap_or_default() {
+        let (guest_path, host_path) = dir.split_once("::").unwrap_or((dir, dir));
+        dispatcher.wasi.preopen_dir(guest_path, host_path);
+    }
     let mut inst =
         Instance::for_module(&arena, &module, dispatcher, is_debug_mode).unwrap_or_else(|e| {
             eprintln!("{e}");
             process::exit(2);
         });
 
+    if is_debugger_mode {
+        // The program's own output goes to stdout, so keep the debugger's out of its way
+        inst.attach_debugger(Debugger::new(io::stdin().lock(), io::stderr()));
+    }
+
     // Run
 
     let result = inst.call_export_from_cli(&module, start_fn_name, &wasi_argv);
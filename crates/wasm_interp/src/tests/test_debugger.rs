@@ -0,0 +1,131 @@
+use crate::{Debugger, DefaultImportDispatcher, Instance};
+use bumpalo::Bump;
+use roc_wasm_module::{
+    opcodes::OpCode, sections::MemorySection, Serialize, Signature, Value, ValueType, WasmModule,
+};
+
+use super::create_exported_function_no_locals;
+
+/// Run `two_plus_two` in the debugger, with a script of commands, and return what it printed
+fn run_debugger(script: &str) -> (Result<Option<Value>, String>, String) {
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    // Function 0: calculate 2+2
+    let signature0 = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "two_plus_two", signature0, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::END as u8);
+    });
+
+    // Function 1: add two numbers
+    let func1_offset = module.code.bytes.len() as u32;
+    module.code.function_offsets.push(func1_offset);
+    module.code.function_count += 1;
+    module.add_function_signature(Signature {
+        param_types: bumpalo::vec![in &arena; ValueType::I32, ValueType::I32],
+        ret_type: Some(ValueType::I32),
+    });
+    [
+        0, // no locals
+        OpCode::GETLOCAL as u8,
+        0,
+        OpCode::GETLOCAL as u8,
+        1,
+        OpCode::I32ADD as u8,
+        OpCode::END as u8,
+    ]
+    .serialize(&mut module.code.bytes);
+
+    module.names.function_names.push((0, "two_plus_two"));
+    module.names.function_names.push((1, "add"));
+
+    let mut output = Vec::new();
+    let result = {
+        let mut inst =
+            Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false)
+                .unwrap();
+        inst.memory[..4].copy_from_slice(b"Roc!");
+        inst.attach_debugger(Debugger::new(script.as_bytes(), &mut output));
+        inst.call_export("two_plus_two", [])
+    };
+
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_breakpoint_by_name() {
+    let (result, output) = run_debugger("break add\ncontinue\nlocals\nbt\ncontinue\n");
+
+    assert_eq!(result, Ok(Some(Value::I32(4))));
+    assert_eq!(
+        output,
+        "\
+000006 I32CONST in func[0] two_plus_two
+(wasm) Breakpoint 0: add at 00000f
+(wasm) Breakpoint 0: add
+00000f GETLOCAL in func[1] add
+(wasm) args     0: I32(2), 1: I32(2)
+locals
+(wasm) #0 00000f func[1] add
+#1 00000a func[0] two_plus_two
+(wasm) "
+    );
+}
+
+#[test]
+fn test_step_and_stack() {
+    let (result, output) = run_debugger("step 2\nstack\nstep\n\nstack\nc\n");
+
+    assert_eq!(result, Ok(Some(Value::I32(4))));
+    assert_eq!(
+        output,
+        "\
+000006 I32CONST in func[0] two_plus_two
+(wasm) 00000a CALL in func[0] two_plus_two
+(wasm) [I32(2), I32(2)]
+(wasm) 00000f GETLOCAL in func[1] add
+(wasm) 000011 GETLOCAL in func[1] add
+(wasm) [I32(2)]
+(wasm) "
+    );
+}
+
+#[test]
+fn test_memory() {
+    let (result, output) = run_debugger("x 0 4\nquit\n");
+
+    assert!(result.is_err());
+    assert_eq!(
+        output,
+        "\
+000006 I32CONST in func[0] two_plus_two
+(wasm) 00000000  52 6f 63 21                                      Roc!
+(wasm) "
+    );
+}
+
+#[test]
+fn test_bad_commands() {
+    let (result, output) = run_debugger("break nope\nbreak 7\nfly\nq\n");
+
+    assert!(result.is_err());
+    assert_eq!(
+        output,
+        "\
+000006 I32CONST in func[0] two_plus_two
+(wasm) I couldn't find a function called nope
+(wasm) I couldn't find a function called 7
+(wasm) I don't understand `fly`. Type `help` to see what I do understand.
+(wasm) "
+    );
+}
@@ -0,0 +1,407 @@
+use crate::wasi::{Errno, WasiDispatcher};
+use roc_wasm_module::Value;
+use std::fs;
+
+const PTR_OUT: i32 = 0x10;
+const PTR_PATH: i32 = 0x100;
+const PTR_IOVS: i32 = 0x200;
+const PTR_DATA: i32 = 0x300;
+
+const RIGHTS_READ_WRITE: i64 = (1 << 1) | (1 << 6);
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_DIRECTORY: i32 = 2;
+
+/// The file descriptor of the only preopened directory
+const DIR_FD: i32 = 3;
+
+struct Setup {
+    wasi: WasiDispatcher<'static>,
+    memory: Vec<u8>,
+    dir: tempfile::TempDir,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wasi = WasiDispatcher::default();
+        wasi.preopen_dir("sandbox", dir.path());
+
+        Setup {
+            wasi,
+            memory: vec![0; 0x1000],
+            dir,
+        }
+    }
+
+    /// Returns the errno
+    fn call(&mut self, function_name: &str, arguments: &[Value]) -> i32 {
+        let result = self
+            .wasi
+            .dispatch(function_name, arguments, &mut self.memory);
+        result.unwrap().expect_i32().unwrap()
+    }
+
+    fn out_u32(&self) -> u32 {
+        u32::from_le_bytes(self.memory[PTR_OUT as usize..][..4].try_into().unwrap())
+    }
+
+    fn out_u64(&self) -> u64 {
+        u64::from_le_bytes(self.memory[PTR_OUT as usize..][..8].try_into().unwrap())
+    }
+
+    /// Put a path in memory, and return the arguments that refer to it
+    fn path(&mut self, path: &str) -> [Value; 2] {
+        self.memory[PTR_PATH as usize..][..path.len()].copy_from_slice(path.as_bytes());
+        [Value::I32(PTR_PATH), Value::I32(path.len() as i32)]
+    }
+
+    /// Put a single iovec in memory, pointing at `len` bytes of data
+    fn iovec(&mut self, data: &[u8], len: usize) {
+        self.memory[PTR_DATA as usize..][..data.len()].copy_from_slice(data);
+        self.memory[PTR_IOVS as usize..][..4].copy_from_slice(&(PTR_DATA as u32).to_le_bytes());
+        self.memory[PTR_IOVS as usize + 4..][..4].copy_from_slice(&(len as u32).to_le_bytes());
+    }
+
+    fn open(&mut self, path: &str, oflags: i32) -> Result<i32, i32> {
+        let [ptr_path, path_len] = self.path(path);
+        let args = [
+            Value::I32(DIR_FD),
+            Value::I32(0),
+            ptr_path,
+            path_len,
+            Value::I32(oflags),
+            Value::I64(RIGHTS_READ_WRITE),
+            Value::I64(0),
+            Value::I32(0),
+            Value::I32(PTR_OUT),
+        ];
+        match self.call("path_open", &args) {
+            0 => Ok(self.out_u32() as i32),
+            errno => Err(errno),
+        }
+    }
+}
+
+#[test]
+fn test_prestat() {
+    let mut setup = Setup::new();
+
+    assert_eq!(
+        setup.call("fd_prestat_get", &[Value::I32(DIR_FD), Value::I32(PTR_OUT)]),
+        Errno::Success as i32
+    );
+    let name_len = u32::from_le_bytes(
+        setup.memory[PTR_OUT as usize + 4..][..4]
+            .try_into()
+            .unwrap(),
+    );
+    assert_eq!(name_len, 7);
+
+    let args = [Value::I32(DIR_FD), Value::I32(PTR_PATH), Value::I32(7)];
+    assert_eq!(
+        setup.call("fd_prestat_dir_name", &args),
+        Errno::Success as i32
+    );
+    assert_eq!(&setup.memory[PTR_PATH as usize..][..7], b"sandbox");
+
+    // WASI libc stops looking for preopens at the first file descriptor that isn't one
+    assert_eq!(
+        setup.call(
+            "fd_prestat_get",
+            &[Value::I32(DIR_FD + 1), Value::I32(PTR_OUT)]
+        ),
+        Errno::Badf as i32
+    );
+}
+
+#[test]
+fn test_write_seek_read() {
+    let mut setup = Setup::new();
+    let fd = setup.open("hello.txt", OFLAGS_CREAT).unwrap();
+
+    setup.iovec(b"Hello, World!", 13);
+    let args = [
+        Value::I32(fd),
+        Value::I32(PTR_IOVS),
+        Value::I32(1),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_write", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 13);
+
+    let args = [
+        Value::I32(fd),
+        Value::I64(7),
+        Value::I32(0),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_seek", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u64(), 7);
+
+    setup.iovec(&[0; 13], 13);
+    let args = [
+        Value::I32(fd),
+        Value::I32(PTR_IOVS),
+        Value::I32(1),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_read", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 6);
+    assert_eq!(&setup.memory[PTR_DATA as usize..][..6], b"World!");
+
+    assert_eq!(
+        setup.call("fd_close", &[Value::I32(fd)]),
+        Errno::Success as i32
+    );
+    assert_eq!(
+        setup.call("fd_close", &[Value::I32(fd)]),
+        Errno::Badf as i32
+    );
+
+    let on_host = fs::read_to_string(setup.dir.path().join("hello.txt")).unwrap();
+    assert_eq!(on_host, "Hello, World!");
+}
+
+#[test]
+fn test_reuses_closed_fds() {
+    let mut setup = Setup::new();
+    let first = setup.open("a", OFLAGS_CREAT).unwrap();
+    assert_eq!(
+        setup.call("fd_close", &[Value::I32(first)]),
+        Errno::Success as i32
+    );
+
+    assert_eq!(setup.open("b", OFLAGS_CREAT), Ok(first));
+}
+
+#[test]
+fn test_filestat() {
+    let mut setup = Setup::new();
+    fs::write(setup.dir.path().join("data"), [1, 2, 3]).unwrap();
+
+    let [ptr_path, path_len] = setup.path("data");
+    let args = [
+        Value::I32(DIR_FD),
+        Value::I32(1),
+        ptr_path,
+        path_len,
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(
+        setup.call("path_filestat_get", &args),
+        Errno::Success as i32
+    );
+
+    let stat = &setup.memory[PTR_OUT as usize..][..64];
+    const FILETYPE_REGULAR_FILE: u8 = 4;
+    assert_eq!(stat[16], FILETYPE_REGULAR_FILE);
+    assert_eq!(u64::from_le_bytes(stat[32..40].try_into().unwrap()), 3);
+}
+
+#[test]
+fn test_directories() {
+    let mut setup = Setup::new();
+
+    let args = setup.path("sub");
+    assert_eq!(
+        setup.call(
+            "path_create_directory",
+            &[Value::I32(DIR_FD), args[0], args[1]]
+        ),
+        Errno::Success as i32
+    );
+    assert!(setup.dir.path().join("sub").is_dir());
+
+    fs::write(setup.dir.path().join("sub/file"), "").unwrap();
+    let args = setup.path("sub");
+    assert_eq!(
+        setup.call(
+            "path_remove_directory",
+            &[Value::I32(DIR_FD), args[0], args[1]]
+        ),
+        Errno::Notempty as i32
+    );
+
+    let args = setup.path("sub/file");
+    assert_eq!(
+        setup.call("path_unlink_file", &[Value::I32(DIR_FD), args[0], args[1]]),
+        Errno::Success as i32
+    );
+
+    let args = setup.path("sub");
+    assert_eq!(
+        setup.call(
+            "path_remove_directory",
+            &[Value::I32(DIR_FD), args[0], args[1]]
+        ),
+        Errno::Success as i32
+    );
+    assert!(!setup.dir.path().join("sub").exists());
+}
+
+#[test]
+fn test_readdir() {
+    let mut setup = Setup::new();
+    fs::write(setup.dir.path().join("b"), "").unwrap();
+    fs::create_dir(setup.dir.path().join("a")).unwrap();
+
+    let fd = setup.open(".", OFLAGS_DIRECTORY);
+    // Opening a directory for writing isn't allowed, so open it the way opendir does
+    assert_eq!(fd, Err(Errno::Isdir as i32));
+
+    let [ptr_path, path_len] = setup.path(".");
+    let args = [
+        Value::I32(DIR_FD),
+        Value::I32(0),
+        ptr_path,
+        path_len,
+        Value::I32(OFLAGS_DIRECTORY),
+        Value::I64(1 << 14), // RIGHTS_FD_READDIR
+        Value::I64(0),
+        Value::I32(0),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("path_open", &args), Errno::Success as i32);
+    let fd = setup.out_u32() as i32;
+
+    let args = [
+        Value::I32(fd),
+        Value::I32(PTR_DATA),
+        Value::I32(0x200),
+        Value::I64(0),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_readdir", &args), Errno::Success as i32);
+    let bufused = setup.out_u32() as usize;
+
+    let mut entries = Vec::new();
+    let mut buf = &setup.memory[PTR_DATA as usize..][..bufused];
+    while !buf.is_empty() {
+        let namlen = u32::from_le_bytes(buf[16..20].try_into().unwrap()) as usize;
+        let name = std::str::from_utf8(&buf[24..][..namlen]).unwrap();
+        entries.push((name.to_string(), buf[20]));
+        buf = &buf[24 + namlen..];
+    }
+
+    const FILETYPE_DIRECTORY: u8 = 3;
+    const FILETYPE_REGULAR_FILE: u8 = 4;
+    assert_eq!(
+        entries,
+        [
+            (".".to_string(), FILETYPE_DIRECTORY),
+            ("..".to_string(), FILETYPE_DIRECTORY),
+            ("a".to_string(), FILETYPE_DIRECTORY),
+            ("b".to_string(), FILETYPE_REGULAR_FILE),
+        ]
+    );
+
+    // Starting from the cookie of the third entry skips the first three
+    let args = [
+        Value::I32(fd),
+        Value::I32(PTR_DATA),
+        Value::I32(0x200),
+        Value::I64(3),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_readdir", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 24 + 1);
+}
+
+#[test]
+fn test_sandbox() {
+    let mut setup = Setup::new();
+
+    assert_eq!(
+        setup.open("../outside", OFLAGS_CREAT),
+        Err(Errno::Notcapable as i32)
+    );
+    assert_eq!(setup.open("/etc/passwd", 0), Err(Errno::Notcapable as i32));
+    assert_eq!(setup.open("missing", 0), Err(Errno::Noent as i32));
+
+    // Going up is fine, as long as we stay inside
+    fs::create_dir(setup.dir.path().join("sub")).unwrap();
+    assert!(setup.open("sub/../inside", OFLAGS_CREAT).is_ok());
+    assert!(setup.dir.path().join("inside").exists());
+}
+
+#[test]
+fn test_bad_fds() {
+    let mut setup = Setup::new();
+
+    let args = [
+        Value::I32(42),
+        Value::I64(0),
+        Value::I32(0),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_seek", &args), Errno::Badf as i32);
+
+    // stdout is a stream, so it can't seek
+    let args = [
+        Value::I32(1),
+        Value::I64(0),
+        Value::I32(0),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_seek", &args), Errno::Spipe as i32);
+}
+
+#[test]
+fn test_clocks() {
+    let mut setup = Setup::new();
+    const CLOCKID_REALTIME: i32 = 0;
+    const CLOCKID_MONOTONIC: i32 = 1;
+
+    let args = [
+        Value::I32(CLOCKID_REALTIME),
+        Value::I64(1),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("clock_time_get", &args), Errno::Success as i32);
+    let realtime = setup.out_u64();
+    let expected = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    assert!(expected - realtime < 60_000_000_000);
+
+    let args = [
+        Value::I32(CLOCKID_MONOTONIC),
+        Value::I64(1),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("clock_time_get", &args), Errno::Success as i32);
+    let before = setup.out_u64();
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    assert_eq!(setup.call("clock_time_get", &args), Errno::Success as i32);
+    assert!(setup.out_u64() > before);
+
+    let args = [Value::I32(99), Value::I64(1), Value::I32(PTR_OUT)];
+    assert_eq!(setup.call("clock_time_get", &args), Errno::Inval as i32);
+}
+
+#[test]
+fn test_in_memory_files_are_pipes() {
+    use crate::WasiFile;
+
+    let mut setup = Setup::new();
+    setup.wasi.files[0] = WasiFile::ReadOnly(b"abc".to_vec());
+
+    setup.iovec(&[0; 2], 2);
+    let args = [
+        Value::I32(0),
+        Value::I32(PTR_IOVS),
+        Value::I32(1),
+        Value::I32(PTR_OUT),
+    ];
+    assert_eq!(setup.call("fd_read", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 2);
+    assert_eq!(&setup.memory[PTR_DATA as usize..][..2], b"ab");
+
+    assert_eq!(setup.call("fd_read", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 1);
+    assert_eq!(&setup.memory[PTR_DATA as usize..][..1], b"c");
+
+    assert_eq!(setup.call("fd_read", &args), Errno::Success as i32);
+    assert_eq!(setup.out_u32(), 0);
+}
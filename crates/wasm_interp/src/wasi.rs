@@ -1,14 +1,19 @@
 use rand::prelude::*;
 use roc_wasm_module::Value;
-use std::io::{self, Read, StderrLock, StdoutLock, Write};
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, StderrLock, StdoutLock, Write};
+use std::path::{Component, Path, PathBuf};
 use std::process::exit;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub const MODULE_NAME: &str = "wasi_snapshot_preview1";
 
 pub struct WasiDispatcher<'a> {
     pub args: &'a [&'a [u8]],
     pub rng: ThreadRng,
+    /// Indexed by file descriptor
     pub files: Vec<WasiFile>,
+    start_time: Instant,
 }
 
 impl Default for WasiDispatcher<'_> {
@@ -18,18 +23,59 @@ impl Default for WasiDispatcher<'_> {
 }
 
 pub enum WasiFile {
+    /// In-memory files behave like pipes: reading consumes bytes from the front, and writing
+    /// appends to the end. They're useful for faking stdio in tests.
     ReadOnly(Vec<u8>),
     WriteOnly(Vec<u8>),
     ReadWrite(Vec<u8>),
+    /// The host's stdin, stdout, or stderr, depending on the file descriptor
     HostSystemFile,
+    /// A file on the host, opened by the app using `path_open`
+    HostFile(File),
+    /// A directory on the host, whose contents the app can access using the `path_*` functions.
+    /// Preopened directories are given to the app when it starts, and have a name it can see.
+    HostDirectory {
+        host_path: PathBuf,
+        preopen_name: Option<String>,
+    },
+    /// A file descriptor the app has closed. `path_open` can reuse it.
+    Closed,
 }
 
 enum WriteLock<'a> {
     StdOut(StdoutLock<'a>),
     Stderr(StderrLock<'a>),
     RegularFile(&'a mut Vec<u8>),
+    HostFile(&'a mut File),
 }
 
+// https://github.com/WebAssembly/WASI/blob/snapshot-01/phases/snapshot/docs.md#-filetype-enumu8
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_BLOCK_DEVICE: u8 = 1;
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const FILETYPE_SOCKET_STREAM: u8 = 6;
+const FILETYPE_SYMBOLIC_LINK: u8 = 7;
+
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+/// We don't restrict what apps can do with the files they can open, so we grant every right.
+const RIGHTS_ALL: u64 = (1 << 29) - 1;
+
+const OFLAGS_CREAT: u32 = 1 << 0;
+const OFLAGS_DIRECTORY: u32 = 1 << 1;
+const OFLAGS_EXCL: u32 = 1 << 2;
+const OFLAGS_TRUNC: u32 = 1 << 3;
+
+const FDFLAGS_APPEND: u32 = 1 << 0;
+const LOOKUPFLAGS_SYMLINK_FOLLOW: u32 = 1 << 0;
+
+const CLOCKID_REALTIME: u32 = 0;
+const CLOCKID_MONOTONIC: u32 = 1;
+const CLOCKID_PROCESS_CPUTIME: u32 = 2;
+const CLOCKID_THREAD_CPUTIME: u32 = 3;
+
 /// Implementation of WASI syscalls
 /// References for other engines:
 /// https://github.com/wasmerio/wasmer/blob/ef8d2f651ed29b4b06fdc2070eb8189922c54d82/lib/wasi/src/syscalls/mod.rs
@@ -44,9 +90,20 @@ impl<'a> WasiDispatcher<'a> {
                 WasiFile::HostSystemFile,
                 WasiFile::HostSystemFile,
             ],
+            start_time: Instant::now(),
         }
     }
 
+    /// Give the app access to a directory on the host, which it will see as `guest_path`.
+    /// WASI libc looks for preopened directories in consecutive file descriptors right after
+    /// stdio, when the app starts, so they must all be added before anything else is opened.
+    pub fn preopen_dir(&mut self, guest_path: &str, host_path: impl Into<PathBuf>) {
+        self.files.push(WasiFile::HostDirectory {
+            host_path: host_path.into(),
+            preopen_name: Some(guest_path.to_string()),
+        });
+    }
+
     pub fn dispatch(
         &mut self,
         function_name: &str,
@@ -107,23 +164,66 @@ impl<'a> WasiDispatcher<'a> {
 
                 success_code
             }
-            "clock_res_get" => success_code, // this dummy implementation seems to be good enough for some functions
-            "clock_time_get" => success_code,
-            "fd_advise" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_allocate" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_close" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_datasync" => todo!("WASI {}({:?})", function_name, arguments),
+            "clock_res_get" => {
+                let clock_id = arg_u32(arguments, 0);
+                let ptr_resolution = arg_u32(arguments, 1);
+
+                errno_result(self.clock_time(clock_id).and_then(|_| {
+                    // Nanoseconds. This is what Rust's standard library gives us on most hosts.
+                    let resolution: u64 = 1;
+                    write_bytes(memory, ptr_resolution, &resolution.to_le_bytes())
+                }))
+            }
+            "clock_time_get" => {
+                let clock_id = arg_u32(arguments, 0);
+                // We always give the most precise time we can, so we don't need the precision.
+                let ptr_time = arg_u32(arguments, 2);
+
+                errno_result(
+                    self.clock_time(clock_id)
+                        .and_then(|time| write_bytes(memory, ptr_time, &time.to_le_bytes())),
+                )
+            }
+            "fd_advise" => {
+                // Advice is optional, so we can ignore it for valid file descriptors.
+                let fd = arg_u32(arguments, 0);
+                errno_result(self.file(fd).map(|_| ()))
+            }
+            "fd_allocate" => errno_result(self.fd_allocate(arguments)),
+            "fd_close" => {
+                let fd = arg_u32(arguments, 0);
+                let result = self.file(fd).map(|_| ());
+                errno_result(result.map(|()| self.files[fd as usize] = WasiFile::Closed))
+            }
+            "fd_datasync" | "fd_sync" => {
+                let fd = arg_u32(arguments, 0);
+                let result = match self.file_mut(fd) {
+                    Ok(WasiFile::HostFile(file)) if function_name == "fd_datasync" => {
+                        file.sync_data().map_err(Errno::from)
+                    }
+                    Ok(WasiFile::HostFile(file)) => file.sync_all().map_err(Errno::from),
+                    Ok(WasiFile::HostDirectory { .. }) => Ok(()),
+                    Ok(_) => Err(Errno::Inval),
+                    Err(errno) => Err(errno),
+                };
+                errno_result(result)
+            }
             "fd_fdstat_get" => {
                 // (i32, i32) -> i32
 
                 // file descriptor
-                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let fd = arg_u32(arguments, 0);
                 // ptr to a wasi_fdstat_t
-                let stat_mut_ptr = arguments[1].expect_i32().unwrap() as usize;
+                let stat_mut_ptr = arg_u32(arguments, 1);
 
-                match fd {
-                    1 => {
-                        // Tell WASI that stdout is a tty (no seek or tell)
+                let result = self.file(fd).and_then(|file| {
+                    let (filetype, rights_base, rights_inheriting) = match file {
+                        WasiFile::HostFile(_) => (FILETYPE_REGULAR_FILE, RIGHTS_ALL, 0),
+                        WasiFile::HostDirectory { .. } => {
+                            (FILETYPE_DIRECTORY, RIGHTS_ALL, RIGHTS_ALL)
+                        }
+                        // Tell WASI that stdio (and in-memory files standing in for it) is a tty
+                        // (no seek or tell)
                         // https://github.com/WebAssembly/wasi-libc/blob/659ff414560721b1660a19685110e484a081c3d4/libc-bottom-half/sources/isatty.c
                         // *Not* a tty if:
                         //     (statbuf.fs_filetype != __WASI_FILETYPE_CHARACTER_DEVICE ||
@@ -131,104 +231,244 @@ impl<'a> WasiDispatcher<'a> {
                         // So it's sufficient to set:
                         //     .fs_filetype = __WASI_FILETYPE_CHARACTER_DEVICE
                         //     .fs_rights_base = 0
+                        _ => (FILETYPE_CHARACTER_DEVICE, 0, 0),
+                    };
 
-                        const WASI_FILETYPE_CHARACTER_DEVICE: u8 = 2;
-                        memory[stat_mut_ptr] = WASI_FILETYPE_CHARACTER_DEVICE;
+                    // struct fdstat { u8 filetype; u16 flags; u64 rights_base; u64 rights_inheriting; }
+                    let mut stat = [0; 24];
+                    stat[0] = filetype;
+                    stat[8..16].copy_from_slice(&rights_base.to_le_bytes());
+                    stat[16..24].copy_from_slice(&rights_inheriting.to_le_bytes());
 
-                        for b in memory[stat_mut_ptr + 1..stat_mut_ptr + 24].iter_mut() {
-                            *b = 0;
-                        }
+                    write_bytes(memory, stat_mut_ptr, &stat)
+                });
+
+                errno_result(result)
+            }
+            "fd_fdstat_set_flags" => {
+                let fd = arg_u32(arguments, 0);
+                let flags = arg_u32(arguments, 1);
+
+                // Files can't be switched into (or out of) append or non-blocking mode once open.
+                let result = self.file(fd).and(match flags {
+                    0 => Ok(()),
+                    _ => Err(Errno::Notsup),
+                });
+                errno_result(result)
+            }
+            "fd_fdstat_set_rights" => {
+                // We grant every right, and don't enforce any of them, so there's nothing to do.
+                let fd = arg_u32(arguments, 0);
+                errno_result(self.file(fd).map(|_| ()))
+            }
+            "fd_filestat_get" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_buf = arg_u32(arguments, 1);
+
+                let result = match self.file(fd) {
+                    Ok(WasiFile::HostFile(file)) => file.metadata().map_err(Errno::from),
+                    Ok(WasiFile::HostDirectory { host_path, .. }) => {
+                        fs::metadata(host_path).map_err(Errno::from)
                     }
-                    _ => todo!("WASI {}({:?})", function_name, arguments),
-                }
+                    Ok(WasiFile::ReadOnly(content))
+                    | Ok(WasiFile::WriteOnly(content))
+                    | Ok(WasiFile::ReadWrite(content)) => {
+                        let stat = stream_filestat(content.len() as u64);
+                        return errno_result(write_bytes(memory, ptr_buf, &stat));
+                    }
+                    Ok(_) => {
+                        let stat = stream_filestat(0);
+                        return errno_result(write_bytes(memory, ptr_buf, &stat));
+                    }
+                    Err(errno) => Err(errno),
+                };
 
-                success_code
+                errno_result(
+                    result.and_then(|metadata| write_bytes(memory, ptr_buf, &filestat(&metadata))),
+                )
+            }
+            "fd_filestat_set_size" => {
+                let fd = arg_u32(arguments, 0);
+                let size = arg_u64(arguments, 1);
+
+                errno_result(
+                    self.host_file(fd, Errno::Inval)
+                        .and_then(|file| file.set_len(size).map_err(Errno::from)),
+                )
+            }
+            "fd_filestat_set_times" => {
+                let fd = arg_u32(arguments, 0);
+                let atim = arg_u64(arguments, 1);
+                let mtim = arg_u64(arguments, 2);
+                let fst_flags = arg_u32(arguments, 3);
+
+                let result = match self.file(fd) {
+                    Ok(WasiFile::HostFile(file)) => set_file_times(file, atim, mtim, fst_flags),
+                    Ok(WasiFile::HostDirectory { host_path, .. }) => {
+                        set_path_times(host_path, true, atim, mtim, fst_flags)
+                    }
+                    Ok(_) => Err(Errno::Inval),
+                    Err(errno) => Err(errno),
+                };
+
+                errno_result(result)
+            }
+            "fd_pread" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_iovs = arg_u32(arguments, 1);
+                let iovs_len = arg_u32(arguments, 2);
+                let offset = arg_u64(arguments, 3);
+                let ptr_nread = arg_u32(arguments, 4);
+
+                let result = iovecs(memory, ptr_iovs, iovs_len).and_then(|iovs| {
+                    let file = self.host_file(fd, Errno::Spipe)?;
+                    let n_read = at_offset(file, offset, |file| read_iovs(file, memory, &iovs))?;
+                    write_bytes(memory, ptr_nread, &(n_read as u32).to_le_bytes())
+                });
+
+                errno_result(result)
             }
-            "fd_fdstat_set_flags" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_fdstat_set_rights" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_size" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_pread" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_prestat_get" => {
                 // The preopened file descriptor to query
-                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let fd = arg_u32(arguments, 0) as usize;
                 // ptr_buf: Where the metadata will be written
                 //  preopen type: 4 bytes, where 0=dir is the only one supported, it seems
                 //  preopen name length: 4 bytes
-                let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
-                memory[ptr_buf..][..8].copy_from_slice(&0u64.to_le_bytes());
-                if fd < self.files.len() {
-                    success_code
-                } else {
-                    println!("WASI warning: file descriptor {fd} does not exist");
-                    Some(Value::I32(Errno::Badf as i32))
-                }
+                let ptr_buf = arg_u32(arguments, 1);
+
+                // WASI libc finds the preopens by asking about every file descriptor after stdio,
+                // until it gets an error.
+                let result = match self.files.get(fd) {
+                    Some(WasiFile::HostDirectory {
+                        preopen_name: Some(name),
+                        ..
+                    }) => {
+                        let mut prestat = [0; 8];
+                        prestat[4..8].copy_from_slice(&(name.len() as u32).to_le_bytes());
+                        write_bytes(memory, ptr_buf, &prestat)
+                    }
+                    _ => Err(Errno::Badf),
+                };
+
+                errno_result(result)
             }
             "fd_prestat_dir_name" => {
-                // We're not giving names to any of our files so just return success
-                success_code
+                let fd = arg_u32(arguments, 0) as usize;
+                let ptr_path = arg_u32(arguments, 1);
+                let path_len = arg_u32(arguments, 2) as usize;
+
+                let result = match self.files.get(fd) {
+                    Some(WasiFile::HostDirectory {
+                        preopen_name: Some(name),
+                        ..
+                    }) => {
+                        let len = name.len().min(path_len);
+                        write_bytes(memory, ptr_path, &name.as_bytes()[..len])
+                    }
+                    _ => Err(Errno::Badf),
+                };
+
+                errno_result(result)
+            }
+            "fd_pwrite" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_iovs = arg_u32(arguments, 1);
+                let iovs_len = arg_u32(arguments, 2);
+                let offset = arg_u64(arguments, 3);
+                let ptr_nwritten = arg_u32(arguments, 4);
+
+                let result = iovecs(memory, ptr_iovs, iovs_len).and_then(|iovs| {
+                    let file = self.host_file(fd, Errno::Spipe)?;
+                    let n_written =
+                        at_offset(file, offset, |file| write_iovs(file, memory, &iovs))?;
+                    write_bytes(memory, ptr_nwritten, &(n_written as u32).to_le_bytes())
+                });
+
+                errno_result(result)
             }
-            "fd_pwrite" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_read" => {
                 use WasiFile::*;
 
                 // file descriptor
                 let fd = arguments[0].expect_i32().unwrap() as usize;
                 // Array of IO vectors
-                let ptr_iovs = arguments[1].expect_i32().unwrap() as usize;
+                let ptr_iovs = arg_u32(arguments, 1);
                 // Length of array
-                let iovs_len = arguments[2].expect_i32().unwrap();
+                let iovs_len = arg_u32(arguments, 2);
                 // Out param: number of bytes read
-                let ptr_nread = arguments[3].expect_i32().unwrap() as usize;
+                let ptr_nread = arg_u32(arguments, 3);
 
-                // https://man7.org/linux/man-pages/man2/readv.2.html
-                // struct iovec {
-                //     void  *iov_base;    /* Starting address */
-                //     size_t iov_len;     /* Number of bytes to transfer */
-                // };
+                let iovs = match iovecs(memory, ptr_iovs, iovs_len) {
+                    Ok(iovs) => iovs,
+                    Err(errno) => return errno_result(Err(errno)),
+                };
 
-                let mut n_read: usize = 0;
-                match self.files.get(fd) {
+                let n_read = match self.files.get_mut(fd) {
                     Some(ReadOnly(content) | ReadWrite(content)) => {
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
-                            let remaining = content.len() - n_read;
-                            let len = remaining.min(iov_len);
-                            if len == 0 {
-                                break;
-                            }
-                            memory[iov_base..][..len].copy_from_slice(&content[n_read..][..len]);
-                            n_read += len;
-                        }
+                        let mut unread = content.as_slice();
+                        read_iovs(&mut unread, memory, &iovs).map(|n_read| {
+                            content.drain(..n_read);
+                            n_read
+                        })
                     }
                     Some(HostSystemFile) if fd == 0 => {
-                        let mut stdin = io::stdin();
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
-                            match stdin.read(&mut memory[iov_base..][..iov_len]) {
-                                Ok(n) => {
-                                    n_read += n;
-                                }
-                                Err(_) => {
-                                    break;
-                                }
-                            }
-                        }
+                        read_iovs(&mut io::stdin().lock(), memory, &iovs)
                     }
-                    _ => return Some(Value::I32(Errno::Badf as i32)),
+                    Some(HostFile(file)) => read_iovs(file, memory, &iovs),
+                    Some(HostDirectory { .. }) => Err(Errno::Isdir),
+                    _ => Err(Errno::Badf),
                 };
 
-                memory[ptr_nread..][..4].copy_from_slice(&(n_read as u32).to_le_bytes());
-                success_code
+                errno_result(n_read.and_then(|n_read| {
+                    write_bytes(memory, ptr_nread, &(n_read as u32).to_le_bytes())
+                }))
+            }
+            "fd_readdir" => errno_result(self.fd_readdir(arguments, memory)),
+            "fd_renumber" => {
+                let from = arg_u32(arguments, 0);
+                let to = arg_u32(arguments, 1);
+
+                let valid = self.file(from).and_then(|_| self.file(to)).map(|_| ());
+                let result = valid.map(|()| {
+                    if from != to {
+                        self.files.swap(from as usize, to as usize);
+                        self.files[from as usize] = WasiFile::Closed;
+                    }
+                });
+
+                errno_result(result)
+            }
+            "fd_seek" => {
+                let fd = arg_u32(arguments, 0);
+                let offset = arguments[1].expect_i64().unwrap();
+                let whence = arg_u32(arguments, 2);
+                let ptr_newoffset = arg_u32(arguments, 3);
+
+                let result = match whence {
+                    0 if offset >= 0 => Ok(SeekFrom::Start(offset as u64)),
+                    1 => Ok(SeekFrom::Current(offset)),
+                    2 => Ok(SeekFrom::End(offset)),
+                    _ => Err(Errno::Inval),
+                }
+                .and_then(|seek_from| {
+                    let file = self.host_file(fd, Errno::Spipe)?;
+                    let new_offset = file.seek(seek_from)?;
+                    write_bytes(memory, ptr_newoffset, &new_offset.to_le_bytes())
+                });
+
+                errno_result(result)
+            }
+            "fd_tell" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_offset = arg_u32(arguments, 1);
+
+                let result = self.host_file(fd, Errno::Spipe).and_then(|file| {
+                    let offset = file.stream_position()?;
+                    write_bytes(memory, ptr_offset, &offset.to_le_bytes())
+                });
+
+                errno_result(result)
             }
-            "fd_readdir" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_renumber" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_seek" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_sync" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_tell" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_write" => {
                 use WasiFile::*;
 
@@ -252,6 +492,7 @@ impl<'a> WasiDispatcher<'a> {
                     Some(WriteOnly(content) | ReadWrite(content)) => {
                         WriteLock::RegularFile(content)
                     }
+                    Some(HostFile(file)) => WriteLock::HostFile(file),
                     _ => return Some(Value::I32(Errno::Badf as i32)),
                 };
 
@@ -280,6 +521,7 @@ impl<'a> WasiDispatcher<'a> {
                         WriteLock::StdOut(stdout) => stdout.write_all(bytes),
                         WriteLock::Stderr(stderr) => stderr.write_all(bytes),
                         WriteLock::RegularFile(content) => content.write_all(bytes),
+                        WriteLock::HostFile(file) => file.write_all(bytes),
                     };
                     if write_result.is_err() {
                         break;
@@ -297,26 +539,160 @@ impl<'a> WasiDispatcher<'a> {
 
                 match write_result {
                     Ok(()) => success_code,
-                    Err(_) => Some(Value::I32(Errno::Io as i32)),
+                    Err(e) => Some(Value::I32(Errno::from(e) as i32)),
                 }
             }
-            "path_create_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_link" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_open" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_readlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_remove_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_rename" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_symlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_unlink_file" => todo!("WASI {}({:?})", function_name, arguments),
-            "poll_oneoff" => todo!("WASI {}({:?})", function_name, arguments),
+            "path_create_directory" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_path = arg_u32(arguments, 1);
+                let path_len = arg_u32(arguments, 2);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| fs::create_dir(path).map_err(Errno::from));
+
+                errno_result(result)
+            }
+            "path_filestat_get" => {
+                let fd = arg_u32(arguments, 0);
+                let flags = arg_u32(arguments, 1);
+                let ptr_path = arg_u32(arguments, 2);
+                let path_len = arg_u32(arguments, 3);
+                let ptr_buf = arg_u32(arguments, 4);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| {
+                        let metadata = if flags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0 {
+                            fs::metadata(path)?
+                        } else {
+                            fs::symlink_metadata(path)?
+                        };
+                        write_bytes(memory, ptr_buf, &filestat(&metadata))
+                    });
+
+                errno_result(result)
+            }
+            "path_filestat_set_times" => {
+                let fd = arg_u32(arguments, 0);
+                let flags = arg_u32(arguments, 1);
+                let ptr_path = arg_u32(arguments, 2);
+                let path_len = arg_u32(arguments, 3);
+                let atim = arg_u64(arguments, 4);
+                let mtim = arg_u64(arguments, 5);
+                let fst_flags = arg_u32(arguments, 6);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| {
+                        let follow_symlinks = flags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0;
+                        set_path_times(&path, follow_symlinks, atim, mtim, fst_flags)
+                    });
+
+                errno_result(result)
+            }
+            "path_link" => {
+                let old_fd = arg_u32(arguments, 0);
+                let ptr_old_path = arg_u32(arguments, 2);
+                let old_path_len = arg_u32(arguments, 3);
+                let new_fd = arg_u32(arguments, 4);
+                let ptr_new_path = arg_u32(arguments, 5);
+                let new_path_len = arg_u32(arguments, 6);
+
+                let result = self
+                    .resolve(old_fd, memory, ptr_old_path, old_path_len)
+                    .and_then(|old_path| {
+                        let new_path = self.resolve(new_fd, memory, ptr_new_path, new_path_len)?;
+                        fs::hard_link(old_path, new_path).map_err(Errno::from)
+                    });
+
+                errno_result(result)
+            }
+            "path_open" => errno_result(self.path_open(arguments, memory)),
+            "path_readlink" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_path = arg_u32(arguments, 1);
+                let path_len = arg_u32(arguments, 2);
+                let ptr_buf = arg_u32(arguments, 3);
+                let buf_len = arg_u32(arguments, 4) as usize;
+                let ptr_bufused = arg_u32(arguments, 5);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| {
+                        let target = fs::read_link(path)?;
+                        let target = target.to_str().ok_or(Errno::Ilseq)?.as_bytes();
+                        // Like readlink(2), we silently truncate the target if the buffer is too small.
+                        let len = target.len().min(buf_len);
+                        write_bytes(memory, ptr_buf, &target[..len])?;
+                        write_bytes(memory, ptr_bufused, &(len as u32).to_le_bytes())
+                    });
+
+                errno_result(result)
+            }
+            "path_remove_directory" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_path = arg_u32(arguments, 1);
+                let path_len = arg_u32(arguments, 2);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| fs::remove_dir(path).map_err(Errno::from));
+
+                errno_result(result)
+            }
+            "path_rename" => {
+                let old_fd = arg_u32(arguments, 0);
+                let ptr_old_path = arg_u32(arguments, 1);
+                let old_path_len = arg_u32(arguments, 2);
+                let new_fd = arg_u32(arguments, 3);
+                let ptr_new_path = arg_u32(arguments, 4);
+                let new_path_len = arg_u32(arguments, 5);
+
+                let result = self
+                    .resolve(old_fd, memory, ptr_old_path, old_path_len)
+                    .and_then(|old_path| {
+                        let new_path = self.resolve(new_fd, memory, ptr_new_path, new_path_len)?;
+                        fs::rename(old_path, new_path).map_err(Errno::from)
+                    });
+
+                errno_result(result)
+            }
+            "path_symlink" => {
+                let ptr_old_path = arg_u32(arguments, 0);
+                let old_path_len = arg_u32(arguments, 1);
+                let fd = arg_u32(arguments, 2);
+                let ptr_new_path = arg_u32(arguments, 3);
+                let new_path_len = arg_u32(arguments, 4);
+
+                let result = read_str(memory, ptr_old_path, old_path_len).and_then(|target| {
+                    let link = self.resolve(fd, memory, ptr_new_path, new_path_len)?;
+                    symlink(target, &link)
+                });
+
+                errno_result(result)
+            }
+            "path_unlink_file" => {
+                let fd = arg_u32(arguments, 0);
+                let ptr_path = arg_u32(arguments, 1);
+                let path_len = arg_u32(arguments, 2);
+
+                let result = self
+                    .resolve(fd, memory, ptr_path, path_len)
+                    .and_then(|path| fs::remove_file(path).map_err(Errno::from));
+
+                errno_result(result)
+            }
+            "poll_oneoff" => errno_result(self.poll_oneoff(arguments, memory)),
             "proc_exit" => {
                 let exit_code = arguments[0].expect_i32().unwrap();
                 exit(exit_code);
             }
-            "proc_raise" => todo!("WASI {}({:?})", function_name, arguments),
-            "sched_yield" => todo!("WASI {}({:?})", function_name, arguments),
+            "proc_raise" => Some(Value::I32(Errno::Nosys as i32)),
+            "sched_yield" => {
+                std::thread::yield_now();
+                success_code
+            }
             "random_get" => {
                 // A pointer to a buffer where the random bytes will be written
                 let ptr_buf = arguments[0].expect_i32().unwrap() as usize;
@@ -327,12 +703,551 @@ impl<'a> WasiDispatcher<'a> {
                 }
                 success_code
             }
-            "sock_recv" => todo!("WASI {}({:?})", function_name, arguments),
-            "sock_send" => todo!("WASI {}({:?})", function_name, arguments),
-            "sock_shutdown" => todo!("WASI {}({:?})", function_name, arguments),
+            // There's no way to open a socket in this version of WASI, so there are none to use.
+            "sock_recv" | "sock_send" | "sock_shutdown" => Some(Value::I32(Errno::Notsock as i32)),
             _ => panic!("Unknown WASI function {function_name}({arguments:?})"),
         }
     }
+
+    fn file(&self, fd: u32) -> Result<&WasiFile, Errno> {
+        match self.files.get(fd as usize) {
+            None | Some(WasiFile::Closed) => Err(Errno::Badf),
+            Some(file) => Ok(file),
+        }
+    }
+
+    fn file_mut(&mut self, fd: u32) -> Result<&mut WasiFile, Errno> {
+        match self.files.get_mut(fd as usize) {
+            None | Some(WasiFile::Closed) => Err(Errno::Badf),
+            Some(file) => Ok(file),
+        }
+    }
+
+    /// Get a file on the host, for operations that only make sense on regular files.
+    /// Returns `stream_errno` if the file descriptor is for a stream, like stdio.
+    fn host_file(&mut self, fd: u32, stream_errno: Errno) -> Result<&mut File, Errno> {
+        match self.file_mut(fd)? {
+            WasiFile::HostFile(file) => Ok(file),
+            WasiFile::HostDirectory { .. } => Err(Errno::Isdir),
+            _ => Err(stream_errno),
+        }
+    }
+
+    /// Find the host path for a path the app gave us, relative to one of its directories.
+    /// Apps can only see what's inside the directories we've given them, so the path may not
+    /// go outside of that directory. This only looks at the path itself, so it doesn't stop
+    /// symlinks inside the directory from pointing outside of it.
+    fn resolve(
+        &self,
+        fd: u32,
+        memory: &[u8],
+        ptr_path: u32,
+        path_len: u32,
+    ) -> Result<PathBuf, Errno> {
+        let mut resolved = match self.file(fd)? {
+            WasiFile::HostDirectory { host_path, .. } => host_path.clone(),
+            _ => return Err(Errno::Notdir),
+        };
+
+        let path = read_str(memory, ptr_path, path_len)?;
+        if path.is_empty() {
+            return Err(Errno::Noent);
+        }
+
+        let mut depth = 0;
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(name) => {
+                    resolved.push(name);
+                    depth += 1;
+                }
+                Component::CurDir => {}
+                Component::ParentDir if depth > 0 => {
+                    resolved.pop();
+                    depth -= 1;
+                }
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(Errno::Notcapable);
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Nanoseconds since some fixed point in time, which depends on the clock
+    fn clock_time(&self, clock_id: u32) -> Result<u64, Errno> {
+        match clock_id {
+            CLOCKID_REALTIME => {
+                let since_epoch = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                Ok(since_epoch.as_nanos() as u64)
+            }
+            // We don't measure CPU time, but the time since the app started is an upper bound.
+            CLOCKID_MONOTONIC | CLOCKID_PROCESS_CPUTIME | CLOCKID_THREAD_CPUTIME => {
+                Ok(self.start_time.elapsed().as_nanos() as u64)
+            }
+            _ => Err(Errno::Inval),
+        }
+    }
+
+    fn fd_allocate(&mut self, arguments: &[Value]) -> Result<(), Errno> {
+        let fd = arg_u32(arguments, 0);
+        let offset = arg_u64(arguments, 1);
+        let len = arg_u64(arguments, 2);
+
+        let file = self.host_file(fd, Errno::Spipe)?;
+        let end = offset.checked_add(len).ok_or(Errno::Fbig)?;
+        if file.metadata()?.len() < end {
+            file.set_len(end)?;
+        }
+
+        Ok(())
+    }
+
+    fn fd_readdir(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        let fd = arg_u32(arguments, 0);
+        let ptr_buf = arg_u32(arguments, 1);
+        let buf_len = arg_u32(arguments, 2) as usize;
+        // The index of the entry to start from, which is the `d_next` of the last entry we read.
+        let cookie = arg_u64(arguments, 3);
+        let ptr_bufused = arg_u32(arguments, 4);
+
+        let host_path = match self.file(fd)? {
+            WasiFile::HostDirectory { host_path, .. } => host_path,
+            _ => return Err(Errno::Notdir),
+        };
+
+        let mut entries = vec![
+            (b".".to_vec(), FILETYPE_DIRECTORY, 0),
+            (b"..".to_vec(), FILETYPE_DIRECTORY, 0),
+        ];
+        let mut children = Vec::new();
+        for entry in fs::read_dir(host_path)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_str().ok_or(Errno::Ilseq)?.as_bytes().to_vec();
+            children.push((name, filetype(&entry.file_type()?), inode(&entry)));
+        }
+        // Keep the order stable between calls, so that cookies stay meaningful.
+        children.sort();
+        entries.extend(children);
+
+        let mut buf = Vec::new();
+        for (index, (name, filetype, inode)) in entries.iter().enumerate().skip(cookie as usize) {
+            if buf.len() >= buf_len {
+                break;
+            }
+
+            // struct dirent { u64 d_next; u64 d_ino; u32 d_namlen; u8 d_type; }
+            let mut dirent = [0; 24];
+            dirent[0..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+            dirent[8..16].copy_from_slice(&inode.to_le_bytes());
+            dirent[16..20].copy_from_slice(&(name.len() as u32).to_le_bytes());
+            dirent[20] = *filetype;
+
+            buf.extend_from_slice(&dirent);
+            buf.extend_from_slice(name);
+        }
+
+        // A full buffer tells the app there may be more entries, which it will ask for again
+        // starting from the last entry it was able to read completely.
+        buf.truncate(buf_len);
+        write_bytes(memory, ptr_buf, &buf)?;
+        write_bytes(memory, ptr_bufused, &(buf.len() as u32).to_le_bytes())
+    }
+
+    fn path_open(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        let dir_fd = arg_u32(arguments, 0);
+        // Symlinks are always followed.
+        let ptr_path = arg_u32(arguments, 2);
+        let path_len = arg_u32(arguments, 3);
+        let oflags = arg_u32(arguments, 4);
+        let rights_base = arg_u64(arguments, 5);
+        let fdflags = arg_u32(arguments, 7);
+        let ptr_fd = arg_u32(arguments, 8);
+
+        let host_path = self.resolve(dir_fd, memory, ptr_path, path_len)?;
+        let read = rights_base & RIGHTS_FD_READ != 0;
+        let write = rights_base & RIGHTS_FD_WRITE != 0;
+
+        let file = if host_path.is_dir() {
+            if oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0 {
+                return Err(Errno::Exist);
+            } else if write || oflags & OFLAGS_TRUNC != 0 {
+                return Err(Errno::Isdir);
+            }
+
+            WasiFile::HostDirectory {
+                host_path,
+                preopen_name: None,
+            }
+        } else if oflags & OFLAGS_DIRECTORY != 0 {
+            return Err(if host_path.exists() {
+                Errno::Notdir
+            } else {
+                Errno::Noent
+            });
+        } else {
+            let append = fdflags & FDFLAGS_APPEND != 0;
+            let file = OpenOptions::new()
+                // The host needs to open the file somehow, even if the app will only stat it.
+                .read(read || !(write || append))
+                .write(write)
+                .append(append)
+                .create(oflags & OFLAGS_CREAT != 0)
+                .create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+                .truncate(oflags & OFLAGS_TRUNC != 0)
+                .open(host_path)?;
+
+            WasiFile::HostFile(file)
+        };
+
+        let fd = match self
+            .files
+            .iter()
+            .position(|file| matches!(file, WasiFile::Closed))
+        {
+            Some(fd) => {
+                self.files[fd] = file;
+                fd
+            }
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            }
+        };
+
+        write_bytes(memory, ptr_fd, &(fd as u32).to_le_bytes())
+    }
+
+    /// We never block on file descriptors, since reading and writing them never blocks either,
+    /// so this only ever waits for clocks, which is how WASI libc implements `sleep`.
+    fn poll_oneoff(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        const EVENTTYPE_CLOCK: u8 = 0;
+        const SUBCLOCKFLAGS_ABSTIME: u16 = 1 << 0;
+
+        let ptr_subscriptions = arg_u32(arguments, 0);
+        let ptr_events = arg_u32(arguments, 1);
+        let nsubscriptions = arg_u32(arguments, 2);
+        let ptr_nevents = arg_u32(arguments, 3);
+
+        // (userdata, event type, time to wait for clocks)
+        let mut subscriptions = Vec::with_capacity(nsubscriptions as usize);
+        for i in 0..nsubscriptions {
+            // struct subscription { u64 userdata; u8 tag; union { clock, fd_readwrite } u; }
+            let subscription = read_bytes(memory, ptr_subscriptions + 48 * i, 48)?;
+            let userdata = u64::from_le_bytes(subscription[0..8].try_into().unwrap());
+            let event_type = subscription[8];
+
+            let wait = if event_type == EVENTTYPE_CLOCK {
+                // struct subscription_clock { u32 id; u64 timeout; u64 precision; u16 flags; }
+                let clock_id = u32::from_le_bytes(subscription[16..20].try_into().unwrap());
+                let timeout = u64::from_le_bytes(subscription[24..32].try_into().unwrap());
+                let flags = u16::from_le_bytes(subscription[40..42].try_into().unwrap());
+
+                let now = self.clock_time(clock_id)?;
+                if flags & SUBCLOCKFLAGS_ABSTIME != 0 {
+                    Some(timeout.saturating_sub(now))
+                } else {
+                    Some(timeout)
+                }
+            } else {
+                None
+            };
+
+            subscriptions.push((userdata, event_type, wait));
+        }
+
+        let any_fd_ready = subscriptions.iter().any(|(_, _, wait)| wait.is_none());
+        let shortest_wait = subscriptions.iter().filter_map(|(_, _, wait)| *wait).min();
+        let waited = match shortest_wait {
+            Some(nanos) if !any_fd_ready => {
+                std::thread::sleep(Duration::from_nanos(nanos));
+                nanos
+            }
+            _ => 0,
+        };
+
+        let mut nevents = 0;
+        for (userdata, event_type, wait) in subscriptions {
+            if wait.map_or(true, |nanos| nanos <= waited) {
+                // struct event { u64 userdata; u16 error; u8 type; fd_readwrite; }
+                let mut event = [0; 32];
+                event[0..8].copy_from_slice(&userdata.to_le_bytes());
+                event[10] = event_type;
+
+                write_bytes(memory, ptr_events + 32 * nevents, &event)?;
+                nevents += 1;
+            }
+        }
+
+        write_bytes(memory, ptr_nevents, &nevents.to_le_bytes())
+    }
+}
+
+fn arg_u32(arguments: &[Value], index: usize) -> u32 {
+    arguments[index].expect_i32().unwrap() as u32
+}
+
+fn arg_u64(arguments: &[Value], index: usize) -> u64 {
+    arguments[index].expect_i64().unwrap() as u64
+}
+
+fn errno_result(result: Result<(), Errno>) -> Option<Value> {
+    let errno = match result {
+        Ok(()) => Errno::Success,
+        Err(errno) => errno,
+    };
+    Some(Value::I32(errno as i32))
+}
+
+fn read_bytes(memory: &[u8], ptr: u32, len: u32) -> Result<&[u8], Errno> {
+    memory
+        .get(ptr as usize..)
+        .and_then(|bytes| bytes.get(..len as usize))
+        .ok_or(Errno::Fault)
+}
+
+fn write_bytes(memory: &mut [u8], ptr: u32, bytes: &[u8]) -> Result<(), Errno> {
+    memory
+        .get_mut(ptr as usize..)
+        .and_then(|target| target.get_mut(..bytes.len()))
+        .ok_or(Errno::Fault)?
+        .copy_from_slice(bytes);
+    Ok(())
+}
+
+fn read_str(memory: &[u8], ptr: u32, len: u32) -> Result<&str, Errno> {
+    std::str::from_utf8(read_bytes(memory, ptr, len)?).map_err(|_| Errno::Ilseq)
+}
+
+/// Read an array of `struct iovec { u8* iov_base; u32 iov_len; }` as (base, len) pairs
+fn iovecs(memory: &[u8], ptr_iovs: u32, iovs_len: u32) -> Result<Vec<(u32, u32)>, Errno> {
+    let bytes = read_bytes(
+        memory,
+        ptr_iovs,
+        iovs_len.checked_mul(8).ok_or(Errno::Fault)?,
+    )?;
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|iov| {
+            let base = u32::from_le_bytes(iov[0..4].try_into().unwrap());
+            let len = u32::from_le_bytes(iov[4..8].try_into().unwrap());
+            (base, len)
+        })
+        .collect())
+}
+
+fn read_iovs(
+    reader: &mut impl Read,
+    memory: &mut [u8],
+    iovs: &[(u32, u32)],
+) -> Result<usize, Errno> {
+    let mut n_read = 0;
+    for &(base, len) in iovs {
+        let target = memory
+            .get_mut(base as usize..)
+            .and_then(|target| target.get_mut(..len as usize))
+            .ok_or(Errno::Fault)?;
+        let n = reader.read(target)?;
+        n_read += n;
+        // A short read means there's nothing more to read right now.
+        if n < len as usize {
+            break;
+        }
+    }
+    Ok(n_read)
+}
+
+fn write_iovs(writer: &mut impl Write, memory: &[u8], iovs: &[(u32, u32)]) -> Result<usize, Errno> {
+    let mut n_written = 0;
+    for &(base, len) in iovs {
+        writer.write_all(read_bytes(memory, base, len)?)?;
+        n_written += len as usize;
+    }
+    Ok(n_written)
+}
+
+/// Do something at a given offset in a file, without moving its cursor
+fn at_offset<T>(
+    file: &mut File,
+    offset: u64,
+    f: impl FnOnce(&mut File) -> Result<T, Errno>,
+) -> Result<T, Errno> {
+    let original = file.stream_position()?;
+    file.seek(SeekFrom::Start(offset))?;
+    let result = f(file);
+    file.seek(SeekFrom::Start(original))?;
+    result
+}
+
+/// `struct filestat` for an in-memory file or stdio
+fn stream_filestat(size: u64) -> [u8; 64] {
+    let mut stat = [0; 64];
+    stat[16] = FILETYPE_CHARACTER_DEVICE;
+    stat[24..32].copy_from_slice(&1u64.to_le_bytes());
+    stat[32..40].copy_from_slice(&size.to_le_bytes());
+    stat
+}
+
+fn filestat(metadata: &Metadata) -> [u8; 64] {
+    fn nanos(time: io::Result<SystemTime>) -> u64 {
+        time.ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since_epoch| since_epoch.as_nanos() as u64)
+    }
+
+    #[cfg(unix)]
+    let (dev, ino, nlink, ctim) = {
+        use std::os::unix::fs::MetadataExt;
+        let ctim = metadata.ctime() as u64 * 1_000_000_000 + metadata.ctime_nsec() as u64;
+        (metadata.dev(), metadata.ino(), metadata.nlink(), ctim)
+    };
+    #[cfg(not(unix))]
+    let (dev, ino, nlink, ctim) = (0, 0, 1, nanos(metadata.modified()));
+
+    // struct filestat {
+    //     u64 dev; u64 ino; u8 filetype; u64 nlink; u64 size; u64 atim; u64 mtim; u64 ctim;
+    // }
+    let mut stat = [0; 64];
+    stat[0..8].copy_from_slice(&dev.to_le_bytes());
+    stat[8..16].copy_from_slice(&ino.to_le_bytes());
+    stat[16] = filetype(&metadata.file_type());
+    stat[24..32].copy_from_slice(&nlink.to_le_bytes());
+    stat[32..40].copy_from_slice(&metadata.len().to_le_bytes());
+    stat[40..48].copy_from_slice(&nanos(metadata.accessed()).to_le_bytes());
+    stat[48..56].copy_from_slice(&nanos(metadata.modified()).to_le_bytes());
+    stat[56..64].copy_from_slice(&ctim.to_le_bytes());
+    stat
+}
+
+fn filetype(file_type: &fs::FileType) -> u8 {
+    if file_type.is_dir() {
+        return FILETYPE_DIRECTORY;
+    } else if file_type.is_file() {
+        return FILETYPE_REGULAR_FILE;
+    } else if file_type.is_symlink() {
+        return FILETYPE_SYMBOLIC_LINK;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_block_device() {
+            return FILETYPE_BLOCK_DEVICE;
+        } else if file_type.is_char_device() {
+            return FILETYPE_CHARACTER_DEVICE;
+        } else if file_type.is_socket() {
+            return FILETYPE_SOCKET_STREAM;
+        }
+    }
+
+    FILETYPE_UNKNOWN
+}
+
+#[cfg(unix)]
+fn inode(entry: &fs::DirEntry) -> u64 {
+    use std::os::unix::fs::DirEntryExt;
+    entry.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_entry: &fs::DirEntry) -> u64 {
+    0
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, link: &Path) -> Result<(), Errno> {
+    std::os::unix::fs::symlink(target, link).map_err(Errno::from)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &str, _link: &Path) -> Result<(), Errno> {
+    Err(Errno::Nosys)
+}
+
+/// Convert WASI's timestamps and `fstflags` to what `utimensat` and `futimens` expect
+#[cfg(unix)]
+fn timespecs(atim: u64, mtim: u64, fst_flags: u32) -> Result<[libc::timespec; 2], Errno> {
+    const FSTFLAGS_ATIM: u32 = 1 << 0;
+    const FSTFLAGS_ATIM_NOW: u32 = 1 << 1;
+    const FSTFLAGS_MTIM: u32 = 1 << 2;
+    const FSTFLAGS_MTIM_NOW: u32 = 1 << 3;
+
+    let timespec = |nanos: u64, set: u32, set_now: u32| match (fst_flags & set, fst_flags & set_now)
+    {
+        (0, 0) => Ok(libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT,
+        }),
+        (0, _) => Ok(libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_NOW,
+        }),
+        (_, 0) => Ok(libc::timespec {
+            tv_sec: (nanos / 1_000_000_000) as _,
+            tv_nsec: (nanos % 1_000_000_000) as _,
+        }),
+        _ => Err(Errno::Inval),
+    };
+
+    Ok([
+        timespec(atim, FSTFLAGS_ATIM, FSTFLAGS_ATIM_NOW)?,
+        timespec(mtim, FSTFLAGS_MTIM, FSTFLAGS_MTIM_NOW)?,
+    ])
+}
+
+#[cfg(unix)]
+fn set_file_times(file: &File, atim: u64, mtim: u64, fst_flags: u32) -> Result<(), Errno> {
+    use std::os::unix::io::AsRawFd;
+
+    let times = timespecs(atim, mtim, fst_flags)?;
+    match unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error().into()),
+    }
+}
+
+#[cfg(unix)]
+fn set_path_times(
+    path: &Path,
+    follow_symlinks: bool,
+    atim: u64,
+    mtim: u64,
+    fst_flags: u32,
+) -> Result<(), Errno> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let times = timespecs(atim, mtim, fst_flags)?;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|_| Errno::Inval)?;
+    let flags = if follow_symlinks {
+        0
+    } else {
+        libc::AT_SYMLINK_NOFOLLOW
+    };
+
+    match unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), flags) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error().into()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_file_times(_file: &File, _atim: u64, _mtim: u64, _fst_flags: u32) -> Result<(), Errno> {
+    Err(Errno::Nosys)
+}
+
+#[cfg(not(unix))]
+fn set_path_times(
+    _path: &Path,
+    _follow_symlinks: bool,
+    _atim: u64,
+    _mtim: u64,
+    _fst_flags: u32,
+) -> Result<(), Errno> {
+    Err(Errno::Nosys)
 }
 
 fn read_u32(memory: &[u8], addr: usize) -> u32 {
@@ -360,7 +1275,7 @@ fn write_i32(memory: &mut [u8], addr: usize, value: i32) {
 /// API; some are used in higher-level library layers, and others are provided
 /// merely for alignment with POSIX.
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Errno {
     /// No error occurred. System call completed successfully.
     Success,
@@ -517,3 +1432,67 @@ pub enum Errno {
     /// Extension: Capabilities insufficient.
     Notcapable,
 }
+
+impl From<io::Error> for Errno {
+    fn from(error: io::Error) -> Self {
+        #[cfg(unix)]
+        if let Some(code) = error.raw_os_error() {
+            return Errno::from_host_errno(code);
+        }
+
+        match error.kind() {
+            io::ErrorKind::NotFound => Errno::Noent,
+            io::ErrorKind::PermissionDenied => Errno::Access,
+            io::ErrorKind::AlreadyExists => Errno::Exist,
+            io::ErrorKind::InvalidInput => Errno::Inval,
+            io::ErrorKind::Interrupted => Errno::Intr,
+            io::ErrorKind::WouldBlock => Errno::Again,
+            io::ErrorKind::Unsupported => Errno::Notsup,
+            io::ErrorKind::OutOfMemory => Errno::Nomem,
+            _ => Errno::Io,
+        }
+    }
+}
+
+impl Errno {
+    #[cfg(unix)]
+    fn from_host_errno(code: i32) -> Self {
+        match code {
+            libc::E2BIG => Errno::Toobig,
+            libc::EACCES => Errno::Access,
+            libc::EAGAIN => Errno::Again,
+            libc::EBADF => Errno::Badf,
+            libc::EBUSY => Errno::Busy,
+            libc::EEXIST => Errno::Exist,
+            libc::EFAULT => Errno::Fault,
+            libc::EFBIG => Errno::Fbig,
+            libc::EILSEQ => Errno::Ilseq,
+            libc::EINTR => Errno::Intr,
+            libc::EINVAL => Errno::Inval,
+            libc::EIO => Errno::Io,
+            libc::EISDIR => Errno::Isdir,
+            libc::ELOOP => Errno::Loop,
+            libc::EMFILE => Errno::Mfile,
+            libc::EMLINK => Errno::Mlink,
+            libc::ENAMETOOLONG => Errno::Nametoolong,
+            libc::ENFILE => Errno::Nfile,
+            libc::ENODEV => Errno::Nodev,
+            libc::ENOENT => Errno::Noent,
+            libc::ENOMEM => Errno::Nomem,
+            libc::ENOSPC => Errno::Nospc,
+            libc::ENOSYS => Errno::Nosys,
+            libc::ENOTDIR => Errno::Notdir,
+            libc::ENOTEMPTY => Errno::Notempty,
+            libc::ENOTSUP => Errno::Notsup,
+            libc::ENXIO => Errno::Nxio,
+            libc::EOVERFLOW => Errno::Overflow,
+            libc::EPERM => Errno::Perm,
+            libc::EPIPE => Errno::Pipe,
+            libc::EROFS => Errno::Rofs,
+            libc::ESPIPE => Errno::Spipe,
+            libc::ETXTBSY => Errno::Txtbsy,
+            libc::EXDEV => Errno::Xdev,
+            _ => Errno::Io,
+        }
+    }
+}
@@ -672,8 +672,19 @@ impl<'a> Parse<&'a Bump> for LinkingSection<'a> {
                         section.segment_info.push(item);
                     }
                 }
-                SubSectionId::InitFuncs | SubSectionId::ComdatInfo => {
-                    // We don't use these subsections, just skip over them.
+                SubSectionId::InitFuncs => {
+                    let count = u32::parse((), bytes, cursor)?;
+                    for _ in 0..count {
+                        let priority = u32::parse((), bytes, cursor)?;
+                        let symbol_index = u32::parse((), bytes, cursor)?;
+                        section.init_funcs.push(LinkingInitFunc {
+                            priority,
+                            symbol_index,
+                        });
+                    }
+                }
+                SubSectionId::ComdatInfo => {
+                    // We don't use this subsection, just skip over it.
                     *cursor += len as usize;
                 }
             }
@@ -1,5 +1,5 @@
 interface ExampleApp
-    exposes [exampleApp, State]
+    exposes [exampleApp]
     imports [
         pf.Html.{ App, Html, html, head, body, div, text, h1 },
     ]